@@ -24,6 +24,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         read_timeout_ms: 1000,
         ping_interval_secs: 30,
         auto_reconnect: false,
+        ..Default::default()
     };
 
     println!("Creating WebSocket connection to {}...", config.url);
@@ -0,0 +1,98 @@
+//! Command-line arguments
+//!
+//! Lets launcher scripts and kiosk setups open a program, connect and start
+//! it without touching the UI.
+
+use crate::settings::Settings;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Command-line options for the rCandle binary
+#[derive(Debug, Clone, Default, Parser)]
+#[command(name = "rcandle", version, about = "GRBL controller with G-Code visualization")]
+pub struct Cli {
+    /// G-Code file to open on startup
+    pub file: Option<PathBuf>,
+
+    /// Serial port (or address) to use instead of the first detected port
+    #[arg(long, value_name = "PORT")]
+    pub port: Option<String>,
+
+    /// Connect to the machine on startup
+    #[arg(long)]
+    pub connect: bool,
+
+    /// Start the program once connected and idle, after the pre-run checklist if one is set (implies --connect)
+    #[arg(long)]
+    pub run: bool,
+
+    /// Settings file to use instead of the default config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Start in fullscreen mode
+    #[arg(long)]
+    pub fullscreen: bool,
+}
+
+impl Cli {
+    /// Whether to connect on startup
+    pub fn should_connect(&self) -> bool {
+        self.connect || self.run
+    }
+
+    /// Load the settings selected on the command line
+    ///
+    /// Falls back to defaults when an explicit config file is missing or
+    /// invalid, so a bad path never prevents the application from starting.
+    pub fn load_settings(&self) -> Settings {
+        match &self.config {
+            Some(path) => Settings::load(path).unwrap_or_else(|e| {
+                tracing::warn!("Failed to load settings from {:?}: {}", path, e);
+                Settings::default()
+            }),
+            None => Settings::load_or_default(),
+        }
+    }
+
+    /// Settings file in use: the one given with `--config` or the default
+    pub fn settings_path(&self) -> Option<PathBuf> {
+        match &self.config {
+            Some(path) => Some(path.clone()),
+            None => Settings::default_config_path().ok(),
+        }
+    }
+
+    /// Save settings back to where they were loaded from
+    pub fn save_settings(&self, settings: &Settings) -> crate::Result<()> {
+        match &self.config {
+            Some(path) => settings.save(path),
+            None => settings.save_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_launcher_arguments() {
+        let cli = Cli::try_parse_from([
+            "rcandle", "part.gcode", "--port", "/dev/ttyUSB0", "--run", "--fullscreen",
+        ])
+        .unwrap();
+        assert_eq!(cli.file, Some(PathBuf::from("part.gcode")));
+        assert_eq!(cli.port.as_deref(), Some("/dev/ttyUSB0"));
+        assert!(cli.run && cli.fullscreen);
+        assert!(cli.should_connect());
+    }
+
+    #[test]
+    fn test_no_arguments() {
+        let cli = Cli::try_parse_from(["rcandle"]).unwrap();
+        assert!(cli.file.is_none());
+        assert!(!cli.should_connect());
+        assert!(cli.config.is_none());
+    }
+}
@@ -0,0 +1,196 @@
+//! Programmatic GRBL client
+//!
+//! [`GrblClient`] wraps a [`ConnectionManager`] with the calls a program
+//! driving a machine without the UI needs: connect, send commands, stream a
+//! G-Code file and subscribe to status reports. It is the entry point for
+//! bots, bridges and scripts built on the library.
+//!
+//! ```no_run
+//! use rcandle::connection::SerialConnection;
+//! use rcandle::GrblClient;
+//!
+//! # async fn run() -> rcandle::Result<()> {
+//! let mut client = GrblClient::new(Box::new(SerialConnection::new("/dev/ttyUSB0".to_string(), 115200)));
+//! client.connect().await?;
+//! let mut status = client.subscribe_status();
+//! tokio::spawn(async move {
+//!     while let Ok(status) = status.recv().await {
+//!         println!("{:?} at {:?}", status.state, status.mpos);
+//!     }
+//! });
+//! let lines = client.stream_file("part.nc").await?;
+//! println!("Streamed {} lines", lines);
+//! client.disconnect().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::connection::{Connection, ConnectionEvent, ConnectionManager, ConnectionManagerConfig};
+use crate::grbl::{GrblCommand, GrblResponse, GrblStatus, ProgramStreamer, RealtimeCommand, StreamOptions};
+use crate::parser::{ExpressionEvaluator, ProgramExpander};
+use crate::utils::error::{Error, Result};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Default time allowed for the controller to answer when connecting
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// GRBL controller driven from code
+pub struct GrblClient {
+    manager: ConnectionManager,
+    connect_timeout: Duration,
+}
+
+impl GrblClient {
+    /// Create a client for a connection with the default manager configuration
+    pub fn new(connection: Box<dyn Connection>) -> Self {
+        Self::with_config(connection, ConnectionManagerConfig::default())
+    }
+
+    /// Create a client for a connection with a custom manager configuration
+    pub fn with_config(connection: Box<dyn Connection>, config: ConnectionManagerConfig) -> Self {
+        Self {
+            manager: ConnectionManager::with_config(connection, config),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        }
+    }
+
+    /// Set the time allowed for the controller to answer when connecting
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Open the connection and start status polling
+    pub async fn connect(&mut self) -> Result<()> {
+        self.manager.connect(self.connect_timeout).await
+    }
+
+    /// Stop polling and close the connection
+    pub async fn disconnect(&mut self) -> Result<()> {
+        self.manager.disconnect().await
+    }
+
+    /// Whether the connection is open
+    pub async fn is_connected(&self) -> bool {
+        self.manager.is_connected().await
+    }
+
+    /// Queue a command; its `ok` or `error` arrives on the response channel
+    pub async fn send(&self, command: GrblCommand) -> Result<()> {
+        self.manager.send_command(command).await
+    }
+
+    /// Queue a line of G-Code
+    pub async fn send_gcode(&self, line: &str) -> Result<()> {
+        self.send(GrblCommand::GCode(line.to_string())).await
+    }
+
+    /// Send a real-time command such as a feed hold, bypassing the queue
+    pub async fn realtime(&self, command: RealtimeCommand) -> Result<()> {
+        self.manager.send_realtime(command.as_byte()).await
+    }
+
+    /// Receive status reports as they are polled
+    pub fn subscribe_status(&self) -> broadcast::Receiver<GrblStatus> {
+        self.manager.subscribe_status()
+    }
+
+    /// Receive every response from the controller
+    pub fn subscribe_responses(&self) -> broadcast::Receiver<GrblResponse> {
+        self.manager.subscribe_responses()
+    }
+
+    /// Receive connection events such as the link dropping
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.manager.subscribe_events()
+    }
+
+    /// The underlying connection manager, for queue and link details
+    pub fn manager(&self) -> &ConnectionManager {
+        &self.manager
+    }
+
+    /// Stream a G-Code file and wait for it to finish
+    pub async fn stream_file<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let program = std::fs::read_to_string(path)?;
+        self.stream_program(&program, StreamOptions::default()).await
+    }
+
+    /// Stream a program and wait until every line is acknowledged
+    ///
+    /// Expressions are evaluated and subprograms and canned cycles expanded
+    /// first, as when running a program from the UI. Tool changes are sent
+    /// as they are; holding at them needs an operator, so
+    /// `hold_at_tool_change` is ignored. The stream stops at the first error
+    /// or alarm, clearing the queue, and the error names the program line.
+    /// Other commands must not be queued while a program streams, since
+    /// their acknowledgments would be counted against it.
+    ///
+    /// Returns the number of lines acknowledged.
+    pub async fn stream_program(&self, program: &str, options: StreamOptions) -> Result<usize> {
+        let program = ExpressionEvaluator::new().evaluate(program)?;
+        let lines = ProgramExpander::new().expand_with_sources(&program)?;
+        let options = StreamOptions { hold_at_tool_change: false, ..options };
+        let mut streamer = ProgramStreamer::from_lines(
+            lines.iter().map(|line| (line.source_line, line.text.as_str())),
+            options,
+        );
+
+        // Keep the watchdog from reconnecting, and resetting GRBL, mid-job
+        self.manager.set_job_active(true);
+        let result = self.stream_lines(&mut streamer).await;
+        self.manager.set_job_active(false);
+        result
+    }
+
+    /// Send a streamer's lines, returning the number acknowledged
+    async fn stream_lines(&self, streamer: &mut ProgramStreamer) -> Result<usize> {
+        let mut responses = self.manager.subscribe_responses();
+        let mut events = self.manager.subscribe_events();
+        while !streamer.is_complete() {
+            for line in streamer.next_lines() {
+                self.manager.send_command(GrblCommand::GCode(line.text)).await?;
+            }
+
+            let response = tokio::select! {
+                response = responses.recv() => response,
+                event = events.recv() => match event {
+                    Ok(ConnectionEvent::Disconnected) | Err(RecvError::Closed) => {
+                        return Err(Error::connection("Connection lost while streaming"));
+                    }
+                    Ok(ConnectionEvent::Error(e)) => {
+                        return Err(Error::connection(format!("Connection error while streaming: {}", e)));
+                    }
+                    Ok(ConnectionEvent::JobAborted) => {
+                        return Err(Error::connection("Controller stopped responding; streaming aborted"));
+                    }
+                    _ => continue,
+                },
+            };
+            let failure = match response {
+                Ok(GrblResponse::Ok) => {
+                    streamer.acknowledge();
+                    continue;
+                }
+                Ok(GrblResponse::Error(code)) => format!("error:{}", code),
+                Ok(GrblResponse::Alarm(code)) => format!("ALARM:{}", code),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    format!("{} responses were missed, progress is unknown", missed)
+                }
+                Err(RecvError::Closed) => return Err(Error::connection("Connection closed while streaming")),
+            };
+
+            self.manager.clear_queue().await?;
+            let line = streamer.acknowledge().map(|line| line + 1);
+            return Err(Error::grbl(match line {
+                Some(line) => format!("Streaming stopped at line {}: {}", line, failure),
+                None => format!("Streaming stopped: {}", failure),
+            }));
+        }
+        Ok(streamer.acknowledged())
+    }
+}
@@ -0,0 +1,227 @@
+//! Automatic connection
+//!
+//! Schedules connection attempts on startup with exponential backoff and
+//! reconnects when the saved port reappears after the device was unplugged.
+
+use std::time::{Duration, Instant};
+
+/// Delay before the first retry
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between retries
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Attempts before giving up until the port reappears
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Where the auto-connect cycle currently is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoConnectState {
+    /// Not connecting automatically
+    Idle,
+    /// Next attempt is due at the given time
+    Scheduled(Instant),
+    /// An attempt is in progress
+    Connecting,
+    /// Connected; watching for the device to be unplugged
+    Connected,
+    /// Device disappeared or attempts ran out; waiting for the port to appear
+    WaitingForPort,
+}
+
+/// Retry and replug logic for connecting without user interaction
+#[derive(Debug)]
+pub struct AutoConnect {
+    /// Delay before the first retry, doubled for each further retry
+    base_delay: Duration,
+    /// Cap on the retry delay
+    max_delay: Duration,
+    /// Attempts before waiting for the port to reappear
+    max_attempts: u32,
+    /// Attempts made in the current cycle
+    attempts: u32,
+    /// Current state
+    state: AutoConnectState,
+    /// Whether the port was present at the last check
+    port_present: Option<bool>,
+}
+
+impl Default for AutoConnect {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_DELAY, DEFAULT_MAX_DELAY, DEFAULT_MAX_ATTEMPTS)
+    }
+}
+
+impl AutoConnect {
+    /// Create an idle auto-connector
+    pub fn new(base_delay: Duration, max_delay: Duration, max_attempts: u32) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            max_attempts: max_attempts.max(1),
+            attempts: 0,
+            state: AutoConnectState::Idle,
+            port_present: None,
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> AutoConnectState {
+        self.state
+    }
+
+    /// Attempts made in the current cycle
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Whether an attempt is scheduled or running, i.e. there is something to cancel
+    pub fn is_retrying(&self) -> bool {
+        matches!(self.state, AutoConnectState::Scheduled(_) | AutoConnectState::Connecting)
+    }
+
+    /// Whether the port should be watched for the device coming and going
+    pub fn is_watching(&self) -> bool {
+        self.state != AutoConnectState::Idle
+    }
+
+    /// Time left before the next attempt
+    pub fn next_attempt_in(&self, now: Instant) -> Option<Duration> {
+        match self.state {
+            AutoConnectState::Scheduled(at) => Some(at.saturating_duration_since(now)),
+            _ => None,
+        }
+    }
+
+    /// Begin a new cycle with an immediate attempt
+    pub fn start(&mut self, now: Instant) {
+        self.attempts = 0;
+        self.state = AutoConnectState::Scheduled(now);
+    }
+
+    /// Stop connecting automatically
+    pub fn cancel(&mut self) {
+        self.state = AutoConnectState::Idle;
+    }
+
+    /// Returns true when an attempt is due; the caller must then connect and
+    /// report the outcome with [`AutoConnect::succeeded`] or [`AutoConnect::failed`]
+    pub fn poll(&mut self, now: Instant) -> bool {
+        match self.state {
+            AutoConnectState::Scheduled(at) if now >= at => {
+                self.attempts += 1;
+                self.state = AutoConnectState::Connecting;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Record a successful attempt
+    pub fn succeeded(&mut self) {
+        if self.state == AutoConnectState::Connecting {
+            self.state = AutoConnectState::Connected;
+        }
+    }
+
+    /// Record a failed attempt and schedule the next one
+    pub fn failed(&mut self, now: Instant) {
+        if self.state != AutoConnectState::Connecting {
+            return;
+        }
+        self.state = if self.attempts >= self.max_attempts {
+            AutoConnectState::WaitingForPort
+        } else {
+            AutoConnectState::Scheduled(now + self.retry_delay(self.attempts))
+        };
+    }
+
+    /// Delay after the given failed attempt (1-based)
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Record whether the saved port is currently present
+    ///
+    /// Returns true when the device was plugged back in and the caller should
+    /// drop any stale connection before the new attempt starts.
+    pub fn port_checked(&mut self, present: bool, now: Instant) -> bool {
+        let was_present = self.port_present.replace(present);
+        match self.state {
+            AutoConnectState::Connected if !present => {
+                self.state = AutoConnectState::WaitingForPort;
+                false
+            }
+            AutoConnectState::WaitingForPort if present && was_present == Some(false) => {
+                self.start(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connector() -> AutoConnect {
+        AutoConnect::new(Duration::from_secs(1), Duration::from_secs(5), 3)
+    }
+
+    #[test]
+    fn test_backoff() {
+        let auto = connector();
+        assert_eq!(auto.retry_delay(1), Duration::from_secs(1));
+        assert_eq!(auto.retry_delay(2), Duration::from_secs(2));
+        assert_eq!(auto.retry_delay(3), Duration::from_secs(4));
+        assert_eq!(auto.retry_delay(4), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_retries_then_waits_for_port() {
+        let mut auto = connector();
+        let start = Instant::now();
+        auto.start(start);
+        assert!(auto.poll(start));
+        assert!(!auto.poll(start));
+
+        auto.failed(start);
+        assert!(!auto.poll(start));
+        assert!(auto.poll(start + Duration::from_secs(1)));
+        auto.failed(start);
+        assert!(auto.poll(start + Duration::from_secs(2)));
+        auto.failed(start);
+        assert_eq!(auto.state(), AutoConnectState::WaitingForPort);
+        assert!(!auto.is_retrying());
+    }
+
+    #[test]
+    fn test_replug_restarts_cycle() {
+        let mut auto = connector();
+        let now = Instant::now();
+        auto.start(now);
+        assert!(auto.poll(now));
+        auto.succeeded();
+        assert!(!auto.port_checked(true, now));
+
+        // Unplugged, then plugged back in
+        assert!(!auto.port_checked(false, now));
+        assert_eq!(auto.state(), AutoConnectState::WaitingForPort);
+        assert!(auto.port_checked(true, now));
+        assert!(auto.poll(now));
+        assert_eq!(auto.attempts(), 1);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut auto = connector();
+        let now = Instant::now();
+        auto.start(now);
+        auto.cancel();
+        assert!(!auto.poll(now));
+        assert!(!auto.port_checked(true, now));
+        assert!(!auto.is_watching());
+    }
+}
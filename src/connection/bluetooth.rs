@@ -0,0 +1,498 @@
+//! Bluetooth serial (SPP/RFCOMM) connection
+//!
+//! Connects to GRBL boards fitted with HC-05/HC-06 style Bluetooth modules.
+//! On Linux the RFCOMM channel is opened directly, so no `/dev/rfcomm*`
+//! binding is needed; on other platforms the virtual serial port the OS
+//! creates when the module is paired is used instead.
+
+use async_trait::async_trait;
+use serialport::SerialPortType;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::serial::{SerialConfig, SerialConnection};
+use super::traits::{Connection, ConnectionStatus};
+use crate::utils::error::{Error, Result};
+
+/// Default RFCOMM channel used by SPP modules
+pub const DEFAULT_RFCOMM_CHANNEL: u8 = 1;
+
+/// A Bluetooth device address (e.g. `98:D3:31:F5:12:34`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BluetoothAddress(pub [u8; 6]);
+
+impl FromStr for BluetoothAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::connection(format!("Invalid Bluetooth address: {}", s));
+        let mut bytes = [0u8; 6];
+        let mut parts = s.trim().split([':', '-']);
+        for byte in bytes.iter_mut() {
+            let part = parts.next().ok_or_else(invalid)?;
+            if part.len() != 2 {
+                return Err(invalid());
+            }
+            *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for BluetoothAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}", a, b, c, d, e, g)
+    }
+}
+
+/// Where a Bluetooth connection is made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BluetoothTarget {
+    /// Direct RFCOMM connection to a device (Linux only)
+    Rfcomm {
+        /// Device address
+        address: BluetoothAddress,
+        /// RFCOMM channel
+        channel: u8,
+    },
+    /// Virtual serial port created by the OS for a paired device
+    Port(String),
+}
+
+impl BluetoothTarget {
+    /// Interpret a user-entered target: a device address, or otherwise the
+    /// name of the device's virtual serial port
+    pub fn parse(target: &str, channel: u8) -> Result<Self> {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(Error::connection("No Bluetooth device selected"));
+        }
+        match target.parse::<BluetoothAddress>() {
+            Ok(address) => Ok(Self::Rfcomm { address, channel }),
+            Err(_) => Ok(Self::Port(target.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for BluetoothTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rfcomm { address, channel } => write!(f, "{} channel {}", address, channel),
+            Self::Port(port) => write!(f, "{}", port),
+        }
+    }
+}
+
+/// A Bluetooth device found during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BluetoothDevice {
+    /// Device name, if known
+    pub name: String,
+    /// How to connect to the device
+    pub target: BluetoothTarget,
+}
+
+impl BluetoothDevice {
+    /// Whether the device looks like a serial module commonly fitted to
+    /// GRBL boards (HC-05, HC-06, JDY-31, ...)
+    pub fn is_serial_module(&self) -> bool {
+        let name = self.name.to_uppercase();
+        ["HC-0", "HC0", "JDY", "BT05", "SPP", "GRBL"]
+            .iter()
+            .any(|pattern| name.contains(pattern))
+    }
+
+    /// Text used to select the device (address or port name)
+    pub fn target_text(&self) -> String {
+        match &self.target {
+            BluetoothTarget::Rfcomm { address, .. } => address.to_string(),
+            BluetoothTarget::Port(port) => port.clone(),
+        }
+    }
+}
+
+/// Bluetooth connection configuration
+#[derive(Debug, Clone)]
+pub struct BluetoothConfig {
+    /// Device to connect to
+    pub target: BluetoothTarget,
+    /// Baud rate used when connecting through a virtual serial port
+    pub baud_rate: u32,
+}
+
+/// The transport behind a Bluetooth connection
+enum Link {
+    /// OS virtual serial port
+    Serial(SerialConnection),
+    /// Direct RFCOMM socket
+    #[cfg(target_os = "linux")]
+    Rfcomm(rfcomm::RfcommSocket),
+}
+
+/// Bluetooth SPP connection
+pub struct BluetoothConnection {
+    config: BluetoothConfig,
+    link: Option<Link>,
+    status: ConnectionStatus,
+}
+
+impl BluetoothConnection {
+    /// Create a new Bluetooth connection
+    pub fn new(config: BluetoothConfig) -> Self {
+        Self {
+            config,
+            link: None,
+            status: ConnectionStatus::Disconnected,
+        }
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &BluetoothConfig {
+        &self.config
+    }
+
+    /// Find Bluetooth devices that can be connected to
+    ///
+    /// Lists virtual serial ports the OS has created for paired devices and,
+    /// on Linux, devices known to BlueZ (via `bluetoothctl`). Devices that
+    /// look like serial modules are listed first.
+    pub fn discover() -> Vec<BluetoothDevice> {
+        let mut devices = Vec::new();
+
+        if let Ok(ports) = serialport::available_ports() {
+            for port in ports {
+                let is_bluetooth = matches!(port.port_type, SerialPortType::BluetoothPort)
+                    || port.port_name.contains("rfcomm");
+                if is_bluetooth {
+                    let name = port
+                        .port_name
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(&port.port_name)
+                        .trim_start_matches("cu.")
+                        .to_string();
+                    devices.push(BluetoothDevice {
+                        name,
+                        target: BluetoothTarget::Port(port.port_name),
+                    });
+                }
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Ok(output) = std::process::Command::new("bluetoothctl").arg("devices").output() {
+            devices.extend(parse_bluetoothctl_devices(&String::from_utf8_lossy(&output.stdout)));
+        }
+
+        devices.sort_by_key(|device| !device.is_serial_module());
+        devices
+    }
+
+    /// Hints for pairing a module with this computer
+    pub fn pairing_hints() -> Vec<&'static str> {
+        let mut hints = vec![
+            "HC-05/HC-06 modules usually pair with PIN 1234 (sometimes 0000).",
+            "The module's UART must match GRBL's baud rate (115200): AT+UART=115200,0,0 on HC-05, AT+BAUD8 on HC-06.",
+        ];
+        if cfg!(target_os = "linux") {
+            hints.push("Pair with bluetoothctl: 'scan on', then 'pair <address>' and 'trust <address>'. rCandle connects to the address directly.");
+        } else if cfg!(target_os = "windows") {
+            hints.push("Pair in Settings > Bluetooth, then use the 'Outgoing' COM port listed under More Bluetooth options > COM Ports.");
+        } else if cfg!(target_os = "macos") {
+            hints.push("Pair in System Settings > Bluetooth; the module then appears as /dev/cu.<name>.");
+        }
+        hints
+    }
+
+    fn link_mut(&mut self) -> Result<&mut Link> {
+        self.link.as_mut().ok_or_else(|| Error::connection("Not connected"))
+    }
+}
+
+/// Parse the output of `bluetoothctl devices`
+///
+/// Lines look like `Device 98:D3:31:F5:12:34 HC-05`.
+fn parse_bluetoothctl_devices(output: &str) -> Vec<BluetoothDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("Device ")?;
+            let (address, name) = rest.split_once(' ').unwrap_or((rest, ""));
+            let address = address.parse().ok()?;
+            Some(BluetoothDevice {
+                name: name.trim().to_string(),
+                target: BluetoothTarget::Rfcomm {
+                    address,
+                    channel: DEFAULT_RFCOMM_CHANNEL,
+                },
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Connection for BluetoothConnection {
+    async fn connect(&mut self, timeout: Duration) -> Result<()> {
+        self.status = ConnectionStatus::Connecting;
+
+        let link = match &self.config.target {
+            BluetoothTarget::Port(port) => {
+                let mut serial = SerialConnection::with_config(SerialConfig {
+                    port: port.clone(),
+                    baud_rate: self.config.baud_rate,
+                    ..Default::default()
+                });
+                serial.connect(timeout).await?;
+                Link::Serial(serial)
+            }
+            #[cfg(target_os = "linux")]
+            BluetoothTarget::Rfcomm { address, channel } => {
+                Link::Rfcomm(rfcomm::RfcommSocket::connect(*address, *channel, timeout)?)
+            }
+            #[cfg(not(target_os = "linux"))]
+            BluetoothTarget::Rfcomm { .. } => {
+                self.status = ConnectionStatus::Error;
+                return Err(Error::connection(
+                    "Direct RFCOMM connections are only supported on Linux; pair the device and use its serial port",
+                ));
+            }
+        };
+
+        self.link = Some(link);
+        self.status = ConnectionStatus::Connected;
+        tracing::info!("Connected to Bluetooth device {}", self.config.target);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        if let Some(Link::Serial(mut serial)) = self.link.take() {
+            serial.disconnect().await?;
+        }
+        self.status = ConnectionStatus::Disconnected;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.link.is_some() && self.status == ConnectionStatus::Connected
+    }
+
+    fn status(&self) -> ConnectionStatus {
+        self.status
+    }
+
+    async fn send_line(&mut self, data: &str) -> Result<()> {
+        let mut line = data.to_string();
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        self.send_bytes(line.as_bytes()).await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<()> {
+        match self.link_mut()? {
+            Link::Serial(serial) => serial.send_bytes(data).await,
+            #[cfg(target_os = "linux")]
+            Link::Rfcomm(socket) => socket.send(data),
+        }
+    }
+
+    async fn receive_line(&mut self, timeout: Duration) -> Result<Option<String>> {
+        let result = match self.link_mut()? {
+            Link::Serial(serial) => serial.receive_line(timeout).await,
+            #[cfg(target_os = "linux")]
+            Link::Rfcomm(socket) => socket.receive_line(timeout).await,
+        };
+        if result.is_err() {
+            self.status = ConnectionStatus::Error;
+        }
+        result
+    }
+
+    fn description(&self) -> String {
+        format!("Bluetooth: {}", self.config.target)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        match self.link_mut()? {
+            Link::Serial(serial) => serial.flush().await,
+            #[cfg(target_os = "linux")]
+            Link::Rfcomm(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod rfcomm {
+    //! Direct RFCOMM sockets using the Linux Bluetooth socket API
+
+    use super::BluetoothAddress;
+    use crate::utils::error::{Error, Result};
+    use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::Shutdown;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// `AF_BLUETOOTH`
+    const AF_BLUETOOTH: i32 = 31;
+
+    /// `BTPROTO_RFCOMM`
+    const BTPROTO_RFCOMM: i32 = 3;
+
+    /// `struct sockaddr_rc` from `<bluetooth/rfcomm.h>`
+    #[repr(C)]
+    struct SockaddrRc {
+        rc_family: u16,
+        rc_bdaddr: [u8; 6],
+        rc_channel: u8,
+    }
+
+    /// A connected RFCOMM socket
+    ///
+    /// Reads block, so a reader thread owns a clone of the socket and hands
+    /// complete lines over a channel instead of blocking the async runtime.
+    pub(super) struct RfcommSocket {
+        socket: Socket,
+        lines: mpsc::UnboundedReceiver<Result<String>>,
+    }
+
+    impl RfcommSocket {
+        /// Connect to a device's RFCOMM channel
+        pub(super) fn connect(address: BluetoothAddress, channel: u8, timeout: Duration) -> Result<Self> {
+            let socket = Socket::new(Domain::from(AF_BLUETOOTH), Type::STREAM, Some(Protocol::from(BTPROTO_RFCOMM)))
+                .map_err(|e| Error::connection(format!("Bluetooth is not available: {}", e)))?;
+
+            // bdaddr_t stores the address least significant byte first
+            let mut bdaddr = address.0;
+            bdaddr.reverse();
+            let sockaddr = SockaddrRc {
+                rc_family: AF_BLUETOOTH as u16,
+                rc_bdaddr: bdaddr,
+                rc_channel: channel,
+            };
+            // SAFETY: the storage is large enough for a sockaddr_rc, and the
+            // length is set to exactly the bytes written
+            let ((), addr) = unsafe {
+                SockAddr::try_init(|storage, len| {
+                    storage.cast::<SockaddrRc>().write(sockaddr);
+                    *len = std::mem::size_of::<SockaddrRc>() as _;
+                    Ok(())
+                })
+            }
+            .map_err(|e| Error::connection(format!("Invalid Bluetooth address: {}", e)))?;
+
+            socket
+                .connect_timeout(&addr, timeout)
+                .map_err(|e| Error::connection(format!("Failed to connect to {}: {}", address, e)))?;
+            let reader = socket.try_clone().map_err(|e| Error::connection(e.to_string()))?;
+            let (tx, lines) = mpsc::unbounded_channel();
+            std::thread::Builder::new()
+                .name("rfcomm reader".to_string())
+                .spawn(move || read_lines(reader, tx))
+                .map_err(|e| Error::connection(format!("Failed to start the Bluetooth reader: {}", e)))?;
+
+            Ok(Self { socket, lines })
+        }
+
+        /// Send raw bytes
+        pub(super) fn send(&mut self, data: &[u8]) -> Result<()> {
+            self.socket
+                .write_all(data)
+                .map_err(|e| Error::connection(format!("Failed to send data: {}", e)))
+        }
+
+        /// Receive a line, waiting up to `timeout`
+        pub(super) async fn receive_line(&mut self, timeout: Duration) -> Result<Option<String>> {
+            match tokio::time::timeout(timeout, self.lines.recv()).await {
+                Ok(Some(line)) => line.map(Some),
+                Ok(None) => Err(Error::connection("Bluetooth connection closed")),
+                Err(_) => Ok(None),
+            }
+        }
+    }
+
+    impl Drop for RfcommSocket {
+        fn drop(&mut self) {
+            // Wakes the reader thread, whose blocked read then fails
+            let _ = self.socket.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Read lines from the socket until it closes, sending them to `lines`
+    fn read_lines(mut socket: Socket, lines: mpsc::UnboundedSender<Result<String>>) {
+        let mut partial = Vec::new();
+        let mut buf = [0u8; 256];
+        loop {
+            match socket.read(&mut buf) {
+                Ok(0) => {
+                    let _ = lines.send(Err(Error::connection("Bluetooth connection closed")));
+                    return;
+                }
+                Ok(n) => {
+                    partial.extend_from_slice(&buf[..n]);
+                    while let Some(end) = partial.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = partial.drain(..=end).collect();
+                        let line = String::from_utf8_lossy(&line).trim().to_string();
+                        // Stop once the connection is gone
+                        if !line.is_empty() && lines.send(Ok(line)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    let _ = lines.send(Err(Error::connection(format!("Failed to read: {}", e))));
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_round_trip() {
+        let address: BluetoothAddress = "98:d3:31:f5:12:34".parse().unwrap();
+        assert_eq!(address.0, [0x98, 0xD3, 0x31, 0xF5, 0x12, 0x34]);
+        assert_eq!(address.to_string(), "98:D3:31:F5:12:34");
+
+        assert!("98:D3:31:F5:12".parse::<BluetoothAddress>().is_err());
+        assert!("98:D3:31:F5:12:34:56".parse::<BluetoothAddress>().is_err());
+        assert!("/dev/rfcomm0".parse::<BluetoothAddress>().is_err());
+    }
+
+    #[test]
+    fn test_target_parse() {
+        assert_eq!(
+            BluetoothTarget::parse("98:D3:31:F5:12:34", 2).unwrap(),
+            BluetoothTarget::Rfcomm {
+                address: BluetoothAddress([0x98, 0xD3, 0x31, 0xF5, 0x12, 0x34]),
+                channel: 2,
+            }
+        );
+        assert_eq!(
+            BluetoothTarget::parse("COM7", 1).unwrap(),
+            BluetoothTarget::Port("COM7".to_string())
+        );
+        assert!(BluetoothTarget::parse("  ", 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_bluetoothctl_devices() {
+        let output = "Device 98:D3:31:F5:12:34 HC-05\nDevice 11:22:33:44:55:66 Headphones\ngarbage\n";
+        let devices = parse_bluetoothctl_devices(output);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "HC-05");
+        assert!(devices[0].is_serial_module());
+        assert!(!devices[1].is_serial_module());
+        assert_eq!(devices[1].target_text(), "11:22:33:44:55:66");
+    }
+}
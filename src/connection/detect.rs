@@ -0,0 +1,208 @@
+//! GRBL device detection
+//!
+//! Probes serial ports at common baud rates to find controllers that answer
+//! like GRBL, either with the welcome banner or a status report.
+
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+use super::serial::SerialConnection;
+use crate::utils::error::{Error, Result};
+
+/// Baud rates tried when scanning, most common first
+pub const COMMON_BAUD_RATES: &[u32] = &[115200, 250000, 230400, 57600, 38400, 19200, 9600];
+
+/// Default time spent listening on each port and baud rate
+///
+/// Long enough for an Arduino to reboot after the port is opened.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// A controller found on a serial port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedDevice {
+    /// Serial port name
+    pub port: String,
+    /// Baud rate the controller answered at
+    pub baud_rate: u32,
+    /// Firmware version (e.g. "Grbl 1.1h"), if reported
+    pub version: Option<String>,
+}
+
+impl DetectedDevice {
+    /// Label for the port selector
+    pub fn label(&self) -> String {
+        let firmware = self.version.as_deref().unwrap_or("GRBL");
+        format!("{} - {} @ {}", self.port, firmware, self.baud_rate)
+    }
+}
+
+/// What a line received from a port says about the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Identification {
+    /// Not recognizably GRBL
+    Unknown,
+    /// A GRBL status report
+    Status,
+    /// A welcome banner or build info with the firmware version
+    Version(String),
+}
+
+/// Identify a line received while probing
+fn identify(line: &str) -> Identification {
+    let line = line.trim();
+
+    // Welcome banner: "Grbl 1.1h ['$' for help]" or "GrblHAL 1.1f ['$' or '$HELP' for help]"
+    if line.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("grbl")) {
+        let version = line.split('[').next().unwrap_or(line).trim();
+        return Identification::Version(version.to_string());
+    }
+
+    // Build info from $I: "[VER:1.1h.20190825:]"
+    if let Some(rest) = line.strip_prefix("[VER:") {
+        let version = rest.split(':').next().unwrap_or("").trim_end_matches(']');
+        if !version.is_empty() {
+            return Identification::Version(format!("Grbl {}", version));
+        }
+    }
+
+    // Status report: "<Idle|MPos:0.000,0.000,0.000|...>"
+    if line.starts_with('<') && line.ends_with('>') && line.contains('|') {
+        return Identification::Status;
+    }
+
+    Identification::Unknown
+}
+
+/// Probe one port at one baud rate
+///
+/// Listens for the welcome banner, sends `?` partway through in case the
+/// board did not reset, and asks for build info (`$I`) once a status report
+/// arrives. Blocks for up to `timeout`.
+pub fn probe_port(port: &str, baud_rate: u32, timeout: Duration) -> Result<Option<DetectedDevice>> {
+    let mut serial = serialport::new(port, baud_rate)
+        .timeout(Duration::from_millis(50))
+        .open()
+        .map_err(|e| Error::connection(format!("Failed to open {}: {}", port, e)))?;
+    let _ = serial.clear(ClearBuffer::Input);
+
+    let reader = serial
+        .try_clone()
+        .map_err(|e| Error::connection(format!("Failed to clone port: {}", e)))?;
+    let mut reader = BufReader::new(reader);
+
+    let start = Instant::now();
+    let mut queried = false;
+    let mut detected = false;
+    let mut line = String::new();
+
+    while start.elapsed() < timeout {
+        if !queried && start.elapsed() >= timeout / 2 {
+            send(serial.as_mut(), b"?");
+            queried = true;
+        }
+
+        line.clear();
+        // Garbage at the wrong baud rate may not be valid UTF-8; skip it
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            continue;
+        }
+
+        match identify(&line) {
+            Identification::Version(version) => {
+                return Ok(Some(DetectedDevice {
+                    port: port.to_string(),
+                    baud_rate,
+                    version: Some(version),
+                }));
+            }
+            Identification::Status if !detected => {
+                detected = true;
+                send(serial.as_mut(), b"$I\n");
+            }
+            _ => {}
+        }
+    }
+
+    Ok(detected.then(|| DetectedDevice {
+        port: port.to_string(),
+        baud_rate,
+        version: None,
+    }))
+}
+
+/// Write to a port, ignoring failures (the probe just times out)
+fn send(serial: &mut dyn SerialPort, data: &[u8]) {
+    let _ = serial.write_all(data).and_then(|_| serial.flush());
+}
+
+/// Scan every available port for GRBL controllers
+///
+/// Each port is tried at each of `baud_rates` until it answers. Ports in
+/// `skip` (e.g. the one already connected) are left alone. Blocks for up to
+/// `timeout` per port and baud rate, so call it off the UI thread.
+pub fn scan_for_grbl(baud_rates: &[u32], timeout: Duration, skip: &[String]) -> Vec<DetectedDevice> {
+    let ports = SerialConnection::list_ports().unwrap_or_default();
+    let mut devices = Vec::new();
+
+    for port in ports {
+        if skip.contains(&port.port_name) {
+            continue;
+        }
+        for &baud_rate in baud_rates {
+            match probe_port(&port.port_name, baud_rate, timeout) {
+                Ok(Some(device)) => {
+                    tracing::info!("Found {}", device.label());
+                    devices.push(device);
+                    break;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // Busy or inaccessible; other baud rates won't help
+                    tracing::debug!("Skipping {}: {}", port.port_name, e);
+                    break;
+                }
+            }
+        }
+    }
+
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identify_welcome() {
+        assert_eq!(
+            identify("Grbl 1.1h ['$' for help]\r\n"),
+            Identification::Version("Grbl 1.1h".to_string())
+        );
+        assert_eq!(
+            identify("GrblHAL 1.1f ['$' or '$HELP' for help]"),
+            Identification::Version("GrblHAL 1.1f".to_string())
+        );
+    }
+
+    #[test]
+    fn test_identify_status_and_build_info() {
+        assert_eq!(identify("<Idle|MPos:0.000,0.000,0.000|FS:0,0>"), Identification::Status);
+        assert_eq!(
+            identify("[VER:1.1h.20190825:]"),
+            Identification::Version("Grbl 1.1h.20190825".to_string())
+        );
+        assert_eq!(identify("ok"), Identification::Unknown);
+        assert_eq!(identify("<garbage>"), Identification::Unknown);
+    }
+
+    #[test]
+    fn test_device_label() {
+        let device = DetectedDevice {
+            port: "/dev/ttyUSB0".to_string(),
+            baud_rate: 115200,
+            version: Some("Grbl 1.1h".to_string()),
+        };
+        assert_eq!(device.label(), "/dev/ttyUSB0 - Grbl 1.1h @ 115200");
+    }
+}
@@ -3,13 +3,17 @@
 //! Coordinates connection lifecycle, command sending, response receiving,
 //! and status broadcasting.
 
-use crate::connection::{Connection, ConnectionEvent, ConnectionStatus};
+use crate::connection::metrics::MetricsTracker;
+use crate::connection::watchdog::{StatusWatchdog, WatchdogAction};
+use crate::connection::{Connection, ConnectionEvent, ConnectionStatus, LinkMetrics};
 use crate::grbl::{CommandQueue, GrblCommand, GrblResponse, GrblStatus, QueueState};
 use crate::utils::error::{Error, Result};
-use std::sync::Arc;
-use std::time::Duration;
+use crate::utils::{TaskFailure, TaskSupervisor};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, RwLock};
-use tokio::time::{interval, sleep};
+use tokio::time::{interval, interval_at, sleep};
 
 /// Default status query interval (milliseconds)
 const DEFAULT_STATUS_INTERVAL_MS: u64 = 250;
@@ -17,25 +21,50 @@ const DEFAULT_STATUS_INTERVAL_MS: u64 = 250;
 /// Default response timeout
 const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
 
+/// Default time to wait for a command acknowledgement
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Default reconnection attempts
 const DEFAULT_RECONNECT_ATTEMPTS: u32 = 3;
 
 /// Default reconnection delay
 const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(2);
 
+/// How long background tasks get to stop on disconnect before being aborted
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Default number of missed status intervals before the link is stale
+const DEFAULT_WATCHDOG_MISSED_INTERVALS: u32 = 4;
+
+/// Default controller RX buffer for character counting on network links
+///
+/// GRBL's serial buffer is 128 bytes; one is kept free.
+const DEFAULT_NETWORK_RX_BUFFER: usize = 127;
+
+/// Longest status query interval on a slow network link
+const MAX_NETWORK_STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Connection manager configuration
 #[derive(Debug, Clone)]
 pub struct ConnectionManagerConfig {
     /// Interval for automatic status queries (milliseconds)
     pub status_interval_ms: u64,
-    /// Response timeout duration
+    /// Response timeout duration, also used when reconnecting
     pub response_timeout: Duration,
+    /// Time to wait for a command to be acknowledged before dropping it
+    pub command_timeout: Duration,
     /// Maximum reconnection attempts on disconnect
     pub reconnect_attempts: u32,
     /// Delay between reconnection attempts
     pub reconnect_delay: Duration,
     /// Enable automatic status queries
     pub auto_status_query: bool,
+    /// Missed status intervals before the watchdog pauses streaming and
+    /// attempts recovery (0 disables the watchdog)
+    pub watchdog_missed_intervals: u32,
+    /// Controller RX buffer size used for character counting on network
+    /// links (0 waits for each "ok" like serial links)
+    pub network_rx_buffer: usize,
 }
 
 impl Default for ConnectionManagerConfig {
@@ -43,9 +72,12 @@ impl Default for ConnectionManagerConfig {
         Self {
             status_interval_ms: DEFAULT_STATUS_INTERVAL_MS,
             response_timeout: DEFAULT_RESPONSE_TIMEOUT,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
             reconnect_attempts: DEFAULT_RECONNECT_ATTEMPTS,
             reconnect_delay: DEFAULT_RECONNECT_DELAY,
             auto_status_query: true,
+            watchdog_missed_intervals: DEFAULT_WATCHDOG_MISSED_INTERVALS,
+            network_rx_buffer: DEFAULT_NETWORK_RX_BUFFER,
         }
     }
 }
@@ -73,11 +105,23 @@ pub struct ConnectionManager {
     /// Response broadcast channel
     response_tx: broadcast::Sender<GrblResponse>,
     
-    /// Shutdown signal
-    shutdown_tx: Option<broadcast::Sender<()>>,
+    /// Background tasks for the active connection
+    supervisor: TaskSupervisor,
     
     /// Current connection status
     status: Arc<RwLock<ConnectionStatus>>,
+    
+    /// Link throughput and latency metrics
+    metrics: Arc<Mutex<MetricsTracker>>,
+    
+    /// Stale status watchdog
+    watchdog: Arc<Mutex<StatusWatchdog>>,
+    
+    /// Whether the connection runs over a network
+    network: bool,
+    
+    /// Whether a program is streaming, so the watchdog must not reconnect
+    job_active: Arc<AtomicBool>,
 }
 
 impl ConnectionManager {
@@ -104,16 +148,36 @@ impl ConnectionManager {
         let (status_tx, _) = broadcast::channel(100);
         let (event_tx, _) = broadcast::channel(100);
         let (response_tx, _) = broadcast::channel(100);
+        let watchdog = StatusWatchdog::new(
+            Duration::from_millis(config.status_interval_ms),
+            config.watchdog_missed_intervals,
+            config.reconnect_attempts,
+            Instant::now(),
+        );
+        
+        let network = connection.is_network();
+        let mut queue = CommandQueue::new();
+        queue.set_timeout(config.command_timeout);
+        if network {
+            queue.set_rx_buffer_size(config.network_rx_buffer);
+        }
+        
+        let mut metrics = MetricsTracker::new();
+        metrics.record_status_interval(Duration::from_millis(config.status_interval_ms));
         
         Self {
             connection: Arc::new(RwLock::new(connection)),
-            queue: Arc::new(RwLock::new(CommandQueue::new())),
+            queue: Arc::new(RwLock::new(queue)),
             config,
             status_tx,
             event_tx,
             response_tx,
-            shutdown_tx: None,
+            supervisor: TaskSupervisor::new(),
             status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
+            metrics: Arc::new(Mutex::new(metrics)),
+            watchdog: Arc::new(Mutex::new(watchdog)),
+            network,
+            job_active: Arc::new(AtomicBool::new(false)),
         }
     }
     
@@ -135,6 +199,8 @@ impl ConnectionManager {
             Ok(()) => {
                 drop(conn); // Release lock before starting background tasks
                 *self.status.write().await = ConnectionStatus::Connected;
+                self.metrics.lock().unwrap().clear_pending();
+                self.watchdog.lock().unwrap().reset(Instant::now());
                 
                 // Broadcast connection event
                 let _ = self.event_tx.send(ConnectionEvent::Connected);
@@ -255,6 +321,19 @@ impl ConnectionManager {
         self.response_tx.subscribe()
     }
     
+    /// Subscribe to background task failures
+    ///
+    /// # Returns
+    /// A receiver for reports of panicked background tasks
+    pub fn subscribe_task_failures(&self) -> broadcast::Receiver<TaskFailure> {
+        self.supervisor.subscribe_failures()
+    }
+    
+    /// Names of the background tasks currently running
+    pub fn running_tasks(&self) -> Vec<String> {
+        self.supervisor.running()
+    }
+    
     /// Pause the command queue
     pub async fn pause(&self) -> Result<()> {
         let queue = self.queue.write().await;
@@ -281,6 +360,34 @@ impl ConnectionManager {
         queue.state().await
     }
     
+    /// Check whether status reports have stopped arriving
+    pub fn is_link_stale(&self) -> bool {
+        self.watchdog.lock().unwrap().is_stale()
+    }
+    
+    /// Tell the watchdog whether a program is streaming
+    ///
+    /// While a job is active a stale link aborts the job instead of
+    /// reconnecting in the middle of it.
+    pub fn set_job_active(&self, active: bool) {
+        self.job_active.store(active, Ordering::SeqCst);
+    }
+    
+    /// Get a snapshot of the link metrics
+    pub async fn metrics(&self) -> LinkMetrics {
+        let queue_stats = self.queue.read().await.get_stats().await;
+        self.metrics.lock().unwrap().snapshot(queue_stats, Instant::now())
+    }
+    
+    /// Reset the link metrics and queue statistics
+    pub async fn reset_metrics(&self) {
+        self.queue.read().await.reset_stats().await;
+        let mut metrics = self.metrics.lock().unwrap();
+        let status_interval = metrics.status_interval();
+        *metrics = MetricsTracker::new();
+        metrics.record_status_interval(status_interval);
+    }
+    
     /// Get connection description
     pub async fn description(&self) -> String {
         let conn = self.connection.read().await;
@@ -289,9 +396,6 @@ impl ConnectionManager {
     
     /// Start background tasks for receiving data and status queries
     async fn start_background_tasks(&mut self) -> Result<()> {
-        let (shutdown_tx, _) = broadcast::channel(1);
-        self.shutdown_tx = Some(shutdown_tx.clone());
-        
         // Task 1: Receive and parse responses
         let connection_recv = Arc::clone(&self.connection);
         let response_tx = self.response_tx.clone();
@@ -299,26 +403,38 @@ impl ConnectionManager {
         let event_tx = self.event_tx.clone();
         let queue_recv = Arc::clone(&self.queue);
         let status_recv = Arc::clone(&self.status);
-        let mut shutdown_rx_recv = shutdown_tx.subscribe();
-        
-        tokio::spawn(async move {
+        let metrics_recv = Arc::clone(&self.metrics);
+        let keep_receiving = self.watchdog_enabled();
+        let retry_delay = self.config.reconnect_delay;
+        // Don't hold the link longer than a status interval, so queries aren't
+        // delayed long enough to trip the watchdog
+        let receive_timeout = Duration::from_millis(self.config.status_interval_ms)
+            .min(DEFAULT_RESPONSE_TIMEOUT);
+        
+        self.supervisor.spawn("reader", |mut shutdown| async move {
             loop {
                 tokio::select! {
-                    _ = shutdown_rx_recv.recv() => {
+                    _ = shutdown.recv() => {
                         break;
                     }
                     result = Self::receive_and_parse(
                         &connection_recv,
+                        receive_timeout,
                         &response_tx,
                         &status_tx,
                         &event_tx,
                         &queue_recv,
+                        &metrics_recv,
                     ) => {
                         if let Err(e) = result {
                             tracing::error!("Error receiving data: {}", e);
                             *status_recv.write().await = ConnectionStatus::Error;
                             let _ = event_tx.send(ConnectionEvent::Error(e.to_string()));
-                            break;
+                            if !keep_receiving {
+                                break;
+                            }
+                            // Keep listening so a watchdog reconnect can recover the link
+                            sleep(retry_delay).await;
                         }
                     }
                 }
@@ -328,16 +444,20 @@ impl ConnectionManager {
         // Task 2: Send commands from queue
         let connection_send = Arc::clone(&self.connection);
         let queue_send = Arc::clone(&self.queue);
-        let mut shutdown_rx_send = shutdown_tx.subscribe();
+        let metrics_send = Arc::clone(&self.metrics);
         
-        tokio::spawn(async move {
+        self.supervisor.spawn("queue pump", |mut shutdown| async move {
             loop {
                 tokio::select! {
-                    _ = shutdown_rx_send.recv() => {
+                    _ = shutdown.recv() => {
                         break;
                     }
                     _ = sleep(Duration::from_millis(10)) => {
-                        if let Err(e) = Self::process_queue(&connection_send, &queue_send).await {
+                        // Drop a command the controller never acknowledged so the queue keeps moving
+                        if let Err(e) = queue_send.read().await.check_timeouts().await {
+                            tracing::warn!("{}; sending the next command", e);
+                        }
+                        if let Err(e) = Self::process_queue(&connection_send, &queue_send, &metrics_send).await {
                             tracing::error!("Error processing queue: {}", e);
                         }
                     }
@@ -348,22 +468,43 @@ impl ConnectionManager {
         // Task 3: Periodic status queries (if enabled)
         if self.config.auto_status_query {
             let connection_status = Arc::clone(&self.connection);
-            let interval_ms = self.config.status_interval_ms;
-            let mut shutdown_rx_status = shutdown_tx.subscribe();
+            let base_interval = Duration::from_millis(self.config.status_interval_ms);
+            let metrics_status = Arc::clone(&self.metrics);
+            let watchdog_status = Arc::clone(&self.watchdog);
+            let adaptive = self.network;
             
-            tokio::spawn(async move {
-                let mut timer = interval(Duration::from_millis(interval_ms));
+            self.supervisor.spawn("status poller", |mut shutdown| async move {
+                let mut current_interval = base_interval;
+                let mut timer = interval(current_interval);
                 loop {
                     tokio::select! {
-                        _ = shutdown_rx_status.recv() => {
+                        _ = shutdown.recv() => {
                             break;
                         }
                         _ = timer.tick() => {
                             let mut conn = connection_status.write().await;
                             if conn.is_connected() {
                                 // Send status query (? is 0x3F)
-                                if let Err(e) = conn.send_bytes(&[b'?']).await {
-                                    tracing::error!("Error sending status query: {}", e);
+                                match conn.send_bytes(&[b'?']).await {
+                                    Ok(()) => metrics_status.lock().unwrap().record_status_query(Instant::now()),
+                                    Err(e) => {
+                                        tracing::error!("Error sending status query: {}", e);
+                                        metrics_status.lock().unwrap().record_serial_error();
+                                    }
+                                }
+                            }
+                            drop(conn);
+                            
+                            // Slow down on links whose round trip approaches the interval
+                            if adaptive {
+                                let rtt = metrics_status.lock().unwrap().status_rtt();
+                                let next = Self::adaptive_status_interval(base_interval, rtt);
+                                if next != current_interval {
+                                    tracing::debug!("Status interval now {:?} (round trip {:?} ms)", next, rtt);
+                                    current_interval = next;
+                                    timer = interval_at(tokio::time::Instant::now() + next, next);
+                                    metrics_status.lock().unwrap().record_status_interval(next);
+                                    watchdog_status.lock().unwrap().set_interval(next);
                                 }
                             }
                         }
@@ -372,29 +513,155 @@ impl ConnectionManager {
             });
         }
         
+        // Task 4: Watchdog for stale status reports (if enabled)
+        if self.watchdog_enabled() {
+            let connection_watchdog = Arc::clone(&self.connection);
+            let queue_watchdog = Arc::clone(&self.queue);
+            let status_watchdog = Arc::clone(&self.status);
+            let event_watchdog = self.event_tx.clone();
+            let watchdog = Arc::clone(&self.watchdog);
+            let metrics_watchdog = Arc::clone(&self.metrics);
+            let job_active = Arc::clone(&self.job_active);
+            let config = self.config.clone();
+            let mut status_rx_watchdog = self.status_tx.subscribe();
+            
+            self.supervisor.spawn("status watchdog", |mut shutdown| async move {
+                let mut timer = interval(Duration::from_millis(config.status_interval_ms));
+                loop {
+                    tokio::select! {
+                        _ = shutdown.recv() => {
+                            break;
+                        }
+                        Ok(_) = status_rx_watchdog.recv() => {
+                            if watchdog.lock().unwrap().status_received(Instant::now()) {
+                                tracing::info!("Status reports restored");
+                                let _ = event_watchdog.send(ConnectionEvent::StatusRestored);
+                            }
+                        }
+                        _ = timer.tick() => {
+                            let action = watchdog.lock().unwrap().check(Instant::now());
+                            Self::handle_watchdog_action(
+                                action,
+                                &connection_watchdog,
+                                &queue_watchdog,
+                                &status_watchdog,
+                                &event_watchdog,
+                                &metrics_watchdog,
+                                &job_active,
+                                &config,
+                            ).await;
+                        }
+                    }
+                }
+            });
+        }
+        
         Ok(())
     }
     
-    /// Stop background tasks
-    async fn stop_background_tasks(&mut self) {
-        if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(());
+    /// Status query interval for a network link with the given round trip
+    ///
+    /// Queries are spaced at least two status round trips apart, so reports
+    /// don't pile up behind each other on slow links.
+    fn adaptive_status_interval(base: Duration, status_rtt_ms: Option<f64>) -> Duration {
+        let Some(rtt_ms) = status_rtt_ms.filter(|rtt| rtt.is_finite() && *rtt > 0.0) else {
+            return base;
+        };
+        Duration::from_secs_f64(rtt_ms * 2.0 / 1000.0).clamp(base, MAX_NETWORK_STATUS_INTERVAL.max(base))
+    }
+    
+    /// Whether the stale status watchdog should run
+    fn watchdog_enabled(&self) -> bool {
+        self.config.auto_status_query && self.config.watchdog_missed_intervals > 0
+    }
+    
+    /// Carry out a watchdog action
+    async fn handle_watchdog_action(
+        action: WatchdogAction,
+        connection: &Arc<RwLock<Box<dyn Connection>>>,
+        queue: &Arc<RwLock<CommandQueue>>,
+        status: &Arc<RwLock<ConnectionStatus>>,
+        event_tx: &broadcast::Sender<ConnectionEvent>,
+        metrics: &Arc<Mutex<MetricsTracker>>,
+        job_active: &AtomicBool,
+        config: &ConnectionManagerConfig,
+    ) {
+        match action {
+            WatchdogAction::None => {}
+            WatchdogAction::Stale(missed) => {
+                tracing::warn!("No status report for {} intervals, pausing stream", missed);
+                queue.read().await.pause().await;
+                let _ = event_tx.send(ConnectionEvent::StatusStale { missed });
+                
+                // Re-issue the status query in case only the query was lost
+                let mut conn = connection.write().await;
+                if conn.send_bytes(b"?").await.is_ok() {
+                    metrics.lock().unwrap().record_status_query(Instant::now());
+                }
+            }
+            WatchdogAction::Reconnect(attempt) if job_active.swap(false, Ordering::SeqCst) => {
+                // Reconnecting toggles DTR, which resets GRBL and loses its
+                // position, so the job is abandoned rather than carried on
+                tracing::error!("Status still stale while streaming; aborting the job instead of reconnecting");
+                queue.read().await.clear().await;
+                let _ = event_tx.send(ConnectionEvent::JobAborted);
+            }
+            WatchdogAction::Reconnect(attempt) => {
+                tracing::warn!("Status still stale, reconnecting (attempt {})", attempt);
+                let _ = event_tx.send(ConnectionEvent::Reconnecting { attempt });
+                *status.write().await = ConnectionStatus::Connecting;
+                
+                let _ = connection.write().await.disconnect().await;
+                sleep(config.reconnect_delay).await;
+                
+                let result = connection.write().await.connect(config.response_timeout).await;
+                match result {
+                    Ok(()) => {
+                        *status.write().await = ConnectionStatus::Connected;
+                        metrics.lock().unwrap().clear_pending();
+                    }
+                    Err(e) => {
+                        tracing::error!("Reconnect attempt {} failed: {}", attempt, e);
+                        *status.write().await = ConnectionStatus::Error;
+                        metrics.lock().unwrap().record_serial_error();
+                    }
+                }
+            }
+            WatchdogAction::GiveUp => {
+                tracing::error!("Controller stopped responding; giving up on recovery");
+                *status.write().await = ConnectionStatus::Error;
+                let _ = event_tx.send(ConnectionEvent::Error(
+                    "Controller stopped responding to status queries".to_string(),
+                ));
+            }
         }
     }
     
+    /// Stop background tasks, waiting for them to finish
+    async fn stop_background_tasks(&mut self) {
+        self.supervisor.shutdown(SHUTDOWN_GRACE).await;
+    }
+    
     /// Receive and parse data from connection
     async fn receive_and_parse(
         connection: &Arc<RwLock<Box<dyn Connection>>>,
+        receive_timeout: Duration,
         response_tx: &broadcast::Sender<GrblResponse>,
         status_tx: &broadcast::Sender<GrblStatus>,
         event_tx: &broadcast::Sender<ConnectionEvent>,
         queue: &Arc<RwLock<CommandQueue>>,
+        metrics: &Arc<Mutex<MetricsTracker>>,
     ) -> Result<()> {
         let mut conn = connection.write().await;
         
-        match conn.receive_line(DEFAULT_RESPONSE_TIMEOUT).await? {
+        let received = conn.receive_line(receive_timeout).await;
+        if received.is_err() {
+            metrics.lock().unwrap().record_serial_error();
+        }
+        match received? {
             Some(line) => {
                 tracing::debug!("Received: {}", line);
+                metrics.lock().unwrap().record_line_received(line.len() + 1);
                 
                 // Broadcast raw data event
                 let _ = event_tx.send(ConnectionEvent::DataReceived(line.clone()));
@@ -408,10 +675,12 @@ impl ConnectionManager {
                         // Handle specific response types
                         match &response {
                             GrblResponse::Ok => {
+                                metrics.lock().unwrap().record_ack(Instant::now());
                                 let q = queue.write().await;
                                 q.handle_response(&response).await?;
                             }
                             GrblResponse::Error(_) => {
+                                metrics.lock().unwrap().record_ack(Instant::now());
                                 let q = queue.write().await;
                                 q.handle_response(&response).await?;
                             }
@@ -420,6 +689,8 @@ impl ConnectionManager {
                                 q.handle_response(&response).await?;
                             }
                             GrblResponse::Status(status) => {
+                                metrics.lock().unwrap().record_status(Instant::now());
+                                
                                 // Broadcast status update
                                 let _ = status_tx.send(status.clone());
                             }
@@ -428,6 +699,7 @@ impl ConnectionManager {
                     }
                     Err(e) => {
                         tracing::warn!("Failed to parse response: {} - Error: {}", line, e);
+                        metrics.lock().unwrap().record_parse_error();
                     }
                 }
             }
@@ -440,42 +712,58 @@ impl ConnectionManager {
     }
     
     /// Process the command queue
+    ///
+    /// Sends every command the queue has ready. With character counting
+    /// several may be ready; they go out in one write so a network link
+    /// carries them in a single packet or message.
     async fn process_queue(
         connection: &Arc<RwLock<Box<dyn Connection>>>,
         queue: &Arc<RwLock<CommandQueue>>,
+        metrics: &Arc<Mutex<MetricsTracker>>,
     ) -> Result<()> {
         let q = queue.write().await;
         
         // Check if we can send the next command
-        if let Some(command) = q.next_command().await? {
-            let command_str = command.to_string();
-            tracing::info!("Sending command to GRBL: {}", command_str);
-            
-            // Send the command
-            let mut conn = connection.write().await;
-            if !conn.is_connected() {
-                tracing::error!("Attempted to send command but connection is not active");
-                return Err(Error::Connection("Not connected".to_string()));
-            }
-            
-            conn.send_line(&command_str).await?;
-            tracing::info!("Command sent successfully: {}", command_str);
-            
-            // Mark as sent in queue
+        let Some(first) = q.next_command().await? else {
+            return Ok(());
+        };
+        
+        let mut conn = connection.write().await;
+        if !conn.is_connected() {
+            tracing::error!("Attempted to send command but connection is not active");
+            return Err(Error::Connection("Not connected".to_string()));
+        }
+        
+        // Collect the commands that fit, marking each as sent so the next
+        // one is checked against the buffer space left
+        let mut batch = vec![first.to_string()];
+        q.mark_sent().await?;
+        while let Some(command) = q.next_command().await? {
+            batch.push(command.to_string());
             q.mark_sent().await?;
         }
         
+        let data = batch.join("\n");
+        tracing::info!("Sending {} command(s) to GRBL: {}", batch.len(), data);
+        if let Err(e) = conn.send_line(&data).await {
+            metrics.lock().unwrap().record_serial_error();
+            return Err(e);
+        }
+        let now = Instant::now();
+        let mut metrics = metrics.lock().unwrap();
+        for line in &batch {
+            metrics.record_line_sent(line.len() + 1, now);
+        }
+        
         Ok(())
     }
 }
 
 impl Drop for ConnectionManager {
     fn drop(&mut self) {
-        // Best effort cleanup - spawn a task to shutdown gracefully
-        // Note: In a real shutdown, disconnect() should be called explicitly
-        if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(());
-        }
+        // Best effort cleanup - disconnect() shuts tasks down gracefully,
+        // here they can only be aborted
+        self.supervisor.abort_all();
     }
 }
 
@@ -615,14 +903,65 @@ mod tests {
         drop(rx);
     }
     
+    #[test]
+    fn test_adaptive_status_interval() {
+        let base = Duration::from_millis(250);
+        assert_eq!(ConnectionManager::adaptive_status_interval(base, None), base);
+        assert_eq!(ConnectionManager::adaptive_status_interval(base, Some(20.0)), base);
+        assert_eq!(
+            ConnectionManager::adaptive_status_interval(base, Some(300.0)),
+            Duration::from_millis(600)
+        );
+        assert_eq!(
+            ConnectionManager::adaptive_status_interval(base, Some(5000.0)),
+            MAX_NETWORK_STATUS_INTERVAL
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_watchdog_aborts_job_instead_of_reconnecting() {
+        let config = ConnectionManagerConfig {
+            reconnect_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let manager = ConnectionManager::with_config(Box::new(MockConnection::new()), config);
+        let mut events = manager.subscribe_events();
+        let manager = &manager;
+        let reconnect = move |attempt| {
+            ConnectionManager::handle_watchdog_action(
+                WatchdogAction::Reconnect(attempt),
+                &manager.connection,
+                &manager.queue,
+                &manager.status,
+                &manager.event_tx,
+                &manager.metrics,
+                &manager.job_active,
+                &manager.config,
+            )
+        };
+        
+        manager.set_job_active(true);
+        reconnect(1).await;
+        assert!(matches!(events.try_recv(), Ok(ConnectionEvent::JobAborted)));
+        assert!(!manager.connection.read().await.is_connected());
+        
+        // With the job gone the next attempt reconnects
+        reconnect(2).await;
+        assert!(matches!(events.try_recv(), Ok(ConnectionEvent::Reconnecting { attempt: 2 })));
+        assert_eq!(manager.status().await, ConnectionStatus::Connected);
+    }
+    
     #[tokio::test]
     async fn test_manager_config() {
         let config = ConnectionManagerConfig {
             status_interval_ms: 100,
             response_timeout: Duration::from_secs(2),
+            command_timeout: Duration::from_secs(5),
             reconnect_attempts: 5,
             reconnect_delay: Duration::from_secs(3),
             auto_status_query: false,
+            watchdog_missed_intervals: 0,
+            network_rx_buffer: 0,
         };
         
         let conn = Box::new(MockConnection::new());
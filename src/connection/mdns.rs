@@ -0,0 +1,320 @@
+//! Network device discovery
+//!
+//! FluidNC and grblHAL boards with networking advertise their Telnet and
+//! WebSocket servers over mDNS (Bonjour). Discovery sends a one-shot
+//! ("legacy unicast") mDNS query for those service types and collects the
+//! answers for a short while, so no multicast group has to be joined.
+
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::utils::error::{Error, Result};
+
+/// mDNS multicast address and port
+const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+
+/// DNS record types used by discovery
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_SRV: u16 = 33;
+
+/// Default time spent listening for answers
+pub const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Protocol of a network connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NetworkProtocol {
+    /// Raw TCP/Telnet stream
+    #[default]
+    Telnet,
+    /// WebSocket (ws:// or wss://)
+    WebSocket,
+}
+
+impl NetworkProtocol {
+    /// mDNS service type advertising the protocol
+    pub fn service_type(&self) -> &'static str {
+        match self {
+            NetworkProtocol::Telnet => "_telnet._tcp.local",
+            NetworkProtocol::WebSocket => "_websocket._tcp.local",
+        }
+    }
+
+    /// Port used when none is given
+    pub fn default_port(&self) -> u16 {
+        match self {
+            NetworkProtocol::Telnet => 23,
+            NetworkProtocol::WebSocket => 81,
+        }
+    }
+}
+
+impl std::fmt::Display for NetworkProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkProtocol::Telnet => write!(f, "Telnet"),
+            NetworkProtocol::WebSocket => write!(f, "WebSocket"),
+        }
+    }
+}
+
+/// A controller found on the network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDevice {
+    /// Advertised instance name, e.g. "fluidnc"
+    pub name: String,
+    /// Protocol of the advertised service
+    pub protocol: NetworkProtocol,
+    /// IPv4 address, or the host name if no address was included
+    pub host: String,
+    /// Service port
+    pub port: u16,
+}
+
+impl NetworkDevice {
+    /// Label for the connection selector
+    pub fn label(&self) -> String {
+        format!("{} - {} {}:{}", self.name, self.protocol, self.host, self.port)
+    }
+}
+
+/// A resource record of interest from an mDNS answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Record {
+    Ptr { name: String, target: String },
+    Srv { name: String, port: u16, target: String },
+    A { name: String, address: Ipv4Addr },
+}
+
+/// Discover Telnet and WebSocket controllers on the local network
+///
+/// Blocks for `timeout` while collecting answers.
+pub fn discover_network_devices(timeout: Duration) -> Result<Vec<NetworkDevice>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| Error::connection(format!("Failed to open discovery socket: {}", e)))?;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .map_err(|e| Error::connection(format!("Failed to configure discovery socket: {}", e)))?;
+
+    let services = [NetworkProtocol::Telnet, NetworkProtocol::WebSocket];
+    let query = build_query(&services.map(|p| p.service_type()));
+    socket
+        .send_to(&query, SocketAddr::from(MDNS_ADDR))
+        .map_err(|e| Error::connection(format!("Failed to send discovery query: {}", e)))?;
+
+    let start = Instant::now();
+    let mut records = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while start.elapsed() < timeout {
+        if let Ok((len, _)) = socket.recv_from(&mut buffer) {
+            records.extend(parse_records(&buffer[..len]));
+        }
+    }
+    Ok(assemble(&records))
+}
+
+/// Build an mDNS query asking for PTR records of the given names
+fn build_query(names: &[&str]) -> Vec<u8> {
+    let mut packet = vec![0, 0, 0, 0];
+    packet.extend_from_slice(&(names.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    for name in names {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&1u16.to_be_bytes());
+    }
+    packet
+}
+
+/// Read a possibly compressed name at `offset`, returning it and the offset after it
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bound the number of pointer jumps so a malicious packet can't loop
+    for _ in 0..64 {
+        let len = *packet.get(offset)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(offset + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            let pointer = ((len & 0x3F) << 8) | *packet.get(offset + 1)? as usize;
+            end.get_or_insert(offset + 2);
+            offset = pointer;
+            continue;
+        }
+        let label = packet.get(offset + 1..offset + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        offset += 1 + len;
+    }
+    None
+}
+
+/// Read a big-endian u16
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(offset)?, *packet.get(offset + 1)?]))
+}
+
+/// PTR, SRV and A records of an mDNS response
+fn parse_records(packet: &[u8]) -> Vec<Record> {
+    let mut records = Vec::new();
+    let (Some(questions), Some(answers), Some(authority), Some(additional)) = (
+        read_u16(packet, 4),
+        read_u16(packet, 6),
+        read_u16(packet, 8),
+        read_u16(packet, 10),
+    ) else {
+        return records;
+    };
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        let Some((_, next)) = read_name(packet, offset) else {
+            return records;
+        };
+        offset = next + 4;
+    }
+
+    for _ in 0..(answers as usize + authority as usize + additional as usize) {
+        let Some((name, next)) = read_name(packet, offset) else {
+            break;
+        };
+        let (Some(kind), Some(length)) = (read_u16(packet, next), read_u16(packet, next + 8)) else {
+            break;
+        };
+        let data = next + 10;
+        match kind {
+            TYPE_PTR => {
+                if let Some((target, _)) = read_name(packet, data) {
+                    records.push(Record::Ptr { name, target });
+                }
+            }
+            TYPE_SRV => {
+                if let (Some(port), Some((target, _))) = (read_u16(packet, data + 4), read_name(packet, data + 6)) {
+                    records.push(Record::Srv { name, port, target });
+                }
+            }
+            TYPE_A if length == 4 => {
+                if let Some(bytes) = packet.get(data..data + 4) {
+                    let address = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+                    records.push(Record::A { name, address });
+                }
+            }
+            _ => {}
+        }
+        offset = data + length as usize;
+    }
+    records
+}
+
+/// Join PTR, SRV and A records into devices
+fn assemble(records: &[Record]) -> Vec<NetworkDevice> {
+    let mut devices: Vec<NetworkDevice> = Vec::new();
+    for record in records {
+        let Record::Ptr { name: service, target: instance } = record else {
+            continue;
+        };
+        let protocol = match service.as_str() {
+            s if s.eq_ignore_ascii_case(NetworkProtocol::Telnet.service_type()) => NetworkProtocol::Telnet,
+            s if s.eq_ignore_ascii_case(NetworkProtocol::WebSocket.service_type()) => NetworkProtocol::WebSocket,
+            _ => continue,
+        };
+        let Some((port, target)) = records.iter().find_map(|r| match r {
+            Record::Srv { name, port, target } if name == instance => Some((*port, target)),
+            _ => None,
+        }) else {
+            continue;
+        };
+        let host = records
+            .iter()
+            .find_map(|r| match r {
+                Record::A { name, address } if name == target => Some(address.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| target.clone());
+        let name = instance
+            .strip_suffix(&format!(".{}", service))
+            .unwrap_or(instance)
+            .to_string();
+        let device = NetworkDevice { name, protocol, host, port };
+        if !devices.contains(&device) {
+            devices.push(device);
+        }
+    }
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Append an uncompressed name
+    fn push_name(packet: &mut Vec<u8>, name: &str) {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+    }
+
+    /// Append a record header and its data
+    fn push_record(packet: &mut Vec<u8>, name: &str, kind: u16, data: &[u8]) {
+        push_name(packet, name);
+        packet.extend_from_slice(&kind.to_be_bytes());
+        packet.extend_from_slice(&[0x80, 0x01, 0, 0, 0x11, 0x94]);
+        packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        packet.extend_from_slice(data);
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = build_query(&["_telnet._tcp.local"]);
+        assert_eq!(read_u16(&query, 4), Some(1));
+        assert_eq!(read_name(&query, 12), Some(("_telnet._tcp.local".to_string(), 32)));
+        assert_eq!(read_u16(&query, 32), Some(TYPE_PTR));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 0];
+
+        let mut ptr = Vec::new();
+        push_name(&mut ptr, "fluidnc._telnet._tcp.local");
+        push_record(&mut packet, "_telnet._tcp.local", TYPE_PTR, &ptr);
+
+        let mut srv = vec![0, 0, 0, 0, 0, 23];
+        push_name(&mut srv, "fluidnc.local");
+        push_record(&mut packet, "fluidnc._telnet._tcp.local", TYPE_SRV, &srv);
+
+        push_record(&mut packet, "fluidnc.local", TYPE_A, &[192, 168, 1, 40]);
+
+        let records = parse_records(&packet);
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            assemble(&records),
+            vec![NetworkDevice {
+                name: "fluidnc".to_string(),
+                protocol: NetworkProtocol::Telnet,
+                host: "192.168.1.40".to_string(),
+                port: 23,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compressed_name() {
+        // "local" at offset 12, then "grbl" + pointer to it
+        let mut packet = vec![0; 12];
+        push_name(&mut packet, "local");
+        packet.extend_from_slice(&[4, b'g', b'r', b'b', b'l', 0xC0, 12]);
+        assert_eq!(read_name(&packet, 19), Some(("grbl.local".to_string(), 26)));
+
+        // A pointer to itself is rejected instead of looping
+        let looped = [0xC0, 0];
+        assert_eq!(read_name(&looped, 0), None);
+    }
+}
@@ -0,0 +1,353 @@
+//! Link metrics
+//!
+//! Tracks throughput and latency of the controller link so users can tune
+//! buffer mode and baud settings.
+
+use crate::grbl::QueueStats;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window used for the lines-per-second rate
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Smoothing factor for the latency averages
+const SMOOTHING: f64 = 0.2;
+
+/// Maximum number of unanswered status queries remembered
+const MAX_PENDING_STATUS: usize = 8;
+
+/// Snapshot of link metrics
+#[derive(Debug, Clone, Default)]
+pub struct LinkMetrics {
+    /// Command queue statistics
+    pub queue: QueueStats,
+    /// Lines sent per second over the last few seconds
+    pub lines_per_sec: f64,
+    /// Smoothed time from sending a line to its `ok`/`error` (milliseconds)
+    pub avg_ack_latency_ms: f64,
+    /// Smoothed round trip of a `?` query to its status report (milliseconds)
+    pub status_rtt_ms: f64,
+    /// Current interval between status queries (milliseconds)
+    ///
+    /// Stretched on slow network links.
+    pub status_interval_ms: u64,
+    /// Transport errors while sending or receiving
+    pub serial_errors: u64,
+    /// Received lines that could not be parsed
+    pub parse_errors: u64,
+    /// Total bytes sent
+    pub bytes_sent: u64,
+    /// Total bytes received
+    pub bytes_received: u64,
+}
+
+impl LinkMetrics {
+    /// Look up a metric by name, as exposed to scripts
+    pub fn get(&self, name: &str) -> Option<f64> {
+        let value = match name {
+            "lines_per_sec" => self.lines_per_sec,
+            "avg_ack_latency_ms" => self.avg_ack_latency_ms,
+            "status_rtt_ms" => self.status_rtt_ms,
+            "status_interval_ms" => self.status_interval_ms as f64,
+            "serial_errors" => self.serial_errors as f64,
+            "parse_errors" => self.parse_errors as f64,
+            "bytes_sent" => self.bytes_sent as f64,
+            "bytes_received" => self.bytes_received as f64,
+            "queue_length" => self.queue.current_length as f64,
+            "commands_sent" => self.queue.total_sent as f64,
+            "commands_completed" => self.queue.total_completed as f64,
+            "commands_failed" => self.queue.total_failed as f64,
+            "commands_timed_out" => self.queue.total_timeouts as f64,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Accumulates link metrics as traffic passes through the connection manager
+#[derive(Debug, Default)]
+pub(crate) struct MetricsTracker {
+    /// Send times of recent lines
+    sent_times: VecDeque<Instant>,
+    /// Send times of lines awaiting acknowledgment
+    pending_acks: VecDeque<Instant>,
+    /// Send times of unanswered status queries
+    pending_status: VecDeque<Instant>,
+    /// Smoothed acknowledgment latency
+    ack_latency_ms: Option<f64>,
+    /// Smoothed status round trip
+    status_rtt_ms: Option<f64>,
+    /// Current status query interval
+    status_interval: Duration,
+    serial_errors: u64,
+    parse_errors: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+impl MetricsTracker {
+    /// Create an empty tracker
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a line sent to the controller
+    pub(crate) fn record_line_sent(&mut self, bytes: usize, now: Instant) {
+        self.bytes_sent += bytes as u64;
+        self.sent_times.push_back(now);
+        self.pending_acks.push_back(now);
+        self.prune(now);
+    }
+
+    /// Record a status query sent to the controller
+    pub(crate) fn record_status_query(&mut self, now: Instant) {
+        self.bytes_sent += 1;
+        if self.pending_status.len() >= MAX_PENDING_STATUS {
+            self.pending_status.pop_front();
+        }
+        self.pending_status.push_back(now);
+    }
+
+    /// Record a line received from the controller
+    pub(crate) fn record_line_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes as u64;
+    }
+
+    /// Record an `ok` or `error` acknowledgment
+    pub(crate) fn record_ack(&mut self, now: Instant) {
+        if let Some(sent) = self.pending_acks.pop_front() {
+            let latency = now.duration_since(sent).as_secs_f64() * 1000.0;
+            self.ack_latency_ms = Some(smooth(self.ack_latency_ms, latency));
+        }
+    }
+
+    /// Record a status report
+    pub(crate) fn record_status(&mut self, now: Instant) {
+        if let Some(sent) = self.pending_status.pop_front() {
+            let rtt = now.duration_since(sent).as_secs_f64() * 1000.0;
+            self.status_rtt_ms = Some(smooth(self.status_rtt_ms, rtt));
+        }
+    }
+
+    /// Smoothed status round trip so far (milliseconds)
+    pub(crate) fn status_rtt(&self) -> Option<f64> {
+        self.status_rtt_ms
+    }
+
+    /// Record the interval status queries are sent at
+    pub(crate) fn record_status_interval(&mut self, interval: Duration) {
+        self.status_interval = interval;
+    }
+
+    /// Current status query interval
+    pub(crate) fn status_interval(&self) -> Duration {
+        self.status_interval
+    }
+
+    /// Record a transport error
+    pub(crate) fn record_serial_error(&mut self) {
+        self.serial_errors += 1;
+    }
+
+    /// Record a line that failed to parse
+    pub(crate) fn record_parse_error(&mut self) {
+        self.parse_errors += 1;
+    }
+
+    /// Forget lines awaiting acknowledgment (e.g. after a reset)
+    pub(crate) fn clear_pending(&mut self) {
+        self.pending_acks.clear();
+        self.pending_status.clear();
+    }
+
+    /// Build a snapshot of the current metrics
+    pub(crate) fn snapshot(&mut self, queue: QueueStats, now: Instant) -> LinkMetrics {
+        self.prune(now);
+        LinkMetrics {
+            queue,
+            lines_per_sec: self.sent_times.len() as f64 / RATE_WINDOW.as_secs_f64(),
+            avg_ack_latency_ms: self.ack_latency_ms.unwrap_or(0.0),
+            status_rtt_ms: self.status_rtt_ms.unwrap_or(0.0),
+            status_interval_ms: self.status_interval.as_millis() as u64,
+            serial_errors: self.serial_errors,
+            parse_errors: self.parse_errors,
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+        }
+    }
+
+    /// Drop send times that have left the rate window
+    fn prune(&mut self, now: Instant) {
+        while let Some(&oldest) = self.sent_times.front() {
+            if now.duration_since(oldest) > RATE_WINDOW {
+                self.sent_times.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// TX/RX activity and controller buffer fill for status indicators
+///
+/// Fed with metrics snapshots and status report buffer values; an indicator
+/// stays lit for a short hold time after its byte counter last moved.
+#[derive(Debug, Clone, Default)]
+pub struct LinkActivity {
+    /// Bytes sent at the last update
+    bytes_sent: u64,
+    /// Bytes received at the last update
+    bytes_received: u64,
+    /// When bytes were last seen going out
+    last_tx: Option<Instant>,
+    /// When bytes were last seen coming in
+    last_rx: Option<Instant>,
+    /// Most planner blocks ever reported free (the planner size)
+    planner_capacity: u32,
+    /// Most RX buffer bytes ever reported free (the buffer size)
+    rx_capacity: u32,
+    /// Planner blocks free in the last report
+    planner_free: u32,
+    /// RX buffer bytes free in the last report
+    rx_free: u32,
+}
+
+impl LinkActivity {
+    /// Create with no recorded activity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the byte counters from a metrics snapshot
+    pub fn update(&mut self, metrics: &LinkMetrics, now: Instant) {
+        if metrics.bytes_sent != self.bytes_sent {
+            self.last_tx = Some(now);
+        }
+        if metrics.bytes_received != self.bytes_received {
+            self.last_rx = Some(now);
+        }
+        self.bytes_sent = metrics.bytes_sent;
+        self.bytes_received = metrics.bytes_received;
+    }
+
+    /// Record free buffer space from a status report (`Bf:planner,rx`)
+    ///
+    /// GRBL reports free space, so the largest value seen (normally while
+    /// idle) is taken as the buffer size.
+    pub fn update_buffer(&mut self, planner_free: u32, rx_free: u32) {
+        self.planner_capacity = self.planner_capacity.max(planner_free);
+        self.rx_capacity = self.rx_capacity.max(rx_free);
+        self.planner_free = planner_free;
+        self.rx_free = rx_free;
+    }
+
+    /// Whether data went out within `hold`
+    pub fn tx_active(&self, now: Instant, hold: Duration) -> bool {
+        self.last_tx.is_some_and(|t| now.duration_since(t) <= hold)
+    }
+
+    /// Whether data came in within `hold`
+    pub fn rx_active(&self, now: Instant, hold: Duration) -> bool {
+        self.last_rx.is_some_and(|t| now.duration_since(t) <= hold)
+    }
+
+    /// Planner and RX buffer fill (0.0 to 1.0), if reported
+    pub fn buffer_fill(&self) -> Option<(f32, f32)> {
+        if self.planner_capacity == 0 || self.rx_capacity == 0 {
+            return None;
+        }
+        let fill = |free: u32, capacity: u32| 1.0 - free as f32 / capacity as f32;
+        Some((
+            fill(self.planner_free, self.planner_capacity),
+            fill(self.rx_free, self.rx_capacity),
+        ))
+    }
+}
+
+/// Blend a new sample into a smoothed average
+fn smooth(average: Option<f64>, sample: f64) -> f64 {
+    match average {
+        Some(average) => average + SMOOTHING * (sample - average),
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_per_second() {
+        let start = Instant::now();
+        let mut tracker = MetricsTracker::new();
+        for i in 0..10 {
+            tracker.record_line_sent(10, start + Duration::from_millis(i * 100));
+        }
+
+        let metrics = tracker.snapshot(QueueStats::default(), start + Duration::from_secs(1));
+        assert_eq!(metrics.lines_per_sec, 2.0);
+        assert_eq!(metrics.bytes_sent, 100);
+
+        // Lines age out of the window
+        let metrics = tracker.snapshot(QueueStats::default(), start + Duration::from_secs(10));
+        assert_eq!(metrics.lines_per_sec, 0.0);
+    }
+
+    #[test]
+    fn test_ack_latency_and_status_rtt() {
+        let start = Instant::now();
+        let mut tracker = MetricsTracker::new();
+
+        tracker.record_line_sent(5, start);
+        tracker.record_ack(start + Duration::from_millis(20));
+        tracker.record_status_query(start);
+        tracker.record_status(start + Duration::from_millis(8));
+
+        let metrics = tracker.snapshot(QueueStats::default(), start);
+        assert!((metrics.avg_ack_latency_ms - 20.0).abs() < 1e-6);
+        assert!((metrics.status_rtt_ms - 8.0).abs() < 1e-6);
+
+        // An unmatched ack leaves the average alone
+        tracker.record_ack(start + Duration::from_secs(5));
+        let metrics = tracker.snapshot(QueueStats::default(), start);
+        assert!((metrics.avg_ack_latency_ms - 20.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_link_activity() {
+        let start = Instant::now();
+        let hold = Duration::from_millis(300);
+        let mut activity = LinkActivity::new();
+        assert!(!activity.tx_active(start, hold));
+
+        let metrics = LinkMetrics {
+            bytes_sent: 10,
+            ..Default::default()
+        };
+        activity.update(&metrics, start);
+        assert!(activity.tx_active(start + Duration::from_millis(100), hold));
+        assert!(!activity.rx_active(start + Duration::from_millis(100), hold));
+
+        // No change since, so the blinker goes out
+        activity.update(&metrics, start + Duration::from_millis(250));
+        assert!(!activity.tx_active(start + Duration::from_millis(400), hold));
+
+        assert_eq!(activity.buffer_fill(), None);
+        activity.update_buffer(15, 128);
+        activity.update_buffer(5, 64);
+        let (planner, rx) = activity.buffer_fill().unwrap();
+        assert!((planner - 2.0 / 3.0).abs() < 1e-6);
+        assert!((rx - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_metric_lookup() {
+        let metrics = LinkMetrics {
+            serial_errors: 3,
+            ..Default::default()
+        };
+        assert_eq!(metrics.get("serial_errors"), Some(3.0));
+        assert_eq!(metrics.get("queue_length"), Some(0.0));
+        assert_eq!(metrics.get("nonsense"), None);
+    }
+}
@@ -0,0 +1,1176 @@
+//! Scripted mock GRBL device
+//!
+//! Simulates a GRBL 1.1 controller in-process: status reports, settings
+//! queries, jogging, feed hold/resume, soft reset and program streaming with
+//! a bounded serial RX buffer and planner. Motion takes simulated time based
+//! on distance and feed rate, so `ok` responses are delayed when the planner
+//! is full just like on real hardware.
+//!
+//! With [`MockDeviceConfig::acceleration_limited`] each block follows a
+//! trapezoidal velocity profile limited by the axis max rates ($110-$112)
+//! and accelerations ($120-$122), with junction speeds from the junction
+//! deviation ($11) like GRBL's planner, so dry runs take realistic time.
+//! The values can be changed with `$110=...` like on a real controller.
+//!
+//! The device can be used as a regular [`Connection`] (e.g. to try the UI
+//! without a machine) and as a test fixture: [`MockScript`] injects custom
+//! responses for matching commands, and [`MockStats`] records protocol
+//! violations such as RX buffer overflows.
+
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::connection::traits::{Connection, ConnectionStatus};
+use crate::parser::{Token, Tokenizer};
+use crate::utils::error::{Error, Result};
+
+/// Interval at which `receive_line` re-checks for scheduled output
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Mock device configuration
+#[derive(Debug, Clone)]
+pub struct MockDeviceConfig {
+    /// Reported firmware version
+    pub version: String,
+    /// Number of planner blocks
+    pub planner_blocks: usize,
+    /// Serial RX buffer size in bytes
+    pub rx_buffer_size: usize,
+    /// Rapid (G0) rate in mm/min
+    pub rapid_rate: f64,
+    /// Delay between accepting a command and sending its response
+    pub response_latency: Duration,
+    /// Minimum execution time of a motion block
+    pub min_block_time: Duration,
+    /// Time taken by a homing cycle
+    pub homing_time: Duration,
+    /// Simulation speed multiplier (2.0 runs motion twice as fast)
+    pub time_scale: f64,
+    /// Limit motion by axis rates and accelerations instead of moving at the
+    /// programmed feed throughout
+    pub acceleration_limited: bool,
+    /// Axis max rates in mm/min ($110-$112), also the rapid rate when
+    /// acceleration limited
+    pub max_rates: [f64; 3],
+    /// Axis accelerations in mm/s² ($120-$122)
+    pub accelerations: [f64; 3],
+    /// Junction deviation in mm ($11)
+    pub junction_deviation: f64,
+}
+
+impl Default for MockDeviceConfig {
+    fn default() -> Self {
+        Self {
+            version: "1.1h".to_string(),
+            planner_blocks: 15,
+            rx_buffer_size: 128,
+            rapid_rate: 5000.0,
+            response_latency: Duration::from_millis(2),
+            min_block_time: Duration::from_millis(5),
+            homing_time: Duration::from_secs(2),
+            time_scale: 1.0,
+            acceleration_limited: false,
+            max_rates: [5000.0, 5000.0, 2000.0],
+            accelerations: [200.0, 200.0, 100.0],
+            junction_deviation: 0.01,
+        }
+    }
+}
+
+/// A scripted response rule
+#[derive(Debug, Clone)]
+struct MockRule {
+    /// Command prefix to match (case-insensitive)
+    pattern: String,
+    /// Lines sent instead of the default response
+    responses: Vec<String>,
+    /// Remove the rule after it fires once
+    once: bool,
+}
+
+/// Scripted responses overriding the default device behavior
+#[derive(Debug, Clone, Default)]
+pub struct MockScript {
+    rules: Vec<MockRule>,
+}
+
+impl MockScript {
+    /// Create an empty script
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Always respond to commands starting with `pattern` with `responses`
+    pub fn respond<S: Into<String>>(mut self, pattern: S, responses: &[&str]) -> Self {
+        self.rules.push(MockRule {
+            pattern: pattern.into(),
+            responses: responses.iter().map(|r| r.to_string()).collect(),
+            once: false,
+        });
+        self
+    }
+
+    /// Respond to the next command starting with `pattern` with `responses`
+    pub fn respond_once<S: Into<String>>(mut self, pattern: S, responses: &[&str]) -> Self {
+        self.rules.push(MockRule {
+            pattern: pattern.into(),
+            responses: responses.iter().map(|r| r.to_string()).collect(),
+            once: true,
+        });
+        self
+    }
+
+    /// Take the responses for a command, if a rule matches
+    fn take_responses(&mut self, command: &str) -> Option<Vec<String>> {
+        let index = self.rules.iter().position(|rule| {
+            command
+                .to_ascii_uppercase()
+                .starts_with(&rule.pattern.to_ascii_uppercase())
+        })?;
+
+        if self.rules[index].once {
+            Some(self.rules.remove(index).responses)
+        } else {
+            Some(self.rules[index].responses.clone())
+        }
+    }
+}
+
+/// Protocol statistics recorded by the mock device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockStats {
+    /// Lines received (excluding real-time commands)
+    pub lines_received: usize,
+    /// Status queries received
+    pub status_queries: usize,
+    /// Highest number of bytes waiting in the RX buffer
+    pub max_rx_bytes: usize,
+    /// Times the host sent more data than the RX buffer can hold
+    pub rx_overflows: usize,
+    /// Highest number of blocks in the planner
+    pub max_planner_blocks: usize,
+}
+
+/// Simulated machine state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Run,
+    Hold,
+    Jog,
+    Home,
+    Alarm,
+}
+
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            State::Idle => "Idle",
+            State::Run => "Run",
+            State::Hold => "Hold:0",
+            State::Jog => "Jog",
+            State::Home => "Home",
+            State::Alarm => "Alarm",
+        }
+    }
+}
+
+/// A motion block in the planner
+#[derive(Debug, Clone)]
+struct Block {
+    start: [f64; 3],
+    target: [f64; 3],
+    duration: Duration,
+    jog: bool,
+    /// Velocity profile when acceleration limited
+    profile: Option<Profile>,
+}
+
+impl Block {
+    /// Fraction of the distance covered at a fraction of the duration
+    fn distance_fraction(&self, time_fraction: f64) -> f64 {
+        match &self.profile {
+            Some(profile) if profile.length > 0.0 => {
+                profile.distance_at(time_fraction * profile.duration()) / profile.length
+            }
+            _ => time_fraction,
+        }
+    }
+}
+
+/// Trapezoidal velocity profile of a block
+///
+/// Speeds are in mm/s and the acceleration in mm/s².
+#[derive(Debug, Clone, Copy)]
+struct Profile {
+    /// Unit direction of travel
+    direction: [f64; 3],
+    length: f64,
+    /// Cruise speed limited by the feed and axis max rates
+    nominal: f64,
+    /// Acceleration limited by the axis accelerations
+    acceleration: f64,
+    entry: f64,
+    exit: f64,
+}
+
+impl Profile {
+    /// Highest speed reached
+    fn peak(&self) -> f64 {
+        let reachable = (self.acceleration * self.length + (self.entry.powi(2) + self.exit.powi(2)) / 2.0).sqrt();
+        reachable.min(self.nominal).max(self.entry.max(self.exit))
+    }
+
+    /// Time to run the block in seconds
+    fn duration(&self) -> f64 {
+        let peak = self.peak();
+        if peak <= 0.0 {
+            return 0.0;
+        }
+        let a = self.acceleration;
+        let accel_distance = (peak.powi(2) - self.entry.powi(2)) / (2.0 * a);
+        let decel_distance = (peak.powi(2) - self.exit.powi(2)) / (2.0 * a);
+        let cruise = (self.length - accel_distance - decel_distance).max(0.0);
+        (peak - self.entry) / a + (peak - self.exit) / a + cruise / peak
+    }
+
+    /// Distance covered `t` seconds into the block
+    fn distance_at(&self, t: f64) -> f64 {
+        let peak = self.peak();
+        let a = self.acceleration;
+        let accel_time = (peak - self.entry) / a;
+        let decel_time = (peak - self.exit) / a;
+        let cruise_time = (self.duration() - accel_time - decel_time).max(0.0);
+        let accel_distance = self.entry * accel_time + 0.5 * a * accel_time.powi(2);
+
+        let distance = if t <= accel_time {
+            self.entry * t + 0.5 * a * t.powi(2)
+        } else if t <= accel_time + cruise_time {
+            accel_distance + peak * (t - accel_time)
+        } else {
+            let t = (t - accel_time - cruise_time).min(decel_time);
+            accel_distance + peak * cruise_time + peak * t - 0.5 * a * t.powi(2)
+        };
+        distance.clamp(0.0, self.length)
+    }
+
+    /// Highest speed the block can be entered at and still stop at its end
+    fn max_entry(&self) -> f64 {
+        (self.exit.powi(2) + 2.0 * self.acceleration * self.length).sqrt()
+    }
+}
+
+/// The simulated GRBL device
+///
+/// All methods take the current time explicitly, so the device can be
+/// driven deterministically in tests.
+#[derive(Debug)]
+pub struct MockDevice {
+    config: MockDeviceConfig,
+    script: MockScript,
+    state: State,
+    position: [f64; 3],
+    absolute: bool,
+    feed: f64,
+    partial_line: String,
+    overflowing: bool,
+    rx: VecDeque<String>,
+    planner: VecDeque<Block>,
+    block_started: Option<Instant>,
+    hold_started: Option<Instant>,
+    output: VecDeque<(Instant, String)>,
+    received: Vec<String>,
+    stats: MockStats,
+    link_down: bool,
+}
+
+impl MockDevice {
+    /// Create a device with a configuration and script
+    pub fn new(config: MockDeviceConfig, script: MockScript) -> Self {
+        Self {
+            config,
+            script,
+            state: State::Idle,
+            position: [0.0; 3],
+            absolute: true,
+            feed: 0.0,
+            partial_line: String::new(),
+            overflowing: false,
+            rx: VecDeque::new(),
+            planner: VecDeque::new(),
+            block_started: None,
+            hold_started: None,
+            output: VecDeque::new(),
+            received: Vec::new(),
+            stats: MockStats::default(),
+            link_down: false,
+        }
+    }
+
+    /// Power up the device, sending the welcome message
+    pub fn power_on(&mut self, now: Instant) {
+        self.reset(now);
+        self.state = State::Idle;
+    }
+
+    /// Simulate a dead link: while down, input is dropped and nothing is
+    /// sent back
+    pub fn set_link_down(&mut self, down: bool) {
+        self.link_down = down;
+    }
+
+    /// Lines received so far (excluding real-time commands)
+    pub fn received(&self) -> &[String] {
+        &self.received
+    }
+
+    /// Protocol statistics
+    pub fn stats(&self) -> &MockStats {
+        &self.stats
+    }
+
+    /// Current configuration, including settings changed with `$N=value`
+    pub fn config(&self) -> &MockDeviceConfig {
+        &self.config
+    }
+
+    /// Current machine position
+    pub fn position(&self, now: Instant) -> [f64; 3] {
+        let Some(block) = self.planner.front() else {
+            return self.position;
+        };
+        let Some(started) = self.block_started else {
+            return self.position;
+        };
+
+        let at = self.hold_started.unwrap_or(now);
+        let elapsed = at.saturating_duration_since(started).as_secs_f64();
+        let fraction = block.distance_fraction((elapsed / block.duration.as_secs_f64().max(1e-9)).clamp(0.0, 1.0));
+        let mut position = [0.0; 3];
+        for (axis, value) in position.iter_mut().enumerate() {
+            *value = block.start[axis] + (block.target[axis] - block.start[axis]) * fraction;
+        }
+        position
+    }
+
+    /// Receive bytes from the host
+    pub fn receive(&mut self, data: &[u8], now: Instant) {
+        self.advance(now);
+        if self.link_down {
+            return;
+        }
+
+        for &byte in data {
+            match byte {
+                b'?' => self.status_report(now),
+                b'!' => self.feed_hold(now),
+                b'~' => self.cycle_start(now),
+                0x18 => self.reset(now),
+                0x85 => self.jog_cancel(now),
+                // Override and other extended real-time commands are accepted silently
+                0x80..=0xFF => {}
+                b'\n' => {
+                    let line = std::mem::take(&mut self.partial_line);
+                    let line = line.trim().to_string();
+                    if !line.is_empty() {
+                        self.received.push(line.clone());
+                        self.stats.lines_received += 1;
+                        self.rx.push_back(line);
+                        self.accept_lines(now);
+                    }
+                }
+                b'\r' => {}
+                _ => {
+                    self.partial_line.push(byte as char);
+
+                    let rx_bytes = self.rx_bytes();
+                    self.stats.max_rx_bytes = self.stats.max_rx_bytes.max(rx_bytes);
+                    if rx_bytes > self.config.rx_buffer_size && !self.overflowing {
+                        self.stats.rx_overflows += 1;
+                    }
+                    self.overflowing = rx_bytes > self.config.rx_buffer_size;
+                }
+            }
+        }
+    }
+
+    /// Take the next output line that is due
+    pub fn poll_output(&mut self, now: Instant) -> Option<String> {
+        self.advance(now);
+        if self.link_down {
+            return None;
+        }
+        match self.output.front() {
+            Some((due, _)) if *due <= now => self.output.pop_front().map(|(_, line)| line),
+            _ => None,
+        }
+    }
+
+    /// Bytes waiting in the RX buffer
+    fn rx_bytes(&self) -> usize {
+        self.rx.iter().map(|line| line.len() + 1).sum::<usize>() + self.partial_line.len()
+    }
+
+    /// Queue an output line
+    fn send(&mut self, now: Instant, line: impl Into<String>) {
+        self.output
+            .push_back((now + self.config.response_latency, line.into()));
+    }
+
+    /// Run the simulation up to `now`
+    fn advance(&mut self, now: Instant) {
+        if self.state == State::Home {
+            if let Some(started) = self.block_started {
+                let end = started + self.scaled(self.config.homing_time);
+                if end <= now {
+                    self.position = [0.0; 3];
+                    self.block_started = None;
+                    self.state = State::Idle;
+                    self.send(end, "ok");
+                }
+            }
+            return;
+        }
+
+        while self.hold_started.is_none() {
+            let (Some(block), Some(started)) = (self.planner.front(), self.block_started) else {
+                break;
+            };
+            let end = started + block.duration;
+            if end > now {
+                break;
+            }
+
+            self.position = block.target;
+            self.planner.pop_front();
+            self.block_started = if self.planner.is_empty() { None } else { Some(end) };
+            self.accept_lines(end);
+        }
+
+        self.accept_lines(now);
+
+        if self.planner.is_empty() && matches!(self.state, State::Run | State::Jog) {
+            self.state = State::Idle;
+        }
+    }
+
+    /// Move lines from the RX buffer into the planner while there is room
+    fn accept_lines(&mut self, now: Instant) {
+        while let Some(line) = self.rx.front() {
+            if self.planner.len() >= self.config.planner_blocks || self.state == State::Home {
+                return;
+            }
+            let line = line.clone();
+            self.rx.pop_front();
+            self.process_line(&line, now);
+        }
+    }
+
+    /// Execute a single line
+    fn process_line(&mut self, line: &str, now: Instant) {
+        if let Some(responses) = self.script.take_responses(line) {
+            for response in responses {
+                if response.starts_with("ALARM") {
+                    self.planner.clear();
+                    self.block_started = None;
+                    self.state = State::Alarm;
+                }
+                self.send(now, response);
+            }
+            return;
+        }
+
+        if let Some(command) = line.strip_prefix('$') {
+            self.system_command(command, now);
+            return;
+        }
+
+        if self.state == State::Alarm {
+            self.send(now, "error:9");
+            return;
+        }
+
+        match self.gcode_block(line, false) {
+            Ok(Some(block)) => {
+                self.push_block(block, now);
+                if self.state == State::Idle {
+                    self.state = State::Run;
+                }
+                self.send(now, "ok");
+            }
+            Ok(None) => self.send(now, "ok"),
+            Err(code) => self.send(now, format!("error:{}", code)),
+        }
+    }
+
+    /// Handle a `$` system command
+    fn system_command(&mut self, command: &str, now: Instant) {
+        let upper = command.to_ascii_uppercase();
+
+        if let Some(jog) = upper.strip_prefix("J=") {
+            if !matches!(self.state, State::Idle | State::Jog) {
+                self.send(now, "error:8");
+                return;
+            }
+            match self.gcode_block(jog, true) {
+                Ok(Some(block)) => {
+                    self.push_block(block, now);
+                    self.state = State::Jog;
+                    self.send(now, "ok");
+                }
+                Ok(None) => self.send(now, "ok"),
+                Err(code) => self.send(now, format!("error:{}", code)),
+            }
+            return;
+        }
+
+        if let Some((Ok(number), value)) = upper.split_once('=').map(|(n, v)| (n.parse::<u32>(), v)) {
+            match value.trim().parse::<f64>() {
+                Ok(value) => {
+                    self.set_setting(number, value);
+                    self.send(now, "ok");
+                }
+                Err(_) => self.send(now, "error:3"),
+            }
+            return;
+        }
+
+        match upper.as_str() {
+            "$" => {
+                let settings = [
+                    "$0=10", "$1=25", "$2=0", "$3=0", "$4=0", "$5=0", "$6=0", "$10=1",
+                ];
+                for setting in settings {
+                    self.send(now, setting);
+                }
+                self.send(now, format!("$11={:.3}", self.config.junction_deviation));
+                let settings = [
+                    "$12=0.002", "$13=0", "$20=0", "$21=0", "$22=1", "$23=0", "$24=25.000",
+                    "$25=500.000", "$26=250", "$27=1.000", "$30=1000", "$31=0", "$32=0",
+                    "$100=250.000", "$101=250.000", "$102=250.000",
+                ];
+                for setting in settings {
+                    self.send(now, setting);
+                }
+                for (axis, rate) in self.config.max_rates.into_iter().enumerate() {
+                    self.send(now, format!("${}={:.3}", 110 + axis, rate));
+                }
+                for (axis, acceleration) in self.config.accelerations.into_iter().enumerate() {
+                    self.send(now, format!("${}={:.3}", 120 + axis, acceleration));
+                }
+                for setting in ["$130=200.000", "$131=200.000", "$132=200.000"] {
+                    self.send(now, setting);
+                }
+                self.send(now, "ok");
+            }
+            "I" => {
+                let version = format!("[VER:{}.20190830:]", self.config.version);
+                let options = format!(
+                    "[OPT:V,{},{}]",
+                    self.config.planner_blocks, self.config.rx_buffer_size
+                );
+                self.send(now, version);
+                self.send(now, options);
+                self.send(now, "ok");
+            }
+            "G" => {
+                let distance = if self.absolute { "G90" } else { "G91" };
+                let state = format!(
+                    "[GC:G0 G54 G17 G21 {} G94 M5 M9 T0 F{} S0]",
+                    distance, self.feed
+                );
+                self.send(now, state);
+                self.send(now, "ok");
+            }
+            "#" => {
+                for wcs in ["G54", "G55", "G56", "G57", "G58", "G59", "G28", "G30", "G92"] {
+                    self.send(now, format!("[{}:0.000,0.000,0.000]", wcs));
+                }
+                self.send(now, "[TLO:0.000]");
+                self.send(now, "ok");
+            }
+            "X" => {
+                if self.state == State::Alarm {
+                    self.state = State::Idle;
+                    self.send(now, "[MSG:Caution: Unlocked]");
+                }
+                self.send(now, "ok");
+            }
+            "H" => {
+                // The ok is sent by advance() once the cycle completes
+                self.planner.clear();
+                self.state = State::Home;
+                self.block_started = Some(now);
+            }
+            _ => self.send(now, "ok"),
+        }
+    }
+
+    /// Apply a `$N=value` setting that affects the simulation
+    ///
+    /// Other settings are accepted and ignored.
+    fn set_setting(&mut self, number: u32, value: f64) {
+        match number {
+            11 => self.config.junction_deviation = value.max(0.0),
+            110..=112 => self.config.max_rates[(number - 110) as usize] = value.max(1.0),
+            120..=122 => self.config.accelerations[(number - 120) as usize] = value.max(1.0),
+            _ => {}
+        }
+    }
+
+    /// Velocity profile of a straight move at `rate` mm/min, from rest to rest
+    fn profile(&self, start: [f64; 3], target: [f64; 3], rate: f64) -> Option<Profile> {
+        let delta = [target[0] - start[0], target[1] - start[1], target[2] - start[2]];
+        let length = delta.iter().map(|d| d * d).sum::<f64>().sqrt();
+        if length <= 0.0 {
+            return None;
+        }
+        let direction = delta.map(|d| d / length);
+
+        // Each axis limits the path speed and acceleration by its share of the move
+        let mut nominal = rate / 60.0;
+        let mut acceleration = f64::INFINITY;
+        let limits = self.config.max_rates.into_iter().zip(self.config.accelerations);
+        for (component, (max_rate, max_acceleration)) in direction.iter().zip(limits) {
+            let share = component.abs();
+            if share > 1e-9 {
+                nominal = nominal.min(max_rate / 60.0 / share);
+                acceleration = acceleration.min(max_acceleration / share);
+            }
+        }
+
+        Some(Profile {
+            direction,
+            length,
+            nominal,
+            acceleration: acceleration.max(1e-3),
+            entry: 0.0,
+            exit: 0.0,
+        })
+    }
+
+    /// Highest speed through the junction between two blocks, as GRBL
+    /// computes it from the junction deviation
+    fn junction_speed(&self, previous: &Profile, next: &Profile) -> f64 {
+        let cos_theta = -previous
+            .direction
+            .iter()
+            .zip(&next.direction)
+            .map(|(a, b)| a * b)
+            .sum::<f64>();
+        if cos_theta > 0.999_999 {
+            // Reversal
+            return 0.0;
+        }
+        let limit = previous.nominal.min(next.nominal);
+        if cos_theta < -0.999_999 {
+            // Straight through
+            return limit;
+        }
+        let sin_half = (0.5 * (1.0 - cos_theta)).sqrt();
+        let acceleration = previous.acceleration.min(next.acceleration);
+        let speed = (acceleration * self.config.junction_deviation * sin_half / (1.0 - sin_half)).sqrt();
+        speed.min(limit)
+    }
+
+    /// Duration of a block with a profile
+    fn profile_duration(&self, profile: &Profile) -> Duration {
+        self.scaled(Duration::from_secs_f64(profile.duration()).max(self.config.min_block_time))
+    }
+
+    /// Parse a G-code line, returning a motion block if it moves the machine
+    ///
+    /// Errors are GRBL error codes.
+    fn gcode_block(&mut self, line: &str, jog: bool) -> std::result::Result<Option<Block>, u8> {
+        let tokens = Tokenizer::new(line).tokenize().map_err(|_| 1u8)?;
+
+        let mut motion = None;
+        let mut absolute = self.absolute;
+        let mut feed = None;
+        let mut dwell = None;
+        let mut axes: [Option<f64>; 3] = [None; 3];
+
+        for token in tokens {
+            match token {
+                Token::GCommand(0) => motion = Some(0),
+                Token::GCommand(n @ 1..=3) => motion = Some(n),
+                Token::GCommand(4) => dwell = Some(0.0),
+                Token::GCommand(90) => absolute = true,
+                Token::GCommand(91) => absolute = false,
+                Token::FCommand(f) => feed = Some(f),
+                Token::Parameter { letter: 'X', value } => axes[0] = Some(value),
+                Token::Parameter { letter: 'Y', value } => axes[1] = Some(value),
+                Token::Parameter { letter: 'Z', value } => axes[2] = Some(value),
+                Token::Parameter { letter: 'P', value } if dwell.is_some() => dwell = Some(value),
+                _ => {}
+            }
+        }
+
+        if jog {
+            // Jog distance mode and feed only apply to the jog itself
+            if feed.is_none() {
+                return Err(22);
+            }
+        } else {
+            self.absolute = absolute;
+            if let Some(f) = feed {
+                self.feed = f;
+            }
+        }
+
+        if let Some(seconds) = dwell {
+            return Ok(Some(Block {
+                start: self.end_position(),
+                target: self.end_position(),
+                duration: self.scaled(Duration::from_secs_f64(seconds.max(0.0))),
+                jog,
+                profile: None,
+            }));
+        }
+
+        if axes.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+
+        let start = self.end_position();
+        let mut target = start;
+        for (axis, value) in axes.iter().enumerate() {
+            if let Some(v) = value {
+                target[axis] = if absolute { *v } else { start[axis] + v };
+            }
+        }
+
+        let rapid_rate = if self.config.acceleration_limited {
+            // Rapids run at the axis max rates
+            f64::INFINITY
+        } else {
+            self.config.rapid_rate
+        };
+        let rate = match (jog, motion) {
+            (true, _) => feed.unwrap_or(self.feed),
+            (false, Some(0)) => rapid_rate,
+            _ if self.feed <= 0.0 => return Err(22),
+            _ => self.feed,
+        };
+
+        if self.config.acceleration_limited {
+            if let Some(profile) = self.profile(start, target, rate) {
+                return Ok(Some(Block {
+                    start,
+                    target,
+                    duration: self.profile_duration(&profile),
+                    jog,
+                    profile: Some(profile),
+                }));
+            }
+        }
+        let distance = start
+            .iter()
+            .zip(target.iter())
+            .map(|(a, b)| (b - a) * (b - a))
+            .sum::<f64>()
+            .sqrt();
+        let duration = Duration::from_secs_f64(distance / rate * 60.0).max(self.config.min_block_time);
+
+        Ok(Some(Block {
+            start,
+            target,
+            duration: self.scaled(duration),
+            jog,
+            profile: None,
+        }))
+    }
+
+    /// Position at the end of the last planned block
+    fn end_position(&self) -> [f64; 3] {
+        self.planner.back().map(|b| b.target).unwrap_or(self.position)
+    }
+
+    fn push_block(&mut self, mut block: Block, now: Instant) {
+        if self.planner.is_empty() {
+            self.block_started = Some(now);
+        }
+        // Carry speed through the junction with the previous block unless it
+        // is already running, since its timing can't change any more
+        let running = self.planner.len() == 1 && self.block_started.is_some();
+        if !running {
+            if let (Some(previous), Some(next)) = (self.planner.back(), block.profile) {
+                if let Some(mut profile) = previous.profile {
+                    let speed = self
+                        .junction_speed(&profile, &next)
+                        .min(next.max_entry())
+                        .min((profile.entry.powi(2) + 2.0 * profile.acceleration * profile.length).sqrt());
+                    profile.exit = speed;
+                    let duration = self.profile_duration(&profile);
+                    if let Some(previous) = self.planner.back_mut() {
+                        previous.profile = Some(profile);
+                        previous.duration = duration;
+                    }
+                    let mut next = next;
+                    next.entry = speed;
+                    block.duration = self.profile_duration(&next);
+                    block.profile = Some(next);
+                }
+            }
+        }
+        self.planner.push_back(block);
+        self.stats.max_planner_blocks = self.stats.max_planner_blocks.max(self.planner.len());
+    }
+
+    fn scaled(&self, duration: Duration) -> Duration {
+        duration.div_f64(self.config.time_scale.max(1e-3))
+    }
+
+    fn status_report(&mut self, now: Instant) {
+        self.stats.status_queries += 1;
+        let position = self.position(now);
+        let planner_free = self.config.planner_blocks.saturating_sub(self.planner.len());
+        let rx_free = self.config.rx_buffer_size.saturating_sub(self.rx_bytes());
+        let feed = if self.planner.is_empty() { 0.0 } else { self.feed };
+
+        let report = format!(
+            "<{}|MPos:{:.3},{:.3},{:.3}|Bf:{},{}|FS:{},0>",
+            self.state.name(),
+            position[0],
+            position[1],
+            position[2],
+            planner_free,
+            rx_free,
+            feed
+        );
+        // Real-time reports bypass the response latency of queued commands
+        self.output.push_back((now, report));
+    }
+
+    fn feed_hold(&mut self, now: Instant) {
+        match self.state {
+            State::Run => {
+                self.state = State::Hold;
+                self.hold_started = Some(now);
+            }
+            State::Jog => self.jog_cancel(now),
+            _ => {}
+        }
+    }
+
+    fn cycle_start(&mut self, now: Instant) {
+        if self.state != State::Hold {
+            return;
+        }
+        if let (Some(held), Some(started)) = (self.hold_started.take(), self.block_started) {
+            self.block_started = Some(started + now.saturating_duration_since(held));
+        }
+        self.state = if self.planner.is_empty() { State::Idle } else { State::Run };
+    }
+
+    fn jog_cancel(&mut self, now: Instant) {
+        if self.state != State::Jog {
+            return;
+        }
+        self.position = self.position(now);
+        self.planner.retain(|block| !block.jog);
+        self.rx.retain(|line| !line.to_ascii_uppercase().starts_with("$J="));
+        self.block_started = if self.planner.is_empty() { None } else { Some(now) };
+        self.state = State::Idle;
+    }
+
+    fn reset(&mut self, now: Instant) {
+        let moving = !self.planner.is_empty() && self.hold_started.is_none();
+        if !self.planner.is_empty() {
+            self.position = self.position(now);
+        }
+
+        self.partial_line.clear();
+        self.rx.clear();
+        self.planner.clear();
+        self.block_started = None;
+        self.hold_started = None;
+        self.absolute = true;
+
+        if moving {
+            // Position is lost when resetting during motion
+            self.state = State::Alarm;
+            self.send(now, "ALARM:3");
+        } else if self.state != State::Alarm {
+            self.state = State::Idle;
+        }
+        self.send(now, format!("Grbl {} ['$' for help]", self.config.version));
+    }
+}
+
+/// Connection to an in-process mock GRBL device
+pub struct MockConnection {
+    device: Arc<Mutex<MockDevice>>,
+    status: ConnectionStatus,
+}
+
+impl MockConnection {
+    /// Create a mock connection with the default configuration
+    pub fn new() -> Self {
+        Self::with_script(MockDeviceConfig::default(), MockScript::default())
+    }
+
+    /// Create a mock connection with a configuration and script
+    pub fn with_script(config: MockDeviceConfig, script: MockScript) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(MockDevice::new(config, script))),
+            status: ConnectionStatus::Disconnected,
+        }
+    }
+
+    /// Get a handle to the simulated device for inspection
+    ///
+    /// The handle remains valid after the connection is handed to a
+    /// connection manager.
+    pub fn device(&self) -> Arc<Mutex<MockDevice>> {
+        Arc::clone(&self.device)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockDevice> {
+        self.device.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MockConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Connection for MockConnection {
+    async fn connect(&mut self, _timeout: Duration) -> Result<()> {
+        self.lock().power_on(Instant::now());
+        self.status = ConnectionStatus::Connected;
+        tracing::info!("Connected to mock GRBL device");
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.status = ConnectionStatus::Disconnected;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.status == ConnectionStatus::Connected
+    }
+
+    fn status(&self) -> ConnectionStatus {
+        self.status
+    }
+
+    async fn send_line(&mut self, data: &str) -> Result<()> {
+        let mut line = data.to_string();
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        self.send_bytes(line.as_bytes()).await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<()> {
+        if !self.is_connected() {
+            return Err(Error::connection("Mock device not connected"));
+        }
+        self.lock().receive(data, Instant::now());
+        Ok(())
+    }
+
+    async fn receive_line(&mut self, timeout: Duration) -> Result<Option<String>> {
+        if !self.is_connected() {
+            return Err(Error::connection("Mock device not connected"));
+        }
+
+        // Return promptly so senders waiting on the connection aren't starved
+        let deadline = Instant::now() + timeout.min(POLL_INTERVAL);
+        loop {
+            if let Some(line) = self.lock().poll_output(Instant::now()) {
+                return Ok(Some(line));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    fn description(&self) -> String {
+        "Mock GRBL device".to_string()
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(device: &mut MockDevice, now: Instant) -> Vec<String> {
+        std::iter::from_fn(|| device.poll_output(now)).collect()
+    }
+
+    fn device() -> MockDevice {
+        let config = MockDeviceConfig {
+            response_latency: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut device = MockDevice::new(config, MockScript::default());
+        device.power_on(Instant::now());
+        device
+    }
+
+    #[test]
+    fn test_welcome_and_status() {
+        let mut device = device();
+        let now = Instant::now();
+
+        device.receive(b"?", now);
+        let output = drain(&mut device, now);
+        assert!(output[0].starts_with("Grbl 1.1h"));
+        assert!(output[1].starts_with("<Idle|MPos:0.000,0.000,0.000|Bf:15,128"));
+    }
+
+    #[test]
+    fn test_motion_takes_time() {
+        let mut device = device();
+        let start = Instant::now();
+        drain(&mut device, start);
+
+        // 10mm at 600mm/min takes one second
+        device.receive(b"G1 X10 F600\n", start);
+        assert_eq!(drain(&mut device, start), vec!["ok"]);
+
+        device.receive(b"?", start + Duration::from_millis(500));
+        let status = drain(&mut device, start + Duration::from_millis(500));
+        assert!(status[0].starts_with("<Run|MPos:5.000,0.000,0.000"));
+
+        device.receive(b"?", start + Duration::from_secs(2));
+        let status = drain(&mut device, start + Duration::from_secs(2));
+        assert!(status[0].starts_with("<Idle|MPos:10.000,0.000,0.000"));
+    }
+
+    #[test]
+    fn test_planner_full_delays_ok() {
+        let config = MockDeviceConfig {
+            planner_blocks: 2,
+            response_latency: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut device = MockDevice::new(config, MockScript::default());
+        let start = Instant::now();
+        device.power_on(start);
+        drain(&mut device, start);
+
+        device.receive(b"G1 X1 F60\nG1 X2\nG1 X3\n", start);
+        assert_eq!(drain(&mut device, start), vec!["ok", "ok"]);
+        assert_eq!(device.stats().max_planner_blocks, 2);
+
+        // The first block finishes after one second, making room for the third line
+        assert_eq!(drain(&mut device, start + Duration::from_millis(1100)), vec!["ok"]);
+    }
+
+    #[test]
+    fn test_rx_overflow_is_recorded() {
+        let mut device = device();
+        let now = Instant::now();
+        let line = format!("G4 P0 ({})\n", "x".repeat(200));
+
+        device.receive(line.as_bytes(), now);
+        assert_eq!(device.stats().rx_overflows, 1);
+    }
+
+    #[test]
+    fn test_hold_resume_and_reset() {
+        let mut device = device();
+        let start = Instant::now();
+        drain(&mut device, start);
+
+        device.receive(b"G1 X10 F600\n!", start + Duration::from_millis(100));
+        device.receive(b"?", start + Duration::from_secs(5));
+        let status = drain(&mut device, start + Duration::from_secs(5));
+        assert!(status.iter().any(|s| s.starts_with("<Hold:0|MPos:0.000")));
+
+        device.receive(&[0x18], start + Duration::from_secs(5));
+        let output = drain(&mut device, start + Duration::from_secs(5));
+        assert!(output.iter().any(|s| s.starts_with("Grbl ")));
+    }
+
+    #[test]
+    fn test_script_and_alarm_lockout() {
+        let script = MockScript::new().respond_once("G1 X99", &["ALARM:2"]);
+        let config = MockDeviceConfig {
+            response_latency: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut device = MockDevice::new(config, script);
+        let now = Instant::now();
+        device.power_on(now);
+        drain(&mut device, now);
+
+        device.receive(b"G1 X99 F100\nG0 X1\n$X\nG0 X1\n", now);
+        assert_eq!(
+            drain(&mut device, now),
+            vec!["ALARM:2", "error:9", "[MSG:Caution: Unlocked]", "ok", "ok"]
+        );
+    }
+
+    #[test]
+    fn test_acceleration_limited_motion() {
+        let config = MockDeviceConfig {
+            response_latency: Duration::ZERO,
+            acceleration_limited: true,
+            ..Default::default()
+        };
+        let mut device = MockDevice::new(config, MockScript::default());
+        let start = Instant::now();
+        device.power_on(start);
+        drain(&mut device, start);
+
+        // 10mm at 600mm/min with 10mm/s²: 1s to reach 10mm/s over 5mm, then
+        // 1s to stop, so the move takes 2s instead of 1s
+        device.receive(b"$120=10\nG1 X10 F600\n", start);
+        assert_eq!(drain(&mut device, start), vec!["ok", "ok"]);
+        device.receive(b"?", start + Duration::from_millis(500));
+        let status = drain(&mut device, start + Duration::from_millis(500));
+        assert!(status[0].starts_with("<Run|MPos:1.250,0.000,0.000"), "{}", status[0]);
+        device.receive(b"?", start + Duration::from_millis(1900));
+        let status = drain(&mut device, start + Duration::from_millis(1900));
+        assert!(status[0].starts_with("<Run|"), "{}", status[0]);
+        device.receive(b"?", start + Duration::from_millis(2100));
+        let status = drain(&mut device, start + Duration::from_millis(2100));
+        assert!(status[0].starts_with("<Idle|MPos:10.000"), "{}", status[0]);
+        assert_eq!(device.config().accelerations[0], 10.0);
+    }
+
+    #[test]
+    fn test_junction_speed() {
+        let device = MockDevice::new(
+            MockDeviceConfig {
+                acceleration_limited: true,
+                ..Default::default()
+            },
+            MockScript::default(),
+        );
+        let straight = device.profile([0.0; 3], [10.0, 0.0, 0.0], 6000.0).unwrap();
+        let corner = device.profile([10.0, 0.0, 0.0], [10.0, 10.0, 0.0], 6000.0).unwrap();
+        let back = device.profile([10.0, 0.0, 0.0], [0.0, 0.0, 0.0], 6000.0).unwrap();
+        assert_eq!(device.junction_speed(&straight, &straight), straight.nominal);
+        assert_eq!(device.junction_speed(&straight, &back), 0.0);
+        let speed = device.junction_speed(&straight, &corner);
+        assert!(speed > 0.0 && speed < straight.nominal);
+    }
+
+    #[test]
+    fn test_jog_requires_feed() {
+        let mut device = device();
+        let now = Instant::now();
+        drain(&mut device, now);
+
+        device.receive(b"$J=G91 X1\n$J=G91 X1 F100\n", now);
+        assert_eq!(drain(&mut device, now), vec!["error:22", "ok"]);
+    }
+}
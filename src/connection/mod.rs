@@ -1,16 +1,36 @@
 //! Connection module
 //!
 //! This module provides abstract interfaces for communicating with GRBL controllers
-//! via different connection types (serial, telnet, websocket).
+//! via different connection types (serial, Bluetooth, telnet, websocket, and a
+//! simulated mock device). Network controllers can be discovered over mDNS.
+//! Plugins can wrap any of them to rewrite or watch the lines exchanged.
 
+mod auto_connect;
+mod bluetooth;
+mod detect;
 mod manager;
+mod mdns;
+mod metrics;
+mod mock;
+mod plugin;
 mod serial;
 mod telnet;
 mod traits;
+mod watchdog;
 mod websocket;
 
+pub use auto_connect::{AutoConnect, AutoConnectState};
+pub use bluetooth::{
+    BluetoothAddress, BluetoothConfig, BluetoothConnection, BluetoothDevice, BluetoothTarget,
+    DEFAULT_RFCOMM_CHANNEL,
+};
+pub use detect::{probe_port, scan_for_grbl, DetectedDevice, COMMON_BAUD_RATES, DEFAULT_PROBE_TIMEOUT};
 pub use manager::{ConnectionManager, ConnectionManagerConfig};
-pub use serial::{SerialConfig, SerialConnection};
+pub use mdns::{discover_network_devices, NetworkDevice, NetworkProtocol, DEFAULT_DISCOVERY_TIMEOUT};
+pub use metrics::{LinkActivity, LinkMetrics};
+pub use mock::{MockConnection, MockDevice, MockDeviceConfig, MockScript, MockStats};
+pub use plugin::PluginConnection;
+pub use serial::{LineControl, SerialConfig, SerialConnection};
 pub use telnet::{TelnetConfig, TelnetConnection};
 pub use traits::{Connection, ConnectionEvent, ConnectionStatus};
-pub use websocket::{WebSocketConfig, WebSocketConnection};
+pub use websocket::{TlsOptions, WebSocketAuth, WebSocketConfig, WebSocketConnection};
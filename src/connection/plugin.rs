@@ -0,0 +1,83 @@
+//! Plugin connection wrapper
+//!
+//! Passes the lines sent to and received from another connection through
+//! the enabled plugins' `on_send` and `on_receive`. Realtime bytes go
+//! straight through, so feed hold and reset never wait on a script.
+
+use async_trait::async_trait;
+use std::time::Duration;
+
+use crate::connection::traits::{Connection, ConnectionStatus};
+use crate::script::ConnectionHooks;
+use crate::utils::error::{Error, Result};
+
+/// Connection whose lines plugins can rewrite or watch
+pub struct PluginConnection {
+    inner: Box<dyn Connection>,
+    hooks: ConnectionHooks,
+}
+
+impl PluginConnection {
+    /// Wrap a connection with the enabled plugins' hooks
+    pub fn new(inner: Box<dyn Connection>, hooks: ConnectionHooks) -> Self {
+        Self { inner, hooks }
+    }
+}
+
+#[async_trait]
+impl Connection for PluginConnection {
+    async fn connect(&mut self, timeout: Duration) -> Result<()> {
+        self.inner.connect(timeout).await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.inner.disconnect().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn status(&self) -> ConnectionStatus {
+        self.inner.status()
+    }
+
+    async fn send_line(&mut self, data: &str) -> Result<()> {
+        let original = data.trim_end_matches(['\r', '\n']);
+        let line = self.hooks.on_send(original)?;
+        // Network links keep several lines in the controller's buffer, counted
+        // by the length of the lines queued, so a longer line could overflow it
+        if self.inner.is_network() && line.len() > original.len() {
+            return Err(Error::Connection(format!(
+                "Plugin lengthened \"{}\" on a network link, which can overflow the controller's buffer",
+                original
+            )));
+        }
+        // An empty line is still sent so the controller acknowledges it
+        self.inner.send_line(&line).await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<()> {
+        self.inner.send_bytes(data).await
+    }
+
+    async fn receive_line(&mut self, timeout: Duration) -> Result<Option<String>> {
+        let line = self.inner.receive_line(timeout).await?;
+        if let Some(line) = &line {
+            self.hooks.on_receive(line);
+        }
+        Ok(line)
+    }
+
+    fn is_network(&self) -> bool {
+        self.inner.is_network()
+    }
+
+    fn description(&self) -> String {
+        self.inner.description()
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+}
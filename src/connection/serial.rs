@@ -5,13 +5,42 @@
 use crate::Result;
 use crate::utils::error::Error;
 use async_trait::async_trait;
-use serialport::{SerialPort, SerialPortInfo};
+use serde::{Deserialize, Serialize};
+use serialport::{ClearBuffer, SerialPort, SerialPortInfo};
 use std::io::{BufRead, BufReader, Write};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use super::traits::{Connection, ConnectionStatus};
 
+/// How a modem control line (DTR or RTS) is driven when connecting
+///
+/// Many Arduino-based boards reset when DTR toggles, while others need RTS
+/// held low to run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineControl {
+    /// Leave the line as the driver opens it
+    #[default]
+    Unchanged,
+    /// Hold the line high (asserted)
+    High,
+    /// Hold the line low (deasserted)
+    Low,
+    /// Pulse the line low then high, resetting boards wired to it
+    Pulse,
+}
+
+impl std::fmt::Display for LineControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineControl::Unchanged => write!(f, "Unchanged"),
+            LineControl::High => write!(f, "High"),
+            LineControl::Low => write!(f, "Low"),
+            LineControl::Pulse => write!(f, "Pulse (reset)"),
+        }
+    }
+}
+
 /// Serial connection configuration
 #[derive(Debug, Clone)]
 pub struct SerialConfig {
@@ -27,6 +56,16 @@ pub struct SerialConfig {
     pub parity: serialport::Parity,
     /// Flow control
     pub flow_control: serialport::FlowControl,
+    /// DTR behavior on connect
+    pub dtr: LineControl,
+    /// RTS behavior on connect
+    pub rts: LineControl,
+    /// Length of the low phase of a reset pulse
+    pub reset_pulse: Duration,
+    /// Delay after opening the port before the first command
+    pub startup_delay: Duration,
+    /// Discard anything already received once the startup delay has passed
+    pub flush_on_connect: bool,
 }
 
 impl Default for SerialConfig {
@@ -38,6 +77,11 @@ impl Default for SerialConfig {
             stop_bits: serialport::StopBits::One,
             parity: serialport::Parity::None,
             flow_control: serialport::FlowControl::None,
+            dtr: LineControl::Unchanged,
+            rts: LineControl::Unchanged,
+            reset_pulse: Duration::from_millis(100),
+            startup_delay: Duration::from_millis(100),
+            flush_on_connect: false,
         }
     }
 }
@@ -118,6 +162,30 @@ impl SerialConnection {
         &self.config
     }
 
+    /// Drive a control line according to its configured behavior
+    async fn apply_line_control(
+        port: &mut dyn SerialPort,
+        control: LineControl,
+        pulse: Duration,
+        write: fn(&mut dyn SerialPort, bool) -> serialport::Result<()>,
+    ) -> Result<()> {
+        let set = |port: &mut dyn SerialPort, level: bool| {
+            write(port, level)
+                .map_err(|e| Error::Connection(format!("Failed to set control line: {}", e)))
+        };
+        match control {
+            LineControl::Unchanged => {}
+            LineControl::High => set(port, true)?,
+            LineControl::Low => set(port, false)?,
+            LineControl::Pulse => {
+                set(port, false)?;
+                tokio::time::sleep(pulse).await;
+                set(port, true)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Read available lines from the port into the buffer
     fn read_available_lines(&self) -> Result<()> {
         let port_guard = self.port.lock().unwrap();
@@ -149,21 +217,35 @@ impl Connection for SerialConnection {
     async fn connect(&mut self, _connection_timeout: Duration) -> Result<()> {
         self.status = ConnectionStatus::Connecting;
 
-        let port = serialport::new(&self.config.port, self.config.baud_rate)
+        let mut builder = serialport::new(&self.config.port, self.config.baud_rate)
             .data_bits(self.config.data_bits)
             .stop_bits(self.config.stop_bits)
             .parity(self.config.parity)
             .flow_control(self.config.flow_control)
-            .timeout(Duration::from_millis(100))
+            .timeout(Duration::from_millis(100));
+        if self.config.dtr == LineControl::Low {
+            // Avoid the reset some boards do when DTR is raised on open
+            builder = builder.dtr_on_open(false);
+        }
+        let mut port = builder
             .open()
             .map_err(|e| Error::Connection(format!("Failed to open port: {}", e)))?;
 
+        let pulse = self.config.reset_pulse;
+        Self::apply_line_control(port.as_mut(), self.config.dtr, pulse, |p, level| p.write_data_terminal_ready(level)).await?;
+        Self::apply_line_control(port.as_mut(), self.config.rts, pulse, |p, level| p.write_request_to_send(level)).await?;
+
+        // Wait for the connection to stabilize (and the board to boot after a reset)
+        tokio::time::sleep(self.config.startup_delay).await;
+
+        if self.config.flush_on_connect {
+            port.clear(ClearBuffer::All)
+                .map_err(|e| Error::Connection(format!("Failed to flush: {}", e)))?;
+        }
+
         *self.port.lock().unwrap() = Some(port);
         self.status = ConnectionStatus::Connected;
 
-        // Wait a bit for the connection to stabilize
-        tokio::time::sleep(Duration::from_millis(100)).await;
-
         Ok(())
     }
 
@@ -262,6 +344,9 @@ mod tests {
         assert_eq!(config.data_bits, serialport::DataBits::Eight);
         assert_eq!(config.stop_bits, serialport::StopBits::One);
         assert_eq!(config.parity, serialport::Parity::None);
+        assert_eq!(config.dtr, LineControl::Unchanged);
+        assert_eq!(config.rts, LineControl::Unchanged);
+        assert!(!config.flush_on_connect);
     }
 
     #[test]
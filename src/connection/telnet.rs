@@ -252,6 +252,10 @@ impl Connection for TelnetConnection {
         format!("Telnet: {}:{}", self.config.host, self.config.port)
     }
 
+    fn is_network(&self) -> bool {
+        true
+    }
+
     async fn flush(&mut self) -> Result<()> {
         let mut stream_lock = self.stream.lock().await;
         let stream = stream_lock
@@ -30,6 +30,21 @@ pub enum ConnectionEvent {
     DataReceived(String),
     /// Error occurred
     Error(String),
+    /// Status reports stopped arriving; streaming has been paused
+    StatusStale {
+        /// Number of status intervals missed
+        missed: u32,
+    },
+    /// Attempting to reconnect after status reports stopped
+    Reconnecting {
+        /// Attempt number, starting at 1
+        attempt: u32,
+    },
+    /// Status reports are arriving again after going stale
+    StatusRestored,
+    /// The link went stale while a program was streaming; the job was
+    /// aborted and the queue cleared instead of reconnecting mid-job
+    JobAborted,
 }
 
 /// Abstract connection trait for GRBL communication
@@ -103,6 +118,14 @@ pub trait Connection: Send + Sync {
     /// * `Err(Error)` if error occurred
     async fn receive_line(&mut self, timeout: Duration) -> Result<Option<String>>;
 
+    /// Whether the connection runs over a network (Telnet, WebSocket)
+    ///
+    /// Network links have round trips long enough that the connection
+    /// manager keeps several commands in flight and polls status less often.
+    fn is_network(&self) -> bool {
+        false
+    }
+
     /// Get connection description/address
     ///
     /// # Returns
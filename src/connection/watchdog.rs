@@ -0,0 +1,157 @@
+//! Status watchdog
+//!
+//! Detects a link that has stopped delivering status reports while connected
+//! and decides when to attempt recovery.
+
+use std::time::{Duration, Instant};
+
+/// Action requested by the watchdog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WatchdogAction {
+    /// Nothing to do
+    None,
+    /// Status reports have just gone stale (number of missed intervals)
+    Stale(u32),
+    /// Try reconnecting (attempt number, starting at 1)
+    Reconnect(u32),
+    /// Recovery failed; stop trying until a status report arrives
+    GiveUp,
+}
+
+/// Tracks status report arrival and escalates when they stop
+#[derive(Debug)]
+pub(crate) struct StatusWatchdog {
+    /// Expected interval between status reports
+    interval: Duration,
+    /// Missed intervals before the link is considered stale (0 disables)
+    missed_limit: u32,
+    /// Maximum reconnection attempts
+    max_reconnects: u32,
+    /// When the last status report arrived
+    last_status: Instant,
+    /// Whether the link is currently stale
+    stale: bool,
+    /// Reconnection attempts made since going stale
+    reconnects: u32,
+    /// Whether recovery has been abandoned
+    gave_up: bool,
+}
+
+impl StatusWatchdog {
+    /// Create a watchdog that starts counting from `now`
+    pub(crate) fn new(interval: Duration, missed_limit: u32, max_reconnects: u32, now: Instant) -> Self {
+        Self {
+            interval,
+            missed_limit,
+            max_reconnects,
+            last_status: now,
+            stale: false,
+            reconnects: 0,
+            gave_up: false,
+        }
+    }
+
+    /// Change the expected interval between status reports
+    pub(crate) fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Restart counting from `now`, e.g. after connecting
+    pub(crate) fn reset(&mut self, now: Instant) {
+        self.last_status = now;
+        self.stale = false;
+        self.reconnects = 0;
+        self.gave_up = false;
+    }
+
+    /// Record a status report, returning true if the link was stale
+    pub(crate) fn status_received(&mut self, now: Instant) -> bool {
+        let was_stale = self.stale;
+        self.reset(now);
+        was_stale
+    }
+
+    /// Whether the link is currently stale
+    pub(crate) fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Number of whole status intervals without a report
+    fn missed(&self, now: Instant) -> u32 {
+        let interval = self.interval.as_millis().max(1);
+        (now.duration_since(self.last_status).as_millis() / interval) as u32
+    }
+
+    /// Check the link, returning the action to take
+    pub(crate) fn check(&mut self, now: Instant) -> WatchdogAction {
+        if self.missed_limit == 0 || self.gave_up {
+            return WatchdogAction::None;
+        }
+
+        let missed = self.missed(now);
+        if !self.stale {
+            if missed >= self.missed_limit {
+                self.stale = true;
+                return WatchdogAction::Stale(missed);
+            }
+            return WatchdogAction::None;
+        }
+
+        // Once stale, try another reconnect every `missed_limit` intervals
+        let reconnects_due = (missed / self.missed_limit).saturating_sub(1);
+        if reconnects_due > self.reconnects {
+            if self.reconnects >= self.max_reconnects {
+                self.gave_up = true;
+                return WatchdogAction::GiveUp;
+            }
+            self.reconnects += 1;
+            return WatchdogAction::Reconnect(self.reconnects);
+        }
+
+        WatchdogAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_escalation() {
+        let start = Instant::now();
+        let mut watchdog = StatusWatchdog::new(Duration::from_millis(100), 4, 2, start);
+
+        assert_eq!(watchdog.check(at(start, 350)), WatchdogAction::None);
+        assert_eq!(watchdog.check(at(start, 400)), WatchdogAction::Stale(4));
+        assert!(watchdog.is_stale());
+        assert_eq!(watchdog.check(at(start, 500)), WatchdogAction::None);
+        assert_eq!(watchdog.check(at(start, 800)), WatchdogAction::Reconnect(1));
+        assert_eq!(watchdog.check(at(start, 900)), WatchdogAction::None);
+        assert_eq!(watchdog.check(at(start, 1200)), WatchdogAction::Reconnect(2));
+        assert_eq!(watchdog.check(at(start, 1600)), WatchdogAction::GiveUp);
+        assert_eq!(watchdog.check(at(start, 5000)), WatchdogAction::None);
+    }
+
+    #[test]
+    fn test_status_restores_link() {
+        let start = Instant::now();
+        let mut watchdog = StatusWatchdog::new(Duration::from_millis(100), 3, 1, start);
+
+        assert!(!watchdog.status_received(at(start, 100)));
+        assert_eq!(watchdog.check(at(start, 400)), WatchdogAction::Stale(3));
+        assert!(watchdog.status_received(at(start, 450)));
+        assert!(!watchdog.is_stale());
+        assert_eq!(watchdog.check(at(start, 600)), WatchdogAction::None);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let start = Instant::now();
+        let mut watchdog = StatusWatchdog::new(Duration::from_millis(100), 0, 3, start);
+        assert_eq!(watchdog.check(at(start, 10_000)), WatchdogAction::None);
+    }
+}
@@ -3,21 +3,115 @@
 //! This module provides a WebSocket connection implementation for connecting
 //! to GRBL controllers through WebSocket protocols. This is useful for
 //! web-based interfaces and cloud-connected CNC machines.
+//!
+//! Secure (wss://) connections validate the server certificate against the
+//! system roots by default. Controllers behind a cloud bridge or a shop
+//! proxy can require a bearer token or basic credentials, which are sent as
+//! an `Authorization` header with the handshake.
 
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::time::timeout;
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, HeaderValue, StatusCode};
+use tokio_tungstenite::{connect_async_tls_with_config, tungstenite, tungstenite::Message, Connector, WebSocketStream};
 use tokio_tungstenite::MaybeTlsStream;
 use tokio::net::TcpStream;
 
 use crate::connection::traits::{Connection, ConnectionStatus};
 use crate::utils::error::{Error, Result};
 
+/// Authentication sent with the WebSocket handshake
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebSocketAuth {
+    /// No authentication
+    #[default]
+    None,
+    /// `Authorization: Bearer <token>`
+    Bearer { token: String },
+    /// `Authorization: Basic <credentials>`
+    Basic { username: String, password: String },
+}
+
+impl WebSocketAuth {
+    /// Value of the `Authorization` header, if any
+    pub fn header_value(&self) -> Option<String> {
+        match self {
+            WebSocketAuth::None => None,
+            WebSocketAuth::Bearer { token } => Some(format!("Bearer {}", token.trim())),
+            WebSocketAuth::Basic { username, password } => {
+                Some(format!("Basic {}", base64_encode(format!("{}:{}", username, password).as_bytes())))
+            }
+        }
+    }
+}
+
+// Keep secrets out of logs and error reports
+impl std::fmt::Debug for WebSocketAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebSocketAuth::None => write!(f, "None"),
+            WebSocketAuth::Bearer { .. } => write!(f, "Bearer {{ token: \"***\" }}"),
+            WebSocketAuth::Basic { username, .. } => {
+                write!(f, "Basic {{ username: {:?}, password: \"***\" }}", username)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WebSocketAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebSocketAuth::None => write!(f, "None"),
+            WebSocketAuth::Bearer { .. } => write!(f, "Token"),
+            WebSocketAuth::Basic { .. } => write!(f, "Username/Password"),
+        }
+    }
+}
+
+/// Certificate validation options for wss:// connections
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsOptions {
+    /// Accept certificates that fail validation, e.g. self-signed ones
+    pub accept_invalid_certs: bool,
+    /// Accept certificates issued for a different host name
+    pub accept_invalid_hostnames: bool,
+    /// Additional trusted root certificate (PEM), for a private CA
+    pub ca_certificate: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    /// TLS connector for these options, or `None` for the system defaults
+    pub fn connector(&self) -> Result<Option<Connector>> {
+        if *self == Self::default() {
+            return Ok(None);
+        }
+        let mut builder = native_tls::TlsConnector::builder();
+        builder
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .danger_accept_invalid_hostnames(self.accept_invalid_hostnames);
+        if let Some(path) = &self.ca_certificate {
+            let pem = std::fs::read(path).map_err(|e| {
+                Error::config(format!("Failed to read CA certificate {}: {}", path.display(), e))
+            })?;
+            let certificate = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| Error::config(format!("Invalid CA certificate {}: {}", path.display(), e)))?;
+            builder.add_root_certificate(certificate);
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| Error::Connection(format!("Failed to set up TLS: {}", e)))?;
+        Ok(Some(Connector::NativeTls(connector)))
+    }
+}
+
 /// Configuration for a WebSocket connection
 #[derive(Debug, Clone)]
 pub struct WebSocketConfig {
@@ -31,6 +125,10 @@ pub struct WebSocketConfig {
     pub ping_interval_secs: u64,
     /// Reconnect automatically on disconnect
     pub auto_reconnect: bool,
+    /// Authentication sent with the handshake
+    pub auth: WebSocketAuth,
+    /// Certificate validation for wss:// URLs
+    pub tls: TlsOptions,
 }
 
 impl Default for WebSocketConfig {
@@ -41,6 +139,8 @@ impl Default for WebSocketConfig {
             read_timeout_ms: 1000,
             ping_interval_secs: 30,
             auto_reconnect: false,
+            auth: WebSocketAuth::None,
+            tls: TlsOptions::default(),
         }
     }
 }
@@ -77,11 +177,50 @@ impl WebSocketConnection {
     pub fn config(&self) -> &WebSocketConfig {
         &self.config
     }
+
+    /// Handshake request carrying the configured credentials
+    fn handshake_request(&self) -> Result<tungstenite::handshake::client::Request> {
+        let url = &self.config.url;
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| Error::Connection(format!("Invalid WebSocket URL {}: {}", url, e)))?;
+        if let Some(value) = self.config.auth.header_value() {
+            if url.starts_with("ws://") {
+                tracing::warn!("Sending credentials to {} without TLS", url);
+            }
+            let value = HeaderValue::from_str(&value)
+                .map_err(|_| Error::config("WebSocket credentials contain invalid characters"))?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Standard base64 with padding, for basic auth credentials
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((group >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
 }
 
 #[async_trait]
 impl Connection for WebSocketConnection {
     async fn connect(&mut self, connect_timeout: Duration) -> Result<()> {
+        let request = self.handshake_request()?;
+        let connector = self.config.tls.connector()?;
+
         // Update status to connecting
         {
             let mut status = self.status.lock().await;
@@ -91,10 +230,27 @@ impl Connection for WebSocketConnection {
         // Attempt connection with timeout
         let url = self.config.url.clone();
 
-        let (ws_stream, _) = timeout(connect_timeout, connect_async(&url))
+        let result = timeout(connect_timeout, connect_async_tls_with_config(request, None, false, connector))
             .await
-            .map_err(|_| Error::Connection(format!("Connection timeout to {}", url)))?
-            .map_err(|e| Error::Connection(format!("Failed to connect to {}: {}", url, e)))?;
+            .map_err(|_| Error::Connection(format!("Connection timeout to {}", url)));
+        let (ws_stream, _) = match result {
+            Ok(Ok(connected)) => connected,
+            Ok(Err(e)) => {
+                *self.status.lock().await = ConnectionStatus::Error;
+                return Err(match e {
+                    tungstenite::Error::Http(response)
+                        if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) =>
+                    {
+                        Error::Connection(format!("{} rejected the credentials ({})", url, response.status()))
+                    }
+                    e => Error::Connection(format!("Failed to connect to {}: {}", url, e)),
+                });
+            }
+            Err(e) => {
+                *self.status.lock().await = ConnectionStatus::Error;
+                return Err(e);
+            }
+        };
 
         // Store the stream
         {
@@ -258,6 +414,10 @@ impl Connection for WebSocketConnection {
         format!("WebSocket: {}", self.config.url)
     }
 
+    fn is_network(&self) -> bool {
+        true
+    }
+
     async fn flush(&mut self) -> Result<()> {
         // WebSocket connections are automatically flushed on send
         // No explicit flush needed
@@ -313,6 +473,46 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_websocket_auth_header() {
+        assert_eq!(WebSocketAuth::None.header_value(), None);
+        let bearer = WebSocketAuth::Bearer { token: " abc123 ".to_string() };
+        assert_eq!(bearer.header_value().as_deref(), Some("Bearer abc123"));
+        let basic = WebSocketAuth::Basic {
+            username: "Aladdin".to_string(),
+            password: "open sesame".to_string(),
+        };
+        assert_eq!(basic.header_value().as_deref(), Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="));
+        assert!(!format!("{:?}", basic).contains("sesame"));
+
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn test_handshake_request() {
+        let conn = WebSocketConnection::new(WebSocketConfig {
+            url: "wss://bridge.example.com/grbl".to_string(),
+            auth: WebSocketAuth::Bearer { token: "abc123".to_string() },
+            ..Default::default()
+        });
+        let request = conn.handshake_request().unwrap();
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer abc123");
+
+        let invalid = WebSocketConnection::with_url("not a url".to_string());
+        assert!(invalid.handshake_request().is_err());
+    }
+
+    #[test]
+    fn test_tls_connector() {
+        assert!(TlsOptions::default().connector().unwrap().is_none());
+        let missing = TlsOptions {
+            ca_certificate: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        assert!(missing.connector().is_err());
+    }
+
     #[tokio::test]
     async fn test_websocket_is_connected() {
         let conn = WebSocketConnection::new(WebSocketConfig::default());
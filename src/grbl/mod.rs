@@ -7,12 +7,23 @@ mod responses;
 mod realtime;
 mod queue;
 mod overrides;
+mod probing;
+mod reference;
+mod streamer;
+mod passes;
 
 pub use commands::{GrblCommand, GrblSettings};
-pub use responses::{GrblResponse, GrblStatus, MachineState, Position};
+pub use responses::{
+    AccessoryState, GrblParameter, GrblResponse, GrblStatus, MachineState, Position, ProbeResult,
+};
 pub use realtime::RealtimeCommand;
 pub use queue::{CommandQueue, QueueState, QueueStats};
 pub use overrides::{
-    OverrideCommand, OverrideType, OverrideState,
+    OverrideCommand, OverrideRamp, OverrideType, OverrideState,
     FeedRateOverride, SpindleOverride, RapidOverride,
 };
+pub use probing::{EdgeFinder, StockEdge};
+pub use reference::{ReferenceEntry, ReferenceTopic};
+pub use streamer::{ProgramStreamer, SentModes, StreamLine, StreamOptions};
+pub use passes::StreamPasses;
+pub(crate) use streamer::word_value;
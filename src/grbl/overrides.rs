@@ -1,8 +1,11 @@
 //! GRBL override controls
 //!
 //! Implements feed rate, spindle speed, and rapid override controls.
+//! Feed override changes can be ramped so the load on the cutter rises
+//! gradually instead of in one jump.
 
 use std::fmt;
+use std::time::Instant;
 
 /// Override type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,7 +30,7 @@ pub enum OverrideCommand {
 }
 
 /// Feed rate override commands
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FeedRateOverride {
     /// Reset to 100%
     Reset,
@@ -193,6 +196,94 @@ impl OverrideState {
     }
 }
 
+/// Moves the feed override to a target at a limited rate
+///
+/// GRBL applies each override byte as soon as it reads it, so a burst of
+/// them changes the feed in one jump. The ramp hands out 1% steps spaced
+/// to the rate instead; a rate of 0 hands out every step at once, in 10%
+/// and 1% steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverrideRamp {
+    /// Override sent so far (%)
+    current: f64,
+    /// Override being ramped to (%)
+    target: f64,
+    /// When steps were last handed out, while ramping
+    last_step: Option<Instant>,
+    /// Percent the rate has allowed but not yet handed out
+    allowance: f64,
+}
+
+impl OverrideRamp {
+    /// Ramp resting at an override (%)
+    pub fn new(percent: f64) -> Self {
+        Self { current: percent, target: percent, last_step: None, allowance: 0.0 }
+    }
+
+    /// Override sent so far (%)
+    pub fn current(&self) -> f64 {
+        self.current
+    }
+
+    /// Override being ramped to (%)
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    /// Whether steps remain to be sent
+    pub fn is_ramping(&self) -> bool {
+        (self.target - self.current).abs() >= 0.5
+    }
+
+    /// Ramp to a new override (%) from where the ramp is now
+    pub fn set_target(&mut self, percent: f64) {
+        self.target = percent;
+    }
+
+    /// Steps to send now to change the override at `rate` (%/s)
+    pub fn step(&mut self, now: Instant, rate: f64) -> Vec<FeedRateOverride> {
+        let mut steps = Vec::new();
+        if !self.is_ramping() {
+            self.last_step = None;
+            self.allowance = 0.0;
+            return steps;
+        }
+
+        if rate <= 0.0 {
+            while (self.target - self.current).abs() >= 10.0 {
+                steps.push(self.take_step(10.0));
+            }
+        } else {
+            // The first step goes out at once; later ones as the rate allows
+            self.allowance += match self.last_step {
+                Some(last) => rate * now.saturating_duration_since(last).as_secs_f64(),
+                None => 1.0,
+            };
+            self.last_step = Some(now);
+        }
+        while self.is_ramping() && (rate <= 0.0 || self.allowance >= 1.0) {
+            steps.push(self.take_step(1.0));
+            self.allowance -= 1.0;
+        }
+        if !self.is_ramping() {
+            self.last_step = None;
+            self.allowance = 0.0;
+        }
+        steps
+    }
+
+    fn take_step(&mut self, size: f64) -> FeedRateOverride {
+        let up = self.target > self.current;
+        self.current += if up { size } else { -size };
+        match (up, size >= 10.0) {
+            (true, true) => FeedRateOverride::CoarseUp,
+            (false, true) => FeedRateOverride::CoarseDown,
+            (true, false) => FeedRateOverride::FineUp,
+            (false, false) => FeedRateOverride::FineDown,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -248,4 +339,28 @@ mod tests {
         state.apply_feed_rate(FeedRateOverride::CoarseDown);
         assert_eq!(state.feed_rate, 10); // Should not go below 10
     }
+    
+    #[test]
+    fn test_override_ramp() {
+        use std::time::Duration;
+        
+        // Without a rate the whole change goes out at once
+        let start = Instant::now();
+        let mut ramp = OverrideRamp::new(100.0);
+        ramp.set_target(123.0);
+        let steps = ramp.step(start, 0.0);
+        assert_eq!(steps.iter().filter(|step| **step == FeedRateOverride::CoarseUp).count(), 2);
+        assert_eq!(steps.iter().filter(|step| **step == FeedRateOverride::FineUp).count(), 3);
+        assert!(!ramp.is_ramping());
+        
+        // At 20%/s a 10% drop takes half a second, one step at a time
+        ramp.set_target(113.0);
+        assert_eq!(ramp.step(start, 20.0), vec![FeedRateOverride::FineDown]);
+        assert!(ramp.step(start + Duration::from_millis(20), 20.0).is_empty());
+        assert_eq!(ramp.step(start + Duration::from_millis(230), 20.0).len(), 4);
+        assert_eq!(ramp.current(), 118.0);
+        assert_eq!(ramp.step(start + Duration::from_secs(5), 20.0).len(), 5);
+        assert_eq!(ramp.current(), 113.0);
+        assert!(!ramp.is_ramping());
+    }
 }
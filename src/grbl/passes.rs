@@ -0,0 +1,135 @@
+//! Program passes applied before streaming
+//!
+//! Plunge entries, safe retracts, dry runs, backlash compensation, spindle
+//! calibration, plasma pierces and auto-leveling all rewrite the program
+//! sent to the machine, often replacing one line with several. Every line
+//! they produce keeps the index of the loaded program line it came from, so
+//! breakpoints, pauses, resume points and progress still refer to the lines
+//! shown in the editor.
+
+use crate::heightmap::AutoLeveler;
+use crate::parser::{
+    BacklashCompensator, DryRun, ExpandedLine, PlasmaPostProcessor, PlungeRewriter, ProgramExpander, SafeRetract,
+    SpindleCalibration,
+};
+use crate::utils::error::Result;
+
+/// The passes a program goes through on its way to the streamer, in order
+#[derive(Default)]
+pub struct StreamPasses {
+    /// Expand subprograms and canned cycles instead of sending them as they are
+    pub expand: bool,
+    /// Ramped or helical plunge entries
+    pub entry: Option<PlungeRewriter>,
+    /// Retracts to the safe height before rapids
+    pub retract: Option<SafeRetract>,
+    /// Dry run above the stock
+    pub dry_run: Option<DryRun>,
+    /// Backlash compensation
+    pub backlash: Option<BacklashCompensator>,
+    /// Spindle speed calibration
+    pub calibration: Option<SpindleCalibration>,
+    /// Plasma pierce handling
+    pub plasma: Option<PlasmaPostProcessor>,
+    /// Auto-leveling, applied last so its Z limit guard sees the Z values actually sent
+    pub leveler: Option<AutoLeveler>,
+}
+
+impl StreamPasses {
+    /// Rewrite a program, returning the lines to send with their source lines
+    pub fn apply(&mut self, program: &str) -> Result<Vec<ExpandedLine>> {
+        let mut lines = if self.expand {
+            ProgramExpander::new().expand_with_sources(program)?
+        } else {
+            program
+                .lines()
+                .enumerate()
+                .map(|(source_line, text)| ExpandedLine {
+                    source_line,
+                    text: text.to_string(),
+                })
+                .collect()
+        };
+
+        if let Some(pass) = self.entry.as_mut() {
+            let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+            let mut replacements = pass.process_lines(&texts).into_iter();
+            lines = rewrite(lines, |_, _| replacements.next().unwrap_or_default());
+        }
+        if let Some(pass) = self.retract.as_mut() {
+            lines = rewrite(lines, |text, _| pass.process_line(text));
+        }
+        if let Some(pass) = self.dry_run.as_mut() {
+            lines = rewrite(lines, |text, _| pass.process_line(text));
+        }
+        if let Some(compensator) = self.backlash.as_mut() {
+            for line in &mut lines {
+                line.text = compensator.compensate_line(&line.text);
+            }
+        }
+        if let Some(calibration) = &self.calibration {
+            for line in &mut lines {
+                line.text = calibration.calibrate_line(&line.text);
+            }
+        }
+        if let Some(pass) = self.plasma.as_mut() {
+            lines = rewrite(lines, |text, _| pass.process_line(text));
+        }
+        if let Some(leveler) = self.leveler.as_mut() {
+            lines = rewrite(lines, |text, source_line| leveler.process_line(text, source_line));
+        }
+        Ok(lines)
+    }
+}
+
+/// Replace each line with the lines a pass returns for it, keeping its source line
+fn rewrite(lines: Vec<ExpandedLine>, mut pass: impl FnMut(&str, usize) -> Vec<String>) -> Vec<ExpandedLine> {
+    lines
+        .into_iter()
+        .flat_map(|line| {
+            let source_line = line.source_line;
+            pass(&line.text, source_line)
+                .into_iter()
+                .map(move |text| ExpandedLine { source_line, text })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grbl::{ProgramStreamer, StreamOptions};
+    use crate::heightmap::Heightmap;
+    use crate::parser::PlasmaSettings;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_breakpoint_keeps_source_line() {
+        let map = Heightmap::parse("0,0,0\n10,0,0.1\n20,0,0.2\n0,10,0\n10,10,0.1\n20,10,0.2").unwrap();
+        let mut passes = StreamPasses {
+            plasma: Some(PlasmaPostProcessor::new(PlasmaSettings {
+                enabled: true,
+                ..Default::default()
+            })),
+            leveler: Some(AutoLeveler::new(map, 5.0, -10.0)),
+            ..Default::default()
+        };
+        let program = "G21 G90\nG0 X0 Y0 Z1\nM3\nG1 X20 F100\nG1 Y10\nM5";
+        let lines = passes.apply(program).unwrap();
+        assert!(lines.len() > program.lines().count());
+
+        let mut streamer = ProgramStreamer::from_lines(
+            lines.iter().map(|line| (line.source_line, line.text.as_str())),
+            StreamOptions::default(),
+        )
+        .with_max_in_flight(100);
+        streamer.set_breakpoints(BTreeSet::from([4]));
+        let batch = streamer.next_lines();
+        assert_eq!(batch.last().map(|line| line.line_index), Some(3));
+        while streamer.acknowledge().is_some() {}
+
+        // Streaming stops before the first line leveled from line 4
+        assert_eq!(streamer.at_breakpoint(), Some(4));
+        assert_eq!(streamer.lines()[batch.len()].line_index, 4);
+    }
+}
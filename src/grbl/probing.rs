@@ -0,0 +1,122 @@
+//! Probing cycles
+//!
+//! Builds the commands for guided probing cycles and turns the reported
+//! trigger positions into work offsets.
+
+/// Edge of the stock to find
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockEdge {
+    /// Left edge (X-), probed moving in +X
+    Left,
+    /// Right edge (X+), probed moving in -X
+    Right,
+    /// Front edge (Y-), probed moving in +Y
+    Front,
+    /// Back edge (Y+), probed moving in -Y
+    Back,
+}
+
+impl StockEdge {
+    /// All edges
+    pub const ALL: [StockEdge; 4] = [StockEdge::Left, StockEdge::Right, StockEdge::Front, StockEdge::Back];
+
+    /// Axis the probe moves along
+    pub fn axis(self) -> char {
+        match self {
+            StockEdge::Left | StockEdge::Right => 'X',
+            StockEdge::Front | StockEdge::Back => 'Y',
+        }
+    }
+
+    /// Direction of probe travel (+1.0 or -1.0)
+    pub fn direction(self) -> f64 {
+        match self {
+            StockEdge::Left | StockEdge::Front => 1.0,
+            StockEdge::Right | StockEdge::Back => -1.0,
+        }
+    }
+}
+
+impl std::fmt::Display for StockEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StockEdge::Left => write!(f, "Left (X-)"),
+            StockEdge::Right => write!(f, "Right (X+)"),
+            StockEdge::Front => write!(f, "Front (Y-)"),
+            StockEdge::Back => write!(f, "Back (Y+)"),
+        }
+    }
+}
+
+/// Edge finding cycle with a probe or electronic edge finder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeFinder {
+    /// Edge being found
+    pub edge: StockEdge,
+    /// Maximum probe travel (mm)
+    pub distance: f64,
+    /// Probe feed rate (mm/min)
+    pub feed_rate: f64,
+    /// Back-off distance after contact (mm)
+    pub retract: f64,
+    /// Probe tip or edge finder diameter (mm)
+    pub tip_diameter: f64,
+}
+
+impl EdgeFinder {
+    /// Commands that probe toward the edge and back off
+    pub fn probe_commands(&self) -> Vec<String> {
+        let axis = self.edge.axis();
+        let direction = self.edge.direction();
+        vec![
+            format!("G91 G38.2 {}{:.3} F{:.0}", axis, direction * self.distance.abs(), self.feed_rate),
+            format!("G0 {}{:.3}", axis, -direction * self.retract.abs()),
+            "G90".to_string(),
+        ]
+    }
+
+    /// Machine coordinate of the edge for a trigger position on the probed axis
+    ///
+    /// The tip centre stops one radius short of the edge.
+    pub fn edge_position(&self, trigger: f64) -> f64 {
+        trigger + self.edge.direction() * self.tip_diameter.abs() / 2.0
+    }
+
+    /// Command setting the edge as zero in a work coordinate system
+    ///
+    /// `p_number` selects the system as in `G10 L2` (1 for G54).
+    pub fn offset_command(&self, p_number: u32, trigger: f64) -> String {
+        format!("G10 L2 P{} {}{:.3}", p_number, self.edge.axis(), self.edge_position(trigger))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finder(edge: StockEdge) -> EdgeFinder {
+        EdgeFinder {
+            edge,
+            distance: 10.0,
+            feed_rate: 100.0,
+            retract: 2.0,
+            tip_diameter: 4.0,
+        }
+    }
+
+    #[test]
+    fn test_probe_commands() {
+        assert_eq!(
+            finder(StockEdge::Left).probe_commands(),
+            vec!["G91 G38.2 X10.000 F100", "G0 X-2.000", "G90"]
+        );
+        assert_eq!(finder(StockEdge::Back).probe_commands()[0], "G91 G38.2 Y-10.000 F100");
+    }
+
+    #[test]
+    fn test_tip_compensation() {
+        assert_eq!(finder(StockEdge::Left).edge_position(-100.0), -98.0);
+        assert_eq!(finder(StockEdge::Right).edge_position(-100.0), -102.0);
+        assert_eq!(finder(StockEdge::Front).offset_command(2, -50.0), "G10 L2 P2 Y-48.000");
+    }
+}
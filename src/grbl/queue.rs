@@ -3,7 +3,8 @@
 /// This module implements a command queue that:
 /// - Maintains a bounded queue of commands
 /// - Handles command acknowledgments from GRBL
-/// - Implements flow control (wait for "ok" before sending next command)
+/// - Implements flow control (wait for "ok" before sending next command, or
+///   character counting against the controller RX buffer)
 /// - Tracks command timeouts
 /// - Supports priority for real-time commands
 
@@ -72,14 +73,16 @@ pub struct QueueStats {
 pub struct CommandQueue {
     /// Queue of pending commands
     queue: Arc<Mutex<VecDeque<QueuedCommand>>>,
-    /// Current command being executed
-    current_command: Arc<Mutex<Option<QueuedCommand>>>,
+    /// Commands sent and awaiting acknowledgment, oldest first
+    in_flight: Arc<Mutex<VecDeque<QueuedCommand>>>,
     /// Queue state
     state: Arc<Mutex<QueueState>>,
     /// Maximum queue capacity
     capacity: usize,
     /// Command timeout duration
     timeout: Duration,
+    /// Controller RX buffer size for character counting (0 waits for each "ok")
+    rx_buffer_size: usize,
     /// Next command ID
     next_id: Arc<Mutex<u64>>,
     /// Queue statistics
@@ -98,10 +101,11 @@ impl CommandQueue {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             queue: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
-            current_command: Arc::new(Mutex::new(None)),
+            in_flight: Arc::new(Mutex::new(VecDeque::new())),
             state: Arc::new(Mutex::new(QueueState::Idle)),
             capacity,
             timeout: DEFAULT_COMMAND_TIMEOUT,
+            rx_buffer_size: 0,
             next_id: Arc::new(Mutex::new(0)),
             stats: Arc::new(Mutex::new(QueueStats::default())),
             command_tx: None,
@@ -113,6 +117,24 @@ impl CommandQueue {
         self.timeout = timeout;
     }
 
+    /// Use character counting against a controller RX buffer of `bytes`
+    ///
+    /// Several commands are then kept in flight as long as their lines fit
+    /// in the buffer, instead of waiting for each "ok". 0 restores
+    /// send-response flow control.
+    pub fn set_rx_buffer_size(&mut self, bytes: usize) {
+        self.rx_buffer_size = bytes;
+    }
+
+    /// Whether `command` may be sent with `in_flight` unacknowledged
+    fn fits(&self, in_flight: &VecDeque<QueuedCommand>, command: &GrblCommand) -> bool {
+        if in_flight.is_empty() {
+            return true;
+        }
+        let used: usize = in_flight.iter().map(|cmd| cmd.command.to_string().len() + 1).sum();
+        self.rx_buffer_size > 0 && used + command.to_string().len() + 1 <= self.rx_buffer_size
+    }
+
     /// Set the command sender channel
     pub fn set_command_sender(&mut self, tx: mpsc::UnboundedSender<GrblCommand>) {
         self.command_tx = Some(tx);
@@ -181,9 +203,9 @@ impl CommandQueue {
 
     /// Handle OK response (command completed successfully)
     async fn handle_ok(&self) -> Result<()> {
-        let mut current = self.current_command.lock().await;
+        let mut in_flight = self.in_flight.lock().await;
         
-        if let Some(cmd) = current.take() {
+        if let Some(cmd) = in_flight.pop_front() {
             // Calculate execution time
             if let Some(sent_at) = cmd.sent_at {
                 let execution_time = sent_at.elapsed();
@@ -199,37 +221,35 @@ impl CommandQueue {
                 stats.avg_execution_time_ms = (old_avg * (total - 1.0) + new_time) / total;
             }
         }
+        drop(in_flight);
+
+        self.acknowledged().await
+    }
 
-        // Set state back to idle
+    /// Leave the waiting state once a command is acknowledged and send more
+    ///
+    /// A paused queue stays paused.
+    async fn acknowledged(&self) -> Result<()> {
         let mut state = self.state.lock().await;
-        *state = QueueState::Idle;
+        if *state == QueueState::WaitingForAck {
+            *state = QueueState::Idle;
+        }
         drop(state);
 
         // Try to send next command
-        self.try_send_next().await?;
-
-        Ok(())
+        self.try_send_next().await
     }
 
     /// Handle error response
     async fn handle_error(&self, _code: u8) -> Result<()> {
-        let mut current = self.current_command.lock().await;
-        current.take(); // Remove failed command
+        self.in_flight.lock().await.pop_front(); // Remove failed command
 
         // Update statistics
         let mut stats = self.stats.lock().await;
         stats.total_failed += 1;
         drop(stats);
 
-        // Set state back to idle
-        let mut state = self.state.lock().await;
-        *state = QueueState::Idle;
-        drop(state);
-
-        // Try to send next command
-        self.try_send_next().await?;
-
-        Ok(())
+        self.acknowledged().await
     }
 
     /// Handle alarm response
@@ -239,88 +259,51 @@ impl CommandQueue {
         *state = QueueState::Paused;
         drop(state);
 
-        // Clear current command
-        let mut current = self.current_command.lock().await;
-        current.take();
+        // Commands in flight are discarded by the controller
+        self.in_flight.lock().await.clear();
 
         Ok(())
     }
 
-    /// Try to send the next command in the queue
+    /// Send queued commands through the command sender while they fit
     async fn try_send_next(&self) -> Result<()> {
-        // Check if we can send
-        let state = self.state.lock().await;
-        if *state != QueueState::Idle {
-            return Ok(());
-        }
-        drop(state);
-
         // Check if there's a command sender
         let command_tx = match &self.command_tx {
             Some(tx) => tx.clone(),
             None => return Ok(()), // No sender yet
         };
 
-        // Get next command
-        let mut queue = self.queue.lock().await;
-        let mut cmd = match queue.pop_front() {
-            Some(cmd) => cmd,
-            None => return Ok(()), // Queue is empty
-        };
-
-        // Update statistics
-        let mut stats = self.stats.lock().await;
-        stats.current_length = queue.len();
-        stats.total_sent += 1;
-        drop(stats);
-        drop(queue);
-
-        // Mark as sent
-        cmd.sent_at = Some(Instant::now());
-
-        // Send the command
-        command_tx
-            .send(cmd.command.clone())
-            .map_err(|e| Error::Connection(format!("Failed to send command: {}", e)))?;
-
-        // Set current command
-        let mut current = self.current_command.lock().await;
-        *current = Some(cmd);
-
-        // Update state
-        let mut state = self.state.lock().await;
-        *state = QueueState::WaitingForAck;
+        while let Some(command) = self.next_command().await? {
+            command_tx
+                .send(command)
+                .map_err(|e| Error::Connection(format!("Failed to send command: {}", e)))?;
+            self.mark_sent().await?;
+        }
 
         Ok(())
     }
 
     /// Check for timed-out commands
     pub async fn check_timeouts(&self) -> Result<()> {
-        let mut current = self.current_command.lock().await;
-        
-        if let Some(cmd) = current.as_ref() {
-            if let Some(sent_at) = cmd.sent_at {
-                if sent_at.elapsed() > self.timeout {
-                    // Command timed out
-                    current.take();
-                    
-                    // Update statistics
-                    let mut stats = self.stats.lock().await;
-                    stats.total_timeouts += 1;
-                    drop(stats);
-
-                    // Set state back to idle
-                    let mut state = self.state.lock().await;
-                    *state = QueueState::Idle;
-                    drop(state);
-                    drop(current);
-
-                    // Try to send next command
-                    self.try_send_next().await?;
-                    
-                    return Err(Error::Timeout("Command execution timed out".to_string()));
-                }
-            }
+        let mut in_flight = self.in_flight.lock().await;
+        
+        // Only the oldest command can be overdue first
+        let timed_out = in_flight
+            .front()
+            .and_then(|cmd| cmd.sent_at)
+            .is_some_and(|sent_at| sent_at.elapsed() > self.timeout);
+        if timed_out {
+            in_flight.pop_front();
+            drop(in_flight);
+            
+            // Update statistics
+            let mut stats = self.stats.lock().await;
+            stats.total_timeouts += 1;
+            drop(stats);
+
+            self.acknowledged().await?;
+            
+            return Err(Error::Timeout("Command execution timed out".to_string()));
         }
 
         Ok(())
@@ -366,20 +349,26 @@ impl CommandQueue {
     
     /// Get the next command to send (if ready)
     ///
-    /// Returns None if queue is empty, paused, or waiting for acknowledgment
+    /// Returns None if queue is empty, paused, or waiting for acknowledgment.
+    /// With character counting, commands are ready while they fit in the
+    /// controller RX buffer next to the ones in flight.
     pub async fn next_command(&self) -> Result<Option<GrblCommand>> {
         // Check if we can send
         let state = self.state.lock().await;
         tracing::debug!("Queue: next_command called, current state: {:?}", *state);
-        if *state != QueueState::Idle {
-            tracing::debug!("Queue: not in Idle state, returning None");
+        if !matches!(*state, QueueState::Idle | QueueState::WaitingForAck) {
+            tracing::debug!("Queue: not accepting commands, returning None");
             return Ok(None);
         }
         drop(state);
         
         // Get next command
         let queue = self.queue.lock().await;
-        let cmd = queue.front().map(|cmd| cmd.command.clone());
+        let in_flight = self.in_flight.lock().await;
+        let cmd = queue
+            .front()
+            .map(|cmd| cmd.command.clone())
+            .filter(|command| self.fits(&in_flight, command));
         if let Some(ref c) = cmd {
             tracing::info!("Queue: next_command returning: {:?}", c);
         } else {
@@ -408,10 +397,7 @@ impl CommandQueue {
         
         // Mark as sent
         cmd.sent_at = Some(Instant::now());
-        
-        // Set current command
-        let mut current = self.current_command.lock().await;
-        *current = Some(cmd);
+        self.in_flight.lock().await.push_back(cmd);
         
         // Update state
         let mut state = self.state.lock().await;
@@ -597,4 +583,53 @@ mod tests {
         
         assert_eq!(queue.get_state().await, QueueState::Paused);
     }
+
+    #[tokio::test]
+    async fn test_character_counting() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut queue = CommandQueue::new();
+        queue.set_rx_buffer_size(20);
+        queue.set_command_sender(tx);
+        
+        // "G0 X10\n" is 7 bytes, so two fit in 20 and the third waits
+        for _ in 0..3 {
+            queue.enqueue(GrblCommand::GCode("G0 X10".to_string())).await.unwrap();
+        }
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(queue.len().await, 1);
+        
+        queue.handle_response(&GrblResponse::Ok).await.unwrap();
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(queue.get_stats().await.total_sent, 3);
+        
+        // A paused queue stays paused when acknowledgments arrive
+        queue.pause().await;
+        queue.handle_response(&GrblResponse::Ok).await.unwrap();
+        assert_eq!(queue.get_state().await, QueueState::Paused);
+    }
+
+    #[tokio::test]
+    async fn test_check_timeouts() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut queue = CommandQueue::new();
+        queue.set_timeout(Duration::from_millis(20));
+        queue.set_command_sender(tx);
+        
+        queue.enqueue(GrblCommand::GCode("G0 X10".to_string())).await.unwrap();
+        queue.enqueue(GrblCommand::GCode("G0 X20".to_string())).await.unwrap();
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+        
+        // Not overdue yet
+        assert!(queue.check_timeouts().await.is_ok());
+        
+        // The overdue command is dropped and the next one sent
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(matches!(queue.check_timeouts().await, Err(Error::Timeout(_))));
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(queue.get_stats().await.total_timeouts, 1);
+        assert_eq!(queue.len().await, 0);
+    }
 }
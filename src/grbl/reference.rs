@@ -0,0 +1,272 @@
+//! Offline GRBL reference
+//!
+//! The `$` settings, error and alarm codes of GRBL 1.1 and common wiring
+//! and communication problems, kept in the crate so they can be looked up
+//! without a network connection. Error and alarm titles are the messages
+//! shown in the console; the reference adds what to check.
+
+use super::responses::{get_alarm_message, get_error_message};
+use std::fmt;
+
+/// Section of the reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceTopic {
+    /// `$n` settings
+    Setting,
+    /// `error:n` responses
+    Error,
+    /// `ALARM:n` states
+    Alarm,
+    /// Wiring and communication problems
+    Troubleshooting,
+}
+
+impl ReferenceTopic {
+    /// All sections in display order
+    pub const ALL: [ReferenceTopic; 4] = [
+        ReferenceTopic::Setting,
+        ReferenceTopic::Error,
+        ReferenceTopic::Alarm,
+        ReferenceTopic::Troubleshooting,
+    ];
+}
+
+impl fmt::Display for ReferenceTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferenceTopic::Setting => write!(f, "Settings"),
+            ReferenceTopic::Error => write!(f, "Errors"),
+            ReferenceTopic::Alarm => write!(f, "Alarms"),
+            ReferenceTopic::Troubleshooting => write!(f, "Troubleshooting"),
+        }
+    }
+}
+
+/// One page of the reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceEntry {
+    /// Section
+    pub topic: ReferenceTopic,
+    /// Setting, error or alarm number; `None` for troubleshooting pages
+    pub code: Option<u32>,
+    /// One-line summary
+    pub title: &'static str,
+    /// Explanation and what to check
+    pub text: &'static str,
+}
+
+impl ReferenceEntry {
+    /// Code as GRBL prints it, e.g. `$110`, `error:20` or `ALARM:1`
+    pub fn key(&self) -> String {
+        match (self.topic, self.code) {
+            (ReferenceTopic::Setting, Some(code)) => format!("${}", code),
+            (ReferenceTopic::Error, Some(code)) => format!("error:{}", code),
+            (ReferenceTopic::Alarm, Some(code)) => format!("ALARM:{}", code),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether every word of the query appears in the key, title or text
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!("{} {} {}", self.key(), self.title, self.text).to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+
+    /// Every entry of the reference, by section and code
+    pub fn all() -> Vec<Self> {
+        let settings = SETTINGS.iter().map(|&(code, title, text)| ReferenceEntry {
+            topic: ReferenceTopic::Setting,
+            code: Some(code),
+            title,
+            text,
+        });
+        let errors = ERROR_CODES.iter().map(|&code| ReferenceEntry {
+            topic: ReferenceTopic::Error,
+            code: Some(code as u32),
+            title: get_error_message(code),
+            text: error_hint(code),
+        });
+        let alarms = (1..=9).map(|code| ReferenceEntry {
+            topic: ReferenceTopic::Alarm,
+            code: Some(code as u32),
+            title: get_alarm_message(code),
+            text: alarm_hint(code),
+        });
+        let troubleshooting = TROUBLESHOOTING.iter().map(|&(title, text)| ReferenceEntry {
+            topic: ReferenceTopic::Troubleshooting,
+            code: None,
+            title,
+            text,
+        });
+        settings.chain(errors).chain(alarms).chain(troubleshooting).collect()
+    }
+
+    /// Entry for a setting, error or alarm number
+    pub fn lookup(topic: ReferenceTopic, code: u32) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|entry| entry.topic == topic && entry.code == Some(code))
+    }
+
+    /// First `error:n` or `ALARM:n` code mentioned in a message
+    pub fn code_in(message: &str) -> Option<(ReferenceTopic, u32)> {
+        let lower = message.to_lowercase();
+        [("error:", ReferenceTopic::Error), ("alarm:", ReferenceTopic::Alarm)]
+            .into_iter()
+            .filter_map(|(prefix, topic)| {
+                let at = lower.find(prefix)?;
+                let digits: String = lower[at + prefix.len()..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                Some((at, topic, digits.parse().ok()?))
+            })
+            .min_by_key(|(at, _, _)| *at)
+            .map(|(_, topic, code)| (topic, code))
+    }
+}
+
+/// `$` settings of GRBL 1.1: number, name and description
+const SETTINGS: &[(u32, &str, &str)] = &[
+    (0, "Step pulse time (µs)", "Length of the step pulse. Most drivers work with 10; raise it if a driver misses steps at high rates. Must be at least 3."),
+    (1, "Step idle delay (ms)", "How long the motors stay powered after a move. 255 keeps them always enabled, which holds position against cutting forces."),
+    (2, "Step pulse invert (mask)", "Inverts the step signal of each axis (X=1, Y=2, Z=4). Only needed for drivers that step on the falling edge."),
+    (3, "Direction invert (mask)", "Reverses the direction of each axis (X=1, Y=2, Z=4). Use it when an axis jogs the wrong way instead of swapping motor wires."),
+    (4, "Invert step enable pin", "Inverts the enable signal to the drivers. Set it if the motors are not powered while the machine should hold."),
+    (5, "Invert limit pins", "Inverts the limit switch inputs. Normally-closed switches without an inverted board need 1; if every limit reads triggered, flip it."),
+    (6, "Invert probe pin", "Inverts the probe input. If the probe reads triggered while nothing touches, flip it."),
+    (10, "Status report options (mask)", "Selects what the status report contains. 1 reports machine position, 2 the planner and serial buffer; rCandle needs both (3)."),
+    (11, "Junction deviation (mm)", "How fast the machine takes corners. Larger values corner faster but harder; 0.010 is typical."),
+    (12, "Arc tolerance (mm)", "How finely arcs are split into lines. 0.002 is typical; smaller values load the controller more."),
+    (13, "Report in inches", "Report positions in inches instead of millimeters."),
+    (20, "Soft limits enable", "Rejects moves outside the travel set by $130-$132. Needs homing ($22) so the machine position is known."),
+    (21, "Hard limits enable", "Stops immediately with ALARM:1 when a limit switch triggers. Needs working limit switches; noisy wiring gives false alarms."),
+    (22, "Homing cycle enable", "Enables the homing cycle ($H). With homing enabled the controller starts in alarm until homed or unlocked."),
+    (23, "Homing direction invert (mask)", "Homes each axis toward minus instead of plus (X=1, Y=2, Z=4)."),
+    (24, "Homing locate feed rate (mm/min)", "Slow feed used to find the exact switch position after the first touch."),
+    (25, "Homing search seek rate (mm/min)", "Fast feed used to find the switches. Too fast and the machine can overrun the switch."),
+    (26, "Homing switch debounce (ms)", "Delay that filters switch bounce during homing. 250 is typical."),
+    (27, "Homing switch pull-off (mm)", "Distance moved off the switches after homing. Raise it if homing ends with ALARM:8."),
+    (30, "Maximum spindle speed (RPM)", "Spindle speed at full PWM output. S values are scaled against it; set it to the spindle's real maximum."),
+    (31, "Minimum spindle speed (RPM)", "Spindle speed at the lowest PWM output. Lower S values are raised to it."),
+    (32, "Laser mode enable", "Keeps moving through power changes instead of stopping for each S word, and turns the laser off on rapids."),
+    (100, "X steps/mm", "Steps for one millimeter of X travel: motor steps per revolution × microstepping ÷ mm per revolution."),
+    (101, "Y steps/mm", "Steps for one millimeter of Y travel: motor steps per revolution × microstepping ÷ mm per revolution."),
+    (102, "Z steps/mm", "Steps for one millimeter of Z travel: motor steps per revolution × microstepping ÷ mm per revolution."),
+    (110, "X max rate (mm/min)", "Fastest X move, also the rapid speed. Too high and the motor stalls."),
+    (111, "Y max rate (mm/min)", "Fastest Y move, also the rapid speed. Too high and the motor stalls."),
+    (112, "Z max rate (mm/min)", "Fastest Z move, also the rapid speed. Too high and the motor stalls."),
+    (120, "X acceleration (mm/s²)", "X acceleration. Lower it if the axis loses steps when starting or stopping."),
+    (121, "Y acceleration (mm/s²)", "Y acceleration. Lower it if the axis loses steps when starting or stopping."),
+    (122, "Z acceleration (mm/s²)", "Z acceleration. Lower it if the axis loses steps when starting or stopping."),
+    (130, "X max travel (mm)", "Length of X travel from home, used by soft limits and the jog limits."),
+    (131, "Y max travel (mm)", "Length of Y travel from home, used by soft limits and the jog limits."),
+    (132, "Z max travel (mm)", "Length of Z travel from home, used by soft limits and the jog limits."),
+];
+
+/// Error codes GRBL 1.1 reports
+const ERROR_CODES: [u8; 36] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34,
+    35, 36, 37, 38,
+];
+
+/// What to check for an error code
+fn error_hint(code: u8) -> &'static str {
+    match code {
+        1..=4 => "The line or command was mistyped or damaged in transfer. Check the line, and the cable if errors appear at random.",
+        5 => "Enable homing with $22=1, or use Unlock ($X) instead of homing.",
+        6 => "Set $0 to 3 or more.",
+        7 => "The settings were lost and reset to defaults. Restore them from a backup of $$ and check the controller's power supply.",
+        8 => "Wait for the machine to be Idle, or stop the program first.",
+        9 => "The machine is in alarm. Home it or Unlock ($X) before sending G-code.",
+        10 => "Enable homing ($22=1) before soft limits ($20=1).",
+        11 => "Shorten the line; GRBL accepts at most 80 characters. Remove comments or reduce the decimals in the post processor.",
+        12 => "Lower $110-$112 or the steps/mm ($100-$102); the controller cannot step that fast.",
+        13 => "Close the safety door, then resume.",
+        15 => "Jog a shorter distance or home the machine so the travel limits are right.",
+        16 => "Jog commands start with $J= and may only contain G20/G21, G90/G91, G53, axis words and F.",
+        17 => "Laser mode ($32) needs a PWM spindle output; check the controller build.",
+        20 => "The program uses a G-code GRBL does not support. Check the post processor and the compatibility report.",
+        21 | 24 | 25 => "Split the line so each block has one command per modal group and no repeated words.",
+        22 => "Add an F word before the first G1, G2 or G3 move.",
+        33..=35 => "An arc is invalid: check the I/J/K or R words and the arc plane (G17-G19), or output arcs as lines.",
+        _ => "Check the G-code line sent just before the error; the post processor may need a GRBL setting.",
+    }
+}
+
+/// What to check for an alarm code
+fn alarm_hint(code: u8) -> &'static str {
+    match code {
+        1 => "A limit switch triggered. Jog off the switch after Unlock ($X), then home. Repeated false alarms point at electrical noise: use shielded cable and check the switch wiring.",
+        2 => "The move would leave the travel set by $130-$132. Check the work zero and the program extents, or home the machine first.",
+        3 => "A reset stopped the machine while moving, so steps may be lost. Home the machine again.",
+        4 => "The probe was triggered before probing started. Check the probe wiring and $6.",
+        5 => "The probe did not touch within the probing distance. Start closer to the surface or increase the distance.",
+        6 | 7 => "Homing was interrupted. Clear the cause and home again.",
+        8 => "Homing could not move off a switch. Increase the pull-off ($27) and check that the switch releases.",
+        9 => "A switch was not found. Check the homing direction ($23), the switch wiring and the travel ($130-$132).",
+        _ => "Unlock ($X) or home, then check what triggered the alarm.",
+    }
+}
+
+/// Common wiring and communication problems: title and steps
+const TROUBLESHOOTING: &[(&str, &str)] = &[
+    (
+        "Port not listed",
+        "Check the USB cable (some only carry power) and try another USB port. Install the driver for the board's USB chip (CH340, FTDI or CP210x). On Linux, add the user to the dialout group and log in again.",
+    ),
+    (
+        "Connected but no response",
+        "Check the baud rate: GRBL 1.1 uses 115200, some grblHAL boards 250000. Close other programs using the port. Press the reset button on the board; the controller should print its welcome message.",
+    ),
+    (
+        "Connection drops during a job",
+        "Usually electrical noise from the spindle or a power-saving USB hub. Use a shielded USB cable with ferrite beads, keep it away from motor and spindle wires, and disable USB selective suspend.",
+    ),
+    (
+        "Axis moves the wrong way",
+        "Flip the axis bit of the direction invert mask ($3): X=1, Y=2, Z=4. Homing may then also need $23.",
+    ),
+    (
+        "Axis moves the wrong distance",
+        "Check the steps/mm ($100-$102) against the driver microstepping and the screw pitch or belt. Measure a long move and scale: new = old × commanded ÷ measured.",
+    ),
+    (
+        "Motor buzzes but does not turn",
+        "One coil is wired wrong or disconnected: check the motor connector pairs. It can also be a rate or acceleration too high for the motor ($110-$122).",
+    ),
+    (
+        "Limit switch alarms at random",
+        "Electrical noise on the switch wires. Use shielded cable grounded at the controller, normally-closed switches, or a small capacitor across the input; check $5.",
+    ),
+    (
+        "Probe never triggers",
+        "Touch the probe to the tool by hand and watch the P pin flag in the status. If it never shows, check the wiring and the clip; if it shows all the time, flip $6.",
+    ),
+    (
+        "Spindle speed is wrong",
+        "Set $30 to the spindle's maximum speed and $31 to its minimum; S values are scaled between them. Check that laser mode ($32) is off for a spindle.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_lookup() {
+        let entry = ReferenceEntry::lookup(ReferenceTopic::Setting, 110).unwrap();
+        assert_eq!(entry.key(), "$110");
+        assert!(entry.matches("x MAX rate"));
+        assert_eq!(ReferenceEntry::lookup(ReferenceTopic::Alarm, 1).unwrap().key(), "ALARM:1");
+        assert!(ReferenceEntry::lookup(ReferenceTopic::Error, 18).is_none());
+        assert!(ReferenceEntry::all().iter().all(|entry| !entry.title.starts_with("Unknown")));
+
+        assert_eq!(ReferenceEntry::code_in("Streaming stopped at line 12: error:20"), Some((ReferenceTopic::Error, 20)));
+        assert_eq!(ReferenceEntry::code_in("ALARM:9 then error:3"), Some((ReferenceTopic::Alarm, 9)));
+        assert_eq!(ReferenceEntry::code_in("Connection closed"), None);
+    }
+}
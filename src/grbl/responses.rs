@@ -239,6 +239,44 @@ impl GrblStatus {
 
         Ok(status)
     }
+
+    /// Accessory state from the `A:` field
+    ///
+    /// GRBL only sends `A:` alongside `Ov:` and omits it when nothing is on,
+    /// so a report with overrides but no accessories means all are off.
+    /// Returns `None` when the report carries neither.
+    pub fn accessory_state(&self) -> Option<AccessoryState> {
+        match &self.accessories {
+            Some(field) => Some(AccessoryState::parse(field.strip_prefix("A:").unwrap_or(field))),
+            None if self.feed_override.is_some() => Some(AccessoryState::default()),
+            None => None,
+        }
+    }
+}
+
+/// Spindle and coolant state reported in the `A:` field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessoryState {
+    /// Spindle on, clockwise (S)
+    pub spindle_cw: bool,
+    /// Spindle on, counter-clockwise (C)
+    pub spindle_ccw: bool,
+    /// Flood coolant on (F)
+    pub flood: bool,
+    /// Mist coolant on (M)
+    pub mist: bool,
+}
+
+impl AccessoryState {
+    /// Parse the flags of an `A:` field (e.g. "SFM")
+    pub fn parse(flags: &str) -> Self {
+        AccessoryState {
+            spindle_cw: flags.contains('S'),
+            spindle_ccw: flags.contains('C'),
+            flood: flags.contains('F'),
+            mist: flags.contains('M'),
+        }
+    }
 }
 
 /// Machine state from GRBL
@@ -283,6 +321,56 @@ impl FromStr for MachineState {
     }
 }
 
+/// Stored position reported by `$#` (e.g. `[G28:0.000,0.000,0.000]`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrblParameter {
+    /// Parameter name (G54-G59, G28, G30 or G92)
+    pub name: String,
+    /// Stored position (extra axes are ignored)
+    pub position: Position,
+}
+
+impl GrblParameter {
+    /// Parse feedback message content (without [ ])
+    ///
+    /// Returns `None` for feedback that is not a stored position, such as
+    /// `TLO` or `PRB`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let (name, values) = content.split_once(':')?;
+        if !matches!(name, "G54" | "G55" | "G56" | "G57" | "G58" | "G59" | "G28" | "G30" | "G92") {
+            return None;
+        }
+        let values: Vec<&str> = values.split(',').take(3).collect();
+        let position = Position::parse(&values.join(",")).ok()?;
+        Some(GrblParameter {
+            name: name.to_string(),
+            position,
+        })
+    }
+}
+
+/// Probe cycle result (e.g. `[PRB:0.000,0.000,-12.340:1]`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeResult {
+    /// Machine position where the probe stopped
+    pub position: Position,
+    /// Whether the probe triggered
+    pub success: bool,
+}
+
+impl ProbeResult {
+    /// Parse feedback message content (without [ ])
+    pub fn parse(content: &str) -> Option<Self> {
+        let rest = content.strip_prefix("PRB:")?;
+        let (values, success) = rest.rsplit_once(':')?;
+        let values: Vec<&str> = values.split(',').take(3).collect();
+        Some(ProbeResult {
+            position: Position::parse(&values.join(",")).ok()?,
+            success: success == "1",
+        })
+    }
+}
+
 /// 3D position
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Position {
@@ -325,7 +413,7 @@ impl Position {
 }
 
 /// Get error message for GRBL error code
-fn get_error_message(code: u8) -> &'static str {
+pub(super) fn get_error_message(code: u8) -> &'static str {
     match code {
         1 => "G-code words consist of a letter and a value. Letter was not found.",
         2 => "Numeric value format is not valid or missing an expected value.",
@@ -368,7 +456,7 @@ fn get_error_message(code: u8) -> &'static str {
 }
 
 /// Get alarm message for GRBL alarm code
-fn get_alarm_message(code: u8) -> &'static str {
+pub(super) fn get_alarm_message(code: u8) -> &'static str {
     match code {
         1 => "Hard limit triggered. Machine position is likely lost.",
         2 => "G-code motion target exceeds machine travel.",
@@ -387,6 +475,45 @@ fn get_alarm_message(code: u8) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_accessory_state() {
+        let status = GrblStatus::parse("Run|MPos:0.000,0.000,0.000|Ov:100,100,100|A:SF").unwrap();
+        let accessories = status.accessory_state().unwrap();
+        assert!(accessories.spindle_cw && accessories.flood);
+        assert!(!accessories.mist && !accessories.spindle_ccw);
+
+        let status = GrblStatus::parse("Idle|MPos:0.000,0.000,0.000|Ov:100,100,100").unwrap();
+        assert_eq!(status.accessory_state(), Some(AccessoryState::default()));
+
+        let status = GrblStatus::parse("Idle|MPos:0.000,0.000,0.000").unwrap();
+        assert_eq!(status.accessory_state(), None);
+    }
+
+    #[test]
+    fn test_parse_probe_result() {
+        let result = ProbeResult::parse("PRB:-10.000,-20.000,-35.125:1").unwrap();
+        assert_eq!(result.position, Position::new(-10.0, -20.0, -35.125));
+        assert!(result.success);
+
+        let result = ProbeResult::parse("PRB:0.000,0.000,0.000:0").unwrap();
+        assert!(!result.success);
+        assert!(ProbeResult::parse("G28:0.000,0.000,0.000").is_none());
+    }
+
+    #[test]
+    fn test_parse_parameter() {
+        let parameter = GrblParameter::parse("G28:-10.000,-20.500,-1.000").unwrap();
+        assert_eq!(parameter.name, "G28");
+        assert_eq!(parameter.position, Position::new(-10.0, -20.5, -1.0));
+
+        let parameter = GrblParameter::parse("G30:1.000,2.000,3.000,4.000").unwrap();
+        assert_eq!(parameter.position, Position::new(1.0, 2.0, 3.0));
+
+        assert!(GrblParameter::parse("TLO:0.000").is_none());
+        assert!(GrblParameter::parse("PRB:0.000,0.000,0.000:0").is_none());
+        assert!(GrblParameter::parse("MSG:Reset to continue").is_none());
+    }
+
     #[test]
     fn test_parse_ok() {
         let response = GrblResponse::parse("ok").unwrap();
@@ -0,0 +1,882 @@
+//! Program streaming
+//!
+//! Prepares the lines of a G-Code program for sending to GRBL and tracks
+//! how many lines have been handed to the command queue and acknowledged.
+
+use std::collections::BTreeSet;
+
+/// Options controlling how program lines are prepared for streaming
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Skip block delete ("/") lines instead of sending them
+    pub skip_block_delete: bool,
+    /// Hold streaming at M6 tool changes instead of sending them
+    pub hold_at_tool_change: bool,
+    /// Dwell inserted after M3/M4 so the spindle reaches speed (milliseconds, 0 for none)
+    pub spindle_dwell_ms: u32,
+}
+
+/// A single line prepared for streaming
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamLine {
+    /// Index of the line in the original program (0-based)
+    pub line_index: usize,
+    /// Text to send to GRBL
+    pub text: String,
+    /// Tool number requested if the line was an M6 tool change
+    pub tool_change: Option<u32>,
+}
+
+/// Modal state left by the lines sent so far
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SentModes {
+    /// Inch units (G20)
+    pub inches: bool,
+    /// Incremental distances (G91)
+    pub incremental: bool,
+    /// Motion mode (G0-G3), unless a probe, a canned cycle or G80 followed it
+    pub motion: Option<u32>,
+    /// Feed rate, once set
+    pub feed: Option<f64>,
+}
+
+/// G codes setting the motion mode
+const MOTION_CODES: &[f64] = &[
+    0.0, 1.0, 2.0, 3.0, 38.2, 38.3, 38.4, 38.5, 80.0, 81.0, 82.0, 83.0, 84.0, 85.0, 86.0, 87.0, 88.0, 89.0,
+];
+
+/// Default number of lines handed to the command queue ahead of acknowledgment
+const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+
+/// Tracks streaming progress through a program
+#[derive(Debug, Clone)]
+pub struct ProgramStreamer {
+    /// Prepared lines
+    lines: Vec<StreamLine>,
+    /// Index of the next line to hand out
+    next: usize,
+    /// Number of lines acknowledged by GRBL
+    acknowledged: usize,
+    /// Maximum number of lines handed out but not yet acknowledged
+    max_in_flight: usize,
+    /// Whether the tool change at `next` has been completed
+    tool_change_released: bool,
+    /// Whether streaming is held for an operation between lines
+    hold_requested: bool,
+    /// Index of a line streaming stops before
+    stop_at: Option<usize>,
+    /// Program lines streaming pauses before
+    breakpoints: BTreeSet<usize>,
+    /// Pause before every program line
+    step_mode: bool,
+    /// Whether the breakpoint at `next` has been passed
+    breakpoint_released: bool,
+    /// Dwell after starting the spindle (milliseconds)
+    spindle_dwell_ms: u32,
+}
+
+impl ProgramStreamer {
+    /// Create a streamer for a program
+    pub fn new(program: &str, options: StreamOptions) -> Self {
+        Self::from_lines(program.lines().enumerate(), options)
+    }
+
+    /// Create a streamer from lines paired with their index in the source program
+    ///
+    /// Used when the streamed text differs from the loaded program (e.g.
+    /// after canned cycle expansion) so progress still maps to source lines.
+    pub fn from_lines<'a, I>(lines: I, options: StreamOptions) -> Self
+    where
+        I: IntoIterator<Item = (usize, &'a str)>,
+    {
+        let mut tool = 0;
+        let lines = lines
+            .into_iter()
+            .filter_map(|(line_index, line)| {
+                let text = Self::prepare_line(line, options)?;
+                if let Some(number) = word_value(&text, 'T') {
+                    tool = number as u32;
+                }
+                if options.hold_at_tool_change {
+                    if let Some(text) = strip_tool_change(&text) {
+                        return Some(StreamLine {
+                            line_index,
+                            text,
+                            tool_change: Some(tool),
+                        });
+                    }
+                }
+                Some(StreamLine {
+                    line_index,
+                    text,
+                    tool_change: None,
+                })
+            })
+            .flat_map(|line| {
+                let dwell = (options.spindle_dwell_ms > 0
+                    && has_word(&line.text, 'M', &[3.0, 4.0])
+                    && !has_word(&line.text, 'G', &[4.0]))
+                .then(|| StreamLine {
+                    line_index: line.line_index,
+                    text: format!("G4 P{:.3}", options.spindle_dwell_ms as f64 / 1000.0),
+                    tool_change: None,
+                });
+                std::iter::once(line).chain(dwell)
+            })
+            .collect();
+
+        Self {
+            lines,
+            next: 0,
+            acknowledged: 0,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            tool_change_released: false,
+            hold_requested: false,
+            stop_at: None,
+            breakpoints: BTreeSet::new(),
+            step_mode: false,
+            breakpoint_released: false,
+            spindle_dwell_ms: options.spindle_dwell_ms,
+        }
+    }
+
+    /// Set the maximum number of unacknowledged lines
+    pub fn with_max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max.max(1);
+        self
+    }
+
+    /// Prepare a single program line for sending
+    ///
+    /// Comments, program delimiters ("%") and empty lines are removed.
+    /// Block delete lines are dropped or sent without the marker depending
+    /// on the options, since GRBL does not understand "/".
+    pub fn prepare_line(line: &str, options: StreamOptions) -> Option<String> {
+        let mut text = line.trim();
+
+        if text.starts_with('%') {
+            return None;
+        }
+
+        if let Some(rest) = text.strip_prefix('/') {
+            if options.skip_block_delete {
+                return None;
+            }
+            text = rest;
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut in_comment = false;
+        for ch in text.chars() {
+            match ch {
+                ';' if !in_comment => break,
+                '(' => in_comment = true,
+                ')' if in_comment => in_comment = false,
+                _ if !in_comment => result.push(ch),
+                _ => {}
+            }
+        }
+
+        let result = result.trim();
+        if result.is_empty() {
+            None
+        } else {
+            Some(result.to_string())
+        }
+    }
+
+    /// Get all prepared lines
+    pub fn lines(&self) -> &[StreamLine] {
+        &self.lines
+    }
+
+    /// Take the lines that can be queued now without exceeding the in-flight limit
+    ///
+    /// Streaming stops before a held tool change until it is released,
+    /// before a breakpoint until it is released, and while a hold is
+    /// requested.
+    pub fn next_lines(&mut self) -> Vec<StreamLine> {
+        if self.hold_requested {
+            return Vec::new();
+        }
+        let in_flight = self.next - self.acknowledged;
+        let available = self.max_in_flight.saturating_sub(in_flight);
+        let mut end = (self.next + available).min(self.lines.len());
+        if let Some(stop) = self.stop_at.filter(|stop| *stop >= self.next) {
+            end = end.min(stop);
+        }
+
+        let start = if self.tool_change_released { self.next + 1 } else { self.next };
+        if let Some(offset) = self.lines[start.min(end)..end].iter().position(|l| l.tool_change.is_some()) {
+            end = start + offset;
+        }
+        let start = if self.breakpoint_released { self.next + 1 } else { self.next };
+        if let Some(index) = (start..end).find(|index| self.breakpoint_before(*index).is_some()) {
+            end = index;
+        }
+        if end > self.next {
+            self.tool_change_released = false;
+            self.breakpoint_released = false;
+        }
+
+        let batch = self.lines[self.next..end].to_vec();
+        self.next = end;
+        batch
+    }
+
+    /// Tool number of the tool change the program is waiting on
+    ///
+    /// Only reported once every line before it has been acknowledged, so the
+    /// machine has stopped moving.
+    pub fn pending_tool_change(&self) -> Option<u32> {
+        if self.tool_change_released || self.acknowledged < self.next {
+            return None;
+        }
+        self.lines.get(self.next)?.tool_change
+    }
+
+    /// Continue streaming after a tool change
+    ///
+    /// A tool change line that only contained M6 has nothing left to send
+    /// and is completed immediately.
+    pub fn release_tool_change(&mut self) {
+        if self.pending_tool_change().is_none() {
+            return;
+        }
+        if self.lines[self.next].text.is_empty() {
+            self.next += 1;
+            self.acknowledged += 1;
+        } else {
+            self.tool_change_released = true;
+        }
+    }
+
+    /// Stop handing out lines until [`release_hold`](Self::release_hold)
+    pub fn request_hold(&mut self) {
+        self.hold_requested = true;
+    }
+
+    /// Whether a hold was requested and every line sent before it has been acknowledged
+    pub fn is_held(&self) -> bool {
+        self.hold_requested && self.acknowledged >= self.next
+    }
+
+    /// Continue streaming after a hold
+    pub fn release_hold(&mut self) {
+        self.hold_requested = false;
+    }
+
+    /// Stop streaming before the first line from a program line
+    pub fn stop_before_line(&mut self, line_index: usize) {
+        self.stop_at = self.lines.iter().position(|line| line.line_index == line_index);
+    }
+
+    /// Whether streaming reached the stop line and every line before it has been acknowledged
+    pub fn is_stopped(&self) -> bool {
+        self.stop_at == Some(self.next) && self.acknowledged >= self.next
+    }
+
+    /// Continue streaming past the stop line
+    pub fn clear_stop(&mut self) {
+        self.stop_at = None;
+    }
+
+    /// Continue streaming past the stop line, stopping again before the
+    /// next line from a program line
+    ///
+    /// A program line sent more than once, as in a loop, stops at its next
+    /// occurrence after the current stop.
+    pub fn advance_stop(&mut self, line_index: usize) {
+        let from = self.stop_at.map_or(self.next, |stop| stop + 1);
+        self.stop_at = self.lines[from.min(self.lines.len())..]
+            .iter()
+            .position(|line| line.line_index == line_index)
+            .map(|offset| from + offset);
+    }
+
+    /// Set the program lines streaming pauses before
+    pub fn set_breakpoints(&mut self, breakpoints: BTreeSet<usize>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// Pause before every program line
+    pub fn set_step_mode(&mut self, step_mode: bool) {
+        self.step_mode = step_mode;
+    }
+
+    /// Program line of the breakpoint streaming pauses at before a prepared line
+    ///
+    /// Only the first line prepared from a program line can break, so a
+    /// dwell added after a spindle start is sent with its line. A breakpoint
+    /// on a line with nothing to send, such as a comment, breaks before the
+    /// next line that is sent.
+    fn breakpoint_before(&self, index: usize) -> Option<usize> {
+        let line_index = self.lines[index].line_index;
+        let previous = index.checked_sub(1).map(|previous| self.lines[previous].line_index);
+        if previous == Some(line_index) {
+            return None;
+        }
+        let from = previous.map_or(0, |previous| previous + 1);
+        self.breakpoints
+            .range(from..=line_index)
+            .next()
+            .copied()
+            .or(self.step_mode.then_some(line_index))
+    }
+
+    /// Program line of the breakpoint streaming is paused at
+    ///
+    /// Only reported once every line before it has been acknowledged.
+    pub fn at_breakpoint(&self) -> Option<usize> {
+        if self.breakpoint_released || self.acknowledged < self.next || self.next >= self.lines.len() {
+            return None;
+        }
+        self.breakpoint_before(self.next)
+    }
+
+    /// Continue streaming past the breakpoint, up to the next one
+    pub fn release_breakpoint(&mut self) {
+        if self.at_breakpoint().is_some() {
+            self.breakpoint_released = true;
+        }
+    }
+
+    /// Program line of the last acknowledged line
+    pub fn last_acknowledged_line(&self) -> Option<usize> {
+        self.acknowledged.checked_sub(1).map(|index| self.lines[index].line_index)
+    }
+
+    /// Units, distance mode, motion mode and feed the sent lines left
+    pub fn sent_modes(&self) -> SentModes {
+        let mut modes = SentModes::default();
+        for line in &self.lines[..self.next] {
+            let text = &line.text;
+            if has_word(text, 'G', &[20.0]) {
+                modes.inches = true;
+            } else if has_word(text, 'G', &[21.0]) {
+                modes.inches = false;
+            }
+            if has_word(text, 'G', &[91.0]) {
+                modes.incremental = true;
+            } else if has_word(text, 'G', &[90.0]) {
+                modes.incremental = false;
+            }
+            if let Some(motion) = [0, 1, 2, 3].into_iter().find(|code| has_word(text, 'G', &[*code as f64])) {
+                modes.motion = Some(motion);
+            } else if has_word(text, 'G', MOTION_CODES) {
+                modes.motion = None;
+            }
+            if let Some(feed) = word_value(text, 'F') {
+                modes.feed = Some(feed);
+            }
+        }
+        modes
+    }
+
+    /// Give the next line moving by the modal motion mode an explicit motion word
+    ///
+    /// Commands sent between program lines, such as a tool check, leave
+    /// their own motion mode behind. Without the word, a line continuing an
+    /// arc would run as a straight move.
+    pub fn restore_motion(&mut self, motion: u32) {
+        let next = self.lines[self.next..].iter_mut().find(|line| {
+            has_word(&line.text, 'G', MOTION_CODES)
+                || ['X', 'Y', 'Z', 'A', 'B', 'C'].into_iter().any(|axis| signed_word_value(&line.text, axis).is_some())
+        });
+        if let Some(line) = next.filter(|line| !has_word(&line.text, 'G', MOTION_CODES)) {
+            line.text = format!("G{} {}", motion, line.text);
+        }
+    }
+
+    /// Command restarting the spindle as the acknowledged lines left it
+    ///
+    /// Returns e.g. "M3 S12000", or `None` if the spindle is off.
+    pub fn spindle_restart(&self) -> Option<String> {
+        spindle_command(&self.lines[..self.acknowledged])
+    }
+
+    /// Program line to resume an interrupted run from
+    ///
+    /// GRBL acknowledges a line once it is in the planner, so the last
+    /// `planner_blocks` acknowledged lines may not have run yet. The line
+    /// returned is the one before them, which had at least started.
+    pub fn safe_resume_line(&self, planner_blocks: usize) -> usize {
+        self.acknowledged
+            .checked_sub(planner_blocks + 1)
+            .map_or(0, |index| self.lines[index].line_index)
+    }
+
+    /// Start streaming at a program line, as when resuming an interrupted run
+    ///
+    /// The lines before it are replaced by a preamble restoring the state
+    /// they left: units, plane, work coordinate system, distance mode,
+    /// spindle, coolant and feed. The tool is raised to at least `safe_z`,
+    /// moved over the last position and fed down to it. Positions are only
+    /// followed in absolute mode; returns `false` if an incremental move
+    /// left the position unknown, so the tool stays at the safe height.
+    pub fn resume_from(&mut self, line_index: usize, safe_z: f64) -> bool {
+        let start = self
+            .lines
+            .iter()
+            .position(|line| line.line_index >= line_index)
+            .unwrap_or(self.lines.len());
+        let (preamble, positioned) = resume_preamble(&self.lines[..start], safe_z, self.spindle_dwell_ms);
+        let preamble: Vec<StreamLine> = preamble
+            .into_iter()
+            .map(|text| StreamLine {
+                line_index,
+                text,
+                tool_change: None,
+            })
+            .collect();
+        self.lines.splice(..start, preamble);
+        self.next = 0;
+        self.acknowledged = 0;
+        self.tool_change_released = false;
+        self.breakpoint_released = false;
+        positioned
+    }
+
+    /// Record an acknowledgment, returning the program line it completed
+    pub fn acknowledge(&mut self) -> Option<usize> {
+        if self.acknowledged >= self.next {
+            return None;
+        }
+
+        let line = self.lines[self.acknowledged].line_index;
+        self.acknowledged += 1;
+        Some(line)
+    }
+
+    /// Total number of lines to stream
+    pub fn total(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Number of lines handed to the command queue
+    pub fn sent(&self) -> usize {
+        self.next
+    }
+
+    /// Number of lines acknowledged by GRBL
+    pub fn acknowledged(&self) -> usize {
+        self.acknowledged
+    }
+
+    /// Whether every line has been acknowledged
+    pub fn is_complete(&self) -> bool {
+        self.acknowledged >= self.lines.len()
+    }
+}
+
+/// Value of the first word with the given letter in a prepared line
+pub(crate) fn word_value(text: &str, letter: char) -> Option<f64> {
+    let upper = text.to_ascii_uppercase();
+    let start = upper.find(letter)? + 1;
+    let number: String = upper[start..]
+        .chars()
+        .skip_while(|c| *c == ' ')
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number.parse().ok()
+}
+
+/// Value of the first word with the given letter, allowing a sign
+fn signed_word_value(text: &str, letter: char) -> Option<f64> {
+    let upper = text.to_ascii_uppercase();
+    let start = upper.find(letter)? + 1;
+    let number: String = upper[start..]
+        .chars()
+        .skip_while(|c| *c == ' ')
+        .enumerate()
+        .take_while(|(index, c)| c.is_ascii_digit() || *c == '.' || (*index == 0 && matches!(c, '-' | '+')))
+        .map(|(_, c)| c)
+        .collect();
+    number.parse().ok()
+}
+
+/// Spindle command as the lines left it, e.g. "M3 S12000"; `None` if off
+fn spindle_command(lines: &[StreamLine]) -> Option<String> {
+    let direction = lines.iter().rev().find_map(|line| {
+        [3.0, 4.0, 5.0]
+            .into_iter()
+            .find(|value| has_word(&line.text, 'M', &[*value]))
+    })?;
+    if direction == 5.0 {
+        return None;
+    }
+    match lines.iter().rev().find_map(|line| word_value(&line.text, 'S')) {
+        Some(speed) => Some(format!("M{} S{}", direction, speed)),
+        None => Some(format!("M{}", direction)),
+    }
+}
+
+/// Modal G-code groups restored when resuming: units, plane, work
+/// coordinate system and distance mode, with their power-on defaults
+const RESUME_GROUPS: [&[f64]; 4] = [
+    &[20.0, 21.0],
+    &[17.0, 18.0, 19.0],
+    &[54.0, 55.0, 56.0, 57.0, 58.0, 59.0],
+    &[90.0, 91.0],
+];
+
+/// Lines restoring the state left by `lines`, and whether the tool is
+/// moved back to the last position
+fn resume_preamble(lines: &[StreamLine], safe_z: f64, spindle_dwell_ms: u32) -> (Vec<String>, bool) {
+    let mut modes = [21.0, 17.0, 54.0, 90.0];
+    let mut motion = 0.0;
+    let mut feed = None;
+    let (mut mist, mut flood) = (false, false);
+    let mut position: [Option<f64>; 3] = [None; 3];
+    let mut lost = false;
+
+    for line in lines {
+        let text = &line.text;
+        for (group, mode) in RESUME_GROUPS.iter().zip(modes.iter_mut()) {
+            if let Some(value) = group.iter().find(|value| has_word(text, 'G', &[**value])) {
+                *mode = *value;
+            }
+        }
+        if let Some(value) = [0.0, 1.0, 2.0, 3.0].into_iter().find(|value| has_word(text, 'G', &[*value])) {
+            motion = value;
+        }
+        if let Some(value) = word_value(text, 'F') {
+            feed = Some(value);
+        }
+        mist = (mist || has_word(text, 'M', &[7.0])) && !has_word(text, 'M', &[9.0]);
+        flood = (flood || has_word(text, 'M', &[8.0])) && !has_word(text, 'M', &[9.0]);
+
+        // Machine-coordinate and reference moves do not end at a work position
+        if has_word(text, 'G', &[28.0, 30.0, 53.0, 92.0]) {
+            continue;
+        }
+        for (axis, letter) in ['X', 'Y', 'Z'].into_iter().enumerate() {
+            if let Some(value) = signed_word_value(text, letter) {
+                if modes[3] == 91.0 {
+                    lost = true;
+                }
+                position[axis] = Some(value);
+            }
+        }
+    }
+
+    let mut preamble = vec![format!("G{} G{} G{} G90", modes[0], modes[1], modes[2])];
+    let clearance = position[2].map_or(safe_z, |z| z.max(safe_z));
+    preamble.push(format!("G0 Z{:.3}", clearance));
+    let positioned = !lost;
+    if let (Some(x), Some(y), true) = (position[0], position[1], positioned) {
+        preamble.push(format!("G0 X{:.3} Y{:.3}", x, y));
+    }
+    if let Some(spindle) = spindle_command(lines) {
+        preamble.push(spindle);
+        if spindle_dwell_ms > 0 {
+            preamble.push(format!("G4 P{:.3}", spindle_dwell_ms as f64 / 1000.0));
+        }
+    }
+    if mist {
+        preamble.push("M7".to_string());
+    }
+    if flood {
+        preamble.push("M8".to_string());
+    }
+    match (position[2].filter(|_| positioned), feed) {
+        (Some(z), Some(feed)) => preamble.push(format!("G1 Z{:.3} F{}", z, feed)),
+        (Some(z), None) => preamble.push(format!("G0 Z{:.3}", z)),
+        (None, Some(feed)) => preamble.push(format!("F{}", feed)),
+        (None, None) => {}
+    }
+    if modes[3] == 91.0 {
+        preamble.push("G91".to_string());
+    }
+    if motion <= 1.0 {
+        preamble.push(format!("G{}", motion));
+    }
+    (preamble, positioned)
+}
+
+/// Whether a prepared line has a word with the given letter and one of the values
+fn has_word(text: &str, letter: char, values: &[f64]) -> bool {
+    let upper = text.to_ascii_uppercase();
+    upper.match_indices(letter).any(|(start, _)| {
+        let number: String = upper[start + 1..]
+            .chars()
+            .skip_while(|c| *c == ' ')
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        number.parse::<f64>().is_ok_and(|value| values.contains(&value))
+    })
+}
+
+/// Remove an M6 word from a prepared line, returning the rest
+///
+/// Returns `None` if the line has no M6. GRBL rejects M6, so it is never
+/// sent; any other words on the line (e.g. the T word) are kept.
+fn strip_tool_change(text: &str) -> Option<String> {
+    let upper = text.to_ascii_uppercase();
+    let bytes = upper.as_bytes();
+    let mut search = 0;
+    while let Some(found) = upper[search..].find('M') {
+        let start = search + found;
+        let mut end = start + 1;
+        while end < bytes.len() && bytes[end] == b'0' {
+            end += 1;
+        }
+        if end < bytes.len() && bytes[end] == b'6' && !bytes.get(end + 1).is_some_and(|b| b.is_ascii_digit() || *b == b'.') {
+            let rest = format!("{} {}", &text[..start], &text[end + 1..]);
+            return Some(rest.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        search = start + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_line() {
+        let options = StreamOptions::default();
+        assert_eq!(ProgramStreamer::prepare_line("G0 X10 (move)", options), Some("G0 X10".to_string()));
+        assert_eq!(ProgramStreamer::prepare_line("G1 X5 ; cut", options), Some("G1 X5".to_string()));
+        assert_eq!(ProgramStreamer::prepare_line("(comment only)", options), None);
+        assert_eq!(ProgramStreamer::prepare_line("%", options), None);
+        assert_eq!(ProgramStreamer::prepare_line("   ", options), None);
+    }
+
+    #[test]
+    fn test_block_delete_lines() {
+        let include = StreamOptions { skip_block_delete: false, ..Default::default() };
+        let skip = StreamOptions { skip_block_delete: true, ..Default::default() };
+
+        assert_eq!(ProgramStreamer::prepare_line("/G0 Z5", include), Some("G0 Z5".to_string()));
+        assert_eq!(ProgramStreamer::prepare_line("/G0 Z5", skip), None);
+
+        let streamer = ProgramStreamer::new("G0 X0\n/M8\nG1 X10", skip);
+        assert_eq!(streamer.total(), 2);
+        assert_eq!(streamer.lines()[1].line_index, 2);
+    }
+
+    #[test]
+    fn test_flow_control() {
+        let program = "G0 X0\nG0 X1\nG0 X2\nG0 X3\nG0 X4";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default()).with_max_in_flight(2);
+
+        let batch = streamer.next_lines();
+        assert_eq!(batch.len(), 2);
+        assert!(streamer.next_lines().is_empty());
+
+        assert_eq!(streamer.acknowledge(), Some(0));
+        let batch = streamer.next_lines();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].text, "G0 X2");
+
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.acknowledged(), 3);
+        assert!(!streamer.is_complete());
+
+        streamer.next_lines();
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_complete());
+        assert_eq!(streamer.acknowledge(), None);
+    }
+
+    #[test]
+    fn test_tool_change_hold() {
+        let options = StreamOptions { hold_at_tool_change: true, ..Default::default() };
+        let program = "T2\nG0 X1\nM6\nG0 X2\nT3 M06\nG0 X3";
+        let mut streamer = ProgramStreamer::new(program, options);
+        assert_eq!(streamer.total(), 6);
+
+        let batch = streamer.next_lines();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(streamer.pending_tool_change(), None);
+
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.pending_tool_change(), Some(2));
+        assert!(streamer.next_lines().is_empty());
+
+        // An M6-only line has nothing to send
+        streamer.release_tool_change();
+        assert_eq!(streamer.acknowledged(), 3);
+        let batch = streamer.next_lines();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].text, "G0 X2");
+
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.pending_tool_change(), Some(3));
+        streamer.release_tool_change();
+        assert_eq!(streamer.pending_tool_change(), None);
+        let batch = streamer.next_lines();
+        assert_eq!(batch[0].text, "T3");
+        assert_eq!(batch[1].text, "G0 X3");
+
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_complete());
+    }
+
+    #[test]
+    fn test_hold() {
+        let program = "M3 S12000\nG1 X1 F100\nM5\nM4\nG1 X2";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default()).with_max_in_flight(2);
+        assert_eq!(streamer.last_acknowledged_line(), None);
+
+        streamer.next_lines();
+        streamer.request_hold();
+        assert!(streamer.next_lines().is_empty());
+        assert!(!streamer.is_held());
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_held());
+        assert_eq!(streamer.last_acknowledged_line(), Some(1));
+        assert_eq!(streamer.spindle_restart(), Some("M3 S12000".to_string()));
+
+        streamer.release_hold();
+        assert_eq!(streamer.next_lines().len(), 2);
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.spindle_restart(), Some("M4 S12000".to_string()));
+    }
+
+    #[test]
+    fn test_sent_modes() {
+        let program = "G20 G91\nG2 X1 Y1 I0.5 F20\nM8\nX1 Y-1 I0.5\nG1 X1";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default()).with_max_in_flight(2);
+        assert_eq!(streamer.sent_modes(), SentModes::default());
+
+        streamer.next_lines();
+        assert_eq!(
+            streamer.sent_modes(),
+            SentModes { inches: true, incremental: true, motion: Some(2), feed: Some(20.0) }
+        );
+
+        // The arc continuing without a motion word gets one back
+        streamer.restore_motion(2);
+        let texts: Vec<&str> = streamer.lines().iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["G20 G91", "G2 X1 Y1 I0.5 F20", "M8", "G2 X1 Y-1 I0.5", "G1 X1"]);
+        streamer.restore_motion(2);
+        assert_eq!(streamer.lines()[3].text, "G2 X1 Y-1 I0.5");
+    }
+
+    #[test]
+    fn test_stop_before_line() {
+        let program = "G0 X0\n(comment)\nG1 X1 F100\nG1 X2\nG1 X3";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default());
+        streamer.stop_before_line(3);
+        assert_eq!(streamer.next_lines().len(), 2);
+        assert!(streamer.next_lines().is_empty());
+        assert!(!streamer.is_stopped());
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_stopped());
+
+        streamer.clear_stop();
+        assert_eq!(streamer.next_lines().len(), 2);
+        assert!(!streamer.is_stopped());
+    }
+
+    #[test]
+    fn test_advance_stop() {
+        let program = "G1 X1 F100
+G1 X2
+G1 X1
+G1 X2
+G1 X3";
+        let lines = program.lines().enumerate().chain([(1, "G1 X2"), (4, "G1 X3")]);
+        let mut streamer = ProgramStreamer::from_lines(lines, StreamOptions::default());
+        streamer.stop_before_line(1);
+        assert_eq!(streamer.next_lines().len(), 1);
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_stopped());
+
+        // The same program line again stops at its next occurrence
+        streamer.advance_stop(1);
+        assert_eq!(streamer.next_lines().len(), 4);
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_stopped());
+        streamer.advance_stop(4);
+        assert_eq!(streamer.next_lines().len(), 1);
+        while streamer.acknowledge().is_some() {}
+        assert!(streamer.is_stopped());
+        assert_eq!(streamer.next_lines().len(), 0);
+    }
+
+    #[test]
+    fn test_breakpoints_and_step_mode() {
+        let options = StreamOptions { spindle_dwell_ms: 1000, ..Default::default() };
+        let program = "G0 X0\nM3 S10000\nG1 X1 F100\nG1 X2\nG1 X3";
+        let mut streamer = ProgramStreamer::new(program, options);
+        streamer.set_breakpoints([1, 3].into());
+        assert_eq!(streamer.next_lines().len(), 1);
+        assert_eq!(streamer.at_breakpoint(), None);
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.at_breakpoint(), Some(1));
+        assert!(streamer.next_lines().is_empty());
+
+        // The dwell after M3 goes with its line
+        streamer.release_breakpoint();
+        assert_eq!(streamer.at_breakpoint(), None);
+        assert_eq!(streamer.next_lines().len(), 3);
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.at_breakpoint(), Some(3));
+
+        streamer.set_step_mode(true);
+        streamer.release_breakpoint();
+        assert_eq!(streamer.next_lines().len(), 1);
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.at_breakpoint(), Some(4));
+    }
+
+    #[test]
+    fn test_breakpoint_on_comment_line() {
+        let program = "G0 X0\n(CHECK clamps)\nG1 X1 F100";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default());
+        streamer.set_breakpoints([1].into());
+        assert_eq!(streamer.next_lines().len(), 1);
+        while streamer.acknowledge().is_some() {}
+        assert_eq!(streamer.at_breakpoint(), Some(1));
+        streamer.release_breakpoint();
+        assert_eq!(streamer.next_lines().len(), 1);
+    }
+
+    #[test]
+    fn test_spindle_dwell() {
+        let options = StreamOptions { spindle_dwell_ms: 2500, ..Default::default() };
+        let program = "M3 S12000\nG1 Z-1 F100\nM5\nM04 S8000 G4 P1\nM30";
+        let streamer = ProgramStreamer::new(program, options);
+        let texts: Vec<&str> = streamer.lines().iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["M3 S12000", "G4 P2.500", "G1 Z-1 F100", "M5", "M04 S8000 G4 P1", "M30"]);
+        assert_eq!(streamer.lines()[1].line_index, 0);
+
+        let streamer = ProgramStreamer::new(program, StreamOptions::default());
+        assert_eq!(streamer.total(), 5);
+    }
+
+    #[test]
+    fn test_resume_from() {
+        let program = "G21 G90 G55\nM3 S10000\nM8\nG0 X10 Y-5\nG1 Z-1.5 F300\nG1 X20\nG1 X30";
+        let mut streamer = ProgramStreamer::new(program, StreamOptions::default());
+        streamer.next_lines();
+        for _ in 0..5 {
+            streamer.acknowledge();
+        }
+        assert_eq!(streamer.safe_resume_line(2), 2);
+
+        assert!(streamer.resume_from(6, 5.0));
+        let texts: Vec<&str> = streamer.lines().iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["G21 G17 G55 G90", "G0 Z5.000", "G0 X20.000 Y-5.000", "M3 S10000", "M8", "G1 Z-1.500 F300", "G1", "G1 X30"]
+        );
+        assert_eq!(streamer.acknowledged(), 0);
+
+        let mut streamer = ProgramStreamer::new("G91\nG1 X5 F100\nG1 X5", StreamOptions::default());
+        assert!(!streamer.resume_from(2, 5.0));
+        assert_eq!(streamer.lines()[1].text, "G0 Z5.000");
+    }
+
+    #[test]
+    fn test_tool_change_not_held_by_default() {
+        let streamer = ProgramStreamer::new("T1 M6\nM62 P1", StreamOptions::default());
+        assert!(streamer.lines().iter().all(|l| l.tool_change.is_none()));
+        assert_eq!(strip_tool_change("M62 P1"), None);
+        assert_eq!(strip_tool_change("T1M6"), Some("T1".to_string()));
+    }
+}
@@ -0,0 +1,199 @@
+//! Surface flatness and tram check
+//!
+//! Fits a plane through the probed heights by least squares. The plane's
+//! slope is the tilt of the surface relative to the machine's XY travel;
+//! what is left once the tilt is removed is the unevenness of the surface
+//! itself.
+
+use super::Heightmap;
+
+/// A corner of the probed area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    /// Minimum X, minimum Y
+    FrontLeft,
+    /// Maximum X, minimum Y
+    FrontRight,
+    /// Minimum X, maximum Y
+    BackLeft,
+    /// Maximum X, maximum Y
+    BackRight,
+}
+
+impl Corner {
+    /// All corners
+    pub const ALL: [Corner; 4] = [Corner::FrontLeft, Corner::FrontRight, Corner::BackLeft, Corner::BackRight];
+}
+
+impl std::fmt::Display for Corner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Corner::FrontLeft => "Front left",
+            Corner::FrontRight => "Front right",
+            Corner::BackLeft => "Back left",
+            Corner::BackRight => "Back right",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Best-fit plane `z = offset + slope_x * x + slope_y * y`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// Height at X0 Y0
+    pub offset: f64,
+    /// Rise per unit of X
+    pub slope_x: f64,
+    /// Rise per unit of Y
+    pub slope_y: f64,
+}
+
+impl Plane {
+    /// Least-squares plane through the points, `None` if they are collinear
+    pub fn fit(points: impl Iterator<Item = [f64; 3]>) -> Option<Self> {
+        // Normal equations, centered on the mean for numerical stability
+        let points: Vec<[f64; 3]> = points.collect();
+        let n = points.len() as f64;
+        let mean = [0, 1, 2].map(|i| points.iter().map(|p| p[i]).sum::<f64>() / n);
+        let (mut sxx, mut sxy, mut syy, mut sxz, mut syz) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for p in &points {
+            let (x, y, z) = (p[0] - mean[0], p[1] - mean[1], p[2] - mean[2]);
+            sxx += x * x;
+            sxy += x * y;
+            syy += y * y;
+            sxz += x * z;
+            syz += y * z;
+        }
+        let det = sxx * syy - sxy * sxy;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let slope_x = (sxz * syy - syz * sxy) / det;
+        let slope_y = (syz * sxx - sxz * sxy) / det;
+        Some(Self {
+            offset: mean[2] - slope_x * mean[0] - slope_y * mean[1],
+            slope_x,
+            slope_y,
+        })
+    }
+
+    /// Height of the plane at a point
+    pub fn height(&self, x: f64, y: f64) -> f64 {
+        self.offset + self.slope_x * x + self.slope_y * y
+    }
+}
+
+/// Flatness metrics for a heightmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatnessReport {
+    /// Best-fit plane
+    pub plane: Plane,
+    /// Mean probed height
+    pub mean: f64,
+    /// Highest minus lowest probed height
+    pub range: f64,
+    /// Largest distance of a point from the mean height
+    pub max_deviation: f64,
+    /// Highest minus lowest point once the tilt is removed
+    pub flatness: f64,
+    /// Shim under each corner that would level the plane, raising the low corners
+    pub shims: [(Corner, f64); 4],
+}
+
+impl FlatnessReport {
+    /// Analyze a heightmap
+    pub fn new(map: &Heightmap) -> Option<Self> {
+        let plane = Plane::fit(map.points())?;
+        let heights: Vec<f64> = map.points().map(|p| p[2]).collect();
+        let mean = heights.iter().sum::<f64>() / heights.len() as f64;
+        let (low, high) = min_max(heights.iter().copied());
+        let residuals = map.points().map(|[x, y, z]| z - plane.height(x, y));
+        let (residual_low, residual_high) = min_max(residuals);
+
+        let corner_height = |corner| {
+            let (col, row) = corner_index(map, corner);
+            plane.height(map.x(col), map.y(row))
+        };
+        let top = Corner::ALL.iter().map(|c| corner_height(*c)).fold(f64::MIN, f64::max);
+        let shims = Corner::ALL.map(|corner| (corner, top - corner_height(corner)));
+
+        Some(Self {
+            plane,
+            mean,
+            range: high - low,
+            max_deviation: heights.iter().map(|z| (z - mean).abs()).fold(0.0, f64::max),
+            flatness: residual_high - residual_low,
+            shims,
+        })
+    }
+
+    /// Tilt along X and Y in degrees
+    pub fn tilt_degrees(&self) -> [f64; 2] {
+        [self.plane.slope_x.atan().to_degrees(), self.plane.slope_y.atan().to_degrees()]
+    }
+
+    /// Rise over 100 units of travel along X and Y
+    pub fn tilt_per_100(&self) -> [f64; 2] {
+        [self.plane.slope_x * 100.0, self.plane.slope_y * 100.0]
+    }
+}
+
+/// Grid indices of a corner
+pub fn corner_index(map: &Heightmap, corner: Corner) -> (usize, usize) {
+    let (right, back) = (map.cols() - 1, map.rows() - 1);
+    match corner {
+        Corner::FrontLeft => (0, 0),
+        Corner::FrontRight => (right, 0),
+        Corner::BackLeft => (0, back),
+        Corner::BackRight => (right, back),
+    }
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::MAX, f64::MIN), |(low, high), v| (low.min(v), high.max(v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tilted_map() -> Heightmap {
+        // Rises 0.1 per 100 in X, with a 0.02 bump in the middle
+        let xs = vec![0.0, 100.0, 200.0];
+        let ys = vec![0.0, 100.0, 200.0];
+        let mut z = Vec::new();
+        for y in &ys {
+            for x in &xs {
+                let bump = if *x == 100.0 && *y == 100.0 { 0.02 } else { 0.0 };
+                z.push(x * 0.001 + bump);
+            }
+        }
+        Heightmap::new(xs, ys, z).unwrap()
+    }
+
+    #[test]
+    fn test_plane_fit() {
+        let plane = Plane::fit([[0.0, 0.0, 1.0], [10.0, 0.0, 2.0], [0.0, 10.0, 0.5], [10.0, 10.0, 1.5]].into_iter())
+            .unwrap();
+        assert!((plane.slope_x - 0.1).abs() < 1e-9);
+        assert!((plane.slope_y + 0.05).abs() < 1e-9);
+        assert!((plane.height(0.0, 0.0) - 1.0).abs() < 1e-9);
+        assert_eq!(Plane::fit([[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0]].into_iter()), None);
+    }
+
+    #[test]
+    fn test_flatness_report() {
+        let report = FlatnessReport::new(&tilted_map()).unwrap();
+        assert!((report.range - 0.2).abs() < 1e-9);
+        let [x, y] = report.tilt_per_100();
+        assert!((x - 0.1).abs() < 1e-9);
+        assert!(y.abs() < 1e-9);
+        // The bump is what remains once the tilt is removed
+        assert!((report.flatness - 0.02).abs() < 1e-9);
+
+        // The left corners are low and need shimming by the tilt over 200 mm
+        let shim = |corner| report.shims.iter().find(|(c, _)| *c == corner).unwrap().1;
+        assert!((shim(Corner::FrontLeft) - 0.2).abs() < 1e-9);
+        assert!(shim(Corner::BackRight).abs() < 1e-9);
+    }
+}
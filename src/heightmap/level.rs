@@ -0,0 +1,319 @@
+//! Auto-leveling
+//!
+//! Adds the probed surface height under the tool to the Z of every move, so
+//! a cut follows a warped or tilted surface. Heights are looked up in work
+//! coordinates in millimeters; outside the probed area the nearest edge of
+//! the heightmap is used. Feed moves are split into pieces no longer than
+//! the segment length so the Z follows the surface between probe points;
+//! rapids and arcs only level their end point.
+//!
+//! Every leveled Z is checked against the lowest allowed Z. A bad probe
+//! point, such as a missed contact recorded far below the surface, would
+//! otherwise drive the tool into the spoilboard. Every line going below the
+//! limit is recorded so streaming can be refused or held before each one.
+//!
+//! Relative moves are sent as they are, and lines that move to machine or
+//! stored positions (G53, G28, G30, G38, G92, G10) make the tracked
+//! position unknown until the next absolute move.
+
+use super::Heightmap;
+use crate::parser::{format_coord, Token, Tokenizer};
+
+/// Tolerance below the lowest allowed Z before a move counts as a violation
+const TOLERANCE: f64 = 0.0001;
+
+/// A leveled move below the lowest allowed Z
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZLimitViolation {
+    /// Line the move came from
+    pub line: usize,
+    /// Position of the move in work coordinates (mm), Z as leveled
+    pub position: [f64; 3],
+    /// Height the heightmap added to the programmed Z (mm)
+    pub correction: f64,
+    /// Probe point closest to the move
+    pub probe_point: [f64; 3],
+    /// Lowest allowed Z (mm)
+    pub lowest_z: f64,
+}
+
+impl std::fmt::Display for ZLimitViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y, z] = self.position;
+        let [px, py, pz] = self.probe_point;
+        write!(
+            f,
+            "Line {} would move to Z{:.3} at X{:.3} Y{:.3}, below the lowest allowed Z{:.3}. \
+             The heightmap lowers it by {:.3} mm; check the probe point at X{:.3} Y{:.3} (Z{:.3}).",
+            self.line + 1,
+            z,
+            x,
+            y,
+            self.lowest_z,
+            -self.correction,
+            px,
+            py,
+            pz
+        )
+    }
+}
+
+/// Levels program lines to a heightmap, tracking modal state across lines
+#[derive(Debug, Clone)]
+pub struct AutoLeveler {
+    map: Heightmap,
+    /// Longest leveled piece of a feed move (mm)
+    max_segment: f64,
+    /// Lowest leveled Z allowed (mm)
+    lowest_z: f64,
+    /// Absolute distance mode (G90)
+    absolute: bool,
+    /// Metric units (G21)
+    metric: bool,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Programmed position (program units), once known
+    position: [Option<f64>; 3],
+    /// First move below the lowest allowed Z of each line, in order
+    violations: Vec<ZLimitViolation>,
+    /// Whether the line being leveled already has a violation recorded
+    line_violated: bool,
+}
+
+impl AutoLeveler {
+    /// Create a leveler splitting feed moves into `max_segment` mm pieces
+    pub fn new(map: Heightmap, max_segment: f64, lowest_z: f64) -> Self {
+        Self {
+            map,
+            max_segment: max_segment.max(0.1),
+            lowest_z,
+            absolute: true,
+            metric: true,
+            motion: None,
+            position: [None; 3],
+            violations: Vec::new(),
+            line_violated: false,
+        }
+    }
+
+    /// Lines found going below the lowest allowed Z, in program order
+    ///
+    /// A line repeated by a subprogram or loop is recorded each time it is leveled.
+    pub fn violations(&self) -> &[ZLimitViolation] {
+        &self.violations
+    }
+
+    /// Level a whole program
+    ///
+    /// Violations refer to lines of the leveled program.
+    pub fn apply(&mut self, program: &str) -> String {
+        let mut output: Vec<String> = Vec::new();
+        for line in program.lines() {
+            let index = output.len();
+            output.extend(self.process_line(line, index));
+        }
+        output.join("\n")
+    }
+
+    /// Level one line, returning the lines to send in its place
+    ///
+    /// `index` is recorded as the line of a violation found on this line.
+    pub fn process_line(&mut self, line: &str, index: usize) -> Vec<String> {
+        self.line_violated = false;
+        let unchanged = vec![line.to_string()];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('$') || trimmed.starts_with('%') {
+            return unchanged;
+        }
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        let mut passthrough = false;
+        let mut feed = None;
+        let mut targets = [None; 3];
+        for token in &tokens {
+            match token {
+                Token::GCommand(code) => match code {
+                    0..=3 => self.motion = Some(*code),
+                    20 => self.metric = false,
+                    21 => self.metric = true,
+                    90 => self.absolute = true,
+                    91 => self.absolute = false,
+                    10 | 28 | 30 | 38 | 53 | 92 => passthrough = true,
+                    80 => self.motion = None,
+                    _ => {}
+                },
+                Token::FCommand(value) => feed = Some(*value),
+                Token::Parameter { letter, value } => match letter.to_ascii_uppercase() {
+                    'X' => targets[0] = Some(*value),
+                    'Y' => targets[1] = Some(*value),
+                    'Z' => targets[2] = Some(*value),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if targets.iter().all(Option::is_none) {
+            return unchanged;
+        }
+        if passthrough || self.motion.is_none() {
+            // The machine ends up somewhere this pass can't follow
+            self.position = [None; 3];
+            return unchanged;
+        }
+        let start = self.position;
+        for axis in 0..3 {
+            if let Some(value) = targets[axis] {
+                self.position[axis] = if self.absolute {
+                    Some(value)
+                } else {
+                    start[axis].map(|p| p + value)
+                };
+            }
+        }
+        if !self.absolute {
+            return unchanged;
+        }
+        let [Some(x), Some(y), Some(z)] = self.position else {
+            return unchanged;
+        };
+
+        let scale = if self.metric { 1.0 } else { 25.4 };
+        let mut lines = Vec::new();
+        if let (Some(1), [Some(x0), Some(y0), Some(z0)]) = (self.motion, start) {
+            let length = (x - x0).hypot(y - y0) * scale;
+            let pieces = (length / self.max_segment).ceil() as usize;
+            for piece in 1..pieces {
+                let t = piece as f64 / pieces as f64;
+                let point = [x0 + (x - x0) * t, y0 + (y - y0) * t, z0 + (z - z0) * t];
+                let leveled = self.level(point, scale, index);
+                let mut text = format!(
+                    "G1 X{} Y{} Z{}",
+                    format_coord(point[0]),
+                    format_coord(point[1]),
+                    format_coord(leveled)
+                );
+                if let Some(feed) = feed.filter(|_| piece == 1) {
+                    text.push_str(&format!(" F{}", format_coord(feed)));
+                }
+                lines.push(text);
+            }
+        }
+        let leveled = self.level([x, y, z], scale, index);
+        lines.push(set_z_word(line, &format!("Z{}", format_coord(leveled))));
+        lines
+    }
+
+    /// Leveled Z of a programmed point, recording the line's first violation
+    fn level(&mut self, point: [f64; 3], scale: f64, index: usize) -> f64 {
+        let [x, y, z] = point.map(|value| value * scale);
+        let correction = self.map.interpolate_clamped(x, y);
+        let leveled = z + correction;
+        if leveled < self.lowest_z - TOLERANCE && !self.line_violated {
+            self.line_violated = true;
+            self.violations.push(ZLimitViolation {
+                line: index,
+                position: [x, y, leveled],
+                correction,
+                probe_point: self.map.nearest_point(x, y),
+                lowest_z: self.lowest_z,
+            });
+        }
+        leveled / scale
+    }
+}
+
+/// Set the Z word of a line outside comments, adding it if there is none
+fn set_z_word(line: &str, word: &str) -> String {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut in_comment = false;
+    let mut comment_start = None;
+    for (i, &(start, ch)) in chars.iter().enumerate() {
+        match ch {
+            '(' if !in_comment => {
+                in_comment = true;
+                comment_start.get_or_insert(start);
+            }
+            ')' if in_comment => in_comment = false,
+            ';' if !in_comment => {
+                comment_start.get_or_insert(start);
+                break;
+            }
+            'Z' | 'z' if !in_comment => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+                    .map_or(line.len(), |&(index, _)| index);
+                return format!("{}{}{}", &line[..start], word, &line[end..]);
+            }
+            _ => {}
+        }
+    }
+    match comment_start {
+        Some(start) => format!("{} {} {}", line[..start].trim_end(), word, &line[start..]),
+        None => format!("{} {}", line.trim_end(), word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Surface rising 0.1 mm per 10 mm of X
+    fn map() -> Heightmap {
+        Heightmap::parse("0,0,0\n10,0,0.1\n20,0,0.2\n0,10,0\n10,10,0.1\n20,10,0.2").unwrap()
+    }
+
+    #[test]
+    fn test_level_moves() {
+        let mut leveler = AutoLeveler::new(map(), 5.0, -1.0);
+        let program = "G21 G90\nG0 X0 Y0 Z1\nG1 Z-0.5 F100 (plunge)\nG1 X10\nG2 X20 Y0 I5 J0";
+        assert_eq!(
+            leveler.apply(program),
+            "G21 G90\nG0 X0 Y0 Z1\nG1 Z-0.5 F100 (plunge)\nG1 X5 Y0 Z-0.45\nG1 X10 Z-0.4\nG2 X20 Y0 I5 J0 Z-0.3"
+        );
+        assert!(leveler.violations().is_empty());
+    }
+
+    #[test]
+    fn test_relative_moves_pass_through() {
+        let mut leveler = AutoLeveler::new(map(), 5.0, -1.0);
+        let program = "G0 X10 Y0 Z0\nG91 G1 X10 F100\nG90 G1 X0";
+        assert_eq!(
+            leveler.apply(program),
+            "G0 X10 Y0 Z0.1\nG91 G1 X10 F100\nG1 X15 Y0 Z0.15\nG1 X10 Y0 Z0.1\nG1 X5 Y0 Z0.05\nG90 G1 X0 Z0"
+        );
+    }
+
+    #[test]
+    fn test_z_limit_violation() {
+        // A missed contact recorded 3 mm low at X10 Y10
+        let bad = Heightmap::parse("0,0,0\n10,0,0\n0,10,0\n10,10,-3").unwrap();
+        let mut leveler = AutoLeveler::new(bad, 100.0, -1.0);
+        leveler.apply("G0 X0 Y0 Z1\nG1 Z-0.2 F100\nG1 X2 Y2\nG1 X9 Y9");
+
+        let violation = &leveler.violations()[0];
+        assert_eq!(violation.line, 3);
+        assert!((violation.position[2] - (-0.2 - 3.0 * 0.81)).abs() < 1e-9);
+        assert_eq!(violation.probe_point, [10.0, 10.0, -3.0]);
+        assert!(violation.to_string().starts_with("Line 4 would move to Z-2.630"));
+    }
+
+    #[test]
+    fn test_every_violation_recorded() {
+        let bad = Heightmap::parse("0,0,0\n10,0,0\n0,10,0\n10,10,-3").unwrap();
+        let mut leveler = AutoLeveler::new(bad, 2.0, -1.0);
+        leveler.apply("G0 X0 Y0 Z1\nG1 Z-0.2 F100\nG1 X9 Y9\nG1 X0 Y0\nG1 X10 Y10 (again)");
+
+        // One per line, though the split moves go below the limit several times
+        let lines: Vec<usize> = leveler.violations().iter().map(|violation| violation.line).collect();
+        assert_eq!(lines, [2, 9, 16]);
+    }
+
+    #[test]
+    fn test_set_z_word() {
+        assert_eq!(set_z_word("G1 X1 Z-1 F100", "Z-0.9"), "G1 X1 Z-0.9 F100");
+        assert_eq!(set_z_word("G1 X1 (to Z0) ; done", "Z0.1"), "G1 X1 Z0.1 (to Z0) ; done");
+        assert_eq!(set_z_word("X2", "Z0"), "X2 Z0");
+    }
+}
@@ -0,0 +1,183 @@
+//! Heightmap grid
+//!
+//! A heightmap is a rectangular grid of probed Z heights. Files list one
+//! probe point per line as `X,Y,Z` (commas, semicolons, tabs or spaces may
+//! separate the values); blank lines, `#` comments and header lines are
+//! skipped. The points must cover every combination of the X and Y values.
+
+use crate::utils::error::{Error, Result};
+
+/// Grid of probed heights
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightmap {
+    /// Column X positions, ascending
+    xs: Vec<f64>,
+    /// Row Y positions, ascending
+    ys: Vec<f64>,
+    /// Heights, row by row
+    z: Vec<f64>,
+}
+
+impl Heightmap {
+    /// Build a heightmap from grid positions and heights given row by row
+    pub fn new(xs: Vec<f64>, ys: Vec<f64>, z: Vec<f64>) -> Result<Self> {
+        if xs.len() < 2 || ys.len() < 2 {
+            return Err(Error::parse("Heightmap needs at least 2x2 points"));
+        }
+        if z.len() != xs.len() * ys.len() {
+            return Err(Error::parse(format!(
+                "Heightmap has {} heights for a {}x{} grid",
+                z.len(),
+                xs.len(),
+                ys.len()
+            )));
+        }
+        Ok(Self { xs, ys, z })
+    }
+
+    /// Parse a list of `X,Y,Z` probe points
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut points = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let values: Vec<f64> = line
+                .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map_while(|v| v.parse().ok())
+                .collect();
+            if values.len() == 3 {
+                points.push([values[0], values[1], values[2]]);
+            }
+        }
+        if points.is_empty() {
+            return Err(Error::parse("No X,Y,Z points found in heightmap"));
+        }
+
+        let axis = |index: usize| {
+            let mut values: Vec<f64> = points.iter().map(|p| p[index]).collect();
+            values.sort_by(f64::total_cmp);
+            values.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+            values
+        };
+        let xs = axis(0);
+        let ys = axis(1);
+
+        let mut z = vec![None; xs.len() * ys.len()];
+        for [x, y, height] in points {
+            let col = xs.iter().position(|v| (v - x).abs() < 1e-6).unwrap_or_default();
+            let row = ys.iter().position(|v| (v - y).abs() < 1e-6).unwrap_or_default();
+            z[row * xs.len() + col] = Some(height);
+        }
+        if let Some(missing) = z.iter().position(Option::is_none) {
+            return Err(Error::parse(format!(
+                "Heightmap is missing the point at X{} Y{}",
+                xs[missing % xs.len()],
+                ys[missing / xs.len()]
+            )));
+        }
+        Self::new(xs, ys, z.into_iter().flatten().collect())
+    }
+
+    /// Number of columns (X positions)
+    pub fn cols(&self) -> usize {
+        self.xs.len()
+    }
+
+    /// Number of rows (Y positions)
+    pub fn rows(&self) -> usize {
+        self.ys.len()
+    }
+
+    /// X position of a column
+    pub fn x(&self, col: usize) -> f64 {
+        self.xs[col]
+    }
+
+    /// Y position of a row
+    pub fn y(&self, row: usize) -> f64 {
+        self.ys[row]
+    }
+
+    /// Height at a grid point
+    pub fn height(&self, col: usize, row: usize) -> f64 {
+        self.z[row * self.xs.len() + col]
+    }
+
+    /// Every grid point as `[x, y, z]`
+    pub fn points(&self) -> impl Iterator<Item = [f64; 3]> + '_ {
+        (0..self.rows()).flat_map(move |row| {
+            (0..self.cols()).map(move |col| [self.xs[col], self.ys[row], self.height(col, row)])
+        })
+    }
+
+    /// Bilinearly interpolated height, or `None` outside the probed area
+    pub fn interpolate(&self, x: f64, y: f64) -> Option<f64> {
+        let col = segment(&self.xs, x)?;
+        let row = segment(&self.ys, y)?;
+        let tx = (x - self.xs[col]) / (self.xs[col + 1] - self.xs[col]);
+        let ty = (y - self.ys[row]) / (self.ys[row + 1] - self.ys[row]);
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let front = lerp(self.height(col, row), self.height(col + 1, row), tx);
+        let back = lerp(self.height(col, row + 1), self.height(col + 1, row + 1), tx);
+        Some(lerp(front, back, ty))
+    }
+
+    /// Interpolated height, using the nearest edge of the probed area outside it
+    pub fn interpolate_clamped(&self, x: f64, y: f64) -> f64 {
+        let x = x.clamp(self.xs[0], self.xs[self.xs.len() - 1]);
+        let y = y.clamp(self.ys[0], self.ys[self.ys.len() - 1]);
+        self.interpolate(x, y).unwrap_or_default()
+    }
+
+    /// Probe point closest to a position in XY
+    pub fn nearest_point(&self, x: f64, y: f64) -> [f64; 3] {
+        let nearest = |values: &[f64], value: f64| {
+            (0..values.len())
+                .min_by(|a, b| (values[*a] - value).abs().total_cmp(&(values[*b] - value).abs()))
+                .unwrap_or_default()
+        };
+        let (col, row) = (nearest(&self.xs, x), nearest(&self.ys, y));
+        [self.xs[col], self.ys[row], self.height(col, row)]
+    }
+}
+
+/// Index of the grid interval containing `value`
+fn segment(values: &[f64], value: f64) -> Option<usize> {
+    let last = values.len() - 1;
+    if value < values[0] || value > values[last] {
+        return None;
+    }
+    Some(values.iter().rposition(|v| *v <= value).unwrap_or(0).min(last - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "# x,y,z\nX,Y,Z\n0,0,0.0\n10,0,0.1\n0,10,0.2\n10,10,0.3\n";
+
+    #[test]
+    fn test_parse_points() {
+        let map = Heightmap::parse(MAP).unwrap();
+        assert_eq!((map.cols(), map.rows()), (2, 2));
+        assert_eq!(map.height(1, 0), 0.1);
+        assert_eq!(map.height(0, 1), 0.2);
+        assert_eq!(map.points().count(), 4);
+    }
+
+    #[test]
+    fn test_missing_point_is_rejected() {
+        let err = Heightmap::parse("0 0 0\n10 0 0\n0 10 0\n").unwrap_err();
+        assert!(err.to_string().contains("X10 Y10"));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let map = Heightmap::parse(MAP).unwrap();
+        assert!((map.interpolate(5.0, 5.0).unwrap() - 0.15).abs() < 1e-9);
+        assert_eq!(map.interpolate(10.0, 10.0), Some(0.3));
+        assert_eq!(map.interpolate(11.0, 5.0), None);
+        assert!((map.interpolate_clamped(11.0, 5.0) - 0.2).abs() < 1e-9);
+        assert_eq!(map.nearest_point(8.0, -3.0), [10.0, 0.0, 0.1]);
+    }
+}
@@ -1,5 +1,12 @@
 //! Heightmap module
 //!
-//! TODO: Add module documentation
+//! Probed surface heights and the analysis built on them, such as the
+//! flatness and tram check, and auto-leveling programs to the surface.
 
-#![allow(dead_code)] // Remove after implementation
+mod flatness;
+mod level;
+mod map;
+
+pub use flatness::{corner_index, Corner, FlatnessReport, Plane};
+pub use level::{AutoLeveler, ZLimitViolation};
+pub use map::Heightmap;
@@ -1,10 +1,13 @@
 //! rCandle core library
 //!
 //! This library provides the core functionality for the rCandle GRBL controller.
+//! [`GrblClient`] drives a controller from code without the UI.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod cli;
+pub mod client;
 pub mod connection;
 pub mod grbl;
 pub mod heightmap;
@@ -18,6 +21,7 @@ pub mod utils;
 
 // Re-export commonly used types
 pub use utils::error::{Error, Result};
+pub use client::GrblClient;
 
 /// Application version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -2,16 +2,22 @@
 //!
 //! A Rust-based GRBL controller with G-Code visualization.
 
+use clap::Parser;
 use rcandle::{
+    cli::Cli,
+    settings::LowPowerMode,
     ui::RCandleApp,
-    utils::init_logging,
+    utils::{crash, init_logging},
 };
 
 fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     // Initialize logging
     let log_dir = directories::ProjectDirs::from("", "", "rCandle")
         .map(|d| d.data_dir().join("logs"));
-    init_logging(log_dir)?;
+    init_logging(log_dir.clone())?;
+    crash::install(log_dir, crash::default_report_dir());
 
     tracing::info!("rCandle v{} starting...", rcandle::VERSION);
 
@@ -19,16 +25,36 @@ fn main() -> anyhow::Result<()> {
     let runtime = tokio::runtime::Runtime::new()?;
     let _guard = runtime.enter();
 
+    // VSync is fixed when the surface is created; low-power mode always uses it to cap the frame rate
+    let settings = cli.load_settings();
+    let vsync = settings.visualization.vsync || settings.visualization.low_power == LowPowerMode::On;
+    let present_mode = if vsync {
+        eframe::wgpu::PresentMode::AutoVsync
+    } else {
+        eframe::wgpu::PresentMode::AutoNoVsync
+    };
+
     // Configure and run the egui application
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_title(&format!("rCandle v{} - GRBL Controller", rcandle::VERSION))
+        .with_inner_size([settings.ui.window_width as f32, settings.ui.window_height as f32])
+        .with_maximized(settings.ui.window_maximized);
+    if let Some(position) = settings.ui.restored_position() {
+        viewport = viewport.with_position(position);
+    }
     let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_title(&format!("rCandle v{} - GRBL Controller", rcandle::VERSION))
-            .with_inner_size([1280.0, 800.0])
+        viewport: viewport
             .with_min_inner_size([800.0, 600.0])
             .with_active(true)
             .with_visible(true)
             .with_decorations(true)
-            .with_resizable(true),
+            .with_resizable(true)
+            .with_fullscreen(cli.fullscreen),
+        vsync,
+        wgpu_options: eframe::egui_wgpu::WgpuConfiguration {
+            present_mode,
+            ..Default::default()
+        },
         ..Default::default()
     };
 
@@ -39,7 +65,7 @@ fn main() -> anyhow::Result<()> {
     eframe::run_native(
         "rCandle",
         native_options,
-        Box::new(|cc| Ok(Box::new(RCandleApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(RCandleApp::new(cc, cli)))),
     ).map_err(|e| anyhow::anyhow!("Failed to run eframe: {}", e))?;
 
     tracing::info!("rCandle shutting down");
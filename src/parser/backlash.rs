@@ -0,0 +1,228 @@
+//! Backlash compensation
+//!
+//! Machines with slack in their lead screws or belts lose a fixed distance
+//! each time an axis reverses. This pass shifts the X/Y/Z words of linear
+//! moves so that moves in the negative direction travel the extra slack,
+//! which keeps the carriage where the program expects it.
+//!
+//! Arcs keep the offsets of the move before them so their geometry stays
+//! intact. Lines that move to machine or stored positions (G53, G28, G30,
+//! G38, G92, G10) are passed through and the tracked position is forgotten.
+
+use super::multipass::format_coord;
+use super::tokenizer::{Token, Tokenizer};
+
+/// Rewrites program lines to take up backlash on direction reversals
+#[derive(Debug, Clone)]
+pub struct BacklashCompensator {
+    /// Backlash per axis (mm)
+    backlash: [f64; 3],
+    /// Absolute distance mode (G90)
+    absolute: bool,
+    /// Metric units (G21)
+    metric: bool,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Programmed position, once known
+    position: [Option<f64>; 3],
+    /// Offset currently added to each axis (program units)
+    offset: [f64; 3],
+}
+
+impl BacklashCompensator {
+    /// Create a compensator for the backlash of each axis in millimeters
+    pub fn new(backlash: [f64; 3]) -> Self {
+        Self {
+            backlash: backlash.map(|b| b.max(0.0)),
+            absolute: true,
+            metric: true,
+            motion: None,
+            position: [None; 3],
+            offset: [0.0; 3],
+        }
+    }
+
+    /// Compensate a whole program
+    pub fn apply(&mut self, program: &str) -> String {
+        program
+            .lines()
+            .map(|line| self.compensate_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Compensate one line, tracking modal state across calls
+    pub fn compensate_line(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('$') || trimmed.starts_with('%') {
+            return line.to_string();
+        }
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        let mut targets = [None; 3];
+        let mut passthrough = false;
+        for token in &tokens {
+            match token {
+                Token::GCommand(code) => match code {
+                    0..=3 => self.motion = Some(*code),
+                    20 => self.metric = false,
+                    21 => self.metric = true,
+                    90 => self.absolute = true,
+                    91 => self.absolute = false,
+                    10 | 28 | 30 | 38 | 53 | 92 => passthrough = true,
+                    80 => self.motion = None,
+                    _ => {}
+                },
+                Token::Parameter { letter, value } => {
+                    if let Some(axis) = axis_index(*letter) {
+                        targets[axis] = Some(*value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if targets.iter().all(Option::is_none) {
+            return line.to_string();
+        }
+        if passthrough || self.motion.is_none() {
+            // The machine ends up somewhere this pass can't follow
+            for (axis, target) in targets.iter().enumerate() {
+                if target.is_some() {
+                    self.position[axis] = None;
+                    self.offset[axis] = 0.0;
+                }
+            }
+            return line.to_string();
+        }
+
+        let linear = matches!(self.motion, Some(0 | 1));
+        let scale = if self.metric { 1.0 } else { 1.0 / 25.4 };
+        let mut words = [None; 3];
+        for (axis, value) in targets.iter().enumerate() {
+            let Some(value) = *value else {
+                continue;
+            };
+            let (delta, target) = if self.absolute {
+                (self.position[axis].map(|p| value - p), Some(value))
+            } else {
+                (Some(value), self.position[axis].map(|p| p + value))
+            };
+
+            let previous_offset = self.offset[axis];
+            match delta {
+                Some(delta) if linear && delta < 0.0 => self.offset[axis] = -self.backlash[axis] * scale,
+                Some(delta) if linear && delta > 0.0 => self.offset[axis] = 0.0,
+                _ => {}
+            }
+
+            let compensated = if self.absolute {
+                value + self.offset[axis]
+            } else {
+                value + self.offset[axis] - previous_offset
+            };
+            if (compensated - value).abs() > f64::EPSILON {
+                words[axis] = Some(compensated);
+            }
+            self.position[axis] = target;
+        }
+
+        replace_axis_words(line, words)
+    }
+}
+
+/// Index of an axis letter
+fn axis_index(letter: char) -> Option<usize> {
+    match letter.to_ascii_uppercase() {
+        'X' => Some(0),
+        'Y' => Some(1),
+        'Z' => Some(2),
+        _ => None,
+    }
+}
+
+/// Replace the values of X/Y/Z words on a line, leaving comments untouched
+fn replace_axis_words(line: &str, values: [Option<f64>; 3]) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len() + 8);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            ';' => {
+                result.extend(&chars[i..]);
+                break;
+            }
+            '(' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ')')
+                    .map(|p| i + p + 1)
+                    .unwrap_or(chars.len());
+                result.extend(&chars[i..end]);
+                i = end;
+            }
+            c if axis_index(c).and_then(|axis| values[axis]).is_some() => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len()
+                    && (chars[end].is_ascii_digit() || matches!(chars[end], '.' | '-' | '+'))
+                {
+                    end += 1;
+                }
+                let value = axis_index(c).and_then(|axis| values[axis]).unwrap_or_default();
+                result.push(c);
+                result.push_str(&format_coord(value));
+                i = end;
+            }
+            _ => {
+                result.push(ch);
+                i += 1;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reversal_takes_up_backlash() {
+        let mut compensator = BacklashCompensator::new([0.1, 0.0, 0.0]);
+        let program = "G21 G90\nG0 X10\nG1 X20 F500\nG1 X5 (back)\nG1 X8\nG2 X6 Y2 I-1 J1";
+        let expected = "G21 G90\nG0 X10\nG1 X20 F500\nG1 X4.9 (back)\nG1 X8\nG2 X6 Y2 I-1 J1";
+        assert_eq!(compensator.apply(program), expected);
+    }
+
+    #[test]
+    fn test_relative_moves() {
+        let mut compensator = BacklashCompensator::new([0.0, 0.2, 0.0]);
+        let program = "G91\nG1 Y5\nG1 Y-5\nG1 Y-1\nG1 Y3";
+        assert_eq!(compensator.apply(program), "G91\nG1 Y5\nG1 Y-5.2\nG1 Y-1\nG1 Y3.2");
+    }
+
+    #[test]
+    fn test_arcs_keep_offset() {
+        let mut compensator = BacklashCompensator::new([0.1, 0.0, 0.0]);
+        let program = "G1 X10\nG1 X5\nG2 X3 Y2 R2\nG1 X4";
+        assert_eq!(compensator.apply(program), "G1 X10\nG1 X4.9\nG2 X2.9 Y2 R2\nG1 X4");
+    }
+
+    #[test]
+    fn test_machine_moves_reset_tracking() {
+        let mut compensator = BacklashCompensator::new([0.0, 0.0, 0.05]);
+        let program = "G0 Z5\nG1 Z-1\nG53 G0 Z0\nG1 Z-2";
+        assert_eq!(compensator.apply(program), "G0 Z5\nG1 Z-1.05\nG53 G0 Z0\nG1 Z-2");
+    }
+
+    #[test]
+    fn test_imperial_units() {
+        let mut compensator = BacklashCompensator::new([0.254, 0.0, 0.0]);
+        let program = "G20\nG1 X1\nG1 X0.5";
+        assert_eq!(compensator.apply(program), "G20\nG1 X1\nG1 X0.49");
+    }
+}
@@ -0,0 +1,264 @@
+//! G-Code flavor compatibility
+//!
+//! Programs written for 3D printers (Marlin) or LinuxCNC often contain
+//! words GRBL rejects with `error:20`. The compatibility report names each
+//! such word, the flavor it comes from and what it does, so the words can
+//! be stripped from the program or skipped while streaming. Stripping a
+//! word also removes the parameters that belong to it, and drops the line
+//! if nothing else is left on it.
+
+use std::ops::Range;
+
+/// Firmware a word is specific to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Marlin and other 3D printer firmware
+    Marlin,
+    /// LinuxCNC
+    LinuxCnc,
+}
+
+impl std::fmt::Display for Flavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Flavor::Marlin => write!(f, "Marlin"),
+            Flavor::LinuxCnc => write!(f, "LinuxCNC"),
+        }
+    }
+}
+
+/// A word GRBL does not support
+struct KnownWord {
+    /// Word such as "M104", or a bare letter matching any value
+    word: &'static str,
+    flavor: Flavor,
+    description: &'static str,
+    /// Parameter letters removed together with the word
+    params: &'static [char],
+}
+
+const KNOWN_WORDS: &[KnownWord] = &[
+    KnownWord { word: "M104", flavor: Flavor::Marlin, description: "Set hotend temperature", params: &['S', 'T', 'R'] },
+    KnownWord { word: "M109", flavor: Flavor::Marlin, description: "Wait for hotend temperature", params: &['S', 'T', 'R'] },
+    KnownWord { word: "M140", flavor: Flavor::Marlin, description: "Set bed temperature", params: &['S'] },
+    KnownWord { word: "M190", flavor: Flavor::Marlin, description: "Wait for bed temperature", params: &['S', 'R'] },
+    KnownWord { word: "M106", flavor: Flavor::Marlin, description: "Part cooling fan on", params: &['S', 'P'] },
+    KnownWord { word: "M107", flavor: Flavor::Marlin, description: "Part cooling fan off", params: &['P'] },
+    KnownWord { word: "M82", flavor: Flavor::Marlin, description: "Absolute extrusion", params: &[] },
+    KnownWord { word: "M83", flavor: Flavor::Marlin, description: "Relative extrusion", params: &[] },
+    KnownWord { word: "M84", flavor: Flavor::Marlin, description: "Disable steppers", params: &['S'] },
+    KnownWord { word: "G29", flavor: Flavor::Marlin, description: "Automatic bed leveling", params: &[] },
+    KnownWord { word: "E", flavor: Flavor::Marlin, description: "Extruder axis position", params: &[] },
+    KnownWord { word: "G64", flavor: Flavor::LinuxCnc, description: "Path blending tolerance", params: &['P', 'Q'] },
+    KnownWord { word: "G61.1", flavor: Flavor::LinuxCnc, description: "Exact stop mode", params: &[] },
+    KnownWord { word: "G41", flavor: Flavor::LinuxCnc, description: "Cutter compensation left, the path is cut uncompensated", params: &['D'] },
+    KnownWord { word: "G42", flavor: Flavor::LinuxCnc, description: "Cutter compensation right, the path is cut uncompensated", params: &['D'] },
+    KnownWord { word: "G43", flavor: Flavor::LinuxCnc, description: "Tool table length offset, GRBL only has G43.1", params: &['H'] },
+];
+
+/// An unsupported word found in a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityIssue {
+    /// Line of the program (0-based)
+    pub line: usize,
+    /// Word as normalized, e.g. "M104", or the letter for bare letters
+    pub word: String,
+    /// Firmware the word is specific to
+    pub flavor: Flavor,
+    /// What the word does
+    pub description: &'static str,
+}
+
+/// Unsupported words of a program, grouped by word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSummary {
+    /// Word as normalized
+    pub word: String,
+    /// Firmware the word is specific to
+    pub flavor: Flavor,
+    /// What the word does
+    pub description: &'static str,
+    /// Lines using the word (0-based)
+    pub lines: Vec<usize>,
+}
+
+/// Unsupported words found in a program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Issues in program order
+    pub issues: Vec<CompatibilityIssue>,
+}
+
+impl CompatibilityReport {
+    /// Check a program for words GRBL does not support
+    pub fn check(program: &str) -> Self {
+        let mut issues = Vec::new();
+        for (line, text) in program.lines().enumerate() {
+            for (letter, value, _) in word_spans(text) {
+                if let Some(known) = known_word(letter, &value) {
+                    issues.push(CompatibilityIssue {
+                        line,
+                        word: known.word.to_string(),
+                        flavor: known.flavor,
+                        description: known.description,
+                    });
+                }
+            }
+        }
+        Self { issues }
+    }
+
+    /// Whether the program only uses supported words
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues grouped by word, in order of first use
+    pub fn summary(&self) -> Vec<WordSummary> {
+        let mut summary: Vec<WordSummary> = Vec::new();
+        for issue in &self.issues {
+            match summary.iter_mut().find(|s| s.word == issue.word) {
+                Some(entry) => {
+                    if entry.lines.last() != Some(&issue.line) {
+                        entry.lines.push(issue.line);
+                    }
+                }
+                None => summary.push(WordSummary {
+                    word: issue.word.clone(),
+                    flavor: issue.flavor,
+                    description: issue.description,
+                    lines: vec![issue.line],
+                }),
+            }
+        }
+        summary
+    }
+}
+
+/// Remove the given words and their parameters from a program
+///
+/// Lines left without any words are dropped; comments are kept.
+pub fn strip_words(program: &str, words: &[String]) -> String {
+    if words.is_empty() {
+        return program.to_string();
+    }
+    let output: Vec<String> = program.lines().filter_map(|line| strip_line(line, words)).collect();
+    let mut result = output.join("\n");
+    if program.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Strip words from one line, returning `None` if the line becomes empty
+pub fn strip_line(line: &str, words: &[String]) -> Option<String> {
+    let spans = word_spans(line);
+    let params: Vec<char> = spans
+        .iter()
+        .filter_map(|(letter, value, _)| known_word(*letter, value))
+        .filter(|known| words.iter().any(|w| w == known.word))
+        .flat_map(|known| known.params.iter().copied())
+        .collect();
+    let remove: Vec<&Range<usize>> = spans
+        .iter()
+        .filter(|(letter, value, _)| {
+            known_word(*letter, value).is_some_and(|known| words.iter().any(|w| w == known.word))
+                || params.contains(letter)
+        })
+        .map(|(_, _, range)| range)
+        .collect();
+    if remove.is_empty() {
+        return Some(line.to_string());
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut position = 0;
+    for range in remove {
+        result.push_str(&line[position..range.start]);
+        position = range.end;
+        while line[position..].starts_with(' ') {
+            position += 1;
+        }
+    }
+    result.push_str(&line[position..]);
+    let result = result.trim_end().to_string();
+    (!result.trim().is_empty()).then_some(result)
+}
+
+/// Table entry for a word, if GRBL does not support it
+fn known_word(letter: char, value: &str) -> Option<&'static KnownWord> {
+    let name = word_name(letter, value)?;
+    KNOWN_WORDS.iter().find(|known| {
+        known.word == name || (known.word.len() == 1 && known.word.starts_with(letter))
+    })
+}
+
+/// Normalized name of a G or M word, e.g. "M0104" becomes "M104"
+fn word_name(letter: char, value: &str) -> Option<String> {
+    let number: f64 = value.parse().ok()?;
+    if number.fract() == 0.0 {
+        Some(format!("{}{}", letter, number as i64))
+    } else {
+        Some(format!("{}{}", letter, number))
+    }
+}
+
+/// Words outside comments: uppercase letter, value text and byte range
+pub(super) fn word_spans(line: &str) -> Vec<(char, String, Range<usize>)> {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut spans = Vec::new();
+    let mut in_comment = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, ch) = chars[i];
+        match ch {
+            '(' if !in_comment => in_comment = true,
+            ')' if in_comment => in_comment = false,
+            ';' if !in_comment => break,
+            c if !in_comment && c.is_ascii_alphabetic() => {
+                let mut j = i + 1;
+                while j < chars.len() && (chars[j].1.is_ascii_digit() || matches!(chars[j].1, '.' | '-' | '+')) {
+                    j += 1;
+                }
+                let end = chars.get(j).map_or(line.len(), |&(index, _)| index);
+                spans.push((c.to_ascii_uppercase(), line[start + 1..end].to_string(), start..end));
+                i = j;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRINTER_PROGRAM: &str = "M104 S200\nG64 P0.01 (blend)\nG1 X10 E1.5 F600\nG1 X20 E3\nM107";
+
+    #[test]
+    fn test_check() {
+        let report = CompatibilityReport::check(PRINTER_PROGRAM);
+        let words: Vec<&str> = report.issues.iter().map(|i| i.word.as_str()).collect();
+        assert_eq!(words, vec!["M104", "G64", "E", "E", "M107"]);
+        assert_eq!(report.issues[1].flavor, Flavor::LinuxCnc);
+
+        let summary = report.summary();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[2].word, "E");
+        assert_eq!(summary[2].lines, vec![2, 3]);
+
+        assert!(CompatibilityReport::check("G0 X0 (M104 in a comment)\nG61\nM3 S1000").is_empty());
+    }
+
+    #[test]
+    fn test_strip_words() {
+        let all: Vec<String> = ["M104", "G64", "E", "M107"].iter().map(|w| w.to_string()).collect();
+        assert_eq!(strip_words(PRINTER_PROGRAM, &all), "(blend)\nG1 X10 F600\nG1 X20");
+
+        let only_e = vec!["E".to_string()];
+        assert_eq!(strip_words("G1 X10 E1.5 F600\nM0104 S200\n", &only_e), "G1 X10 F600\nM0104 S200\n");
+    }
+}
@@ -0,0 +1,200 @@
+//! Dry run above the stock
+//!
+//! Rewrites a program so the machine traces the job in the air: every Z the
+//! program goes to is kept at or above a clearance over the stock surface
+//! (work Z0), feed rates can be scaled up to get through the job faster,
+//! and the spindle can be left off. XY motion is unchanged, so the real
+//! machine shows where the job will run and whether it fits the travel.
+//!
+//! Lines that set offsets or move to machine or stored positions (G10,
+//! G28, G30, G38, G43.1, G53, G92) are passed through, and the tracked Z is
+//! forgotten when they move Z. A relative Z move while the Z is unknown, as
+//! at the start of a program, is preceded by an absolute rapid to the
+//! clearance so it can't take the tool into the stock.
+
+use super::compatibility::word_spans;
+use super::multipass::format_coord;
+use super::tokenizer::{Token, Tokenizer};
+
+/// Rewrites program lines for a dry run, tracking modal state across lines
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    /// Lowest Z above the work zero (mm)
+    clearance: f64,
+    /// Multiplier for F words
+    feed_scale: f64,
+    /// Remove spindle starts (M3, M4)
+    spindle_off: bool,
+    /// Absolute distance mode (G90)
+    absolute: bool,
+    /// Metric units (G21)
+    metric: bool,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Programmed Z, once known
+    z: Option<f64>,
+}
+
+impl DryRun {
+    /// Create a pass keeping Z at least `clearance` mm above the stock surface
+    pub fn new(clearance: f64, feed_scale: f64, spindle_off: bool) -> Self {
+        Self {
+            clearance: clearance.max(0.0),
+            feed_scale: if feed_scale > 0.0 { feed_scale } else { 1.0 },
+            spindle_off,
+            absolute: true,
+            metric: true,
+            motion: None,
+            z: None,
+        }
+    }
+
+    /// Rewrite a whole program
+    pub fn apply(&mut self, program: &str) -> String {
+        program
+            .lines()
+            .flat_map(|line| self.process_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Rewrite one line, returning the lines to send in its place
+    pub fn process_line(&mut self, line: &str) -> Vec<String> {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('$') || trimmed.starts_with('%') {
+            return vec![line.to_string()];
+        }
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        let mut passthrough = false;
+        let (mut explicit_motion, mut explicit_incremental) = (false, false);
+        for token in &tokens {
+            if let Token::GCommand(code) = token {
+                match code {
+                    0..=3 => {
+                        self.motion = Some(*code);
+                        explicit_motion = true;
+                    }
+                    20 => self.metric = false,
+                    21 => self.metric = true,
+                    38 | 80..=89 => {
+                        self.motion = None;
+                        explicit_motion = true;
+                    }
+                    90 => self.absolute = true,
+                    91 => {
+                        self.absolute = false;
+                        explicit_incremental = true;
+                    }
+                    _ => {}
+                }
+                if matches!(code, 10 | 28 | 30 | 38 | 43 | 53 | 92) {
+                    passthrough = true;
+                }
+            }
+        }
+        let spans = word_spans(line);
+        let moves_z = spans.iter().any(|(letter, _, _)| *letter == 'Z');
+        if passthrough {
+            if moves_z {
+                self.z = None;
+            }
+            return vec![line.to_string()];
+        }
+
+        let clearance = if self.metric { self.clearance } else { self.clearance / 25.4 };
+        let mut lines = Vec::new();
+        let mut line = line.to_string();
+        if moves_z && !self.absolute && self.z.is_none() {
+            // Moving relative to an unknown height could go anywhere, so start from the clearance
+            lines.push(format!("G90 G0 Z{}", format_coord(clearance)));
+            if !explicit_incremental {
+                lines.push("G91".to_string());
+            }
+            if let Some(motion) = self.motion.filter(|_| !explicit_motion) {
+                line = format!("G{} {}", motion, line.trim_start());
+            }
+            self.z = Some(clearance);
+        }
+        let line = line.as_str();
+        let spans = word_spans(line);
+        let mut result = String::with_capacity(line.len());
+        let mut position = 0;
+        for (letter, value, range) in spans {
+            let Ok(number) = value.parse::<f64>() else {
+                continue;
+            };
+            let replacement = match letter {
+                'Z' => {
+                    let target = if self.absolute { Some(number) } else { self.z.map(|z| z + number) };
+                    let lifted = match (target, self.z) {
+                        (Some(target), _) if self.absolute => Some(target.max(clearance)),
+                        (Some(target), Some(start)) => Some(target.max(clearance) - start.max(clearance)),
+                        _ => None,
+                    };
+                    self.z = target;
+                    lifted.map(|z| format!("Z{}", format_coord(z)))
+                }
+                'F' if self.feed_scale != 1.0 => Some(format!("F{}", format_coord(number * self.feed_scale))),
+                'M' if self.spindle_off && (number == 3.0 || number == 4.0) => Some(String::new()),
+                _ => None,
+            };
+            let Some(replacement) = replacement else {
+                continue;
+            };
+            result.push_str(&line[position..range.start]);
+            result.push_str(&replacement);
+            position = range.end;
+            if replacement.is_empty() {
+                while line[position..].starts_with(' ') {
+                    position += 1;
+                }
+            }
+        }
+        result.push_str(&line[position..]);
+        lines.push(result.trim_end().to_string());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lift_depths() {
+        let program = "G21 G90\nM3 S12000\nG0 Z5\nG1 Z-2 F300 (plunge)\nG1 X10 Y5\nG0 Z5";
+        assert_eq!(
+            DryRun::new(2.0, 1.0, false).apply(program),
+            "G21 G90\nM3 S12000\nG0 Z5\nG1 Z2 F300 (plunge)\nG1 X10 Y5\nG0 Z5"
+        );
+    }
+
+    #[test]
+    fn test_feed_scale_and_spindle_off() {
+        let program = "M3 S12000 G1 X1 F300\nM4\nG53 G0 Z-1\nG43.1 Z-0.5";
+        assert_eq!(
+            DryRun::new(2.0, 2.5, true).apply(program),
+            "S12000 G1 X1 F750\n\nG53 G0 Z-1\nG43.1 Z-0.5"
+        );
+    }
+
+    #[test]
+    fn test_relative_and_inch() {
+        // 0.2 in clearance: 5.08 mm
+        let program = "G20 G90\nG0 Z0.5\nG91\nG1 Z-0.6 F10\nG1 Z-0.1\nG1 Z0.8";
+        assert_eq!(
+            DryRun::new(5.08, 1.0, false).apply(program),
+            "G20 G90\nG0 Z0.5\nG91\nG1 Z-0.3 F10\nG1 Z0\nG1 Z0.4"
+        );
+    }
+
+    #[test]
+    fn test_relative_from_unknown_z() {
+        let program = "G91 G1 Z-5 F100\nG1 X10\nZ-1\nG53 G0 Z-2\nG91\nG1 X1\nZ-3";
+        assert_eq!(
+            DryRun::new(2.0, 1.0, false).apply(program),
+            "G90 G0 Z2\nG91 G1 Z0 F100\nG1 X10\nZ0\nG53 G0 Z-2\nG91\nG1 X1\nG90 G0 Z2\nG91\nG1 Z0"
+        );
+    }
+}
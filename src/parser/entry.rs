@@ -0,0 +1,314 @@
+//! Ramped and helical plunge entries in the program text
+//!
+//! The preview replaces straight plunges into the stock with the entry
+//! chosen in the processing settings. This pass makes the same replacement
+//! in the lines sent to the machine, so it cuts what the preview shows: a
+//! feed move going straight down in Z below the surface (work Z0) becomes
+//! the ramp or helix moves [`Preprocessor`] generates for the preview.
+//!
+//! Only plain plunges are rewritten, holding nothing but G1, distance,
+//! unit and feed modes, Z, F, a line number and comments. Lines that move
+//! to machine or stored positions (G10, G28, G30, G38, G53, G92) are passed
+//! through and the tracked position is forgotten.
+
+use super::multipass::format_coord;
+use super::preprocessor::{PlungeEntry, Preprocessor};
+use super::segment::Segment;
+use super::tokenizer::{Token, Tokenizer};
+use super::types::Point3D;
+
+/// Rewrites straight plunges, tracking modal state across lines
+#[derive(Debug, Clone)]
+pub struct PlungeRewriter {
+    /// Entry strategy, as for the preview
+    entry: PlungeEntry,
+    /// Absolute distance mode (G90)
+    absolute: bool,
+    /// Metric units (G21)
+    metric: bool,
+    /// Inverse time feed mode (G93), where F is not a feed rate
+    inverse_time: bool,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Programmed X, Y and Z, each once known
+    position: [Option<f64>; 3],
+    /// Modal feed rate, once set
+    feed: Option<f64>,
+}
+
+/// The words of a line this pass looks at
+#[derive(Debug, Default)]
+struct LineWords {
+    /// G codes, in order
+    codes: Vec<u32>,
+    /// X, Y and Z words
+    axes: [Option<f64>; 3],
+    /// F word
+    feed: Option<f64>,
+    /// N word
+    line_number: Option<u32>,
+    /// Comment texts
+    comments: Vec<String>,
+    /// Holds a block delete or a word other than those above
+    other: bool,
+}
+
+impl LineWords {
+    fn parse(line: &str) -> Self {
+        let mut words = LineWords::default();
+        for token in Tokenizer::new(line).tokenize().unwrap_or_default() {
+            match token {
+                Token::GCommand(code) => words.codes.push(code),
+                Token::FCommand(feed) => words.feed = Some(feed),
+                Token::Parameter { letter, value } => match letter.to_ascii_uppercase() {
+                    'X' => words.axes[0] = Some(value),
+                    'Y' => words.axes[1] = Some(value),
+                    'Z' => words.axes[2] = Some(value),
+                    _ => words.other = true,
+                },
+                Token::LineNumber(number) => words.line_number = Some(number),
+                Token::Comment(text) => words.comments.push(text),
+                Token::EndOfLine => {}
+                _ => words.other = true,
+            }
+        }
+        words
+    }
+
+    fn moves(&self) -> bool {
+        self.axes.iter().any(Option::is_some)
+    }
+}
+
+impl PlungeRewriter {
+    /// Create a pass replacing plunges with `entry`
+    pub fn new(entry: PlungeEntry) -> Self {
+        Self {
+            entry,
+            absolute: true,
+            metric: true,
+            inverse_time: false,
+            motion: None,
+            position: [None; 3],
+            feed: None,
+        }
+    }
+
+    /// Rewrite a whole program
+    pub fn apply(&mut self, program: &str) -> String {
+        let lines: Vec<&str> = program.lines().collect();
+        self.process_lines(&lines)
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Rewrite a program given as lines, returning the lines to send in place of each
+    ///
+    /// A ramp runs along the next move, so each line is rewritten knowing
+    /// the line that moves after it.
+    pub fn process_lines<S: AsRef<str>>(&mut self, lines: &[S]) -> Vec<Vec<String>> {
+        let words: Vec<LineWords> = lines.iter().map(|line| LineWords::parse(line.as_ref())).collect();
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let next = words[index + 1..].iter().find(|words| words.moves());
+                self.step(line.as_ref(), &words[index], next)
+            })
+            .collect()
+    }
+
+    /// Process one line, returning its replacement
+    fn step(&mut self, line: &str, words: &LineWords, next: Option<&LineWords>) -> Vec<String> {
+        let unchanged = vec![line.to_string()];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('$') || trimmed.starts_with('%') {
+            return unchanged;
+        }
+
+        let mut passthrough = false;
+        let mut plain = !words.other;
+        for code in &words.codes {
+            match code {
+                0..=3 => self.motion = Some(*code),
+                20 | 21 => {
+                    // Positions so far are in the other units
+                    if self.metric != (*code == 21) {
+                        self.position = [None; 3];
+                    }
+                    self.metric = *code == 21;
+                }
+                90 => self.absolute = true,
+                91 => self.absolute = false,
+                93 => self.inverse_time = true,
+                94 | 95 => self.inverse_time = false,
+                10 | 28 | 30 | 38 | 53 | 92 => passthrough = true,
+                80 => self.motion = None,
+                _ => plain = false,
+            }
+        }
+        if let Some(feed) = words.feed {
+            self.feed = Some(feed);
+        }
+        if passthrough {
+            // The tool ends up somewhere this pass can't follow
+            self.position = [None; 3];
+            return unchanged;
+        }
+
+        let start = self.position;
+        self.position = self.target(start, &words.axes, self.absolute);
+        let is_z_only = words.axes[0].is_none() && words.axes[1].is_none() && words.axes[2].is_some();
+        if !plain || !is_z_only || self.motion != Some(1) || self.inverse_time {
+            return unchanged;
+        }
+        let (Some(x), Some(y), Some(from), Some(to), Some(feed)) =
+            (start[0], start[1], start[2], self.position[2], self.feed)
+        else {
+            return unchanged;
+        };
+        let plunge = Segment::linear(Point3D::new(x, y, from), Point3D::new(x, y, to), feed);
+        if self.entry == PlungeEntry::Straight || !Preprocessor::is_plunge(&plunge) {
+            return unchanged;
+        }
+
+        let next = next.map(|next| self.next_segment(next, plunge.end));
+        let moves = Preprocessor::new()
+            .with_plunge_entry(self.entry)
+            .expand_plunge(&plunge, next.as_ref());
+        self.format_moves(words, &plunge, &moves)
+    }
+
+    /// Position after moving to a line's axis words
+    fn target(&self, start: [Option<f64>; 3], axes: &[Option<f64>; 3], absolute: bool) -> [Option<f64>; 3] {
+        let mut end = start;
+        for (end, (start, value)) in end.iter_mut().zip(start.iter().zip(axes)) {
+            if let Some(value) = value {
+                *end = if absolute { Some(*value) } else { start.map(|start| start + value) };
+            }
+        }
+        end
+    }
+
+    /// The move a following line makes from the end of the plunge
+    fn next_segment(&self, next: &LineWords, from: Point3D) -> Segment {
+        let mut motion = Some(1);
+        let mut absolute = self.absolute;
+        for code in &next.codes {
+            match code {
+                0..=3 => motion = Some(*code),
+                90 => absolute = true,
+                91 => absolute = false,
+                _ => {}
+            }
+        }
+        let end = self.target([Some(from.x), Some(from.y), Some(from.z)], &next.axes, absolute);
+        let end = Point3D::new(
+            end[0].unwrap_or(from.x),
+            end[1].unwrap_or(from.y),
+            end[2].unwrap_or(from.z),
+        );
+        if motion == Some(0) {
+            Segment::rapid(from, end)
+        } else {
+            Segment::linear(from, end, self.feed.unwrap_or_default())
+        }
+    }
+
+    /// Lines for the entry moves, keeping the plunge's modal words, number and comments
+    fn format_moves(&self, words: &LineWords, plunge: &Segment, moves: &[Segment]) -> Vec<String> {
+        let mut lines = Vec::with_capacity(moves.len());
+        // Relative moves go between rounded positions so they add up to the plunge
+        let round = |value: f64| format_coord(value).parse::<f64>().unwrap_or(value);
+        let mut from = plunge.start;
+        for (index, segment) in moves.iter().enumerate() {
+            let mut parts = Vec::new();
+            if index == 0 {
+                parts.extend(words.line_number.map(|number| format!("N{}", number)));
+                parts.extend(words.codes.iter().filter(|code| **code > 3).map(|code| format!("G{}", code)));
+                parts.push("G1".to_string());
+            }
+            for (letter, from, to) in [
+                ('X', from.x, segment.end.x),
+                ('Y', from.y, segment.end.y),
+                ('Z', from.z, segment.end.z),
+            ] {
+                let value = if self.absolute { to } else { round(to) - round(from) };
+                parts.push(format!("{}{}", letter, format_coord(value)));
+            }
+            if index == 0 {
+                parts.push(format!("F{}", format_coord(plunge.feed_rate)));
+                parts.extend(words.comments.iter().map(|comment| format!("({})", comment)));
+            }
+            lines.push(parts.join(" "));
+            from = segment.end;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAMP: PlungeEntry = PlungeEntry::Ramp { angle: 45.0, length: 2.0 };
+
+    #[test]
+    fn test_ramp_entry() {
+        let program = "G21 G90\nG0 X0 Y0 Z5\nG1 Z-1 F100 (plunge)\nG1 X10";
+        assert_eq!(
+            PlungeRewriter::new(RAMP).apply(program),
+            "G21 G90\nG0 X0 Y0 Z5\nG1 X0 Y0 Z0 F100 (plunge)\nX2 Y0 Z-1\nX0 Y0 Z-1\nG1 X10"
+        );
+
+        // The ramp runs along the next cut
+        let program = "G0 X0 Y0 Z0\nG1 Z-1 F100\n\nG1 Y-10";
+        assert_eq!(
+            PlungeRewriter::new(RAMP).apply(program),
+            "G0 X0 Y0 Z0\nG1 X0 Y-2 Z-1 F100\nX0 Y0 Z-1\n\nG1 Y-10"
+        );
+    }
+
+    #[test]
+    fn test_relative_entry() {
+        let program = "G0 X1 Y1 Z0\nG91 G1 Z-1 F100\nG1 X5";
+        assert_eq!(
+            PlungeRewriter::new(RAMP).apply(program),
+            "G0 X1 Y1 Z0\nG91 G1 X2 Y0 Z-1 F100\nX-2 Y0 Z0\nG1 X5"
+        );
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let programs = [
+            // Straight entry, above the surface, unknown position and moves in XY
+            "G0 X0 Y0 Z5\nG1 Z-1 F100",
+            "G0 X0 Y0 Z5\nG1 Z1 F100",
+            "G1 Z-1 F100\nG1 X5",
+            "G0 X0 Y0 Z5\nG1 X1 Z-1 F100",
+            // Other words on the plunge, and positions the pass can't follow
+            "G0 X0 Y0 Z5\nG1 Z-1 F100 M8",
+            "G0 X0 Y0 Z5\nG53 G0 Z-10\nG1 Z-1 F100",
+        ];
+        let mut straight = PlungeRewriter::new(PlungeEntry::Straight);
+        assert_eq!(straight.apply(programs[0]), programs[0]);
+        for program in &programs[1..] {
+            assert_eq!(PlungeRewriter::new(RAMP).apply(program), *program);
+        }
+    }
+
+    #[test]
+    fn test_helix_matches_preview() {
+        let entry = PlungeEntry::Helix { angle: 10.0, diameter: 4.0 };
+        let program = "G0 X3 Y4 Z0\nG1 Z-2 F200\nG1 X10";
+        let rewritten = PlungeRewriter::new(entry).apply(program);
+
+        let plunge = Segment::linear(Point3D::new(3.0, 4.0, 0.0), Point3D::new(3.0, 4.0, -2.0), 200.0);
+        let moves = Preprocessor::new().with_plunge_entry(entry).expand_plunge(&plunge, None);
+        let lines: Vec<&str> = rewritten.lines().collect();
+        assert_eq!(lines.len(), moves.len() + 2);
+        assert_eq!(lines[lines.len() - 2], "X3 Y4 Z-2");
+    }
+}
@@ -0,0 +1,76 @@
+//! Job time estimation
+//!
+//! Estimates how long a program takes to run from its motion segments.
+
+use super::segment::{Segment, SegmentType};
+use std::time::Duration;
+
+/// Estimated machine time and travel for a program
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JobEstimate {
+    /// Time spent on feed moves
+    pub cutting_time: Duration,
+    /// Time spent on rapid moves
+    pub rapid_time: Duration,
+    /// Distance travelled at feed rate
+    pub cutting_distance: f64,
+    /// Distance travelled at rapid rate
+    pub rapid_distance: f64,
+}
+
+impl JobEstimate {
+    /// Estimate a program from its segments
+    ///
+    /// Rapid moves carry no feed rate, so they are timed at `rapid_rate`
+    /// (units per minute). Acceleration is not modelled, so short moves make
+    /// the estimate optimistic. G93 and G95 feeds are timed correctly since
+    /// the parser converts them to units per minute.
+    pub fn from_segments(segments: &[Segment], rapid_rate: f64) -> Self {
+        let mut cutting_secs = 0.0;
+        let mut rapid_secs = 0.0;
+        let mut estimate = Self::default();
+
+        for segment in segments {
+            let length = segment.length();
+            if segment.segment_type == SegmentType::Rapid {
+                estimate.rapid_distance += length;
+                if rapid_rate > 0.0 {
+                    rapid_secs += length / (rapid_rate / 60.0);
+                }
+            } else {
+                estimate.cutting_distance += length;
+                cutting_secs += segment.estimated_time();
+            }
+        }
+
+        estimate.cutting_time = Duration::from_secs_f64(cutting_secs);
+        estimate.rapid_time = Duration::from_secs_f64(rapid_secs);
+        estimate
+    }
+
+    /// Total machine time
+    pub fn total_time(&self) -> Duration {
+        self.cutting_time + self.rapid_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Point3D;
+
+    #[test]
+    fn test_job_estimate() {
+        let segments = vec![
+            Segment::rapid(Point3D::new(0.0, 0.0, 0.0), Point3D::new(100.0, 0.0, 0.0)),
+            Segment::linear(Point3D::new(100.0, 0.0, 0.0), Point3D::new(100.0, 50.0, 0.0), 500.0),
+        ];
+
+        let estimate = JobEstimate::from_segments(&segments, 1000.0);
+        assert_eq!(estimate.rapid_distance, 100.0);
+        assert_eq!(estimate.cutting_distance, 50.0);
+        assert!((estimate.rapid_time.as_secs_f64() - 6.0).abs() < 1e-6);
+        assert!((estimate.cutting_time.as_secs_f64() - 6.0).abs() < 1e-6);
+        assert!((estimate.total_time().as_secs_f64() - 12.0).abs() < 1e-6);
+    }
+}
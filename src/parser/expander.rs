@@ -0,0 +1,622 @@
+//! Subprogram and canned cycle expansion
+//!
+//! Stock GRBL understands neither subprogram calls (M98/M99) nor drilling
+//! canned cycles (G81-G83). This module rewrites a program into plain
+//! G0/G1/G4 moves so it can be visualized and streamed to such controllers.
+//! Lines that need no expansion are passed through unchanged.
+
+use std::collections::HashMap;
+
+use super::multipass::format_coord;
+use super::tokenizer::{Token, Tokenizer};
+use crate::utils::error::{Error, Result};
+
+/// Default nesting limit for subprogram calls
+const DEFAULT_MAX_CALL_DEPTH: usize = 16;
+
+/// Default limit on the number of lines subprogram calls may expand to
+const DEFAULT_MAX_LINES: usize = 1_000_000;
+
+/// Default clearance above the previous peck depth for G83
+const DEFAULT_PECK_CLEARANCE: f64 = 0.5;
+
+/// Expands subprogram calls and canned cycles into linear moves
+#[derive(Debug, Clone)]
+pub struct ProgramExpander {
+    /// Maximum nesting depth of subprogram calls
+    max_call_depth: usize,
+    /// Maximum number of lines the expanded program may have
+    max_lines: usize,
+    /// Distance above the previous peck depth G83 rapids back down to
+    peck_clearance: f64,
+}
+
+impl ProgramExpander {
+    /// Create a new expander with default limits
+    pub fn new() -> Self {
+        Self {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_lines: DEFAULT_MAX_LINES,
+            peck_clearance: DEFAULT_PECK_CLEARANCE,
+        }
+    }
+
+    /// Set the maximum nesting depth of subprogram calls
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Set the maximum number of lines subprogram calls may expand to
+    pub fn with_max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = lines;
+        self
+    }
+
+    /// Set the clearance used when re-entering a G83 peck hole
+    pub fn with_peck_clearance(mut self, clearance: f64) -> Self {
+        self.peck_clearance = clearance.max(0.0);
+        self
+    }
+
+    /// Expand a program
+    ///
+    /// Subprograms are defined by an `O` word and end with M99; they are
+    /// removed from the main program and inserted wherever M98 P<n> L<count>
+    /// calls them. Canned cycles are expanded into rapid and feed moves
+    /// honouring G98/G99 retract modes.
+    pub fn expand(&self, program: &str) -> Result<String> {
+        let lines: Vec<String> = self
+            .expand_with_sources(program)?
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+
+        let mut result = lines.join("\n");
+        if program.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+
+    /// Expand a program, keeping track of the source line of every output line
+    pub fn expand_with_sources(&self, program: &str) -> Result<Vec<ExpandedLine>> {
+        let lines: Vec<(usize, &str)> = program.lines().enumerate().collect();
+        let (main, subprograms) = split_subprograms(&lines);
+
+        let mut state = ModalState::default();
+        let mut output = Vec::with_capacity(lines.len());
+        self.expand_lines(&main, &subprograms, 0, &mut state, &mut output)?;
+        Ok(output)
+    }
+
+    /// Expand a block of lines, recursing into subprogram calls
+    fn expand_lines(
+        &self,
+        lines: &[(usize, &str)],
+        subprograms: &HashMap<u32, SourceLines<'_>>,
+        depth: usize,
+        state: &mut ModalState,
+        output: &mut Vec<ExpandedLine>,
+    ) -> Result<()> {
+        for &(source_line, line) in lines {
+            let words = LineWords::parse(line);
+
+            if words.m_codes.contains(&98) {
+                let number = words
+                    .value('P')
+                    .ok_or_else(|| Error::parse("M98 requires a P word naming the subprogram"))?
+                    as u32;
+                let body = subprograms
+                    .get(&number)
+                    .ok_or_else(|| Error::parse(format!("Subprogram O{} is not defined", number)))?;
+                if depth >= self.max_call_depth {
+                    return Err(Error::parse(format!(
+                        "Subprogram calls nested deeper than {}",
+                        self.max_call_depth
+                    )));
+                }
+
+                // A large L count would otherwise expand without bound
+                let too_long = || {
+                    Error::parse(format!(
+                        "Line {}: calling O{} expands to more than {} lines",
+                        source_line + 1,
+                        number,
+                        self.max_lines
+                    ))
+                };
+                let repeats = words.value('L').map(|l| l.max(0.0) as usize).unwrap_or(1);
+                if repeats > self.max_lines {
+                    return Err(too_long());
+                }
+                for _ in 0..repeats {
+                    self.expand_lines(body, subprograms, depth + 1, state, output)?;
+                    if output.len() > self.max_lines {
+                        return Err(too_long());
+                    }
+                }
+                continue;
+            }
+
+            if words.m_codes.contains(&99) {
+                // M99 in the main program loops back to the start; don't stream it
+                continue;
+            }
+
+            let mut emit = |text: String| output.push(ExpandedLine { source_line, text });
+            self.expand_line(line, &words, state, &mut emit)?;
+        }
+
+        Ok(())
+    }
+
+    /// Expand a single non-call line
+    fn expand_line(
+        &self,
+        line: &str,
+        words: &LineWords,
+        state: &mut ModalState,
+        emit: &mut impl FnMut(String),
+    ) -> Result<()> {
+        for &g in &words.g_codes {
+            match g {
+                90 => state.absolute = true,
+                91 => state.absolute = false,
+                98 => state.retract_to_initial = true,
+                99 => state.retract_to_initial = false,
+                0..=3 | 80 => state.cycle = None,
+                _ => {}
+            }
+        }
+        if let Some(feed) = words.feed {
+            state.feed = Some(feed);
+        }
+
+        let cycle_code = words.g_codes.iter().copied().find(|g| (81..=83).contains(g));
+        if let Some(kind) = cycle_code {
+            if !state.absolute {
+                return Err(Error::parse(
+                    "Canned cycles in incremental mode (G91) are not supported",
+                ));
+            }
+            state.cycle = Some(Cycle::from_words(kind, words, state)?);
+        }
+
+        let Some(cycle) = state.cycle.clone() else {
+            // Not in a cycle: pass the line through, minus retract mode words GRBL rejects
+            if words.g_codes.iter().any(|g| matches!(g, 98 | 99)) {
+                let stripped = retain_words(line, |letter, number| {
+                    !(letter == 'G' && matches!(number, "98" | "99"))
+                });
+                if !stripped.trim().is_empty() {
+                    emit(stripped);
+                }
+            } else {
+                emit(line.to_string());
+            }
+            state.track_position(words);
+            return Ok(());
+        };
+
+        // Everything on a cycle line that isn't part of the cycle is kept on its own line
+        let remainder = retain_words(line, |letter, number| match letter {
+            'G' => !matches!(number, "80" | "81" | "82" | "83" | "98" | "99"),
+            'X' | 'Y' | 'Z' | 'R' | 'Q' | 'P' | 'L' | 'F' => false,
+            _ => true,
+        });
+        if !remainder.trim().is_empty() {
+            emit(remainder);
+        }
+
+        if cycle_code.is_some() || words.value('X').is_some() || words.value('Y').is_some() {
+            let x = words.value('X').unwrap_or(state.x);
+            let y = words.value('Y').unwrap_or(state.y);
+            self.drill(&cycle, x, y, state, emit);
+        }
+
+        Ok(())
+    }
+
+    /// Emit the moves for one hole of a canned cycle
+    fn drill(&self, cycle: &Cycle, x: f64, y: f64, state: &mut ModalState, emit: &mut impl FnMut(String)) {
+        let feed = state
+            .feed
+            .map(|f| format!(" F{}", format_coord(f)))
+            .unwrap_or_default();
+        let clear_z = if state.retract_to_initial {
+            cycle.initial_z.max(cycle.r)
+        } else {
+            cycle.r
+        };
+
+        if state.z < cycle.r {
+            emit(format!("G0 Z{}", format_coord(cycle.r)));
+        }
+        emit(format!("G0 X{} Y{}", format_coord(x), format_coord(y)));
+        emit(format!("G0 Z{}", format_coord(cycle.r)));
+
+        match cycle.peck {
+            Some(peck) => {
+                let mut depth = cycle.r;
+                loop {
+                    let target = (depth - peck).max(cycle.z);
+                    if depth < cycle.r {
+                        emit(format!("G0 Z{}", format_coord(depth + self.peck_clearance)));
+                    }
+                    emit(format!("G1 Z{}{}", format_coord(target), feed));
+                    if target <= cycle.z {
+                        break;
+                    }
+                    emit(format!("G0 Z{}", format_coord(cycle.r)));
+                    depth = target;
+                }
+            }
+            None => emit(format!("G1 Z{}{}", format_coord(cycle.z), feed)),
+        }
+
+        if let Some(dwell) = cycle.dwell {
+            emit(format!("G4 P{}", format_coord(dwell)));
+        }
+        emit(format!("G0 Z{}", format_coord(clear_z)));
+
+        state.x = x;
+        state.y = y;
+        state.z = clear_z;
+    }
+}
+
+impl Default for ProgramExpander {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A line of an expanded program
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpandedLine {
+    /// Index of the line in the original program that produced this line (0-based)
+    pub source_line: usize,
+    /// Expanded G-Code text
+    pub text: String,
+}
+
+/// An active canned cycle
+#[derive(Debug, Clone)]
+struct Cycle {
+    /// Retract plane (R)
+    r: f64,
+    /// Hole bottom (Z)
+    z: f64,
+    /// Peck increment for G83 (Q)
+    peck: Option<f64>,
+    /// Dwell at the bottom in seconds for G82 (P)
+    dwell: Option<f64>,
+    /// Z height when the cycle was first invoked, used by G98
+    initial_z: f64,
+}
+
+impl Cycle {
+    /// Build a cycle from a line's words, inheriting values from an active cycle
+    fn from_words(kind: u32, words: &LineWords, state: &ModalState) -> Result<Self> {
+        let previous = state.cycle.as_ref();
+
+        let r = words
+            .value('R')
+            .or(previous.map(|c| c.r))
+            .ok_or_else(|| Error::parse(format!("G{} requires an R word", kind)))?;
+        let z = words
+            .value('Z')
+            .or(previous.map(|c| c.z))
+            .ok_or_else(|| Error::parse(format!("G{} requires a Z word", kind)))?;
+
+        let peck = if kind == 83 {
+            let q = words
+                .value('Q')
+                .or(previous.and_then(|c| c.peck))
+                .ok_or_else(|| Error::parse("G83 requires a Q word"))?;
+            if q <= 0.0 {
+                return Err(Error::parse("G83 peck increment Q must be greater than zero"));
+            }
+            Some(q)
+        } else {
+            None
+        };
+
+        let dwell = if kind == 82 {
+            words.value('P').or(previous.and_then(|c| c.dwell))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            r,
+            z,
+            peck,
+            dwell,
+            initial_z: previous.map(|c| c.initial_z).unwrap_or(state.z),
+        })
+    }
+}
+
+/// Modal state carried across lines and subprogram calls
+#[derive(Debug, Clone)]
+struct ModalState {
+    absolute: bool,
+    retract_to_initial: bool,
+    feed: Option<f64>,
+    cycle: Option<Cycle>,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Default for ModalState {
+    fn default() -> Self {
+        Self {
+            absolute: true,
+            retract_to_initial: true,
+            feed: None,
+            cycle: None,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+}
+
+impl ModalState {
+    /// Update the tracked position from a passed-through line
+    fn track_position(&mut self, words: &LineWords) {
+        let apply = |current: f64, value: Option<f64>, absolute: bool| match value {
+            Some(v) if absolute => v,
+            Some(v) => current + v,
+            None => current,
+        };
+
+        // Non-modal G-codes (G4, G10, G28, G53...) don't move to their axis words in a way we track
+        if words.g_codes.iter().any(|g| matches!(g, 4 | 10 | 28 | 30 | 92)) {
+            return;
+        }
+
+        self.x = apply(self.x, words.value('X'), self.absolute);
+        self.y = apply(self.y, words.value('Y'), self.absolute);
+        self.z = apply(self.z, words.value('Z'), self.absolute);
+    }
+}
+
+/// Words extracted from a single line
+#[derive(Debug, Default)]
+struct LineWords {
+    g_codes: Vec<u32>,
+    m_codes: Vec<u32>,
+    feed: Option<f64>,
+    parameters: Vec<(char, f64)>,
+}
+
+impl LineWords {
+    fn parse(line: &str) -> Self {
+        let mut words = Self::default();
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        for token in tokens {
+            match token {
+                Token::GCommand(n) => words.g_codes.push(n),
+                Token::MCommand(n) => words.m_codes.push(n),
+                Token::FCommand(f) => words.feed = Some(f),
+                Token::Parameter { letter, value } => words.parameters.push((letter, value)),
+                _ => {}
+            }
+        }
+
+        words
+    }
+
+    /// Get the value of a parameter word
+    fn value(&self, letter: char) -> Option<f64> {
+        self.parameters
+            .iter()
+            .find(|(l, _)| *l == letter)
+            .map(|(_, v)| *v)
+    }
+
+    /// Get the subprogram number if the line starts an `O` definition
+    fn program_number(&self) -> Option<u32> {
+        self.value('O').map(|n| n as u32)
+    }
+}
+
+/// Program lines paired with their index in the original program
+type SourceLines<'a> = Vec<(usize, &'a str)>;
+
+/// Separate subprogram definitions from the main program
+fn split_subprograms<'a>(lines: &[(usize, &'a str)]) -> (SourceLines<'a>, HashMap<u32, SourceLines<'a>>) {
+    let mut main = Vec::with_capacity(lines.len());
+    let mut subprograms = HashMap::new();
+    let mut current: Option<(u32, SourceLines<'a>)> = None;
+
+    for &(index, line) in lines {
+        let words = LineWords::parse(line);
+
+        if let Some((_, body)) = current.as_mut() {
+            if words.m_codes.contains(&99) {
+                if let Some((number, body)) = current.take() {
+                    subprograms.insert(number, body);
+                }
+            } else {
+                body.push((index, line));
+            }
+            continue;
+        }
+
+        match words.program_number() {
+            Some(number) => current = Some((number, Vec::new())),
+            None => main.push((index, line)),
+        }
+    }
+
+    // An unterminated subprogram is still callable
+    if let Some((number, body)) = current {
+        subprograms.insert(number, body);
+    }
+
+    (main, subprograms)
+}
+
+/// Rebuild a line keeping only the words accepted by `keep`
+///
+/// Comments and anything that isn't a word are kept verbatim; whitespace
+/// following a removed word is dropped with it.
+fn retain_words(line: &str, keep: impl Fn(char, &str) -> bool) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            ';' => {
+                result.extend(&chars[i..]);
+                break;
+            }
+            '(' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ')')
+                    .map(|p| i + p + 1)
+                    .unwrap_or(chars.len());
+                result.extend(&chars[i..end]);
+                i = end;
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len()
+                    && (chars[end].is_ascii_digit() || matches!(chars[end], '.' | '-' | '+'))
+                {
+                    end += 1;
+                }
+                let number: String = chars[start..end].iter().collect();
+                if keep(c.to_ascii_uppercase(), &number) {
+                    result.extend(&chars[i..end]);
+                } else {
+                    while end < chars.len() && chars[end] == ' ' {
+                        end += 1;
+                    }
+                }
+                i = end;
+            }
+            _ => {
+                result.push(ch);
+                i += 1;
+            }
+        }
+    }
+
+    result.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subprogram_call() {
+        let program = "G0 X0 Y0\nM98 P100 L2\nM30\nO100\nG91 G1 X5 F100\nG90\nM99\n";
+        let output = ProgramExpander::new().expand(program).unwrap();
+
+        assert_eq!(output, "G0 X0 Y0\nG91 G1 X5 F100\nG90\nG91 G1 X5 F100\nG90\nM30\n");
+    }
+
+    #[test]
+    fn test_source_lines() {
+        let program = "M98 P5\nM30\nO5\nG0 X1\nM99";
+        let lines = ProgramExpander::new().expand_with_sources(program).unwrap();
+
+        assert_eq!(lines[0], ExpandedLine { source_line: 3, text: "G0 X1".to_string() });
+        assert_eq!(lines[1].source_line, 1);
+    }
+
+    #[test]
+    fn test_undefined_and_recursive_subprograms() {
+        assert!(ProgramExpander::new().expand("M98 P7\n").is_err());
+
+        let recursive = "M98 P1\nO1\nM98 P1\nM99\n";
+        assert!(ProgramExpander::new().expand(recursive).is_err());
+    }
+
+    #[test]
+    fn test_repeat_limit() {
+        let program = "G0 X0\nM98 P1 L1000000000\nM30\nO1\nG91 G1 X1 F100\nM99\n";
+        let error = ProgramExpander::new().with_max_lines(100).expand(program).unwrap_err();
+        assert!(error.to_string().contains("Line 2"), "{}", error);
+
+        let nested = "M98 P1 L20\nO1\nM98 P2 L20\nM99\nO2\nG1 X1\nM99\n";
+        assert!(ProgramExpander::new().with_max_lines(100).expand(nested).is_err());
+        assert!(ProgramExpander::new().with_max_lines(400).expand(nested).is_ok());
+    }
+
+    #[test]
+    fn test_drill_cycle() {
+        let program = "G0 Z10\nG81 X5 Y5 Z-3 R2 F50\nX15\nG80\nG0 Z20";
+        let output = ProgramExpander::new().expand(program).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "G0 Z10",
+                "G0 X5 Y5",
+                "G0 Z2",
+                "G1 Z-3 F50",
+                "G0 Z10",
+                "G0 X15 Y5",
+                "G0 Z2",
+                "G1 Z-3 F50",
+                "G0 Z10",
+                "G80",
+                "G0 Z20",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dwell_and_retract_to_r() {
+        let program = "G0 Z10\nG99 G82 X1 Y1 Z-2 R1 P0.5 F80\nG80";
+        let output = ProgramExpander::new().expand(program).unwrap();
+
+        assert!(output.contains("G1 Z-2 F80\nG4 P0.5\nG0 Z1"));
+        assert!(!output.contains("G99"));
+    }
+
+    #[test]
+    fn test_peck_cycle() {
+        let program = "G0 Z5\nG83 X0 Y0 Z-5 R1 Q2 F60\nG80";
+        let output = ProgramExpander::new().with_peck_clearance(0.5).expand(program).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "G0 Z5",
+                "G0 X0 Y0",
+                "G0 Z1",
+                "G1 Z-1 F60",
+                "G0 Z1",
+                "G0 Z-0.5",
+                "G1 Z-3 F60",
+                "G0 Z1",
+                "G0 Z-2.5",
+                "G1 Z-5 F60",
+                "G0 Z5",
+                "G80",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycle_requires_absolute_mode() {
+        let program = "G91\nG81 X1 Y1 Z-1 R1 F50";
+        assert!(ProgramExpander::new().expand(program).is_err());
+    }
+}
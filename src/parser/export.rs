@@ -0,0 +1,170 @@
+//! Toolpath export
+//!
+//! Writes the generated segments to CSV or JSON so toolpaths can be analyzed
+//! in a spreadsheet or script. Each segment becomes one row or object with
+//! its type, start, end and arc center, feed and spindle speed, N word and
+//! the line of the program it came from (1-based).
+
+use super::segment::{Segment, SegmentType};
+use crate::utils::error::{Error, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// CSV header row
+const CSV_HEADER: &str =
+    "index,type,start_x,start_y,start_z,end_x,end_y,end_z,center_x,center_y,center_z,feed,spindle,n,line";
+
+/// File format for an exported toolpath
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolpathFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// JSON array of segment objects
+    Json,
+}
+
+impl ToolpathFormat {
+    /// Format matching a file's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// One exported segment
+#[derive(Debug, Serialize)]
+struct SegmentRecord {
+    index: usize,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    start: [f64; 3],
+    end: [f64; 3],
+    center: Option<[f64; 3]>,
+    feed: f64,
+    spindle: f64,
+    n: Option<u32>,
+    line: Option<usize>,
+}
+
+impl SegmentRecord {
+    fn new(index: usize, segment: &Segment) -> Self {
+        let point = |p: &super::Point3D| [p.x, p.y, p.z];
+        Self {
+            index,
+            kind: match segment.segment_type {
+                SegmentType::Rapid => "rapid",
+                SegmentType::Linear => "line",
+                SegmentType::ArcCW => "arc_cw",
+                SegmentType::ArcCCW => "arc_ccw",
+            },
+            start: point(&segment.start),
+            end: point(&segment.end),
+            center: segment.center.as_ref().map(point),
+            feed: segment.feed_rate,
+            spindle: segment.spindle_speed,
+            n: segment.line_number,
+            line: segment.source_line.map(|line| line + 1),
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let center = match self.center {
+            Some([x, y, z]) => format!("{},{},{}", x, y, z),
+            None => ",,".to_string(),
+        };
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.index,
+            self.kind,
+            self.start[0],
+            self.start[1],
+            self.start[2],
+            self.end[0],
+            self.end[1],
+            self.end[2],
+            center,
+            self.feed,
+            self.spindle,
+            optional(self.n.map(|n| n.to_string())),
+            optional(self.line.map(|line| line.to_string())),
+        )
+    }
+}
+
+/// Write segments as text in the given format
+pub fn export_toolpath(segments: &[Segment], format: ToolpathFormat) -> Result<String> {
+    let records = segments.iter().enumerate().map(|(index, segment)| SegmentRecord::new(index, segment));
+    match format {
+        ToolpathFormat::Csv => {
+            let mut text = String::from(CSV_HEADER);
+            text.push('\n');
+            for record in records {
+                text.push_str(&record.csv_row());
+                text.push('\n');
+            }
+            Ok(text)
+        }
+        ToolpathFormat::Json => serde_json::to_string_pretty(&records.collect::<Vec<_>>())
+            .map_err(|e| Error::generic(format!("Failed to serialize toolpath: {}", e))),
+    }
+}
+
+/// Save segments to a file, choosing CSV or JSON from its extension
+pub fn save_toolpath<P: AsRef<Path>>(path: P, segments: &[Segment]) -> Result<()> {
+    let path = path.as_ref();
+    let format = ToolpathFormat::from_path(path)
+        .ok_or_else(|| Error::generic(format!("Unknown toolpath export format: {}", path.display())))?;
+    std::fs::write(path, export_toolpath(segments, format)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ArcDirection, Point3D};
+
+    fn segments() -> Vec<Segment> {
+        vec![
+            Segment::rapid(Point3D::new(0.0, 0.0, 5.0), Point3D::new(0.0, 0.0, 0.5)),
+            Segment::arc(
+                Point3D::new(0.0, 0.0, 0.0),
+                Point3D::new(10.0, 0.0, 0.0),
+                Point3D::new(5.0, 0.0, 0.0),
+                ArcDirection::CounterClockwise,
+                600.0,
+            )
+            .with_line_number(20)
+            .with_source_line(3),
+        ]
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = export_toolpath(&segments(), ToolpathFormat::Csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(rows[1], "0,rapid,0,0,5,0,0,0.5,,,,0,0,,");
+        assert_eq!(rows[2], "1,arc_ccw,0,0,0,10,0,0,5,0,0,600,0,20,4");
+        assert_eq!(rows[0].split(',').count(), rows[2].split(',').count());
+    }
+
+    #[test]
+    fn test_json_export() {
+        let json = export_toolpath(&segments(), ToolpathFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["type"], "rapid");
+        assert!(value[0]["center"].is_null());
+        assert_eq!(value[1]["center"][0], 5.0);
+        assert_eq!(value[1]["line"], 4);
+        assert_eq!(
+            ToolpathFormat::from_path(Path::new("part.JSON")),
+            Some(ToolpathFormat::Json)
+        );
+        assert_eq!(ToolpathFormat::from_path(Path::new("part.nc")), None);
+    }
+}
@@ -0,0 +1,410 @@
+//! Variable and expression evaluation
+//!
+//! Many post-processors emit numbered (`#100`) or named (`#<depth>`)
+//! variables and bracketed expressions such as `X[#100 * 2]`. GRBL accepts
+//! only plain numbers, so this pass resolves variables and arithmetic and
+//! rewrites each word with its numeric value before the program is
+//! tokenized. Lines without `#` or `[` are passed through unchanged, and
+//! the number of lines is preserved so line indices stay valid.
+
+use std::collections::HashMap;
+
+use super::multipass::format_coord;
+use crate::utils::error::{Error, Result};
+
+/// Evaluates G-Code variables and expressions
+#[derive(Debug, Clone, Default)]
+pub struct ExpressionEvaluator {
+    /// Variables defined before evaluation starts
+    variables: HashMap<String, f64>,
+}
+
+impl ExpressionEvaluator {
+    /// Create a new evaluator with no predefined variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Predefine a variable, e.g. `with_variable("100", 5.0)` or `with_variable("depth", 2.0)`
+    pub fn with_variable<S: Into<String>>(mut self, name: S, value: f64) -> Self {
+        self.variables.insert(name.into().to_ascii_lowercase(), value);
+        self
+    }
+
+    /// Resolve all variables and expressions in a program
+    ///
+    /// Assignments (`#1 = 2`) take effect after the line they are on, as in
+    /// RS274NGC, and are removed from the output. If any variable is used
+    /// before it is assigned, an error listing every unresolved variable and
+    /// its line is returned.
+    pub fn evaluate(&self, program: &str) -> Result<String> {
+        let mut variables = self.variables.clone();
+        let mut unresolved = Vec::new();
+        let mut output = Vec::new();
+
+        for (index, line) in program.lines().enumerate() {
+            if !line.contains(['#', '[']) {
+                output.push(line.to_string());
+                continue;
+            }
+
+            let mut missing = Vec::new();
+            let (text, assignments) = evaluate_line(line, &variables, &mut missing)
+                .map_err(|e| Error::parse(format!("Line {}: {}", index + 1, e)))?;
+
+            for name in missing {
+                unresolved.push(format!("{} (line {})", display_variable(&name), index + 1));
+            }
+            variables.extend(assignments);
+            output.push(text);
+        }
+
+        if !unresolved.is_empty() {
+            return Err(Error::parse(format!(
+                "Unresolved variables: {}",
+                unresolved.join(", ")
+            )));
+        }
+
+        let mut result = output.join("\n");
+        if program.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+/// Evaluate a single line, returning its rewritten text and any assignments
+fn evaluate_line(
+    line: &str,
+    variables: &HashMap<String, f64>,
+    missing: &mut Vec<String>,
+) -> std::result::Result<(String, Vec<(String, f64)>), String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len());
+    let mut assignments = Vec::new();
+    let mut expr = Expr {
+        chars: &chars,
+        pos: 0,
+        variables,
+        missing,
+    };
+
+    while expr.pos < chars.len() {
+        let ch = chars[expr.pos];
+        match ch {
+            ';' => {
+                result.extend(&chars[expr.pos..]);
+                break;
+            }
+            '(' => {
+                let end = chars[expr.pos..]
+                    .iter()
+                    .position(|&c| c == ')')
+                    .map(|p| expr.pos + p + 1)
+                    .unwrap_or(chars.len());
+                result.extend(&chars[expr.pos..end]);
+                expr.pos = end;
+            }
+            '#' => {
+                let name = expr.variable_name()?;
+                expr.skip_whitespace();
+                if expr.peek() != Some('=') {
+                    return Err(format!("Expected '=' after {}", display_variable(&name)));
+                }
+                expr.pos += 1;
+                let value = expr.unary()?;
+                assignments.push((name, value));
+                expr.skip_whitespace();
+            }
+            c if c.is_ascii_alphabetic() => {
+                result.push(c);
+                expr.pos += 1;
+                expr.skip_whitespace();
+
+                let start = expr.pos;
+                let value = expr.unary()?;
+                let literal = &chars[start..expr.pos];
+                if literal.iter().any(|&c| matches!(c, '#' | '[')) {
+                    result.push_str(&format_coord(value));
+                } else {
+                    result.extend(literal.iter().filter(|c| !c.is_whitespace()));
+                }
+            }
+            _ => {
+                result.push(ch);
+                expr.pos += 1;
+            }
+        }
+    }
+
+    Ok((result.trim_end().to_string(), assignments))
+}
+
+/// Format a variable name the way it is written in G-Code
+fn display_variable(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        format!("#{}", name)
+    } else {
+        format!("#<{}>", name)
+    }
+}
+
+/// Recursive descent expression parser over a single line
+struct Expr<'a> {
+    chars: &'a [char],
+    pos: usize,
+    variables: &'a HashMap<String, f64>,
+    missing: &'a mut Vec<String>,
+}
+
+impl Expr<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume a keyword (case-insensitive) if it is next
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let end = self.pos + keyword.len();
+        if end > self.chars.len() {
+            return false;
+        }
+        let matches = self.chars[self.pos..end]
+            .iter()
+            .zip(keyword.chars())
+            .all(|(a, b)| a.eq_ignore_ascii_case(&b));
+        if matches {
+            self.pos = end;
+        }
+        matches
+    }
+
+    /// Parse `a + b`, `a - b`
+    fn expression(&mut self) -> std::result::Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.keyword("+") {
+                value += self.term()?;
+            } else if self.keyword("-") {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parse `a * b`, `a / b`, `a MOD b`
+    fn term(&mut self) -> std::result::Result<f64, String> {
+        let mut value = self.power()?;
+        loop {
+            if self.keyword("*") {
+                value *= self.power()?;
+            } else if self.keyword("/") {
+                let divisor = self.power()?;
+                if divisor == 0.0 {
+                    return Err("Division by zero".to_string());
+                }
+                value /= divisor;
+            } else if self.keyword("MOD") {
+                let divisor = self.power()?;
+                if divisor == 0.0 {
+                    return Err("Division by zero".to_string());
+                }
+                value = value.rem_euclid(divisor);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parse `a ** b`
+    fn power(&mut self) -> std::result::Result<f64, String> {
+        let mut value = self.unary()?;
+        while self.keyword("**") {
+            value = value.powf(self.unary()?);
+        }
+        Ok(value)
+    }
+
+    /// Parse a signed primary value
+    fn unary(&mut self) -> std::result::Result<f64, String> {
+        if self.keyword("-") {
+            return Ok(-self.unary()?);
+        }
+        if self.keyword("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    /// Parse a number, variable, bracketed expression or function call
+    fn primary(&mut self) -> std::result::Result<f64, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => self.bracketed(),
+            Some('#') => {
+                let name = self.variable_name()?;
+                match self.variables.get(&name) {
+                    Some(&value) => Ok(value),
+                    None => {
+                        if !self.missing.contains(&name) {
+                            self.missing.push(name);
+                        }
+                        Ok(0.0)
+                    }
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.function(),
+            Some(c) => Err(format!("Unexpected '{}' in expression", c)),
+            None => Err("Expected a value".to_string()),
+        }
+    }
+
+    fn bracketed(&mut self) -> std::result::Result<f64, String> {
+        if !self.keyword("[") {
+            return Err("Expected '['".to_string());
+        }
+        let value = self.expression()?;
+        if !self.keyword("]") {
+            return Err("Missing ']'".to_string());
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> std::result::Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse::<f64>()
+            .map_err(|_| format!("Invalid number '{}'", text))
+    }
+
+    fn function(&mut self) -> std::result::Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect::<String>().to_ascii_uppercase();
+        let arg = self.bracketed()?;
+
+        let value = match name.as_str() {
+            "SIN" => arg.to_radians().sin(),
+            "COS" => arg.to_radians().cos(),
+            "TAN" => arg.to_radians().tan(),
+            "ASIN" => arg.asin().to_degrees(),
+            "ACOS" => arg.acos().to_degrees(),
+            "ATAN" => {
+                // ATAN[y]/[x] is the two-argument form
+                if self.keyword("/") {
+                    let x = self.bracketed()?;
+                    arg.atan2(x).to_degrees()
+                } else {
+                    arg.atan().to_degrees()
+                }
+            }
+            "SQRT" => arg.sqrt(),
+            "ABS" => arg.abs(),
+            "ROUND" => arg.round(),
+            "FIX" => arg.floor(),
+            "FUP" => arg.ceil(),
+            "EXP" => arg.exp(),
+            "LN" => arg.ln(),
+            _ => return Err(format!("Unknown function '{}'", name)),
+        };
+
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(format!("{} result is not a finite number", name))
+        }
+    }
+
+    /// Parse a variable reference after '#': `#100`, `#<name>` or `#[expr]`
+    fn variable_name(&mut self) -> std::result::Result<String, String> {
+        self.pos += 1;
+        match self.peek() {
+            Some('<') => {
+                let start = self.pos + 1;
+                let end = self.chars[start..]
+                    .iter()
+                    .position(|&c| c == '>')
+                    .map(|p| start + p)
+                    .ok_or_else(|| "Missing '>' in named variable".to_string())?;
+                self.pos = end + 1;
+                let name: String = self.chars[start..end].iter().filter(|c| !c.is_whitespace()).collect();
+                Ok(name.to_ascii_lowercase())
+            }
+            Some('[') => {
+                let number = self.bracketed()?;
+                Ok(format!("{}", number.round() as i64))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let number = self.number()?;
+                Ok(format!("{}", number as i64))
+            }
+            _ => Err("Expected a variable number or name after '#'".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbered_and_named_variables() {
+        let program = "#100 = 5\n#<depth> = -1.5\nG1 X#100 Z#<depth> F100\n";
+        let output = ExpressionEvaluator::new().evaluate(program).unwrap();
+
+        assert_eq!(output, "\n\nG1 X5 Z-1.5 F100\n");
+    }
+
+    #[test]
+    fn test_expressions() {
+        let evaluator = ExpressionEvaluator::new().with_variable("1", 4.0);
+        let output = evaluator
+            .evaluate("G1 X[#1 * 2 + 1] Y[2 ** 3] Z-[#1 / 8] F[SQRT[16] * 25]")
+            .unwrap();
+
+        assert_eq!(output, "G1 X9 Y8 Z-0.5 F100");
+        assert_eq!(evaluator.evaluate("X[COS[60]]").unwrap(), "X0.5");
+        assert_eq!(evaluator.evaluate("X[7 MOD 3]").unwrap(), "X1");
+    }
+
+    #[test]
+    fn test_assignment_takes_effect_after_line() {
+        let output = ExpressionEvaluator::new()
+            .evaluate("#1 = 1\n#1 = 2 G0 X#1\nG0 X#1")
+            .unwrap();
+
+        assert_eq!(output, "\nG0 X1\nG0 X2");
+    }
+
+    #[test]
+    fn test_unresolved_variables_are_reported() {
+        let err = ExpressionEvaluator::new()
+            .evaluate("G0 X#5\nG0 Y#<width> Z#5")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("#5 (line 1)"));
+        assert!(err.contains("#<width> (line 2)"));
+        assert!(err.contains("#5 (line 2)"));
+    }
+
+    #[test]
+    fn test_plain_lines_unchanged() {
+        let program = "G1 X1.23456 (note [x])\nG0 Z5 ; #1";
+        assert_eq!(ExpressionEvaluator::new().evaluate(program).unwrap(), program);
+    }
+}
@@ -0,0 +1,272 @@
+//! G-Code reformatting
+//!
+//! Cleans up a program line by line: renumbers or removes N words, strips
+//! comments and blank lines, normalizes spacing, case and word order, and
+//! rounds coordinates and feeds to a fixed precision. Lines the formatter
+//! does not understand, such as expressions, O-words and `%` markers, are
+//! kept as they are apart from their line number.
+
+/// Letters whose values are rounded to the configured precision
+const PRECISION_LETTERS: &str = "XYZABCUVWIJKRQF";
+
+/// Word order within a normalized line; letters not listed go last
+const WORD_ORDER: &str = "NGXYZABCUVWIJKRPQLHDFSTM";
+
+/// What to do with N line numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// Leave them as they are
+    Keep,
+    /// Remove them
+    Remove,
+    /// Number every code line from `start` in steps of `step`
+    Renumber {
+        /// First line number
+        start: u32,
+        /// Increment between lines
+        step: u32,
+    },
+}
+
+/// Program reformatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct GCodeFormatter {
+    line_numbers: LineNumbers,
+    strip_comments: bool,
+    strip_blank_lines: bool,
+    normalize: bool,
+    precision: Option<usize>,
+}
+
+impl Default for GCodeFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GCodeFormatter {
+    /// Create a formatter that leaves programs unchanged
+    pub fn new() -> Self {
+        Self {
+            line_numbers: LineNumbers::Keep,
+            strip_comments: false,
+            strip_blank_lines: false,
+            normalize: false,
+            precision: None,
+        }
+    }
+
+    /// Set how line numbers are handled
+    pub fn with_line_numbers(mut self, line_numbers: LineNumbers) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Remove comments; lines left empty are dropped
+    pub fn with_strip_comments(mut self, strip: bool) -> Self {
+        self.strip_comments = strip;
+        self
+    }
+
+    /// Remove blank lines
+    pub fn with_strip_blank_lines(mut self, strip: bool) -> Self {
+        self.strip_blank_lines = strip;
+        self
+    }
+
+    /// Uppercase words, separate them with single spaces and put them in a standard order
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Round coordinates and feeds to a number of decimals
+    pub fn with_precision(mut self, decimals: Option<usize>) -> Self {
+        self.precision = decimals;
+        self
+    }
+
+    /// Reformat a program
+    pub fn format(&self, program: &str) -> String {
+        let mut number = match self.line_numbers {
+            LineNumbers::Renumber { start, .. } => start,
+            _ => 0,
+        };
+        let mut output = Vec::new();
+
+        for line in program.lines() {
+            let formatted = match parse_line(line) {
+                Some(words) => self.format_words(words),
+                None => line.trim_end().to_string(),
+            };
+            if formatted.trim().is_empty() {
+                let dropped = self.strip_blank_lines || (self.strip_comments && !line.trim().is_empty());
+                if !dropped {
+                    output.push(String::new());
+                }
+                continue;
+            }
+            // Comments, program markers and O-word lines are not numbered
+            let numbered = !formatted.starts_with(['(', ';', '%', 'O', 'o']);
+            match self.line_numbers {
+                LineNumbers::Renumber { step, .. } if numbered => {
+                    output.push(format!("N{} {}", number, formatted));
+                    number += step;
+                }
+                _ => output.push(formatted),
+            }
+        }
+
+        let mut text = output.join("\n");
+        if program.ends_with('\n') {
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reassemble the words of a parsed line
+    fn format_words(&self, mut words: Vec<Word>) -> String {
+        if self.line_numbers != LineNumbers::Keep {
+            words.retain(|word| !matches!(word, Word::Code(letter, _) if letter.eq_ignore_ascii_case(&'N')));
+        }
+        if self.strip_comments {
+            words.retain(|word| !matches!(word, Word::Comment(_)));
+        }
+        if self.normalize {
+            // Comments move to the end of the line; codes keep their relative order within a letter
+            words.retain(|word| !matches!(word, Word::Space(_)));
+            words.sort_by_key(|word| match word {
+                Word::Code(letter, _) => {
+                    WORD_ORDER.find(letter.to_ascii_uppercase()).unwrap_or(WORD_ORDER.len())
+                }
+                _ => WORD_ORDER.len() + 1,
+            });
+        }
+
+        let mut parts = Vec::new();
+        for word in words {
+            let text = match word {
+                Word::Code(letter, value) => {
+                    let letter = if self.normalize { letter.to_ascii_uppercase() } else { letter };
+                    let value = match self.precision {
+                        Some(decimals) if PRECISION_LETTERS.contains(letter.to_ascii_uppercase()) => {
+                            round_value(&value, decimals)
+                        }
+                        _ => value,
+                    };
+                    format!("{}{}", letter, value)
+                }
+                Word::Comment(text) | Word::Space(text) => text,
+            };
+            parts.push(text);
+        }
+        parts.join(if self.normalize { " " } else { "" }).trim().to_string()
+    }
+}
+
+/// Part of a line
+#[derive(Debug, Clone, PartialEq)]
+enum Word {
+    /// Letter and the value as written
+    Code(char, String),
+    /// Comment including its delimiters
+    Comment(String),
+    /// Spacing between words
+    Space(String),
+}
+
+/// Split a line into words and comments, or `None` if it has anything else
+fn parse_line(line: &str) -> Option<Vec<Word>> {
+    let chars: Vec<char> = line.trim().chars().collect();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_whitespace() {
+            let start = i;
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            words.push(Word::Space(chars[start..i].iter().collect()));
+        } else if ch == '(' {
+            let end = chars[i..].iter().position(|c| *c == ')').map(|p| i + p + 1).unwrap_or(chars.len());
+            words.push(Word::Comment(chars[i..end].iter().collect()));
+            i = end;
+        } else if ch == ';' {
+            words.push(Word::Comment(chars[i..].iter().collect()));
+            i = chars.len();
+        } else if ch.is_ascii_alphabetic() {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | '-' | '+')) {
+                i += 1;
+            }
+            if i == start {
+                return None;
+            }
+            words.push(Word::Code(ch, chars[start..i].iter().collect()));
+        } else {
+            return None;
+        }
+    }
+    Some(words)
+}
+
+/// Round a number to at most `decimals` places, dropping trailing zeros
+fn round_value(value: &str, decimals: usize) -> String {
+    let Ok(number) = value.parse::<f64>() else {
+        return value.to_string();
+    };
+    let text = format!("{:.*}", decimals, number);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    };
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = "(header)\nN10 g1 x1.23456 y2  f500 (cut)\n\nN20 G0 Z5.0000\n#1 = 5\n";
+
+    #[test]
+    fn test_default_keeps_program() {
+        let program = "G1X1Y2\n(note)\n\nN5 G0  X1 ; rapid\n";
+        assert_eq!(GCodeFormatter::new().format(program), program);
+    }
+
+    #[test]
+    fn test_renumber_and_normalize() {
+        let formatted = GCodeFormatter::new()
+            .with_line_numbers(LineNumbers::Renumber { start: 100, step: 5 })
+            .with_normalize(true)
+            .with_strip_blank_lines(true)
+            .format(PROGRAM);
+        assert_eq!(formatted, "(header)\nN100 G1 X1.23456 Y2 F500 (cut)\nN105 G0 Z5.0000\nN110 #1 = 5\n");
+    }
+
+    #[test]
+    fn test_strip_comments_and_precision() {
+        let formatted = GCodeFormatter::new()
+            .with_line_numbers(LineNumbers::Remove)
+            .with_strip_comments(true)
+            .with_normalize(true)
+            .with_precision(Some(3))
+            .format(PROGRAM);
+        assert_eq!(formatted, "G1 X1.235 Y2 F500\n\nG0 Z5\n#1 = 5\n");
+    }
+
+    #[test]
+    fn test_word_order() {
+        let formatted = GCodeFormatter::new().with_normalize(true).format("F100 Y2 X1 M3 S1000 G1");
+        assert_eq!(formatted, "G1 X1 Y2 F100 S1000 M3");
+        assert_eq!(round_value("-0.0001", 3), "0");
+    }
+}
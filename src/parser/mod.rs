@@ -8,15 +8,61 @@
 //! - **Parser**: Converts tokens into structured commands
 //! - **Segment Generator**: Converts commands into motion segments
 //! - **Preprocessor**: Optimizes and transforms segments
+//!
+//! A job time estimate can be derived from the segments, and statistics
+//! for checking post-processor output from the text and segments together.
+//! The segments can be exported to CSV or JSON for external analysis.
+//!
+//! Program-level utilities such as multi-pass generation, reformatting, expression
+//! evaluation, subprogram / canned cycle expansion, backlash compensation,
+//! spindle speed calibration, plasma pierce handling, ramped or helical plunge
+//! entries, safe-Z retracts before rapids and dry runs above the stock operate on the G-Code
+//! text directly and produce a new program. The outline splits a program into its CAM operations for
+//! the editor. The compatibility report finds words from other G-Code
+//! flavors that GRBL would reject.
 
 mod tokenizer;
 mod parser;
 mod segment;
 mod preprocessor;
+mod multipass;
+mod formatter;
+mod backlash;
+mod expander;
+mod expression;
+mod estimate;
+mod export;
+mod statistics;
+mod compatibility;
+mod outline;
+mod rotary;
+mod spindle;
+mod plasma;
+mod entry;
+mod retract;
+mod dry_run;
 mod types;
 
 pub use tokenizer::{Token, Tokenizer};
 pub use parser::{Parser, ParsedCommand};
 pub use segment::{ArcDirection, Point3D, Segment, SegmentType};
-pub use preprocessor::Preprocessor;
+pub use preprocessor::{PlungeEntry, Preprocessor};
+pub use multipass::MultiPassGenerator;
+pub use formatter::{GCodeFormatter, LineNumbers};
+pub use backlash::BacklashCompensator;
+pub use expander::{ExpandedLine, ProgramExpander};
+pub use expression::ExpressionEvaluator;
+pub use estimate::JobEstimate;
+pub use export::{export_toolpath, save_toolpath, ToolpathFormat};
+pub use statistics::ProgramStatistics;
+pub use compatibility::{strip_line, strip_words, CompatibilityIssue, CompatibilityReport, Flavor, WordSummary};
+pub use outline::{marker_comments, operations, Operation};
+pub use rotary::{RotaryProjection, RotaryWrap};
+pub use spindle::{CalibrationPoint, SpindleCalibration};
+pub use plasma::{CutChartEntry, PlasmaPostProcessor, PlasmaSettings};
+pub use entry::PlungeRewriter;
+pub use retract::{RapidIssue, SafeRetract};
+pub use dry_run::DryRun;
 pub use types::*;
+
+pub(crate) use multipass::format_coord;
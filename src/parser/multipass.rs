@@ -0,0 +1,397 @@
+//! Multi-pass depth generation
+//!
+//! Many simple CAM outputs cut a 2D profile in a single pass at full depth.
+//! This module regenerates such a program as a series of Z passes with a
+//! configurable depth per pass and final depth, adding a lead-in move before
+//! every pass so the cutter never plunges straight down onto the profile.
+
+use super::tokenizer::{Token, Tokenizer};
+use super::types::Point3D;
+use crate::utils::error::{Error, Result};
+
+/// Multi-pass program generator
+#[derive(Debug, Clone)]
+pub struct MultiPassGenerator {
+    /// Maximum depth removed by a single pass
+    depth_per_pass: f64,
+    /// Total depth below the stock top
+    final_depth: f64,
+    /// Z coordinate of the stock top
+    stock_top: f64,
+    /// Safe Z height for moves between passes
+    safe_z: f64,
+    /// Feed rate used for plunging into each pass
+    plunge_feed: f64,
+    /// Length of the lead-in move along the first cut direction
+    lead_in_length: f64,
+}
+
+impl MultiPassGenerator {
+    /// Create a new generator for the given final depth and depth per pass
+    pub fn new(final_depth: f64, depth_per_pass: f64) -> Self {
+        Self {
+            depth_per_pass,
+            final_depth,
+            stock_top: 0.0,
+            safe_z: 5.0,
+            plunge_feed: 100.0,
+            lead_in_length: 2.0,
+        }
+    }
+
+    /// Set the Z coordinate of the stock top
+    pub fn with_stock_top(mut self, z: f64) -> Self {
+        self.stock_top = z;
+        self
+    }
+
+    /// Set the safe Z height used between passes
+    pub fn with_safe_z(mut self, z: f64) -> Self {
+        self.safe_z = z;
+        self
+    }
+
+    /// Set the plunge feed rate
+    pub fn with_plunge_feed(mut self, feed: f64) -> Self {
+        self.plunge_feed = feed;
+        self
+    }
+
+    /// Set the lead-in length (0 disables the lead-in)
+    pub fn with_lead_in_length(mut self, length: f64) -> Self {
+        self.lead_in_length = length;
+        self
+    }
+
+    /// Calculate the Z coordinate of every pass, ending at the final depth
+    pub fn pass_depths(&self) -> Result<Vec<f64>> {
+        if self.depth_per_pass <= 0.0 {
+            return Err(Error::config("Depth per pass must be greater than zero"));
+        }
+        if self.final_depth <= 0.0 {
+            return Err(Error::config("Final depth must be greater than zero"));
+        }
+
+        let count = (self.final_depth / self.depth_per_pass - 1e-9).ceil().max(1.0) as usize;
+        Ok((1..=count)
+            .map(|pass| {
+                let depth = (pass as f64 * self.depth_per_pass).min(self.final_depth);
+                self.stock_top - depth
+            })
+            .collect())
+    }
+
+    /// Regenerate a single-depth program as a multi-pass program
+    ///
+    /// The program must use absolute positioning and contain a single profile:
+    /// one plunge to the cutting depth, the XY cutting moves, and a retract.
+    /// Everything before the plunge and after the retract is kept unchanged.
+    pub fn generate(&self, program: &str) -> Result<String> {
+        let depths = self.pass_depths()?;
+        let lines: Vec<&str> = program.lines().collect();
+        let profile = find_profile(&lines)?;
+
+        let first_target = profile
+            .first_cut
+            .ok_or_else(|| Error::parse("Profile contains no XY cutting moves"))?;
+        let lead_in = self.lead_in_point(profile.start, first_target);
+
+        let mut output: Vec<String> = lines[..profile.plunge_line]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        for (pass, &z) in depths.iter().enumerate() {
+            output.push(format!("(Pass {} of {}, Z{})", pass + 1, depths.len(), format_coord(z)));
+            output.push(format!("G0 Z{}", format_coord(self.safe_z)));
+            output.push(format!(
+                "G0 X{} Y{}",
+                format_coord(lead_in.x),
+                format_coord(lead_in.y)
+            ));
+            output.push(format!(
+                "G1 Z{} F{}",
+                format_coord(z),
+                format_coord(self.plunge_feed)
+            ));
+
+            let feed = profile
+                .cut_feed
+                .map(|f| format!(" F{}", format_coord(f)))
+                .unwrap_or_default();
+            if self.lead_in_length > 0.0 {
+                output.push(format!(
+                    "G1 X{} Y{}{}",
+                    format_coord(profile.start.x),
+                    format_coord(profile.start.y),
+                    feed
+                ));
+            } else if !feed.is_empty() {
+                output.push(format!("G1{}", feed));
+            }
+
+            let offset = z - profile.cut_z;
+            for line in &lines[profile.plunge_line + 1..profile.retract_line] {
+                output.push(shift_z_words(line, offset));
+            }
+        }
+
+        output.extend(lines[profile.retract_line..].iter().map(|line| line.to_string()));
+
+        let mut result = output.join("\n");
+        if program.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+
+    /// Calculate the XY point the lead-in starts from
+    fn lead_in_point(&self, start: Point3D, first_target: Point3D) -> Point3D {
+        let dx = first_target.x - start.x;
+        let dy = first_target.y - start.y;
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if self.lead_in_length <= 0.0 || length < 1e-9 {
+            return start;
+        }
+
+        Point3D::new(
+            start.x - dx / length * self.lead_in_length,
+            start.y - dy / length * self.lead_in_length,
+            start.z,
+        )
+    }
+}
+
+/// Location of the single-depth profile within a program
+#[derive(Debug)]
+struct Profile {
+    /// Index of the line plunging to cutting depth
+    plunge_line: usize,
+    /// Index of the line retracting from cutting depth
+    retract_line: usize,
+    /// Cutting depth used by the original program
+    cut_z: f64,
+    /// XY position where the profile starts
+    start: Point3D,
+    /// Target of the first XY cutting move
+    first_cut: Option<Point3D>,
+    /// Last feed rate seen before or during the profile
+    cut_feed: Option<f64>,
+}
+
+/// Words of interest extracted from a single line
+#[derive(Debug, Default)]
+struct LineWords {
+    motion: Option<u32>,
+    x: Option<f64>,
+    y: Option<f64>,
+    z: Option<f64>,
+    feed: Option<f64>,
+    incremental: bool,
+}
+
+impl LineWords {
+    fn parse(line: &str) -> Self {
+        let mut words = Self::default();
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        for token in tokens {
+            match token {
+                Token::GCommand(n @ 0..=3) => words.motion = Some(n),
+                Token::GCommand(91) => words.incremental = true,
+                Token::FCommand(f) => words.feed = Some(f),
+                Token::Parameter { letter: 'X', value } => words.x = Some(value),
+                Token::Parameter { letter: 'Y', value } => words.y = Some(value),
+                Token::Parameter { letter: 'Z', value } => words.z = Some(value),
+                _ => {}
+            }
+        }
+
+        words
+    }
+}
+
+/// Scan a program and locate the plunge, cutting moves and retract of its profile
+fn find_profile(lines: &[&str]) -> Result<Profile> {
+    let mut position = Point3D::zero();
+    let mut motion = 0;
+    let mut feed = None;
+    let mut plunge: Option<(usize, f64, Point3D)> = None;
+    let mut first_cut = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let words = LineWords::parse(line);
+        if words.incremental {
+            return Err(Error::parse(
+                "Multi-pass generation requires absolute positioning (G90)",
+            ));
+        }
+
+        if let Some(m) = words.motion {
+            motion = m;
+        }
+        if words.feed.is_some() {
+            feed = words.feed;
+        }
+
+        let previous = position;
+        position.x = words.x.unwrap_or(position.x);
+        position.y = words.y.unwrap_or(position.y);
+        position.z = words.z.unwrap_or(position.z);
+
+        let has_motion = words.x.is_some() || words.y.is_some() || words.z.is_some();
+        if !has_motion {
+            continue;
+        }
+
+        match plunge {
+            None => {
+                let is_plunge = motion != 0
+                    && words.z.is_some()
+                    && position.z < previous.z
+                    && words.x.is_none()
+                    && words.y.is_none();
+                if is_plunge {
+                    plunge = Some((index, position.z, position));
+                }
+            }
+            Some((plunge_line, cut_z, start)) => {
+                if position.z > cut_z + 1e-9 {
+                    return Ok(Profile {
+                        plunge_line,
+                        retract_line: index,
+                        cut_z,
+                        start,
+                        first_cut,
+                        cut_feed: feed,
+                    });
+                }
+                if first_cut.is_none() && motion != 0 {
+                    first_cut = Some(position);
+                }
+            }
+        }
+    }
+
+    match plunge {
+        None => Err(Error::parse("No plunge into material found in program")),
+        Some(_) => Err(Error::parse("No retract found after the cutting moves")),
+    }
+}
+
+/// Shift every Z word on a line by an offset, leaving comments untouched
+fn shift_z_words(line: &str, offset: f64) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            ';' => {
+                result.extend(&chars[i..]);
+                break;
+            }
+            '(' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ')')
+                    .map(|p| i + p + 1)
+                    .unwrap_or(chars.len());
+                result.extend(&chars[i..end]);
+                i = end;
+            }
+            'Z' | 'z' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len()
+                    && (chars[end].is_ascii_digit() || matches!(chars[end], '.' | '-' | '+'))
+                {
+                    end += 1;
+                }
+                let number: String = chars[start..end].iter().collect();
+                match number.parse::<f64>() {
+                    Ok(value) => {
+                        result.push(ch);
+                        result.push_str(&format_coord(value + offset));
+                    }
+                    Err(_) => result.extend(&chars[i..end]),
+                }
+                i = end;
+            }
+            _ => {
+                result.push(ch);
+                i += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// Format a coordinate with three decimals and no trailing zeros
+pub(crate) fn format_coord(value: f64) -> String {
+    let formatted = format!("{:.3}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = "G21 G90\nM3 S10000\nG0 Z5\nG0 X0 Y0\nG1 Z-3 F100\nG1 X10 F500\nG1 Y10\nG1 X0\nG1 Y0\nG0 Z5\nM5\n";
+
+    #[test]
+    fn test_pass_depths() {
+        let generator = MultiPassGenerator::new(3.0, 1.0);
+        assert_eq!(generator.pass_depths().unwrap(), vec![-1.0, -2.0, -3.0]);
+
+        let generator = MultiPassGenerator::new(2.5, 1.0);
+        assert_eq!(generator.pass_depths().unwrap(), vec![-1.0, -2.0, -2.5]);
+
+        assert!(MultiPassGenerator::new(2.0, 0.0).pass_depths().is_err());
+    }
+
+    #[test]
+    fn test_generate_passes() {
+        let generator = MultiPassGenerator::new(3.0, 1.5).with_lead_in_length(2.0);
+        let output = generator.generate(PROFILE).unwrap();
+
+        assert!(output.starts_with("G21 G90\nM3 S10000\nG0 Z5\nG0 X0 Y0\n"));
+        assert!(output.contains("G1 Z-1.5 F100"));
+        assert!(output.contains("G1 Z-3 F100"));
+        assert_eq!(output.matches("G1 X10 F500").count(), 2);
+        assert_eq!(output.matches("G0 X-2 Y0").count(), 2);
+        assert!(output.ends_with("G0 Z5\nM5\n"));
+    }
+
+    #[test]
+    fn test_body_z_words_follow_pass() {
+        let program = "G0 X0 Y0\nG1 Z-2 F100\nG1 X5 Z-2\nG0 Z5\n";
+        let output = MultiPassGenerator::new(2.0, 1.0)
+            .with_lead_in_length(0.0)
+            .generate(program)
+            .unwrap();
+
+        assert!(output.contains("G1 X5 Z-1"));
+        assert!(output.contains("G1 X5 Z-2"));
+    }
+
+    #[test]
+    fn test_requires_absolute_positioning() {
+        let program = "G91\nG1 Z-2 F100\nG1 X5\nG0 Z5\n";
+        assert!(MultiPassGenerator::new(2.0, 1.0).generate(program).is_err());
+    }
+
+    #[test]
+    fn test_shift_z_words_skips_comments() {
+        assert_eq!(shift_z_words("G1 Z-1 (Z-1)", -1.0), "G1 Z-2 (Z-1)");
+        assert_eq!(shift_z_words("G1 X1 ; Z", 1.0), "G1 X1 ; Z");
+    }
+}
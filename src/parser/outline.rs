@@ -0,0 +1,213 @@
+//! Program outline
+//!
+//! Splits a program into the operations CAM software writes one after the
+//! other, so the editor can fold them and list them for navigation. An
+//! operation starts at a comment naming it, such as `(Operation: Pocket 1)`
+//! or `; Toolpath: Profile`, or at a tool change. A tool change right after
+//! an operation comment, before any other code, belongs to that operation.
+
+use std::collections::BTreeMap;
+
+/// Words that mark a comment as naming an operation
+const OPERATION_KEYWORDS: [&str; 4] = ["operation", "toolpath", "op:", "section"];
+
+/// A block of the program making up one operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    /// Name from the operation comment, or the tool change
+    pub name: String,
+    /// First line (0-based)
+    pub start: usize,
+    /// Last line (0-based, inclusive)
+    pub end: usize,
+    /// Tool loaded for the operation, if it changes tools
+    pub tool: Option<u32>,
+}
+
+impl Operation {
+    /// Number of lines in the operation
+    pub fn len(&self) -> usize {
+        self.end + 1 - self.start
+    }
+
+    /// Whether the operation has no lines (never the case for a found operation)
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Whether a line belongs to the operation
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+}
+
+/// Find the operations of a program; lines before the first are not part of any
+pub fn operations(program: &str) -> Vec<Operation> {
+    let mut operations: Vec<Operation> = Vec::new();
+    // Whether the current operation has code besides its header
+    let mut has_code = false;
+    let mut last_tool = None;
+    let mut line_count = 0;
+
+    for (index, line) in program.lines().enumerate() {
+        line_count = index + 1;
+        let (code, comment) = split_comment(line);
+        let code = code.to_ascii_uppercase();
+
+        if let Some(tool) = word_value(&code, 'T') {
+            last_tool = Some(tool as u32);
+        }
+
+        if code.trim().is_empty() {
+            let name = comment.trim();
+            let lower = name.to_lowercase();
+            if OPERATION_KEYWORDS.iter().any(|keyword| lower.starts_with(keyword)) {
+                start_operation(&mut operations, index, name.to_string(), None);
+                has_code = false;
+            }
+            continue;
+        }
+
+        if word_values(&code, 'M').any(|m| m == 6.0) {
+            match operations.last_mut() {
+                Some(current) if !has_code && current.tool.is_none() => current.tool = last_tool,
+                _ => {
+                    let name = match (last_tool, comment.trim()) {
+                        (Some(tool), "") => format!("Tool T{}", tool),
+                        (Some(tool), comment) => format!("Tool T{}: {}", tool, comment),
+                        (None, _) => "Tool change".to_string(),
+                    };
+                    start_operation(&mut operations, index, name, last_tool);
+                }
+            }
+        }
+        has_code = true;
+    }
+
+    if let Some(last) = operations.last_mut() {
+        last.end = line_count.saturating_sub(1).max(last.start);
+    }
+    operations
+}
+
+/// End the current operation before `line` and start a new one there
+fn start_operation(operations: &mut Vec<Operation>, line: usize, name: String, tool: Option<u32>) {
+    if let Some(previous) = operations.last_mut() {
+        previous.end = line.saturating_sub(1).max(previous.start);
+    }
+    operations.push(Operation { name, start: line, end: line, tool });
+}
+
+/// Comments starting with one of the markers, by line (0-based)
+///
+/// Markers match case-insensitively as the first word of a comment, e.g.
+/// "PAUSE" matches `(Pause: flip the part)` but not `(Pauses)`. The whole comment text is returned.
+pub fn marker_comments(program: &str, markers: &[String]) -> BTreeMap<usize, String> {
+    let markers: Vec<String> = markers
+        .iter()
+        .map(|marker| marker.trim().to_uppercase())
+        .filter(|marker| !marker.is_empty())
+        .collect();
+    if markers.is_empty() {
+        return BTreeMap::new();
+    }
+    program
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let (_, comment) = split_comment(line);
+            let comment = comment.trim();
+            let upper = comment.to_uppercase();
+            markers
+                .iter()
+                .any(|marker| {
+                    upper.starts_with(marker.as_str())
+                        && !upper[marker.len()..].starts_with(|c: char| c.is_alphanumeric())
+                })
+                .then(|| (index, comment.to_string()))
+        })
+        .collect()
+}
+
+/// Split a line into its code and the text of its comments
+pub(super) fn split_comment(line: &str) -> (String, String) {
+    let mut code = String::new();
+    let mut comment = String::new();
+    let mut in_paren = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            '(' if !in_paren => in_paren = true,
+            ')' if in_paren => in_paren = false,
+            ';' if !in_paren => {
+                comment.push_str(&line[index + 1..]);
+                break;
+            }
+            _ if in_paren => comment.push(ch),
+            _ => code.push(ch),
+        }
+    }
+    (code, comment)
+}
+
+/// Values of every word with the given letter in uppercase code
+fn word_values(code: &str, letter: char) -> impl Iterator<Item = f64> + '_ {
+    code.match_indices(letter).filter_map(|(index, _)| {
+        let number: String = code[index + 1..]
+            .chars()
+            .skip_while(|c| *c == ' ')
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        number.parse().ok()
+    })
+}
+
+/// Value of the first word with the given letter
+fn word_value(code: &str, letter: char) -> Option<f64> {
+    word_values(code, letter).next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_comments_and_tool_changes() {
+        let program = "G21 G90\n\
+            (Operation: Pocket 1)\n\
+            T1 M6\n\
+            G0 X0 Y0\n\
+            G1 Z-1 F100\n\
+            (Operation: Profile)\n\
+            G1 X10\n\
+            T2 M06 (3mm drill)\n\
+            G81 X5 Y5 Z-3 R1\n\
+            M30\n";
+        let ops = operations(program);
+        assert_eq!(ops.len(), 3);
+
+        assert_eq!(ops[0].name, "Operation: Pocket 1");
+        assert_eq!((ops[0].start, ops[0].end, ops[0].tool), (1, 4, Some(1)));
+        assert_eq!(ops[1].name, "Operation: Profile");
+        assert_eq!((ops[1].start, ops[1].end, ops[1].tool), (5, 6, None));
+        assert_eq!(ops[2].name, "Tool T2: 3mm drill");
+        assert_eq!((ops[2].start, ops[2].end, ops[2].tool), (7, 9, Some(2)));
+        assert!(ops[2].contains(9));
+        assert_eq!(ops[2].len(), 3);
+    }
+
+    #[test]
+    fn test_marker_comments() {
+        let program = "G0 X0\n(PAUSE: flip the part)\nG1 X1 ; check depth\n(Checkpoint)\n(note)";
+        let markers = vec!["pause".to_string(), "CHECK ".to_string(), String::new()];
+        let found = marker_comments(program, &markers);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[&1], "PAUSE: flip the part");
+        assert_eq!(found[&2], "check depth");
+        assert!(marker_comments(program, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_program_without_operations() {
+        assert!(operations("G0 X0\n(just a note)\nG1 X1 M60\n").is_empty());
+    }
+}
@@ -27,6 +27,10 @@ pub struct ParsedCommand {
     pub spindle_speed: Option<f64>,
     /// Comments
     pub comments: Vec<String>,
+    /// Whether the command is on a block delete ("/") line
+    pub block_delete: bool,
+    /// Line of the parsed text the command is on (0-based)
+    pub source_line: usize,
 }
 
 impl ParsedCommand {
@@ -41,6 +45,8 @@ impl ParsedCommand {
             feed_rate: None,
             spindle_speed: None,
             comments: Vec::new(),
+            block_delete: false,
+            source_line: 0,
         }
     }
 
@@ -76,10 +82,20 @@ pub struct ParserState {
     pub coordinate_system: CoordinateSystem,
     /// Current position
     pub position: Point3D,
+    /// Current rotary (A) axis angle in degrees
+    pub rotary: f64,
+    /// Whether the program has moved the rotary axis
+    pub rotary_used: bool,
     /// Current feed rate
     pub feed_rate: f64,
-    /// Current spindle speed
+    /// Current spindle speed (RPM, or surface speed in G96)
     pub spindle_speed: f64,
+    /// Whether S is a spindle speed or a surface speed
+    pub spindle_speed_mode: SpindleSpeedMode,
+    /// Spindle speed limit for constant surface speed (G96 D word, 0 for none)
+    pub max_spindle_speed: f64,
+    /// Lathe diameter mode (G7): X words are diameters
+    pub diameter_mode: bool,
     /// Spindle state
     pub spindle_state: SpindleState,
     /// Coolant state
@@ -100,8 +116,13 @@ impl ParserState {
             feed_rate_mode: FeedRateMode::UnitsPerMinute,
             coordinate_system: CoordinateSystem::default(),
             position: Point3D::zero(),
+            rotary: 0.0,
+            rotary_used: false,
             feed_rate: 0.0,
             spindle_speed: 0.0,
+            spindle_speed_mode: SpindleSpeedMode::Rpm,
+            max_spindle_speed: 0.0,
+            diameter_mode: false,
             spindle_state: SpindleState::Off,
             coolant_state: CoolantState::Off,
             tool: 0,
@@ -119,6 +140,8 @@ impl Default for ParserState {
 /// G-Code parser
 pub struct Parser {
     state: ParserState,
+    /// Skip block delete ("/") lines
+    skip_block_delete: bool,
 }
 
 impl Parser {
@@ -126,12 +149,28 @@ impl Parser {
     pub fn new() -> Self {
         Self {
             state: ParserState::new(),
+            skip_block_delete: false,
         }
     }
 
     /// Create a parser with a specific initial state
     pub fn with_state(state: ParserState) -> Self {
-        Self { state }
+        Self {
+            state,
+            skip_block_delete: false,
+        }
+    }
+
+    /// Set whether block delete ("/") lines are skipped
+    pub fn with_skip_block_delete(mut self, skip: bool) -> Self {
+        self.skip_block_delete = skip;
+        self
+    }
+
+    /// Set whether X words start out as diameters (lathe G7 mode)
+    pub fn with_diameter_mode(mut self, diameter_mode: bool) -> Self {
+        self.state.diameter_mode = diameter_mode;
+        self
     }
 
     /// Get the current parser state
@@ -144,13 +183,21 @@ impl Parser {
         let mut commands = Vec::new();
         let mut current_command = ParsedCommand::new();
         let mut has_content = false;
+        let mut block_delete = false;
+        let mut line = 0;
 
         for token in tokens {
+            if block_delete && self.skip_block_delete && *token != Token::EndOfLine {
+                continue;
+            }
+
             match token {
                 Token::GCommand(n) => {
                     if has_content {
                         commands.push(current_command);
                         current_command = ParsedCommand::new();
+                        current_command.block_delete = block_delete;
+                        current_command.source_line = line;
                     }
                     current_command.g_command = Some(*n);
                     has_content = true;
@@ -159,6 +206,8 @@ impl Parser {
                     if has_content {
                         commands.push(current_command);
                         current_command = ParsedCommand::new();
+                        current_command.block_delete = block_delete;
+                        current_command.source_line = line;
                     }
                     current_command.m_command = Some(*n);
                     has_content = true;
@@ -167,6 +216,8 @@ impl Parser {
                     if has_content {
                         commands.push(current_command);
                         current_command = ParsedCommand::new();
+                        current_command.block_delete = block_delete;
+                        current_command.source_line = line;
                     }
                     current_command.t_command = Some(*n);
                     has_content = true;
@@ -192,12 +243,19 @@ impl Parser {
                 Token::Checksum(_) => {
                     // Checksums are validated during tokenization, ignore here
                 }
+                Token::BlockDelete => {
+                    block_delete = true;
+                    current_command.block_delete = true;
+                }
                 Token::EndOfLine => {
                     if has_content {
                         commands.push(current_command);
                         current_command = ParsedCommand::new();
                         has_content = false;
                     }
+                    block_delete = false;
+                    line += 1;
+                    current_command.source_line = line;
                 }
             }
         }
@@ -258,6 +316,8 @@ impl Parser {
             }
             
             match g {
+                7 => self.state.diameter_mode = true,
+                8 => self.state.diameter_mode = false,
                 17 => self.state.plane = Plane::XY,
                 18 => self.state.plane = Plane::XZ,
                 19 => self.state.plane = Plane::YZ,
@@ -267,6 +327,14 @@ impl Parser {
                 91 => self.state.positioning_mode = PositioningMode::Relative,
                 93 => self.state.feed_rate_mode = FeedRateMode::InverseTime,
                 94 => self.state.feed_rate_mode = FeedRateMode::UnitsPerMinute,
+                95 => self.state.feed_rate_mode = FeedRateMode::UnitsPerRevolution,
+                96 => {
+                    self.state.spindle_speed_mode = SpindleSpeedMode::ConstantSurfaceSpeed;
+                    if let Some(d) = command.get_param('D') {
+                        self.state.max_spindle_speed = d;
+                    }
+                }
+                97 => self.state.spindle_speed_mode = SpindleSpeedMode::Rpm,
                 54 => self.state.coordinate_system = CoordinateSystem::G54,
                 55 => self.state.coordinate_system = CoordinateSystem::G55,
                 56 => self.state.coordinate_system = CoordinateSystem::G56,
@@ -313,6 +381,14 @@ impl Parser {
 
         // Calculate target position
         let target = self.calculate_target_position(command)?;
+        let rotary_start = self.state.rotary;
+        if let Some(a) = command.get_param('A') {
+            self.state.rotary_used = true;
+            self.state.rotary = match self.state.positioning_mode {
+                PositioningMode::Absolute => a,
+                PositioningMode::Relative => rotary_start + a,
+            };
+        }
 
         let segment = match g {
             0 => {
@@ -349,22 +425,78 @@ impl Parser {
         // Update position
         self.state.position = target;
 
-        // Add line number and spindle speed if available
+        // Add line number, spindle speed and rotary angles if available
         Ok(segment.map(|s| {
-            let mut seg = s.with_spindle_speed(self.state.spindle_speed);
+            let rpm = self.spindle_rpm(s.start.x);
+            let mut seg = s.with_spindle_speed(rpm);
+            if seg.is_cutting() {
+                seg.feed_rate = self.effective_feed_rate(command, seg.length(), rpm);
+                seg = seg.with_feed_rate_mode(self.state.feed_rate_mode);
+            }
+            if self.state.rotary_used {
+                seg = seg.with_rotary(rotary_start, self.state.rotary);
+            }
             if let Some(ln) = command.line_number {
                 seg = seg.with_line_number(ln);
             }
-            seg
+            seg.with_source_line(command.source_line)
         }))
     }
 
+    /// Feed rate of a cutting move in units per minute
+    ///
+    /// In inverse-time mode (G93) F is the reciprocal of the move time in
+    /// minutes, so the equivalent feed is the move length times F. GRBL
+    /// requires F on every G93 block; a missing one falls back to the last
+    /// F seen. In per-revolution mode (G95) F is multiplied by the spindle
+    /// speed, giving zero while the spindle speed is unknown.
+    fn effective_feed_rate(&self, command: &ParsedCommand, length: f64, rpm: f64) -> f64 {
+        match self.state.feed_rate_mode {
+            FeedRateMode::UnitsPerMinute => self.state.feed_rate,
+            FeedRateMode::InverseTime => {
+                let f = command.feed_rate.unwrap_or(self.state.feed_rate);
+                f * length
+            }
+            FeedRateMode::UnitsPerRevolution => self.state.feed_rate * rpm,
+        }
+    }
+
+    /// Spindle speed in RPM with the tool at radius `x`
+    ///
+    /// In constant surface speed mode (G96) S is in m/min (ft/min in G20)
+    /// and the speed rises as the tool nears the axis, capped by the G96 D
+    /// limit. At the axis itself only the limit is meaningful.
+    fn spindle_rpm(&self, x: f64) -> f64 {
+        match self.state.spindle_speed_mode {
+            SpindleSpeedMode::Rpm => self.state.spindle_speed,
+            SpindleSpeedMode::ConstantSurfaceSpeed => {
+                let diameter = 2.0 * x.abs();
+                if diameter < f64::EPSILON {
+                    return self.state.max_spindle_speed;
+                }
+                let units_per_surface_unit = match self.state.units {
+                    Units::Metric => 1000.0,
+                    Units::Imperial => 12.0,
+                };
+                let rpm = self.state.spindle_speed * units_per_surface_unit
+                    / (std::f64::consts::PI * diameter);
+                if self.state.max_spindle_speed > 0.0 {
+                    rpm.min(self.state.max_spindle_speed)
+                } else {
+                    rpm
+                }
+            }
+        }
+    }
+
     /// Calculate target position from command parameters
     fn calculate_target_position(&self, command: &ParsedCommand) -> Result<Point3D> {
         let mut target = self.state.position;
 
-        // Get coordinate values from parameters
-        let x = command.get_param('X');
+        // Get coordinate values from parameters, X as a radius in diameter mode
+        let x = command
+            .get_param('X')
+            .map(|x| if self.state.diameter_mode { x / 2.0 } else { x });
         let y = command.get_param('Y');
         let z = command.get_param('Z');
 
@@ -504,6 +636,66 @@ mod tests {
         assert_eq!(segments[0].feed_rate, 1000.0);
     }
 
+    #[test]
+    fn test_inverse_time_feed() {
+        // F2 on a 10 mm move means the move takes half a minute
+        let input = "G93\nG1 X10 F2\nG1 X15 F0.5\nG94\nG1 X20 F300";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        let segments = parser.generate_segments(&commands).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].feed_rate_mode, FeedRateMode::InverseTime);
+        assert!((segments[0].feed_rate - 20.0).abs() < 1e-9);
+        assert!((segments[0].estimated_time() - 30.0).abs() < 1e-9);
+        assert!((segments[1].estimated_time() - 120.0).abs() < 1e-9);
+        assert_eq!(segments[2].feed_rate_mode, FeedRateMode::UnitsPerMinute);
+        assert_eq!(segments[2].feed_rate, 300.0);
+    }
+
+    #[test]
+    fn test_units_per_revolution_feed() {
+        let input = "G95 M3 S1000\nG1 X10 F0.1\nS500\nG1 X20";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        let segments = parser.generate_segments(&commands).unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].feed_rate_mode, FeedRateMode::UnitsPerRevolution);
+        assert!((segments[0].feed_rate - 100.0).abs() < 1e-9);
+        assert!((segments[1].feed_rate - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lathe_diameter_and_surface_speed() {
+        let input = "G7\nG96 D2000 S100\nG0 X40 Z0\nG1 X20 F100\nG1 X2\nG8\nG97 S800\nG1 X5";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        let segments: Vec<Segment> = parser
+            .generate_segments(&commands)
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.length() > 0.0)
+            .collect();
+
+        // X40 in diameter mode is a radius of 20
+        assert_eq!(segments[0].end.x, 20.0);
+        assert_eq!(segments[1].end.x, 10.0);
+        // 100 m/min on a 40 mm diameter
+        let expected = 100.0 * 1000.0 / (std::f64::consts::PI * 40.0);
+        assert!((segments[1].spindle_speed - expected).abs() < 1e-6);
+        // G96 D sets the speed limit, G97 returns to plain RPM
+        assert_eq!(parser.state().max_spindle_speed, 2000.0);
+        assert_eq!(segments[3].spindle_speed, 800.0);
+        assert_eq!(segments[3].end.x, 5.0);
+    }
+
     #[test]
     fn test_modal_state() {
         let input = "G90\nG1 X10\nX20";
@@ -533,4 +725,35 @@ mod tests {
         assert_eq!(segments[0].end.x, 10.0);
         assert_eq!(segments[1].end.x, 20.0); // Relative to previous
     }
+
+    #[test]
+    fn test_block_delete_lines() {
+        let input = "G1 X10 F100\n/G1 X20\nG1 X30";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        assert_eq!(commands.len(), 3);
+        assert!(commands[1].block_delete);
+
+        let mut parser = Parser::new().with_skip_block_delete(true);
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        let segments = parser.generate_segments(&commands).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(segments[1].start.x, 10.0);
+        assert_eq!(segments[1].end.x, 30.0);
+    }
+
+    #[test]
+    fn test_source_lines() {
+        let input = "(header)\nG0 Z5\n\nN40 G1 X10 F100 G1 Y10\nX20";
+        let tokens = Tokenizer::new(input).tokenize().unwrap();
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        let segments = parser.generate_segments(&commands).unwrap();
+
+        let lines: Vec<Option<usize>> = segments.iter().map(|s| s.source_line).collect();
+        assert_eq!(lines, vec![Some(1), Some(3), Some(3), Some(4)]);
+        assert_eq!(segments[1].line_number, Some(40));
+    }
 }
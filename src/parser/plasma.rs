@@ -0,0 +1,330 @@
+//! Plasma cutting post-processing
+//!
+//! Plasma programs from simple CAM tools switch the torch with M3/M5 like a
+//! spindle and leave pierce handling to the operator. With a plasma profile
+//! every torch-on is replaced by a pierce sequence: rise to the pierce
+//! height, fire the torch, wait for the pierce delay and drop to the cut
+//! height. A cut chart maps the material thickness to the cutting feed and
+//! pierce delay. The pierce leaves the machine in G1 at the plunge feed, so
+//! the program's motion mode and feed are set again after it.
+
+use serde::{Deserialize, Serialize};
+
+use super::multipass::format_coord;
+use super::tokenizer::{Token, Tokenizer};
+
+/// Cutting parameters for one material thickness
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CutChartEntry {
+    /// Material name, e.g. "Mild steel"
+    pub material: String,
+    /// Material thickness (mm)
+    pub thickness: f64,
+    /// Cutting feed rate (mm/min)
+    pub feed_rate: f64,
+    /// Time to wait after firing the torch (seconds)
+    pub pierce_delay: f64,
+}
+
+/// Plasma torch settings of a machine profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlasmaSettings {
+    /// Post-process programs for plasma cutting when streaming
+    pub enabled: bool,
+    /// Commands firing the torch, in place of M3/M4
+    pub torch_on: String,
+    /// Commands switching the torch off, in place of M5
+    pub torch_off: String,
+    /// Torch height while piercing (mm)
+    pub pierce_height: f64,
+    /// Torch height while cutting (mm)
+    pub cut_height: f64,
+    /// Pierce delay used when the cut chart has no entry (seconds)
+    pub pierce_delay: f64,
+    /// Feed rate from the pierce height down to the cut height (mm/min)
+    pub plunge_feed: f64,
+    /// Thickness of the material being cut (mm)
+    pub thickness: f64,
+    /// Cutting parameters by material thickness
+    pub cut_chart: Vec<CutChartEntry>,
+}
+
+impl Default for PlasmaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            torch_on: "M3 S1000".to_string(),
+            torch_off: "M5".to_string(),
+            pierce_height: 3.8,
+            cut_height: 1.5,
+            pierce_delay: 0.5,
+            plunge_feed: 500.0,
+            thickness: 3.0,
+            cut_chart: Vec::new(),
+        }
+    }
+}
+
+impl PlasmaSettings {
+    /// Cut chart entry for the material thickness
+    ///
+    /// The thinnest entry at least as thick as the material is used, so an
+    /// in-between thickness gets the slower feed and longer delay.
+    pub fn chart_entry(&self) -> Option<&CutChartEntry> {
+        self.cut_chart
+            .iter()
+            .filter(|entry| entry.thickness >= self.thickness)
+            .min_by(|a, b| a.thickness.total_cmp(&b.thickness))
+    }
+
+    /// Pierce delay in seconds, from the cut chart if it has an entry
+    pub fn effective_pierce_delay(&self) -> f64 {
+        self.chart_entry().map_or(self.pierce_delay, |entry| entry.pierce_delay)
+    }
+
+    /// Lines replacing a torch-on
+    pub fn pierce_sequence(&self) -> Vec<String> {
+        let mut lines = vec![format!("G0 Z{}", format_coord(self.pierce_height))];
+        lines.extend(command_lines(&self.torch_on));
+        lines.push(format!("G4 P{}", format_coord(self.effective_pierce_delay())));
+        lines.push(format!(
+            "G1 Z{} F{}",
+            format_coord(self.cut_height),
+            format_coord(self.plunge_feed)
+        ));
+        lines
+    }
+}
+
+/// Applies a plasma profile to program lines, tracking modal state across lines
+///
+/// The pierce ends with a G1 at the plunge feed, so the program's motion
+/// mode and feed (or the chart feed) are set again after it.
+#[derive(Debug, Clone)]
+pub struct PlasmaPostProcessor {
+    settings: PlasmaSettings,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Program feed rate, once set
+    feed: Option<f64>,
+}
+
+impl PlasmaPostProcessor {
+    /// Create a pass applying `settings`
+    pub fn new(settings: PlasmaSettings) -> Self {
+        Self {
+            settings,
+            motion: None,
+            feed: None,
+        }
+    }
+
+    /// Post-process a single program line
+    ///
+    /// M3/M4 become the pierce sequence and M5 the torch-off commands. The
+    /// pierce goes before any motion on its line, so the torch fires where
+    /// the move starts; other words are sent first. With a cut chart entry
+    /// every F word is set to the chart feed. Lines that need no change, or
+    /// can't be tokenized, are returned as they are.
+    pub fn process_line(&mut self, line: &str) -> Vec<String> {
+        let Ok(tokens) = Tokenizer::new(line).tokenize() else {
+            return vec![line.to_string()];
+        };
+        for token in &tokens {
+            match token {
+                Token::GCommand(code @ 0..=3) => self.motion = Some(*code),
+                Token::GCommand(38 | 80..=89) => self.motion = None,
+                Token::FCommand(feed) => self.feed = Some(*feed),
+                _ => {}
+            }
+        }
+        let chart_feed = self.settings.chart_entry().map(|entry| entry.feed_rate);
+        let torch_on = tokens.iter().any(|t| matches!(t, Token::MCommand(3 | 4)));
+        let torch_off = tokens.iter().any(|t| matches!(t, Token::MCommand(5)));
+        let has_feed = tokens.iter().any(|t| matches!(t, Token::FCommand(_)));
+        if !torch_on && !torch_off && !(has_feed && chart_feed.is_some()) {
+            return vec![line.to_string()];
+        }
+
+        let rest: Vec<String> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::MCommand(3..=5) | Token::Checksum(_) | Token::EndOfLine => None,
+                Token::SCommand(_) if torch_on => None,
+                Token::FCommand(_) => Some(match chart_feed {
+                    Some(feed) => Token::FCommand(feed).to_string(),
+                    None => token.to_string(),
+                }),
+                _ => Some(token.to_string()),
+            })
+            .collect();
+        let rest_moves = tokens.iter().any(|t| {
+            matches!(t, Token::Parameter { letter: 'X' | 'Y' | 'Z' | 'A' | 'B' | 'C', .. })
+        });
+
+        let inserted = if torch_on {
+            self.settings.pierce_sequence()
+        } else if torch_off {
+            command_lines(&self.settings.torch_off)
+        } else {
+            Vec::new()
+        };
+        // A pierce goes before motion on its line, other words go first
+        let pierce_first = torch_on && rest_moves;
+        let mut lines = Vec::new();
+        if !pierce_first && !rest.is_empty() {
+            lines.push(rest.join(" "));
+        }
+        lines.extend(inserted.iter().cloned());
+        let pending: &[Token] = if pierce_first { &tokens } else { &[] };
+        lines.extend(self.restore_line(&inserted, pending));
+        if pierce_first {
+            lines.push(rest.join(" "));
+        }
+        lines
+    }
+
+    /// Line setting the program's motion mode and feed again after `inserted`
+    /// lines that changed them
+    ///
+    /// Words the program line still to be sent sets itself (in `pending`)
+    /// are left out.
+    fn restore_line(&self, inserted: &[String], pending: &[Token]) -> Option<String> {
+        let inserted: Vec<Token> = inserted
+            .iter()
+            .flat_map(|line| Tokenizer::new(line).tokenize().unwrap_or_default())
+            .collect();
+        let changes_motion = inserted.iter().any(|t| matches!(t, Token::GCommand(0..=3)));
+        let changes_feed = inserted.iter().any(|t| matches!(t, Token::FCommand(_)));
+        let sets_motion = pending.iter().any(|t| matches!(t, Token::GCommand(0..=3)));
+        let sets_feed = pending.iter().any(|t| matches!(t, Token::FCommand(_)));
+
+        let mut words = Vec::new();
+        if changes_motion && !sets_motion {
+            words.extend(self.motion.map(|motion| format!("G{}", motion)));
+        }
+        if changes_feed && !sets_feed {
+            let feed = self.settings.chart_entry().map(|entry| entry.feed_rate).or(self.feed);
+            words.extend(feed.map(|feed| format!("F{}", format_coord(feed))));
+        }
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+
+    /// Post-process a whole program
+    pub fn process(&mut self, program: &str) -> String {
+        program
+            .lines()
+            .flat_map(|line| self.process_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Split configured commands into lines, accepting ";" as a separator
+fn command_lines(commands: &str) -> Vec<String> {
+    commands
+        .split(['\n', ';'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> PlasmaSettings {
+        PlasmaSettings {
+            enabled: true,
+            cut_chart: vec![
+                CutChartEntry {
+                    material: "Mild steel".to_string(),
+                    thickness: 6.0,
+                    feed_rate: 1200.0,
+                    pierce_delay: 0.8,
+                },
+                CutChartEntry {
+                    material: "Mild steel".to_string(),
+                    thickness: 3.0,
+                    feed_rate: 2500.0,
+                    pierce_delay: 0.4,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chart_entry() {
+        let mut settings = settings();
+        assert_eq!(settings.chart_entry().unwrap().feed_rate, 2500.0);
+        settings.thickness = 4.0;
+        assert_eq!(settings.chart_entry().unwrap().feed_rate, 1200.0);
+        assert_eq!(settings.effective_pierce_delay(), 0.8);
+        settings.thickness = 10.0;
+        assert!(settings.chart_entry().is_none());
+        assert_eq!(settings.effective_pierce_delay(), 0.5);
+    }
+
+    #[test]
+    fn test_pierce_sequence() {
+        let mut plasma = PlasmaPostProcessor::new(settings());
+        assert_eq!(
+            plasma.process_line("M3 S1000"),
+            vec!["G0 Z3.8", "M3 S1000", "G4 P0.4", "G1 Z1.5 F500", "F2500"]
+        );
+        // The torch fires before the move on its line
+        assert_eq!(
+            plasma.process_line("G0 X10 M3"),
+            vec!["G0 Z3.8", "M3 S1000", "G4 P0.4", "G1 Z1.5 F500", "F2500", "G0 X10"]
+        );
+
+        let mut custom = PlasmaPostProcessor::new(PlasmaSettings {
+            torch_off: "M5; G0 Z10".to_string(),
+            ..settings()
+        });
+        assert_eq!(custom.process_line("M5"), vec!["M5", "G0 Z10"]);
+    }
+
+    #[test]
+    fn test_modes_restored() {
+        let no_chart = PlasmaSettings {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // An unfeeded G1 after the pierce cuts at the program feed, not the plunge feed
+        let program = "G1 F800\nG0 X0 Y0\nM3\nG1 X10";
+        assert_eq!(
+            PlasmaPostProcessor::new(no_chart.clone()).process(program),
+            "G1 F800\nG0 X0 Y0\nG0 Z3.8\nM3 S1000\nG4 P0.5\nG1 Z1.5 F500\nG0 F800\nG1 X10"
+        );
+
+        // A modal G0 move stays a rapid
+        let program = "G0 X5\nM3\nX10";
+        assert_eq!(
+            PlasmaPostProcessor::new(no_chart).process(program),
+            "G0 X5\nG0 Z3.8\nM3 S1000\nG4 P0.5\nG1 Z1.5 F500\nG0\nX10"
+        );
+
+        // With a cut chart the chart feed is set again
+        let program = "G1 X0 F800\nM3\nX10";
+        assert_eq!(
+            PlasmaPostProcessor::new(settings()).process(program),
+            "G1 X0 F2500\nG0 Z3.8\nM3 S1000\nG4 P0.4\nG1 Z1.5 F500\nG1 F2500\nX10"
+        );
+    }
+
+    #[test]
+    fn test_chart_feed() {
+        let mut plasma = PlasmaPostProcessor::new(settings());
+        assert_eq!(plasma.process_line("G1 X10 Y5 F800"), vec!["G1 X10 Y5 F2500"]);
+        assert_eq!(plasma.process_line("G1 X20 (cut)"), vec!["G1 X20 (cut)"]);
+
+        let mut no_chart = PlasmaPostProcessor::new(PlasmaSettings::default());
+        assert_eq!(no_chart.process_line("G1 X10 F800"), vec!["G1 X10 F800"]);
+        assert_eq!(no_chart.process("G1 X1\nM5"), "G1 X1\nM5");
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! This module provides preprocessing functionality for segments:
 //! - Arc expansion (converting arcs to line segments)
+//! - Plunge entry replacement (ramped or helical entries)
 //! - Unit conversion
 //! - Optimization (removing unnecessary rapids)
 
@@ -11,12 +12,36 @@ use super::segment::ArcDirection;
 use super::types::{Point3D, Units};
 use crate::utils::error::Result;
 
+/// Strategy used to enter material on straight plunges
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PlungeEntry {
+    /// Keep straight Z-only plunges unchanged
+    #[default]
+    Straight,
+    /// Zig-zag ramp along the first cut direction
+    Ramp {
+        /// Ramp angle in degrees from horizontal
+        angle: f64,
+        /// Length of each ramp leg
+        length: f64,
+    },
+    /// Helical descent on a circle starting at the plunge point
+    Helix {
+        /// Helix angle in degrees from horizontal
+        angle: f64,
+        /// Helix diameter
+        diameter: f64,
+    },
+}
+
 /// Preprocessor for optimizing and transforming segments
 pub struct Preprocessor {
     /// Arc precision (maximum deviation in units)
     arc_precision: f64,
     /// Target units for conversion
     target_units: Units,
+    /// Entry strategy for straight plunges
+    plunge_entry: PlungeEntry,
 }
 
 impl Preprocessor {
@@ -25,6 +50,7 @@ impl Preprocessor {
         Self {
             arc_precision: 0.1,
             target_units: Units::Metric,
+            plunge_entry: PlungeEntry::Straight,
         }
     }
 
@@ -40,16 +66,26 @@ impl Preprocessor {
         self
     }
 
+    /// Set the entry strategy for straight plunges
+    pub fn with_plunge_entry(mut self, entry: PlungeEntry) -> Self {
+        self.plunge_entry = entry;
+        self
+    }
+
     /// Process a list of segments
     pub fn process(&self, segments: &[Segment]) -> Result<Vec<Segment>> {
         let mut result = Vec::new();
 
-        for segment in segments {
+        for (index, segment) in segments.iter().enumerate() {
             match segment.segment_type {
                 SegmentType::ArcCW | SegmentType::ArcCCW => {
                     // Expand arcs into line segments
                     let expanded = self.expand_arc(segment)?;
-                    result.extend(expanded);
+                    result.extend(Self::spread_rotary(segment, expanded));
+                }
+                SegmentType::Linear if Self::is_plunge(segment) => {
+                    let entry = self.expand_plunge(segment, segments.get(index + 1));
+                    result.extend(Self::spread_rotary(segment, entry));
                 }
                 _ => {
                     result.push(segment.clone());
@@ -60,6 +96,158 @@ impl Preprocessor {
         Ok(result)
     }
 
+    /// Spread the rotary angles of a segment evenly over its replacement pieces
+    fn spread_rotary(source: &Segment, mut pieces: Vec<Segment>) -> Vec<Segment> {
+        if let Some((start, end)) = source.rotary {
+            let count = pieces.len().max(1) as f64;
+            for (i, piece) in pieces.iter_mut().enumerate() {
+                let t0 = i as f64 / count;
+                let t1 = (i + 1) as f64 / count;
+                piece.rotary = Some((start + (end - start) * t0, start + (end - start) * t1));
+            }
+        }
+        pieces
+    }
+
+    /// Check whether a segment is a straight Z-only feed move into material
+    ///
+    /// The material surface is assumed to be at work Z0.
+    pub(crate) fn is_plunge(segment: &Segment) -> bool {
+        const TOLERANCE: f64 = 0.0001;
+        (segment.start.x - segment.end.x).abs() < TOLERANCE
+            && (segment.start.y - segment.end.y).abs() < TOLERANCE
+            && segment.end.z < segment.start.z - TOLERANCE
+            && segment.end.z < -TOLERANCE
+    }
+
+    /// Replace a straight plunge with the configured entry strategy
+    pub(crate) fn expand_plunge(&self, plunge: &Segment, next: Option<&Segment>) -> Vec<Segment> {
+        if self.plunge_entry == PlungeEntry::Straight {
+            return vec![plunge.clone()];
+        }
+
+        // Descend straight through the air down to the material surface
+        let mut result = Vec::new();
+        let mut in_material = plunge.clone();
+        if plunge.start.z > 0.0 {
+            let surface = Point3D::new(plunge.start.x, plunge.start.y, 0.0);
+            result.push(self.entry_segment(plunge, plunge.start, surface));
+            in_material.start = surface;
+        }
+
+        result.extend(self.material_entry(&in_material, next));
+        result
+    }
+
+    /// Generate the entry moves for the part of a plunge below the surface
+    fn material_entry(&self, plunge: &Segment, next: Option<&Segment>) -> Vec<Segment> {
+        match self.plunge_entry {
+            PlungeEntry::Straight => vec![plunge.clone()],
+            PlungeEntry::Ramp { angle, length } => {
+                if angle <= 0.0 || angle >= 90.0 || length <= 0.0 {
+                    return vec![plunge.clone()];
+                }
+                let direction = next
+                    .filter(|s| s.segment_type != SegmentType::Rapid)
+                    .and_then(|s| {
+                        let dx = s.end.x - s.start.x;
+                        let dy = s.end.y - s.start.y;
+                        let len = (dx * dx + dy * dy).sqrt();
+                        (len > 0.0001).then(|| (dx / len, dy / len))
+                    })
+                    .unwrap_or((1.0, 0.0));
+                self.ramp_entry(plunge, angle, length, direction)
+            }
+            PlungeEntry::Helix { angle, diameter } => {
+                if angle <= 0.0 || angle >= 90.0 || diameter <= 0.0 {
+                    return vec![plunge.clone()];
+                }
+                self.helix_entry(plunge, angle, diameter)
+            }
+        }
+    }
+
+    /// Generate a zig-zag ramp that ends at the plunge target
+    fn ramp_entry(
+        &self,
+        plunge: &Segment,
+        angle: f64,
+        length: f64,
+        direction: (f64, f64),
+    ) -> Vec<Segment> {
+        let depth = plunge.start.z - plunge.end.z;
+        let leg_drop = length * angle.to_radians().tan();
+        let legs = (depth / leg_drop).ceil().max(1.0) as usize;
+        let drop = depth / legs as f64;
+
+        let far = (
+            plunge.start.x + direction.0 * length,
+            plunge.start.y + direction.1 * length,
+        );
+
+        let mut result = Vec::with_capacity(legs + 1);
+        let mut current = plunge.start;
+        for leg in 1..=legs {
+            let (x, y) = if leg % 2 == 1 {
+                far
+            } else {
+                (plunge.start.x, plunge.start.y)
+            };
+            let next = Point3D::new(x, y, plunge.start.z - drop * leg as f64);
+            result.push(self.entry_segment(plunge, current, next));
+            current = next;
+        }
+
+        // Return to the plunge point at full depth
+        if legs % 2 == 1 {
+            result.push(self.entry_segment(plunge, current, plunge.end));
+        }
+
+        result
+    }
+
+    /// Generate a helical descent that ends at the plunge target
+    fn helix_entry(&self, plunge: &Segment, angle: f64, diameter: f64) -> Vec<Segment> {
+        let radius = diameter / 2.0;
+        let depth = plunge.start.z - plunge.end.z;
+        let pitch = 2.0 * std::f64::consts::PI * radius * angle.to_radians().tan();
+        let turns = (depth / pitch).ceil().max(1.0) as usize;
+
+        // The helix circle passes through the plunge point
+        let center = Point3D::new(plunge.start.x - radius, plunge.start.y, plunge.start.z);
+        let steps_per_turn = self.calculate_arc_segments(radius);
+        let total_steps = steps_per_turn * turns;
+
+        let mut result = Vec::with_capacity(total_steps);
+        let mut current = plunge.start;
+        for step in 1..=total_steps {
+            let progress = step as f64 / total_steps as f64;
+            let angle = 2.0 * std::f64::consts::PI * step as f64 / steps_per_turn as f64;
+            let next = if step == total_steps {
+                plunge.end
+            } else {
+                Point3D::new(
+                    center.x + radius * angle.cos(),
+                    center.y + radius * angle.sin(),
+                    plunge.start.z - depth * progress,
+                )
+            };
+            result.push(self.entry_segment(plunge, current, next));
+            current = next;
+        }
+
+        result
+    }
+
+    /// Create an entry move inheriting feed, spindle and line numbers from the plunge
+    fn entry_segment(&self, plunge: &Segment, start: Point3D, end: Point3D) -> Segment {
+        let mut segment =
+            Segment::linear(start, end, plunge.feed_rate).with_spindle_speed(plunge.spindle_speed);
+        segment.line_number = plunge.line_number;
+        segment.source_line = plunge.source_line;
+        segment
+    }
+
     /// Expand an arc into line segments
     fn expand_arc(&self, arc: &Segment) -> Result<Vec<Segment>> {
         let center = match arc.center {
@@ -111,8 +299,10 @@ impl Preprocessor {
                 arc.start.z + z_step * i as f64,
             );
             
-            let segment = Segment::linear(current_pos, next_pos, arc.feed_rate)
+            let mut segment = Segment::linear(current_pos, next_pos, arc.feed_rate)
                 .with_spindle_speed(arc.spindle_speed);
+            segment.line_number = arc.line_number;
+            segment.source_line = arc.source_line;
             
             result.push(segment);
             current_pos = next_pos;
@@ -276,4 +466,90 @@ mod tests {
         // Should remove the duplicate rapid
         assert_eq!(optimized.len(), 2);
     }
+
+    #[test]
+    fn test_straight_plunge_unchanged() {
+        let segments = vec![Segment::linear(
+            Point3D::new(0.0, 0.0, 0.0),
+            Point3D::new(0.0, 0.0, -3.0),
+            100.0,
+        )];
+
+        let processed = Preprocessor::new().process(&segments).unwrap();
+        assert_eq!(processed.len(), 1);
+    }
+
+    #[test]
+    fn test_ramp_plunge_entry() {
+        let segments = vec![
+            Segment::linear(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, -3.0), 100.0),
+            Segment::linear(Point3D::new(0.0, 0.0, -3.0), Point3D::new(0.0, 20.0, -3.0), 500.0),
+        ];
+
+        let preprocessor = Preprocessor::new().with_plunge_entry(PlungeEntry::Ramp {
+            angle: 10.0,
+            length: 5.0,
+        });
+        let processed = preprocessor.process(&segments).unwrap();
+
+        // Ramp legs replace the plunge, followed by the original cut
+        assert!(processed.len() > 2);
+        let entry = &processed[..processed.len() - 1];
+        let last = entry.last().unwrap();
+        assert!(Preprocessor::points_equal(last.end, Point3D::new(0.0, 0.0, -3.0)));
+
+        // Ramp follows the first cut direction and respects the angle
+        assert!((entry[0].end.y - 5.0).abs() < 0.0001);
+        let max_slope = 10.0f64.to_radians().tan() + 0.0001;
+        for seg in entry {
+            let horizontal = ((seg.end.x - seg.start.x).powi(2) + (seg.end.y - seg.start.y).powi(2)).sqrt();
+            let vertical = (seg.start.z - seg.end.z).abs();
+            assert!(horizontal > 0.0);
+            assert!(vertical / horizontal <= max_slope);
+        }
+    }
+
+    #[test]
+    fn test_ramp_starts_at_surface() {
+        let segments = vec![Segment::linear(
+            Point3D::new(0.0, 0.0, 2.0),
+            Point3D::new(0.0, 0.0, -1.0),
+            100.0,
+        )];
+
+        let preprocessor = Preprocessor::new().with_plunge_entry(PlungeEntry::Ramp {
+            angle: 5.0,
+            length: 10.0,
+        });
+        let processed = preprocessor.process(&segments).unwrap();
+
+        // Straight descent through the air, then the ramp
+        assert!(Preprocessor::points_equal(processed[0].end, Point3D::new(0.0, 0.0, 0.0)));
+        assert!(processed.len() > 2);
+    }
+
+    #[test]
+    fn test_helix_plunge_entry() {
+        let segments = vec![Segment::linear(
+            Point3D::new(5.0, 5.0, 0.0),
+            Point3D::new(5.0, 5.0, -2.0),
+            100.0,
+        )
+        .with_line_number(7)];
+
+        let preprocessor = Preprocessor::new().with_plunge_entry(PlungeEntry::Helix {
+            angle: 3.0,
+            diameter: 6.0,
+        });
+        let processed = preprocessor.process(&segments).unwrap();
+
+        assert!(processed.len() > 1);
+        assert!(Preprocessor::points_equal(processed[0].start, Point3D::new(5.0, 5.0, 0.0)));
+        assert!(Preprocessor::points_equal(
+            processed.last().unwrap().end,
+            Point3D::new(5.0, 5.0, -2.0)
+        ));
+        assert!(processed.iter().all(|s| s.line_number == Some(7)));
+        assert!(processed.windows(2).all(|w| w[1].end.z <= w[0].end.z));
+    }
 }
@@ -0,0 +1,283 @@
+//! Safe-Z retracts before rapids
+//!
+//! Some CAM output rapids in XY while the tool is still below the stock
+//! surface, dragging it through material at full speed. This pass follows
+//! the programmed Z (work coordinates, with the surface at Z0) and finds
+//! every rapid that starts moving in XY below the surface. Fixing inserts a
+//! retract to the safe Z in front of such a rapid; the tool stays up until
+//! the next feed move, which is preceded by a feed back down to the depth
+//! the program expects unless it sets Z itself.
+//!
+//! Lines that move to machine or stored positions (G53, G28, G30, G38,
+//! G92, G10) are passed through and the tracked Z is forgotten.
+
+use super::multipass::format_coord;
+use super::tokenizer::{Token, Tokenizer};
+
+/// Tolerance below the surface before a rapid counts as in material
+const TOLERANCE: f64 = 0.0001;
+
+/// A rapid moving in XY below the stock surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RapidIssue {
+    /// Line of the program (0-based)
+    pub line: usize,
+    /// Z at the start of the rapid (program units)
+    pub depth: f64,
+}
+
+/// Finds and fixes rapids through material, tracking modal state across lines
+#[derive(Debug, Clone)]
+pub struct SafeRetract {
+    /// Retract height in work coordinates (mm)
+    safe_z: f64,
+    /// Absolute distance mode (G90)
+    absolute: bool,
+    /// Metric units (G21)
+    metric: bool,
+    /// Modal motion mode (G0-G3), if any
+    motion: Option<u32>,
+    /// Programmed Z, once known
+    z: Option<f64>,
+    /// Modal feed rate, once set
+    feed: Option<f64>,
+    /// Depth to feed back to before the next cut, while retracted
+    retracted: Option<f64>,
+}
+
+impl SafeRetract {
+    /// Create a pass retracting to `safe_z` millimeters above the work zero
+    pub fn new(safe_z: f64) -> Self {
+        Self {
+            safe_z: safe_z.max(0.0),
+            absolute: true,
+            metric: true,
+            motion: None,
+            z: None,
+            feed: None,
+            retracted: None,
+        }
+    }
+
+    /// Rapids through material in a program
+    pub fn check(&mut self, program: &str) -> Vec<RapidIssue> {
+        program
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                let (_, depth) = self.step(text);
+                depth.map(|depth| RapidIssue { line, depth })
+            })
+            .collect()
+    }
+
+    /// Fix a whole program
+    pub fn apply(&mut self, program: &str) -> String {
+        program
+            .lines()
+            .flat_map(|line| self.process_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Fix one line, returning the lines to send in its place
+    pub fn process_line(&mut self, line: &str) -> Vec<String> {
+        self.step(line).0
+    }
+
+    /// Safe Z in program units
+    fn safe(&self) -> f64 {
+        if self.metric {
+            self.safe_z
+        } else {
+            self.safe_z / 25.4
+        }
+    }
+
+    /// Z word moving between two absolute heights in the current distance mode
+    fn z_word(&self, from: f64, to: f64) -> String {
+        let value = if self.absolute { to } else { to - from };
+        format!("Z{}", format_coord(value))
+    }
+
+    /// Process one line, returning its replacement and the depth of a rapid through material
+    fn step(&mut self, line: &str) -> (Vec<String>, Option<f64>) {
+        let unchanged = vec![line.to_string()];
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('$') || trimmed.starts_with('%') {
+            return (unchanged, None);
+        }
+        let tokens = Tokenizer::new(line).tokenize().unwrap_or_default();
+
+        let mut passthrough = false;
+        let mut explicit_motion = false;
+        let (mut has_xy, mut z_value) = (false, None);
+        for token in &tokens {
+            match token {
+                Token::GCommand(code) => match code {
+                    0..=3 => {
+                        self.motion = Some(*code);
+                        explicit_motion = true;
+                    }
+                    20 => self.metric = false,
+                    21 => self.metric = true,
+                    90 => self.absolute = true,
+                    91 => self.absolute = false,
+                    10 | 28 | 30 | 38 | 53 | 92 => passthrough = true,
+                    80 => self.motion = None,
+                    _ => {}
+                },
+                Token::FCommand(feed) => self.feed = Some(*feed),
+                Token::Parameter { letter, value } => match letter.to_ascii_uppercase() {
+                    'X' | 'Y' => has_xy = true,
+                    'Z' => z_value = Some(*value),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if passthrough {
+            // The tool ends up somewhere this pass can't follow
+            if z_value.is_some() || explicit_motion {
+                self.z = None;
+                self.retracted = None;
+            }
+            return (unchanged, None);
+        }
+        if !has_xy && z_value.is_none() && !explicit_motion {
+            return (unchanged, None);
+        }
+        let Some(motion) = self.motion else {
+            return (unchanged, None);
+        };
+
+        let start = self.z;
+        let end = match (z_value, start) {
+            (Some(value), _) if self.absolute => Some(value),
+            (Some(value), Some(start)) => Some(start + value),
+            (Some(_), None) => None,
+            (None, start) => start,
+        };
+        self.z = end;
+        let safe = self.safe();
+
+        if motion == 0 {
+            let Some(start) = start.filter(|z| has_xy && *z < -TOLERANCE) else {
+                if z_value.is_some() && self.absolute {
+                    self.retracted = None;
+                }
+                return (unchanged, None);
+            };
+            let end = end.unwrap_or(start);
+            let mut lines = Vec::new();
+            if self.retracted.is_none() {
+                lines.push(format!("G0 {}", self.z_word(start, safe)));
+            }
+            // Stay up until the next cut instead of rapiding down into material
+            let rapid = match z_value {
+                Some(_) if end < -TOLERANCE => replace_z_word(line, &self.z_word(safe, safe)),
+                _ => line.to_string(),
+            };
+            lines.push(rapid);
+            self.retracted = (end < -TOLERANCE).then_some(end);
+            return (lines, Some(start));
+        }
+
+        // A feed move ends the retract, feeding back down first unless it sets Z itself
+        let Some(depth) = self.retracted.take() else {
+            return (unchanged, None);
+        };
+        if z_value.is_some() && self.absolute {
+            return (unchanged, None);
+        }
+        match self.feed {
+            Some(feed) => (vec![format!("G1 {} F{}", self.z_word(safe, depth), format_coord(feed)), line.to_string()], None),
+            None => (unchanged, None),
+        }
+    }
+}
+
+/// Replace the Z word of a line, leaving comments untouched
+fn replace_z_word(line: &str, word: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len());
+    let mut in_comment = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            ';' if !in_comment => {
+                result.extend(&chars[i..]);
+                break;
+            }
+            '(' => in_comment = true,
+            ')' if in_comment => in_comment = false,
+            'Z' | 'z' if !in_comment => {
+                let mut end = i + 1;
+                while end < chars.len() && (chars[end].is_ascii_digit() || matches!(chars[end], '.' | '-' | '+' | ' ')) {
+                    end += 1;
+                }
+                result.push_str(word);
+                if chars.get(end - 1) == Some(&' ') && end < chars.len() {
+                    result.push(' ');
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        result.push(ch);
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = "G21 G90\nG0 Z5\nG0 X0 Y0\nG1 Z-2 F300\nG1 X10\nG0 X20 Y5\nG1 X30\nG0 Z5";
+
+    #[test]
+    fn test_check() {
+        let issues = SafeRetract::new(5.0).check(PROGRAM);
+        assert_eq!(issues, vec![RapidIssue { line: 5, depth: -2.0 }]);
+
+        let clean = "G0 Z5\nG1 Z-1 F100\nG1 X5\nG0 Z5\nG0 X20\nG53 G0 Z0\nG0 X0";
+        assert!(SafeRetract::new(5.0).check(clean).is_empty());
+    }
+
+    #[test]
+    fn test_fix_feeds_back_down() {
+        let fixed = SafeRetract::new(5.0).apply(PROGRAM);
+        assert_eq!(
+            fixed,
+            "G21 G90\nG0 Z5\nG0 X0 Y0\nG1 Z-2 F300\nG1 X10\nG0 Z5\nG0 X20 Y5\nG1 Z-2 F300\nG1 X30\nG0 Z5"
+        );
+
+        // A cut that sets Z itself needs no feed back down
+        let program = "G0 Z1\nG1 Z-1 F100\nG0 X5\nG1 X6 Z-1.5";
+        assert_eq!(
+            SafeRetract::new(5.0).apply(program),
+            "G0 Z1\nG1 Z-1 F100\nG0 Z5\nG0 X5\nG1 X6 Z-1.5"
+        );
+    }
+
+    #[test]
+    fn test_rapid_into_material() {
+        let program = "G0 Z1\nG1 Z-1 F100\nG0 X5 Y5 Z-1 (next pocket)\nX8\nG1 X10";
+        assert_eq!(
+            SafeRetract::new(2.0).apply(program),
+            "G0 Z1\nG1 Z-1 F100\nG0 Z2\nG0 X5 Y5 Z2 (next pocket)\nX8\nG1 Z-1 F100\nG1 X10"
+        );
+    }
+
+    #[test]
+    fn test_relative_and_inch() {
+        let program = "G20 G91\nG0 Z0.5\nG1 Z-0.6 F10\nG0 X1";
+        let mut pass = SafeRetract::new(25.4);
+        pass.z = Some(0.0);
+        assert_eq!(pass.apply(program), "G20 G91\nG0 Z0.5\nG1 Z-0.6 F10\nG0 Z1.1\nG0 X1");
+    }
+}
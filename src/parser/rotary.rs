@@ -0,0 +1,137 @@
+//! Rotary axis visualization
+//!
+//! Rotary engraving programs move the A axis (rotation about X) while X, Y
+//! and Z stay close to a line, which previews as a flat tangle. These
+//! transforms map the A angle onto the stock surface using the cylinder
+//! diameter so the preview shows the finished part.
+
+use super::{Point3D, Segment, SegmentType};
+
+/// Largest A step per piece when wrapping onto the cylinder (degrees)
+const MAX_WRAP_STEP: f64 = 5.0;
+
+/// How A moves are projected for the preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotaryProjection {
+    /// Roll the cylinder out flat: A becomes distance along Y
+    Unwrapped,
+    /// Draw the toolpath around the cylinder in 3D
+    Wrapped,
+}
+
+/// Maps A axis moves onto a cylinder for visualization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotaryWrap {
+    /// Projection to apply
+    pub projection: RotaryProjection,
+    /// Stock diameter at Z0
+    pub diameter: f64,
+}
+
+impl RotaryWrap {
+    /// Create a wrap for a stock diameter
+    pub fn new(projection: RotaryProjection, diameter: f64) -> Self {
+        Self { projection, diameter }
+    }
+
+    /// Project segments, leaving programs without A moves unchanged
+    pub fn apply(&self, segments: &[Segment]) -> Vec<Segment> {
+        if segments.iter().all(|s| s.rotary.is_none()) {
+            return segments.to_vec();
+        }
+
+        let mut result = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let (a_start, a_end) = segment.rotary.unwrap_or((0.0, 0.0));
+            let steps = match self.projection {
+                RotaryProjection::Unwrapped => 1,
+                RotaryProjection::Wrapped => ((a_end - a_start).abs() / MAX_WRAP_STEP).ceil().max(1.0) as usize,
+            };
+
+            for step in 0..steps {
+                let t0 = step as f64 / steps as f64;
+                let t1 = (step + 1) as f64 / steps as f64;
+                let mut piece = segment.clone();
+                if piece.center.take().is_some() {
+                    piece.segment_type = SegmentType::Linear;
+                }
+                piece.start = self.map(lerp(segment.start, segment.end, t0), a_start + (a_end - a_start) * t0);
+                piece.end = self.map(lerp(segment.start, segment.end, t1), a_start + (a_end - a_start) * t1);
+                result.push(piece);
+            }
+        }
+        result
+    }
+
+    /// Map a point at an A angle (degrees) onto the projection
+    fn map(&self, point: Point3D, a: f64) -> Point3D {
+        match self.projection {
+            RotaryProjection::Unwrapped => {
+                let surface = a.to_radians() * self.diameter / 2.0;
+                Point3D::new(point.x, point.y + surface, point.z)
+            }
+            RotaryProjection::Wrapped => {
+                let radius = self.diameter / 2.0 + point.z;
+                let angle = a.to_radians();
+                Point3D::new(point.x, point.y + radius * angle.sin(), radius * angle.cos())
+            }
+        }
+    }
+}
+
+/// Linear interpolation between two points
+fn lerp(a: Point3D, b: Point3D, t: f64) -> Point3D {
+    Point3D::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t, a.z + (b.z - a.z) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, Tokenizer};
+
+    fn segments(program: &str) -> Vec<Segment> {
+        let tokens = Tokenizer::new(program).tokenize().unwrap();
+        let mut parser = Parser::new();
+        let commands = parser.parse_tokens(&tokens).unwrap();
+        parser.generate_segments(&commands).unwrap()
+    }
+
+    #[test]
+    fn test_parser_tracks_rotary() {
+        let segments = segments("G0 X0 Z1\nG1 Z-0.5 F100\nG1 X10 A90\nG91 G1 A-45\n");
+        assert!(segments[0].rotary.is_none());
+        assert_eq!(segments[2].rotary, Some((0.0, 90.0)));
+        assert_eq!(segments[3].rotary, Some((90.0, 45.0)));
+    }
+
+    #[test]
+    fn test_unwrapped() {
+        let segments = segments("G1 X10 A180 F100\n");
+        let wrap = RotaryWrap::new(RotaryProjection::Unwrapped, 20.0);
+        let result = wrap.apply(&segments);
+        assert_eq!(result.len(), 1);
+        // Half a turn of a 20mm cylinder is 10*pi along the surface
+        assert!((result[0].end.y - 10.0 * std::f64::consts::PI).abs() < 1e-9);
+        assert_eq!(result[0].end.x, 10.0);
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let segments = segments("G1 Z-1 F100\nG1 A90\n");
+        let wrap = RotaryWrap::new(RotaryProjection::Wrapped, 20.0);
+        let result = wrap.apply(&segments);
+        // The Z move has no A motion; the A move is split into 5 degree pieces
+        assert_eq!(result.len(), 1 + 18);
+        let end = result.last().unwrap().end;
+        assert!((end.y - 9.0).abs() < 1e-9);
+        assert!(end.z.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_without_rotary_unchanged() {
+        let segments = segments("G1 X10 Y5 F100\n");
+        let result = RotaryWrap::new(RotaryProjection::Wrapped, 20.0).apply(&segments);
+        assert_eq!(result[0].end.y, 5.0);
+        assert_eq!(result[0].end.z, 0.0);
+    }
+}
@@ -3,7 +3,7 @@
 //! This module defines the different types of motion segments that can be
 //! generated from parsed G-Code commands.
 
-pub use super::types::{ArcDirection, Point3D};
+pub use super::types::{ArcDirection, FeedRateMode, Point3D};
 
 /// Type of motion segment
 #[derive(Debug, Clone, PartialEq)]
@@ -30,11 +30,20 @@ pub struct Segment {
     /// Center point (for arcs only)
     pub center: Option<Point3D>,
     /// Feed rate (units per minute)
+    ///
+    /// Inverse-time (G93) and per-revolution (G95) feeds are converted when
+    /// the segment is generated, see `feed_rate_mode`.
     pub feed_rate: f64,
+    /// Feed rate mode the segment was programmed in
+    pub feed_rate_mode: FeedRateMode,
     /// Spindle speed (RPM)
     pub spindle_speed: f64,
     /// Line number in original G-Code (if available)
     pub line_number: Option<u32>,
+    /// Rotary (A) axis angle at the start and end, in degrees, for programs using A
+    pub rotary: Option<(f64, f64)>,
+    /// Line of the parsed program the segment came from (0-based)
+    pub source_line: Option<usize>,
 }
 
 impl Segment {
@@ -46,8 +55,11 @@ impl Segment {
             end,
             center: None,
             feed_rate: 0.0,
+            feed_rate_mode: FeedRateMode::UnitsPerMinute,
             spindle_speed: 0.0,
             line_number: None,
+            rotary: None,
+            source_line: None,
         }
     }
 
@@ -59,8 +71,11 @@ impl Segment {
             end,
             center: None,
             feed_rate,
+            feed_rate_mode: FeedRateMode::UnitsPerMinute,
             spindle_speed: 0.0,
             line_number: None,
+            rotary: None,
+            source_line: None,
         }
     }
 
@@ -83,8 +98,11 @@ impl Segment {
             end,
             center: Some(center),
             feed_rate,
+            feed_rate_mode: FeedRateMode::UnitsPerMinute,
             spindle_speed: 0.0,
             line_number: None,
+            rotary: None,
+            source_line: None,
         }
     }
 
@@ -158,6 +176,24 @@ impl Segment {
         self.spindle_speed = speed;
         self
     }
+
+    /// Set the feed rate mode the segment was programmed in
+    pub fn with_feed_rate_mode(mut self, mode: FeedRateMode) -> Self {
+        self.feed_rate_mode = mode;
+        self
+    }
+
+    /// Set the rotary (A) axis angles at the start and end
+    pub fn with_rotary(mut self, start: f64, end: f64) -> Self {
+        self.rotary = Some((start, end));
+        self
+    }
+
+    /// Set the program line the segment came from
+    pub fn with_source_line(mut self, line: usize) -> Self {
+        self.source_line = Some(line);
+        self
+    }
 }
 
 #[cfg(test)]
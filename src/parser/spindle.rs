@@ -0,0 +1,216 @@
+//! Spindle speed calibration
+//!
+//! Cheap spindles and VFD/PWM chains rarely turn at the commanded speed. A
+//! calibration table pairs commanded S values with the RPM measured with a
+//! tachometer; streaming then rewrites each S word to the value that makes
+//! the spindle actually reach the programmed speed, interpolating linearly
+//! between points.
+
+use serde::{Deserialize, Serialize};
+
+/// A measured point of the calibration table
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    /// Commanded S value
+    pub commanded: f64,
+    /// Spindle speed measured at that S value (RPM)
+    pub measured: f64,
+}
+
+/// Calibration table mapping programmed spindle speeds to S values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpindleCalibration {
+    /// Rewrite S words while streaming
+    pub enabled: bool,
+    /// Measured points
+    pub points: Vec<CalibrationPoint>,
+    /// Controller maximum spindle speed ($30), for the PWM preview
+    pub pwm_max: f64,
+    /// Controller minimum spindle speed ($31), for the PWM preview
+    pub pwm_min: f64,
+}
+
+impl Default for SpindleCalibration {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            points: Vec::new(),
+            pwm_max: 1000.0,
+            pwm_min: 0.0,
+        }
+    }
+}
+
+impl SpindleCalibration {
+    /// Points sorted by measured speed, ignoring unusable ones
+    fn sorted_points(&self) -> Vec<CalibrationPoint> {
+        let mut points: Vec<CalibrationPoint> = self
+            .points
+            .iter()
+            .copied()
+            .filter(|p| p.commanded.is_finite() && p.measured.is_finite() && p.measured >= 0.0)
+            .collect();
+        points.sort_by(|a, b| a.measured.total_cmp(&b.measured));
+        points
+    }
+
+    /// Whether the table has enough points to interpolate
+    pub fn is_usable(&self) -> bool {
+        self.sorted_points().len() >= 2
+    }
+
+    /// Lowest and highest measured speed
+    pub fn range(&self) -> Option<(f64, f64)> {
+        let points = self.sorted_points();
+        if points.len() < 2 {
+            return None;
+        }
+        Some((points[0].measured, points[points.len() - 1].measured))
+    }
+
+    /// S value to command so the spindle turns at `rpm`
+    ///
+    /// Outside the calibrated range the nearest segment is extrapolated.
+    /// Zero always maps to zero so the spindle can be stopped.
+    pub fn commanded_for(&self, rpm: f64) -> Option<f64> {
+        if rpm <= 0.0 {
+            return Some(0.0);
+        }
+        let points = self.sorted_points();
+        if points.len() < 2 {
+            return None;
+        }
+        let segment = points
+            .windows(2)
+            .find(|pair| rpm <= pair[1].measured)
+            .unwrap_or(&points[points.len() - 2..]);
+        let (a, b) = (segment[0], segment[1]);
+        let span = b.measured - a.measured;
+        if span.abs() < f64::EPSILON {
+            return Some(a.commanded);
+        }
+        let t = (rpm - a.measured) / span;
+        Some((a.commanded + t * (b.commanded - a.commanded)).max(0.0))
+    }
+
+    /// PWM duty cycle (0-100 %) GRBL outputs for an S value
+    pub fn pwm_duty(&self, commanded: f64) -> f64 {
+        let span = self.pwm_max - self.pwm_min;
+        if commanded <= 0.0 || span <= 0.0 {
+            return 0.0;
+        }
+        ((commanded - self.pwm_min) / span * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Rewrite the S word of a line to its calibrated value
+    ///
+    /// Comments and `$` system commands are left untouched.
+    pub fn calibrate_line(&self, line: &str) -> String {
+        if !self.enabled || line.trim_start().starts_with('$') {
+            return line.to_string();
+        }
+        let mut result = String::with_capacity(line.len());
+        let mut chars = line.char_indices().peekable();
+        let mut in_comment = false;
+        while let Some((index, ch)) = chars.next() {
+            match ch {
+                ';' if !in_comment => {
+                    result.push_str(&line[index..]);
+                    break;
+                }
+                '(' => in_comment = true,
+                ')' if in_comment => in_comment = false,
+                'S' | 's' if !in_comment => {
+                    let start = index + 1;
+                    let mut end = start;
+                    while let Some(&(next, c)) = chars.peek() {
+                        if c.is_ascii_digit() || c == '.' || (c == ' ' && end == start) {
+                            end = next + c.len_utf8();
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    let value = line[start..end].trim().parse::<f64>().ok();
+                    match value.and_then(|rpm| self.commanded_for(rpm)) {
+                        Some(commanded) => result.push_str(&format!("{}{:.0}", ch, commanded)),
+                        None => result.push_str(&line[index..end]),
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            result.push(ch);
+        }
+        result
+    }
+
+    /// First program line whose S word lies outside the calibrated range
+    ///
+    /// Returns the 0-based line index and the programmed speed. S0 is
+    /// always allowed.
+    pub fn out_of_range(&self, program: &str) -> Option<(usize, f64)> {
+        let (low, high) = self.range()?;
+        program.lines().enumerate().find_map(|(index, line)| {
+            let text = crate::grbl::ProgramStreamer::prepare_line(line, Default::default())?;
+            let rpm = crate::grbl::word_value(&text, 'S')?;
+            (rpm > 0.0 && (rpm < low || rpm > high)).then_some((index, rpm))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibration() -> SpindleCalibration {
+        SpindleCalibration {
+            enabled: true,
+            points: vec![
+                CalibrationPoint { commanded: 10000.0, measured: 9000.0 },
+                CalibrationPoint { commanded: 5000.0, measured: 4000.0 },
+                CalibrationPoint { commanded: 20000.0, measured: 19000.0 },
+            ],
+            pwm_max: 24000.0,
+            pwm_min: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_commanded_for() {
+        let calibration = calibration();
+        assert_eq!(calibration.range(), Some((4000.0, 19000.0)));
+        assert_eq!(calibration.commanded_for(9000.0), Some(10000.0));
+        assert_eq!(calibration.commanded_for(14000.0), Some(15000.0));
+        assert_eq!(calibration.commanded_for(0.0), Some(0.0));
+        // Extrapolated beyond the last point
+        assert_eq!(calibration.commanded_for(24000.0), Some(25000.0));
+
+        assert_eq!(SpindleCalibration::default().commanded_for(1000.0), None);
+    }
+
+    #[test]
+    fn test_calibrate_line() {
+        let calibration = calibration();
+        assert_eq!(calibration.calibrate_line("M3 S9000"), "M3 S10000");
+        assert_eq!(calibration.calibrate_line("G1 X10 s14000 F500 (S1)"), "G1 X10 s15000 F500 (S1)");
+        assert_eq!(calibration.calibrate_line("M5 ; S9000"), "M5 ; S9000");
+        assert_eq!(calibration.calibrate_line("$SLP"), "$SLP");
+
+        let disabled = SpindleCalibration { enabled: false, ..calibration };
+        assert_eq!(disabled.calibrate_line("M3 S9000"), "M3 S9000");
+    }
+
+    #[test]
+    fn test_out_of_range_and_pwm() {
+        let calibration = calibration();
+        let program = "M3 S12000\nG1 X10\nS0\nM3 S22000 (too fast)\n";
+        assert_eq!(calibration.out_of_range(program), Some((3, 22000.0)));
+        assert_eq!(calibration.out_of_range("M3 S12000\nM5 S0"), None);
+
+        assert_eq!(calibration.pwm_duty(12000.0), 50.0);
+        assert_eq!(calibration.pwm_duty(30000.0), 100.0);
+        assert_eq!(calibration.pwm_duty(0.0), 0.0);
+    }
+}
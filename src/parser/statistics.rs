@@ -0,0 +1,175 @@
+//! Program statistics
+//!
+//! Summarizes a program for checking post-processor output: how often each
+//! G and M code is used, the feeds and spindle speeds it sets, its tool
+//! changes, and how its motion splits between rapids, lines and arcs.
+//! Words are counted from the program text as written; motion comes from
+//! the parsed segments.
+
+use super::estimate::JobEstimate;
+use super::outline::split_comment;
+use super::segment::{Segment, SegmentType};
+
+/// Statistics for one program
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgramStatistics {
+    /// G codes and their number of uses, in code order
+    pub g_codes: Vec<(String, usize)>,
+    /// M codes and their number of uses, in code order
+    pub m_codes: Vec<(String, usize)>,
+    /// Distinct feed rates set by F words, ascending
+    pub feeds: Vec<f64>,
+    /// Distinct spindle speeds set by S words, ascending
+    pub spindle_speeds: Vec<f64>,
+    /// Number of M6 tool changes
+    pub tool_changes: usize,
+    /// Rapid move segments
+    pub rapids: usize,
+    /// Linear feed segments
+    pub lines: usize,
+    /// Arc segments
+    pub arcs: usize,
+    /// Distance travelled at rapid rate
+    pub rapid_distance: f64,
+    /// Distance travelled at feed rate
+    pub cutting_distance: f64,
+}
+
+impl ProgramStatistics {
+    /// Gather statistics from a program's text and its segments
+    pub fn new(program: &str, segments: &[Segment]) -> Self {
+        let mut g_codes: Vec<(f64, usize)> = Vec::new();
+        let mut m_codes: Vec<(f64, usize)> = Vec::new();
+        let mut feeds = Vec::new();
+        let mut spindle_speeds = Vec::new();
+        let mut tool_changes = 0;
+
+        for line in program.lines() {
+            let (code, _) = split_comment(line);
+            for (letter, value) in words(&code) {
+                match letter {
+                    'G' => count(&mut g_codes, value),
+                    'M' => {
+                        if value == 6.0 {
+                            tool_changes += 1;
+                        }
+                        count(&mut m_codes, value)
+                    }
+                    'F' => feeds.push(value),
+                    'S' => spindle_speeds.push(value),
+                    _ => {}
+                }
+            }
+        }
+
+        let estimate = JobEstimate::from_segments(segments, 0.0);
+        let motion = |kind: &[SegmentType]| segments.iter().filter(|s| kind.contains(&s.segment_type)).count();
+
+        Self {
+            g_codes: named(g_codes, 'G'),
+            m_codes: named(m_codes, 'M'),
+            feeds: distinct(feeds),
+            spindle_speeds: distinct(spindle_speeds),
+            tool_changes,
+            rapids: motion(&[SegmentType::Rapid]),
+            lines: motion(&[SegmentType::Linear]),
+            arcs: motion(&[SegmentType::ArcCW, SegmentType::ArcCCW]),
+            rapid_distance: estimate.rapid_distance,
+            cutting_distance: estimate.cutting_distance,
+        }
+    }
+
+    /// Lowest and highest feed rate
+    pub fn feed_range(&self) -> Option<(f64, f64)> {
+        Some((*self.feeds.first()?, *self.feeds.last()?))
+    }
+
+    /// Lowest and highest spindle speed
+    pub fn spindle_range(&self) -> Option<(f64, f64)> {
+        Some((*self.spindle_speeds.first()?, *self.spindle_speeds.last()?))
+    }
+}
+
+/// Letters and values of the words in a line's code, skipping any that aren't plain numbers
+fn words(code: &str) -> impl Iterator<Item = (char, f64)> + '_ {
+    code.char_indices().filter(|(_, c)| c.is_ascii_alphabetic()).filter_map(|(index, letter)| {
+        let number: String = code[index + 1..]
+            .chars()
+            .skip_while(|c| *c == ' ')
+            .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+            .collect();
+        number.parse().ok().map(|value| (letter.to_ascii_uppercase(), value))
+    })
+}
+
+/// Add one use of a code
+fn count(codes: &mut Vec<(f64, usize)>, value: f64) {
+    match codes.iter_mut().find(|(code, _)| *code == value) {
+        Some((_, uses)) => *uses += 1,
+        None => codes.push((value, 1)),
+    }
+}
+
+/// Sort codes and name them with their letter, e.g. `G38.2`
+fn named(mut codes: Vec<(f64, usize)>, letter: char) -> Vec<(String, usize)> {
+    codes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    codes.into_iter().map(|(code, uses)| (format!("{}{}", letter, code), uses)).collect()
+}
+
+/// Sort values and drop repeats
+fn distinct(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ArcDirection, Point3D};
+
+    #[test]
+    fn test_word_counts() {
+        let program = "G21 G90 (G0 in a comment)\n\
+            T1 M06\n\
+            M3 S12000\n\
+            G0 X0 Y0\n\
+            G1 Z-1 F300\n\
+            g1 x10 f1200 ; F9999\n\
+            G2 X20 I5 J0\n\
+            G38.2 Z-10 F100\n\
+            T2 M6\n\
+            S8000 M3\n\
+            M30\n";
+        let stats = ProgramStatistics::new(program, &[]);
+
+        let g: Vec<_> = stats.g_codes.iter().map(|(code, uses)| (code.as_str(), *uses)).collect();
+        assert_eq!(g, [("G0", 1), ("G1", 2), ("G2", 1), ("G21", 1), ("G38.2", 1), ("G90", 1)]);
+        let m: Vec<_> = stats.m_codes.iter().map(|(code, uses)| (code.as_str(), *uses)).collect();
+        assert_eq!(m, [("M3", 2), ("M6", 2), ("M30", 1)]);
+        assert_eq!(stats.tool_changes, 2);
+        assert_eq!(stats.feeds, [100.0, 300.0, 1200.0]);
+        assert_eq!(stats.feed_range(), Some((100.0, 1200.0)));
+        assert_eq!(stats.spindle_range(), Some((8000.0, 12000.0)));
+    }
+
+    #[test]
+    fn test_motion_split() {
+        let segments = vec![
+            Segment::rapid(Point3D::new(0.0, 0.0, 5.0), Point3D::new(0.0, 0.0, 0.0)),
+            Segment::linear(Point3D::new(0.0, 0.0, 0.0), Point3D::new(10.0, 0.0, 0.0), 500.0),
+            Segment::arc(
+                Point3D::new(10.0, 0.0, 0.0),
+                Point3D::new(20.0, 0.0, 0.0),
+                Point3D::new(15.0, 0.0, 0.0),
+                ArcDirection::Clockwise,
+                500.0,
+            ),
+        ];
+        let stats = ProgramStatistics::new("", &segments);
+        assert_eq!((stats.rapids, stats.lines, stats.arcs), (1, 1, 1));
+        assert_eq!(stats.rapid_distance, 5.0);
+        assert!(stats.cutting_distance > 10.0);
+        assert_eq!(stats.feed_range(), None);
+    }
+}
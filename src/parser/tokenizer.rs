@@ -7,6 +7,7 @@
 //! - Comments (parentheses and semicolon styles)
 //! - Line numbers (N)
 //! - Checksums (*)
+//! - Block delete markers (/)
 
 use crate::utils::error::{Error, Result};
 
@@ -36,6 +37,8 @@ pub enum Token {
     Comment(String),
     /// Checksum value
     Checksum(u32),
+    /// Block delete marker ("/" at the start of a line)
+    BlockDelete,
     /// End of line
     EndOfLine,
 }
@@ -52,6 +55,7 @@ impl std::fmt::Display for Token {
             Token::LineNumber(n) => write!(f, "N{}", n),
             Token::Comment(s) => write!(f, "({})", s),
             Token::Checksum(n) => write!(f, "*{}", n),
+            Token::BlockDelete => write!(f, "/"),
             Token::EndOfLine => write!(f, "\\n"),
         }
     }
@@ -62,6 +66,8 @@ pub struct Tokenizer {
     input: Vec<char>,
     position: usize,
     line: usize,
+    /// No token has been read on the current line yet
+    line_start: bool,
 }
 
 impl Tokenizer {
@@ -71,6 +77,7 @@ impl Tokenizer {
             input: input.chars().collect(),
             position: 0,
             line: 1,
+            line_start: true,
         }
     }
 
@@ -85,6 +92,7 @@ impl Tokenizer {
             }
 
             let token = self.next_token()?;
+            self.line_start = token == Token::EndOfLine;
             tokens.push(token);
         }
 
@@ -119,6 +127,12 @@ impl Tokenizer {
                 }
                 Ok(Token::Comment(comment))
             }
+            '/' if self.line_start => {
+                // Block delete - optional skip of the rest of the line; the
+                // streamer only honours it as the line's first character too
+                self.advance();
+                Ok(Token::BlockDelete)
+            }
             '*' => {
                 // Checksum
                 self.advance();
@@ -430,6 +444,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_block_delete() {
+        let input = "/G0 X10";
+        let mut tokenizer = Tokenizer::new(input);
+        let tokens = tokenizer.tokenize().unwrap();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0], Token::BlockDelete);
+        assert_eq!(tokens[1], Token::GCommand(0));
+
+        // Only at the start of a line
+        let tokens = Tokenizer::new("G0 X1\n  /G1 X2").tokenize().unwrap();
+        assert_eq!(tokens[3], Token::BlockDelete);
+        assert!(Tokenizer::new("G0 /X10").tokenize().is_err());
+        assert!(Tokenizer::new("(note) /G0 X10").tokenize().is_err());
+    }
+
     #[test]
     fn test_arc_parameters() {
         let input = "G2 X10 Y10 I5 J5";
@@ -82,8 +82,21 @@ pub enum Plane {
 pub enum FeedRateMode {
     /// Units per minute (G94)
     UnitsPerMinute,
-    /// Inverse time mode (G93)
+    /// Inverse time mode (G93): F is the reciprocal of the move time in minutes
     InverseTime,
+    /// Units per spindle revolution (G95)
+    UnitsPerRevolution,
+}
+
+impl FeedRateMode {
+    /// G-Code selecting the mode
+    pub fn gcode(&self) -> &'static str {
+        match self {
+            FeedRateMode::UnitsPerMinute => "G94",
+            FeedRateMode::InverseTime => "G93",
+            FeedRateMode::UnitsPerRevolution => "G95",
+        }
+    }
 }
 
 /// Spindle state
@@ -97,6 +110,15 @@ pub enum SpindleState {
     CounterClockwise,
 }
 
+/// Meaning of the S word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpindleSpeedMode {
+    /// Spindle speed in RPM (G97)
+    Rpm,
+    /// Constant surface speed (G96), in m/min or ft/min
+    ConstantSurfaceSpeed,
+}
+
 /// Coolant state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CoolantState {
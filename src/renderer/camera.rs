@@ -2,6 +2,7 @@
 //!
 //! Provides camera positioning, rotation, and projection.
 
+use super::picking::Ray;
 use nalgebra as na;
 
 /// 3D Camera for viewing the toolpath
@@ -58,6 +59,23 @@ impl Camera {
         self.projection_matrix() * self.view_matrix()
     }
 
+    /// Ray from the camera through a point in normalized device coordinates
+    ///
+    /// `x` and `y` run from -1 to 1, with `y` pointing up.
+    pub fn ray(&self, x: f32, y: f32) -> Ray {
+        let inverse = self
+            .view_projection_matrix()
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity);
+        let unproject = |z: f32| {
+            let p = inverse * na::Vector4::new(x, y, z, 1.0);
+            na::Point3::new(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+        Ray::new(near, far - near)
+    }
+
     /// Update aspect ratio (called when viewport resizes)
     pub fn set_aspect(&mut self, width: f32, height: f32) {
         self.aspect = width / height;
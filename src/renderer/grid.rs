@@ -1,6 +1,36 @@
 //! Grid rendering for the 3D viewport
 //!
-//! Renders a reference grid and coordinate axes.
+//! Renders a reference grid and coordinate axes, and picks grid spacings
+//! that suit the zoom.
+
+/// Grid spacings to pick from in millimeters, repeated in steps of ten
+const METRIC_SPACINGS: [f32; 4] = [1.0, 5.0, 10.0, 50.0];
+
+/// Grid spacings to pick from in inches, repeated in steps of ten
+const INCH_SPACINGS: [f32; 4] = [0.1, 0.5, 1.0, 5.0];
+
+/// Closest grid lines may be drawn (pixels)
+pub const MIN_GRID_PIXELS: f32 = 12.0;
+
+/// Finest grid spacing whose lines are at least [`MIN_GRID_PIXELS`] apart
+///
+/// `pixels_per_unit` is the zoom in pixels per millimeter, or per inch when
+/// not `metric`. Zoomed far out the spacings continue as 100, 500, ...
+pub fn adaptive_spacing(pixels_per_unit: f32, metric: bool) -> f32 {
+    let spacings = if metric { METRIC_SPACINGS } else { INCH_SPACINGS };
+    let mut decade = 1.0;
+    while decade < 1e6 {
+        if let Some(spacing) = spacings
+            .iter()
+            .map(|spacing| spacing * decade)
+            .find(|spacing| spacing * pixels_per_unit >= MIN_GRID_PIXELS)
+        {
+            return spacing;
+        }
+        decade *= 10.0;
+    }
+    spacings[spacings.len() - 1] * decade
+}
 
 /// Grid configuration
 #[derive(Debug, Clone)]
@@ -231,6 +261,16 @@ mod tests {
         assert_eq!(vertices.len(), 0);
     }
 
+    #[test]
+    fn test_adaptive_spacing() {
+        assert_eq!(adaptive_spacing(20.0, true), 1.0);
+        assert_eq!(adaptive_spacing(2.0, true), 10.0);
+        assert_eq!(adaptive_spacing(0.5, true), 50.0);
+        assert_eq!(adaptive_spacing(0.02, true), 1000.0);
+        assert_eq!(adaptive_spacing(100.0, false), 0.5);
+        assert_eq!(adaptive_spacing(500.0, false), 0.1);
+    }
+
     #[test]
     fn test_axes_default() {
         let axes = Axes::default();
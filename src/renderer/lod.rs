@@ -0,0 +1,210 @@
+//! Level of detail for large toolpaths
+//!
+//! Splits the toolpath into chunks, each with decimated copies at coarser
+//! tolerances, so a frame only draws the chunks in view at the detail the
+//! screen can resolve. Keeps orbiting responsive for files with millions
+//! of segments.
+
+use super::toolpath::{BoundingBox, ToolpathVertex};
+use super::Camera;
+use nalgebra as na;
+
+/// Lines per chunk
+pub const CHUNK_LINES: usize = 16_384;
+
+/// Decimation tolerances as fractions of the toolpath size, finest first
+const LOD_SCALES: [f32; 4] = [0.0, 0.0005, 0.002, 0.008];
+
+/// Approximate viewport height in pixels used to pick a detail level
+const LOD_VIEWPORT_PIXELS: f32 = 1000.0;
+
+/// A run of toolpath lines with its bounds and decimated copies
+#[derive(Debug, Clone)]
+pub struct ToolpathChunk {
+    /// Bounds of the chunk
+    pub bounds: BoundingBox,
+    /// Line list vertices per detail level, finest first
+    pub levels: Vec<Vec<ToolpathVertex>>,
+    /// Decimation tolerance of each level (world units)
+    pub tolerances: Vec<f32>,
+}
+
+impl ToolpathChunk {
+    /// Split line list vertices into chunks
+    ///
+    /// `size` is the size of the whole toolpath, which scales the
+    /// decimation tolerances.
+    pub fn build(vertices: &[ToolpathVertex], size: f32) -> Vec<Self> {
+        vertices
+            .chunks(CHUNK_LINES * 2)
+            .map(|lines| {
+                let tolerances: Vec<f32> = LOD_SCALES.iter().map(|scale| scale * size).collect();
+                let levels = tolerances
+                    .iter()
+                    .map(|&tolerance| {
+                        if tolerance > 0.0 {
+                            decimate(lines, tolerance)
+                        } else {
+                            lines.to_vec()
+                        }
+                    })
+                    .collect();
+                Self {
+                    bounds: bounds(lines),
+                    levels,
+                    tolerances,
+                }
+            })
+            .collect()
+    }
+
+    /// Coarsest level whose error stays below a pixel from the camera
+    pub fn level_for(&self, camera: &Camera) -> usize {
+        let nearest = na::Point3::new(
+            camera.position.x.clamp(self.bounds.min.x, self.bounds.max.x),
+            camera.position.y.clamp(self.bounds.min.y, self.bounds.max.y),
+            camera.position.z.clamp(self.bounds.min.z, self.bounds.max.z),
+        );
+        let distance = na::distance(&camera.position, &nearest);
+        let pixel = distance * camera.fov.to_radians() / LOD_VIEWPORT_PIXELS;
+        self.tolerances
+            .iter()
+            .rposition(|&tolerance| tolerance <= pixel)
+            .unwrap_or(0)
+    }
+
+    /// Whether any part of the chunk may be inside the view frustum
+    pub fn visible(&self, view_projection: &na::Matrix4<f32>) -> bool {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        let corners: Vec<na::Vector4<f32>> = (0..8)
+            .map(|i| {
+                let corner = na::Vector4::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                    1.0,
+                );
+                view_projection * corner
+            })
+            .collect();
+
+        // Culled only when every corner is outside the same clip plane
+        let outside = |test: &dyn Fn(&na::Vector4<f32>) -> bool| corners.iter().all(test);
+        !(outside(&|c| c.x < -c.w)
+            || outside(&|c| c.x > c.w)
+            || outside(&|c| c.y < -c.w)
+            || outside(&|c| c.y > c.w)
+            || outside(&|c| c.z < -c.w)
+            || outside(&|c| c.z > c.w))
+    }
+}
+
+/// Merge runs of short connected lines into chords
+///
+/// Consecutive lines of the same color are joined while the chord from the
+/// start of the run stays within `tolerance`, so no line shorter than the
+/// tolerance survives on its own and long lines pass through unchanged.
+pub fn decimate(vertices: &[ToolpathVertex], tolerance: f32) -> Vec<ToolpathVertex> {
+    let mut result = Vec::with_capacity(vertices.len());
+    let mut run: Option<(ToolpathVertex, ToolpathVertex)> = None;
+
+    for line in vertices.chunks_exact(2) {
+        let (start, end) = (line[0], line[1]);
+        if let Some((run_start, run_end)) = run.as_mut() {
+            let connected = run_end.position == start.position && run_end.color == start.color;
+            if connected && distance(run_start, &end) <= tolerance {
+                *run_end = end;
+                continue;
+            }
+            result.push(*run_start);
+            result.push(*run_end);
+        }
+        run = Some((start, end));
+    }
+
+    if let Some((start, end)) = run {
+        result.push(start);
+        result.push(end);
+    }
+    result
+}
+
+/// Distance between two vertices
+fn distance(a: &ToolpathVertex, b: &ToolpathVertex) -> f32 {
+    let [ax, ay, az] = a.position;
+    let [bx, by, bz] = b.position;
+    ((bx - ax).powi(2) + (by - ay).powi(2) + (bz - az).powi(2)).sqrt()
+}
+
+/// Bounds of a set of vertices
+fn bounds(vertices: &[ToolpathVertex]) -> BoundingBox {
+    let mut min = na::Point3::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut max = na::Point3::new(f32::MIN, f32::MIN, f32::MIN);
+    for vertex in vertices {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(vertex.position[axis]);
+            max[axis] = max[axis].max(vertex.position[axis]);
+        }
+    }
+    BoundingBox { min, max }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_list(points: &[[f32; 3]]) -> Vec<ToolpathVertex> {
+        let color = [1.0, 1.0, 1.0, 1.0];
+        points
+            .windows(2)
+            .flat_map(|pair| {
+                [
+                    ToolpathVertex { position: pair[0], color },
+                    ToolpathVertex { position: pair[1], color },
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decimate() {
+        // Ten 0.1 long steps followed by one long line
+        let mut points: Vec<[f32; 3]> = (0..=10).map(|i| [i as f32 * 0.1, 0.0, 0.0]).collect();
+        points.push([10.0, 0.0, 0.0]);
+        let vertices = line_list(&points);
+
+        let decimated = decimate(&vertices, 0.5);
+        assert_eq!(decimated.len(), 6);
+        assert_eq!(decimated.last().unwrap().position, [10.0, 0.0, 0.0]);
+
+        // Nothing below the tolerance is merged
+        assert_eq!(decimate(&vertices, 0.01).len(), vertices.len());
+    }
+
+    #[test]
+    fn test_chunks_and_levels() {
+        let points: Vec<[f32; 3]> = (0..=CHUNK_LINES + 10).map(|i| [i as f32 * 0.01, 0.0, 0.0]).collect();
+        let chunks = ToolpathChunk::build(&line_list(&points), 100.0);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].levels[0].len(), CHUNK_LINES * 2);
+        assert!(chunks[0].levels[3].len() < chunks[0].levels[1].len());
+
+        let mut camera = Camera::new();
+        camera.position = na::Point3::new(0.0, 0.0, 1.0);
+        assert_eq!(chunks[0].level_for(&camera), 0);
+        camera.position = na::Point3::new(0.0, 0.0, 10_000.0);
+        assert_eq!(chunks[0].level_for(&camera), LOD_SCALES.len() - 1);
+    }
+
+    #[test]
+    fn test_frustum_culling() {
+        let chunks = ToolpathChunk::build(&line_list(&[[0.0, 0.0, 0.0], [1.0, 1.0, 0.0]]), 1.0);
+        let mut camera = Camera::new();
+        camera.far = 10_000.0;
+        assert!(chunks[0].visible(&camera.view_projection_matrix()));
+
+        // Looking away from the chunk
+        camera.target = na::Point3::new(0.0, 0.0, 20.0);
+        assert!(!chunks[0].visible(&camera.view_projection_matrix()));
+    }
+}
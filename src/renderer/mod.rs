@@ -10,11 +10,16 @@
 
 mod camera;
 mod grid;
+mod lod;
+mod picking;
 mod renderer;
 mod toolpath;
 mod view_presets;
 
 pub use camera::{Camera, CameraController};
-pub use renderer::Renderer;
-pub use toolpath::ToolpathRenderer;
+pub use grid::{adaptive_spacing, MIN_GRID_PIXELS};
+pub use lod::ToolpathChunk;
+pub use picking::{Ray, SegmentIndex};
+pub use renderer::{is_weak_gpu, Renderer};
+pub use toolpath::{BoundingBox, ClipAxis, ClipPlane, DepthGradient, FeedGradient, LinePattern, ToolpathColorMode, ToolpathRenderer, ZFilter};
 pub use view_presets::{ViewPreset, calculate_view_distance, calculate_center};
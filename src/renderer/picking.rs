@@ -0,0 +1,261 @@
+//! Toolpath picking
+//!
+//! Finds the segment under the mouse by casting a ray through the cursor.
+//! Segments are bucketed into a sparse uniform grid so a pick only tests
+//! the segments near the ray, which keeps hovering cheap on large files.
+
+use crate::parser::Segment;
+use nalgebra as na;
+use std::collections::{HashMap, HashSet};
+
+/// Grid cells along the longest side of the toolpath
+const GRID_RESOLUTION: f32 = 64.0;
+
+/// A ray in world space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    /// Start of the ray
+    pub origin: na::Point3<f32>,
+    /// Unit direction
+    pub direction: na::Vector3<f32>,
+}
+
+impl Ray {
+    /// Create a ray, normalizing the direction
+    pub fn new(origin: na::Point3<f32>, direction: na::Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Point at a distance along the ray
+    pub fn at(&self, t: f32) -> na::Point3<f32> {
+        self.origin + self.direction * t
+    }
+
+    /// Closest distance between the ray and a line segment
+    pub fn distance_to_segment(&self, a: &na::Point3<f32>, b: &na::Point3<f32>) -> f32 {
+        let u = self.direction;
+        let v = b - a;
+        let w = self.origin - a;
+        let vv = v.dot(&v);
+        let uv = u.dot(&v);
+        let denom = vv - uv * uv;
+
+        // Parameter along the segment of the closest approach, clamped to it
+        let s = if vv <= f32::EPSILON {
+            0.0
+        } else if denom.abs() <= f32::EPSILON * vv {
+            (w.dot(&v) / vv).clamp(0.0, 1.0)
+        } else {
+            ((w.dot(&v) - uv * u.dot(&w)) / denom).clamp(0.0, 1.0)
+        };
+        let point = a + v * s;
+        let t = (point - self.origin).dot(&u).max(0.0);
+        (self.at(t) - point).norm()
+    }
+}
+
+/// Spatial index of segments for picking
+#[derive(Debug, Clone, Default)]
+pub struct SegmentIndex {
+    /// Grid origin
+    min: na::Point3<f32>,
+    /// Grid bounds
+    max: na::Point3<f32>,
+    /// Cell edge length
+    cell: f32,
+    /// Segment indices per occupied cell
+    cells: HashMap<(i32, i32, i32), Vec<u32>>,
+    /// Segment end points
+    lines: Vec<(na::Point3<f32>, na::Point3<f32>)>,
+}
+
+impl SegmentIndex {
+    /// Build an index over segments
+    pub fn build(segments: &[Segment]) -> Self {
+        let lines: Vec<_> = segments
+            .iter()
+            .map(|s| {
+                (
+                    na::Point3::new(s.start.x as f32, s.start.y as f32, s.start.z as f32),
+                    na::Point3::new(s.end.x as f32, s.end.y as f32, s.end.z as f32),
+                )
+            })
+            .collect();
+        if lines.is_empty() {
+            return Self::default();
+        }
+
+        let mut min = lines[0].0;
+        let mut max = lines[0].0;
+        for (a, b) in &lines {
+            for p in [a, b] {
+                min = min.inf(p);
+                max = max.sup(p);
+            }
+        }
+        let extent = (max - min).max();
+        let cell = (extent / GRID_RESOLUTION).max(1e-3);
+
+        let mut index = Self {
+            min,
+            max,
+            cell,
+            cells: HashMap::new(),
+            lines,
+        };
+        for (i, &(a, b)) in index.lines.iter().enumerate() {
+            // Sample every half cell so long segments land in each cell they cross
+            let steps = ((b - a).norm() / (cell * 0.5)).ceil().max(1.0) as usize;
+            let mut last = None;
+            for step in 0..=steps {
+                let key = index.key(&(a + (b - a) * (step as f32 / steps as f32)));
+                if last != Some(key) {
+                    let bucket = index.cells.entry(key).or_default();
+                    if bucket.last() != Some(&(i as u32)) {
+                        bucket.push(i as u32);
+                    }
+                    last = Some(key);
+                }
+            }
+        }
+        index
+    }
+
+    /// Number of indexed segments
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Cell containing a point
+    fn key(&self, p: &na::Point3<f32>) -> (i32, i32, i32) {
+        let cell = |value: f32, min: f32| ((value - min) / self.cell).floor() as i32;
+        (cell(p.x, self.min.x), cell(p.y, self.min.y), cell(p.z, self.min.z))
+    }
+
+    /// Range of the ray inside the grid bounds grown by a margin
+    fn clip(&self, ray: &Ray, margin: f32) -> Option<(f32, f32)> {
+        let (mut t0, mut t1) = (0.0f32, f32::MAX);
+        for axis in 0..3 {
+            let lo = self.min[axis] - margin;
+            let hi = self.max[axis] + margin;
+            let origin = ray.origin[axis];
+            let direction = ray.direction[axis];
+            if direction.abs() < 1e-9 {
+                if origin < lo || origin > hi {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((lo - origin) / direction, (hi - origin) / direction);
+            t0 = t0.max(a.min(b));
+            t1 = t1.min(a.max(b));
+        }
+        (t0 <= t1).then_some((t0, t1))
+    }
+
+    /// Segment closest to the ray within a tolerance (world units)
+    pub fn pick(&self, ray: &Ray, tolerance: f32) -> Option<usize> {
+        let (t0, t1) = self.clip(ray, tolerance)?;
+        let reach = (tolerance / self.cell).ceil() as i32 + 1;
+        let step = self.cell * 0.5;
+
+        let mut tested = HashSet::new();
+        let mut best: Option<(f32, usize)> = None;
+        let mut visited_cells = HashSet::new();
+        let mut t = t0;
+        while t <= t1 + step {
+            let (cx, cy, cz) = self.key(&ray.at(t.min(t1)));
+            if visited_cells.insert((cx, cy, cz)) {
+                for dx in -reach..=reach {
+                    for dy in -reach..=reach {
+                        for dz in -reach..=reach {
+                            let Some(bucket) = self.cells.get(&(cx + dx, cy + dy, cz + dz)) else {
+                                continue;
+                            };
+                            for &i in bucket {
+                                if !tested.insert(i) {
+                                    continue;
+                                }
+                                let (a, b) = &self.lines[i as usize];
+                                let distance = ray.distance_to_segment(a, b);
+                                if distance <= tolerance && best.map_or(true, |(d, _)| distance < d) {
+                                    best = Some((distance, i as usize));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if t + step == t {
+                break;
+            }
+            t += step;
+        }
+        best.map(|(_, i)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Point3D;
+    use crate::renderer::Camera;
+
+    fn segments() -> Vec<Segment> {
+        let p = |x, y, z| Point3D { x, y, z };
+        vec![
+            Segment::rapid(p(0.0, 0.0, 5.0), p(0.0, 0.0, 0.0)),
+            Segment::linear(p(0.0, 0.0, 0.0), p(100.0, 0.0, 0.0), 500.0),
+            Segment::linear(p(100.0, 0.0, 0.0), p(100.0, 50.0, -1.0), 500.0),
+            Segment::linear(p(100.0, 50.0, -1.0), p(0.0, 50.0, -1.0), 500.0),
+        ]
+    }
+
+    #[test]
+    fn test_distance_to_segment() {
+        let ray = Ray::new(na::Point3::new(5.0, 2.0, 10.0), na::Vector3::new(0.0, 0.0, -1.0));
+        let a = na::Point3::new(0.0, 0.0, 0.0);
+        let b = na::Point3::new(10.0, 0.0, 0.0);
+        assert!((ray.distance_to_segment(&a, &b) - 2.0).abs() < 1e-5);
+
+        // Past the end of the segment
+        let ray = Ray::new(na::Point3::new(13.0, 4.0, 10.0), na::Vector3::new(0.0, 0.0, -1.0));
+        assert!((ray.distance_to_segment(&a, &b) - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_pick_from_above() {
+        let index = SegmentIndex::build(&segments());
+        let down = na::Vector3::new(0.0, 0.0, -1.0);
+
+        let ray = Ray::new(na::Point3::new(60.0, 0.4, 20.0), down);
+        assert_eq!(index.pick(&ray, 1.0), Some(1));
+
+        let ray = Ray::new(na::Point3::new(40.0, 49.5, 20.0), down);
+        assert_eq!(index.pick(&ray, 1.0), Some(3));
+
+        let ray = Ray::new(na::Point3::new(50.0, 25.0, 20.0), down);
+        assert_eq!(index.pick(&ray, 1.0), None);
+    }
+
+    #[test]
+    fn test_pick_through_camera() {
+        let index = SegmentIndex::build(&segments());
+        let mut camera = Camera::new();
+        camera.position = na::Point3::new(50.0, -100.0, 100.0);
+        camera.target = na::Point3::new(50.0, 0.0, 0.0);
+        camera.up = na::Vector3::new(0.0, 0.0, 1.0);
+
+        // The view center looks straight at the first cut
+        let ray = camera.ray(0.0, 0.0);
+        assert_eq!(index.pick(&ray, 1.0), Some(1));
+    }
+}
@@ -2,11 +2,16 @@
 //!
 //! Manages WGPU rendering context and coordinates rendering of grid, axes, and toolpath.
 
-use super::{Camera, CameraController, grid::{Grid, Axes}, toolpath::ToolpathRenderer};
+use super::{Camera, CameraController, grid::{Grid, Axes}, lod::ToolpathChunk, picking::SegmentIndex, toolpath::{BoundingBox, ToolpathRenderer}};
 use crate::parser::Segment;
+use crate::settings::VisualizationSettings;
+use std::cell::RefCell;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
 
+/// Format of the depth target
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
 /// Main renderer for 3D visualization
 pub struct Renderer {
     /// WGPU device
@@ -15,6 +20,24 @@ pub struct Renderer {
     queue: Arc<wgpu::Queue>,
     /// Render pipeline
     pipeline: wgpu::RenderPipeline,
+    /// Shader module, kept to rebuild the pipeline
+    shader: wgpu::ShaderModule,
+    /// Pipeline layout, kept to rebuild the pipeline
+    pipeline_layout: wgpu::PipelineLayout,
+    /// Color target format
+    format: wgpu::TextureFormat,
+    /// MSAA sample count of the pipeline and targets
+    sample_count: u32,
+    /// Sample counts the adapter supports for the targets
+    supported_samples: Vec<u32>,
+    /// Depth and multisample targets, sized by `resize`
+    targets: Option<RenderTargets>,
+    /// Background clear color
+    background: [f32; 4],
+    /// Draw the bounding box of the job
+    show_job_bounds: bool,
+    /// Color of the job bounding box
+    bounds_color: [f32; 4],
     /// Camera
     camera: Camera,
     /// Camera controller
@@ -29,11 +52,38 @@ pub struct Renderer {
     uniform_buffer: wgpu::Buffer,
     /// Bind group
     bind_group: wgpu::BindGroup,
+    /// Toolpath chunks uploaded to the GPU, rebuilt after the toolpath changes
+    chunks: RefCell<Option<Vec<GpuChunk>>>,
+    /// Spatial index of the segments for picking
+    segment_index: SegmentIndex,
+}
+
+/// Depth and multisample color targets owned by the renderer
+struct RenderTargets {
+    /// Size in pixels
+    size: (u32, u32),
+    /// Depth target
+    depth: wgpu::TextureView,
+    /// Multisampled color target, resolved into the output view
+    msaa: Option<wgpu::TextureView>,
+}
+
+/// Toolpath chunk with a vertex buffer per detail level
+struct GpuChunk {
+    /// Bounds and tolerances (vertices are dropped after upload)
+    chunk: ToolpathChunk,
+    /// Vertex buffer and vertex count per level
+    buffers: Vec<Option<(wgpu::Buffer, u32)>>,
 }
 
 impl Renderer {
     /// Create a new renderer
-    pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>, format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        format: wgpu::TextureFormat,
+    ) -> Self {
         // Create shader module
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Renderer Shader"),
@@ -80,18 +130,61 @@ impl Renderer {
             push_constant_ranges: &[],
         });
 
-        // Create render pipeline
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let sample_count = 1;
+        let pipeline = Self::create_pipeline(&device, &shader, &pipeline_layout, format, sample_count);
+
+        // Sample counts usable for both the color and depth targets
+        let color_samples = adapter.get_texture_format_features(format).flags;
+        let depth_samples = adapter.get_texture_format_features(DEPTH_FORMAT).flags;
+        let supported_samples = [1, 2, 4, 8, 16]
+            .into_iter()
+            .filter(|&n| color_samples.sample_count_supported(n) && depth_samples.sample_count_supported(n))
+            .collect();
+
+        Self {
+            device,
+            queue,
+            pipeline,
+            shader,
+            pipeline_layout,
+            format,
+            sample_count,
+            supported_samples,
+            targets: None,
+            background: [0.1, 0.1, 0.15, 1.0],
+            show_job_bounds: false,
+            bounds_color: [0.5, 0.5, 0.5, 1.0],
+            camera: Camera::new(),
+            camera_controller: CameraController::new(),
+            grid: Grid::new(),
+            axes: Axes::new(),
+            toolpath: ToolpathRenderer::new(),
+            uniform_buffer,
+            bind_group,
+            chunks: RefCell::new(None),
+            segment_index: SegmentIndex::default(),
+        }
+    }
+
+    /// Create the line pipeline for a target format and sample count
+    fn create_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Renderer Pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: "vs_main",
                 buffers: &[super::grid::Vertex::desc()],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format,
@@ -110,34 +203,108 @@ impl Renderer {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
+                format: DEPTH_FORMAT,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::Less,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
+        })
+    }
 
-        Self {
-            device,
-            queue,
-            pipeline,
-            camera: Camera::new(),
-            camera_controller: CameraController::new(),
-            grid: Grid::new(),
-            axes: Axes::new(),
-            toolpath: ToolpathRenderer::new(),
-            uniform_buffer,
-            bind_group,
+    /// Create the depth and multisample targets for a size
+    fn create_targets(&self, width: u32, height: u32) -> RenderTargets {
+        let create = |label, format| {
+            self.device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: self.sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        RenderTargets {
+            size: (width, height),
+            depth: create("Renderer Depth Target", DEPTH_FORMAT),
+            msaa: (self.sample_count > 1).then(|| create("Renderer MSAA Target", self.format)),
         }
     }
 
+    /// Resize the render targets and camera aspect to the output size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if self.targets.as_ref().is_some_and(|t| t.size == (width, height)) {
+            return;
+        }
+        self.camera.set_aspect(width as f32, height as f32);
+        self.targets = Some(self.create_targets(width, height));
+    }
+
+    /// Set the MSAA sample count
+    ///
+    /// Falls back to the largest supported count below the request.
+    pub fn set_msaa_samples(&mut self, samples: u32) {
+        let samples = self
+            .supported_samples
+            .iter()
+            .copied()
+            .filter(|&n| n <= samples)
+            .max()
+            .unwrap_or(1);
+        if samples == self.sample_count {
+            return;
+        }
+        self.sample_count = samples;
+        self.pipeline = Self::create_pipeline(&self.device, &self.shader, &self.pipeline_layout, self.format, samples);
+        if let Some((width, height)) = self.targets.as_ref().map(|t| t.size) {
+            self.targets = Some(self.create_targets(width, height));
+        }
+    }
+
+    /// Apply visualization settings: colors, grid, visibility and MSAA
+    pub fn apply_visualization(&mut self, settings: &VisualizationSettings) {
+        let colors = &settings.color_scheme;
+        self.background = colors.background;
+
+        self.grid.color = colors.grid;
+        self.grid.set_spacing(settings.grid_size);
+        self.grid.set_visible(settings.show_grid);
+        self.axes.visible = settings.show_origin;
+        self.show_job_bounds = settings.show_dimensions;
+        self.bounds_color = colors.bounds;
+        self.camera.fov = settings.fov;
+
+        let toolpath = self.toolpath_mut();
+        toolpath.rapid_color = colors.rapid;
+        toolpath.work_color = colors.toolpath;
+        toolpath.arc_color = colors.arc;
+        toolpath.current_color = colors.tool;
+        toolpath.color_mode = settings.color_mode;
+
+        self.set_msaa_samples(settings.msaa_samples);
+    }
+
+    /// Current MSAA sample count
+    pub fn msaa_samples(&self) -> u32 {
+        self.sample_count
+    }
+
     /// Get mutable reference to camera
     pub fn camera_mut(&mut self) -> &mut Camera {
         &mut self.camera
@@ -180,6 +347,7 @@ impl Renderer {
 
     /// Get mutable reference to toolpath
     pub fn toolpath_mut(&mut self) -> &mut ToolpathRenderer {
+        *self.chunks.get_mut() = None;
         &mut self.toolpath
     }
 
@@ -190,6 +358,8 @@ impl Renderer {
 
     /// Set toolpath segments
     pub fn set_segments(&mut self, segments: Vec<Segment>) {
+        *self.chunks.get_mut() = None;
+        self.segment_index = SegmentIndex::build(&segments);
         self.toolpath.set_segments(segments);
     }
 
@@ -200,8 +370,43 @@ impl Renderer {
         self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(matrix_ref));
     }
 
+    /// Split the toolpath into chunks and upload them
+    fn build_chunks(&self) -> Vec<GpuChunk> {
+        let vertices = self.toolpath.generate_vertices();
+        let size = self.toolpath.bounding_box().map(|b| b.diagonal()).unwrap_or(0.0);
+
+        ToolpathChunk::build(&vertices, size)
+            .into_iter()
+            .map(|mut chunk| {
+                let buffers = chunk
+                    .levels
+                    .drain(..)
+                    .map(|level| {
+                        (!level.is_empty()).then(|| {
+                            let buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some("Toolpath Chunk Buffer"),
+                                contents: bytemuck::cast_slice(&level),
+                                usage: wgpu::BufferUsages::VERTEX,
+                            });
+                            (buffer, level.len() as u32)
+                        })
+                    })
+                    .collect();
+                GpuChunk { chunk, buffers }
+            })
+            .collect()
+    }
+
     /// Render the scene
-    pub fn render(&self, view: &wgpu::TextureView, depth_view: &wgpu::TextureView) {
+    ///
+    /// Draws into `view` using the renderer's own depth and multisample
+    /// targets; `resize` must have been called with the size of `view`.
+    pub fn render(&self, view: &wgpu::TextureView) {
+        let Some(targets) = self.targets.as_ref() else {
+            tracing::warn!("Renderer::render called before resize");
+            return;
+        };
+
         // Update uniform buffer
         self.update_uniforms();
 
@@ -218,7 +423,17 @@ impl Renderer {
             Vec::new()
         };
 
-        let toolpath_vertices = self.toolpath.generate_vertices();
+        let mut chunks = self.chunks.borrow_mut();
+        let chunks = chunks.get_or_insert_with(|| self.build_chunks());
+        let view_projection = self.camera.view_projection_matrix();
+
+        let bounds_vertices = if self.show_job_bounds {
+            BoundingBox::of_job(self.toolpath.segments())
+                .map(|bounds| bounds.edge_vertices(self.bounds_color))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
 
         // Create vertex buffers
         let grid_buffer = if !grid_vertices.is_empty() {
@@ -241,10 +456,10 @@ impl Renderer {
             None
         };
 
-        let toolpath_buffer = if !toolpath_vertices.is_empty() {
+        let bounds_buffer = if !bounds_vertices.is_empty() {
             Some(self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Toolpath Vertex Buffer"),
-                contents: bytemuck::cast_slice(&toolpath_vertices),
+                label: Some("Job Bounds Vertex Buffer"),
+                contents: bytemuck::cast_slice(&bounds_vertices),
                 usage: wgpu::BufferUsages::VERTEX,
             }))
         } else {
@@ -259,23 +474,27 @@ impl Renderer {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Renderer Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
+                    view: targets.msaa.as_ref().unwrap_or(view),
+                    resolve_target: targets.msaa.as_ref().map(|_| view),
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.1,
-                            b: 0.15,
-                            a: 1.0,
+                            r: self.background[0] as f64,
+                            g: self.background[1] as f64,
+                            b: self.background[2] as f64,
+                            a: self.background[3] as f64,
                         }),
-                        store: wgpu::StoreOp::Store,
+                        store: if targets.msaa.is_some() {
+                            wgpu::StoreOp::Discard
+                        } else {
+                            wgpu::StoreOp::Store
+                        },
                     },
                 })],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: depth_view,
+                    view: &targets.depth,
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
+                        store: wgpu::StoreOp::Discard,
                     }),
                     stencil_ops: None,
                 }),
@@ -298,16 +517,43 @@ impl Renderer {
                 render_pass.draw(0..axes_vertices.len() as u32, 0..1);
             }
 
-            // Render toolpath
-            if let Some(ref buffer) = toolpath_buffer {
+            // Render job bounding box
+            if let Some(ref buffer) = bounds_buffer {
                 render_pass.set_vertex_buffer(0, buffer.slice(..));
-                render_pass.draw(0..toolpath_vertices.len() as u32, 0..1);
+                render_pass.draw(0..bounds_vertices.len() as u32, 0..1);
+            }
+
+            // Render toolpath chunks in view at the detail the distance allows
+            for gpu_chunk in chunks.iter() {
+                if !gpu_chunk.chunk.visible(&view_projection) {
+                    continue;
+                }
+                let level = gpu_chunk.chunk.level_for(&self.camera);
+                if let Some((ref buffer, count)) = gpu_chunk.buffers[level] {
+                    render_pass.set_vertex_buffer(0, buffer.slice(..));
+                    render_pass.draw(0..count, 0..1);
+                }
             }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
     }
 
+    /// Segment under a viewport position
+    ///
+    /// `x` and `y` are in pixels from the top-left of a viewport of the
+    /// given size; `tolerance` is the pick radius in pixels around the
+    /// camera target.
+    pub fn pick(&self, x: f32, y: f32, width: f32, height: f32, tolerance: f32) -> Option<usize> {
+        let ndc_x = x / width * 2.0 - 1.0;
+        let ndc_y = 1.0 - y / height * 2.0;
+        let ray = self.camera.ray(ndc_x, ndc_y);
+
+        let distance = (self.camera.position - self.camera.target).norm();
+        let world_per_pixel = 2.0 * distance * (self.camera.fov.to_radians() / 2.0).tan() / height;
+        self.segment_index.pick(&ray, tolerance * world_per_pixel)
+    }
+
     /// Reset camera to default view
     pub fn reset_camera(&mut self) {
         self.camera.reset();
@@ -396,6 +642,17 @@ impl Renderer {
     }
 }
 
+/// Renderer names of Raspberry Pi, mobile and software GPUs
+const WEAK_GPU_NAMES: &[&str] = &[
+    "v3d", "videocore", "vc4", "llvmpipe", "softpipe", "lavapipe", "swiftshader", "mali", "panfrost", "lima", "adreno",
+];
+
+/// Whether a GPU, going by its renderer name, is too weak for continuous 3D rendering
+pub fn is_weak_gpu(name: &str) -> bool {
+    let name = name.to_lowercase();
+    WEAK_GPU_NAMES.iter().any(|weak| name.contains(weak))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -428,4 +685,12 @@ mod tests {
         let vertices = axes.generate_vertices();
         assert_eq!(vertices.len(), 6); // 3 axes * 2 vertices
     }
+
+    #[test]
+    fn test_weak_gpu_names() {
+        assert!(is_weak_gpu("V3D 4.2"));
+        assert!(is_weak_gpu("llvmpipe (LLVM 15.0.6, 128 bits)"));
+        assert!(!is_weak_gpu("NVIDIA GeForce RTX 3060"));
+        assert!(!is_weak_gpu("AMD Radeon RX 6700 XT"));
+    }
 }
@@ -2,8 +2,271 @@
 //!
 //! Renders G-Code toolpaths as 3D lines with different colors for different move types.
 
-use crate::parser::{Segment, SegmentType};
+use crate::parser::{Point3D, Segment, SegmentType};
 use nalgebra as na;
+use serde::{Deserialize, Serialize};
+
+/// How toolpath segments are colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ToolpathColorMode {
+    /// Rapid, linear and arc moves in their own colors
+    #[default]
+    MoveType,
+    /// Cutting moves on a gradient by programmed feed rate
+    FeedRate,
+    /// Cutting moves on a gradient by depth
+    Depth,
+}
+
+/// Z range of the toolpath shown, to isolate depth passes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZFilter {
+    /// Lowest Z shown
+    pub min: f64,
+    /// Highest Z shown
+    pub max: f64,
+}
+
+impl ZFilter {
+    /// Tolerance when comparing Z levels
+    const TOLERANCE: f64 = 1e-4;
+
+    /// Filter showing a single Z level
+    pub fn level(z: f64) -> Self {
+        Self { min: z, max: z }
+    }
+
+    /// Whether a segment lies entirely within the range
+    pub fn contains(&self, segment: &Segment) -> bool {
+        let inside = |z: f64| z >= self.min - Self::TOLERANCE && z <= self.max + Self::TOLERANCE;
+        inside(segment.start.z) && inside(segment.end.z)
+    }
+
+    /// Distinct Z levels of horizontal cutting moves, from top to bottom
+    pub fn levels(segments: &[Segment]) -> Vec<f64> {
+        let mut levels: Vec<f64> = Vec::new();
+        for segment in segments.iter().filter(|s| s.is_cutting()) {
+            let z = segment.end.z;
+            if (segment.start.z - z).abs() > Self::TOLERANCE {
+                continue;
+            }
+            if !levels.iter().any(|level| (level - z).abs() <= Self::TOLERANCE) {
+                levels.push(z);
+            }
+        }
+        levels.sort_by(|a, b| b.total_cmp(a));
+        levels
+    }
+}
+
+/// Line style telling move types apart without relying on color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinePattern {
+    /// Continuous line, for straight cuts
+    Solid,
+    /// Long dashes, for rapids
+    Dashed,
+    /// Short dots, for arcs
+    Dotted,
+}
+
+impl LinePattern {
+    /// Pattern for a move type
+    pub fn of(segment_type: &SegmentType) -> Self {
+        match segment_type {
+            SegmentType::Rapid => LinePattern::Dashed,
+            SegmentType::Linear => LinePattern::Solid,
+            SegmentType::ArcCW | SegmentType::ArcCCW => LinePattern::Dotted,
+        }
+    }
+
+    /// Dash and gap lengths in pixels, or `None` for a solid line
+    pub fn dashes(&self) -> Option<(f32, f32)> {
+        match self {
+            LinePattern::Solid => None,
+            LinePattern::Dashed => Some((8.0, 5.0)),
+            LinePattern::Dotted => Some((2.0, 3.0)),
+        }
+    }
+}
+
+/// Axis a clipping plane is perpendicular to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipAxis {
+    /// Plane across X, showing a section through Y and Z
+    X,
+    /// Plane across Y, showing a section through X and Z
+    Y,
+    /// Horizontal plane, hiding the passes above or below it
+    #[default]
+    Z,
+}
+
+impl std::fmt::Display for ClipAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipAxis::X => write!(f, "X"),
+            ClipAxis::Y => write!(f, "Y"),
+            ClipAxis::Z => write!(f, "Z"),
+        }
+    }
+}
+
+/// Plane hiding the toolpath on one side, to look at inner passes of deep carves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    /// Axis the plane is perpendicular to
+    pub axis: ClipAxis,
+    /// Position of the plane along the axis
+    pub position: f64,
+    /// Show the side above the plane instead of below it
+    pub flip: bool,
+}
+
+impl ClipPlane {
+    /// Coordinate of a point along the plane's axis
+    pub fn coordinate(&self, point: &Point3D) -> f64 {
+        match self.axis {
+            ClipAxis::X => point.x,
+            ClipAxis::Y => point.y,
+            ClipAxis::Z => point.z,
+        }
+    }
+
+    /// Whether any part of a segment is on the shown side of the plane
+    ///
+    /// Segments crossing the plane are shown whole.
+    pub fn contains(&self, segment: &Segment) -> bool {
+        let shown = |point: &Point3D| {
+            let coordinate = self.coordinate(point);
+            if self.flip {
+                coordinate >= self.position
+            } else {
+                coordinate <= self.position
+            }
+        };
+        shown(&segment.start) || shown(&segment.end)
+    }
+
+    /// Range of the segments along an axis, for placing the plane
+    pub fn range(segments: &[Segment], axis: ClipAxis) -> Option<(f64, f64)> {
+        let plane = ClipPlane { axis, position: 0.0, flip: false };
+        segments.iter().flat_map(|s| [s.start, s.end]).fold(None, |range, point| {
+            let value = plane.coordinate(&point);
+            Some(match range {
+                Some((low, high)) => (value.min(low), value.max(high)),
+                None => (value, value),
+            })
+        })
+    }
+}
+
+/// Depth to color gradient, from blue (top) to red (deepest)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthGradient {
+    /// Highest cutting Z
+    pub top: f64,
+    /// Lowest cutting Z
+    pub bottom: f64,
+}
+
+impl DepthGradient {
+    /// Gradient spanning the Z range of the cutting moves, if any
+    pub fn from_segments(segments: &[Segment]) -> Option<Self> {
+        segments
+            .iter()
+            .filter(|s| s.is_cutting())
+            .fold(None, |range: Option<Self>, s| {
+                let low = s.start.z.min(s.end.z);
+                let high = s.start.z.max(s.end.z);
+                Some(match range {
+                    Some(r) => Self {
+                        top: r.top.max(high),
+                        bottom: r.bottom.min(low),
+                    },
+                    None => Self { top: high, bottom: low },
+                })
+            })
+    }
+
+    /// Color for the lowest point of a segment
+    pub fn color(&self, segment: &Segment) -> [f32; 4] {
+        if self.top <= self.bottom {
+            return FeedGradient::color_at(1.0);
+        }
+        let z = segment.start.z.min(segment.end.z);
+        FeedGradient::color_at((self.top - z) / (self.top - self.bottom))
+    }
+}
+
+/// Feed rate to color gradient, from blue (slowest) to red (fastest)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedGradient {
+    /// Slowest cutting feed rate
+    pub min: f64,
+    /// Fastest cutting feed rate
+    pub max: f64,
+}
+
+impl FeedGradient {
+    /// Color used for rapids in feed rate mode
+    pub const RAPID_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.6];
+
+    /// Gradient stops from slow to fast
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.2, 1.0],
+        [0.0, 0.8, 1.0],
+        [0.0, 1.0, 0.2],
+        [1.0, 0.9, 0.0],
+        [1.0, 0.1, 0.0],
+    ];
+
+    /// Gradient spanning the feed rates of the cutting moves, if any
+    pub fn from_segments(segments: &[Segment]) -> Option<Self> {
+        segments
+            .iter()
+            .filter(|s| s.is_cutting() && s.feed_rate > 0.0)
+            .fold(None, |range: Option<Self>, s| {
+                Some(match range {
+                    Some(r) => Self {
+                        min: r.min.min(s.feed_rate),
+                        max: r.max.max(s.feed_rate),
+                    },
+                    None => Self {
+                        min: s.feed_rate,
+                        max: s.feed_rate,
+                    },
+                })
+            })
+    }
+
+    /// Position of a feed rate within the range (0.0 to 1.0)
+    pub fn fraction(&self, feed_rate: f64) -> f64 {
+        if self.max <= self.min {
+            return 1.0;
+        }
+        ((feed_rate - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+
+    /// Color at a position within the range (0.0 to 1.0)
+    pub fn color_at(fraction: f64) -> [f32; 4] {
+        let scaled = fraction.clamp(0.0, 1.0) as f32 * (Self::STOPS.len() - 1) as f32;
+        let index = (scaled.floor() as usize).min(Self::STOPS.len() - 2);
+        let t = scaled - index as f32;
+        let (a, b) = (Self::STOPS[index], Self::STOPS[index + 1]);
+        [
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+            1.0,
+        ]
+    }
+
+    /// Color for a feed rate
+    pub fn color(&self, feed_rate: f64) -> [f32; 4] {
+        Self::color_at(self.fraction(feed_rate))
+    }
+}
 
 /// Toolpath renderer
 #[derive(Debug, Clone)]
@@ -24,6 +287,12 @@ pub struct ToolpathRenderer {
     pub current_line: Option<usize>,
     /// Color for current line
     pub current_color: [f32; 4],
+    /// How segments are colored
+    pub color_mode: ToolpathColorMode,
+    /// Only show segments within this Z range
+    pub z_filter: Option<ZFilter>,
+    /// Only show segments on one side of this plane
+    pub clip_plane: Option<ClipPlane>,
 }
 
 impl Default for ToolpathRenderer {
@@ -37,6 +306,9 @@ impl Default for ToolpathRenderer {
             arc_color: [0.0, 0.5, 1.0, 1.0],        // Blue
             current_line: None,
             current_color: [1.0, 1.0, 0.0, 1.0],    // Yellow
+            color_mode: ToolpathColorMode::MoveType,
+            z_filter: None,
+            clip_plane: None,
         }
     }
 }
@@ -68,12 +340,43 @@ impl ToolpathRenderer {
         self.current_line = line;
     }
 
+    /// Color of a segment for the current color mode, ignoring highlighting
+    pub fn segment_color(
+        &self,
+        segment: &Segment,
+        feed: Option<&FeedGradient>,
+        depth: Option<&DepthGradient>,
+    ) -> [f32; 4] {
+        match (self.color_mode, feed, depth) {
+            (ToolpathColorMode::FeedRate, Some(_), _) | (ToolpathColorMode::Depth, _, Some(_))
+                if !segment.is_cutting() =>
+            {
+                FeedGradient::RAPID_COLOR
+            }
+            (ToolpathColorMode::FeedRate, Some(gradient), _) => gradient.color(segment.feed_rate),
+            (ToolpathColorMode::Depth, _, Some(gradient)) => gradient.color(segment),
+            _ => match segment.segment_type {
+                SegmentType::Rapid => self.rapid_color,
+                SegmentType::Linear => self.work_color,
+                SegmentType::ArcCW | SegmentType::ArcCCW => self.arc_color,
+            },
+        }
+    }
+
     /// Generate vertices for rendering
     pub fn generate_vertices(&self) -> Vec<ToolpathVertex> {
         let mut vertices = Vec::new();
+        let feed = FeedGradient::from_segments(&self.segments);
+        let depth = DepthGradient::from_segments(&self.segments);
 
         for (idx, segment) in self.segments.iter().enumerate() {
             let is_current = self.current_line == Some(idx);
+            if self.z_filter.is_some_and(|filter| !filter.contains(segment)) {
+                continue;
+            }
+            if self.clip_plane.is_some_and(|plane| !plane.contains(segment)) {
+                continue;
+            }
             
             match &segment.segment_type {
                 SegmentType::Rapid => {
@@ -83,7 +386,7 @@ impl ToolpathRenderer {
                     let color = if is_current { 
                         self.current_color 
                     } else { 
-                        self.rapid_color 
+                        self.segment_color(segment, feed.as_ref(), depth.as_ref())
                     };
                     
                     vertices.push(ToolpathVertex {
@@ -102,7 +405,7 @@ impl ToolpathRenderer {
                     let color = if is_current { 
                         self.current_color 
                     } else { 
-                        self.work_color 
+                        self.segment_color(segment, feed.as_ref(), depth.as_ref())
                     };
                     
                     vertices.push(ToolpathVertex {
@@ -121,7 +424,7 @@ impl ToolpathRenderer {
                     let color = if is_current { 
                         self.current_color 
                     } else { 
-                        self.arc_color 
+                        self.segment_color(segment, feed.as_ref(), depth.as_ref())
                     };
                     
                     // Tessellate arc into line segments
@@ -275,6 +578,42 @@ pub struct BoundingBox {
 }
 
 impl BoundingBox {
+    /// Extents of the cutting moves, or of all moves if there are no cuts
+    pub fn of_job(segments: &[Segment]) -> Option<Self> {
+        let cutting = segments.iter().any(|s| s.is_cutting());
+        segments
+            .iter()
+            .filter(|s| s.is_cutting() || !cutting)
+            .flat_map(|s| [s.start, s.end])
+            .fold(None, |bounds: Option<Self>, p| {
+                let p = na::Point3::new(p.x as f32, p.y as f32, p.z as f32);
+                Some(match bounds {
+                    Some(b) => Self {
+                        min: b.min.inf(&p),
+                        max: b.max.sup(&p),
+                    },
+                    None => Self { min: p, max: p },
+                })
+            })
+    }
+
+    /// Line list vertices for the twelve edges of the box
+    pub fn edge_vertices(&self, color: [f32; 4]) -> Vec<ToolpathVertex> {
+        let corner = |i: usize| ToolpathVertex {
+            position: [
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            ],
+            color,
+        };
+        // Corners differing in exactly one axis bit share an edge
+        (0..8)
+            .flat_map(|i| [1, 2, 4].into_iter().filter(move |bit| i & bit == 0).map(move |bit| (i, i | bit)))
+            .flat_map(|(a, b)| [corner(a), corner(b)])
+            .collect()
+    }
+
     /// Get center of bounding box
     pub fn center(&self) -> na::Point3<f32> {
         na::Point3::new(
@@ -403,4 +742,105 @@ mod tests {
         let vertices = renderer.generate_vertices();
         assert_eq!(vertices.len(), 0);
     }
+
+    #[test]
+    fn test_job_bounds() {
+        let point = |x: f64, y: f64, z: f64| Point3D { x, y, z };
+        let segments = vec![
+            Segment::rapid(point(-10.0, -10.0, 15.0), point(0.0, 0.0, 1.0)),
+            Segment::linear(point(0.0, 0.0, 1.0), point(0.0, 0.0, -2.0), 100.0),
+            Segment::linear(point(0.0, 0.0, -2.0), point(40.0, 25.0, -2.0), 500.0),
+        ];
+
+        // Rapids are left out when there are cuts
+        let bounds = BoundingBox::of_job(&segments).unwrap();
+        assert_eq!(bounds.min, na::Point3::new(0.0, 0.0, -2.0));
+        assert_eq!(bounds.size(), na::Vector3::new(40.0, 25.0, 3.0));
+
+        let bounds = BoundingBox::of_job(&segments[..1]).unwrap();
+        assert_eq!(bounds.max.z, 15.0);
+        assert!(BoundingBox::of_job(&[]).is_none());
+
+        assert_eq!(bounds.edge_vertices([1.0; 4]).len(), 24);
+    }
+
+    #[test]
+    fn test_z_filter() {
+        let point = |x: f64, z: f64| Point3D { x, y: 0.0, z };
+        let segments = vec![
+            Segment::rapid(point(0.0, 5.0), point(0.0, 0.0)),
+            Segment::linear(point(0.0, 0.0), point(0.0, -1.0), 100.0),
+            Segment::linear(point(0.0, -1.0), point(10.0, -1.0), 500.0),
+            Segment::linear(point(10.0, -1.0), point(10.0, -2.0), 100.0),
+            Segment::linear(point(10.0, -2.0), point(0.0, -2.0), 500.0),
+        ];
+
+        assert_eq!(ZFilter::levels(&segments), vec![-1.0, -2.0]);
+
+        let depth = DepthGradient::from_segments(&segments).unwrap();
+        assert_eq!(depth, DepthGradient { top: 0.0, bottom: -2.0 });
+        assert_eq!(depth.color(&segments[4]), FeedGradient::color_at(1.0));
+
+        let mut renderer = ToolpathRenderer::new();
+        renderer.set_segments(segments);
+        renderer.z_filter = Some(ZFilter::level(-2.0));
+        assert_eq!(renderer.generate_vertices().len(), 2);
+        renderer.z_filter = Some(ZFilter { min: -2.0, max: -1.0 });
+        assert_eq!(renderer.generate_vertices().len(), 6);
+    }
+
+    #[test]
+    fn test_clip_plane() {
+        let point = |x: f64, z: f64| Point3D { x, y: 0.0, z };
+        let segments = vec![
+            Segment::linear(point(0.0, -1.0), point(10.0, -1.0), 500.0),
+            Segment::linear(point(10.0, -1.0), point(10.0, -3.0), 100.0),
+            Segment::linear(point(10.0, -3.0), point(0.0, -3.0), 500.0),
+        ];
+        assert_eq!(ClipPlane::range(&segments, ClipAxis::Z), Some((-3.0, -1.0)));
+
+        let mut renderer = ToolpathRenderer::new();
+        renderer.set_segments(segments);
+        renderer.clip_plane = Some(ClipPlane { axis: ClipAxis::Z, position: -2.0, flip: false });
+        assert_eq!(renderer.generate_vertices().len(), 4);
+        renderer.clip_plane = Some(ClipPlane { axis: ClipAxis::X, position: 5.0, flip: true });
+        assert_eq!(renderer.generate_vertices().len(), 6);
+        renderer.clip_plane = Some(ClipPlane { axis: ClipAxis::X, position: 12.0, flip: true });
+        assert!(renderer.generate_vertices().is_empty());
+    }
+
+    #[test]
+    fn test_feed_rate_colors() {
+        let origin = Point3D { x: 0.0, y: 0.0, z: 0.0 };
+        let end = Point3D { x: 10.0, y: 0.0, z: 0.0 };
+        let segments = vec![
+            Segment::rapid(origin, end),
+            Segment::linear(end, origin, 200.0),
+            Segment::linear(origin, end, 1000.0),
+            Segment::linear(end, origin, 600.0),
+        ];
+
+        let gradient = FeedGradient::from_segments(&segments).unwrap();
+        assert_eq!(gradient, FeedGradient { min: 200.0, max: 1000.0 });
+        assert_eq!(gradient.fraction(600.0), 0.5);
+        assert_eq!(gradient.color(200.0), [0.0, 0.2, 1.0, 1.0]);
+        let fastest = gradient.color(1000.0);
+        assert_eq!(fastest[0], 1.0);
+        assert!(fastest[1] < 0.11 && fastest[2] == 0.0);
+
+        let mut renderer = ToolpathRenderer::new();
+        renderer.color_mode = ToolpathColorMode::FeedRate;
+        renderer.set_segments(segments);
+        let vertices = renderer.generate_vertices();
+        assert_eq!(vertices[0].color, FeedGradient::RAPID_COLOR);
+        assert_eq!(vertices[6].color, gradient.color(600.0));
+
+        renderer.color_mode = ToolpathColorMode::Depth;
+        let vertices = renderer.generate_vertices();
+        assert_eq!(vertices[2].color, FeedGradient::color_at(1.0));
+
+        // A single feed rate still maps to a color
+        let single = FeedGradient { min: 300.0, max: 300.0 };
+        assert_eq!(single.color(300.0), FeedGradient::color_at(1.0));
+    }
 }
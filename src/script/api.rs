@@ -2,6 +2,7 @@
 //!
 //! Provides the scripting interface to application functionality.
 
+use crate::parser::{Point3D, Segment};
 use crate::state::AppState;
 use tokio::sync::mpsc;
 
@@ -79,12 +80,52 @@ impl ScriptApi {
         self.state.is_connected()
     }
     
+    /// Get a link metric by name (e.g. "lines_per_sec", "status_rtt_ms")
+    pub fn get_metric(&self, name: String) -> f64 {
+        self.state.link_metrics.read().get(&name).unwrap_or(0.0)
+    }
+    
     /// Get current machine state as a string
     pub fn get_state(&self) -> String {
         let machine = self.state.machine.read();
         format!("{:?}", machine.status)
     }
     
+    /// Check if a program is loaded
+    pub fn program_loaded(&self) -> bool {
+        self.state.analysis.read().is_loaded()
+    }
+    
+    /// Number of lines in the loaded program
+    pub fn program_line_count(&self) -> usize {
+        self.state.analysis.read().line_count()
+    }
+    
+    /// Number of motion segments in the loaded program
+    pub fn program_segment_count(&self) -> usize {
+        self.state.analysis.read().segment_count()
+    }
+    
+    /// Minimum and maximum corners of the loaded program's moves
+    pub fn program_bounds(&self) -> Option<(Point3D, Point3D)> {
+        self.state.analysis.read().bounds()
+    }
+    
+    /// Estimated run time of the loaded program in seconds
+    pub fn program_estimated_time(&self) -> f64 {
+        self.state.analysis.read().estimate().total_time().as_secs_f64()
+    }
+    
+    /// Text of a line of the loaded program (1-based), empty if out of range
+    pub fn program_line(&self, number: usize) -> String {
+        self.state.analysis.read().line(number).unwrap_or_default().to_string()
+    }
+    
+    /// Motion segments generated by a line of the loaded program (1-based)
+    pub fn program_line_segments(&self, number: usize) -> Vec<Segment> {
+        self.state.analysis.read().line_segments(number).cloned().collect()
+    }
+    
     /// Start program execution
     pub fn start_program(&self) -> bool {
         self.command_tx.send(ScriptCommand::StartProgram).is_ok()
@@ -2,18 +2,45 @@
 //!
 //! Provides scripting support using the Rhai scripting engine.
 //! Allows users to automate tasks and extend application functionality.
+//! Scripts can also inspect the loaded program read-only, e.g. to refuse
+//! to run one that cuts deeper than the stock allows. Plugins build on the
+//! same engine and functions.
 
-use rhai::{Engine, Scope, Dynamic};
+use rhai::{Array, Engine, FuncArgs, Scope, Dynamic, Map, AST};
 use std::sync::Arc;
+use crate::parser::{Segment, SegmentType};
 use crate::utils::{Error, Result};
 
 mod api;
 mod executor;
+mod plugins;
 mod user_commands;
 
 pub use api::{ScriptApi, ScriptCommand};
 pub use executor::{ScriptExecutor, UserScript, ScriptLibrary};
-pub use user_commands::{UserCommand, UserCommandLibrary};
+pub use plugins::{ConnectionHooks, PanelItem, Plugin, PluginHook, PluginHost, PluginManifest};
+pub(crate) use plugins::MAX_OPERATIONS;
+pub use user_commands::{CommandContext, EnableCondition, UserCommand, UserCommandLibrary};
+
+/// A segment as a script map: type, start and end coordinates and feed
+fn segment_map(segment: &Segment) -> Map {
+    let kind = match segment.segment_type {
+        SegmentType::Rapid => "rapid",
+        SegmentType::Linear => "line",
+        SegmentType::ArcCW => "arc_cw",
+        SegmentType::ArcCCW => "arc_ccw",
+    };
+    let mut map = Map::new();
+    map.insert("type".into(), kind.into());
+    for (name, value) in [
+        ("start_x", segment.start.x), ("start_y", segment.start.y), ("start_z", segment.start.z),
+        ("end_x", segment.end.x), ("end_y", segment.end.y), ("end_z", segment.end.z),
+        ("feed", segment.feed_rate),
+    ] {
+        map.insert(name.into(), value.into());
+    }
+    map
+}
 
 /// Script context containing application state and API access
 pub struct ScriptContext {
@@ -75,6 +102,60 @@ impl ScriptContext {
             api_clone.get_state()
         });
         
+        let api_clone = api.clone();
+        engine.register_fn("get_metric", move |name: &str| {
+            api_clone.get_metric(name.to_string())
+        });
+        
+        // Loaded program analysis
+        let api_clone = api.clone();
+        engine.register_fn("program_loaded", move || {
+            api_clone.program_loaded()
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_line_count", move || {
+            api_clone.program_line_count() as i64
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_segment_count", move || {
+            api_clone.program_segment_count() as i64
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_bounds", move || {
+            let mut bounds = Map::new();
+            if let Some((min, max)) = api_clone.program_bounds() {
+                for (name, value) in [
+                    ("min_x", min.x), ("min_y", min.y), ("min_z", min.z),
+                    ("max_x", max.x), ("max_y", max.y), ("max_z", max.z),
+                ] {
+                    bounds.insert(name.into(), value.into());
+                }
+            }
+            bounds
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_estimated_time", move || {
+            api_clone.program_estimated_time()
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_line", move |number: i64| {
+            api_clone.program_line(number.max(0) as usize)
+        });
+        
+        let api_clone = api.clone();
+        engine.register_fn("program_line_segments", move |number: i64| {
+            api_clone
+                .program_line_segments(number.max(0) as usize)
+                .iter()
+                .map(|segment| Dynamic::from_map(segment_map(segment)))
+                .collect::<Array>()
+        });
+        
         // Program control
         let api_clone = api.clone();
         engine.register_fn("start_program", move || {
@@ -114,4 +195,21 @@ impl ScriptContext {
         self.engine.eval_with_scope(scope, script)
             .map_err(|e| Error::Script(format!("Script error: {}", e)))
     }
+    
+    /// Limit the operations one evaluation or call may take
+    pub fn set_max_operations(&mut self, operations: u64) {
+        self.engine.set_max_operations(operations);
+    }
+    
+    /// Compile a script once to call its functions repeatedly
+    pub fn compile(&self, script: &str) -> Result<AST> {
+        self.engine.compile(script)
+            .map_err(|e| Error::Script(format!("Script error: {}", e)))
+    }
+    
+    /// Call a function defined in a compiled script
+    pub fn call_fn(&self, ast: &AST, name: &str, args: impl FuncArgs) -> Result<Dynamic> {
+        self.engine.call_fn(&mut Scope::new(), ast, name, args)
+            .map_err(|e| Error::Script(format!("{}: {}", name, e)))
+    }
 }
@@ -0,0 +1,453 @@
+//! Plugins
+//!
+//! A plugin is a folder in `plugins/` next to the settings file holding a
+//! `plugin.toml` manifest and a Rhai script. The script extends rCandle by
+//! defining any of these functions:
+//!
+//! - `panel()` returns the rows of a side panel as an array of maps:
+//!   `#{ heading: "..." }`, `#{ label: "..." }`, `#{ value: "Name", text: "..." }`,
+//!   `#{ button: "Text", id: "..." }` or `#{ separator: true }`. Clicking a
+//!   button calls `on_click(id)`.
+//! - `post_process(lines)` returns the lines of a program as it is loaded.
+//! - `on_send(line)` returns the line to send in its place, or "" to send
+//!   an empty line instead; `on_receive(line)` sees each line received.
+//!
+//! Plugins call the same functions as scripts, such as `send_command` and
+//! `get_position`. A plugin only runs once enabled in the plugin manager.
+
+use rhai::{Array, Dynamic, Map, AST};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::{ScriptApi, ScriptContext};
+use crate::utils::{Error, Result};
+
+/// Manifest file in each plugin folder
+const MANIFEST_FILE: &str = "plugin.toml";
+
+/// Operations one plugin call may take, so a runaway loop can't hang the UI
+pub(crate) const MAX_OPERATIONS: u64 = 1_000_000;
+
+fn default_script() -> String {
+    "plugin.rhai".to_string()
+}
+
+/// A plugin's `plugin.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Display name
+    pub name: String,
+    /// Version, for display
+    #[serde(default)]
+    pub version: String,
+    /// What the plugin does
+    #[serde(default)]
+    pub description: String,
+    /// Author, for display
+    #[serde(default)]
+    pub author: String,
+    /// Script file, relative to the plugin folder
+    #[serde(default = "default_script")]
+    pub script: String,
+}
+
+/// Extension point a plugin's script implements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PluginHook {
+    /// `panel()`, with `on_click(id)` for its buttons
+    Panel,
+    /// `post_process(lines)`
+    PostProcess,
+    /// `on_send(line)` or `on_receive(line)`
+    Connection,
+}
+
+impl PluginHook {
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            PluginHook::Panel => "Side panel",
+            PluginHook::PostProcess => "Post-processor",
+            PluginHook::Connection => "Connection wrapper",
+        }
+    }
+
+    /// Hook a script function implements, if any
+    fn of_function(name: &str, params: usize) -> Option<Self> {
+        match (name, params) {
+            ("panel", 0) => Some(PluginHook::Panel),
+            ("post_process", 1) => Some(PluginHook::PostProcess),
+            ("on_send", 1) | ("on_receive", 1) => Some(PluginHook::Connection),
+            _ => None,
+        }
+    }
+}
+
+/// One row of a plugin's side panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanelItem {
+    /// Bold heading
+    Heading(String),
+    /// Plain text
+    Label(String),
+    /// Named readout
+    Value {
+        /// Readout name
+        name: String,
+        /// Readout text
+        text: String,
+    },
+    /// Button calling `on_click(id)`
+    Button {
+        /// Button text
+        text: String,
+        /// Passed to `on_click`
+        id: String,
+    },
+    /// Horizontal line
+    Separator,
+}
+
+impl PanelItem {
+    /// Read a row from what `panel()` returned
+    fn from_dynamic(item: Dynamic) -> Option<Self> {
+        if item.is_string() {
+            return Some(PanelItem::Label(item.to_string()));
+        }
+        let map = item.try_cast::<Map>()?;
+        let text = |key: &str| map.get(key).map(|value| value.to_string());
+        if let Some(heading) = text("heading") {
+            Some(PanelItem::Heading(heading))
+        } else if let Some(name) = text("value") {
+            Some(PanelItem::Value { name, text: text("text").unwrap_or_default() })
+        } else if let Some(button) = text("button") {
+            let id = text("id").unwrap_or_else(|| button.clone());
+            Some(PanelItem::Button { text: button, id })
+        } else if let Some(label) = text("label") {
+            Some(PanelItem::Label(label))
+        } else {
+            map.contains_key("separator").then_some(PanelItem::Separator)
+        }
+    }
+}
+
+/// A plugin found in the plugins folder
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    /// Folder name, which identifies the plugin in the settings
+    pub id: String,
+    /// Manifest, or the folder name if it could not be read
+    pub manifest: PluginManifest,
+    /// Plugin folder
+    pub dir: PathBuf,
+    /// Whether the plugin runs
+    pub enabled: bool,
+    /// Extension points the script implements
+    pub hooks: Vec<PluginHook>,
+    /// Why the plugin failed to load or last failed to run
+    pub error: Option<String>,
+    ast: Option<Arc<AST>>,
+}
+
+impl Plugin {
+    /// Whether the plugin is enabled, loaded and implements a hook
+    pub fn provides(&self, hook: PluginHook) -> bool {
+        self.enabled && self.ast.is_some() && self.hooks.contains(&hook)
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        self.ast.as_ref().is_some_and(|ast| has_function(ast, name))
+    }
+}
+
+fn has_function(ast: &AST, name: &str) -> bool {
+    ast.iter_functions().any(|function| function.name == name)
+}
+
+/// Loads plugins and runs their hooks
+pub struct PluginHost {
+    context: Arc<ScriptContext>,
+    plugins: Vec<Plugin>,
+}
+
+impl PluginHost {
+    /// Create a host with no plugins loaded
+    pub fn new(api: Arc<ScriptApi>) -> Self {
+        let mut context = ScriptContext::new(api);
+        context.set_max_operations(MAX_OPERATIONS);
+        Self { context: Arc::new(context), plugins: Vec::new() }
+    }
+
+    /// Get the plugins folder, next to the settings file
+    pub fn default_dir() -> Result<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "rCandle")
+            .ok_or_else(|| Error::config("Failed to determine config directory"))?;
+
+        let plugin_dir = dirs.config_dir().join("plugins");
+        std::fs::create_dir_all(&plugin_dir)?;
+
+        Ok(plugin_dir)
+    }
+
+    /// Load every plugin folder in `dir`, enabling those listed in `enabled`
+    pub fn load_dir(&mut self, dir: &Path, enabled: &[String]) -> Result<()> {
+        let mut folders: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(MANIFEST_FILE).is_file())
+            .collect();
+        folders.sort();
+        self.plugins = folders.iter().map(|folder| self.load_plugin(folder, enabled)).collect();
+        Ok(())
+    }
+
+    fn load_plugin(&self, dir: &Path, enabled: &[String]) -> Plugin {
+        let id = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut plugin = Plugin {
+            manifest: PluginManifest {
+                name: id.clone(),
+                version: String::new(),
+                description: String::new(),
+                author: String::new(),
+                script: default_script(),
+            },
+            dir: dir.to_path_buf(),
+            enabled: enabled.contains(&id),
+            hooks: Vec::new(),
+            error: None,
+            ast: None,
+            id,
+        };
+        let loaded = Self::read_manifest(dir).and_then(|manifest| {
+            plugin.manifest = manifest;
+            let script = std::fs::read_to_string(dir.join(&plugin.manifest.script))?;
+            self.context.compile(&script)
+        });
+        match loaded {
+            Ok(ast) => {
+                for function in ast.iter_functions() {
+                    if let Some(hook) = PluginHook::of_function(function.name, function.params.len()) {
+                        if !plugin.hooks.contains(&hook) {
+                            plugin.hooks.push(hook);
+                        }
+                    }
+                }
+                plugin.hooks.sort();
+                plugin.ast = Some(Arc::new(ast));
+            }
+            Err(e) => plugin.error = Some(e.to_string()),
+        }
+        plugin
+    }
+
+    fn read_manifest(dir: &Path) -> Result<PluginManifest> {
+        let contents = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::config(format!("Failed to parse plugin manifest: {}", e)))
+    }
+
+    /// Plugins found, in folder name order
+    pub fn plugins(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    /// Enable or disable a plugin, clearing its last error
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(plugin) = self.plugins.get_mut(index) {
+            plugin.enabled = enabled;
+            if plugin.ast.is_some() {
+                plugin.error = None;
+            }
+        }
+    }
+
+    /// Ids of the enabled plugins, for the settings
+    pub fn enabled_ids(&self) -> Vec<String> {
+        self.plugins.iter().filter(|plugin| plugin.enabled).map(|plugin| plugin.id.clone()).collect()
+    }
+
+    /// Run the enabled post-processors over a program
+    ///
+    /// Returns the processed program and the names of the plugins that
+    /// changed it.
+    pub fn post_process(&mut self, content: &str) -> Result<(String, Vec<String>)> {
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut applied = Vec::new();
+        for plugin in self.plugins.iter_mut().filter(|plugin| plugin.provides(PluginHook::PostProcess)) {
+            let Some(ast) = plugin.ast.clone() else {
+                continue;
+            };
+            let input: Array = lines.iter().cloned().map(Dynamic::from).collect();
+            let result = self
+                .context
+                .call_fn(&ast, "post_process", (input,))
+                .and_then(|output| {
+                    output
+                        .into_array()
+                        .map_err(|_| Error::script("post_process must return an array of lines"))
+                })
+                .map_err(|e| {
+                    plugin.error = Some(e.to_string());
+                    e.with_context(format!("Plugin \"{}\" failed", plugin.manifest.name))
+                })?;
+            let output: Vec<String> = result.into_iter().map(|line| line.to_string()).collect();
+            if output != lines {
+                applied.push(plugin.manifest.name.clone());
+                lines = output;
+            }
+        }
+
+        let mut processed = lines.join("\n");
+        if content.ends_with('\n') {
+            processed.push('\n');
+        }
+        Ok((processed, applied))
+    }
+
+    /// Rows of a plugin's side panel
+    ///
+    /// A plugin whose `panel()` fails keeps its error and is not called
+    /// again until it is re-enabled.
+    pub fn panel(&mut self, index: usize) -> Vec<PanelItem> {
+        let Some(plugin) = self.plugins.get_mut(index) else {
+            return Vec::new();
+        };
+        let Some(ast) = plugin.ast.clone().filter(|_| plugin.error.is_none()) else {
+            return Vec::new();
+        };
+        match self.context.call_fn(&ast, "panel", ()).map(Dynamic::into_array) {
+            Ok(Ok(items)) => items.into_iter().filter_map(PanelItem::from_dynamic).collect(),
+            Ok(Err(_)) => {
+                plugin.error = Some("panel must return an array of rows".to_string());
+                Vec::new()
+            }
+            Err(e) => {
+                plugin.error = Some(e.to_string());
+                Vec::new()
+            }
+        }
+    }
+
+    /// Call a plugin's `on_click` for one of its panel buttons
+    pub fn click(&mut self, index: usize, id: &str) {
+        let Some(plugin) = self.plugins.get_mut(index) else {
+            return;
+        };
+        if !plugin.has_function("on_click") {
+            return;
+        }
+        if let Some(ast) = plugin.ast.clone() {
+            if let Err(e) = self.context.call_fn(&ast, "on_click", (id.to_string(),)) {
+                plugin.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Enabled connection wrappers, if there are any
+    pub fn connection_hooks(&self) -> Option<ConnectionHooks> {
+        let scripts: Vec<(String, Arc<AST>)> = self
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.provides(PluginHook::Connection))
+            .filter_map(|plugin| Some((plugin.manifest.name.clone(), plugin.ast.clone()?)))
+            .collect();
+        (!scripts.is_empty()).then(|| ConnectionHooks { context: self.context.clone(), scripts })
+    }
+}
+
+/// Enabled plugins' `on_send` and `on_receive`, for wrapping a connection
+#[derive(Clone)]
+pub struct ConnectionHooks {
+    context: Arc<ScriptContext>,
+    /// Plugin name and script, in load order
+    scripts: Vec<(String, Arc<AST>)>,
+}
+
+impl ConnectionHooks {
+    /// Line to send after every plugin has had it
+    pub fn on_send(&self, line: &str) -> Result<String> {
+        let mut line = line.to_string();
+        for (name, ast) in &self.scripts {
+            if !has_function(ast, "on_send") {
+                continue;
+            }
+            line = self
+                .context
+                .call_fn(ast, "on_send", (line,))
+                .map_err(|e| e.with_context(format!("Plugin \"{}\" failed", name)))?
+                .to_string();
+            if line.contains('\n') {
+                return Err(Error::script(format!("Plugin \"{}\" returned more than one line", name)));
+            }
+        }
+        Ok(line)
+    }
+
+    /// Show a received line to every plugin
+    pub fn on_receive(&self, line: &str) {
+        for (name, ast) in &self.scripts {
+            if has_function(ast, "on_receive") {
+                if let Err(e) = self.context.call_fn(ast, "on_receive", (line.to_string(),)) {
+                    tracing::warn!("Plugin \"{}\" failed in on_receive: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+
+    #[test]
+    fn test_plugin_hooks() {
+        let dir = std::env::temp_dir().join(format!("rcandle_plugins_{}", std::process::id()));
+        let folder = dir.join("inch_guard");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join(MANIFEST_FILE), "name = \"Inch Guard\"\nversion = \"1.0\"\n").unwrap();
+        std::fs::write(
+            folder.join("plugin.rhai"),
+            r#"
+            fn panel() { [#{ heading: "Guard" }, #{ button: "Check", id: "check" }, "ready"] }
+            fn post_process(lines) { lines.filter(|line| !line.starts_with("G20")) }
+            fn on_send(line) { if line == "M0" { "" } else { line } }
+            "#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.join("not_a_plugin")).unwrap();
+
+        let (command_tx, _command_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut host = PluginHost::new(Arc::new(ScriptApi::new(AppState::new(), command_tx)));
+        host.load_dir(&dir, &[]).unwrap();
+        assert_eq!(host.plugins().len(), 1);
+        let plugin = &host.plugins()[0];
+        assert_eq!(plugin.manifest.name, "Inch Guard");
+        assert!(plugin.error.is_none());
+        assert_eq!(plugin.hooks, vec![PluginHook::Panel, PluginHook::PostProcess, PluginHook::Connection]);
+
+        // Nothing runs until the plugin is enabled
+        assert_eq!(host.post_process("G20\nG0 X1\n").unwrap().0, "G20\nG0 X1\n");
+        assert!(host.connection_hooks().is_none());
+
+        host.set_enabled(0, true);
+        assert_eq!(host.enabled_ids(), vec!["inch_guard"]);
+        let (program, applied) = host.post_process("G20\nG0 X1\n").unwrap();
+        assert_eq!(program, "G0 X1\n");
+        assert_eq!(applied, vec!["Inch Guard"]);
+        assert_eq!(
+            host.panel(0),
+            vec![
+                PanelItem::Heading("Guard".to_string()),
+                PanelItem::Button { text: "Check".to_string(), id: "check".to_string() },
+                PanelItem::Label("ready".to_string()),
+            ]
+        );
+
+        let hooks = host.connection_hooks().unwrap();
+        assert_eq!(hooks.on_send("G0 X1").unwrap(), "G0 X1");
+        assert_eq!(hooks.on_send("M0").unwrap(), "");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,8 +1,72 @@
 //! User command system
 //!
 //! Allows users to define custom command buttons with GRBL commands.
+//! Buttons can be limited to the machine states they make sense in, so a
+//! dangerous macro is greyed out instead of failing at runtime.
+//!
+//! The library, including button order, icons, colors, shortcuts and which
+//! categories are collapsed, is kept in `user_commands.toml` next to the
+//! settings file.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use crate::state::MachineStatus;
+use crate::utils::{Error, Result};
+
+/// Machine state a user command button requires to be enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnableCondition {
+    /// A controller is connected
+    Connected,
+    /// The machine reports Idle
+    Idle,
+    /// The machine is in alarm
+    Alarm,
+    /// A program is loaded
+    ProgramLoaded,
+}
+
+impl EnableCondition {
+    /// All conditions, in display order
+    pub const ALL: [EnableCondition; 4] = [
+        EnableCondition::Connected,
+        EnableCondition::Idle,
+        EnableCondition::Alarm,
+        EnableCondition::ProgramLoaded,
+    ];
+
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnableCondition::Connected => "Connected",
+            EnableCondition::Idle => "Idle",
+            EnableCondition::Alarm => "Alarm",
+            EnableCondition::ProgramLoaded => "Program loaded",
+        }
+    }
+
+    /// Whether the condition holds in the given context
+    pub fn is_met(&self, context: &CommandContext) -> bool {
+        match self {
+            EnableCondition::Connected => context.connected,
+            EnableCondition::Idle => context.connected && context.status == MachineStatus::Idle,
+            EnableCondition::Alarm => context.connected && context.status == MachineStatus::Alarm,
+            EnableCondition::ProgramLoaded => context.program_loaded,
+        }
+    }
+}
+
+/// Application state a user command's enable conditions are checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandContext {
+    /// Whether a controller is connected
+    pub connected: bool,
+    /// Last reported machine status
+    pub status: MachineStatus,
+    /// Whether a program is loaded
+    pub program_loaded: bool,
+}
 
 /// A user-defined command button
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +94,18 @@ pub struct UserCommand {
     
     /// Whether the command requires a connection
     pub requires_connection: bool,
+    
+    /// Conditions that must all hold for the button to be enabled
+    #[serde(default)]
+    pub enable_when: Vec<EnableCondition>,
+    
+    /// Prompt shown when confirming (optional, defaults to a generic one)
+    #[serde(default)]
+    pub confirm_message: Option<String>,
+    
+    /// Button color as sRGB (optional, defaults to the theme)
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
 }
 
 impl UserCommand {
@@ -44,6 +120,9 @@ impl UserCommand {
             category: "General".to_string(),
             confirm: false,
             requires_connection: true,
+            enable_when: Vec::new(),
+            confirm_message: None,
+            color: None,
         }
     }
     
@@ -65,6 +144,12 @@ impl UserCommand {
         self
     }
     
+    /// Builder: set button color
+    pub fn with_color(mut self, color: [u8; 3]) -> Self {
+        self.color = Some(color);
+        self
+    }
+    
     /// Builder: set category
     pub fn with_category(mut self, category: String) -> Self {
         self.category = category;
@@ -82,13 +167,60 @@ impl UserCommand {
         self.requires_connection = requires;
         self
     }
+    
+    /// Builder: add an enable condition
+    pub fn enabled_when(mut self, condition: EnableCondition) -> Self {
+        if !self.enable_when.contains(&condition) {
+            self.enable_when.push(condition);
+        }
+        self
+    }
+    
+    /// Builder: require confirmation with a custom prompt
+    pub fn with_confirm_message(mut self, message: String) -> Self {
+        self.confirm = true;
+        self.confirm_message = Some(message);
+        self
+    }
+    
+    /// First condition that is not met, if any
+    pub fn unmet_condition(&self, context: &CommandContext) -> Option<EnableCondition> {
+        if self.requires_connection && !context.connected {
+            return Some(EnableCondition::Connected);
+        }
+        self.enable_when.iter().copied().find(|condition| !condition.is_met(context))
+    }
+    
+    /// Whether the command can run in the given context
+    pub fn is_enabled(&self, context: &CommandContext) -> bool {
+        self.unmet_condition(context).is_none()
+    }
+    
+    /// Prompt to show before running the command
+    pub fn confirm_prompt(&self) -> String {
+        self.confirm_message
+            .clone()
+            .unwrap_or_else(|| format!("Run \"{}\"?", self.name))
+    }
+    
+    /// Button text: the icon, if any, followed by the name
+    pub fn label(&self) -> String {
+        match self.icon.as_deref().map(str::trim) {
+            Some(icon) if !icon.is_empty() => format!("{} {}", icon, self.name),
+            _ => self.name.clone(),
+        }
+    }
 }
 
 /// User command library
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserCommandLibrary {
-    /// User-defined commands
+    /// User-defined commands, in button order
     pub commands: Vec<UserCommand>,
+    
+    /// Categories shown collapsed in the panel
+    #[serde(default)]
+    pub collapsed: BTreeSet<String>,
 }
 
 impl UserCommandLibrary {
@@ -107,14 +239,17 @@ impl UserCommandLibrary {
             vec!["G91".to_string(), "G0 Z5".to_string(), "G90".to_string()],
         )
         .with_description("Raise Z by 5mm in relative mode".to_string())
-        .with_category("Safety".to_string()));
+        .with_category("Safety".to_string())
+        .enabled_when(EnableCondition::Idle));
         
         library.add_command(UserCommand::new(
             "Spindle On".to_string(),
             vec!["M3 S1000".to_string()],
         )
         .with_description("Start spindle at 1000 RPM".to_string())
-        .with_category("Spindle".to_string()));
+        .with_category("Spindle".to_string())
+        .enabled_when(EnableCondition::Idle)
+        .with_confirm_message("Start the spindle at 1000 RPM?".to_string()));
         
         library.add_command(UserCommand::new(
             "Spindle Off".to_string(),
@@ -184,19 +319,198 @@ impl UserCommandLibrary {
         self.commands.iter().map(|c| c.name.clone()).collect()
     }
     
-    /// Get commands by category
-    pub fn commands_by_category(&self, category: &str) -> Vec<&UserCommand> {
-        self.commands.iter().filter(|c| c.category == category).collect()
+    /// Get commands by category, with their index in the library
+    pub fn commands_by_category(&self, category: &str) -> Vec<(usize, &UserCommand)> {
+        self.commands
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.category == category)
+            .collect()
     }
     
-    /// Get all categories
+    /// Get all categories, in the order their first command appears
     pub fn categories(&self) -> Vec<String> {
-        let mut categories: Vec<String> = self.commands
-            .iter()
-            .map(|c| c.category.clone())
-            .collect();
-        categories.sort();
-        categories.dedup();
+        let mut categories: Vec<String> = Vec::new();
+        for command in &self.commands {
+            if !categories.contains(&command.category) {
+                categories.push(command.category.clone());
+            }
+        }
         categories
     }
+    
+    /// Move the command at `from` to position `to`
+    ///
+    /// Returns false if either index is out of range.
+    pub fn move_command(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.commands.len() || to >= self.commands.len() {
+            return false;
+        }
+        let command = self.commands.remove(from);
+        self.commands.insert(to, command);
+        true
+    }
+    
+    /// Whether a category is shown collapsed
+    pub fn is_collapsed(&self, category: &str) -> bool {
+        self.collapsed.contains(category)
+    }
+    
+    /// Collapse or expand a category
+    pub fn set_collapsed(&mut self, category: &str, collapsed: bool) {
+        if collapsed {
+            self.collapsed.insert(category.to_string());
+        } else {
+            self.collapsed.remove(category);
+        }
+    }
+    
+    /// Load a library from a TOML file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::config(format!("Failed to parse user commands: {}", e)))
+    }
+    
+    /// Save the library to a TOML file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| Error::config(format!("Failed to serialize user commands: {}", e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+    
+    /// Get the library file path, next to the settings file
+    pub fn default_path() -> Result<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "rCandle")
+            .ok_or_else(|| Error::config("Failed to determine config directory"))?;
+        
+        let config_dir = dirs.config_dir();
+        std::fs::create_dir_all(config_dir)?;
+        
+        Ok(config_dir.join("user_commands.toml"))
+    }
+    
+    /// Load the library from its default location, or the default commands
+    pub fn load_or_default() -> Self {
+        match Self::default_path() {
+            Ok(path) if path.exists() => Self::load(&path).unwrap_or_else(|e| {
+                tracing::warn!("Failed to load user commands from {:?}: {}", path, e);
+                Self::with_defaults()
+            }),
+            _ => Self::with_defaults(),
+        }
+    }
+    
+    /// Save to the default location
+    pub fn save_default(&self) -> Result<()> {
+        let path = Self::default_path()?;
+        self.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(connected: bool, status: MachineStatus, program_loaded: bool) -> CommandContext {
+        CommandContext { connected, status, program_loaded }
+    }
+
+    #[test]
+    fn test_requires_connection() {
+        let command = UserCommand::new("Home".to_string(), vec!["$H".to_string()]);
+        assert_eq!(
+            command.unmet_condition(&context(false, MachineStatus::Idle, false)),
+            Some(EnableCondition::Connected)
+        );
+        assert!(command.is_enabled(&context(true, MachineStatus::Alarm, false)));
+    }
+
+    #[test]
+    fn test_enable_conditions() {
+        let command = UserCommand::new("Probe".to_string(), vec!["G38.2 Z-10 F50".to_string()])
+            .enabled_when(EnableCondition::Idle)
+            .enabled_when(EnableCondition::ProgramLoaded);
+        assert_eq!(
+            command.unmet_condition(&context(true, MachineStatus::Run, true)),
+            Some(EnableCondition::Idle)
+        );
+        assert_eq!(
+            command.unmet_condition(&context(true, MachineStatus::Idle, false)),
+            Some(EnableCondition::ProgramLoaded)
+        );
+        assert!(command.is_enabled(&context(true, MachineStatus::Idle, true)));
+
+        let unlock = UserCommand::new("Unlock".to_string(), vec!["$X".to_string()])
+            .enabled_when(EnableCondition::Alarm);
+        assert!(!unlock.is_enabled(&context(true, MachineStatus::Idle, false)));
+        assert!(unlock.is_enabled(&context(true, MachineStatus::Alarm, false)));
+    }
+
+    #[test]
+    fn test_confirm_prompt() {
+        let command = UserCommand::new("Spindle Off".to_string(), vec!["M5".to_string()]);
+        assert_eq!(command.confirm_prompt(), "Run \"Spindle Off\"?");
+        let command = command.with_confirm_message("Stop the spindle?".to_string());
+        assert!(command.confirm);
+        assert_eq!(command.confirm_prompt(), "Stop the spindle?");
+    }
+
+    #[test]
+    fn test_label_with_icon() {
+        let command = UserCommand::new("Safe Z".to_string(), vec!["G0 Z5".to_string()]);
+        assert_eq!(command.label(), "Safe Z");
+        assert_eq!(command.with_icon("⬆".to_string()).label(), "⬆ Safe Z");
+    }
+
+    #[test]
+    fn test_reorder_and_categories() {
+        let mut library = UserCommandLibrary::with_defaults();
+        assert_eq!(library.categories(), vec!["Safety", "Spindle", "Coolant"]);
+
+        let last = library.commands.len() - 1;
+        assert!(library.move_command(last, 0));
+        assert_eq!(library.commands[0].name, "Check Mode Off");
+        assert_eq!(library.categories(), vec!["Safety", "Spindle", "Coolant"]);
+        assert!(library.move_command(2, 0));
+        assert_eq!(library.categories()[0], "Spindle");
+        assert!(!library.move_command(0, library.commands.len()));
+
+        let names: Vec<&str> = library
+            .commands_by_category("Safety")
+            .into_iter()
+            .map(|(_, c)| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Check Mode Off", "Safe Z", "Check Mode On"]);
+    }
+
+    #[test]
+    fn test_library_round_trip() {
+        let mut library = UserCommandLibrary::with_defaults();
+        library.commands[0].color = Some([200, 40, 40]);
+        library.commands[0].shortcut = Some("Ctrl+Shift+Z".to_string());
+        library.set_collapsed("Coolant", true);
+
+        let path = std::env::temp_dir().join(format!("rcandle_user_commands_{}.toml", std::process::id()));
+        library.save(&path).unwrap();
+        let loaded = UserCommandLibrary::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.commands.len(), library.commands.len());
+        assert_eq!(loaded.commands[0].color, Some([200, 40, 40]));
+        assert_eq!(loaded.commands[0].shortcut.as_deref(), Some("Ctrl+Shift+Z"));
+        assert_eq!(loaded.commands[1].enable_when, vec![EnableCondition::Idle]);
+        assert!(loaded.is_collapsed("Coolant"));
+        assert!(!loaded.is_collapsed("Safety"));
+    }
+
+    #[test]
+    fn test_deserialize_without_conditions() {
+        let json = r#"{"name":"Safe Z","description":"","commands":["G0 Z5"],"icon":null,
+            "shortcut":null,"category":"Safety","confirm":false,"requires_connection":true}"#;
+        let command: UserCommand = serde_json::from_str(json).unwrap();
+        assert!(command.enable_when.is_empty());
+        assert!(command.confirm_message.is_none());
+    }
 }
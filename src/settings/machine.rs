@@ -0,0 +1,902 @@
+//! Machine profile
+//!
+//! Describes the machine itself rather than the job: named positions such as
+//! the park spot, the tool change position and the tool length probe plate,
+//! together with the parameters used to move between them and to probe.
+//! Work zeros of repeat fixtures can be saved in slots and restored later,
+//! and the fixture library records where vises, pin grids and spoilboard
+//! areas sit on the bed so a job can be placed on one of them.
+//! Cutting time is tracked per tool so the feed can be lowered as bits wear,
+//! and a pre-run checklist can be confirmed before each program.
+//! All positions are in machine coordinates so they survive work offset
+//! changes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grbl::{word_value, EdgeFinder, ProgramStreamer, StockEdge, StreamOptions};
+use crate::parser::{PlasmaSettings, SpindleCalibration};
+use crate::state::RecoverySettings;
+
+/// Purpose of a named position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PositionRole {
+    /// Where the machine is parked after a job
+    Park,
+    /// Where the spindle is accessible for changing tools
+    ToolChange,
+    /// Above the tool length probe plate
+    ProbePlate,
+    /// User-defined position
+    Custom,
+}
+
+impl std::fmt::Display for PositionRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionRole::Park => write!(f, "Park"),
+            PositionRole::ToolChange => write!(f, "Tool Change"),
+            PositionRole::ProbePlate => write!(f, "Probe Plate"),
+            PositionRole::Custom => write!(f, "Custom"),
+        }
+    }
+}
+
+/// What to do with a Z-minus jog while the spindle is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpindleJogInterlock {
+    /// No check
+    Off,
+    /// Ask before jogging
+    Confirm,
+    /// Refuse the jog
+    Block,
+}
+
+impl std::fmt::Display for SpindleJogInterlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpindleJogInterlock::Off => write!(f, "Off"),
+            SpindleJogInterlock::Confirm => write!(f, "Confirm"),
+            SpindleJogInterlock::Block => write!(f, "Block"),
+        }
+    }
+}
+
+/// Maximum number of work zero slots in a profile
+pub const MAX_WORK_ZERO_SLOTS: usize = 10;
+
+/// A saved work zero
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkZeroSlot {
+    /// Display name, e.g. "Vise left jaw"
+    pub name: String,
+    /// Work offset (X, Y, Z) from the machine zero
+    pub offset: [f64; 3],
+}
+
+impl WorkZeroSlot {
+    /// Command writing the slot into a coordinate system (P1 for G54)
+    pub fn restore_command(&self, p_number: u32) -> String {
+        let [x, y, z] = self.offset;
+        format!("G10 L2 P{} X{:.3} Y{:.3} Z{:.3}", p_number, x, y, z)
+    }
+}
+
+/// Kind of fixture on the machine bed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FixtureKind {
+    /// Machine vise
+    Vise,
+    /// Grid of locating pins
+    PinGrid,
+    /// Area of the spoilboard
+    Spoilboard,
+}
+
+impl std::fmt::Display for FixtureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixtureKind::Vise => write!(f, "Vise"),
+            FixtureKind::PinGrid => write!(f, "Pin Grid"),
+            FixtureKind::Spoilboard => write!(f, "Spoilboard"),
+        }
+    }
+}
+
+/// A fixture on the bed providing a work zero
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Display name, e.g. "Vise 1"
+    pub name: String,
+    /// What the fixture is
+    pub kind: FixtureKind,
+    /// Work zero the fixture provides, in machine coordinates
+    pub zero: [f64; 3],
+    /// Usable area from the zero in +X and +Y (mm)
+    pub size: [f64; 2],
+}
+
+impl Fixture {
+    /// Create a fixture of a default size
+    pub fn new(name: impl Into<String>, kind: FixtureKind, zero: [f64; 3]) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            zero,
+            size: [100.0, 100.0],
+        }
+    }
+
+    /// Command setting a coordinate system to the fixture zero (P1 for G54)
+    pub fn apply_command(&self, p_number: u32) -> String {
+        let [x, y, z] = self.zero;
+        format!("G10 L2 P{} X{:.3} Y{:.3} Z{:.3}", p_number, x, y, z)
+    }
+
+    /// Usable area as (min, max) XY corners seen from a work offset
+    pub fn area(&self, work_offset: [f64; 3]) -> ([f64; 2], [f64; 2]) {
+        let x = self.zero[0] - work_offset[0];
+        let y = self.zero[1] - work_offset[1];
+        ([x, y], [x + self.size[0], y + self.size[1]])
+    }
+
+    /// Whether a job with these XY extents (work coordinates) fits the usable area
+    pub fn fits(&self, min: [f64; 2], max: [f64; 2]) -> bool {
+        min[0] >= 0.0 && min[1] >= 0.0 && max[0] <= self.size[0] && max[1] <= self.size[1]
+    }
+}
+
+/// Tool breakage check on the probe plate during programs
+///
+/// The program is held between lines, the spindle stopped and the tool
+/// measured on the probe plate. If its length differs from the length
+/// measured when its offset was applied by more than the threshold, the
+/// program stays held for the operator; otherwise the spindle is restarted
+/// and the machine returns to where it left the cut.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolCheckSettings {
+    /// Check the tool while programs run
+    pub enabled: bool,
+    /// Minutes between timed checks (0 for none)
+    pub interval_minutes: f64,
+    /// Check the outgoing tool at each tool change
+    pub after_each_tool: bool,
+    /// Largest allowed length deviation (mm)
+    pub threshold: f64,
+    /// Wait after restarting the spindle before returning to the cut (seconds)
+    pub spin_up_delay: f64,
+}
+
+impl Default for ToolCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 30.0,
+            after_each_tool: true,
+            threshold: 0.2,
+            spin_up_delay: 3.0,
+        }
+    }
+}
+
+impl ToolCheckSettings {
+    /// Whether a timed check is due `elapsed` after the last one
+    pub fn interval_due(&self, elapsed: std::time::Duration) -> bool {
+        self.enabled && self.interval_minutes > 0.0 && elapsed.as_secs_f64() >= self.interval_minutes * 60.0
+    }
+
+    /// Whether a length deviation means the tool is broken or worn
+    pub fn exceeded(&self, deviation: f64) -> bool {
+        deviation.abs() > self.threshold.abs()
+    }
+}
+
+/// Cutting time of one tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    /// Tool number (T word), 0 for the tool loaded outside a program
+    pub tool: u32,
+    /// Minutes the program ran with the spindle on since the bit was fitted new
+    pub cutting_minutes: f64,
+}
+
+/// Feed reduction as tools wear
+///
+/// Cheap bits dull quickly. Every `interval_minutes` of cutting on a tool the
+/// feed override is lowered by `reduction_percent`, but never below
+/// `minimum_override`. Fitting a new bit resets its cutting time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolWearSettings {
+    /// Reduce the feed override as tools wear
+    pub enabled: bool,
+    /// Minutes of cutting between reductions
+    pub interval_minutes: f64,
+    /// Feed override reduction per interval (percent points)
+    pub reduction_percent: f64,
+    /// Lowest feed override the reductions go to (percent)
+    pub minimum_override: f64,
+    /// Cutting time per tool
+    pub tools: Vec<ToolUsage>,
+}
+
+impl Default for ToolWearSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 20.0,
+            reduction_percent: 5.0,
+            minimum_override: 70.0,
+            tools: Vec::new(),
+        }
+    }
+}
+
+impl ToolWearSettings {
+    /// Cutting time of a tool in minutes
+    pub fn cutting_minutes(&self, tool: u32) -> f64 {
+        self.tools.iter().find(|usage| usage.tool == tool).map_or(0.0, |usage| usage.cutting_minutes)
+    }
+
+    /// Add cutting time to a tool
+    pub fn add_cutting(&mut self, tool: u32, minutes: f64) {
+        match self.tools.iter_mut().find(|usage| usage.tool == tool) {
+            Some(usage) => usage.cutting_minutes += minutes,
+            None => self.tools.push(ToolUsage { tool, cutting_minutes: minutes }),
+        }
+    }
+
+    /// Forget the cutting time of a tool, as when a new bit is fitted
+    pub fn reset(&mut self, tool: u32) {
+        self.tools.retain(|usage| usage.tool != tool);
+    }
+
+    /// Feed override reduction for a tool (percent points)
+    pub fn reduction(&self, tool: u32) -> f64 {
+        if !self.enabled || self.interval_minutes <= 0.0 {
+            return 0.0;
+        }
+        let steps = (self.cutting_minutes(tool) / self.interval_minutes).floor();
+        let limit = (100.0 - self.minimum_override).max(0.0);
+        (steps * self.reduction_percent.max(0.0)).min(limit)
+    }
+}
+
+/// What a maintenance reminder counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaintenanceBasis {
+    /// Hours with the spindle running
+    #[default]
+    SpindleHours,
+    /// Meters traveled by all axes
+    Distance,
+}
+
+impl std::fmt::Display for MaintenanceBasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaintenanceBasis::SpindleHours => write!(f, "spindle hours"),
+            MaintenanceBasis::Distance => write!(f, "m of travel"),
+        }
+    }
+}
+
+/// A maintenance task repeated at an interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceReminder {
+    /// Task, e.g. "Lubricate rails"
+    pub name: String,
+    /// What the interval counts
+    #[serde(default)]
+    pub basis: MaintenanceBasis,
+    /// Interval in spindle hours or meters
+    pub interval: f64,
+    /// Counter value when the task was last done
+    #[serde(default)]
+    pub last_done: f64,
+}
+
+impl MaintenanceReminder {
+    fn new(name: &str, basis: MaintenanceBasis, interval: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            basis,
+            interval,
+            last_done: 0.0,
+        }
+    }
+}
+
+/// Runtime counters and maintenance reminders
+///
+/// The counters are updated while the machine runs and saved with the
+/// profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    /// Hours with the spindle running
+    pub spindle_hours: f64,
+    /// Meters traveled by all axes
+    pub distance_m: f64,
+    /// Maintenance reminders
+    pub reminders: Vec<MaintenanceReminder>,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            spindle_hours: 0.0,
+            distance_m: 0.0,
+            reminders: vec![
+                MaintenanceReminder::new("Lubricate rails", MaintenanceBasis::SpindleHours, 40.0),
+                MaintenanceReminder::new("Check belt tension", MaintenanceBasis::Distance, 5000.0),
+            ],
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    /// Current value of a counter
+    pub fn counter(&self, basis: MaintenanceBasis) -> f64 {
+        match basis {
+            MaintenanceBasis::SpindleHours => self.spindle_hours,
+            MaintenanceBasis::Distance => self.distance_m,
+        }
+    }
+
+    /// Spindle hours or meters left until a reminder is due, negative when overdue
+    pub fn remaining(&self, reminder: &MaintenanceReminder) -> f64 {
+        reminder.last_done + reminder.interval - self.counter(reminder.basis)
+    }
+
+    /// Reminders that are due
+    pub fn due(&self) -> Vec<&MaintenanceReminder> {
+        self.reminders
+            .iter()
+            .filter(|reminder| reminder.interval > 0.0 && self.remaining(reminder) <= 0.0)
+            .collect()
+    }
+
+    /// Record a reminder's task as done now
+    pub fn mark_done(&mut self, index: usize) {
+        let Some(basis) = self.reminders.get(index).map(|reminder| reminder.basis) else {
+            return;
+        };
+        let counter = self.counter(basis);
+        self.reminders[index].last_done = counter;
+    }
+}
+
+/// Actions sent when a program finishes streaming
+///
+/// Sent after the program's last line, so they also run if the program
+/// itself never turns the spindle or coolant off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobEndSettings {
+    /// Turn the spindle off (M5)
+    pub spindle_off: bool,
+    /// Turn the coolant off (M9)
+    pub coolant_off: bool,
+    /// Move to the park position
+    pub park: bool,
+    /// Custom commands sent after parking
+    pub commands: Vec<String>,
+    /// Switch a grblHAL output off (M65), e.g. to cut the spindle contactor power
+    pub power_off: bool,
+    /// Output switched off
+    pub power_output: u8,
+}
+
+impl Default for JobEndSettings {
+    fn default() -> Self {
+        Self {
+            spindle_off: true,
+            coolant_off: true,
+            park: false,
+            commands: Vec::new(),
+            power_off: false,
+            power_output: 0,
+        }
+    }
+}
+
+/// A named position in machine coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedPosition {
+    /// Display name
+    pub name: String,
+    /// What the position is used for
+    pub role: PositionRole,
+    /// Machine coordinates (X, Y, Z)
+    pub position: [f64; 3],
+}
+
+impl NamedPosition {
+    /// Create a named position
+    pub fn new(name: impl Into<String>, role: PositionRole, position: [f64; 3]) -> Self {
+        Self {
+            name: name.into(),
+            role,
+            position,
+        }
+    }
+}
+
+/// Machine profile settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MachineProfile {
+    /// Named positions
+    pub positions: Vec<NamedPosition>,
+
+    /// Machine Z to retract to before moving between positions
+    pub safe_z: f64,
+
+    /// Feed rate for the tool length probe (mm/min)
+    pub probe_feed_rate: f64,
+
+    /// Maximum probe travel below the probe plate position (mm)
+    pub probe_distance: f64,
+
+    /// Retract distance after the probe triggers (mm)
+    pub probe_retract: f64,
+
+    /// Probe tip or edge finder diameter (mm)
+    pub probe_tip_diameter: f64,
+
+    /// Maximum travel when probing for an edge (mm)
+    pub edge_probe_distance: f64,
+
+    /// Hold the program at M6 and move to the tool change position
+    pub tool_change_enabled: bool,
+
+    /// Measure the new tool on the probe plate before continuing
+    pub measure_after_tool_change: bool,
+
+    /// Tool breakage checks during programs
+    pub tool_check: ToolCheckSettings,
+
+    /// Feed reduction as tools wear
+    pub tool_wear: ToolWearSettings,
+
+    /// Rate feed override changes are ramped at (%/s, 0 to change at once)
+    pub feed_override_ramp: f64,
+
+    /// Actions when a program finishes
+    pub job_end: JobEndSettings,
+
+    /// Runtime counters and maintenance reminders
+    pub maintenance: MaintenanceSettings,
+
+    /// Number of grblHAL user outputs (M62-M65); 0 hides the controls
+    pub user_outputs: u8,
+
+    /// Interlock for Z-minus jogs while the spindle is running
+    pub spindle_jog_interlock: SpindleJogInterlock,
+
+    /// Largest Z-minus jog allowed without the interlock while the spindle runs (mm)
+    pub spindle_jog_z_limit: f64,
+
+    /// Maximum spindle speed a program may command (RPM, 0 for no limit)
+    pub max_spindle_rpm: f64,
+
+    /// Commands sent after connecting, following the general startup commands
+    pub startup_commands: Vec<String>,
+
+    /// Ask for the pre-run checklist before a program starts
+    pub checklist_enabled: bool,
+
+    /// Pre-run checklist items
+    pub checklist: Vec<String>,
+
+    /// Measured backlash per axis (mm)
+    pub backlash: [f64; 3],
+
+    /// Compensate the measured backlash when streaming programs
+    pub compensate_backlash: bool,
+
+    /// Automatic responses to alarms and the safety door
+    pub recovery: RecoverySettings,
+
+    /// Commanded S versus measured RPM
+    pub spindle_calibration: SpindleCalibration,
+
+    /// Lathe (grblHAL lathe build): X is the cross slide, Z the carriage
+    pub lathe_mode: bool,
+
+    /// Lathe X words and readouts are diameters (G7) rather than radii
+    pub lathe_diameter_mode: bool,
+
+    /// Plasma torch pierce handling and cut chart
+    pub plasma: PlasmaSettings,
+
+    /// Saved work zeros
+    pub work_zero_slots: Vec<WorkZeroSlot>,
+
+    /// Fixtures on the bed
+    pub fixtures: Vec<Fixture>,
+}
+
+impl Default for MachineProfile {
+    fn default() -> Self {
+        Self {
+            positions: vec![
+                NamedPosition::new("Park", PositionRole::Park, [-5.0, -5.0, -1.0]),
+                NamedPosition::new("Tool Change", PositionRole::ToolChange, [-5.0, -5.0, -1.0]),
+                NamedPosition::new("Probe Plate", PositionRole::ProbePlate, [-5.0, -5.0, -1.0]),
+            ],
+            safe_z: -1.0,
+            probe_feed_rate: 100.0,
+            probe_distance: 50.0,
+            probe_retract: 2.0,
+            probe_tip_diameter: 2.0,
+            edge_probe_distance: 10.0,
+            tool_change_enabled: false,
+            measure_after_tool_change: false,
+            tool_check: ToolCheckSettings::default(),
+            tool_wear: ToolWearSettings::default(),
+            feed_override_ramp: 50.0,
+            job_end: JobEndSettings::default(),
+            maintenance: MaintenanceSettings::default(),
+            user_outputs: 0,
+            spindle_jog_interlock: SpindleJogInterlock::Off,
+            spindle_jog_z_limit: 1.0,
+            max_spindle_rpm: 0.0,
+            startup_commands: Vec::new(),
+            checklist_enabled: false,
+            checklist: ["Stock clamped", "Spindle wrench removed", "Dust shoe on"]
+                .map(str::to_string)
+                .to_vec(),
+            backlash: [0.0; 3],
+            compensate_backlash: false,
+            recovery: RecoverySettings::default(),
+            spindle_calibration: SpindleCalibration::default(),
+            lathe_mode: false,
+            lathe_diameter_mode: false,
+            plasma: PlasmaSettings::default(),
+            work_zero_slots: Vec::new(),
+            fixtures: Vec::new(),
+        }
+    }
+}
+
+impl MachineProfile {
+    /// Checklist items to confirm before a program starts, if the checklist is on
+    pub fn checklist_items(&self) -> Vec<&str> {
+        if !self.checklist_enabled {
+            return Vec::new();
+        }
+        self.checklist.iter().map(|item| item.trim()).filter(|item| !item.is_empty()).collect()
+    }
+
+    /// Whether X is programmed and shown as a diameter
+    pub fn diameter_mode(&self) -> bool {
+        self.lathe_mode && self.lathe_diameter_mode
+    }
+
+    /// DRO label for the X axis
+    ///
+    /// grblHAL already reports X as a diameter while G7 is active, so only
+    /// the label changes.
+    pub fn x_label(&self) -> &'static str {
+        if self.diameter_mode() {
+            "X⌀"
+        } else {
+            "X"
+        }
+    }
+
+    /// First position with the given role
+    pub fn position(&self, role: PositionRole) -> Option<&NamedPosition> {
+        self.positions.iter().find(|p| p.role == role)
+    }
+
+    /// Commands that move to a position
+    ///
+    /// The spindle is raised to the safe Z first, then moved in XY, and only
+    /// then lowered, so nothing is dragged across the work.
+    pub fn go_to_commands(&self, position: &NamedPosition) -> Vec<String> {
+        let [x, y, z] = position.position;
+        vec![
+            format!("G53 G0 Z{:.3}", self.safe_z.max(z)),
+            format!("G53 G0 X{:.3} Y{:.3}", x, y),
+            format!("G53 G0 Z{:.3}", z),
+        ]
+    }
+
+    /// Commands that move to the position with the given role, if defined
+    pub fn go_to_role_commands(&self, role: PositionRole) -> Option<Vec<String>> {
+        self.position(role).map(|p| self.go_to_commands(p))
+    }
+
+    /// Edge finding cycle for an edge using the profile's probe parameters
+    pub fn edge_finder(&self, edge: StockEdge) -> EdgeFinder {
+        EdgeFinder {
+            edge,
+            distance: self.edge_probe_distance,
+            feed_rate: self.probe_feed_rate,
+            retract: self.probe_retract,
+            tip_diameter: self.probe_tip_diameter,
+        }
+    }
+
+    /// Backlash to compensate for when streaming, if enabled and measured
+    pub fn backlash_compensation(&self) -> Option<[f64; 3]> {
+        (self.compensate_backlash && self.backlash.iter().any(|b| *b > 0.0)).then_some(self.backlash)
+    }
+
+    /// Whether a jog trips the spindle interlock
+    pub fn spindle_jog_interlocked(&self, spindle_running: bool, z: f64) -> bool {
+        spindle_running
+            && self.spindle_jog_interlock != SpindleJogInterlock::Off
+            && z < -self.spindle_jog_z_limit.abs()
+    }
+
+    /// First program line commanding a spindle speed above the maximum
+    ///
+    /// Returns the 0-based line index and the commanded speed.
+    pub fn spindle_speed_violation(&self, program: &str) -> Option<(usize, f64)> {
+        if self.max_spindle_rpm <= 0.0 {
+            return None;
+        }
+        program.lines().enumerate().find_map(|(index, line)| {
+            let text = ProgramStreamer::prepare_line(line, StreamOptions::default())?;
+            let rpm = word_value(&text, 'S')?;
+            (rpm > self.max_spindle_rpm).then_some((index, rpm))
+        })
+    }
+
+    /// Save a work zero, replacing a slot with the same name
+    ///
+    /// Returns false if every slot is taken.
+    pub fn save_work_zero(&mut self, name: &str, offset: [f64; 3]) -> bool {
+        if let Some(slot) = self.work_zero_slots.iter_mut().find(|slot| slot.name == name) {
+            slot.offset = offset;
+            return true;
+        }
+        if self.work_zero_slots.len() >= MAX_WORK_ZERO_SLOTS {
+            return false;
+        }
+        self.work_zero_slots.push(WorkZeroSlot { name: name.to_string(), offset });
+        true
+    }
+
+    /// Commands sent when a program finishes streaming
+    ///
+    /// Parking is skipped if the profile has no park position, and the
+    /// output is only switched if the machine has it.
+    pub fn job_end_commands(&self) -> Vec<String> {
+        let job_end = &self.job_end;
+        let mut commands = Vec::new();
+        if job_end.spindle_off {
+            commands.push("M5".to_string());
+        }
+        if job_end.coolant_off {
+            commands.push("M9".to_string());
+        }
+        if job_end.park {
+            commands.extend(self.go_to_role_commands(PositionRole::Park).unwrap_or_default());
+        }
+        commands.extend(
+            job_end
+                .commands
+                .iter()
+                .map(|command| command.trim())
+                .filter(|command| !command.is_empty())
+                .map(str::to_string),
+        );
+        if job_end.power_off && job_end.power_output < self.user_outputs {
+            // M65 acts at once, so wait for the moves above to finish first
+            commands.push("G4 P0".to_string());
+            commands.push(format!("M65 P{}", job_end.power_output));
+        }
+        commands
+    }
+
+    /// Commands that measure the tool on the probe plate
+    ///
+    /// Moves to the probe plate, probes down, backs off and returns to the
+    /// safe Z. The distances are in millimeters, so G21 is set first; G21
+    /// and absolute distances (G90) are left set afterwards.
+    pub fn tool_measure_commands(&self) -> Option<Vec<String>> {
+        let mut commands = vec!["G21".to_string()];
+        commands.extend(self.go_to_role_commands(PositionRole::ProbePlate)?);
+        commands.push(format!(
+            "G91 G38.2 Z-{:.3} F{:.0}",
+            self.probe_distance.abs(),
+            self.probe_feed_rate
+        ));
+        commands.push(format!("G0 Z{:.3}", self.probe_retract.abs()));
+        commands.push("G90".to_string());
+        commands.push(format!("G53 G0 Z{:.3}", self.safe_z));
+        Some(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lathe_diameter_mode() {
+        let mut profile = MachineProfile::default();
+        profile.lathe_diameter_mode = true;
+        assert!(!profile.diameter_mode());
+        assert_eq!(profile.x_label(), "X");
+
+        profile.lathe_mode = true;
+        assert!(profile.diameter_mode());
+        assert_eq!(profile.x_label(), "X⌀");
+    }
+
+    #[test]
+    fn test_backlash_compensation() {
+        let mut profile = MachineProfile {
+            compensate_backlash: true,
+            ..Default::default()
+        };
+        assert_eq!(profile.backlash_compensation(), None);
+
+        profile.backlash = [0.05, 0.0, 0.02];
+        assert_eq!(profile.backlash_compensation(), Some([0.05, 0.0, 0.02]));
+        profile.compensate_backlash = false;
+        assert_eq!(profile.backlash_compensation(), None);
+    }
+
+    #[test]
+    fn test_go_to_commands() {
+        let mut profile = MachineProfile::default();
+        profile.positions[1].position = [-400.0, -10.5, -20.0];
+
+        let commands = profile.go_to_role_commands(PositionRole::ToolChange).unwrap();
+        assert_eq!(
+            commands,
+            vec!["G53 G0 Z-1.000", "G53 G0 X-400.000 Y-10.500", "G53 G0 Z-20.000"]
+        );
+        assert!(profile.go_to_role_commands(PositionRole::Custom).is_none());
+    }
+
+    #[test]
+    fn test_spindle_interlocks() {
+        let mut profile = MachineProfile::default();
+        assert!(!profile.spindle_jog_interlocked(true, -10.0));
+
+        profile.spindle_jog_interlock = SpindleJogInterlock::Block;
+        assert!(profile.spindle_jog_interlocked(true, -10.0));
+        assert!(!profile.spindle_jog_interlocked(true, -0.5));
+        assert!(!profile.spindle_jog_interlocked(true, 10.0));
+        assert!(!profile.spindle_jog_interlocked(false, -10.0));
+
+        let program = "M3 S10000 (S30000 in comment)\nG1 X10\nS26000\n";
+        assert_eq!(profile.spindle_speed_violation(program), None);
+        profile.max_spindle_rpm = 24000.0;
+        assert_eq!(profile.spindle_speed_violation(program), Some((2, 26000.0)));
+    }
+
+    #[test]
+    fn test_work_zero_slots() {
+        let mut profile = MachineProfile::default();
+        assert!(profile.save_work_zero("Vise", [-250.0, -120.5, -40.0]));
+        assert!(profile.save_work_zero("Vise", [-251.0, -120.5, -40.0]));
+        assert_eq!(profile.work_zero_slots.len(), 1);
+        assert_eq!(
+            profile.work_zero_slots[0].restore_command(2),
+            "G10 L2 P2 X-251.000 Y-120.500 Z-40.000"
+        );
+
+        for i in 1..MAX_WORK_ZERO_SLOTS {
+            assert!(profile.save_work_zero(&format!("Slot {}", i), [0.0; 3]));
+        }
+        assert!(!profile.save_work_zero("One too many", [0.0; 3]));
+    }
+
+    #[test]
+    fn test_fixture_area() {
+        let mut vise = Fixture::new("Vise", FixtureKind::Vise, [-300.0, -200.0, -50.0]);
+        vise.size = [150.0, 80.0];
+        assert_eq!(vise.apply_command(1), "G10 L2 P1 X-300.000 Y-200.000 Z-50.000");
+        assert_eq!(vise.area([-310.0, -220.0, -40.0]), ([10.0, 20.0], [160.0, 100.0]));
+
+        assert!(vise.fits([0.0, 0.0], [120.0, 80.0]));
+        assert!(!vise.fits([0.0, 0.0], [160.0, 10.0]));
+        assert!(!vise.fits([-5.0, 0.0], [10.0, 10.0]));
+    }
+
+    #[test]
+    fn test_tool_check_settings() {
+        let mut check = ToolCheckSettings::default();
+        let hour = std::time::Duration::from_secs(3600);
+        assert!(!check.interval_due(hour));
+
+        check.enabled = true;
+        assert!(check.interval_due(hour));
+        assert!(!check.interval_due(std::time::Duration::from_secs(60)));
+        check.interval_minutes = 0.0;
+        assert!(!check.interval_due(hour));
+
+        assert!(check.exceeded(-0.5));
+        assert!(check.exceeded(0.3));
+        assert!(!check.exceeded(-0.05));
+    }
+
+    #[test]
+    fn test_tool_wear() {
+        let mut wear = ToolWearSettings::default();
+        wear.add_cutting(2, 45.0);
+        assert_eq!(wear.reduction(2), 0.0);
+
+        wear.enabled = true;
+        assert_eq!(wear.reduction(2), 10.0);
+        assert_eq!(wear.reduction(1), 0.0);
+
+        // Capped at the minimum override
+        wear.add_cutting(2, 200.0);
+        assert_eq!(wear.cutting_minutes(2), 245.0);
+        assert_eq!(wear.reduction(2), 30.0);
+
+        wear.reset(2);
+        assert_eq!(wear.cutting_minutes(2), 0.0);
+    }
+
+    #[test]
+    fn test_checklist_items() {
+        let mut profile = MachineProfile::default();
+        assert!(profile.checklist_items().is_empty());
+
+        profile.checklist_enabled = true;
+        profile.checklist.push("  ".to_string());
+        profile.checklist.push(" Vacuum on ".to_string());
+        assert_eq!(profile.checklist_items(), ["Stock clamped", "Spindle wrench removed", "Dust shoe on", "Vacuum on"]);
+    }
+
+    #[test]
+    fn test_maintenance_reminders() {
+        let mut maintenance = MaintenanceSettings::default();
+        assert!(maintenance.due().is_empty());
+
+        maintenance.spindle_hours = 41.5;
+        maintenance.distance_m = 1200.0;
+        let due = maintenance.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "Lubricate rails");
+        assert_eq!(maintenance.remaining(&maintenance.reminders[0]), -1.5);
+
+        maintenance.mark_done(0);
+        assert!(maintenance.due().is_empty());
+        assert_eq!(maintenance.remaining(&maintenance.reminders[0]), 40.0);
+        assert_eq!(maintenance.remaining(&maintenance.reminders[1]), 3800.0);
+    }
+
+    #[test]
+    fn test_job_end_commands() {
+        let mut profile = MachineProfile::default();
+        assert_eq!(profile.job_end_commands(), ["M5", "M9"]);
+
+        profile.job_end.coolant_off = false;
+        profile.job_end.park = true;
+        profile.job_end.commands = vec!["M8".to_string(), " ".to_string()];
+        profile.job_end.power_off = true;
+        profile.job_end.power_output = 2;
+        profile.user_outputs = 3;
+        assert_eq!(
+            profile.job_end_commands(),
+            ["M5", "G53 G0 Z-1.000", "G53 G0 X-5.000 Y-5.000", "G53 G0 Z-1.000", "M8", "G4 P0", "M65 P2"]
+        );
+    }
+
+    #[test]
+    fn test_tool_measure_commands() {
+        let profile = MachineProfile::default();
+        let commands = profile.tool_measure_commands().unwrap();
+        assert_eq!(commands[0], "G21");
+        assert_eq!(commands[4], "G91 G38.2 Z-50.000 F100");
+        assert_eq!(commands[5], "G0 Z2.000");
+        assert_eq!(commands[6], "G90");
+
+        let mut profile = profile;
+        profile.positions.retain(|p| p.role != PositionRole::ProbePlate);
+        assert!(profile.tool_measure_commands().is_none());
+    }
+}
@@ -5,8 +5,27 @@
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+use crate::renderer::ToolpathColorMode;
+use crate::connection::{
+    BluetoothConfig, BluetoothTarget, ConnectionManagerConfig, LineControl, NetworkDevice, NetworkProtocol, SerialConfig,
+    TelnetConfig, TlsOptions, WebSocketAuth, WebSocketConfig, DEFAULT_RFCOMM_CHANNEL,
+};
+use std::time::Duration;
 use crate::utils::{Error, Result};
 
+mod machine;
+mod project;
+mod sidecar;
+
+pub use machine::{
+    Fixture, FixtureKind, JobEndSettings, MachineProfile, MaintenanceBasis, MaintenanceReminder, MaintenanceSettings, NamedPosition, PositionRole, SpindleJogInterlock, ToolCheckSettings, ToolUsage, ToolWearSettings, WorkZeroSlot,
+    MAX_WORK_ZERO_SLOTS,
+};
+pub use project::{
+    Project, ProjectAttachment, ProjectOffset, ProjectProgram, ProjectTool, PROJECT_EXTENSION,
+};
+pub use sidecar::{JobRecord, SidecarMetadata};
+
 /// Main application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -24,6 +43,14 @@ pub struct Settings {
     
     /// User interface settings
     pub ui: UiSettings,
+    
+    /// G-Code processing settings
+    #[serde(default)]
+    pub processing: ProcessingSettings,
+    
+    /// Machine profile
+    #[serde(default)]
+    pub machine: MachineProfile,
 }
 
 /// General application settings
@@ -43,17 +70,163 @@ pub struct GeneralSettings {
     
     /// Startup commands to send to GRBL
     pub startup_commands: Vec<String>,
+    
+    /// Whether the first-run setup wizard was completed or skipped
+    ///
+    /// New settings start without it; settings files from before the
+    /// wizard existed count as set up.
+    #[serde(default = "default_enabled")]
+    pub onboarding_done: bool,
+    
+    /// Save a local crash report when rCandle panics
+    #[serde(default)]
+    pub crash_reports: bool,
+    
+    /// Plugins that run, by folder name
+    #[serde(default)]
+    pub enabled_plugins: Vec<String>,
+}
+
+/// Type of connection to the controller
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionType {
+    /// Serial port
+    #[default]
+    Serial,
+    /// Bluetooth serial (SPP) module
+    Bluetooth,
+    /// Built-in simulated GRBL device
+    Simulator,
+    /// Telnet or WebSocket controller from a saved network profile
+    Network,
+}
+
+impl std::fmt::Display for ConnectionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionType::Serial => write!(f, "Serial"),
+            ConnectionType::Bluetooth => write!(f, "Bluetooth"),
+            ConnectionType::Simulator => write!(f, "Simulator"),
+            ConnectionType::Network => write!(f, "Network"),
+        }
+    }
+}
+
+/// A saved Telnet or WebSocket controller
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    /// Display name
+    pub name: String,
+    /// Telnet or WebSocket
+    pub protocol: NetworkProtocol,
+    /// Host name or IP address
+    pub host: String,
+    /// Port number
+    pub port: u16,
+    /// WebSocket path, e.g. "/ws"
+    #[serde(default)]
+    pub path: String,
+    /// Use wss:// for WebSocket connections
+    #[serde(default)]
+    pub secure: bool,
+    /// WebSocket authentication
+    ///
+    /// Credentials are stored in the settings file as entered.
+    #[serde(default)]
+    pub auth: WebSocketAuth,
+    /// Certificate validation for wss://
+    #[serde(default)]
+    pub tls: TlsOptions,
+}
+
+impl NetworkProfile {
+    /// Profile for a discovered device
+    pub fn from_device(device: &NetworkDevice) -> Self {
+        Self {
+            name: device.name.clone(),
+            protocol: device.protocol,
+            host: device.host.clone(),
+            port: device.port,
+            ..Default::default()
+        }
+    }
+    
+    /// WebSocket URL of the profile
+    pub fn url(&self) -> String {
+        let path = if self.path.is_empty() || self.path.starts_with('/') {
+            self.path.clone()
+        } else {
+            format!("/{}", self.path)
+        };
+        let scheme = if self.secure { "wss" } else { "ws" };
+        format!("{}://{}:{}{}", scheme, self.host, self.port, path)
+    }
+    
+    /// Label for the connection selector
+    pub fn label(&self) -> String {
+        match self.protocol {
+            NetworkProtocol::Telnet => format!("{} - {}:{}", self.name, self.host, self.port),
+            NetworkProtocol::WebSocket => format!("{} - {}", self.name, self.url()),
+        }
+    }
+    
+    /// Telnet configuration for the profile
+    pub fn telnet_config(&self, connect_timeout_ms: u64) -> TelnetConfig {
+        TelnetConfig {
+            host: self.host.clone(),
+            port: self.port,
+            connect_timeout_ms,
+            ..Default::default()
+        }
+    }
+    
+    /// WebSocket configuration for the profile
+    pub fn websocket_config(&self, connect_timeout_ms: u64) -> WebSocketConfig {
+        WebSocketConfig {
+            url: self.url(),
+            connect_timeout_ms,
+            auth: self.auth.clone(),
+            tls: self.tls.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for NetworkProfile {
+    fn default() -> Self {
+        Self {
+            name: "Controller".to_string(),
+            protocol: NetworkProtocol::Telnet,
+            host: "192.168.1.100".to_string(),
+            port: NetworkProtocol::Telnet.default_port(),
+            path: String::new(),
+            secure: false,
+            auth: WebSocketAuth::None,
+            tls: TlsOptions::default(),
+        }
+    }
 }
 
 /// Connection settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConnectionSettings {
+    /// Connection type
+    #[serde(default)]
+    pub connection_type: ConnectionType,
+    
     /// Serial port name (e.g., "COM3" or "/dev/ttyUSB0")
     pub port_name: String,
     
     /// Baud rate
     pub baud_rate: u32,
     
+    /// Bluetooth device address, or the virtual serial port of a paired device
+    pub bluetooth_device: String,
+    
+    /// Bluetooth RFCOMM channel
+    pub bluetooth_channel: u8,
+    
     /// Connection timeout in milliseconds
     pub timeout_ms: u64,
     
@@ -61,10 +234,44 @@ pub struct ConnectionSettings {
     pub command_timeout_ms: u64,
     
     /// Status query interval in milliseconds
+    ///
+    /// Network links stretch it automatically when round trips are slow.
     pub status_query_interval_ms: u64,
     
+    /// Controller RX buffer used for character counting on network links
+    /// (0 waits for each "ok")
+    pub network_rx_buffer: usize,
+    
+    /// Missed status intervals before streaming is paused and the link is
+    /// recovered (0 disables the watchdog)
+    pub watchdog_missed_intervals: u32,
+    
+    /// DTR behavior on connect
+    pub dtr: LineControl,
+    
+    /// RTS behavior on connect
+    pub rts: LineControl,
+    
+    /// Length of a DTR/RTS reset pulse in milliseconds
+    pub reset_pulse_ms: u64,
+    
+    /// Delay after opening the port before the first command, in milliseconds
+    pub startup_delay_ms: u64,
+    
+    /// Discard data received while the port was opening
+    pub flush_on_connect: bool,
+    
     /// Auto-connect on startup
     pub auto_connect: bool,
+    
+    /// Simulate acceleration and axis rate limits in the simulator
+    pub simulator_physics: bool,
+    
+    /// Saved Telnet and WebSocket controllers
+    pub network_profiles: Vec<NetworkProfile>,
+    
+    /// Name of the network profile to connect to
+    pub network_profile: String,
 }
 
 /// Visualization settings
@@ -76,6 +283,14 @@ pub struct VisualizationSettings {
     /// Grid size
     pub grid_size: f32,
     
+    /// Pick the grid spacing from the zoom instead of the grid size
+    #[serde(default = "default_enabled")]
+    pub adaptive_grid: bool,
+    
+    /// Label grid lines with their coordinates
+    #[serde(default = "default_enabled")]
+    pub grid_labels: bool,
+    
     /// Show tool position
     pub show_tool: bool,
     
@@ -85,6 +300,14 @@ pub struct VisualizationSettings {
     /// Show machine bounds
     pub show_bounds: bool,
     
+    /// Show the job bounding box with its dimensions
+    #[serde(default)]
+    pub show_dimensions: bool,
+    
+    /// Show the fixtures of the machine profile
+    #[serde(default)]
+    pub show_fixtures: bool,
+    
     /// Anti-aliasing sample count (1, 2, 4, 8, or 16)
     pub msaa_samples: u32,
     
@@ -99,10 +322,133 @@ pub struct VisualizationSettings {
     
     /// Color scheme
     pub color_scheme: ColorScheme,
+    
+    /// Rotary (A) axis preview
+    #[serde(default)]
+    pub rotary: RotaryViewSettings,
+    
+    /// How toolpath segments are colored
+    #[serde(default)]
+    pub color_mode: ToolpathColorMode,
+    
+    /// Reduced-GPU rendering for weak hardware such as the Raspberry Pi
+    #[serde(default)]
+    pub low_power: LowPowerMode,
+    
+    /// Draw rapids dashed and arcs dotted so move types differ without color
+    #[serde(default)]
+    pub line_patterns: bool,
+}
+
+/// When to use the reduced-GPU rendering mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LowPowerMode {
+    /// Use it when the GPU looks weak
+    #[default]
+    Auto,
+    /// Always render normally
+    Off,
+    /// Always use it
+    On,
+}
+
+impl std::fmt::Display for LowPowerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LowPowerMode::Auto => "Auto",
+            LowPowerMode::Off => "Off",
+            LowPowerMode::On => "On",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl VisualizationSettings {
+    /// Whether to render in low-power mode on a GPU that is or isn't weak
+    pub fn low_power_active(&self, weak_gpu: bool) -> bool {
+        match self.low_power {
+            LowPowerMode::Auto => weak_gpu,
+            LowPowerMode::Off => false,
+            LowPowerMode::On => true,
+        }
+    }
+}
+
+/// How rotary (A) axis moves are shown in the preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotaryViewMode {
+    /// Ignore A moves
+    Off,
+    /// Roll the cylinder out flat along Y
+    Unwrapped,
+    /// Draw the toolpath around the cylinder
+    Wrapped,
+}
+
+/// Rotary axis preview settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotaryViewSettings {
+    /// Preview mode
+    pub mode: RotaryViewMode,
+    
+    /// Stock diameter at Z0
+    pub diameter: f64,
+}
+
+impl Default for RotaryViewSettings {
+    fn default() -> Self {
+        Self {
+            mode: RotaryViewMode::Off,
+            diameter: 50.0,
+        }
+    }
+}
+
+impl RotaryViewSettings {
+    /// Get the rotary wrap for these settings, if enabled
+    pub fn wrap(&self) -> Option<crate::parser::RotaryWrap> {
+        use crate::parser::{RotaryProjection, RotaryWrap};
+        
+        let projection = match self.mode {
+            RotaryViewMode::Off => return None,
+            RotaryViewMode::Unwrapped => RotaryProjection::Unwrapped,
+            RotaryViewMode::Wrapped => RotaryProjection::Wrapped,
+        };
+        Some(RotaryWrap::new(projection, self.diameter))
+    }
+}
+
+/// Preset color schemes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorPalette {
+    /// Green cuts and red rapids
+    Classic,
+    /// Okabe-Ito colors, distinguishable with red-green and blue-yellow
+    /// color vision deficiency
+    ColorBlindSafe,
+    /// White, yellow and cyan on black
+    HighContrast,
+}
+
+impl ColorPalette {
+    /// All presets, in menu order
+    pub const ALL: [ColorPalette; 3] = [ColorPalette::Classic, ColorPalette::ColorBlindSafe, ColorPalette::HighContrast];
+}
+
+impl std::fmt::Display for ColorPalette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ColorPalette::Classic => "Classic",
+            ColorPalette::ColorBlindSafe => "Color-Blind Safe",
+            ColorPalette::HighContrast => "High Contrast",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 /// Color scheme for visualization
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColorScheme {
     /// Background color [R, G, B, A]
     pub background: [f32; 4],
@@ -116,6 +462,10 @@ pub struct ColorScheme {
     /// Rapid move color [R, G, B, A]
     pub rapid: [f32; 4],
     
+    /// Arc move color [R, G, B, A]
+    #[serde(default = "default_arc_color")]
+    pub arc: [f32; 4],
+    
     /// Tool position color [R, G, B, A]
     pub tool: [f32; 4],
     
@@ -143,6 +493,178 @@ pub struct JogSettings {
     
     /// Enable continuous jog mode
     pub continuous_mode: bool,
+    
+    /// Clamp jogs to the machine travel
+    #[serde(default)]
+    pub limit_to_travel: bool,
+    
+    /// Machine travel (machine coordinates)
+    #[serde(default)]
+    pub travel: JogLimits,
+    
+    /// Clamp jogs to a user-set fence, e.g. around a fixture
+    #[serde(default)]
+    pub fence_enabled: bool,
+    
+    /// Fence (machine coordinates)
+    #[serde(default)]
+    pub fence: JogLimits,
+    
+    /// Jog with the arrow keys (XY) and Page Up/Down (Z)
+    #[serde(default)]
+    pub keyboard_jog: bool,
+    
+    /// Distance kept from the limits when jogging to an edge (mm)
+    #[serde(default = "default_edge_margin")]
+    pub edge_margin: f64,
+}
+
+/// Default for settings that are on unless turned off
+fn default_enabled() -> bool {
+    true
+}
+
+/// Default arc color, the same as the other cutting moves
+fn default_arc_color() -> [f32; 4] {
+    [0.0, 1.0, 0.0, 1.0]
+}
+
+/// Default scale of the whole layout
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+/// Default distance kept from the limits when jogging to an edge (mm)
+fn default_edge_margin() -> f64 {
+    1.0
+}
+
+/// Millimeters per inch
+pub const MM_PER_INCH: f64 = 25.4;
+
+/// Default metric jog steps (mm)
+const METRIC_STEP_SIZES: [f64; 4] = [0.1, 1.0, 10.0, 100.0];
+
+/// Default imperial jog steps (inches)
+const IMPERIAL_STEP_SIZES: [f64; 4] = [0.001, 0.01, 0.1, 1.0];
+
+impl JogSettings {
+    /// Step size last selected in the jog panel
+    pub fn current_step(&self) -> f64 {
+        self.step_sizes.get(self.default_step_index).copied().unwrap_or(1.0)
+    }
+
+    /// Convert steps and feed rates after the display units changed
+    ///
+    /// The default step list is swapped for the default list of the other
+    /// unit system; custom steps are converted and rounded.
+    pub fn convert_units(&mut self, metric: bool) {
+        let (factor, from_defaults, to_defaults) = if metric {
+            (MM_PER_INCH, IMPERIAL_STEP_SIZES, METRIC_STEP_SIZES)
+        } else {
+            (1.0 / MM_PER_INCH, METRIC_STEP_SIZES, IMPERIAL_STEP_SIZES)
+        };
+        self.xy_feed_rate = (self.xy_feed_rate * factor).round();
+        self.z_feed_rate = (self.z_feed_rate * factor).round();
+        if self.step_sizes == from_defaults {
+            self.step_sizes = to_defaults.to_vec();
+        } else {
+            for step in &mut self.step_sizes {
+                *step = (*step * factor * 10_000.0).round() / 10_000.0;
+            }
+        }
+    }
+
+    /// Jog step adjusted for the held modifier keys
+    ///
+    /// Shift multiplies the step by 10 and Ctrl divides it by 10; holding
+    /// both leaves it unchanged.
+    pub fn modified_step(step: f64, shift: bool, ctrl: bool) -> f64 {
+        match (shift, ctrl) {
+            (true, false) => step * 10.0,
+            (false, true) => step / 10.0,
+            _ => step,
+        }
+    }
+
+    /// Combined jog limits, if any are enabled
+    pub fn jog_limits(&self) -> Option<JogLimits> {
+        match (self.limit_to_travel, self.fence_enabled) {
+            (true, true) => Some(self.travel.intersect(&self.fence)),
+            (true, false) => Some(self.travel),
+            (false, true) => Some(self.fence),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Axis-aligned box that jog moves are kept inside
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct JogLimits {
+    /// Minimum X, Y, Z
+    pub min: [f64; 3],
+    /// Maximum X, Y, Z
+    pub max: [f64; 3],
+}
+
+impl Default for JogLimits {
+    /// Typical GRBL machine space: homed at the top right, travel negative
+    fn default() -> Self {
+        JogLimits {
+            min: [-300.0, -300.0, -80.0],
+            max: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl JogLimits {
+    /// Center of the limit box
+    pub fn center(&self) -> [f64; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) / 2.0)
+    }
+
+    /// Overlap of two limit boxes
+    pub fn intersect(&self, other: &JogLimits) -> JogLimits {
+        let mut limits = *self;
+        for axis in 0..3 {
+            limits.min[axis] = self.min[axis].max(other.min[axis]);
+            limits.max[axis] = self.max[axis].min(other.max[axis]);
+        }
+        limits
+    }
+
+    /// Clamp a relative jog from `position` so it ends inside the limits
+    ///
+    /// An axis already outside the limits may still move back towards them,
+    /// but not further out.
+    pub fn clamp_jog(&self, position: [f64; 3], delta: [f64; 3]) -> [f64; 3] {
+        let mut clamped = delta;
+        for axis in 0..3 {
+            let low = self.min[axis].min(position[axis]);
+            let high = self.max[axis].max(position[axis]).max(low);
+            let target = (position[axis] + delta[axis]).clamp(low, high);
+            clamped[axis] = target - position[axis];
+        }
+        clamped
+    }
+
+    /// Relative jog that takes one axis from `position` to an edge
+    ///
+    /// Stops `margin` short of the limit. The result is zero if the axis is
+    /// already at or beyond that point, so the move never heads outwards.
+    pub fn edge_jog(&self, position: [f64; 3], axis: usize, toward_max: bool, margin: f64) -> [f64; 3] {
+        let mut delta = [0.0; 3];
+        let margin = margin.abs().min((self.max[axis] - self.min[axis]).max(0.0) / 2.0);
+        let distance = if toward_max {
+            self.max[axis] - margin - position[axis]
+        } else {
+            self.min[axis] + margin - position[axis]
+        };
+        if (toward_max && distance > 0.0) || (!toward_max && distance < 0.0) {
+            delta[axis] = distance;
+        }
+        delta
+    }
 }
 
 /// UI settings
@@ -157,12 +679,20 @@ pub struct UiSettings {
     /// Window maximized
     pub window_maximized: bool,
     
+    /// Window position on the desktop, when known
+    #[serde(default)]
+    pub window_position: Option<[f32; 2]>,
+    
     /// Dark mode
     pub dark_mode: bool,
     
     /// Font size
     pub font_size: f32,
     
+    /// Scale of the whole layout on top of the display's own scaling
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    
     /// Show console panel
     pub show_console: bool,
     
@@ -174,6 +704,137 @@ pub struct UiSettings {
     
     /// Console history limit
     pub console_history_limit: usize,
+    
+    /// Switch to the run screen when a program starts
+    #[serde(default)]
+    pub auto_run_screen: bool,
+    
+    /// Keep the window above others while the run screen is shown
+    #[serde(default)]
+    pub run_screen_on_top: bool,
+    
+    /// Start in the touch-screen pendant layout
+    #[serde(default)]
+    pub pendant_mode: bool,
+}
+
+/// Entry strategy for straight plunges into material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlungeMode {
+    /// Keep straight plunges
+    Straight,
+    /// Replace plunges with a zig-zag ramp
+    Ramp,
+    /// Replace plunges with a helical entry
+    Helix,
+}
+
+/// Handling of rapids that move in XY below the stock surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RapidRetract {
+    /// No check
+    Off,
+    /// Warn when the program is loaded
+    #[default]
+    Warn,
+    /// Warn, and retract to the safe Z while streaming
+    Fix,
+}
+
+/// Response to an auto-leveled move below the lowest allowed Z
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ZLimitGuard {
+    /// Refuse to start the program
+    Abort,
+    /// Stream up to the move and hold there
+    #[default]
+    Hold,
+}
+
+/// G-Code processing settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingSettings {
+    /// Entry strategy for straight plunges
+    pub plunge_mode: PlungeMode,
+    
+    /// Ramp or helix angle in degrees from horizontal
+    pub plunge_angle: f64,
+    
+    /// Length of each ramp leg
+    pub ramp_length: f64,
+    
+    /// Helix diameter
+    pub helix_diameter: f64,
+    
+    /// Skip block delete ("/") lines when parsing and streaming
+    pub skip_block_delete: bool,
+    
+    /// Stream subprograms and canned cycles unchanged (for grblHAL)
+    /// instead of expanding them into linear moves
+    pub passthrough_cycles: bool,
+    
+    /// Dwell inserted after M3/M4 while streaming (seconds, 0 for none)
+    pub spindle_dwell: f64,
+    
+    /// The controller waits for the spindle to reach speed itself
+    /// (grblHAL spindle at speed), so no dwell is inserted
+    pub spindle_at_speed: bool,
+    
+    /// Unsupported words (e.g. "M104", "G64") stripped while streaming
+    pub strip_words: Vec<String>,
+    
+    /// Check for rapids through material, retracting to the safe Z if set to fix
+    pub rapid_retract: RapidRetract,
+    
+    /// Longest piece of a feed move when auto-leveling (mm)
+    pub leveling_segment: f64,
+    
+    /// Lowest Z an auto-leveled move may go to (work coordinates, mm)
+    pub leveling_lowest_z: f64,
+    
+    /// What to do when auto-leveling would go below the lowest Z
+    pub z_limit_guard: ZLimitGuard,
+    
+    /// Height above the stock surface a dry run stays at or above (mm)
+    pub dry_run_clearance: f64,
+    
+    /// Feed rate multiplier for dry runs
+    pub dry_run_feed_scale: f64,
+    
+    /// Leave the spindle off during dry runs
+    pub dry_run_spindle_off: bool,
+    
+    /// Comment markers (e.g. "PAUSE") that pause the program and show the comment
+    pub pause_markers: Vec<String>,
+}
+
+impl ProcessingSettings {
+    /// Dwell to insert after spindle starts, in milliseconds
+    pub fn spindle_dwell_ms(&self) -> u32 {
+        if self.spindle_at_speed {
+            0
+        } else {
+            (self.spindle_dwell.max(0.0) * 1000.0).round() as u32
+        }
+    }
+    
+    /// Get the preprocessor plunge entry for these settings
+    pub fn plunge_entry(&self) -> crate::parser::PlungeEntry {
+        use crate::parser::PlungeEntry;
+        
+        match self.plunge_mode {
+            PlungeMode::Straight => PlungeEntry::Straight,
+            PlungeMode::Ramp => PlungeEntry::Ramp {
+                angle: self.plunge_angle,
+                length: self.ramp_length,
+            },
+            PlungeMode::Helix => PlungeEntry::Helix {
+                angle: self.plunge_angle,
+                diameter: self.helix_diameter,
+            },
+        }
+    }
 }
 
 impl Default for Settings {
@@ -184,6 +845,8 @@ impl Default for Settings {
             visualization: VisualizationSettings::default(),
             jog: JogSettings::default(),
             ui: UiSettings::default(),
+            processing: ProcessingSettings::default(),
+            machine: MachineProfile::default(),
         }
     }
 }
@@ -196,6 +859,9 @@ impl Default for GeneralSettings {
             arc_segments: 20,
             safe_z: 5.0,
             startup_commands: vec![],
+            onboarding_done: false,
+            crash_reports: false,
+            enabled_plugins: Vec::new(),
         }
     }
 }
@@ -203,14 +869,73 @@ impl Default for GeneralSettings {
 impl Default for ConnectionSettings {
     fn default() -> Self {
         ConnectionSettings {
+            connection_type: ConnectionType::Serial,
             port_name: String::new(),
             baud_rate: 115200,
+            bluetooth_device: String::new(),
+            bluetooth_channel: DEFAULT_RFCOMM_CHANNEL,
             timeout_ms: 5000,
             command_timeout_ms: 10000,
             status_query_interval_ms: 250,
+            network_rx_buffer: 127,
+            watchdog_missed_intervals: 4,
+            dtr: LineControl::Unchanged,
+            rts: LineControl::Unchanged,
+            reset_pulse_ms: 100,
+            startup_delay_ms: 100,
+            flush_on_connect: false,
             auto_connect: false,
+            simulator_physics: true,
+            network_profiles: Vec::new(),
+            network_profile: String::new(),
+        }
+    }
+}
+
+impl ConnectionSettings {
+    /// Build the serial port configuration for these settings
+    pub fn serial_config(&self) -> SerialConfig {
+        SerialConfig {
+            port: self.port_name.clone(),
+            baud_rate: self.baud_rate,
+            dtr: self.dtr,
+            rts: self.rts,
+            reset_pulse: Duration::from_millis(self.reset_pulse_ms),
+            startup_delay: Duration::from_millis(self.startup_delay_ms),
+            flush_on_connect: self.flush_on_connect,
+            ..Default::default()
+        }
+    }
+    
+    /// Time allowed for opening the connection
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+    
+    /// Build the connection manager configuration for these settings
+    pub fn manager_config(&self) -> ConnectionManagerConfig {
+        ConnectionManagerConfig {
+            status_interval_ms: self.status_query_interval_ms,
+            response_timeout: self.connect_timeout(),
+            command_timeout: Duration::from_millis(self.command_timeout_ms),
+            watchdog_missed_intervals: self.watchdog_missed_intervals,
+            network_rx_buffer: self.network_rx_buffer,
+            ..Default::default()
         }
     }
+    
+    /// Selected network profile
+    pub fn selected_network_profile(&self) -> Option<&NetworkProfile> {
+        self.network_profiles.iter().find(|p| p.name == self.network_profile)
+    }
+    
+    /// Build the Bluetooth configuration for these settings
+    pub fn bluetooth_config(&self) -> Result<BluetoothConfig> {
+        Ok(BluetoothConfig {
+            target: BluetoothTarget::parse(&self.bluetooth_device, self.bluetooth_channel)?,
+            baud_rate: self.baud_rate,
+        })
+    }
 }
 
 impl Default for VisualizationSettings {
@@ -218,30 +943,74 @@ impl Default for VisualizationSettings {
         VisualizationSettings {
             show_grid: true,
             grid_size: 10.0,
+            adaptive_grid: true,
+            grid_labels: true,
             show_tool: true,
             show_origin: true,
             show_bounds: true,
+            show_dimensions: false,
+            show_fixtures: false,
             msaa_samples: 4,
             vsync: true,
             fov: 60.0,
             camera_speed: 1.0,
             color_scheme: ColorScheme::default(),
+            rotary: RotaryViewSettings::default(),
+            color_mode: ToolpathColorMode::MoveType,
+            low_power: LowPowerMode::Auto,
+            line_patterns: false,
         }
     }
 }
 
 impl Default for ColorScheme {
     fn default() -> Self {
-        ColorScheme {
-            background: [0.1, 0.1, 0.1, 1.0],
-            grid: [0.3, 0.3, 0.3, 1.0],
-            toolpath: [0.0, 1.0, 0.0, 1.0],
-            rapid: [1.0, 0.0, 0.0, 1.0],
-            tool: [1.0, 1.0, 0.0, 1.0],
-            origin: [1.0, 1.0, 1.0, 1.0],
-            bounds: [0.5, 0.5, 0.5, 1.0],
+        ColorScheme::from_palette(ColorPalette::Classic)
+    }
+}
+
+impl ColorScheme {
+    /// Colors of a preset
+    pub fn from_palette(palette: ColorPalette) -> Self {
+        let rgb = |r: u8, g: u8, b: u8| [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0];
+        match palette {
+            ColorPalette::Classic => ColorScheme {
+                background: [0.1, 0.1, 0.1, 1.0],
+                grid: [0.3, 0.3, 0.3, 1.0],
+                toolpath: [0.0, 1.0, 0.0, 1.0],
+                rapid: [1.0, 0.0, 0.0, 1.0],
+                arc: default_arc_color(),
+                tool: [1.0, 1.0, 0.0, 1.0],
+                origin: [1.0, 1.0, 1.0, 1.0],
+                bounds: [0.5, 0.5, 0.5, 1.0],
+            },
+            ColorPalette::ColorBlindSafe => ColorScheme {
+                background: [0.1, 0.1, 0.1, 1.0],
+                grid: [0.3, 0.3, 0.3, 1.0],
+                toolpath: rgb(86, 180, 233),
+                rapid: rgb(230, 159, 0),
+                arc: rgb(204, 121, 167),
+                tool: rgb(240, 228, 66),
+                origin: [1.0, 1.0, 1.0, 1.0],
+                bounds: [0.5, 0.5, 0.5, 1.0],
+            },
+            ColorPalette::HighContrast => ColorScheme {
+                background: [0.0, 0.0, 0.0, 1.0],
+                grid: [0.4, 0.4, 0.4, 1.0],
+                toolpath: [1.0, 1.0, 1.0, 1.0],
+                rapid: [1.0, 0.85, 0.0, 1.0],
+                arc: [0.0, 0.9, 1.0, 1.0],
+                tool: [1.0, 0.2, 0.8, 1.0],
+                origin: [1.0, 1.0, 1.0, 1.0],
+                bounds: [0.6, 0.6, 0.6, 1.0],
+            },
         }
     }
+    
+    /// The preset these colors match, if any
+    pub fn palette(&self) -> Option<ColorPalette> {
+        ColorPalette::ALL.into_iter().find(|&palette| ColorScheme::from_palette(palette) == *self)
+    }
 }
 
 impl Default for JogSettings {
@@ -249,9 +1018,15 @@ impl Default for JogSettings {
         JogSettings {
             xy_feed_rate: 1000.0,
             z_feed_rate: 500.0,
-            step_sizes: vec![0.1, 1.0, 10.0, 100.0],
+            step_sizes: METRIC_STEP_SIZES.to_vec(),
             default_step_index: 1,
             continuous_mode: false,
+            limit_to_travel: false,
+            travel: JogLimits::default(),
+            fence_enabled: false,
+            fence: JogLimits::default(),
+            keyboard_jog: false,
+            edge_margin: default_edge_margin(),
         }
     }
 }
@@ -262,12 +1037,85 @@ impl Default for UiSettings {
             window_width: 1280,
             window_height: 720,
             window_maximized: false,
+            window_position: None,
             dark_mode: true,
             font_size: 14.0,
+            ui_scale: default_ui_scale(),
             show_console: true,
             show_state: true,
             show_control: true,
             console_history_limit: 1000,
+            auto_run_screen: false,
+            run_screen_on_top: false,
+            pendant_mode: false,
+        }
+    }
+}
+
+impl UiSettings {
+    /// Range of the layout scale
+    pub const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+    /// Record the current window geometry, returning true if it changed
+    ///
+    /// Size and position are only taken from the normal (not maximized)
+    /// window so that un-maximizing after a restart returns to them.
+    pub fn record_window(&mut self, position: Option<[f32; 2]>, size: [f32; 2], maximized: bool) -> bool {
+        let mut changed = self.window_maximized != maximized;
+        self.window_maximized = maximized;
+        if !maximized {
+            let (width, height) = (size[0].round() as u32, size[1].round() as u32);
+            if width > 0 && height > 0 && (width, height) != (self.window_width, self.window_height) {
+                self.window_width = width;
+                self.window_height = height;
+                changed = true;
+            }
+            if let Some(position) = position.map(|p| [p[0].round(), p[1].round()]) {
+                if self.window_position != Some(position) {
+                    self.window_position = Some(position);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Saved window position, if it is plausibly on screen
+    ///
+    /// Positions far off the desktop are dropped so the window manager
+    /// places the window instead.
+    pub fn restored_position(&self) -> Option<[f32; 2]> {
+        let [x, y] = self.window_position?;
+        let on_desktop = x.is_finite()
+            && y.is_finite()
+            && x > -(self.window_width as f32) / 2.0
+            && y > -10.0
+            && x < 16384.0
+            && y < 16384.0;
+        on_desktop.then_some([x, y])
+    }
+}
+
+impl Default for ProcessingSettings {
+    fn default() -> Self {
+        ProcessingSettings {
+            plunge_mode: PlungeMode::Straight,
+            plunge_angle: 3.0,
+            ramp_length: 10.0,
+            helix_diameter: 3.0,
+            skip_block_delete: true,
+            passthrough_cycles: false,
+            spindle_dwell: 0.0,
+            spindle_at_speed: false,
+            strip_words: Vec::new(),
+            rapid_retract: RapidRetract::default(),
+            leveling_segment: 5.0,
+            leveling_lowest_z: -5.0,
+            z_limit_guard: ZLimitGuard::default(),
+            dry_run_clearance: 5.0,
+            dry_run_feed_scale: 1.0,
+            dry_run_spindle_off: true,
+            pause_markers: vec!["PAUSE".to_string(), "CHECK".to_string()],
         }
     }
 }
@@ -321,6 +1169,21 @@ impl Settings {
         let path = Self::default_config_path()?;
         self.save(path)
     }
+
+    /// Commands to send after connecting
+    ///
+    /// The general startup commands come first, followed by those of the
+    /// machine profile. Blank lines are skipped.
+    pub fn startup_commands(&self) -> Vec<String> {
+        self.general
+            .startup_commands
+            .iter()
+            .chain(&self.machine.startup_commands)
+            .map(|command| command.trim())
+            .filter(|command| !command.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -334,6 +1197,98 @@ mod tests {
         assert_eq!(settings.connection.baud_rate, 115200);
     }
 
+    #[test]
+    fn test_startup_commands_include_machine_profile() {
+        let mut settings = Settings::default();
+        settings.general.startup_commands = vec!["$X".to_string(), "  ".to_string()];
+        settings.machine.startup_commands = vec![" G21 G90 ".to_string()];
+
+        assert_eq!(settings.startup_commands(), vec!["$X".to_string(), "G21 G90".to_string()]);
+    }
+
+    #[test]
+    fn test_connection_configs_follow_settings() {
+        let settings = ConnectionSettings {
+            port_name: "/dev/ttyUSB0".to_string(),
+            baud_rate: 250000,
+            timeout_ms: 2000,
+            command_timeout_ms: 15000,
+            status_query_interval_ms: 100,
+            ..Default::default()
+        };
+
+        let serial = settings.serial_config();
+        assert_eq!(serial.baud_rate, 250000);
+        assert_eq!(serial.port, "/dev/ttyUSB0");
+
+        let manager = settings.manager_config();
+        assert_eq!(manager.status_interval_ms, 100);
+        assert_eq!(manager.network_rx_buffer, 127);
+        assert_eq!(manager.response_timeout, Duration::from_secs(2));
+        assert_eq!(manager.command_timeout, Duration::from_secs(15));
+        assert_eq!(settings.connect_timeout(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_network_profiles() {
+        let mut profile = NetworkProfile {
+            name: "Router".to_string(),
+            protocol: NetworkProtocol::WebSocket,
+            host: "fluidnc.local".to_string(),
+            port: 81,
+            path: "ws".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(profile.url(), "ws://fluidnc.local:81/ws");
+        profile.secure = true;
+        profile.auth = WebSocketAuth::Bearer { token: "abc".to_string() };
+        let config = profile.websocket_config(3000);
+        assert_eq!(config.url, "wss://fluidnc.local:81/ws");
+        assert_eq!(config.auth, profile.auth);
+        profile.protocol = NetworkProtocol::Telnet;
+        profile.port = 23;
+        assert_eq!(profile.label(), "Router - fluidnc.local:23");
+        assert_eq!(profile.telnet_config(3000).port, 23);
+
+        let mut settings = ConnectionSettings {
+            network_profiles: vec![profile],
+            ..Default::default()
+        };
+        assert!(settings.selected_network_profile().is_none());
+        settings.network_profile = "Router".to_string();
+        assert_eq!(settings.selected_network_profile().unwrap().host, "fluidnc.local");
+    }
+
+    #[test]
+    fn test_color_palettes() {
+        assert_eq!(ColorScheme::default().palette(), Some(ColorPalette::Classic));
+        let safe = ColorScheme::from_palette(ColorPalette::ColorBlindSafe);
+        assert_eq!(safe.palette(), Some(ColorPalette::ColorBlindSafe));
+        assert!(safe.toolpath != safe.rapid && safe.arc != safe.toolpath && safe.arc != safe.rapid);
+
+        // Settings saved before arcs had their own color
+        let mut toml_str = toml::to_string(&ColorScheme::default()).unwrap();
+        toml_str = toml_str.lines().filter(|line| !line.starts_with("arc")).collect::<Vec<_>>().join("\n");
+        let scheme: ColorScheme = toml::from_str(&toml_str).unwrap();
+        assert_eq!(scheme, ColorScheme::default());
+    }
+
+    #[test]
+    fn test_window_geometry() {
+        let mut ui = UiSettings::default();
+        assert!(ui.record_window(Some([100.0, 50.0]), [1024.0, 768.0], false));
+        assert!(!ui.record_window(Some([100.0, 50.0]), [1024.0, 768.0], false));
+        assert_eq!((ui.window_width, ui.window_height), (1024, 768));
+
+        // Maximizing keeps the normal geometry for later
+        assert!(ui.record_window(Some([0.0, 0.0]), [1920.0, 1080.0], true));
+        assert_eq!(ui.window_width, 1024);
+        assert_eq!(ui.restored_position(), Some([100.0, 50.0]));
+
+        ui.window_position = Some([-5000.0, 20.0]);
+        assert_eq!(ui.restored_position(), None);
+    }
+
     #[test]
     fn test_settings_serialization() {
         let settings = Settings::default();
@@ -343,4 +1298,96 @@ mod tests {
         assert_eq!(settings.general.units_metric, deserialized.general.units_metric);
         assert_eq!(settings.connection.baud_rate, deserialized.connection.baud_rate);
     }
+
+    #[test]
+    fn test_jog_limits() {
+        let limits = JogLimits {
+            min: [-100.0, -100.0, -50.0],
+            max: [0.0, 0.0, 0.0],
+        };
+
+        // Inside the limits, unchanged
+        assert_eq!(limits.clamp_jog([-50.0, -50.0, -10.0], [10.0, -10.0, 5.0]), [10.0, -10.0, 5.0]);
+        // Clamped at the edge
+        assert_eq!(limits.clamp_jog([-5.0, -95.0, -10.0], [10.0, -10.0, 20.0]), [5.0, -5.0, 10.0]);
+        // Outside the limits: may move back in but not further out
+        assert_eq!(limits.clamp_jog([5.0, 0.0, 0.0], [-10.0, 0.0, 0.0]), [-10.0, 0.0, 0.0]);
+        assert_eq!(limits.clamp_jog([5.0, 0.0, 0.0], [10.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+
+        let mut jog = JogSettings::default();
+        assert_eq!(jog.jog_limits(), None);
+        jog.limit_to_travel = true;
+        jog.travel = limits;
+        jog.fence_enabled = true;
+        jog.fence = JogLimits {
+            min: [-80.0, -200.0, -20.0],
+            max: [10.0, -10.0, 0.0],
+        };
+        let combined = jog.jog_limits().unwrap();
+        assert_eq!(combined.min, [-80.0, -100.0, -20.0]);
+        assert_eq!(combined.max, [0.0, -10.0, 0.0]);
+        assert_eq!(limits.center(), [-50.0, -50.0, -25.0]);
+    }
+
+    #[test]
+    fn test_edge_jog() {
+        let limits = JogLimits {
+            min: [-100.0, -100.0, -50.0],
+            max: [0.0, 0.0, 0.0],
+        };
+        let position = [-40.0, -60.0, -10.0];
+
+        assert_eq!(limits.edge_jog(position, 0, true, 1.0), [39.0, 0.0, 0.0]);
+        assert_eq!(limits.edge_jog(position, 1, false, 1.0), [0.0, -39.0, 0.0]);
+        assert_eq!(limits.edge_jog(position, 2, true, 0.0), [0.0, 0.0, 10.0]);
+        // Already past the margin: no move outwards
+        assert_eq!(limits.edge_jog([-0.5, 0.0, 0.0], 0, true, 1.0), [0.0; 3]);
+        assert_eq!(limits.edge_jog([-101.0, 0.0, 0.0], 0, false, 1.0), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_jog_unit_conversion() {
+        let mut jog = JogSettings::default();
+        assert_eq!(jog.current_step(), 1.0);
+
+        jog.convert_units(false);
+        assert_eq!(jog.step_sizes, IMPERIAL_STEP_SIZES.to_vec());
+        assert_eq!(jog.xy_feed_rate, 39.0);
+        assert_eq!(jog.current_step(), 0.01);
+
+        jog.step_sizes = vec![0.5];
+        jog.convert_units(true);
+        assert_eq!(jog.step_sizes, vec![12.7]);
+        assert_eq!(jog.xy_feed_rate, 991.0);
+    }
+
+    #[test]
+    fn test_modified_jog_step() {
+        assert_eq!(JogSettings::modified_step(1.0, false, false), 1.0);
+        assert_eq!(JogSettings::modified_step(1.0, true, false), 10.0);
+        assert_eq!(JogSettings::modified_step(1.0, false, true), 0.1);
+        assert_eq!(JogSettings::modified_step(1.0, true, true), 1.0);
+    }
+
+    #[test]
+    fn test_low_power_mode() {
+        let mut visualization = VisualizationSettings::default();
+        assert!(!visualization.low_power_active(false));
+        assert!(visualization.low_power_active(true));
+
+        visualization.low_power = LowPowerMode::Off;
+        assert!(!visualization.low_power_active(true));
+        visualization.low_power = LowPowerMode::On;
+        assert!(visualization.low_power_active(false));
+    }
+
+    #[test]
+    fn test_missing_processing_section_uses_defaults() {
+        let mut value = toml::Value::try_from(Settings::default()).unwrap();
+        value.as_table_mut().unwrap().remove("processing");
+        let toml_str = toml::to_string(&value).unwrap();
+
+        let settings: Settings = toml::from_str(&toml_str).expect("Failed to deserialize");
+        assert_eq!(settings.processing.plunge_mode, PlungeMode::Straight);
+    }
 }
@@ -0,0 +1,215 @@
+//! Project files
+//!
+//! A project (`.rcandle`) bundles everything needed to run a job again later:
+//! the G-Code programs, an optional heightmap, work coordinate offsets, the
+//! tool list, job-related settings and free-form notes. Everything is stored
+//! inline in a single TOML file so the project can be archived on its own.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{GeneralSettings, ProcessingSettings};
+use crate::state::{CoordinateSystem, Position};
+use crate::utils::{Error, Result};
+
+/// File extension for project files
+pub const PROJECT_EXTENSION: &str = "rcandle";
+
+/// Coordinate systems in work offset order
+const COORDINATE_SYSTEMS: [CoordinateSystem; 6] = [
+    CoordinateSystem::G54,
+    CoordinateSystem::G55,
+    CoordinateSystem::G56,
+    CoordinateSystem::G57,
+    CoordinateSystem::G58,
+    CoordinateSystem::G59,
+];
+
+/// A G-Code program stored in a project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectProgram {
+    /// File name the program was added from
+    pub name: String,
+    /// Program text
+    pub gcode: String,
+}
+
+/// A file attached to a project, such as a heightmap
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectAttachment {
+    /// Original file name
+    pub name: String,
+    /// File contents
+    pub contents: String,
+}
+
+/// A tool used by the job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectTool {
+    /// Tool number
+    pub number: u32,
+    /// Diameter (mm)
+    pub diameter: f64,
+    /// Number of flutes
+    pub flutes: u32,
+    /// Free-form description (e.g. "6mm 2F upcut")
+    pub description: String,
+}
+
+/// Work offset for one coordinate system
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ProjectOffset {
+    /// Coordinate system
+    pub system: CoordinateSystem,
+    /// Offset from machine zero
+    pub offset: Position,
+}
+
+/// A complete job setup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Project {
+    /// Project name
+    pub name: String,
+    /// Notes about the job (stock, fixturing, ...)
+    pub notes: String,
+    /// G-Code programs, in run order
+    pub programs: Vec<ProjectProgram>,
+    /// Heightmap for surface compensation
+    pub heightmap: Option<ProjectAttachment>,
+    /// Work coordinate offsets
+    pub work_offsets: Vec<ProjectOffset>,
+    /// Tools used by the job
+    pub tools: Vec<ProjectTool>,
+    /// General settings at the time the project was saved
+    pub general: Option<GeneralSettings>,
+    /// Processing settings at the time the project was saved
+    pub processing: Option<ProcessingSettings>,
+}
+
+impl Project {
+    /// Create an empty project
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Load a project file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| Error::config(format!("Failed to parse project: {}", e)))
+    }
+
+    /// Save a project file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| Error::config(format!("Failed to serialize project: {}", e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Add or replace a program by name
+    pub fn set_program(&mut self, name: impl Into<String>, gcode: impl Into<String>) {
+        let name = name.into();
+        let gcode = gcode.into();
+        match self.programs.iter_mut().find(|p| p.name == name) {
+            Some(program) => program.gcode = gcode,
+            None => self.programs.push(ProjectProgram { name, gcode }),
+        }
+    }
+
+    /// Attach a heightmap file
+    pub fn attach_heightmap<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.heightmap = Some(ProjectAttachment {
+            name: file_name(path),
+            contents: std::fs::read_to_string(path)?,
+        });
+        Ok(())
+    }
+
+    /// Record the work offsets (G54 to G59)
+    pub fn set_work_offsets(&mut self, offsets: &[Position; 6]) {
+        self.work_offsets = COORDINATE_SYSTEMS
+            .iter()
+            .zip(offsets)
+            .map(|(&system, &offset)| ProjectOffset { system, offset })
+            .collect();
+    }
+
+    /// G10 commands that restore the work offsets on the controller
+    pub fn work_offset_commands(&self) -> Vec<String> {
+        self.work_offsets
+            .iter()
+            .map(|o| {
+                let index = COORDINATE_SYSTEMS.iter().position(|&s| s == o.system).unwrap_or(0) + 1;
+                format!(
+                    "G10 L2 P{} X{:.3} Y{:.3} Z{:.3}",
+                    index, o.offset.x, o.offset.y, o.offset.z
+                )
+            })
+            .collect()
+    }
+}
+
+/// File name of a path, or the whole path if it has none
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_round_trip() {
+        let dir = std::env::temp_dir().join(format!("rcandle_project_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("job.rcandle");
+
+        let mut project = Project::new("Sign");
+        project.notes = "18mm birch ply, clamped".to_string();
+        project.set_program("profile.nc", "G0 Z5\nG1 Z-1 F100\n");
+        project.set_program("profile.nc", "G0 Z10\n");
+        project.tools.push(ProjectTool {
+            number: 1,
+            diameter: 6.0,
+            flutes: 2,
+            description: "6mm upcut".to_string(),
+        });
+        project.processing = Some(ProcessingSettings::default());
+        project.save(&path).unwrap();
+
+        let loaded = Project::load(&path).unwrap();
+        assert_eq!(loaded.name, "Sign");
+        assert_eq!(loaded.notes, project.notes);
+        assert_eq!(loaded.programs.len(), 1);
+        assert_eq!(loaded.programs[0].gcode, "G0 Z10\n");
+        assert_eq!(loaded.tools, project.tools);
+        assert!(loaded.heightmap.is_none());
+        assert!(loaded.processing.is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_work_offset_commands() {
+        let mut offsets = [Position::default(); 6];
+        offsets[1] = Position::new(10.0, -20.5, 0.25);
+
+        let mut project = Project::new("Offsets");
+        project.set_work_offsets(&offsets);
+
+        let commands = project.work_offset_commands();
+        assert_eq!(commands.len(), 6);
+        assert_eq!(commands[0], "G10 L2 P1 X0.000 Y0.000 Z0.000");
+        assert_eq!(commands[1], "G10 L2 P2 X10.000 Y-20.500 Z0.250");
+    }
+}
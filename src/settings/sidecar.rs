@@ -0,0 +1,127 @@
+//! Sidecar metadata for G-Code files
+//!
+//! Per-file data such as editor bookmarks, job cost estimates and whether to
+//! skip the pre-run checklist is stored next to the program in a
+//! small TOML file named after it (e.g. `part.nc.rcandle.toml`), so the
+//! G-Code itself is never modified.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{Error, Result};
+
+/// Extension appended to the program file name for its sidecar
+const SIDECAR_SUFFIX: &str = ".rcandle.toml";
+
+/// Metadata stored alongside a G-Code file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidecarMetadata {
+    /// Bookmarked lines (0-based line indices)
+    pub bookmarks: BTreeSet<usize>,
+    /// Last feeds, speeds and cost estimate for the job
+    pub job: Option<JobRecord>,
+    /// Start the program without asking for the pre-run checklist
+    pub skip_checklist: bool,
+}
+
+/// Feeds, speeds and cost estimate saved for a job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobRecord {
+    /// Stock material
+    pub material: String,
+    /// Tool diameter (mm)
+    pub tool_diameter: f64,
+    /// Number of flutes
+    pub flutes: u32,
+    /// Spindle speed (RPM)
+    pub spindle_speed: f64,
+    /// Feed rate (mm/min)
+    pub feed_rate: f64,
+    /// Chip load per tooth (mm)
+    pub chip_load: f64,
+    /// Estimated machine time (seconds)
+    pub machine_time: f64,
+    /// Machine cost per hour
+    pub hourly_rate: f64,
+    /// Stock material cost
+    pub material_cost: f64,
+    /// Total job cost
+    pub total_cost: f64,
+}
+
+impl SidecarMetadata {
+    /// Get the sidecar path for a G-Code file
+    pub fn path_for<P: AsRef<Path>>(gcode_path: P) -> PathBuf {
+        let mut name = gcode_path.as_ref().as_os_str().to_os_string();
+        name.push(SIDECAR_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Load metadata from a sidecar file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::config(format!("Failed to parse sidecar metadata: {}", e)))
+    }
+
+    /// Save metadata to a sidecar file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| Error::config(format!("Failed to serialize sidecar metadata: {}", e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Load the metadata for a G-Code file, or defaults if it has none
+    pub fn load_for<P: AsRef<Path>>(gcode_path: P) -> Self {
+        let path = Self::path_for(gcode_path);
+        if path.exists() {
+            Self::load(&path).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save the metadata for a G-Code file
+    pub fn save_for<P: AsRef<Path>>(&self, gcode_path: P) -> Result<()> {
+        self.save(Self::path_for(gcode_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_path() {
+        let path = SidecarMetadata::path_for("/tmp/part.nc");
+        assert_eq!(path, PathBuf::from("/tmp/part.nc.rcandle.toml"));
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = std::env::temp_dir().join(format!("rcandle_sidecar_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let gcode = dir.join("part.nc");
+
+        let mut metadata = SidecarMetadata::default();
+        metadata.bookmarks.insert(3);
+        metadata.bookmarks.insert(10);
+        metadata.job = Some(JobRecord {
+            material: "MDF".to_string(),
+            flutes: 2,
+            total_cost: 42.5,
+            ..Default::default()
+        });
+        metadata.skip_checklist = true;
+        metadata.save_for(&gcode).unwrap();
+
+        let loaded = SidecarMetadata::load_for(&gcode);
+        assert_eq!(loaded, metadata);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+//! Action log
+//!
+//! Records the machine-affecting commands issued from the UI (jogs, zeroing,
+//! coordinate system changes) so they can be reviewed, and undone where an
+//! inverse command is known.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use super::{CoordinateSystem, Position};
+use crate::grbl::GrblCommand;
+
+/// Default number of actions kept
+const DEFAULT_CAPACITY: usize = 200;
+
+/// Kind of UI action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    /// Jog move
+    Jog,
+    /// Work coordinate zeroing
+    Zero,
+    /// Work coordinate system change
+    CoordinateSystem,
+    /// Homing cycle
+    Home,
+}
+
+impl std::fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionKind::Jog => write!(f, "Jog"),
+            ActionKind::Zero => write!(f, "Zero"),
+            ActionKind::CoordinateSystem => write!(f, "WCS"),
+            ActionKind::Home => write!(f, "Home"),
+        }
+    }
+}
+
+/// A command issued from the UI
+#[derive(Debug, Clone)]
+pub struct LoggedAction {
+    /// Unique id within the log
+    pub id: u64,
+    /// When the action was issued
+    pub timestamp: DateTime<Local>,
+    /// Kind of action
+    pub kind: ActionKind,
+    /// Human-readable description
+    pub description: String,
+    /// Command carrying out the action, sent again on redo
+    pub command: GrblCommand,
+    /// Command that reverses the action, if known
+    pub undo: Option<GrblCommand>,
+    /// Whether the action is currently undone
+    pub undone: bool,
+}
+
+impl LoggedAction {
+    /// Whether the action can be undone now
+    pub fn can_undo(&self) -> bool {
+        self.undo.is_some() && !self.undone
+    }
+
+    /// Whether the action can be redone now
+    pub fn can_redo(&self) -> bool {
+        self.undone
+    }
+}
+
+/// Bounded log of UI actions, oldest first
+#[derive(Debug, Clone)]
+pub struct ActionLog {
+    /// Logged actions
+    entries: VecDeque<LoggedAction>,
+    /// Maximum number of actions kept
+    capacity: usize,
+    /// Id for the next action
+    next_id: u64,
+}
+
+impl Default for ActionLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ActionLog {
+    /// Create a log keeping up to `capacity` actions
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_id: 1,
+        }
+    }
+
+    /// Record an action, returning its id
+    pub fn record(
+        &mut self,
+        kind: ActionKind,
+        description: impl Into<String>,
+        command: GrblCommand,
+        undo: Option<GrblCommand>,
+    ) -> u64 {
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back(LoggedAction {
+            id,
+            timestamp: Local::now(),
+            kind,
+            description: description.into(),
+            command,
+            undo,
+            undone: false,
+        });
+        id
+    }
+
+    /// Record zeroing `axes` (e.g. "XYZ") of a work coordinate system, returning its id
+    ///
+    /// `previous` is the offset being replaced and `machine` the machine
+    /// position being zeroed, in millimeters. Both directions set the offset
+    /// outright in millimeters (G21 G10 L2), so redo zeroes the same spot
+    /// after a jog and neither depends on the controller's units.
+    pub fn record_zero(
+        &mut self,
+        description: impl Into<String>,
+        system: CoordinateSystem,
+        axes: &str,
+        previous: Position,
+        machine: Position,
+    ) -> u64 {
+        self.record(
+            ActionKind::Zero,
+            description,
+            offset_command(system, axes, machine),
+            Some(offset_command(system, axes, previous)),
+        )
+    }
+
+    /// Logged actions, oldest first
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LoggedAction> {
+        self.entries.iter()
+    }
+
+    /// Number of logged actions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all actions
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Most recent action that can be undone
+    pub fn last_undoable(&self) -> Option<u64> {
+        self.entries.iter().rev().find(|a| a.can_undo()).map(|a| a.id)
+    }
+
+    /// Mark an action undone, returning the command that reverses it
+    pub fn undo(&mut self, id: u64) -> Option<GrblCommand> {
+        let action = self.entries.iter_mut().find(|a| a.id == id && a.can_undo())?;
+        action.undone = true;
+        action.undo.clone()
+    }
+
+    /// Mark an undone action redone, returning the original command
+    pub fn redo(&mut self, id: u64) -> Option<GrblCommand> {
+        let action = self.entries.iter_mut().find(|a| a.id == id && a.can_redo())?;
+        action.undone = false;
+        Some(action.command.clone())
+    }
+}
+
+/// Command setting `axes` of a work coordinate system's offset, in millimeters
+///
+/// Used to undo offset changes, so it carries G21 rather than relying on
+/// the controller's units.
+pub fn offset_command(system: CoordinateSystem, axes: &str, offset: Position) -> GrblCommand {
+    let words: Vec<String> = [('X', offset.x), ('Y', offset.y), ('Z', offset.z)]
+        .into_iter()
+        .filter(|(axis, _)| axes.contains(*axis))
+        .map(|(axis, value)| format!("{}{:.3}", axis, value))
+        .collect();
+    GrblCommand::GCode(format!("G21 G10 L2 P{} {}", system.p_number(), words.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gcode(text: &str) -> GrblCommand {
+        GrblCommand::GCode(text.to_string())
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut log = ActionLog::default();
+        let zero = log.record(
+            ActionKind::Zero,
+            "Zero X",
+            gcode("G10 L20 P1 X0"),
+            Some(gcode("G10 L2 P1 X12.500")),
+        );
+        log.record(ActionKind::Home, "Home", GrblCommand::HomingCycle, None);
+
+        assert_eq!(log.last_undoable(), Some(zero));
+        assert_eq!(log.undo(zero), Some(gcode("G10 L2 P1 X12.500")));
+        assert_eq!(log.undo(zero), None);
+        assert_eq!(log.last_undoable(), None);
+
+        assert_eq!(log.redo(zero), Some(gcode("G10 L20 P1 X0")));
+        assert_eq!(log.redo(zero), None);
+        assert_eq!(log.last_undoable(), Some(zero));
+    }
+
+    #[test]
+    fn test_zero_undo_redo() {
+        let mut log = ActionLog::default();
+        let previous = Position::new(-100.0, -50.0, -20.0);
+        let machine = Position::new(-80.0, -45.0, -12.5);
+        let zero = log.record_zero("Zero X", CoordinateSystem::G55, "X", previous, machine);
+
+        // Undo and redo write offsets in millimeters whatever the controller's units
+        assert_eq!(log.undo(zero), Some(gcode("G21 G10 L2 P2 X-100.000")));
+        // A jog in between doesn't move the zero redo sets
+        assert_eq!(log.redo(zero), Some(gcode("G21 G10 L2 P2 X-80.000")));
+
+        let all = log.record_zero("Zero all", CoordinateSystem::G54, "XYZ", previous, machine);
+        assert_eq!(log.undo(all), Some(gcode("G21 G10 L2 P1 X-100.000 Y-50.000 Z-20.000")));
+        assert_eq!(log.redo(all), Some(gcode("G21 G10 L2 P1 X-80.000 Y-45.000 Z-12.500")));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut log = ActionLog::new(2);
+        for i in 0..3 {
+            log.record(ActionKind::Jog, format!("Jog {}", i), gcode("G0"), None);
+        }
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.entries().next().unwrap().description, "Jog 1");
+    }
+}
@@ -0,0 +1,107 @@
+//! Loaded program analysis
+//!
+//! A read-only view of the armed program for scripts: its text, parsed
+//! segments, extents and estimated run time, so checks such as "refuse to
+//! run below Z-20" can be written without re-parsing the program.
+
+use crate::parser::{JobEstimate, Point3D, Segment};
+
+/// Rapid rate used for the time estimate, as machines don't report theirs (mm/min)
+const NOMINAL_RAPID_RATE: f64 = 3000.0;
+
+/// Parsed form of the program that runs when the program is started
+#[derive(Debug, Clone, Default)]
+pub struct ProgramAnalysis {
+    /// Program text, one entry per line
+    lines: Vec<String>,
+    /// Motion segments, pointing back at the lines they came from
+    segments: Vec<Segment>,
+    /// Run time and travel
+    estimate: JobEstimate,
+}
+
+impl ProgramAnalysis {
+    /// Analyze a program from its text and parsed segments
+    pub fn new(program: &str, segments: Vec<Segment>) -> Self {
+        Self {
+            lines: program.lines().map(str::to_string).collect(),
+            estimate: JobEstimate::from_segments(&segments, NOMINAL_RAPID_RATE),
+            segments,
+        }
+    }
+
+    /// Whether a program is loaded
+    pub fn is_loaded(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// Number of program lines
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Number of motion segments
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// All motion segments
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Text of a line (1-based)
+    pub fn line(&self, number: usize) -> Option<&str> {
+        self.lines.get(number.checked_sub(1)?).map(String::as_str)
+    }
+
+    /// Segments generated by a line (1-based); arcs and cycles may produce several
+    pub fn line_segments(&self, number: usize) -> impl Iterator<Item = &Segment> {
+        let line = number.checked_sub(1);
+        self.segments.iter().filter(move |segment| line.is_some() && segment.source_line == line)
+    }
+
+    /// Minimum and maximum corners of every move, rapids included
+    pub fn bounds(&self) -> Option<(Point3D, Point3D)> {
+        self.segments.iter().flat_map(|s| [s.start, s.end]).fold(None, |bounds, p| {
+            Some(match bounds {
+                Some((min, max)) => (
+                    Point3D::new(p.x.min(min.x), p.y.min(min.y), p.z.min(min.z)),
+                    Point3D::new(p.x.max(max.x), p.y.max(max.y), p.z.max(max.z)),
+                ),
+                None => (p, p),
+            })
+        })
+    }
+
+    /// Run time and travel, with rapids at a nominal 3000 mm/min
+    pub fn estimate(&self) -> JobEstimate {
+        self.estimate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_analysis() {
+        let segments = vec![
+            Segment::rapid(Point3D::new(0.0, 0.0, 5.0), Point3D::new(10.0, 0.0, 5.0)).with_source_line(0),
+            Segment::linear(Point3D::new(10.0, 0.0, 5.0), Point3D::new(10.0, 0.0, -2.0), 100.0).with_source_line(1),
+        ];
+        let analysis = ProgramAnalysis::new("G0 X10 Z5\nG1 Z-2 F100\n", segments);
+
+        assert!(analysis.is_loaded());
+        assert_eq!((analysis.line_count(), analysis.segment_count()), (2, 2));
+        assert_eq!(analysis.line(2), Some("G1 Z-2 F100"));
+        assert_eq!(analysis.line(0), None);
+        assert_eq!(analysis.line_segments(2).count(), 1);
+        assert_eq!(analysis.line_segments(3).count(), 0);
+
+        let (min, max) = analysis.bounds().unwrap();
+        assert_eq!((min.z, max.x), (-2.0, 10.0));
+        assert!((analysis.estimate().cutting_time.as_secs_f64() - 4.2).abs() < 1e-6);
+        assert!(!ProgramAnalysis::default().is_loaded());
+    }
+}
@@ -1,6 +1,8 @@
 //! Application state management
 
-use super::{MachineState, ProgramState, SharedState};
+use super::{MachineState, MachineStatus, ProgramAnalysis, ProgramState, SharedState};
+use super::events::StateEventBroadcaster;
+use crate::connection::LinkMetrics;
 
 /// Complete application state
 #[derive(Clone)]
@@ -11,8 +13,17 @@ pub struct AppState {
     /// Program state
     pub program: SharedState<ProgramState>,
     
+    /// Parsed form of the program that runs on start, for scripts
+    pub analysis: SharedState<ProgramAnalysis>,
+    
     /// Connection state
     pub connected: SharedState<bool>,
+    
+    /// Latest link metrics
+    pub link_metrics: SharedState<LinkMetrics>,
+    
+    /// State change notifications for the UI and other listeners
+    pub events: StateEventBroadcaster,
 }
 
 impl Default for AppState {
@@ -20,7 +31,10 @@ impl Default for AppState {
         AppState {
             machine: SharedState::new(MachineState::default()),
             program: SharedState::new(ProgramState::default()),
+            analysis: SharedState::new(ProgramAnalysis::default()),
             connected: SharedState::new(false),
+            link_metrics: SharedState::new(LinkMetrics::default()),
+            events: StateEventBroadcaster::default(),
         }
     }
 }
@@ -40,4 +54,9 @@ impl AppState {
     pub fn set_connected(&self, connected: bool) {
         *self.connected.write() = connected;
     }
+
+    /// Wait until the machine reports the given status
+    pub async fn wait_for_status(&self, status: MachineStatus) {
+        self.machine.watch().wait_for(|machine| machine.status == status).await;
+    }
 }
@@ -82,6 +82,12 @@ pub enum StateEvent {
         progress: f64,
     },
     
+    /// GRBL raised an alarm
+    AlarmRaised {
+        /// Alarm code from `ALARM:<code>`
+        code: u8,
+    },
+    
     /// Error occurred
     ErrorOccurred {
         /// Error message
@@ -0,0 +1,160 @@
+//! Job journal
+//!
+//! While a program runs, its progress is written to a small file next to
+//! the settings every few seconds: the program file and a hash of its text,
+//! the last acknowledged line, the work coordinate system and the
+//! overrides. A run that finishes or is stopped removes the file, so
+//! finding one at startup means the last run was cut short by a crash or
+//! power loss, and it can be offered for resuming.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::utils::{Error, Result};
+
+/// File name of the journal in the config directory
+const JOURNAL_FILE: &str = "job_journal.toml";
+
+/// Progress of a running job, as last written to disk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobJournal {
+    /// Program file, if the program was loaded from one
+    pub file: Option<PathBuf>,
+    /// Hash of the program text, to check it is unchanged before resuming
+    pub content_hash: String,
+    /// Number of lines in the program
+    pub total_lines: usize,
+    /// Program line last acknowledged by the controller (0-based)
+    pub acknowledged_line: usize,
+    /// Program line that had certainly started running (0-based)
+    pub safe_line: usize,
+    /// Active work coordinate system, e.g. "G54"
+    pub coordinate_system: String,
+    /// Offset of the active work coordinate system (mm)
+    pub work_offset: [f64; 3],
+    /// Feed override (%)
+    pub feed_override: f64,
+    /// Spindle override (%)
+    pub spindle_override: f64,
+    /// Rapid override (%)
+    pub rapid_override: f64,
+    /// Local time the journal was last written
+    pub updated: String,
+}
+
+impl JobJournal {
+    /// Start a journal for a program
+    pub fn new(file: Option<PathBuf>, content: &str) -> Self {
+        Self {
+            file,
+            content_hash: Self::content_hash(content),
+            total_lines: content.lines().count(),
+            ..Default::default()
+        }
+    }
+
+    /// Hash of a program's text
+    ///
+    /// FNV-1a, so the value stays the same across builds and platforms.
+    pub fn content_hash(content: &str) -> String {
+        let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Whether a program is the one the journal was written for
+    pub fn matches(&self, content: &str) -> bool {
+        self.content_hash == Self::content_hash(content)
+    }
+
+    /// Load a journal file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::config(format!("Failed to parse job journal: {}", e)))
+    }
+
+    /// Save to a journal file
+    ///
+    /// Written to a temporary file first and renamed over the journal, so
+    /// a crash while writing leaves the previous journal intact.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| Error::config(format!("Failed to serialize job journal: {}", e)))?;
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Get the journal path, next to the settings file
+    pub fn default_path() -> Result<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "rCandle")
+            .ok_or_else(|| Error::config("Failed to determine config directory"))?;
+
+        let config_dir = dirs.config_dir();
+        std::fs::create_dir_all(config_dir)?;
+
+        Ok(config_dir.join(JOURNAL_FILE))
+    }
+
+    /// Journal left by an interrupted run, if there is one
+    pub fn load_default() -> Option<Self> {
+        let path = Self::default_path().ok()?;
+        if !path.exists() {
+            return None;
+        }
+        match Self::load(&path) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                tracing::warn!("Failed to load job journal from {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Save as the journal of the running job
+    pub fn save_default(&self) -> Result<()> {
+        self.save(Self::default_path()?)
+    }
+
+    /// Remove the journal once the job has ended
+    pub fn remove_default() -> Result<()> {
+        let path = Self::default_path()?;
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_round_trip() {
+        let dir = std::env::temp_dir().join(format!("rcandle_journal_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(JOURNAL_FILE);
+
+        let program = "G21\nG0 X10\nG1 Z-1 F200\n";
+        let mut journal = JobJournal::new(Some(PathBuf::from("/tmp/part.nc")), program);
+        journal.acknowledged_line = 2;
+        journal.coordinate_system = "G55".to_string();
+        journal.feed_override = 80.0;
+        journal.save(&path).unwrap();
+
+        let loaded = JobJournal::load(&path).unwrap();
+        assert_eq!(loaded, journal);
+        assert_eq!(loaded.total_lines, 3);
+        assert!(loaded.matches(program));
+        assert!(!loaded.matches("G21\nG0 X11\nG1 Z-1 F200\n"));
+        assert_eq!(JobJournal::content_hash(""), "cbf29ce484222325");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -136,6 +136,20 @@ impl std::fmt::Display for CoordinateSystem {
     }
 }
 
+impl CoordinateSystem {
+    /// `P` word selecting this system in `G10 L2`/`G10 L20` (1 for G54)
+    pub fn p_number(&self) -> u32 {
+        match self {
+            CoordinateSystem::G54 => 1,
+            CoordinateSystem::G55 => 2,
+            CoordinateSystem::G56 => 3,
+            CoordinateSystem::G57 => 4,
+            CoordinateSystem::G58 => 5,
+            CoordinateSystem::G59 => 6,
+        }
+    }
+}
+
 /// Complete machine state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MachineState {
@@ -154,12 +168,24 @@ pub struct MachineState {
     /// Work coordinate offsets for each system
     pub work_offsets: [Position; 6],
     
+    /// G28 reference position read back from `$#`
+    pub g28_position: Option<Position>,
+    
+    /// G30 reference position read back from `$#`
+    pub g30_position: Option<Position>,
+    
     /// Spindle speed (RPM)
     pub spindle_speed: f64,
     
     /// Spindle enabled
     pub spindle_enabled: bool,
     
+    /// Flood coolant on (M8)
+    pub flood_coolant: bool,
+    
+    /// Mist coolant on (M7)
+    pub mist_coolant: bool,
+    
     /// Feed rate (mm/min or in/min)
     pub feed_rate: f64,
     
@@ -187,8 +213,12 @@ impl Default for MachineState {
             work_position: Position::default(),
             coordinate_system: CoordinateSystem::default(),
             work_offsets: [Position::default(); 6],
+            g28_position: None,
+            g30_position: None,
             spindle_speed: 0.0,
             spindle_enabled: false,
+            flood_coolant: false,
+            mist_coolant: false,
             feed_rate: 0.0,
             feed_override: 100.0,
             spindle_override: 100.0,
@@ -231,6 +261,30 @@ impl MachineState {
         self.work_offsets[index] = offset;
     }
 
+    /// Store a position reported by `$#`
+    pub fn apply_parameter(&mut self, parameter: &crate::grbl::GrblParameter) {
+        let p = parameter.position;
+        let position = Position::new(p.x, p.y, p.z);
+        let system = match parameter.name.as_str() {
+            "G54" => CoordinateSystem::G54,
+            "G55" => CoordinateSystem::G55,
+            "G56" => CoordinateSystem::G56,
+            "G57" => CoordinateSystem::G57,
+            "G58" => CoordinateSystem::G58,
+            "G59" => CoordinateSystem::G59,
+            "G28" => {
+                self.g28_position = Some(position);
+                return;
+            }
+            "G30" => {
+                self.g30_position = Some(position);
+                return;
+            }
+            _ => return,
+        };
+        self.set_work_offset(system, position);
+    }
+
     /// Update machine position and calculate work position
     pub fn update_machine_position(&mut self, pos: Position) {
         self.machine_position = pos;
@@ -242,6 +296,15 @@ impl MachineState {
         );
     }
 
+    /// Offset currently applied between machine and work position
+    pub fn active_work_offset(&self) -> Position {
+        Position::new(
+            self.machine_position.x - self.work_position.x,
+            self.machine_position.y - self.work_position.y,
+            self.machine_position.z - self.work_position.z,
+        )
+    }
+
     /// Check if machine is in an error state
     pub fn is_error_state(&self) -> bool {
         matches!(self.status, MachineStatus::Alarm)
@@ -336,6 +399,13 @@ impl MachineState {
         if let Some((planner, _rx)) = grbl_status.buffer {
             self.buffer_state = planner as u32;
         }
+        
+        // Update spindle and coolant state if the report carries accessories
+        if let Some(accessories) = grbl_status.accessory_state() {
+            self.spindle_enabled = accessories.spindle_cw || accessories.spindle_ccw;
+            self.flood_coolant = accessories.flood;
+            self.mist_coolant = accessories.mist;
+        }
     }
 }
 
@@ -351,6 +421,20 @@ mod tests {
         assert_eq!(pos.z, 3.0);
     }
 
+    #[test]
+    fn test_apply_parameter() {
+        let mut state = MachineState::new();
+        let parameter = crate::grbl::GrblParameter::parse("G28:-5.000,-10.000,-1.000").unwrap();
+        state.apply_parameter(&parameter);
+        let g28 = state.g28_position.unwrap();
+        assert_eq!((g28.x, g28.y, g28.z), (-5.0, -10.0, -1.0));
+        assert!(state.g30_position.is_none());
+
+        let parameter = crate::grbl::GrblParameter::parse("G55:1.000,2.000,3.000").unwrap();
+        state.apply_parameter(&parameter);
+        assert_eq!(state.get_work_offset(CoordinateSystem::G55).y, 2.0);
+    }
+
     #[test]
     fn test_machine_state_work_offset() {
         let mut state = MachineState::new();
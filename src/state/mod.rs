@@ -2,42 +2,78 @@
 //!
 //! Provides thread-safe state tracking for the machine and application.
 
-use std::sync::{Arc, RwLock};
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use tokio::sync::watch;
 
 mod machine;
 mod program;
+mod analysis;
 mod app;
 mod events;
 mod updater;
+mod action_log;
+mod probe_log;
+mod recovery;
+mod journal;
 
 pub use machine::{MachineState, MachineStatus, Position, CoordinateSystem};
 pub use program::{ProgramState, ExecutionState};
+pub use analysis::ProgramAnalysis;
 pub use app::AppState;
 pub use events::{StateEvent, StateEventBroadcaster};
 pub use updater::StateUpdater;
+pub use action_log::{offset_command, ActionKind, ActionLog, LoggedAction};
+pub use probe_log::{ProbeLog, ProbeRecord, ToolCheck, ToolLength};
+pub use journal::JobJournal;
+pub use recovery::{RecoveryEngine, RecoveryPolicy, RecoveryRecord, RecoverySettings, RecoveryStep, RecoveryTrigger};
 
 /// Shared state wrapper for thread-safe access
-#[derive(Clone)]
+///
+/// Locks never poison, and every write notifies watchers created with
+/// [`SharedState::watch`] so async tasks can await changes instead of polling.
 pub struct SharedState<T> {
-    inner: Arc<RwLock<T>>,
+    inner: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+    value: RwLock<T>,
+    /// Bumped whenever a write guard is released
+    version: watch::Sender<u64>,
+}
+
+impl<T> Clone for SharedState<T> {
+    fn clone(&self) -> Self {
+        SharedState {
+            inner: Arc::clone(&self.inner),
+        }
+    }
 }
 
 impl<T> SharedState<T> {
     /// Create a new shared state
     pub fn new(value: T) -> Self {
+        let (version, _) = watch::channel(0);
         SharedState {
-            inner: Arc::new(RwLock::new(value)),
+            inner: Arc::new(Shared {
+                value: RwLock::new(value),
+                version,
+            }),
         }
     }
 
     /// Read access to the state
-    pub fn read(&self) -> std::sync::RwLockReadGuard<'_, T> {
-        self.inner.read().unwrap()
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.value.read()
     }
 
-    /// Write access to the state
-    pub fn write(&self) -> std::sync::RwLockWriteGuard<'_, T> {
-        self.inner.write().unwrap()
+    /// Write access to the state; watchers are notified when the guard drops
+    pub fn write(&self) -> StateWriteGuard<'_, T> {
+        StateWriteGuard {
+            guard: Some(self.inner.value.write()),
+            version: &self.inner.version,
+        }
     }
 
     /// Update the state with a closure
@@ -48,6 +84,14 @@ impl<T> SharedState<T> {
         let mut state = self.write();
         f(&mut state);
     }
+
+    /// Watch the state for changes
+    pub fn watch(&self) -> StateWatcher<T> {
+        StateWatcher {
+            changes: self.inner.version.subscribe(),
+            inner: Arc::clone(&self.inner),
+        }
+    }
 }
 
 impl<T: Default> Default for SharedState<T> {
@@ -55,3 +99,102 @@ impl<T: Default> Default for SharedState<T> {
         SharedState::new(T::default())
     }
 }
+
+/// Write guard that notifies watchers once the lock is released
+pub struct StateWriteGuard<'a, T> {
+    guard: Option<RwLockWriteGuard<'a, T>>,
+    version: &'a watch::Sender<u64>,
+}
+
+impl<T> Deref for StateWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.as_ref().expect("guard is only taken on drop")
+    }
+}
+
+impl<T> DerefMut for StateWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().expect("guard is only taken on drop")
+    }
+}
+
+impl<T> Drop for StateWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // Release the lock first so woken watchers can read straight away
+        drop(self.guard.take());
+        self.version.send_modify(|version| *version = version.wrapping_add(1));
+    }
+}
+
+/// Async view of a [`SharedState`] that wakes on every write
+pub struct StateWatcher<T> {
+    inner: Arc<Shared<T>>,
+    changes: watch::Receiver<u64>,
+}
+
+impl<T> StateWatcher<T> {
+    /// Read access to the current state
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.value.read()
+    }
+
+    /// Wait for the next write to the state
+    pub async fn changed(&mut self) {
+        // The watcher holds the sender alive, so the channel never closes
+        let _ = self.changes.changed().await;
+    }
+
+    /// Wait until the state satisfies `condition`, checking after every write
+    pub async fn wait_for<F>(&mut self, mut condition: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        loop {
+            self.changes.borrow_and_update();
+            let satisfied = condition(&self.inner.value.read());
+            if satisfied {
+                return;
+            }
+            self.changed().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_write_bumps_version() {
+        let state = SharedState::new(1);
+        let watcher = state.watch();
+        *state.write() = 2;
+        assert!(watcher.changes.has_changed().unwrap());
+        assert_eq!(*watcher.read(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let state = SharedState::new(MachineStatus::Run);
+        let mut watcher = state.watch();
+
+        let writer = state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            *writer.write() = MachineStatus::Hold;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            *writer.write() = MachineStatus::Idle;
+        });
+
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            watcher.wait_for(|status| *status == MachineStatus::Idle),
+        )
+        .await
+        .expect("state never became idle");
+        assert_eq!(*state.read(), MachineStatus::Idle);
+    }
+}
@@ -0,0 +1,182 @@
+//! Probe log and tool length offsets
+//!
+//! Keeps the positions reported after each probe cycle and derives tool
+//! length offsets (G43.1) from a reference tool measured on the same probe
+//! plate. Re-measuring the current tool during a job shows whether it has
+//! broken or worn since its offset was applied.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use super::Position;
+
+/// Default number of probe results kept
+const DEFAULT_CAPACITY: usize = 100;
+
+/// A probe cycle result
+#[derive(Debug, Clone)]
+pub struct ProbeRecord {
+    /// Unique id within the log
+    pub id: u64,
+    /// When the result was received
+    pub timestamp: DateTime<Local>,
+    /// Machine position where the probe stopped
+    pub position: Position,
+    /// Whether the probe triggered
+    pub success: bool,
+    /// Whether the probe measured a tool on the probe plate
+    pub tool_measurement: bool,
+}
+
+/// Bounded log of probe results, oldest first
+#[derive(Debug, Clone)]
+pub struct ProbeLog {
+    /// Logged results
+    entries: VecDeque<ProbeRecord>,
+    /// Maximum number of results kept
+    capacity: usize,
+    /// Id for the next result
+    next_id: u64,
+}
+
+impl Default for ProbeLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ProbeLog {
+    /// Create a log keeping up to `capacity` results
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_id: 1,
+        }
+    }
+
+    /// Record a probe result, returning its id
+    pub fn record(&mut self, position: Position, success: bool, tool_measurement: bool) -> u64 {
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push_back(ProbeRecord {
+            id,
+            timestamp: Local::now(),
+            position,
+            success,
+            tool_measurement,
+        });
+        id
+    }
+
+    /// Logged results, oldest first
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ProbeRecord> {
+        self.entries.iter()
+    }
+
+    /// Result with the given id
+    pub fn get(&self, id: u64) -> Option<&ProbeRecord> {
+        self.entries.iter().find(|r| r.id == id)
+    }
+
+    /// Most recent result
+    pub fn last(&self) -> Option<&ProbeRecord> {
+        self.entries.back()
+    }
+
+    /// Whether the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all results
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Tool length offset bookkeeping
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ToolLength {
+    /// Machine Z at which the reference tool triggered the probe plate
+    pub reference_z: Option<f64>,
+    /// Tool length offset currently applied with G43.1
+    pub offset: f64,
+}
+
+impl ToolLength {
+    /// Offset for a tool that triggered the probe plate at `probe_z`
+    ///
+    /// A longer tool triggers higher, giving a positive offset.
+    pub fn offset_for(&self, probe_z: f64) -> Option<f64> {
+        self.reference_z.map(|reference| probe_z - reference)
+    }
+
+    /// Command applying an offset
+    pub fn apply_command(offset: f64) -> String {
+        format!("G43.1 Z{:.3}", offset)
+    }
+
+    /// Machine Z at which the current tool should trigger the probe plate
+    pub fn expected_z(&self) -> Option<f64> {
+        self.reference_z.map(|reference| reference + self.offset)
+    }
+
+    /// How much longer the tool measured at `probe_z` is than expected
+    ///
+    /// A broken or worn tool triggers lower, giving a negative deviation.
+    pub fn deviation(&self, probe_z: f64) -> Option<f64> {
+        self.expected_z().map(|expected| probe_z - expected)
+    }
+}
+
+/// What started a tool breakage check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCheck {
+    /// The check interval elapsed
+    Interval,
+    /// The program reached a tool change to this tool
+    ToolChange(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_log() {
+        let mut log = ProbeLog::new(2);
+        log.record(Position::new(0.0, 0.0, -10.0), true, false);
+        let second = log.record(Position::new(0.0, 0.0, -11.0), true, true);
+        let third = log.record(Position::new(0.0, 0.0, -12.0), false, false);
+
+        assert_eq!(log.entries().count(), 2);
+        assert!(log.get(1).is_none());
+        assert!(log.get(second).unwrap().tool_measurement);
+        assert_eq!(log.last().unwrap().id, third);
+    }
+
+    #[test]
+    fn test_tool_length_offset() {
+        let mut tool_length = ToolLength::default();
+        assert_eq!(tool_length.offset_for(-30.0), None);
+
+        tool_length.reference_z = Some(-32.5);
+        assert_eq!(tool_length.offset_for(-30.0), Some(2.5));
+        assert_eq!(ToolLength::apply_command(2.5), "G43.1 Z2.500");
+    }
+
+    #[test]
+    fn test_tool_deviation() {
+        let mut tool_length = ToolLength::default();
+        assert_eq!(tool_length.deviation(-30.0), None);
+
+        tool_length.reference_z = Some(-32.5);
+        tool_length.offset = 2.5;
+        assert_eq!(tool_length.expected_z(), Some(-30.0));
+        assert!((tool_length.deviation(-31.2).unwrap() + 1.2).abs() < 1e-9);
+    }
+}
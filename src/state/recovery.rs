@@ -0,0 +1,279 @@
+//! Alarm recovery engine
+//!
+//! Applies the machine profile's recovery policies to state events: on a
+//! given alarm code or when the safety door opens, a policy lists the steps
+//! to take, such as unlocking with `$X` and retracting Z. In dry-run mode the
+//! steps are only logged, so a policy can be checked before it is trusted to
+//! move the machine.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{MachineStatus, StateEvent};
+
+/// Number of log entries kept
+const LOG_CAPACITY: usize = 100;
+
+/// Situation a recovery policy responds to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecoveryTrigger {
+    /// GRBL reported `ALARM:<code>`
+    Alarm(u8),
+    /// The safety door was opened
+    DoorOpen,
+}
+
+impl std::fmt::Display for RecoveryTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecoveryTrigger::Alarm(code) => write!(f, "ALARM:{}", code),
+            RecoveryTrigger::DoorOpen => write!(f, "Door open"),
+        }
+    }
+}
+
+/// Step of a recovery policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecoveryStep {
+    /// Clear the alarm lock with `$X`
+    Unlock,
+    /// Raise Z by the given distance (mm) in relative mode
+    RetractZ(f64),
+    /// Send a feed hold
+    FeedHold,
+    /// Show a message to the operator
+    Notify(String),
+}
+
+impl RecoveryStep {
+    /// G-Code or system commands sent for the step, if any
+    pub fn commands(&self) -> Vec<String> {
+        match self {
+            RecoveryStep::Unlock => vec!["$X".to_string()],
+            RecoveryStep::RetractZ(distance) => vec![
+                format!("G91 G0 Z{:.3}", distance.abs()),
+                "G90".to_string(),
+            ],
+            RecoveryStep::FeedHold | RecoveryStep::Notify(_) => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for RecoveryStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecoveryStep::Unlock => write!(f, "Unlock ($X)"),
+            RecoveryStep::RetractZ(distance) => write!(f, "Retract Z {:.1} mm", distance.abs()),
+            RecoveryStep::FeedHold => write!(f, "Feed hold"),
+            RecoveryStep::Notify(message) => write!(f, "Notify: {}", message),
+        }
+    }
+}
+
+/// Steps to take when a trigger occurs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryPolicy {
+    /// What the policy responds to
+    pub trigger: RecoveryTrigger,
+    /// Steps taken in order
+    pub steps: Vec<RecoveryStep>,
+    /// Whether the policy is active
+    pub enabled: bool,
+}
+
+/// Recovery policies of a machine profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoverySettings {
+    /// Only log what would be done
+    pub dry_run: bool,
+    /// Policies, the first matching enabled one applies
+    pub policies: Vec<RecoveryPolicy>,
+}
+
+impl Default for RecoverySettings {
+    fn default() -> Self {
+        Self {
+            dry_run: true,
+            policies: vec![
+                RecoveryPolicy {
+                    trigger: RecoveryTrigger::Alarm(1),
+                    steps: vec![RecoveryStep::Unlock, RecoveryStep::RetractZ(5.0)],
+                    enabled: false,
+                },
+                RecoveryPolicy {
+                    trigger: RecoveryTrigger::DoorOpen,
+                    steps: vec![
+                        RecoveryStep::FeedHold,
+                        RecoveryStep::Notify("Safety door opened".to_string()),
+                    ],
+                    enabled: false,
+                },
+            ],
+        }
+    }
+}
+
+impl RecoverySettings {
+    /// First enabled policy for a trigger
+    pub fn policy_for(&self, trigger: RecoveryTrigger) -> Option<&RecoveryPolicy> {
+        self.policies.iter().find(|p| p.enabled && p.trigger == trigger)
+    }
+}
+
+/// A policy application recorded in the recovery log
+#[derive(Debug, Clone)]
+pub struct RecoveryRecord {
+    /// When the trigger occurred
+    pub timestamp: DateTime<Local>,
+    /// What triggered the policy
+    pub trigger: RecoveryTrigger,
+    /// Steps of the policy
+    pub steps: Vec<RecoveryStep>,
+    /// Whether the steps were carried out, false in dry-run mode
+    pub executed: bool,
+}
+
+/// Turns state events into recovery steps according to the policies
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryEngine {
+    /// Active policies
+    settings: RecoverySettings,
+    /// Applied policies, oldest first
+    log: VecDeque<RecoveryRecord>,
+}
+
+impl RecoveryEngine {
+    /// Create an engine for the given policies
+    pub fn new(settings: RecoverySettings) -> Self {
+        Self {
+            settings,
+            log: VecDeque::new(),
+        }
+    }
+
+    /// Replace the policies, keeping the log
+    pub fn set_settings(&mut self, settings: RecoverySettings) {
+        self.settings = settings;
+    }
+
+    /// Active policies
+    pub fn settings(&self) -> &RecoverySettings {
+        &self.settings
+    }
+
+    /// Trigger raised by a state event, if any
+    pub fn trigger_for(event: &StateEvent) -> Option<RecoveryTrigger> {
+        match event {
+            StateEvent::AlarmRaised { code } => Some(RecoveryTrigger::Alarm(*code)),
+            StateEvent::MachineStatusChanged { old, new: MachineStatus::Door } if *old != MachineStatus::Door => {
+                Some(RecoveryTrigger::DoorOpen)
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a state event, returning the steps to carry out now
+    ///
+    /// Matching policies are always logged; in dry-run mode no steps are
+    /// returned.
+    pub fn handle_event(&mut self, event: &StateEvent) -> Vec<RecoveryStep> {
+        let Some(trigger) = Self::trigger_for(event) else {
+            return Vec::new();
+        };
+        let Some(policy) = self.settings.policy_for(trigger) else {
+            return Vec::new();
+        };
+        let steps = policy.steps.clone();
+        let executed = !self.settings.dry_run;
+
+        if self.log.len() >= LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(RecoveryRecord {
+            timestamp: Local::now(),
+            trigger,
+            steps: steps.clone(),
+            executed,
+        });
+
+        if executed {
+            steps
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Applied policies, oldest first
+    pub fn log(&self) -> impl DoubleEndedIterator<Item = &RecoveryRecord> {
+        self.log.iter()
+    }
+
+    /// Forget the log
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_settings(dry_run: bool) -> RecoverySettings {
+        let mut settings = RecoverySettings {
+            dry_run,
+            ..Default::default()
+        };
+        for policy in &mut settings.policies {
+            policy.enabled = true;
+        }
+        settings
+    }
+
+    #[test]
+    fn test_hard_limit_policy() {
+        let mut engine = RecoveryEngine::new(enabled_settings(false));
+        let steps = engine.handle_event(&StateEvent::AlarmRaised { code: 1 });
+        assert_eq!(steps, vec![RecoveryStep::Unlock, RecoveryStep::RetractZ(5.0)]);
+        let commands: Vec<String> = steps.iter().flat_map(RecoveryStep::commands).collect();
+        assert_eq!(commands, vec!["$X", "G91 G0 Z5.000", "G90"]);
+
+        assert!(engine.handle_event(&StateEvent::AlarmRaised { code: 2 }).is_empty());
+        assert_eq!(engine.log().count(), 1);
+        assert!(engine.log().next().unwrap().executed);
+    }
+
+    #[test]
+    fn test_door_policy() {
+        let mut engine = RecoveryEngine::new(enabled_settings(false));
+        let opened = StateEvent::MachineStatusChanged {
+            old: MachineStatus::Run,
+            new: MachineStatus::Door,
+        };
+        assert_eq!(engine.handle_event(&opened)[0], RecoveryStep::FeedHold);
+
+        let closed = StateEvent::MachineStatusChanged {
+            old: MachineStatus::Door,
+            new: MachineStatus::Hold,
+        };
+        assert!(engine.handle_event(&closed).is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_only_logs() {
+        let mut engine = RecoveryEngine::new(enabled_settings(true));
+        assert!(engine.handle_event(&StateEvent::AlarmRaised { code: 1 }).is_empty());
+        let record = engine.log().next().unwrap();
+        assert_eq!(record.trigger, RecoveryTrigger::Alarm(1));
+        assert_eq!(record.steps.len(), 2);
+        assert!(!record.executed);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let mut engine = RecoveryEngine::default();
+        assert!(engine.handle_event(&StateEvent::AlarmRaised { code: 1 }).is_empty());
+        assert_eq!(engine.log().count(), 0);
+    }
+}
@@ -2,10 +2,8 @@
 //!
 //! This module provides the logic to update application state based on GRBL responses.
 
-use super::{
-    AppState, CoordinateSystem, ExecutionState, MachineStatus, Position,
-};
-use crate::grbl::{GrblResponse, GrblStatus};
+use super::{AppState, CoordinateSystem, ExecutionState, Position};
+use crate::grbl::{GrblParameter, GrblResponse, GrblStatus};
 use crate::state::events::{StateEvent, StateEventBroadcaster};
 
 /// State updater that processes GRBL responses
@@ -42,6 +40,7 @@ impl StateUpdater {
             GrblResponse::Alarm(code) => {
                 let msg = format!("GRBL Alarm: {}", code);
                 self.handle_error(msg);
+                self.event_broadcaster.send(StateEvent::AlarmRaised { code: *code });
             }
             GrblResponse::Setting { number, value } => {
                 tracing::debug!("Received setting: ${}={}", number, value);
@@ -49,6 +48,9 @@ impl StateUpdater {
             }
             GrblResponse::Feedback(msg) => {
                 tracing::debug!("Feedback: {}", msg);
+                if let Some(parameter) = GrblParameter::parse(msg) {
+                    self.app_state.machine.write().apply_parameter(&parameter);
+                }
             }
             GrblResponse::Message(msg) => {
                 tracing::info!("Message: {}", msg);
@@ -64,95 +66,74 @@ impl StateUpdater {
     }
 
     /// Update state from a status report
+    ///
+    /// The report is applied with [`MachineState::update_from_grbl_status`] and
+    /// an event is broadcast for each group of values that actually changed.
+    ///
+    /// [`MachineState::update_from_grbl_status`]: super::MachineState::update_from_grbl_status
     fn update_from_status_report(&self, status: &GrblStatus) {
-        let mut machine = self.app_state.machine.write();
-        let old_status = machine.status;
-
-        // Update machine status
-        let new_status = convert_grbl_machine_state(&status.state);
-        if old_status != new_status {
-            machine.status = new_status;
-            self.event_broadcaster
-                .send(StateEvent::MachineStatusChanged {
-                    old: old_status,
-                    new: new_status,
+        let mut events = Vec::new();
+        {
+            let mut machine = self.app_state.machine.write();
+            let old = machine.clone();
+            machine.update_from_grbl_status(status);
+
+            if old.status != machine.status {
+                events.push(StateEvent::MachineStatusChanged {
+                    old: old.status,
+                    new: machine.status,
                 });
-        }
+            }
 
-        // Update positions if available
-        if let Some(mpos) = &status.mpos {
-            let new_pos = Position::new(mpos.x, mpos.y, mpos.z);
-            machine.update_machine_position(new_pos);
-            self.event_broadcaster
-                .send(StateEvent::MachinePositionChanged {
+            if moved(&old.machine_position, &machine.machine_position)
+                || moved(&old.work_position, &machine.work_position)
+            {
+                events.push(StateEvent::MachinePositionChanged {
                     machine_pos: machine.machine_position,
                     work_pos: machine.work_position,
                 });
-        }
-
-        // Update work position if available (takes precedence over calculated)
-        if let Some(wpos) = &status.wpos {
-            machine.work_position = Position::new(wpos.x, wpos.y, wpos.z);
-        }
-
-        // Update feed rate
-        if let Some(feed) = status.feed_rate {
-            if (machine.feed_rate - feed).abs() > 0.01 {
-                machine.feed_rate = feed;
-                self.event_broadcaster
-                    .send(StateEvent::FeedRateChanged { feed_rate: feed });
             }
-        }
 
-        // Update spindle speed
-        if let Some(speed) = status.spindle_speed {
-            if (machine.spindle_speed - speed).abs() > 0.01 {
-                machine.spindle_speed = speed;
-                machine.spindle_enabled = speed > 0.0;
-                self.event_broadcaster
-                    .send(StateEvent::SpindleStateChanged {
-                        enabled: machine.spindle_enabled,
-                        speed,
-                    });
+            if changed(old.feed_rate, machine.feed_rate) {
+                events.push(StateEvent::FeedRateChanged {
+                    feed_rate: machine.feed_rate,
+                });
             }
-        }
 
-        // Update overrides
-        let mut overrides_changed = false;
-        if let Some(feed_override) = status.feed_override {
-            let feed_override_f64 = feed_override as f64;
-            if (machine.feed_override - feed_override_f64).abs() > 0.01 {
-                machine.feed_override = feed_override_f64;
-                overrides_changed = true;
-            }
-        }
-        if let Some(rapid_override) = status.rapid_override {
-            let rapid_override_f64 = rapid_override as f64;
-            if (machine.rapid_override - rapid_override_f64).abs() > 0.01 {
-                machine.rapid_override = rapid_override_f64;
-                overrides_changed = true;
-            }
-        }
-        if let Some(spindle_override) = status.spindle_override {
-            let spindle_override_f64 = spindle_override as f64;
-            if (machine.spindle_override - spindle_override_f64).abs() > 0.01 {
-                machine.spindle_override = spindle_override_f64;
-                overrides_changed = true;
+            if changed(old.spindle_speed, machine.spindle_speed)
+                || old.spindle_enabled != machine.spindle_enabled
+            {
+                events.push(StateEvent::SpindleStateChanged {
+                    enabled: machine.spindle_enabled,
+                    speed: machine.spindle_speed,
+                });
             }
-        }
 
-        if overrides_changed {
-            self.event_broadcaster
-                .send(StateEvent::OverridesChanged {
+            if changed(old.feed_override, machine.feed_override)
+                || changed(old.rapid_override, machine.rapid_override)
+                || changed(old.spindle_override, machine.spindle_override)
+            {
+                events.push(StateEvent::OverridesChanged {
                     feed: machine.feed_override,
                     rapid: machine.rapid_override,
                     spindle: machine.spindle_override,
                 });
+            }
+
+            if status.wco.is_some() {
+                let offset = machine.active_work_offset();
+                if moved(&old.active_work_offset(), &offset) {
+                    events.push(StateEvent::WorkOffsetChanged {
+                        system: machine.coordinate_system,
+                        offset,
+                    });
+                }
+            }
         }
 
-        // Update buffer state
-        if let Some((planner, _rx)) = status.buffer {
-            machine.buffer_state = planner as u32;
+        // Broadcast after releasing the lock so listeners can read the new state
+        for event in events {
+            self.event_broadcaster.send(event);
         }
     }
 
@@ -272,47 +253,63 @@ impl StateUpdater {
     }
 }
 
-/// Convert GRBL machine state to application machine status
-fn convert_grbl_machine_state(grbl_state: &crate::grbl::MachineState) -> MachineStatus {
-    match grbl_state {
-        crate::grbl::MachineState::Idle => MachineStatus::Idle,
-        crate::grbl::MachineState::Run => MachineStatus::Run,
-        crate::grbl::MachineState::Hold => MachineStatus::Hold,
-        crate::grbl::MachineState::Jog => MachineStatus::Jog,
-        crate::grbl::MachineState::Alarm => MachineStatus::Alarm,
-        crate::grbl::MachineState::Door => MachineStatus::Door,
-        crate::grbl::MachineState::Check => MachineStatus::Check,
-        crate::grbl::MachineState::Home => MachineStatus::Home,
-        crate::grbl::MachineState::Sleep => MachineStatus::Sleep,
-    }
+/// Whether a scalar value changed enough to be worth reporting
+fn changed(old: f64, new: f64) -> bool {
+    (old - new).abs() > 0.01
+}
+
+/// Whether a position changed enough to be worth reporting
+fn moved(old: &Position, new: &Position) -> bool {
+    changed(old.x, new.x) || changed(old.y, new.y) || changed(old.z, new.z)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::MachineStatus;
+
+    fn status_report(content: &str) -> GrblResponse {
+        GrblResponse::Status(GrblStatus::parse(content).unwrap())
+    }
 
     #[test]
-    fn test_convert_grbl_machine_state() {
-        assert_eq!(
-            convert_grbl_machine_state(&crate::grbl::MachineState::Idle),
-            MachineStatus::Idle
-        );
-        assert_eq!(
-            convert_grbl_machine_state(&crate::grbl::MachineState::Run),
-            MachineStatus::Run
-        );
-        assert_eq!(
-            convert_grbl_machine_state(&crate::grbl::MachineState::Hold),
-            MachineStatus::Hold
-        );
-        assert_eq!(
-            convert_grbl_machine_state(&crate::grbl::MachineState::Jog),
-            MachineStatus::Jog
-        );
-        assert_eq!(
-            convert_grbl_machine_state(&crate::grbl::MachineState::Alarm),
-            MachineStatus::Alarm
-        );
+    fn test_status_report_machine_state() {
+        let app_state = AppState::new();
+        let updater = StateUpdater::new(app_state.clone(), StateEventBroadcaster::new(10));
+
+        for (report, expected) in [
+            ("Idle", MachineStatus::Idle),
+            ("Run", MachineStatus::Run),
+            ("Hold:0", MachineStatus::Hold),
+            ("Jog", MachineStatus::Jog),
+            ("Alarm", MachineStatus::Alarm),
+        ] {
+            updater.process_response(&status_report(report));
+            assert_eq!(app_state.machine.read().status, expected);
+        }
+    }
+
+    #[test]
+    fn test_status_report_events() {
+        let app_state = AppState::new();
+        let broadcaster = StateEventBroadcaster::new(10);
+        let updater = StateUpdater::new(app_state.clone(), broadcaster.clone());
+        let mut receiver = broadcaster.subscribe();
+
+        updater.process_response(&status_report("Run|MPos:1.000,2.000,3.000|F:500|Ov:120,100,100"));
+
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert!(events.iter().any(|e| matches!(e, StateEvent::MachineStatusChanged { new: MachineStatus::Run, .. })));
+        assert!(events.iter().any(|e| matches!(e, StateEvent::MachinePositionChanged { machine_pos, .. } if machine_pos.x == 1.0)));
+        assert!(events.iter().any(|e| matches!(e, StateEvent::FeedRateChanged { feed_rate } if *feed_rate == 500.0)));
+        assert!(events.iter().any(|e| matches!(e, StateEvent::OverridesChanged { feed, .. } if *feed == 120.0)));
+
+        // An identical report changes nothing and stays silent
+        updater.process_response(&status_report("Run|MPos:1.000,2.000,3.000|F:500|Ov:120,100,100"));
+        assert!(receiver.try_recv().is_err());
     }
 
     #[tokio::test]
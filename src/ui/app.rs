@@ -1,27 +1,143 @@
 //! Main application structure for rCandle
 
 use crate::{
-    connection::{ConnectionManager, ConnectionManagerConfig, SerialConnection},
-    grbl::{CommandQueue, GrblCommand, GrblResponse, OverrideCommand, FeedRateOverride, SpindleOverride, RapidOverride},
-    parser::{Parser, Preprocessor, Segment, SegmentType, Tokenizer},
-    renderer::{Renderer, ViewPreset},
-    script::{ScriptLibrary, UserCommandLibrary, UserScript},
-    settings::Settings,
-    state::{AppState, ExecutionState, MachineStatus},
-    ui::widgets::{Console, GCodeEditor},
+    cli::Cli,
+    connection::{
+        discover_network_devices, scan_for_grbl, AutoConnect, BluetoothConnection, BluetoothDevice, Connection, ConnectionEvent, ConnectionManager,
+        DetectedDevice, LineControl, LinkActivity, MockConnection, MockDevice, MockDeviceConfig, MockScript, NetworkDevice, NetworkProtocol, PluginConnection, SerialConfig,
+        SerialConnection, TelnetConnection, WebSocketAuth, WebSocketConnection, COMMON_BAUD_RATES, DEFAULT_DISCOVERY_TIMEOUT, DEFAULT_PROBE_TIMEOUT,
+    },
+    grbl::{CommandQueue, GrblCommand, GrblParameter, GrblResponse, OverrideCommand, OverrideRamp, SpindleOverride, RapidOverride, ProbeResult, ProgramStreamer, RealtimeCommand, StockEdge, StreamOptions, StreamPasses},
+    heightmap::{AutoLeveler, Heightmap, ZLimitViolation},
+    parser::{save_toolpath, BacklashCompensator, DryRun, ExpressionEvaluator, JobEstimate, marker_comments, Parser, PlasmaPostProcessor, PlungeEntry, PlungeRewriter, Preprocessor, ProgramExpander, SafeRetract, Segment, SegmentType, Tokenizer, ToolpathFormat},
+    renderer::{adaptive_spacing, BoundingBox, ClipAxis, ClipPlane, DepthGradient, FeedGradient, LinePattern, Ray, Renderer, SegmentIndex, ToolpathColorMode, ViewPreset, ZFilter},
+    script::{CommandContext, PluginHost, ScriptApi, ScriptCommand, ScriptContext, ScriptLibrary, UserCommandLibrary, UserScript, MAX_OPERATIONS},
+    settings::{ConnectionType, Fixture, FixtureKind, JobRecord, NetworkProfile, JogSettings, PositionRole, Project, RapidRetract, Settings, SidecarMetadata, SpindleJogInterlock, ZLimitGuard, MAX_WORK_ZERO_SLOTS, MM_PER_INCH, PROJECT_EXTENSION},
+    state::{
+        offset_command, ActionKind, ActionLog, AppState, CoordinateSystem, ExecutionState, JobJournal, MachineState, MachineStatus, Position, ProbeLog, ProgramAnalysis,
+        RecoveryEngine, RecoveryStep, RecoveryTrigger, StateEvent, StateUpdater, ToolCheck, ToolLength,
+    },
+    ui::panels::{
+        ActionLogPanel, ActionLogRequest, AxisTestDialog, BacklashAction, BacklashWizard, CalculatorDialog, ChecklistAction, ChecklistDialog, CommandPalette, CompatibilityAction, CompatibilityPanel, DiagnosticsPanel, EdgeFinderDialog, ErrorPresenter, FixtureAction, FixturePanel, FlatnessPanel, FlatnessRequest, FormatterDialog, MaintenanceAction, MaintenancePanel, MultiPassDialog, OnboardingAction, OnboardingWizard, PaletteCommand, PaletteEntry, Pendant, PendantAction, PluginAction, PluginManager, ProbeLogPanel, ProbeLogRequest, ProjectAction,
+        ProjectDialog, RecoveryLogPanel, ReferencePanel, RunScreen, RunScreenAction, RunScreenStatus, StatisticsPanel, StatusChart, UserCommandsAction, UserCommandsPanel,
+    },
+    ui::documents::{self, Document, FileStamp},
+    ui::widgets::{readout, AccessibleLabel, Console, EditorMode, GCodeEditor, ProgressMap},
+    utils::{crash, Error, TaskFailure, TaskSupervisor},
 };
-use std::path::PathBuf;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
+use tokio::sync::broadcast;
 use tokio::sync::Mutex as TokioMutex;
 
+/// Interval between link metrics refreshes
+const METRICS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Frame interval while a feed override change is being ramped
+const FEED_RAMP_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Interval between checks for the auto-connect port coming and going
+const PORT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval between checks for the open file changing on disk
+const FILE_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval between writes of the job journal while a program runs
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Acknowledged lines that may still wait in GRBL's planner buffer
+const PLANNER_BLOCKS: usize = 16;
+
+/// Fallback repaint interval while idle; controller traffic repaints as it arrives
+const IDLE_REPAINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fallback repaint interval while idle in low-power mode
+const LOW_POWER_IDLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Result of a background connection attempt
+type ConnectOutcome = std::result::Result<Arc<TokioMutex<ConnectionManager>>, String>;
+
+/// How long the TX/RX indicators stay lit after activity
+const ACTIVITY_HOLD: Duration = Duration::from_millis(300);
+
+/// Keyboard shortcuts listed in the help window, as (keys, action)
+const KEYBOARD_SHORTCUTS: &[(&str, &str)] = &[
+    ("F5", "Run or resume the program"),
+    ("F6", "Pause the program (feed hold)"),
+    ("F7", "Stop the program"),
+    ("Ctrl+Shift+H", "Home the machine"),
+    ("Ctrl+Shift+U", "Unlock after an alarm"),
+    ("Arrows, Page Up/Down", "Jog X, Y and Z (keyboard jog on)"),
+    ("Shift / Ctrl + jog", "Jog ten times larger / smaller steps"),
+    ("Ctrl+O / Ctrl+S", "Open / save G-Code"),
+    ("Ctrl+F", "Find in the editor"),
+    ("Ctrl+B, F2, Shift+F2", "Toggle bookmark, next / previous bookmark"),
+    ("F9", "Toggle breakpoint"),
+    ("Ctrl+,", "Settings"),
+    ("Ctrl+P", "Command palette"),
+    ("Ctrl +/-, Ctrl+0", "Scale the layout"),
+    ("Tab / Shift+Tab", "Move between controls"),
+    ("Space / Enter", "Press the focused control"),
+    ("F1", "This list"),
+];
+
+/// A jog that the soft limits would shorten
+#[derive(Debug, Clone, Copy)]
+struct PendingJog {
+    /// Distance requested (X, Y, Z)
+    requested: [f64; 3],
+    /// Distance allowed by the limits
+    clamped: [f64; 3],
+}
+
+/// A tool breakage check that failed
+#[derive(Debug, Clone, Copy)]
+struct BrokenTool {
+    /// What started the check
+    check: ToolCheck,
+    /// Machine Z where the probe triggered, `None` if it made no contact
+    probe_z: Option<f64>,
+    /// Length deviation from the expected length (mm)
+    deviation: Option<f64>,
+}
+
+/// Predefined reference position stored by the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReferencePosition {
+    /// G28 home position
+    G28,
+    /// G30 position
+    G30,
+}
+
+impl ReferencePosition {
+    /// G-Code word naming the position
+    fn code(self) -> &'static str {
+        match self {
+            ReferencePosition::G28 => "G28",
+            ReferencePosition::G30 => "G30",
+        }
+    }
+}
+
 /// Main rCandle application state
 pub struct RCandleApp {
     /// Application settings
     settings: Settings,
+    /// Command-line options the application was launched with
+    launch: Cli,
+    /// Start the program once the machine is connected and idle
+    auto_run_pending: bool,
     /// Application state (machine, program, etc.)
     app_state: AppState,
+    /// Applies status reports to the shared state and broadcasts the changes
+    state_updater: StateUpdater,
+    /// State change events driving the cached machine view
+    state_events: broadcast::Receiver<StateEvent>,
+    /// Machine state snapshot for display, refreshed when state events arrive
+    machine_view: MachineState,
     /// Connection status display
     status_message: String,
     /// Currently loaded file path
@@ -34,16 +150,42 @@ pub struct RCandleApp {
     preprocessor: Preprocessor,
     /// G-Code editor widget
     gcode_editor: GCodeEditor,
+    /// Open documents, one per editor tab; the active one's state is held in the fields above
+    documents: Vec<Document>,
+    /// Whether the active document is the clipboard scratch buffer
+    scratch_buffer: bool,
+    /// State of the active document's file when it was loaded or saved
+    file_stamp: Option<FileStamp>,
+    /// When the active document's file was last checked for changes
+    last_file_check: Option<std::time::Instant>,
+    /// Modification time of a change on disk waiting for the user to reload or keep their edits
+    pending_reload: Option<std::time::SystemTime>,
+    /// Document shown in the editor and viewer
+    active_document: usize,
+    /// Document that runs when the program is started
+    armed_document: usize,
     /// Console widget
     console: Console,
     /// Show console panel
     show_console: bool,
     /// 3D renderer (optional until WGPU is initialized)
     renderer: Option<Renderer>,
+    /// Reduced-GPU mode: no 3D renderer or MSAA and a slower idle refresh
+    low_power: bool,
     /// Parsed segments for rendering
     segments: Vec<Segment>,
-    /// Jog step size (in mm or inches depending on units)
-    jog_step_size: f64,
+    /// Spatial index of the segments for picking
+    segment_index: SegmentIndex,
+    /// Segment selected in the viewer
+    selected_segment: Option<usize>,
+    /// Z levels of the horizontal cuts, from top to bottom
+    z_levels: Vec<f64>,
+    /// Z level shown on its own, as an index into `z_levels`
+    z_filter_level: Option<usize>,
+    /// Plane hiding the toolpath on one side, for a section view
+    clip_plane: Option<ClipPlane>,
+    /// Axes locked against jogging (X, Y, Z)
+    jog_axis_locks: [bool; 3],
     /// Spindle speed (RPM)
     spindle_speed: f64,
     /// Feed rate override (percentage, 0-200)
@@ -67,15 +209,41 @@ pub struct RCandleApp {
     /// Connection manager (wrapped in Arc<TokioMutex> for async access)
     connection_manager: Option<Arc<TokioMutex<ConnectionManager>>>,
     /// Pending connection manager (set by async connection task)
-    pending_connection_manager: Option<Arc<TokioMutex<Option<Arc<TokioMutex<ConnectionManager>>>>>>,
+    pending_connection_manager: Option<Arc<TokioMutex<Option<ConnectOutcome>>>>,
+    /// Window geometry differs from the saved settings
+    window_geometry_changed: bool,
+    /// Whether the restored window position has been checked against the monitors
+    window_checked: bool,
+    /// Whether the window is currently kept above others
+    window_on_top: bool,
+    /// Startup auto-connect with retry and replug detection
+    auto_connect: AutoConnect,
+    /// When the auto-connect port was last looked for
+    last_port_check: Option<std::time::Instant>,
     /// Command queue for GRBL
     _command_queue: Arc<TokioMutex<CommandQueue>>,
     /// Selected serial port for connection
     selected_port: String,
     /// Available serial ports
     available_ports: Vec<String>,
+    /// Bluetooth devices found by the last discovery
+    bluetooth_devices: Vec<BluetoothDevice>,
+    /// Running Bluetooth discovery
+    bluetooth_task: Option<tokio::task::JoinHandle<Vec<BluetoothDevice>>>,
+    /// GRBL controllers found by the last port scan
+    detected_devices: Vec<DetectedDevice>,
+    /// Running port scan
+    scan_task: Option<tokio::task::JoinHandle<Vec<DetectedDevice>>>,
+    /// Simulated device while connected to the simulator
+    simulator_device: Option<Arc<std::sync::Mutex<MockDevice>>>,
+    /// Network controllers found by the last mDNS discovery
+    network_devices: Vec<NetworkDevice>,
+    /// Running mDNS discovery
+    discovery_task: Option<tokio::task::JoinHandle<crate::utils::Result<Vec<NetworkDevice>>>>,
     /// Show settings dialog
     show_settings_dialog: bool,
+    /// Show the keyboard shortcut list
+    show_shortcuts: bool,
     /// Temporary settings being edited (None when dialog is closed)
     temp_settings: Option<Settings>,
     /// Script library for user scripts
@@ -88,8 +256,10 @@ pub struct RCandleApp {
     editing_script: Option<UserScript>,
     /// Show user commands panel
     show_user_commands: bool,
-    /// Previous feed override value (for change detection)
-    prev_feed_override: f64,
+    /// User command buttons and their layout editor
+    user_commands_panel: UserCommandsPanel,
+    /// Feed override sent so far and the value being ramped to
+    feed_ramp: OverrideRamp,
     /// Previous rapid override value (for change detection)
     prev_rapid_override: f64,
     /// Previous spindle override value (for change detection)
@@ -98,19 +268,179 @@ pub struct RCandleApp {
     response_receiver: Option<tokio::sync::broadcast::Receiver<GrblResponse>>,
     /// Status receiver for GRBL status updates
     status_receiver: Option<tokio::sync::broadcast::Receiver<crate::grbl::GrblStatus>>,
+    /// Event receiver for connection events
+    event_receiver: Option<tokio::sync::broadcast::Receiver<ConnectionEvent>>,
+    /// Reports of panicked connection background tasks
+    task_failure_receiver: Option<tokio::sync::broadcast::Receiver<TaskFailure>>,
+    /// Warning banner shown while the link is unhealthy
+    link_warning: Option<String>,
+    /// Whether the watchdog paused a running program
+    link_paused_program: bool,
+    /// Whether the connection manager was last told a program is streaming
+    job_active_reported: bool,
+    /// Multi-pass depth dialog
+    multipass_dialog: MultiPassDialog,
+    /// G-Code renumber and reformat dialog
+    formatter_dialog: FormatterDialog,
+    /// Program statistics panel
+    statistics_panel: StatisticsPanel,
+    /// Words of the program GRBL does not support
+    compatibility_panel: CompatibilityPanel,
+    /// Fixture library
+    fixture_panel: FixturePanel,
+    /// Pre-run checklist confirmed before a program starts
+    checklist_dialog: ChecklistDialog,
+    /// Feeds, speeds and job cost calculator
+    calculator_dialog: CalculatorDialog,
+    /// Full-window run screen
+    run_screen: RunScreen,
+    /// Touch-screen pendant layout
+    pendant: Pendant,
+    /// Open project
+    project_dialog: ProjectDialog,
+    /// Machine-affecting actions issued from the UI
+    action_log: ActionLog,
+    /// Jog held back by the spindle interlock, awaiting confirmation
+    pending_spindle_jog: Option<[f64; 3]>,
+    /// Jog held back by the soft limits, awaiting confirmation
+    pending_jog: Option<PendingJog>,
+    /// Action log panel
+    action_log_panel: ActionLogPanel,
+    /// Applies the machine profile's alarm recovery policies
+    recovery_engine: RecoveryEngine,
+    /// Recovery log window
+    recovery_log_panel: RecoveryLogPanel,
+    /// Results of probe cycles
+    probe_log: ProbeLog,
+    /// Reference tool and applied tool length offset
+    tool_length: ToolLength,
+    /// Whether the next probe result measures a tool on the probe plate
+    awaiting_tool_measurement: bool,
+    /// Tool being measured before the program continues past its M6
+    measuring_tool: Option<u32>,
+    /// Tool breakage check waiting for its probe result
+    checking_tool: Option<ToolCheck>,
+    /// Failed tool breakage check waiting for the operator
+    broken_tool: Option<BrokenTool>,
+    /// When the tool was last checked or measured during the running program
+    last_tool_check: Option<std::time::Instant>,
+    /// Stream the next program as a dry run above the stock
+    dry_run: bool,
+    /// Heightmap programs are auto-leveled to, and its name
+    leveling: Option<(String, Heightmap)>,
+    /// Auto-leveled moves below the lowest Z the running program holds before, next first
+    z_limit_holds: Vec<ZLimitViolation>,
+    /// Marker comments of the running program, by line
+    comment_pauses: BTreeMap<usize, String>,
+    /// Marker comment the program is paused at
+    comment_prompt: Option<(usize, String)>,
+    /// Tool in the spindle, from the last tool change (0 before any)
+    active_tool: u32,
+    /// When cutting time was last added to the active tool
+    last_wear_tick: Option<std::time::Instant>,
+    /// Feed override reduction currently applied for tool wear (percent points)
+    wear_reduction: f64,
+    /// When the runtime counters were last updated, and the machine position then
+    last_runtime_tick: Option<(std::time::Instant, [f64; 3])>,
+    /// Number of maintenance reminders due when last checked
+    maintenance_due: usize,
+    /// Maintenance counters and reminders
+    maintenance_panel: MaintenancePanel,
+    /// Top view of the running program for the status bar and run screen
+    progress_map: Option<ProgressMap>,
+    /// Reset the cutting time of the incoming tool at the tool change prompt
+    reset_tool_wear: bool,
+    /// Probe log panel
+    probe_log_panel: ProbeLogPanel,
+    /// Edge finder dialog
+    edge_finder: EdgeFinderDialog,
+    /// Backlash measurement wizard
+    backlash_wizard: BacklashWizard,
+    /// Single-axis test motion
+    axis_test: AxisTestDialog,
+    /// Fuzzy search over the application's commands
+    command_palette: CommandPalette,
+    /// First-run setup wizard
+    onboarding: OnboardingWizard,
+    /// Offline GRBL reference
+    reference_panel: ReferencePanel,
+    /// Modification time of the settings file when it was last read
+    settings_modified: Option<std::time::SystemTime>,
+    /// When the settings file was last checked for changes
+    last_settings_check: Option<std::time::Instant>,
+    /// Settings changed on disk that need a restart
+    restart_needed: Vec<&'static str>,
+    /// Journal of the running job and when it was last written
+    job_journal: Option<(JobJournal, std::time::Instant)>,
+    /// Journal left by a run that did not end, offered for resuming
+    interrupted_job: Option<JobJournal>,
+    /// Interrupted job the next program start resumes
+    resume_job: Option<JobJournal>,
+    /// Crash report left by the last run, not yet shown
+    crash_report: Option<PathBuf>,
+    /// Plugins from the plugins folder
+    plugins: PluginHost,
+    /// Commands sent by plugins and scripts, run on the UI thread
+    plugin_commands: tokio::sync::mpsc::UnboundedReceiver<ScriptCommand>,
+    /// Script API shared by plugins and scripts run from the editor
+    script_api: Arc<ScriptApi>,
+    /// Background tasks owned by the app, such as running scripts
+    tasks: TaskSupervisor,
+    /// Reports of panicked app background tasks
+    app_task_failures: broadcast::Receiver<TaskFailure>,
+    /// Plugin manager window
+    plugin_manager: PluginManager,
+    /// Flatness and tram check report
+    flatness_panel: FlatnessPanel,
+    /// Edge being probed, set from the next probe result
+    probing_edge: Option<StockEdge>,
+    /// Streamer for the running program
+    streamer: Option<ProgramStreamer>,
+    /// Task sending the current batch of program lines
+    stream_task: Option<tokio::task::JoinHandle<()>>,
+    /// Last commanded state of the grblHAL user outputs
+    user_outputs: Vec<bool>,
+    /// Reference position awaiting confirmation before it is overwritten
+    pending_reference: Option<ReferencePosition>,
+    /// Name for the next saved work zero slot
+    zero_slot_name: String,
+    /// User command awaiting confirmation before it runs
+    pending_user_command: Option<String>,
+    /// Tool the running program is waiting on at an M6
+    tool_change: Option<u32>,
+    /// Replies still expected for commands sent during a tool change
+    tool_change_replies: usize,
+    /// Startup commands are sent on the next welcome message
+    startup_pending: bool,
+    /// Startup commands still waiting for a reply
+    startup_replies: VecDeque<String>,
+    /// Diagnostics panel
+    diagnostics_panel: DiagnosticsPanel,
+    /// Chart of feed, spindle, overrides and planner fill over time
+    status_chart: StatusChart,
+    /// Error toasts and details dialog
+    error_presenter: ErrorPresenter,
+    /// When link metrics were last refreshed
+    last_metrics_poll: Option<std::time::Instant>,
+    /// TX/RX activity and buffer fill for the status bar
+    link_activity: LinkActivity,
+    /// Description of the current link (port and baud rate)
+    link_description: Option<String>,
 }
 
 impl RCandleApp {
     /// Create a new rCandle application instance
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, launch: Cli) -> Self {
         // Load settings first
-        let settings = Settings::load_or_default();
+        let settings = launch.load_settings();
+        crash::record_settings(&settings);
         
         // Apply theme from settings
         Self::apply_theme(&cc.egui_ctx, settings.ui.dark_mode);
         
         // Apply font size from settings
         Self::apply_font_size(&cc.egui_ctx, settings.ui.font_size);
+        cc.egui_ctx.set_zoom_factor(settings.ui.ui_scale);
         
         // Configure egui style for better interactivity
         let mut style = (*cc.egui_ctx.style()).clone();
@@ -119,23 +449,37 @@ impl RCandleApp {
         
         // Initialize application state
         let app_state = AppState::new();
+        let state_updater = StateUpdater::new(app_state.clone(), app_state.events.clone());
+        let state_events = app_state.events.subscribe();
+        Self::spawn_repaint_listener(&cc.egui_ctx, &app_state);
+        let (plugin_tx, plugin_commands) = tokio::sync::mpsc::unbounded_channel();
+        let script_api = Arc::new(ScriptApi::new(app_state.clone(), plugin_tx));
+        let plugins = PluginHost::new(script_api.clone());
+        let tasks = TaskSupervisor::new();
+        let app_task_failures = tasks.subscribe_failures();
         
         // Create parser and preprocessor
-        let parser = Parser::new();
-        let preprocessor = Preprocessor::new();
+        let parser = Self::build_parser(&settings);
+        let preprocessor = Self::build_preprocessor(&settings);
         
         // Create G-Code editor
         let gcode_editor = GCodeEditor::new();
         
         // Create console
         let mut console = Console::new();
+        console.set_max_messages(settings.ui.console_history_limit);
         console.info("rCandle initialized".to_string());
         console.info("Ready to connect to GRBL device".to_string());
         
-        // Initialize WGPU renderer
-        let renderer = Self::init_renderer(cc);
+        // Initialize WGPU renderer, unless the GPU is left alone in low-power mode
+        let gpu = Self::detect_gpu(cc);
+        let low_power = settings.visualization.low_power_active(gpu.as_ref().is_some_and(|(_, weak)| *weak));
+        let renderer = if low_power { None } else { Self::init_renderer(cc, &settings) };
         
-        if renderer.is_some() {
+        if low_power {
+            let gpu_name = gpu.map(|(name, _)| format!(" ({})", name)).unwrap_or_default();
+            console.info(format!("Low-power rendering{}: 2D view only, no MSAA", gpu_name));
+        } else if renderer.is_some() {
             console.info("3D renderer initialized".to_string());
         } else {
             console.warning("Failed to initialize 3D renderer".to_string());
@@ -152,20 +496,40 @@ impl RCandleApp {
             .map(|ports| ports.iter().map(|p| p.port_name.clone()).collect())
             .unwrap_or_else(Vec::new);
         
-        Self {
+        let run_screen_on_top = settings.ui.run_screen_on_top;
+        let pendant_mode = settings.ui.pendant_mode;
+        let mut app = Self {
             settings,
+            launch,
+            auto_run_pending: false,
             app_state,
+            state_updater,
+            state_events,
+            machine_view: MachineState::default(),
             status_message: "Ready".to_string(),
             current_file: None,
             gcode_content: String::new(),
             parser,
             preprocessor,
             gcode_editor,
+            documents: vec![Document::default()],
+            scratch_buffer: false,
+            file_stamp: None,
+            last_file_check: None,
+            pending_reload: None,
+            active_document: 0,
+            armed_document: 0,
             console,
             show_console: true,
             renderer,
+            low_power,
             segments: Vec::new(),
-            jog_step_size: 1.0,
+            segment_index: SegmentIndex::default(),
+            selected_segment: None,
+            z_levels: Vec::new(),
+            z_filter_level: None,
+            clip_plane: None,
+            jog_axis_locks: [false; 3],
             spindle_speed: 1000.0,
             feed_override: 100.0,
             rapid_override: 100.0,
@@ -178,26 +542,230 @@ impl RCandleApp {
             current_line: 0,
             connection_manager: None,
             pending_connection_manager: None,
+            window_geometry_changed: false,
+            window_checked: false,
+            window_on_top: false,
+            auto_connect: AutoConnect::default(),
+            last_port_check: None,
             _command_queue: command_queue,
             selected_port: available_ports.first().cloned().unwrap_or_default(),
             available_ports,
+            bluetooth_devices: Vec::new(),
+            bluetooth_task: None,
+            detected_devices: Vec::new(),
+            scan_task: None,
+            simulator_device: None,
+            network_devices: Vec::new(),
+            discovery_task: None,
             show_settings_dialog: false,
+            show_shortcuts: false,
             temp_settings: None,
             script_library: ScriptLibrary::new(),
-            user_command_library: UserCommandLibrary::default(),
+            user_command_library: UserCommandLibrary::load_or_default(),
             show_script_editor: false,
             editing_script: None,
             show_user_commands: true,
-            prev_feed_override: 100.0,
+            user_commands_panel: UserCommandsPanel::default(),
+            feed_ramp: OverrideRamp::new(100.0),
             prev_rapid_override: 100.0,
             prev_spindle_override: 100.0,
             response_receiver: None,
             status_receiver: None,
+            event_receiver: None,
+            task_failure_receiver: None,
+            link_warning: None,
+            link_paused_program: false,
+            job_active_reported: false,
+            multipass_dialog: MultiPassDialog::default(),
+            formatter_dialog: FormatterDialog::default(),
+            statistics_panel: StatisticsPanel::default(),
+            compatibility_panel: CompatibilityPanel::default(),
+            fixture_panel: FixturePanel::default(),
+            checklist_dialog: ChecklistDialog::default(),
+            calculator_dialog: CalculatorDialog::default(),
+            pendant: Pendant { open: pendant_mode, low_power },
+            run_screen: RunScreen {
+                always_on_top: run_screen_on_top,
+                low_power,
+                ..Default::default()
+            },
+            project_dialog: ProjectDialog::default(),
+            action_log: ActionLog::default(),
+            pending_jog: None,
+            pending_spindle_jog: None,
+            probe_log: ProbeLog::default(),
+            tool_length: ToolLength::default(),
+            awaiting_tool_measurement: false,
+            measuring_tool: None,
+            checking_tool: None,
+            broken_tool: None,
+            last_tool_check: None,
+            dry_run: false,
+            leveling: None,
+            z_limit_holds: Vec::new(),
+            comment_pauses: BTreeMap::new(),
+            comment_prompt: None,
+            active_tool: 0,
+            last_wear_tick: None,
+            last_runtime_tick: None,
+            maintenance_due: 0,
+            maintenance_panel: MaintenancePanel::default(),
+            progress_map: None,
+            wear_reduction: 0.0,
+            reset_tool_wear: false,
+            probe_log_panel: ProbeLogPanel::default(),
+            edge_finder: EdgeFinderDialog::default(),
+            backlash_wizard: BacklashWizard::default(),
+            axis_test: AxisTestDialog::default(),
+            command_palette: CommandPalette::default(),
+            onboarding: OnboardingWizard::default(),
+            reference_panel: ReferencePanel::default(),
+            job_journal: None,
+            settings_modified: launch.settings_path().and_then(|path| documents::modified_time(&path)),
+            last_settings_check: None,
+            restart_needed: Vec::new(),
+            interrupted_job: JobJournal::load_default(),
+            resume_job: None,
+            crash_report: crash::take_new_report(),
+            plugins,
+            plugin_commands,
+            script_api,
+            tasks,
+            app_task_failures,
+            plugin_manager: PluginManager::default(),
+            flatness_panel: FlatnessPanel::default(),
+            probing_edge: None,
+            user_outputs: Vec::new(),
+            pending_reference: None,
+            zero_slot_name: String::new(),
+            pending_user_command: None,
+            tool_change: None,
+            tool_change_replies: 0,
+            startup_pending: false,
+            startup_replies: VecDeque::new(),
+            action_log_panel: ActionLogPanel::default(),
+            recovery_engine: RecoveryEngine::new(settings.machine.recovery.clone()),
+            recovery_log_panel: RecoveryLogPanel::default(),
+            streamer: None,
+            stream_task: None,
+            diagnostics_panel: DiagnosticsPanel::default(),
+            status_chart: StatusChart::default(),
+            error_presenter: ErrorPresenter::new(),
+            last_metrics_poll: None,
+            link_activity: LinkActivity::new(),
+            link_description: None,
+        };
+        app.reload_plugins();
+        app.apply_launch_options(&cc.egui_ctx);
+        app
+    }
+
+    /// Open the file, select the port and connect as asked on the command line
+    fn apply_launch_options(&mut self, ctx: &egui::Context) {
+        if let Some(path) = self.launch.file.clone() {
+            self.load_file(path);
+        }
+        let auto_connect = self.settings.connection.auto_connect && !self.launch.should_connect();
+        if auto_connect && !self.settings.connection.port_name.is_empty() {
+            self.selected_port = self.settings.connection.port_name.clone();
+        }
+        if let Some(port) = self.launch.port.clone() {
+            self.selected_port = port;
+        }
+        if self.launch.should_connect() {
+            self.connect_to_grbl(ctx);
+        } else if auto_connect {
+            self.auto_connect.start(std::time::Instant::now());
+        }
+        self.auto_run_pending = self.launch.run;
+        if !self.settings.general.onboarding_done && !self.launch.should_connect() {
+            self.onboarding.start(&self.settings, &self.selected_port);
+        }
+    }
+
+    /// Record window geometry and layout scale, rescue a window left on a
+    /// missing monitor and apply the run screen's always-on-top setting
+    fn track_window(&mut self, ctx: &egui::Context) {
+        let (inner, outer, maximized, fullscreen, monitor) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.inner_rect, viewport.outer_rect, viewport.maximized, viewport.fullscreen, viewport.monitor_size)
+        });
+        
+        // A restored position on a monitor that has since been disconnected
+        // leaves the window on no monitor at all; bring it back into view
+        if !self.window_checked && outer.is_some() {
+            self.window_checked = true;
+            if monitor.is_none() && self.settings.ui.window_position.is_some() {
+                tracing::warn!("Window is not on any monitor, moving it back into view");
+                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(40.0, 40.0)));
+                self.settings.ui.window_position = None;
+                self.window_geometry_changed = true;
+            }
+        }
+        
+        if let Some(inner) = inner {
+            if !fullscreen.unwrap_or(false) {
+                let position = outer.map(|rect| [rect.min.x, rect.min.y]);
+                let size = [inner.width(), inner.height()];
+                if self.settings.ui.record_window(position, size, maximized.unwrap_or(false)) {
+                    self.window_geometry_changed = true;
+                }
+            }
+        }
+        
+        // Keep the layout scale changed with Ctrl +/- for the next start
+        let zoom = ctx.zoom_factor();
+        if (zoom - self.settings.ui.ui_scale).abs() > 0.001 {
+            self.settings.ui.ui_scale = zoom;
+            self.window_geometry_changed = true;
+        }
+        
+        let on_top = self.run_screen.open && self.run_screen.always_on_top;
+        if on_top != self.window_on_top {
+            self.window_on_top = on_top;
+            let level = if on_top { egui::WindowLevel::AlwaysOnTop } else { egui::WindowLevel::Normal };
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        }
+    }
+    
+    /// Start the program requested with `--run` once the machine is ready
+    fn poll_auto_run(&mut self) {
+        if !self.auto_run_pending || self.connection_manager.is_none() {
+            return;
+        }
+        match self.machine_view.status {
+            MachineStatus::Idle => {
+                self.auto_run_pending = false;
+                self.console.info("Starting program (--run)".to_string());
+                // The pre-run checklist still applies to jobs started from the command line
+                self.request_program_start();
+            }
+            MachineStatus::Alarm => {
+                self.auto_run_pending = false;
+                self.console.warning("Not starting program: machine is in alarm".to_string());
+            }
+            _ => {}
         }
     }
 
+    /// Name of the GPU and whether it looks too weak for the 3D renderer
+    fn detect_gpu(cc: &eframe::CreationContext<'_>) -> Option<(String, bool)> {
+        if let Some(wgpu_render_state) = cc.wgpu_render_state.as_ref() {
+            let info = wgpu_render_state.adapter.get_info();
+            let weak = info.device_type == wgpu::DeviceType::Cpu || crate::renderer::is_weak_gpu(&info.name);
+            return Some((info.name, weak));
+        }
+        
+        use eframe::glow::HasContext;
+        let gl = cc.gl.as_ref()?;
+        // SAFETY: the context is current while the app is being created
+        let name = unsafe { gl.get_parameter_string(eframe::glow::RENDERER) };
+        let weak = crate::renderer::is_weak_gpu(&name);
+        Some((name, weak))
+    }
+
     /// Initialize WGPU renderer
-    fn init_renderer(cc: &eframe::CreationContext<'_>) -> Option<Renderer> {
+    fn init_renderer(cc: &eframe::CreationContext<'_>, settings: &Settings) -> Option<Renderer> {
         // Get WGPU render state from eframe
         let wgpu_render_state = cc.wgpu_render_state.as_ref()?;
         
@@ -205,7 +773,21 @@ impl RCandleApp {
         let queue = wgpu_render_state.queue.clone();
         let target_format = wgpu_render_state.target_format;
         
-        Some(Renderer::new(device, queue, target_format))
+        let mut renderer = Renderer::new(&wgpu_render_state.adapter, device, queue, target_format);
+        renderer.apply_visualization(&settings.visualization);
+        Some(renderer)
+    }
+
+    /// Build a preprocessor configured from the processing settings
+    fn build_preprocessor(settings: &Settings) -> Preprocessor {
+        Preprocessor::new().with_plunge_entry(settings.processing.plunge_entry())
+    }
+
+    /// Build the G-Code parser from the processing and lathe settings
+    fn build_parser(settings: &Settings) -> Parser {
+        Parser::new()
+            .with_skip_block_delete(settings.processing.skip_block_delete)
+            .with_diameter_mode(settings.machine.diameter_mode())
     }
 
     /// Open a G-Code file
@@ -215,1195 +797,6135 @@ impl RCandleApp {
             .add_filter("All Files", &["*"])
             .pick_file()
         {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => {
-                    self.gcode_content = content;
-                    self.current_file = Some(path.clone());
-                    self.status_message = format!("Loaded: {}", path.display());
-                    self.console.info(format!("Loaded file: {}", path.display()));
-                    tracing::info!("Loaded G-Code file: {:?}", path);
-                    
-                    // Parse the G-Code
-                    self.parse_gcode();
+            self.load_file(path);
+        }
+    }
+
+    /// Load a G-Code file into a new editor tab, reusing an empty one
+    fn load_file(&mut self, path: PathBuf) {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                self.resume_job = None;
+                if self.current_file.is_some() || !self.gcode_content.is_empty() {
+                    self.new_document();
                 }
-                Err(e) => {
-                    self.status_message = format!("Error loading file: {}", e);
-                    self.console.error(format!("Failed to load file: {}", e));
-                    tracing::error!("Failed to load file {:?}: {}", path, e);
+                if self.armed_content().is_empty() && !self.program_in_progress() {
+                    self.armed_document = self.active_document;
                 }
+                let content = self.post_process(content);
+                self.file_stamp = Some(FileStamp::new(&path, &content));
+                self.gcode_content = content;
+                self.gcode_editor.set_bookmarks(SidecarMetadata::load_for(&path).bookmarks);
+                self.current_file = Some(path.clone());
+                self.status_message = format!("Loaded: {}", path.display());
+                self.console.info(format!("Loaded file: {}", path.display()));
+                tracing::info!("Loaded G-Code file: {:?}", path);
+                
+                // Parse the G-Code
+                self.parse_gcode();
+            }
+            Err(e) => {
+                self.status_message = format!("Error loading file: {}", e);
+                tracing::error!("Failed to load file {:?}: {}", path, e);
+                self.report_error(Error::from(e).with_context("Failed to load file"));
             }
         }
     }
 
-    /// Save the current G-Code to a file
-    fn save_file(&mut self) {
-        if let Some(path) = &self.current_file {
-            if let Err(e) = std::fs::write(path, &self.gcode_content) {
-                self.status_message = format!("Error saving file: {}", e);
-                self.console.error(format!("Failed to save file: {}", e));
-                tracing::error!("Failed to save file {:?}: {}", path, e);
-            } else {
-                self.status_message = format!("Saved: {}", path.display());
-                self.console.info(format!("Saved file: {}", path.display()));
-                tracing::info!("Saved G-Code file: {:?}", path);
-            }
-        } else {
-            self.save_file_as();
+    /// Check whether the active document's file changed on disk
+    ///
+    /// A document without edits is reloaded right away; one with edits waits
+    /// for the user to choose. A running program's file is left alone until
+    /// the run ends.
+    fn poll_file_changes(&mut self) {
+        let now = std::time::Instant::now();
+        if self.pending_reload.is_some()
+            || self.last_file_check.is_some_and(|at| now.duration_since(at) < FILE_WATCH_INTERVAL)
+        {
+            return;
         }
-    }
+        self.last_file_check = Some(now);
 
-    /// Save the current G-Code to a new file
-    fn save_file_as(&mut self) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("G-Code", &["gcode", "nc", "ngc"])
-            .save_file()
+        let (Some(path), Some(stamp)) = (&self.current_file, self.file_stamp) else {
+            return;
+        };
+        // A file that was removed or can't be read keeps the program as it is
+        let Some(modified) = documents::modified_time(path) else {
+            return;
+        };
+        if stamp.modified == Some(modified)
+            || (self.active_document == self.armed_document && self.program_in_progress())
         {
-            if let Err(e) = std::fs::write(&path, &self.gcode_content) {
-                self.status_message = format!("Error saving file: {}", e);
-                self.console.error(format!("Failed to save file: {}", e));
-                tracing::error!("Failed to save file {:?}: {}", path, e);
-            } else {
-                self.current_file = Some(path.clone());
-                self.status_message = format!("Saved: {}", path.display());
-                self.console.info(format!("Saved file: {}", path.display()));
-                tracing::info!("Saved G-Code file: {:?}", path);
-            }
+            return;
+        }
+
+        if stamp.matches(&self.gcode_content) {
+            self.reload_file();
+        } else {
+            self.pending_reload = Some(modified);
         }
     }
 
-    /// Parse the current G-Code content
-    fn parse_gcode(&mut self) {
-        self.console.info("Parsing G-Code...".to_string());
-        
-        // Tokenize
-        let mut tokenizer = Tokenizer::new(&self.gcode_content);
-        let tokens = match tokenizer.tokenize() {
-            Ok(t) => t,
-            Err(e) => {
-                self.status_message = format!("Tokenization error: {}", e);
-                self.console.error(format!("Tokenization failed: {}", e));
-                tracing::error!("Failed to tokenize G-Code: {}", e);
-                return;
-            }
+    /// Reload the active document from its file, keeping the view and editor state
+    fn reload_file(&mut self) {
+        self.pending_reload = None;
+        let Some(path) = self.current_file.clone() else {
+            return;
         };
-        
-        self.console.debug(format!("Tokenized {} tokens", tokens.len()));
-
-        // Parse tokens to commands
-        let commands = match self.parser.parse_tokens(&tokens) {
-            Ok(c) => c,
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let content = self.post_process(content);
+                self.file_stamp = Some(FileStamp::new(&path, &content));
+                self.gcode_content = content;
+                self.status_message = format!("Reloaded: {}", path.display());
+                self.console.info(format!("Reloaded file changed on disk: {}", path.display()));
+                tracing::info!("Reloaded G-Code file: {:?}", path);
+                self.parse_gcode();
+            }
             Err(e) => {
-                self.status_message = format!("Parse error: {}", e);
-                self.console.error(format!("Parse failed: {}", e));
-                tracing::error!("Failed to parse G-Code: {}", e);
-                return;
+                tracing::error!("Failed to reload file {:?}: {}", path, e);
+                self.report_error(Error::from(e).with_context("Failed to reload file"));
             }
-        };
-        
-        self.console.debug(format!("Parsed {} commands", commands.len()));
+        }
+    }
 
-        // Generate segments
-        let segments = match self.parser.generate_segments(&commands) {
-            Ok(s) => s,
+    /// Run a loaded file through the enabled post-processor plugins
+    ///
+    /// The file is stamped with the processed text, so it only counts as
+    /// edited once changed in the editor. A failing plugin leaves the file
+    /// as it was read.
+    fn post_process(&mut self, content: String) -> String {
+        match self.plugins.post_process(&content) {
+            Ok((processed, applied)) => {
+                if !applied.is_empty() {
+                    self.console.info(format!("Post-processed by {}", applied.join(", ")));
+                }
+                processed
+            }
             Err(e) => {
-                self.status_message = format!("Segment generation error: {}", e);
-                self.console.error(format!("Segment generation failed: {}", e));
-                tracing::error!("Failed to generate segments: {}", e);
-                return;
+                self.report_error(e.with_context("Post-processing failed; the file was loaded unchanged"));
+                content
+            }
+        }
+    }
+    
+    /// Load the plugins folder, enabling the plugins enabled in the settings
+    fn reload_plugins(&mut self) {
+        let loaded = PluginHost::default_dir()
+            .and_then(|dir| self.plugins.load_dir(&dir, &self.settings.general.enabled_plugins));
+        if let Err(e) = loaded {
+            self.report_error(e.with_context("Failed to load plugins"));
+        }
+        for plugin in self.plugins.plugins().iter().filter(|plugin| plugin.enabled) {
+            match &plugin.error {
+                Some(error) => self.console.error(format!("Plugin {} failed to load: {}", plugin.manifest.name, error)),
+                None => self.console.info(format!("Plugin loaded: {}", plugin.manifest.name)),
             }
+        }
+    }
+    
+    /// Run the commands plugins sent since the last frame
+    fn poll_plugin_commands(&mut self) {
+        while let Ok(command) = self.plugin_commands.try_recv() {
+            match command {
+                ScriptCommand::SendCommand(line) => {
+                    self.send_command_sequence(vec![line]);
+                }
+                ScriptCommand::Jog { axis, distance } => match axis.to_uppercase().as_str() {
+                    "X" => self.send_jog_command(distance, 0.0, 0.0),
+                    "Y" => self.send_jog_command(0.0, distance, 0.0),
+                    "Z" => self.send_jog_command(0.0, 0.0, distance),
+                    _ => self.console.error(format!("Plugin jog on unknown axis: {}", axis)),
+                },
+                ScriptCommand::Home => self.send_home_command(),
+                ScriptCommand::ZeroAxis(axis) => match axis.to_uppercase().chars().next() {
+                    Some(axis @ ('X' | 'Y' | 'Z')) => self.send_zero_axis(axis),
+                    _ => self.console.error(format!("Plugin zeroed unknown axis: {}", axis)),
+                },
+                ScriptCommand::StartProgram => self.request_program_start(),
+                ScriptCommand::PauseProgram => self.pause_program(),
+                ScriptCommand::StopProgram => self.stop_program(),
+                ScriptCommand::Log(message) => self.console.info(message),
+            }
+        }
+    }
+    
+    /// Ask whether to reload a file that changed on disk while it has edits
+    fn show_reload_prompt(&mut self, ctx: &egui::Context) {
+        let Some(modified) = self.pending_reload else {
+            return;
         };
+        let name = self.document_title(self.active_document);
+        let mut reload = false;
+        let mut keep = false;
 
-        let segment_count = segments.len();
-        self.console.info(format!("Generated {} segments", segment_count));
-        tracing::info!("Parsed {} segments", segment_count);
-        
-        // Apply preprocessing
-        let processed = match self.preprocessor.process(&segments) {
-            Ok(p) => p,
-            Err(e) => {
-                self.status_message = format!("Preprocessing error: {}", e);
-                self.console.error(format!("Preprocessing failed: {}", e));
-                tracing::error!("Failed to preprocess segments: {}", e);
-                return;
+        egui::Window::new("📄 File Changed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} was changed on disk.", name));
+                ui.label("The editor has changes that reloading will discard.");
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("🔄 Reload").clicked() {
+                        reload = true;
+                    }
+                    if ui.button("✏ Keep Editor Version").clicked() {
+                        keep = true;
+                    }
+                });
+            });
+
+        if reload {
+            self.reload_file();
+        } else if keep {
+            // Ask again only if the file changes once more
+            if let Some(stamp) = self.file_stamp.as_mut() {
+                stamp.modified = Some(modified);
             }
-        };
-        
-        let processed_count = processed.len();
-        self.console.info(format!("Preprocessed to {} segments", processed_count));
-        tracing::info!("Preprocessed to {} segments", processed_count);
-        
-        // Store segments for rendering
-        self.segments = processed.clone();
-        
-        // Update renderer with new toolpath
-        if let Some(ref mut renderer) = self.renderer {
-            renderer.set_segments(processed);
-            self.console.info("3D view updated with toolpath".to_string());
+            self.pending_reload = None;
         }
-        
-        // Update program state with the parsed data
-        let mut program = self.app_state.program.write();
-        program.total_lines = self.gcode_content.lines().count();
-        
-        self.status_message = format!(
-            "Parsed {} segments ({} after preprocessing)",
-            segment_count, processed_count
-        );
-        self.console.info("G-Code parsing complete".to_string());
     }
 
-    /// Refresh list of available serial ports
-    fn refresh_ports(&mut self) {
-        self.available_ports = SerialConnection::list_ports()
-            .ok()
-            .map(|ports| ports.iter().map(|p| p.port_name.clone()).collect())
-            .unwrap_or_else(Vec::new);
-        
-        if !self.available_ports.is_empty() && !self.available_ports.contains(&self.selected_port) {
-            self.selected_port = self.available_ports[0].clone();
+    /// Whether the armed program is running or paused
+    fn program_in_progress(&self) -> bool {
+        matches!(self.app_state.program.read().state, ExecutionState::Running | ExecutionState::Paused)
+    }
+
+    /// Move the active document's state into its tab slot
+    fn park_active_document(&mut self) {
+        let document = &mut self.documents[self.active_document];
+        document.path = self.current_file.take();
+        document.stamp = self.file_stamp.take();
+        self.pending_reload = None;
+        document.content = std::mem::take(&mut self.gcode_content);
+        document.scratch = std::mem::take(&mut self.scratch_buffer);
+        document.editor = std::mem::take(&mut self.gcode_editor);
+        document.segments = std::mem::take(&mut self.segments);
+        document.segment_index = std::mem::take(&mut self.segment_index);
+        document.selected_segment = self.selected_segment.take();
+        document.z_levels = std::mem::take(&mut self.z_levels);
+        document.z_filter_level = self.z_filter_level.take();
+    }
+
+    /// Show a parked document in the editor and viewer
+    fn unpark_document(&mut self, index: usize) {
+        let document = std::mem::take(&mut self.documents[index]);
+        self.active_document = index;
+        self.current_file = document.path;
+        self.file_stamp = document.stamp;
+        self.gcode_content = document.content;
+        self.scratch_buffer = document.scratch;
+        self.gcode_editor = document.editor;
+        self.segment_index = document.segment_index;
+        self.selected_segment = document.selected_segment;
+        self.z_levels = document.z_levels;
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.set_segments(document.segments.clone());
         }
-        
-        self.console.info(format!("Found {} serial port(s)", self.available_ports.len()));
+        self.segments = document.segments;
+        self.set_z_filter_level(document.z_filter_level);
     }
 
-    /// Connect to GRBL device
-    fn connect_to_grbl(&mut self, ctx: &egui::Context) {
-        if self.selected_port.is_empty() {
-            self.status_message = "No port selected".to_string();
-            self.console.error("Cannot connect: no port selected".to_string());
+    /// Switch the editor and viewer to another open document
+    fn select_document(&mut self, index: usize) {
+        if index == self.active_document || index >= self.documents.len() {
             return;
         }
-        
-        self.status_message = format!("Connecting to {}...", self.selected_port);
-        self.console.info(format!("Attempting to connect to {}", self.selected_port));
-        
-        // Clone data needed for async operation
-        let port = self.selected_port.clone();
-        let ctx = ctx.clone();
-        let app_state = self.app_state.clone();
-        
-        // Create a shared slot for the connection manager
-        let manager_slot = Arc::new(TokioMutex::new(None::<Arc<TokioMutex<ConnectionManager>>>));
-        let manager_slot_write = manager_slot.clone();
-        
-        // Spawn connection task
-        tokio::spawn(async move {
-            let serial_conn = SerialConnection::new(port.clone(), 115200);
-            let config = ConnectionManagerConfig::default();
-            let mut manager = ConnectionManager::with_config(Box::new(serial_conn), config);
-            
-            match manager.connect(Duration::from_secs(5)).await {
-                Ok(()) => {
-                    tracing::info!("Successfully connected to {}", port);
-                    *app_state.connected.write() = true;
-                    
-                    // Store the manager in the shared slot
-                    let manager_arc = Arc::new(TokioMutex::new(manager));
-                    *manager_slot_write.lock().await = Some(manager_arc);
-                }
-                Err(e) => {
-                    tracing::error!("Connection failed: {}", e);
-                    *app_state.connected.write() = false;
-                }
-            }
-            ctx.request_repaint();
-        });
-        
-        // Store the manager slot so we can retrieve it in the update loop
-        self.pending_connection_manager = Some(manager_slot);
+        self.park_active_document();
+        self.unpark_document(index);
     }
 
-    /// Disconnect from GRBL device
-    fn disconnect_from_grbl(&mut self) {
-        if let Some(manager) = self.connection_manager.take() {
-            self.status_message = "Disconnecting...".to_string();
-            self.console.info("Disconnecting from device".to_string());
-            
-            // Spawn disconnect task
-            tokio::spawn(async move {
-                let mut mgr = manager.lock().await;
-                if let Err(e) = mgr.disconnect().await {
-                    tracing::error!("Error during disconnect: {}", e);
-                }
-            });
-            
-            *self.app_state.connected.write() = false;
-            self.status_message = "Disconnected".to_string();
-            self.console.info("Disconnected".to_string());
-        }
+    /// Open an empty document in a new tab
+    fn new_document(&mut self) {
+        self.park_active_document();
+        self.documents.push(Document::default());
+        self.unpark_document(self.documents.len() - 1);
     }
 
-    /// Send a command to GRBL
-    fn send_command(&mut self, command: GrblCommand) {
-        if self.connection_manager.is_none() {
-            self.console.error("Not connected to device".to_string());
+    /// Close a document's tab; closing the last tab leaves an empty one
+    fn close_document(&mut self, index: usize) {
+        if index == self.armed_document && self.program_in_progress() {
+            self.console.warning("Stop the program before closing its document".to_string());
+            return;
+        }
+        self.park_active_document();
+        if self.documents.len() == 1 {
+            self.documents[0] = Document::default();
+            self.unpark_document(0);
+            self.sync_armed_program();
             return;
         }
-        
-        let command_str = command.format();
-        self.console.sent(command_str.trim().to_string());
-        
-        // Clone the manager and send the command via the connection manager
-        let manager = Arc::clone(self.connection_manager.as_ref().unwrap());
-        tokio::spawn(async move {
-            let mgr = manager.lock().await;
-            if let Err(e) = mgr.send_command(command).await {
-                tracing::error!("Failed to send command: {}", e);
-            }
-        });
-    }
 
-    /// Handle console command submission
-    
-    /// Send jog command for manual positioning
-    fn send_jog_command(&mut self, x: f64, y: f64, z: f64) {
-        let feed_rate = if z != 0.0 {
-            self.settings.jog.z_feed_rate
+        self.documents.remove(index);
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        let active = if self.active_document == index {
+            index.min(self.documents.len() - 1)
         } else {
-            self.settings.jog.xy_feed_rate
-        };
-        
-        let command = GrblCommand::Jog {
-            x: if x != 0.0 { Some(x) } else { None },
-            y: if y != 0.0 { Some(y) } else { None },
-            z: if z != 0.0 { Some(z) } else { None },
-            feed_rate,
+            shift(self.active_document)
         };
-        
-        self.send_command(command);
-        self.status_message = format!("Jogging: X{:.3} Y{:.3} Z{:.3}", x, y, z);
-        tracing::info!("Jog command: X{:.3} Y{:.3} Z{:.3}", x, y, z);
+        let armed_closed = self.armed_document == index;
+        self.armed_document = if armed_closed { active } else { shift(self.armed_document) };
+        self.unpark_document(active);
+        if armed_closed {
+            self.sync_armed_program();
+        }
     }
-    
-    /// Send home command ($H)
-    fn send_home_command(&mut self) {
-        let command = GrblCommand::HomingCycle;
-        self.send_command(command);
-        self.status_message = "Homing...".to_string();
-        tracing::info!("Home command");
+
+    /// Arm a document to run when the program is started
+    fn arm_document(&mut self, index: usize) {
+        if index == self.armed_document {
+            return;
+        }
+        if self.program_in_progress() {
+            self.console.warning("Stop the program before arming another document".to_string());
+            return;
+        }
+        // The execution highlight belongs to the previously armed document
+        self.armed_editor_mut().current_line = None;
+        self.armed_document = index;
+        self.sync_armed_program();
+        self.console.info(format!("Armed {} for execution", self.document_title(index)));
     }
-    
-    /// Send unlock command ($X) to clear alarm state
-    fn send_unlock_command(&mut self) {
-        // Send directly to device, bypassing the command queue
-        let manager_opt = self.connection_manager.clone();
-        self.status_message = "Unlocking alarm...".to_string();
-        self.console.info("Sending unlock command ($X)".to_string());
-        tracing::info!("Unlock command ($X)");
 
-        if let Some(manager) = manager_opt {
-            // Spawn an async task to send raw bytes via send_realtime on the manager
-            tokio::spawn(async move {
-                let bytes = b"$X\n";
-                for &b in bytes.iter() {
-                    let _ = manager.lock().await.send_realtime(b).await;
-                    // Small delay between bytes to avoid overwhelming device
-                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
-                }
-                tracing::info!("Unlock sequence sent");
-            });
+    /// File of an open document
+    fn document_path(&self, index: usize) -> Option<&std::path::Path> {
+        if index == self.active_document {
+            self.current_file.as_deref()
         } else {
-            self.console.error("Not connected: cannot send unlock sequence".to_string());
+            self.documents[index].path.as_deref()
         }
     }
-    
-    /// Zero a specific axis
-    fn send_zero_axis(&mut self, axis: char) {
-        let gcode = format!("G10 L20 P0 {}0", axis);
-        let command = GrblCommand::GCode(gcode.clone());
-        self.send_command(command);
-        self.status_message = format!("Zeroing {} axis", axis);
-        tracing::info!("Zero axis: {}", axis);
+
+    /// Tab title of an open document
+    fn document_title(&self, index: usize) -> String {
+        let scratch = if index == self.active_document { self.scratch_buffer } else { self.documents[index].scratch };
+        documents::title(self.document_path(index), scratch)
     }
-    
-    /// Zero all axes
-    fn send_zero_all(&mut self) {
-        let gcode = "G10 L20 P0 X0 Y0 Z0".to_string();
-        let command = GrblCommand::GCode(gcode.clone());
-        self.send_command(command);
-        self.status_message = "Zeroing all axes".to_string();
-        tracing::info!("Zero all axes");
+
+    /// Plot the G-Code on the clipboard in the scratch buffer
+    fn new_from_clipboard(&mut self) {
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => self.paste_to_scratch(text),
+            Err(e) => self.report_error(Error::generic(format!("Cannot read the clipboard: {}", e))),
+        }
     }
-    
-    /// Send work coordinate system command
-    fn send_wcs_command(&mut self, wcs: u32) {
-        let command = format!("G{}", wcs);
-        self.console.sent(command.clone());
-        self.status_message = format!("Switching to G{}", wcs);
-        
-        // TODO: Send to GRBL via connection manager
-        tracing::info!("WCS command: G{}", wcs);
+
+    /// Show pasted G-Code in the scratch buffer, opening it if needed, and plot it
+    fn paste_to_scratch(&mut self, text: String) {
+        if text.trim().is_empty() {
+            self.console.warning("The clipboard holds no text".to_string());
+            return;
+        }
+        if let Some(index) = (0..self.documents.len()).find(|i| *i != self.active_document && self.documents[*i].scratch) {
+            self.select_document(index);
+        } else if !self.scratch_buffer && (self.current_file.is_some() || !self.gcode_content.is_empty()) {
+            self.new_document();
+        }
+        self.scratch_buffer = true;
+        self.gcode_content = text;
+        self.gcode_editor.set_bookmarks(Default::default());
+        self.console.info(format!("Plotting {} lines from the clipboard", self.gcode_content.lines().count()));
+        self.parse_gcode();
     }
-    
-    /// Handle a response received from GRBL
-    fn handle_grbl_response(&mut self, response: GrblResponse) {
-        // Skip status reports - they're handled separately and would flood the console
-        // Issue #4: Don't display status updates in console
-        if matches!(response, GrblResponse::Status(_)) {
-            tracing::debug!("GRBL status response: {:?}", response);
+
+    /// Replace the scratch buffer with text pasted while it is shown
+    fn handle_scratch_paste(&mut self, ctx: &egui::Context) {
+        if !self.scratch_buffer || self.gcode_editor.mode != EditorMode::View || ctx.wants_keyboard_input() {
             return;
         }
+        let pasted = ctx.input(|i| {
+            i.events.iter().rev().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        if let Some(text) = pasted {
+            self.paste_to_scratch(text);
+        }
+    }
+
+    /// Tabs for the open documents, with the armed one marked
+    fn show_document_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut select = None;
+        let mut close = None;
+        let mut arm = None;
+        let mut create = false;
         
-        // Format the response for display
-        let response_text = match &response {
-            GrblResponse::Ok => "ok".to_string(),
-            GrblResponse::Error(code) => {
-                let msg = response.error_message().unwrap_or("Unknown error");
-                format!("error:{} ({})", code, msg)
-            }
-            GrblResponse::Alarm(code) => {
-                let msg = response.error_message().unwrap_or("Unknown alarm");
-                format!("ALARM:{} ({})", code, msg)
-            }
-            GrblResponse::Status(_) => {
-                // This case is now unreachable due to early return above
-                unreachable!()
-            }
-            GrblResponse::Welcome { version } => {
-                format!("Grbl {} ['$' for help]", version)
-            }
-            GrblResponse::Setting { number, value } => {
-                format!("${}={}", number, value)
-            }
-            GrblResponse::Feedback(msg) => {
-                format!("[{}]", msg)
-            }
-            GrblResponse::Message(msg) => {
-                msg.clone()
-            }
-        };
+        egui::ScrollArea::horizontal().id_source("document_tabs").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for index in 0..self.documents.len() {
+                    let mut title = self.document_title(index);
+                    let scratch = if index == self.active_document { self.scratch_buffer } else { self.documents[index].scratch };
+                    let mut hover = match self.document_path(index) {
+                        Some(path) => path.display().to_string(),
+                        None if scratch => "Scratch buffer: press Ctrl+V to plot other G-Code".to_string(),
+                        None => title.clone(),
+                    };
+                    if index == self.armed_document {
+                        title = format!("▶ {}", title);
+                        hover.push_str("\nArmed: runs when the program is started");
+                    }
+                    let tab = ui.selectable_label(index == self.active_document, title).on_hover_text(hover);
+                    if tab.clicked() {
+                        select = Some(index);
+                    }
+                    tab.context_menu(|ui| {
+                        if ui.button("▶ Arm for Execution").clicked() {
+                            arm = Some(index);
+                            ui.close_menu();
+                        }
+                        if ui.button("✖ Close").clicked() {
+                            close = Some(index);
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.small_button("✖").on_hover_text("Close").clicked() {
+                        close = Some(index);
+                    }
+                }
+                if ui.small_button("➕").on_hover_text("New document").clicked() {
+                    create = true;
+                }
+            });
+        });
         
-        // Add to console with appropriate styling
-        if response.is_error() || response.is_alarm() {
-            self.console.error(response_text);
-        } else {
-            self.console.received(response_text);
+        if self.active_document != self.armed_document {
+            ui.horizontal(|ui| {
+                ui.weak("Not armed for execution");
+                let button = egui::Button::new("▶ Arm");
+                if ui.add_enabled(!self.program_in_progress(), button).clicked() {
+                    arm = Some(self.active_document);
+                }
+            });
         }
         
-        tracing::debug!("GRBL response: {:?}", response);
-    }
-    
-    /// Handle GRBL status update - Issue #1
-    /// 
-    /// This method processes status reports from GRBL (received in response to `?` queries)
-    /// and updates the machine state accordingly.
-    fn handle_grbl_status_update(&mut self, status: crate::grbl::GrblStatus) {
-        // Update machine state from the GRBL status
-        let mut machine = self.app_state.machine.write();
-        machine.update_from_grbl_status(&status);
-        drop(machine);
-        
-        // Log status updates (reduced frequency to avoid spam)
-        static STATUS_COUNT: AtomicUsize = AtomicUsize::new(0);
-        let count = STATUS_COUNT.fetch_add(1, Ordering::Relaxed);
-        if count % 10 == 0 {  // Log every 10th status update
-            tracing::debug!("Status update: state={:?}, MPos={:?}, WPos={:?}", 
-                status.state, status.mpos, status.wpos);
+        if let Some(index) = select {
+            self.select_document(index);
+        }
+        if let Some(index) = arm {
+            self.arm_document(index);
+        }
+        if let Some(index) = close {
+            self.close_document(index);
+        }
+        if create {
+            self.new_document();
         }
     }
-    
-    /// Send spindle control command
-    fn send_spindle_command(&mut self, cw: bool, ccw: bool) {
-        let command = if cw {
-            format!("M3 S{:.0}", self.spindle_speed)
-        } else if ccw {
-            format!("M4 S{:.0}", self.spindle_speed)
-        } else {
-            "M5".to_string()
-        };
-        
-        self.console.sent(command.clone());
-        self.status_message = if cw {
-            format!("Spindle CW at {:.0} RPM", self.spindle_speed)
-        } else if ccw {
-            format!("Spindle CCW at {:.0} RPM", self.spindle_speed)
+
+    /// Point the program state at the armed document
+    fn sync_armed_program(&mut self) {
+        let total_lines = self.armed_content().lines().count();
+        let mut program = self.app_state.program.write();
+        program.total_lines = total_lines;
+        program.current_line = 0;
+        program.lines_sent = 0;
+        program.lines_completed = 0;
+        drop(program);
+        self.current_line = 0;
+        self.publish_armed_analysis();
+    }
+
+    /// Share the armed program's text and segments with scripts
+    fn publish_armed_analysis(&self) {
+        let segments = if self.armed_document == self.active_document {
+            &self.segments
         } else {
-            "Spindle off".to_string()
+            &self.documents[self.armed_document].segments
         };
-        
-        // TODO: Send to GRBL via connection manager
-        tracing::info!("Spindle command: {}", command);
+        *self.app_state.analysis.write() = ProgramAnalysis::new(self.armed_content(), segments.clone());
     }
 
-    /// Send feed rate override command to GRBL
-    fn send_feed_override(&mut self, target_percent: f64) {
-        if self.connection_manager.is_none() {
-            return; // Silently skip if not connected
+    /// Text of the armed document
+    fn armed_content(&self) -> &str {
+        if self.armed_document == self.active_document {
+            &self.gcode_content
+        } else {
+            &self.documents[self.armed_document].content
         }
-        
-        let current = self.prev_feed_override;
-        let diff = target_percent - current;
-        
-        if diff.abs() < 0.5 {
-            return; // No significant change
+    }
+
+    /// File of the armed document
+    fn armed_path(&self) -> Option<&PathBuf> {
+        if self.armed_document == self.active_document {
+            self.current_file.as_ref()
+        } else {
+            self.documents[self.armed_document].path.as_ref()
         }
-        
-        // Determine which override commands to send
-        if diff.abs() >= 10.0 {
-            // Use coarse adjustments for large changes
-            let steps = (diff / 10.0).round() as i32;
-            let cmd = if steps > 0 {
-                OverrideCommand::FeedRate(FeedRateOverride::CoarseUp)
-            } else {
-                OverrideCommand::FeedRate(FeedRateOverride::CoarseDown)
-            };
-            
-            for _ in 0..steps.abs() {
-                self.send_realtime_byte(cmd.to_byte());
-            }
+    }
+
+    /// Editor of the armed document, which holds its breakpoints
+    fn armed_editor(&self) -> &GCodeEditor {
+        if self.armed_document == self.active_document {
+            &self.gcode_editor
         } else {
-            // Use fine adjustments for small changes
-            let steps = diff.round() as i32;
-            let cmd = if steps > 0 {
-                OverrideCommand::FeedRate(FeedRateOverride::FineUp)
-            } else {
-                OverrideCommand::FeedRate(FeedRateOverride::FineDown)
-            };
-            
-            for _ in 0..steps.abs() {
-                self.send_realtime_byte(cmd.to_byte());
-            }
+            &self.documents[self.armed_document].editor
         }
-        
-        self.prev_feed_override = target_percent;
-        self.console.info(format!("Feed override: {:.0}%", target_percent));
-        tracing::debug!("Feed rate override: {:.0}%", target_percent);
     }
 
-    /// Send rapid override command to GRBL
-    fn send_rapid_override(&mut self, target_percent: f64) {
-        if self.connection_manager.is_none() {
-            return; // Silently skip if not connected
+    /// Editor of the armed document, which shows the execution highlight
+    fn armed_editor_mut(&mut self) -> &mut GCodeEditor {
+        if self.armed_document == self.active_document {
+            &mut self.gcode_editor
+        } else {
+            &mut self.documents[self.armed_document].editor
         }
-        
-        let current = self.prev_rapid_override;
-        
-        if (target_percent - current).abs() < 0.5 {
-            return; // No significant change
+    }
+
+    /// Start a new project from the loaded program
+    fn new_project(&mut self) {
+        let mut project = Project::new("Untitled");
+        if !self.gcode_content.is_empty() {
+            project.set_program(self.current_program_name(), self.gcode_content.clone());
         }
-        
-        // GRBL rapid override is discrete: 25%, 50%, or 100%
-        let cmd = if target_percent <= 25.0 {
-            OverrideCommand::Rapid(RapidOverride::Low)
-        } else if target_percent <= 50.0 {
-            OverrideCommand::Rapid(RapidOverride::Medium)
-        } else {
-            OverrideCommand::Rapid(RapidOverride::Reset)
+        self.project_dialog.set_project(project, None);
+        self.project_dialog.modified = true;
+    }
+
+    /// Open a project file and restore its job setup
+    fn open_project(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("rCandle Project", &[PROJECT_EXTENSION])
+            .pick_file()
+        else {
+            return;
         };
-        
-        self.send_realtime_byte(cmd.to_byte());
-        self.prev_rapid_override = target_percent;
-        self.console.info(format!("Rapid override: {:.0}%", target_percent));
-        tracing::debug!("Rapid override: {:.0}%", target_percent);
+
+        let project = match Project::load(&path) {
+            Ok(project) => project,
+            Err(e) => {
+                self.status_message = format!("Error opening project: {}", e);
+                tracing::error!("Failed to open project {:?}: {}", path, e);
+                self.report_error(e.with_context("Failed to open project"));
+                return;
+            }
+        };
+
+        // Restore job-related settings; connection and UI settings stay local
+        if let Some(general) = &project.general {
+            let enabled_plugins = std::mem::take(&mut self.settings.general.enabled_plugins);
+            self.settings.general = general.clone();
+            // Plugins are installed locally, not part of the job
+            self.settings.general.enabled_plugins = enabled_plugins;
+        }
+        if let Some(processing) = &project.processing {
+            self.settings.processing = processing.clone();
+            self.preprocessor = Self::build_preprocessor(&self.settings);
+            self.parser = Self::build_parser(&self.settings);
+        }
+
+        // Restore the stored work offsets locally
+        {
+            let mut machine = self.app_state.machine.write();
+            for offset in &project.work_offsets {
+                machine.set_work_offset(offset.system, offset.offset);
+            }
+        }
+        self.refresh_machine_view();
+
+        self.console.info(format!("Opened project: {}", path.display()));
+        if !project.notes.is_empty() {
+            self.console.info(format!("Project notes: {}", project.notes));
+        }
+        self.status_message = format!("Project: {}", project.name);
+        let has_programs = !project.programs.is_empty();
+        self.project_dialog.set_project(project, Some(path));
+        if has_programs {
+            self.load_project_program(0);
+        }
     }
 
-    /// Send spindle override command to GRBL
-    fn send_spindle_override(&mut self, target_percent: f64) {
-        if self.connection_manager.is_none() {
-            return; // Silently skip if not connected
+    /// Save the open project, asking for a file if needed
+    fn save_project(&mut self, save_as: bool) {
+        let path = match (&self.project_dialog.path, save_as) {
+            (Some(path), false) => path.clone(),
+            _ => {
+                let file_name = format!("{}.{}", self.project_dialog.project.name, PROJECT_EXTENSION);
+                match rfd::FileDialog::new()
+                    .add_filter("rCandle Project", &[PROJECT_EXTENSION])
+                    .set_file_name(file_name)
+                    .save_file()
+                {
+                    Some(path) => path,
+                    None => return,
+                }
+            }
+        };
+
+        let project = &mut self.project_dialog.project;
+        project.general = Some(self.settings.general.clone());
+        project.processing = Some(self.settings.processing.clone());
+        match project.save(&path) {
+            Ok(()) => {
+                self.project_dialog.path = Some(path.clone());
+                self.project_dialog.modified = false;
+                self.status_message = format!("Saved project: {}", path.display());
+                self.console.info(format!("Saved project: {}", path.display()));
+            }
+            Err(e) => {
+                tracing::error!("Failed to save project {:?}: {}", path, e);
+                self.report_error(e.with_context("Failed to save project"));
+            }
         }
-        
-        let current = self.prev_spindle_override;
-        let diff = target_percent - current;
-        
-        if diff.abs() < 0.5 {
-            return; // No significant change
+    }
+
+    /// Load one of the project's programs into the editor
+    fn load_project_program(&mut self, index: usize) {
+        let Some(program) = self.project_dialog.project.programs.get(index) else {
+            return;
+        };
+        self.gcode_content = program.gcode.clone();
+        self.gcode_editor.set_bookmarks(Default::default());
+        self.current_file = None;
+        self.file_stamp = None;
+        self.console.info(format!("Loaded project program: {}", program.name));
+        self.parse_gcode();
+    }
+
+    /// Name for the program in the editor
+    fn current_program_name(&self) -> String {
+        self.current_file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "program.nc".to_string())
+    }
+
+    /// Carry out an action from the project dialog
+    fn handle_project_action(&mut self, action: ProjectAction) {
+        match action {
+            ProjectAction::Save => self.save_project(false),
+            ProjectAction::SaveAs => self.save_project(true),
+            ProjectAction::AddCurrentProgram => {
+                if self.gcode_content.is_empty() {
+                    self.console.warning("No program loaded".to_string());
+                    return;
+                }
+                let name = self.current_program_name();
+                self.project_dialog.project.set_program(name, self.gcode_content.clone());
+                self.project_dialog.modified = true;
+            }
+            ProjectAction::LoadProgram(index) => self.load_project_program(index),
+            ProjectAction::AttachHeightmap => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Heightmap", &["map", "csv", "txt"])
+                    .add_filter("All Files", &["*"])
+                    .pick_file()
+                {
+                    match self.project_dialog.project.attach_heightmap(&path) {
+                        Ok(()) => self.project_dialog.modified = true,
+                        Err(e) => self.report_error(e.with_context("Failed to attach heightmap")),
+                    }
+                }
+            }
+            ProjectAction::CaptureOffsets => {
+                let offsets = self.app_state.machine.read().work_offsets;
+                self.project_dialog.project.set_work_offsets(&offsets);
+                self.project_dialog.modified = true;
+            }
+            ProjectAction::ApplyOffsets => {
+                for command in self.project_dialog.project.work_offset_commands() {
+                    self.send_command(GrblCommand::GCode(command));
+                }
+                self.console.info("Applied project work offsets".to_string());
+            }
         }
-        
-        // Determine which override commands to send
-        if diff.abs() >= 10.0 {
-            // Use coarse adjustments for large changes
-            let steps = (diff / 10.0).round() as i32;
-            let cmd = if steps > 0 {
-                OverrideCommand::SpindleSpeed(SpindleOverride::CoarseUp)
+    }
+
+    /// Save the current G-Code to a file
+    fn save_file(&mut self) {
+        if let Some(path) = &self.current_file {
+            if let Err(e) = std::fs::write(path, &self.gcode_content) {
+                self.status_message = format!("Error saving file: {}", e);
+                tracing::error!("Failed to save file {:?}: {}", path, e);
+                self.report_error(Error::from(e).with_context("Failed to save file"));
             } else {
-                OverrideCommand::SpindleSpeed(SpindleOverride::CoarseDown)
-            };
-            
-            for _ in 0..steps.abs() {
-                self.send_realtime_byte(cmd.to_byte());
+                self.file_stamp = Some(FileStamp::new(path, &self.gcode_content));
+                self.pending_reload = None;
+                self.status_message = format!("Saved: {}", path.display());
+                self.console.info(format!("Saved file: {}", path.display()));
+                tracing::info!("Saved G-Code file: {:?}", path);
             }
         } else {
-            // Use fine adjustments for small changes
-            let steps = diff.round() as i32;
-            let cmd = if steps > 0 {
-                OverrideCommand::SpindleSpeed(SpindleOverride::FineUp)
+            self.save_file_as();
+        }
+    }
+
+    /// Save the current G-Code to a new file
+    fn save_file_as(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("G-Code", &["gcode", "nc", "ngc"])
+            .save_file()
+        {
+            if let Err(e) = std::fs::write(&path, &self.gcode_content) {
+                self.status_message = format!("Error saving file: {}", e);
+                tracing::error!("Failed to save file {:?}: {}", path, e);
+                self.report_error(Error::from(e).with_context("Failed to save file"));
             } else {
-                OverrideCommand::SpindleSpeed(SpindleOverride::FineDown)
-            };
-            
-            for _ in 0..steps.abs() {
-                self.send_realtime_byte(cmd.to_byte());
+                self.file_stamp = Some(FileStamp::new(&path, &self.gcode_content));
+                self.pending_reload = None;
+                self.current_file = Some(path.clone());
+                self.scratch_buffer = false;
+                self.status_message = format!("Saved: {}", path.display());
+                self.console.info(format!("Saved file: {}", path.display()));
+                tracing::info!("Saved G-Code file: {:?}", path);
+                self.save_bookmarks();
             }
         }
-        
-        self.prev_spindle_override = target_percent;
-        self.console.info(format!("Spindle override: {:.0}%", target_percent));
-        tracing::debug!("Spindle speed override: {:.0}%", target_percent);
     }
 
-    /// Send a real-time command byte to GRBL
-    fn send_realtime_byte(&mut self, byte: u8) {
-        if let Some(ref manager) = self.connection_manager {
-            let manager = Arc::clone(manager);
-            tokio::spawn(async move {
-                let mgr = manager.lock().await;
-                if let Err(e) = mgr.send_realtime(byte).await {
-                    tracing::error!("Failed to send real-time command: {}", e);
+    /// Export the filtered console messages to a file
+    fn export_console(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Log", &["log", "txt"])
+            .set_file_name("console.log")
+            .save_file()
+        {
+            match self.console.export_filtered(&path) {
+                Ok(count) => {
+                    self.status_message = format!("Exported {} console messages", count);
+                    tracing::info!("Exported {} console messages to {:?}", count, path);
                 }
-            });
+                Err(e) => {
+                    tracing::error!("Failed to export console to {:?}: {}", path, e);
+                    self.report_error(Error::from(e).with_context("Failed to export console"));
+                }
+            }
         }
     }
 
-    fn handle_console_command(&mut self, command: &str) {
-        let cmd = command.trim();
-        
-        if cmd.is_empty() {
+    /// Export the parsed segments to CSV or JSON
+    fn export_toolpath(&mut self) {
+        if self.segments.is_empty() {
+            self.console.warning("No toolpath to export".to_string());
             return;
         }
+        let name = Path::new(&self.current_program_name()).with_extension("csv");
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .set_file_name(name.to_string_lossy())
+            .save_file()
+        {
+            // Without a known extension the toolpath is saved as CSV
+            let path = match ToolpathFormat::from_path(&path) {
+                Some(_) => path,
+                None => path.with_extension("csv"),
+            };
+            match save_toolpath(&path, &self.segments) {
+                Ok(()) => {
+                    self.status_message = format!("Exported {} segments", self.segments.len());
+                    self.console.info(format!("Exported toolpath: {}", path.display()));
+                    tracing::info!("Exported {} segments to {:?}", self.segments.len(), path);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to export toolpath to {:?}: {}", path, e);
+                    self.report_error(e.with_context("Failed to export toolpath"));
+                }
+            }
+        }
+    }
+
+    /// Show link activity, queue depth and buffer fill in the status bar
+    fn show_link_health(&self, ui: &mut egui::Ui) {
+        let now = std::time::Instant::now();
+        let blinker = |active: bool| {
+            if active {
+                egui::Color32::from_rgb(100, 255, 100)
+            } else {
+                egui::Color32::DARK_GRAY
+            }
+        };
         
-        // Log the command response (simulate sending to GRBL)
-        self.console.info(format!("Sending command: {}", cmd));
+        if let Some(description) = &self.link_description {
+            ui.label(description);
+        }
+        let tx_active = self.link_activity.tx_active(now, ACTIVITY_HOLD);
+        let rx_active = self.link_activity.rx_active(now, ACTIVITY_HOLD);
+        ui.colored_label(blinker(tx_active), "● TX")
+            .on_hover_text("Data sent to the controller");
+        ui.colored_label(blinker(rx_active), "● RX")
+            .on_hover_text("Data received from the controller");
         
-        // TODO: Send command to GRBL via connection manager
-        // For now, just simulate a response
-        self.console.received("ok".to_string());
+        ui.separator();
+        let metrics = self.app_state.link_metrics.read().clone();
+        ui.label(format!("Queue: {}", metrics.queue.current_length));
         
-        tracing::info!("Console command: {}", cmd);
+        if self.settings.connection.connection_type == ConnectionType::Network && metrics.status_rtt_ms > 0.0 {
+            ui.separator();
+            let slowed = metrics.status_interval_ms > self.settings.connection.status_query_interval_ms;
+            let color = if slowed { egui::Color32::YELLOW } else { ui.visuals().text_color() };
+            ui.colored_label(color, format!("⏱ {:.0} ms", metrics.status_rtt_ms))
+                .on_hover_text(format!(
+                    "Network round trip of status queries\nStatus every {} ms{}",
+                    metrics.status_interval_ms,
+                    if slowed { " (slowed for this link)" } else { "" }
+                ));
+        }
+        
+        if let Some((planner, rx)) = self.link_activity.buffer_fill() {
+            ui.separator();
+            ui.label(format!("Buffer: {:.0}% / {:.0}%", planner * 100.0, rx * 100.0))
+                .on_hover_text("Planner / serial RX buffer fill, from status reports");
+        }
+        
+        // Turn the blinkers off on time
+        if tx_active || rx_active {
+            ui.ctx().request_repaint_after(ACTIVITY_HOLD);
+        }
     }
-    
-    /// Start program execution
-    fn start_program(&mut self) {
-        let mut program_state = self.app_state.program.write();
+
+    /// Persist editor bookmarks to the current file's sidecar metadata
+    fn save_bookmarks(&mut self) {
+        let Some(path) = &self.current_file else {
+            return;
+        };
         
-        // Check if we have a program loaded
-        if program_state.total_lines == 0 {
-            self.console.warning("No program loaded".to_string());
-            drop(program_state);
-            self.status_message = "No program loaded".to_string();
+        let mut metadata = SidecarMetadata::load_for(path);
+        metadata.bookmarks = self.gcode_editor.bookmarks.clone();
+        if let Err(e) = metadata.save_for(path) {
+            tracing::error!("Failed to save sidecar metadata for {:?}: {}", path, e);
+            self.report_error(e.with_context("Failed to save bookmarks"));
+        }
+    }
+
+    /// Persist a job cost record to the current file's sidecar metadata
+    fn save_job_record(&mut self, record: JobRecord) {
+        let Some(path) = &self.current_file else {
+            self.console.warning("Save the program to a file before saving job metadata".to_string());
             return;
+        };
+
+        let mut metadata = SidecarMetadata::load_for(path);
+        metadata.job = Some(record);
+        match metadata.save_for(path) {
+            Ok(()) => self.console.info("Saved job estimate to file metadata".to_string()),
+            Err(e) => {
+                tracing::error!("Failed to save sidecar metadata for {:?}: {}", path, e);
+                self.report_error(e.with_context("Failed to save job metadata"));
+            }
         }
+    }
+
+    /// Parse the current G-Code content
+    fn parse_gcode(&mut self) {
+        self.console.info("Parsing G-Code...".to_string());
         
-        // Start or resume execution
-        match program_state.state {
-            ExecutionState::NotLoaded => {
-                self.console.warning("No program loaded".to_string());
-                drop(program_state);
+        // Resolve variables and expressions
+        let program = match ExpressionEvaluator::new().evaluate(&self.gcode_content) {
+            Ok(p) => p,
+            Err(e) => {
+                self.status_message = format!("Expression error: {}", e);
+                tracing::error!("Failed to evaluate G-Code expressions: {}", e);
+                self.report_error(e.with_context("Expression evaluation failed"));
                 return;
             }
-            ExecutionState::Loaded | ExecutionState::Completed => {
-                // Start from beginning
-                program_state.state = ExecutionState::Running;
-                program_state.current_line = 0;
-                program_state.lines_sent = 0;
-                program_state.lines_completed = 0;
-                self.current_line = 0;
-                self.program_start_time = Some(std::time::Instant::now());
-                self.total_paused_duration = std::time::Duration::ZERO;
-                self.console.info("Program started".to_string());
-                self.status_message = "Program started".to_string();
-                tracing::info!("Program execution started");
-            }
-            ExecutionState::Paused => {
-                // Resume from pause
-                program_state.state = ExecutionState::Running;
-                if let Some(paused_time) = self.program_paused_time.take() {
-                    self.total_paused_duration += paused_time.elapsed();
-                }
-                self.console.info("Program resumed".to_string());
-                self.status_message = "Program resumed".to_string();
-                tracing::info!("Program execution resumed");
-            }
-            ExecutionState::Running => {
-                self.console.warning("Program already running".to_string());
-            }
-            ExecutionState::Error => {
-                self.console.warning("Cannot start - program in error state. Reset first.".to_string());
+        };
+        
+        // Expand subprograms and canned cycles so they can be visualized,
+        // remembering the source line of each expanded line
+        let (program, source_lines) = match ProgramExpander::new().expand_with_sources(&program) {
+            Ok(lines) => (
+                lines.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n"),
+                lines.iter().map(|line| line.source_line).collect::<Vec<_>>(),
+            ),
+            Err(e) => {
+                self.status_message = format!("Expansion error: {}", e);
+                tracing::error!("Failed to expand G-Code: {}", e);
+                self.report_error(e.with_context("Subprogram/canned cycle expansion failed"));
+                return;
             }
-        }
+        };
         
-        drop(program_state);
+        // Tokenize
+        let mut tokenizer = Tokenizer::new(&program);
+        let tokens = match tokenizer.tokenize() {
+            Ok(t) => t,
+            Err(e) => {
+                self.status_message = format!("Tokenization error: {}", e);
+                tracing::error!("Failed to tokenize G-Code: {}", e);
+                self.report_error(e.with_context("Tokenization failed"));
+                return;
+            }
+        };
         
-        // TODO: Send to GRBL via connection manager
-    }
-    
-    /// Pause program execution
-    fn pause_program(&mut self) {
-        let mut program_state = self.app_state.program.write();
+        self.console.debug(format!("Tokenized {} tokens", tokens.len()));
+
+        // Parse tokens to commands
+        let commands = match self.parser.parse_tokens(&tokens) {
+            Ok(c) => c,
+            Err(e) => {
+                self.status_message = format!("Parse error: {}", e);
+                tracing::error!("Failed to parse G-Code: {}", e);
+                self.report_error(e.with_context("Parse failed"));
+                return;
+            }
+        };
         
-        if matches!(program_state.state, ExecutionState::Running) {
-            program_state.state = ExecutionState::Paused;
-            self.program_paused_time = Some(std::time::Instant::now());
-            self.console.info("Program paused".to_string());
-            self.status_message = "Program paused".to_string();
-            tracing::info!("Program execution paused");
-            
-            // TODO: Send pause command to GRBL (feed hold)
-        } else {
-            self.console.warning("Program is not running".to_string());
-        }
-        
-        drop(program_state);
-    }
-    
-    /// Stop program execution
-    fn stop_program(&mut self) {
-        let mut program_state = self.app_state.program.write();
+        self.console.debug(format!("Parsed {} commands", commands.len()));
+
+        // Generate segments
+        let segments = match self.parser.generate_segments(&commands) {
+            Ok(s) => s,
+            Err(e) => {
+                self.status_message = format!("Segment generation error: {}", e);
+                tracing::error!("Failed to generate segments: {}", e);
+                self.report_error(e.with_context("Segment generation failed"));
+                return;
+            }
+        };
+
+        let segment_count = segments.len();
+        self.console.info(format!("Generated {} segments", segment_count));
+        tracing::info!("Parsed {} segments", segment_count);
         
-        if !matches!(program_state.state, ExecutionState::Loaded) {
-            program_state.state = ExecutionState::Loaded;
-            self.program_start_time = None;
-            self.program_paused_time = None;
-            self.total_paused_duration = std::time::Duration::ZERO;
-            self.console.warning("Program stopped".to_string());
-            self.status_message = "Program stopped".to_string();
-            tracing::info!("Program execution stopped");
-            
-            // TODO: Send stop command to GRBL (soft reset or queue clear)
-        } else {
-            self.console.warning("Program is not running".to_string());
-        }
+        // Apply preprocessing
+        let processed = match self.preprocessor.process(&segments) {
+            Ok(p) => p,
+            Err(e) => {
+                self.status_message = format!("Preprocessing error: {}", e);
+                tracing::error!("Failed to preprocess segments: {}", e);
+                self.report_error(e.with_context("Preprocessing failed"));
+                return;
+            }
+        };
         
-        drop(program_state);
-    }
-    
-    /// Reset program to beginning
-    fn reset_program(&mut self) {
-        let mut program_state = self.app_state.program.write();
+        let processed_count = processed.len();
+        self.console.info(format!("Preprocessed to {} segments", processed_count));
+        tracing::info!("Preprocessed to {} segments", processed_count);
         
-        program_state.state = ExecutionState::Loaded;
-        program_state.current_line = 0;
-        program_state.lines_sent = 0;
-        program_state.lines_completed = 0;
-        self.current_line = 0;
-        self.program_start_time = None;
-        self.program_paused_time = None;
-        self.total_paused_duration = std::time::Duration::ZERO;
+        // Map rotary (A) moves onto the stock cylinder for the preview
+        let mut processed = match self.settings.visualization.rotary.wrap() {
+            Some(wrap) => wrap.apply(&processed),
+            None => processed,
+        };
         
-        self.console.info("Program reset".to_string());
-        self.status_message = "Program reset".to_string();
-        tracing::info!("Program reset to beginning");
+        // Point segments back at the lines of the file as loaded
+        for segment in &mut processed {
+            segment.source_line = segment.source_line.and_then(|line| source_lines.get(line).copied());
+        }
         
-        drop(program_state);
-    }
-    
-    /// Execute a single step in step mode
-    fn execute_single_step(&mut self) {
-        let mut program_state = self.app_state.program.write();
+        // Store segments for rendering
+        self.segments = processed.clone();
+        self.segment_index = SegmentIndex::build(&processed);
+        self.selected_segment = None;
+        self.z_levels = ZFilter::levels(&processed);
+        self.set_z_filter_level(None);
         
-        // Check if we're in a valid state to step
-        if program_state.total_lines == 0 {
-            self.console.warning("No program loaded".to_string());
-            drop(program_state);
-            return;
+        // Update renderer with new toolpath
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.toolpath_mut().color_mode = self.settings.visualization.color_mode;
+            renderer.set_segments(processed);
+            self.console.info("3D view updated with toolpath".to_string());
         }
         
-        if self.current_line >= program_state.total_lines {
-            self.console.info("End of program reached".to_string());
-            program_state.state = ExecutionState::Completed;
-            drop(program_state);
-            return;
+        // Lathe programs live in the XZ plane
+        if self.settings.machine.lathe_mode {
+            self.apply_view_preset(ViewPreset::Front);
         }
         
-        // Execute next line
-        self.current_line += 1;
-        program_state.current_line = self.current_line;
-        program_state.lines_completed = self.current_line;
+        // Update program state with the parsed data
+        if self.active_document == self.armed_document {
+            self.app_state.program.write().total_lines = self.gcode_content.lines().count();
+            self.publish_armed_analysis();
+        }
         
-        self.console.debug(format!("Step: executing line {}", self.current_line));
-        tracing::debug!("Step mode: executing line {}", self.current_line);
+        // Point out words GRBL would reject before the job is started
+        let report = crate::parser::CompatibilityReport::check(&self.gcode_content);
+        let unhandled: Vec<String> = report
+            .summary()
+            .into_iter()
+            .map(|entry| entry.word)
+            .filter(|word| !self.settings.processing.strip_words.contains(word))
+            .collect();
+        if !unhandled.is_empty() {
+            self.console.warning(format!(
+                "Program uses words GRBL does not support: {} (see Tools → Compatibility Report)",
+                unhandled.join(", ")
+            ));
+        }
         
-        // TODO: Send single line to GRBL
+        // Point out rapids that would drag the tool through the stock
+        let rapid_retract = self.settings.processing.rapid_retract;
+        if rapid_retract != RapidRetract::Off {
+            let issues = SafeRetract::new(self.settings.general.safe_z).check(&self.gcode_content);
+            if let Some(first) = issues.first() {
+                let handling = if rapid_retract == RapidRetract::Fix {
+                    "they are retracted to the safe Z while streaming"
+                } else {
+                    "set Rapids Below Surface to Fix in the processing settings to retract first"
+                };
+                self.console.warning(format!(
+                    "{} rapid(s) move in XY below Z0, first at line {} (Z{:.3}); {}",
+                    issues.len(),
+                    first.line + 1,
+                    first.depth,
+                    handling
+                ));
+            }
+        }
         
-        drop(program_state);
+        self.status_message = format!(
+            "Parsed {} segments ({} after preprocessing)",
+            segment_count, processed_count
+        );
+        self.console.info("G-Code parsing complete".to_string());
     }
-    
-    /// Calculate time estimates for program execution
-    fn calculate_time_estimates(&self) -> (String, String) {
-        let program_state = self.app_state.program.read();
+
+    /// Regenerate the loaded program as multiple depth passes
+    fn apply_multipass(&mut self, generator: crate::parser::MultiPassGenerator) {
+        match generator.generate(&self.gcode_content) {
+            Ok(program) => {
+                self.gcode_content = program;
+                self.console.info("Program regenerated with multiple depth passes".to_string());
+                self.status_message = "Multi-pass program generated".to_string();
+                self.parse_gcode();
+            }
+            Err(e) => {
+                self.status_message = format!("Multi-pass generation failed: {}", e);
+                tracing::error!("Failed to generate multi-pass program: {}", e);
+                self.report_error(e.with_context("Multi-pass generation failed"));
+            }
+        }
+    }
+
+    /// Refresh list of available serial ports
+    fn refresh_ports(&mut self) {
+        self.available_ports = SerialConnection::list_ports()
+            .ok()
+            .map(|ports| ports.iter().map(|p| p.port_name.clone()).collect())
+            .unwrap_or_else(Vec::new);
         
-        // Calculate elapsed time
-        let elapsed = if let Some(start_time) = self.program_start_time {
-            let total_elapsed = start_time.elapsed();
-            let active_elapsed = if let Some(paused_time) = self.program_paused_time {
-                // Currently paused - subtract pause duration
-                total_elapsed - self.total_paused_duration - paused_time.elapsed()
-            } else {
-                // Not paused - just subtract total paused duration
-                total_elapsed - self.total_paused_duration
-            };
-            active_elapsed
-        } else {
-            std::time::Duration::ZERO
-        };
+        if !self.available_ports.is_empty() && !self.available_ports.contains(&self.selected_port) {
+            self.selected_port = self.available_ports[0].clone();
+        }
         
-        let elapsed_text = format_duration(elapsed);
+        self.console.info(format!("Found {} serial port(s)", self.available_ports.len()));
+    }
+
+    /// Start scanning serial ports for GRBL controllers
+    fn scan_for_devices(&mut self) {
+        if self.scan_task.is_some() {
+            return;
+        }
         
-        // Calculate remaining time estimate
-        let remaining_text = if self.current_line > 0 && program_state.total_lines > self.current_line {
-            let progress = self.current_line as f64 / program_state.total_lines as f64;
-            let estimated_total = elapsed.as_secs_f64() / progress;
-            let remaining_secs = estimated_total - elapsed.as_secs_f64();
-            let remaining = std::time::Duration::from_secs_f64(remaining_secs.max(0.0));
-            format_duration(remaining)
-        } else if matches!(program_state.state, ExecutionState::Completed) {
-            "Complete".to_string()
+        // Leave the port in use alone
+        let skip = if self.app_state.is_connected() {
+            vec![self.selected_port.clone()]
         } else {
-            "--:--:--".to_string()
+            Vec::new()
         };
         
-        drop(program_state);
+        self.status_message = "Scanning for GRBL devices...".to_string();
+        self.console.info("Scanning serial ports for GRBL devices".to_string());
+        self.scan_task = Some(tokio::task::spawn_blocking(move || {
+            scan_for_grbl(COMMON_BAUD_RATES, DEFAULT_PROBE_TIMEOUT, &skip)
+        }));
+    }
+    
+    /// Collect the results of a finished port scan
+    fn poll_scan_task(&mut self) {
+        if !self.scan_task.as_ref().is_some_and(|task| task.is_finished()) {
+            return;
+        }
+        let Some(task) = self.scan_task.take() else {
+            return;
+        };
         
-        (elapsed_text, remaining_text)
+        match tokio::runtime::Handle::current().block_on(task) {
+            Ok(devices) => {
+                for device in &devices {
+                    self.console.info(format!("Found {}", device.label()));
+                    if !self.available_ports.contains(&device.port) {
+                        self.available_ports.push(device.port.clone());
+                    }
+                }
+                if let Some(device) = devices.first() {
+                    if !self.app_state.is_connected() {
+                        self.selected_port = device.port.clone();
+                        self.settings.connection.baud_rate = device.baud_rate;
+                    }
+                }
+                self.status_message = format!("Found {} GRBL device(s)", devices.len());
+                self.detected_devices = devices;
+            }
+            Err(e) => {
+                self.report_error(Error::generic(e.to_string()).with_context("Port scan failed"));
+                self.status_message = "Port scan failed".to_string();
+            }
+        }
     }
 
-    /// Draw toolpath in 2D (XY plane projection)
-    fn draw_toolpath_2d(&self, ui: &mut egui::Ui, rect: egui::Rect) {
-        use egui::{Color32, Pos2, Stroke};
-        
-        if self.segments.is_empty() {
+    /// Start discovering network controllers over mDNS
+    fn discover_network(&mut self) {
+        if self.discovery_task.is_some() {
             return;
         }
-        
-        // Calculate bounding box
-        let mut min_x = f64::MAX;
-        let mut max_x = f64::MIN;
-        let mut min_y = f64::MAX;
-        let mut max_y = f64::MIN;
-        
-        for segment in &self.segments {
-            min_x = min_x.min(segment.start.x).min(segment.end.x);
-            max_x = max_x.max(segment.start.x).max(segment.end.x);
-            min_y = min_y.min(segment.start.y).min(segment.end.y);
-            max_y = max_y.max(segment.start.y).max(segment.end.y);
+        self.status_message = "Discovering network devices...".to_string();
+        self.discovery_task = Some(tokio::task::spawn_blocking(|| {
+            discover_network_devices(DEFAULT_DISCOVERY_TIMEOUT)
+        }));
+    }
+    
+    /// Collect the results of a finished mDNS discovery
+    fn poll_discovery_task(&mut self) {
+        if !self.discovery_task.as_ref().is_some_and(|task| task.is_finished()) {
+            return;
         }
+        let Some(task) = self.discovery_task.take() else {
+            return;
+        };
         
-        // Add some padding
-        let padding = 20.0;
-        let width = (max_x - min_x) as f32;
-        let height = (max_y - min_y) as f32;
-        
-        if width == 0.0 || height == 0.0 {
+        match tokio::runtime::Handle::current().block_on(task) {
+            Ok(Ok(devices)) => {
+                for device in &devices {
+                    self.console.info(format!("Found {}", device.label()));
+                }
+                self.status_message = format!("Found {} network device(s)", devices.len());
+                self.network_devices = devices;
+            }
+            Ok(Err(e)) => {
+                self.report_error(e.with_context("Network discovery failed"));
+                self.status_message = "Network discovery failed".to_string();
+            }
+            Err(e) => {
+                self.report_error(Error::generic(e.to_string()).with_context("Network discovery failed"));
+                self.status_message = "Network discovery failed".to_string();
+            }
+        }
+    }
+    
+    /// Start discovering Bluetooth devices
+    fn discover_bluetooth(&mut self) {
+        if self.bluetooth_task.is_some() {
             return;
         }
+        self.status_message = "Discovering Bluetooth devices...".to_string();
+        // bluetoothctl can take a while to answer, so keep it off the UI thread
+        self.bluetooth_task = Some(tokio::task::spawn_blocking(BluetoothConnection::discover));
+    }
+    
+    /// Collect the results of a finished Bluetooth discovery
+    fn poll_bluetooth_task(&mut self) {
+        if !self.bluetooth_task.as_ref().is_some_and(|task| task.is_finished()) {
+            return;
+        }
+        let Some(task) = self.bluetooth_task.take() else {
+            return;
+        };
         
-        // Calculate scale to fit in viewport
-        let viewport_width = rect.width() - padding * 2.0;
-        let viewport_height = rect.height() - padding * 2.0;
-        let scale = (viewport_width / width).min(viewport_height / height);
-        
-        // Center offset
-        let offset_x = rect.left() + padding + (viewport_width - width * scale) / 2.0;
-        let offset_y = rect.top() + padding + (viewport_height - height * scale) / 2.0;
+        match tokio::runtime::Handle::current().block_on(task) {
+            Ok(devices) => {
+                self.console.info(format!("Found {} Bluetooth device(s)", devices.len()));
+                self.status_message = format!("Found {} Bluetooth device(s)", devices.len());
+                self.bluetooth_devices = devices;
+            }
+            Err(e) => {
+                self.report_error(Error::generic(e.to_string()).with_context("Bluetooth discovery failed"));
+                self.status_message = "Bluetooth discovery failed".to_string();
+            }
+        }
+    }
+    
+    /// Connect to GRBL device, returning false if no attempt could be started
+    fn connect_to_grbl(&mut self, ctx: &egui::Context) -> bool {
+        let connection: Box<dyn Connection> = match self.settings.connection.connection_type {
+            ConnectionType::Serial => {
+                if self.selected_port.is_empty() {
+                    self.status_message = "No port selected".to_string();
+                    self.console.error("Cannot connect: no port selected".to_string());
+                    return false;
+                }
+                Box::new(SerialConnection::with_config(SerialConfig {
+                    port: self.selected_port.clone(),
+                    ..self.settings.connection.serial_config()
+                }))
+            }
+            ConnectionType::Bluetooth => match self.settings.connection.bluetooth_config() {
+                Ok(config) => Box::new(BluetoothConnection::new(config)),
+                Err(e) => {
+                    self.status_message = "No Bluetooth device selected".to_string();
+                    self.report_error(e.with_context("Cannot connect"));
+                    return false;
+                }
+            },
+            ConnectionType::Simulator => {
+                let config = MockDeviceConfig {
+                    acceleration_limited: self.settings.connection.simulator_physics,
+                    ..Default::default()
+                };
+                let connection = MockConnection::with_script(config, MockScript::new());
+                self.simulator_device = Some(connection.device());
+                Box::new(connection)
+            }
+            ConnectionType::Network => {
+                let Some(profile) = self.settings.connection.selected_network_profile() else {
+                    self.status_message = "No network profile selected".to_string();
+                    self.console.error("Cannot connect: no network profile selected".to_string());
+                    return false;
+                };
+                let timeout_ms = self.settings.connection.timeout_ms;
+                match profile.protocol {
+                    NetworkProtocol::Telnet => Box::new(TelnetConnection::new(profile.telnet_config(timeout_ms))),
+                    NetworkProtocol::WebSocket => Box::new(WebSocketConnection::new(profile.websocket_config(timeout_ms))),
+                }
+            }
+        };
         
-        // Transform function from G-Code coordinates to screen coordinates
-        let to_screen = |x: f64, y: f64| {
-            Pos2::new(
-                offset_x + ((x - min_x) as f32 * scale),
-                // Flip Y axis (G-Code Y increases upward, screen Y increases downward)
-                offset_y + viewport_height - ((y - min_y) as f32 * scale),
-            )
+        // Enabled plugins see the lines sent and received
+        let connection: Box<dyn Connection> = match self.plugins.connection_hooks() {
+            Some(hooks) => Box::new(PluginConnection::new(connection, hooks)),
+            None => connection,
         };
         
-        // Draw grid
-        let grid_color = Color32::from_rgb(40, 40, 50);
-        let grid_spacing = 10.0; // mm
+        let port = connection.description();
+        self.link_description = Some(port.clone());
+        self.link_activity = LinkActivity::new();
+        self.status_message = format!("Connecting to {}...", port);
+        self.console.info(format!("Attempting to connect to {}", port));
         
-        // Vertical grid lines
-        let mut x = (min_x / grid_spacing).floor() * grid_spacing;
-        while x <= max_x {
-            let p1 = to_screen(x, min_y);
-            let p2 = to_screen(x, max_y);
-            ui.painter().line_segment([p1, p2], Stroke::new(1.0, grid_color));
-            x += grid_spacing;
-        }
+        // Clone data needed for async operation
+        let ctx = ctx.clone();
+        let app_state = self.app_state.clone();
+        let config = self.settings.connection.manager_config();
+        let connect_timeout = self.settings.connection.connect_timeout();
         
-        // Horizontal grid lines
-        let mut y = (min_y / grid_spacing).floor() * grid_spacing;
-        while y <= max_y {
-            let p1 = to_screen(min_x, y);
-            let p2 = to_screen(max_x, y);
-            ui.painter().line_segment([p1, p2], Stroke::new(1.0, grid_color));
-            y += grid_spacing;
-        }
+        // Create a shared slot for the connection manager
+        let manager_slot = Arc::new(TokioMutex::new(None::<ConnectOutcome>));
+        let manager_slot_write = manager_slot.clone();
         
-        // Draw axes
-        let origin = to_screen(0.0, 0.0);
-        if min_x <= 0.0 && max_x >= 0.0 && min_y <= 0.0 && max_y >= 0.0 {
-            // X axis (red)
-            let x_end = to_screen(max_x, 0.0);
-            ui.painter().line_segment(
-                [origin, x_end],
-                Stroke::new(2.0, Color32::from_rgb(200, 50, 50)),
-            );
+        // Spawn connection task
+        tokio::spawn(async move {
+            let mut manager = ConnectionManager::with_config(connection, config);
             
-            // Y axis (green)
-            let y_end = to_screen(0.0, max_y);
-            ui.painter().line_segment(
-                [origin, y_end],
-                Stroke::new(2.0, Color32::from_rgb(50, 200, 50)),
-            );
+            match manager.connect(connect_timeout).await {
+                Ok(()) => {
+                    tracing::info!("Successfully connected to {}", port);
+                    *app_state.connected.write() = true;
+                    
+                    // Store the manager in the shared slot
+                    let manager_arc = Arc::new(TokioMutex::new(manager));
+                    *manager_slot_write.lock().await = Some(Ok(manager_arc));
+                }
+                Err(e) => {
+                    tracing::error!("Connection failed: {}", e);
+                    *app_state.connected.write() = false;
+                    *manager_slot_write.lock().await = Some(Err(e.to_string()));
+                }
+            }
+            ctx.request_repaint();
+        });
+        
+        // Store the manager slot so we can retrieve it in the update loop
+        self.pending_connection_manager = Some(manager_slot);
+        true
+    }
+    
+    /// Run due auto-connect attempts and watch the port for the device being replugged
+    fn poll_auto_connect(&mut self, ctx: &egui::Context) {
+        let now = std::time::Instant::now();
+        
+        let watch_port = self.auto_connect.is_watching()
+            && self.settings.connection.connection_type == ConnectionType::Serial
+            && self.last_port_check.map_or(true, |at| now.duration_since(at) >= PORT_WATCH_INTERVAL);
+        if watch_port {
+            self.last_port_check = Some(now);
+            let present = SerialConnection::list_ports()
+                .map(|ports| ports.iter().any(|p| p.port_name == self.selected_port))
+                .unwrap_or(false);
+            if self.auto_connect.port_checked(present, now) {
+                self.console.info(format!("{} is back, reconnecting", self.selected_port));
+                if self.connection_manager.is_some() {
+                    // Drop the dead link; disconnecting cancels auto-connect, so restart it
+                    self.disconnect_from_grbl();
+                    self.auto_connect.start(now);
+                }
+            }
         }
         
-        // Draw toolpath segments
-        for segment in &self.segments {
-            let start = to_screen(segment.start.x, segment.start.y);
-            let end = to_screen(segment.end.x, segment.end.y);
+        if self.connection_manager.is_none()
+            && self.pending_connection_manager.is_none()
+            && self.auto_connect.poll(now)
+        {
+            self.console.info(format!("Auto-connect attempt {}", self.auto_connect.attempts()));
+            if !self.connect_to_grbl(ctx) {
+                self.auto_connect.cancel();
+            }
+        }
+        
+        if let Some(wait) = self.auto_connect.next_attempt_in(now) {
+            ctx.request_repaint_after(wait);
+        }
+        if self.auto_connect.is_watching() {
+            ctx.request_repaint_after(PORT_WATCH_INTERVAL);
+        }
+    }
+    
+    /// Show auto-connect progress with a button to stop retrying
+    fn show_auto_connect_status(&mut self, ui: &mut egui::Ui) {
+        if !self.auto_connect.is_retrying() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.spinner();
+            let text = match self.auto_connect.next_attempt_in(std::time::Instant::now()) {
+                Some(wait) if !wait.is_zero() => format!(
+                    "Auto-connect: attempt {} failed, retrying in {:.0}s",
+                    self.auto_connect.attempts(),
+                    wait.as_secs_f32().ceil()
+                ),
+                _ => format!("Auto-connect: attempt {}", self.auto_connect.attempts().max(1)),
+            };
+            ui.label(text);
+            if ui.small_button("Cancel").clicked() {
+                self.auto_connect.cancel();
+                self.console.info("Auto-connect cancelled".to_string());
+            }
+        });
+    }
+
+    /// Report an error in the console and as a toast with code and recovery hint
+    fn report_error(&mut self, error: Error) {
+        self.console.error(format!("[{}] {}", error.code(), error));
+        self.error_presenter.report(&error);
+    }
+
+    /// Disconnect from GRBL device
+    fn disconnect_from_grbl(&mut self) {
+        self.auto_connect.cancel();
+        if let Some(manager) = self.connection_manager.take() {
+            self.status_message = "Disconnecting...".to_string();
+            self.console.info("Disconnecting from device".to_string());
             
-            // Color based on segment type
-            let (color, width) = match segment.segment_type {
-                SegmentType::Rapid => (Color32::from_rgb(255, 100, 100), 1.0), // Red for rapids
-                SegmentType::Linear => (Color32::from_rgb(100, 255, 100), 2.0), // Green for cuts
-                SegmentType::ArcCW | SegmentType::ArcCCW => {
-                    (Color32::from_rgb(100, 150, 255), 2.0) // Blue for arcs
+            // Spawn disconnect task
+            tokio::spawn(async move {
+                let mut mgr = manager.lock().await;
+                if let Err(e) = mgr.disconnect().await {
+                    tracing::error!("Error during disconnect: {}", e);
                 }
-            };
+            });
             
-            ui.painter().line_segment([start, end], Stroke::new(width, color));
+            *self.app_state.connected.write() = false;
+            self.event_receiver = None;
+            self.task_failure_receiver = None;
+            self.link_warning = None;
+            self.link_paused_program = false;
+            self.job_active_reported = false;
+            self.link_description = None;
+            self.startup_pending = false;
+            self.startup_replies.clear();
+            self.status_message = "Disconnected".to_string();
+            self.console.info("Disconnected".to_string());
+        }
+    }
+
+    /// Send a command to GRBL
+    fn send_command(&mut self, command: GrblCommand) {
+        if self.connection_manager.is_none() {
+            self.console.error("Not connected to device".to_string());
+            return;
         }
         
-        // Draw start point marker
-        if let Some(first) = self.segments.first() {
-            let start = to_screen(first.start.x, first.start.y);
-            ui.painter().circle_filled(start, 4.0, Color32::from_rgb(100, 255, 255));
-            ui.painter().circle_stroke(start, 4.0, Stroke::new(1.0, Color32::WHITE));
+        let command_str = command.format();
+        self.console.sent(command_str.trim().to_string());
+        
+        // Clone the manager and send the command via the connection manager
+        let manager = Arc::clone(self.connection_manager.as_ref().unwrap());
+        tokio::spawn(async move {
+            let mgr = manager.lock().await;
+            if let Err(e) = mgr.send_command(command).await {
+                tracing::error!("Failed to send command: {}", e);
+            }
+        });
+    }
+
+    /// Send commands in order from a single task
+    fn send_command_sequence(&mut self, commands: Vec<String>) -> Option<tokio::task::JoinHandle<()>> {
+        let Some(manager) = self.connection_manager.as_ref() else {
+            self.console.error("Not connected to device".to_string());
+            return None;
+        };
+        for command in &commands {
+            self.console.sent(command.clone());
+        }
+        
+        let manager = Arc::clone(manager);
+        Some(tokio::spawn(async move {
+            let mgr = manager.lock().await;
+            for command in commands {
+                if let Err(e) = mgr.send_command(GrblCommand::GCode(command)).await {
+                    tracing::error!("Failed to send command: {}", e);
+                    break;
+                }
+            }
+        }))
+    }
+    
+    /// Send the configured startup commands once GRBL has announced itself
+    fn send_startup_commands(&mut self) {
+        let commands = self.settings.startup_commands();
+        if commands.is_empty() {
+            return;
+        }
+        self.console.info(format!("Sending {} startup command(s)", commands.len()));
+        if self.send_command_sequence(commands.clone()).is_some() {
+            self.startup_replies.extend(commands);
+        }
+    }
+    
+    /// Carry out a button press on the pendant keypad
+    fn handle_pendant_action(&mut self, action: PendantAction) {
+        match action {
+            PendantAction::Jog([x, y, z]) => {
+                let step = self.settings.jog.current_step() * self.jog_unit_scale();
+                self.send_jog_command(x * step, y * step, z * step);
+            }
+            PendantAction::SelectStep(index) => self.select_jog_step(index),
+            PendantAction::Zero(axis) => self.send_zero_axis(axis),
+            PendantAction::ZeroAll => self.send_zero_all(),
+            PendantAction::Home => self.send_home_command(),
+            PendantAction::Unlock => self.send_unlock_command(),
+            PendantAction::Start => self.request_program_start(),
+            PendantAction::Hold => self.pause_program(),
+            PendantAction::Stop => self.stop_program(),
+            PendantAction::FeedOverride(percent) => {
+                self.feed_override = percent;
+                self.send_feed_override(percent);
+            }
+            PendantAction::SpindleOverride(percent) => {
+                self.spindle_override = percent;
+                self.send_spindle_override(percent);
+            }
+            PendantAction::Exit => {}
+        }
+    }
+    
+    /// Remember whether pendant mode is active for the next start
+    fn sync_pendant_mode(&mut self) {
+        if self.pendant.open != self.settings.ui.pendant_mode {
+            self.settings.ui.pendant_mode = self.pendant.open;
+            if let Err(e) = self.launch.save_settings(&self.settings) {
+                self.report_error(e.with_context("Failed to save settings"));
+            }
+        }
+    }
+    
+    /// Load a heightmap into the flatness report, or start or stop auto-leveling to it
+    fn handle_flatness_request(&mut self, request: FlatnessRequest) {
+        let (name, contents) = match request {
+            FlatnessRequest::StartLeveling => {
+                if let Some((name, map)) = self.flatness_panel.heightmap() {
+                    self.console.info(format!("Programs are auto-leveled to {}", name));
+                    self.leveling = Some((name.to_string(), map.clone()));
+                }
+                return;
+            }
+            FlatnessRequest::StopLeveling => {
+                self.leveling = None;
+                self.console.info("Auto-leveling off".to_string());
+                return;
+            }
+            FlatnessRequest::OpenFile => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Heightmap", &["map", "csv", "txt"])
+                    .add_filter("All Files", &["*"])
+                    .pick_file()
+                else {
+                    return;
+                };
+                let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                match std::fs::read_to_string(&path) {
+                    Ok(contents) => (name, contents),
+                    Err(e) => {
+                        self.report_error(Error::from(e).with_context("Failed to read heightmap"));
+                        return;
+                    }
+                }
+            }
+            FlatnessRequest::UseProjectHeightmap => match &self.project_dialog.project.heightmap {
+                Some(heightmap) => (heightmap.name.clone(), heightmap.contents.clone()),
+                None => return,
+            },
+        };
+        match Heightmap::parse(&contents) {
+            Ok(map) => self.flatness_panel.load(name, map),
+            Err(e) => self.report_error(e.with_context("Failed to load heightmap")),
+        }
+    }
+    
+    /// Move to a named position from the machine profile
+    fn go_to_position(&mut self, index: usize) {
+        let Some(position) = self.settings.machine.positions.get(index).cloned() else {
+            return;
+        };
+        let commands = self.settings.machine.go_to_commands(&position);
+        if self.send_command_sequence(commands).is_some() {
+            self.status_message = format!("Moving to {}", position.name);
+        }
+    }
+    
+    /// Store the current machine position as a named position
+    fn set_position_from_machine(&mut self, index: usize) {
+        let machine_position = self.app_state.machine.read().machine_position;
+        let Some(position) = self.settings.machine.positions.get_mut(index) else {
+            return;
+        };
+        position.position = [machine_position.x, machine_position.y, machine_position.z];
+        self.console.info(format!(
+            "{} set to X{:.3} Y{:.3} Z{:.3}",
+            position.name, machine_position.x, machine_position.y, machine_position.z
+        ));
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Measure the tool on the probe plate
+    fn measure_tool(&mut self) -> Option<tokio::task::JoinHandle<()>> {
+        let Some(commands) = self.settings.machine.tool_measure_commands() else {
+            self.console.warning("No probe plate position in the machine profile".to_string());
+            return None;
+        };
+        self.status_message = "Measuring tool".to_string();
+        let task = self.send_command_sequence(commands);
+        self.awaiting_tool_measurement = task.is_some();
+        task
+    }
+    
+    /// Log a probe result, applying a tool length offset after a tool measurement
+    fn record_probe_result(&mut self, result: ProbeResult) {
+        let tool_measurement = std::mem::take(&mut self.awaiting_tool_measurement);
+        let p = result.position;
+        let position = Position::new(p.x, p.y, p.z);
+        self.probe_log.record(position, result.success, tool_measurement);
+        if let Some(check) = self.checking_tool.take().filter(|_| tool_measurement) {
+            self.finish_tool_check(check, result.success.then_some(p.z));
+            return;
+        }
+        let tool_change = if tool_measurement { self.measuring_tool.take() } else { None };
+        let edge = if tool_measurement { None } else { self.probing_edge.take() };
+        
+        if !result.success {
+            self.console.warning("Probe did not make contact".to_string());
+            // Ask again rather than running on with an unknown tool length
+            self.tool_change = tool_change;
+            return;
+        }
+        self.console.info(format!("Probe: X{:.3} Y{:.3} Z{:.3}", p.x, p.y, p.z));
+        if let Some(edge) = edge {
+            self.set_edge_offset(edge, if edge.axis() == 'X' { p.x } else { p.y });
+        }
+        if !tool_measurement {
+            return;
+        }
+        let offset = self.tool_length.offset_for(p.z);
+        if offset.is_none() {
+            self.tool_length.reference_z = Some(p.z);
+            self.console.info(format!("Reference tool measured at Z{:.3}", p.z));
+        }
+        if tool_change.is_some() {
+            // Keep the offset ahead of the next program lines
+            let commands = offset.map(ToolLength::apply_command).into_iter().collect();
+            if let Some(offset) = offset {
+                self.tool_length.offset = offset;
+                self.console.info(format!("Tool length offset {:.3}", offset));
+            }
+            self.last_tool_check = Some(std::time::Instant::now());
+            self.release_tool_change(commands);
+        } else if let Some(offset) = offset {
+            let _ = self.apply_tool_length_offset(offset);
+        }
+    }
+    
+    /// Hold the program and measure the tool on the probe plate to detect breakage
+    ///
+    /// Returns false if the check can't run.
+    fn begin_tool_check(&mut self, check: ToolCheck) -> bool {
+        if self.tool_length.expected_z().is_none() {
+            self.console.warning("Tool check skipped: no reference tool measured".to_string());
+            return false;
+        }
+        let Some(measure) = self.settings.machine.tool_measure_commands() else {
+            self.console.warning("Tool check skipped: no probe plate position in the machine profile".to_string());
+            return false;
+        };
+        let mut commands = vec!["M5".to_string()];
+        commands.extend(measure);
+        let replies = commands.len();
+        let Some(task) = self.send_command_sequence(commands) else {
+            return false;
+        };
+        self.tool_change_replies += replies;
+        self.stream_task = Some(task);
+        self.awaiting_tool_measurement = true;
+        self.checking_tool = Some(check);
+        self.status_message = "Checking tool".to_string();
+        self.console.info("Checking the tool for breakage".to_string());
+        true
+    }
+    
+    /// Judge a tool breakage check from its probe result
+    fn finish_tool_check(&mut self, check: ToolCheck, probe_z: Option<f64>) {
+        let deviation = probe_z.and_then(|z| self.tool_length.deviation(z));
+        match deviation {
+            Some(deviation) if !self.settings.machine.tool_check.exceeded(deviation) => {
+                self.console.info(format!("Tool check passed ({:+.3} mm)", deviation));
+                self.continue_after_tool_check(check, None);
+            }
+            _ => {
+                let message = match deviation {
+                    Some(deviation) => format!("Tool check failed: the tool is {}", describe_deviation(deviation)),
+                    None => "Tool check failed: the tool did not reach the probe plate".to_string(),
+                };
+                self.console.error(message);
+                self.status_message = "Tool check failed, program held".to_string();
+                self.broken_tool = Some(BrokenTool { check, probe_z, deviation });
+            }
+        }
+    }
+    
+    /// Continue the program after a tool check, applying a new tool length offset if given
+    fn continue_after_tool_check(&mut self, check: ToolCheck, offset: Option<f64>) {
+        self.last_tool_check = Some(std::time::Instant::now());
+        match check {
+            ToolCheck::ToolChange(tool) => self.begin_tool_change(tool),
+            ToolCheck::Interval => {
+                let mut commands: Vec<String> = offset.map(ToolLength::apply_command).into_iter().collect();
+                commands.extend(self.tool_check_return_commands());
+                let replies = commands.len();
+                if let Some(task) = self.send_command_sequence(commands) {
+                    self.tool_change_replies += replies;
+                    self.stream_task = Some(task);
+                    if let Some(offset) = offset {
+                        self.tool_length.offset = offset;
+                        self.console.info(format!("Tool length offset {:.3}", offset));
+                    }
+                }
+                if let Some(streamer) = self.streamer.as_mut() {
+                    if let Some(motion) = streamer.sent_modes().motion {
+                        streamer.restore_motion(motion);
+                    }
+                    streamer.release_hold();
+                }
+                self.status_message = "Tool check complete, continuing".to_string();
+            }
+        }
+    }
+    
+    /// Commands returning from the probe plate to where the program left the cut
+    ///
+    /// The spindle is restarted above the last programmed position and given
+    /// time to spin up before the tool is lowered back to the cut depth. The
+    /// moves are absolute, in the program's units; the program's distance
+    /// mode and feed are restored after them.
+    fn tool_check_return_commands(&self) -> Vec<String> {
+        let Some(streamer) = self.streamer.as_ref() else {
+            return Vec::new();
+        };
+        let segment = streamer
+            .last_acknowledged_line()
+            .and_then(|line| self.segments.iter().rev().find(|s| s.source_line.is_some_and(|l| l <= line)));
+        let modes = streamer.sent_modes();
+        let mut commands = vec![format!("{} G90", if modes.inches { "G20" } else { "G21" })];
+        if let Some(segment) = segment {
+            commands.push(format!("G0 X{:.3} Y{:.3}", segment.end.x, segment.end.y));
+        }
+        if let Some(spindle) = streamer.spindle_restart() {
+            commands.push(spindle);
+            let delay = self.settings.machine.tool_check.spin_up_delay;
+            if delay > 0.0 {
+                commands.push(format!("G4 P{:.3}", delay));
+            }
+        }
+        if let Some(segment) = segment {
+            commands.push(match segment.segment_type {
+                SegmentType::Rapid => format!("G0 Z{:.3}", segment.end.z),
+                _ => format!("G1 Z{:.3} F{:.0}", segment.end.z, segment.feed_rate),
+            });
+        }
+        commands.extend(self.program_modes_command());
+        commands
+    }
+    
+    /// Show the alert for a failed tool breakage check
+    fn show_broken_tool(&mut self, ctx: &egui::Context) {
+        let Some(broken) = self.broken_tool else {
+            return;
+        };
+        let (mut again, mut accept, mut stop) = (false, false, false);
+        
+        egui::Window::new("⚠ Tool Check Failed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let text = match broken.deviation {
+                    Some(deviation) => format!("The tool is {}", describe_deviation(deviation)),
+                    None => "The tool did not reach the probe plate".to_string(),
+                };
+                ui.label(egui::RichText::new(text).strong().color(ui.visuals().error_fg_color));
+                ui.label("It may be broken or worn. The program is held at the probe plate.");
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    again = ui.button("🔁 Measure Again").clicked();
+                    let (label, hint) = match broken.check {
+                        ToolCheck::Interval => ("▶ Use Measured Length", "Apply the measured length as the tool length offset and continue"),
+                        ToolCheck::ToolChange(_) => ("▶ Continue to Tool Change", "Change the tool as programmed"),
+                    };
+                    accept = ui
+                        .add_enabled(broken.probe_z.is_some(), egui::Button::new(label))
+                        .on_hover_text(hint)
+                        .clicked();
+                    stop = ui.button("⏹ Stop Program").clicked();
+                });
+            });
+        
+        if again {
+            self.broken_tool = None;
+            if !self.begin_tool_check(broken.check) {
+                self.broken_tool = Some(broken);
+            }
+        } else if accept {
+            self.broken_tool = None;
+            let offset = broken.probe_z.and_then(|z| self.tool_length.offset_for(z));
+            self.continue_after_tool_check(broken.check, offset);
+        } else if stop {
+            self.broken_tool = None;
+            self.stop_program();
         }
     }
     
-    /// Apply theme (dark/light mode) to the UI
-    fn apply_theme(ctx: &egui::Context, dark_mode: bool) {
-        if dark_mode {
-            ctx.set_visuals(egui::Visuals::dark());
-        } else {
-            ctx.set_visuals(egui::Visuals::light());
+    /// Start an edge finding cycle
+    fn probe_edge(&mut self, edge: StockEdge) {
+        let commands = self.settings.machine.edge_finder(edge).probe_commands();
+        if self.send_command_sequence(commands).is_some() {
+            self.probing_edge = Some(edge);
+            self.status_message = format!("Probing {} edge", edge);
+            if let Err(e) = self.launch.save_settings(&self.settings) {
+                self.report_error(e.with_context("Failed to save settings"));
+            }
+        }
+    }
+    
+    /// Set the work zero on a found edge
+    fn set_edge_offset(&mut self, edge: StockEdge, trigger: f64) {
+        let finder = self.settings.machine.edge_finder(edge);
+        let (system, offset) = self.current_work_offset();
+        let axis = edge.axis();
+        let command = GrblCommand::GCode(finder.offset_command(system.p_number(), trigger));
+        self.action_log.record(
+            ActionKind::Zero,
+            format!("Edge {} ({})", edge, system),
+            command.clone(),
+            Some(offset_command(system, &axis.to_string(), offset)),
+        );
+        self.send_command(command);
+        self.console.info(format!(
+            "{} edge found at {}{:.3} (machine)",
+            edge,
+            axis,
+            finder.edge_position(trigger)
+        ));
+        self.status_message = format!("{} zeroed on {} edge", axis, edge);
+    }
+    
+    /// Apply a tool length offset with G43.1
+    fn apply_tool_length_offset(&mut self, offset: f64) -> Option<tokio::task::JoinHandle<()>> {
+        let task = self.send_command_sequence(vec![ToolLength::apply_command(offset)]);
+        if task.is_some() {
+            self.tool_length.offset = offset;
+            self.console.info(format!("Tool length offset {:.3}", offset));
+        }
+        task
+    }
+    
+    /// Handle a request from the probe log panel
+    fn handle_probe_log_request(&mut self, request: ProbeLogRequest) {
+        match request {
+            ProbeLogRequest::SetReference(id) => {
+                if let Some(record) = self.probe_log.get(id) {
+                    self.tool_length.reference_z = Some(record.position.z);
+                    self.console.info(format!("Reference tool set to Z{:.3}", record.position.z));
+                }
+            }
+            ProbeLogRequest::ApplyOffset(id) => {
+                let offset = self.probe_log.get(id).and_then(|r| self.tool_length.offset_for(r.position.z));
+                if let Some(offset) = offset {
+                    let _ = self.apply_tool_length_offset(offset);
+                }
+            }
+            ProbeLogRequest::ClearOffset => {
+                self.send_command(GrblCommand::GCode("G49".to_string()));
+                self.tool_length.offset = 0.0;
+            }
+            ProbeLogRequest::Clear => self.probe_log.clear(),
+        }
+    }
+    
+    /// Hold the program at an M6 and move to the tool change position
+    fn begin_tool_change(&mut self, tool: u32) {
+        self.tool_change = Some(tool);
+        self.console.info(format!("Tool change: insert T{}", tool));
+        self.status_message = format!("Tool change: T{}", tool);
+        match self.settings.machine.go_to_role_commands(PositionRole::ToolChange) {
+            Some(moves) => {
+                // The position is in millimeters; the program's units are put back on release
+                let mut commands = vec!["G21".to_string()];
+                commands.extend(moves);
+                self.tool_change_replies += commands.len();
+                self.stream_task = self.send_command_sequence(commands);
+            }
+            None => self.console.warning("No tool change position in the machine profile".to_string()),
+        }
+    }
+    
+    /// Continue the program after a tool change, measuring the tool first if enabled
+    fn finish_tool_change(&mut self, measure: bool) {
+        let Some(tool) = self.tool_change.take() else {
+            return;
+        };
+        if measure {
+            if let Some(commands) = self.settings.machine.tool_measure_commands() {
+                self.tool_change_replies += commands.len();
+            }
+            self.stream_task = self.measure_tool();
+            if self.stream_task.is_some() {
+                // Continue once the probe result has set the tool length offset
+                self.measuring_tool = Some(tool);
+                return;
+            }
+        }
+        self.release_tool_change(Vec::new());
+    }
+    
+    /// Make `tool` the active tool for wear tracking, resetting its cutting time for a new bit
+    fn change_worn_tool(&mut self, tool: u32, reset: bool) {
+        self.active_tool = tool;
+        self.last_wear_tick = None;
+        if reset {
+            self.settings.machine.tool_wear.reset(tool);
+            self.console.info(format!("Cutting time of T{} reset", tool));
+        }
+        self.apply_wear_reduction();
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Add cutting time to the active tool while the program runs with the spindle on
+    fn track_tool_wear(&mut self) {
+        let running = matches!(self.app_state.program.read().state, ExecutionState::Running);
+        let cutting = {
+            let machine_state = self.app_state.machine.read();
+            machine_state.status == MachineStatus::Run && machine_state.spindle_enabled
+        };
+        if !(self.settings.machine.tool_wear.enabled && running && cutting) {
+            self.last_wear_tick = None;
+            return;
+        }
+        let now = std::time::Instant::now();
+        if let Some(previous) = self.last_wear_tick.replace(now) {
+            let minutes = now.duration_since(previous).as_secs_f64() / 60.0;
+            self.settings.machine.tool_wear.add_cutting(self.active_tool, minutes);
+            self.apply_wear_reduction();
+        }
+    }
+    
+    /// Add to the spindle hours and distance traveled, warning when maintenance comes due
+    ///
+    /// Distance only counts while the machine reports motion, so a position
+    /// reset by a soft reset or unlock is not counted as travel.
+    fn track_runtime(&mut self) {
+        let (moving, spindle_on, position) = {
+            let machine_state = self.app_state.machine.read();
+            let moving = matches!(machine_state.status, MachineStatus::Run | MachineStatus::Jog | MachineStatus::Home);
+            let p = machine_state.machine_position;
+            (moving, machine_state.spindle_enabled, [p.x, p.y, p.z])
+        };
+        let now = std::time::Instant::now();
+        if let Some((previous, last_position)) = self.last_runtime_tick.replace((now, position)) {
+            let maintenance = &mut self.settings.machine.maintenance;
+            if spindle_on {
+                maintenance.spindle_hours += now.duration_since(previous).as_secs_f64() / 3600.0;
+            }
+            if moving {
+                let travel: f64 = position.iter().zip(last_position).map(|(a, b)| (a - b).powi(2)).sum();
+                maintenance.distance_m += travel.sqrt() / 1000.0;
+            }
+        }
+        
+        let due = self.settings.machine.maintenance.due();
+        if due.len() > self.maintenance_due {
+            let names: Vec<&str> = due.iter().map(|reminder| reminder.name.as_str()).collect();
+            let message = format!("Maintenance due: {}", names.join(", "));
+            self.console.warning(message.clone());
+            self.status_message = message;
+        }
+        self.maintenance_due = due.len();
+    }
+    
+    /// Write the job journal every few seconds while a program runs, and
+    /// remove it once the run ends
+    fn update_job_journal(&mut self) {
+        let running = matches!(
+            self.app_state.program.read().state,
+            ExecutionState::Running | ExecutionState::Paused
+        );
+        if !running || self.streamer.is_none() {
+            if self.job_journal.take().is_some() {
+                if let Err(e) = JobJournal::remove_default() {
+                    tracing::warn!("Failed to remove job journal: {}", e);
+                }
+            }
+            return;
+        }
+        let now = std::time::Instant::now();
+        if self
+            .job_journal
+            .as_ref()
+            .is_some_and(|(_, written)| now.duration_since(*written) < JOURNAL_INTERVAL)
+        {
+            return;
+        }
+        self.write_job_journal();
+    }
+    
+    /// Write the job journal for the streaming program now
+    fn write_job_journal(&mut self) {
+        let Some(streamer) = self.streamer.as_ref() else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        let mut journal = match self.job_journal.take() {
+            Some((journal, _)) => journal,
+            None => JobJournal::new(self.armed_path().cloned(), self.armed_content()),
+        };
+        journal.acknowledged_line = streamer.last_acknowledged_line().unwrap_or(0);
+        journal.safe_line = streamer.safe_resume_line(PLANNER_BLOCKS);
+        {
+            let machine_state = self.app_state.machine.read();
+            let offset = machine_state.active_work_offset();
+            journal.coordinate_system = machine_state.coordinate_system.to_string();
+            journal.work_offset = [offset.x, offset.y, offset.z];
+        }
+        journal.feed_override = self.feed_override;
+        journal.spindle_override = self.spindle_override;
+        journal.rapid_override = self.rapid_override;
+        journal.updated = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        // Logged rather than reported, as a failure would repeat every few seconds
+        if let Err(e) = journal.save_default() {
+            tracing::warn!("Failed to write job journal: {}", e);
+        }
+        self.job_journal = Some((journal, now));
+    }
+    
+    /// Tell the connection manager whether a program is streaming, so a
+    /// stale link aborts the job rather than reconnecting in the middle of it
+    fn sync_job_activity(&mut self) {
+        let active = self.program_in_progress();
+        if active == self.job_active_reported {
+            return;
+        }
+        let Some(ref manager) = self.connection_manager else {
+            return;
+        };
+        self.job_active_reported = active;
+        let manager = Arc::clone(manager);
+        tokio::spawn(async move {
+            manager.lock().await.set_job_active(active);
+        });
+    }
+    
+    /// Point to the crash report the last run left
+    fn show_crash_report(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.crash_report.clone() else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("⚠ rCandle Crashed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label("rCandle closed unexpectedly last time. A crash report was saved to:");
+                ui.monospace(path.display().to_string());
+                ui.weak("It holds the error, the end of the log and your settings with hosts and passwords removed. \
+                    Attach it to an issue to help get the crash fixed; nothing has been sent.");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy Path").clicked() {
+                        ctx.copy_text(path.display().to_string());
+                    }
+                    if ui.button("🐞 Report Issue").clicked() {
+                        ctx.open_url(egui::OpenUrl::new_tab(crash::ISSUE_URL));
+                    }
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if close {
+            self.crash_report = None;
+        }
+    }
+    
+    /// Offer to resume a job whose run did not end, found at startup
+    fn show_interrupted_job(&mut self, ctx: &egui::Context) {
+        let Some(job) = self.interrupted_job.as_mut() else {
+            return;
+        };
+        let mut resume = false;
+        let mut discard = false;
+        let name = job
+            .file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map_or("an unsaved program".to_string(), |name| name.to_string_lossy().to_string());
+        let file_exists = job.file.as_ref().is_some_and(|path| path.exists());
+        
+        egui::Window::new("⚠ Interrupted Job")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "The last run of {} did not finish. It had reached line {} of {} at {}.",
+                    name,
+                    job.acknowledged_line + 1,
+                    job.total_lines,
+                    job.updated
+                ));
+                egui::Grid::new("interrupted_job_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Work offset:");
+                        ui.monospace(format!(
+                            "{} X{:.3} Y{:.3} Z{:.3}",
+                            job.coordinate_system, job.work_offset[0], job.work_offset[1], job.work_offset[2]
+                        ));
+                        ui.end_row();
+                        
+                        ui.label("Overrides:");
+                        ui.label(format!(
+                            "Feed {:.0}%  Spindle {:.0}%  Rapid {:.0}%",
+                            job.feed_override, job.spindle_override, job.rapid_override
+                        ));
+                        ui.end_row();
+                        
+                        ui.label("Resume at line:");
+                        let mut line = job.safe_line + 1;
+                        ui.add(egui::DragValue::new(&mut line).range(1..=job.total_lines.max(1)))
+                            .on_hover_text("Lines the controller had buffered but maybe not run are repeated");
+                        job.safe_line = line - 1;
+                        ui.end_row();
+                    });
+                ui.weak("If the controller lost power, home the machine and check the work zero before resuming.");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(file_exists, egui::Button::new("▶ Load for Resume"))
+                        .on_disabled_hover_text("The program file is missing")
+                        .clicked()
+                    {
+                        resume = true;
+                    }
+                    if ui.button("Discard").clicked() {
+                        discard = true;
+                    }
+                });
+            });
+        
+        if resume {
+            let job = self.interrupted_job.take().unwrap_or_default();
+            if let Some(path) = job.file.clone() {
+                self.load_file(path);
+            }
+            if job.matches(&self.gcode_content) {
+                let offset = self.app_state.machine.read().active_work_offset();
+                let moved = [offset.x, offset.y, offset.z]
+                    .iter()
+                    .zip(job.work_offset)
+                    .any(|(now, then)| (now - then).abs() > 0.001);
+                if moved {
+                    self.console.warning("The work offset differs from the interrupted run; check the work zero".to_string());
+                }
+                if !self.program_in_progress() {
+                    self.armed_document = self.active_document;
+                }
+                self.status_message = format!("Press Run to resume at line {}", job.safe_line + 1);
+                self.console.info(self.status_message.clone());
+                self.resume_job = Some(job);
+            } else {
+                self.console.error("The program changed since the interrupted run; it cannot be resumed".to_string());
+                self.status_message = "Cannot resume: program changed".to_string();
+            }
+        } else if discard {
+            self.interrupted_job = None;
+            if let Err(e) = JobJournal::remove_default() {
+                self.report_error(e.with_context("Failed to remove job journal"));
+            }
+        }
+    }
+    
+    /// Lower or restore the feed override to match the wear of the active tool
+    fn apply_wear_reduction(&mut self) {
+        let wear = &self.settings.machine.tool_wear;
+        let reduction = wear.reduction(self.active_tool);
+        if (reduction - self.wear_reduction).abs() < 0.5 {
+            return;
+        }
+        let minutes = wear.cutting_minutes(self.active_tool);
+        let target = (self.feed_override - (reduction - self.wear_reduction)).clamp(10.0, 200.0);
+        self.wear_reduction = reduction;
+        self.feed_override = target;
+        self.send_feed_override(target);
+        if reduction > 0.0 {
+            self.console.warning(format!(
+                "T{} has cut for {:.0} min, feed override lowered to {:.0}% for wear",
+                self.active_tool, minutes, target
+            ));
+            // Keep the cutting time if the application is closed mid-job
+            if let Err(e) = self.launch.save_settings(&self.settings) {
+                self.report_error(e.with_context("Failed to save settings"));
+            }
+        }
+    }
+    
+    /// Let the program stream past the held tool change
+    ///
+    /// `commands` are sent first, then the program's modes are put back, as
+    /// the tool change moves and measurement set their own.
+    fn release_tool_change(&mut self, mut commands: Vec<String>) {
+        commands.extend(self.program_modes_command());
+        let replies = commands.len();
+        if let Some(task) = self.send_command_sequence(commands) {
+            self.tool_change_replies += replies;
+            self.stream_task = Some(task);
+        }
+        if let Some(streamer) = self.streamer.as_mut() {
+            if let Some(motion) = streamer.sent_modes().motion {
+                streamer.restore_motion(motion);
+            }
+            streamer.release_tool_change();
+        }
+        self.console.info("Tool change complete, continuing".to_string());
+    }
+    
+    /// Command putting back the units, distance mode and feed the sent program lines left
+    fn program_modes_command(&self) -> Option<String> {
+        let modes = self.streamer.as_ref()?.sent_modes();
+        let mut words = vec![
+            if modes.inches { "G20" } else { "G21" }.to_string(),
+            if modes.incremental { "G91" } else { "G90" }.to_string(),
+        ];
+        words.extend(modes.feed.map(|feed| format!("F{}", feed)));
+        Some(words.join(" "))
+    }
+    
+    /// Show the tool change prompt while the program waits at an M6
+    fn show_tool_change(&mut self, ctx: &egui::Context) {
+        let Some(tool) = self.tool_change else {
+            return;
+        };
+        let mut measure = self.settings.machine.measure_after_tool_change;
+        let mut choice = None;
+        
+        egui::Window::new("🔧 Tool Change")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(format!("Insert tool T{}", tool)).strong());
+                ui.label("The program continues when the new tool is fitted.");
+                ui.add_space(5.0);
+                let wear = &self.settings.machine.tool_wear;
+                if wear.enabled {
+                    let minutes = wear.cutting_minutes(tool);
+                    ui.label(format!("T{} has cut for {:.0} min", tool, minutes));
+                    ui.add_enabled(
+                        minutes > 0.0,
+                        egui::Checkbox::new(&mut self.reset_tool_wear, "New bit: reset its cutting time"),
+                    );
+                }
+                ui.add_enabled(
+                    self.settings.machine.position(PositionRole::ProbePlate).is_some(),
+                    egui::Checkbox::new(&mut measure, "Measure tool on the probe plate"),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Continue").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("⏹ Stop Program").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        
+        self.settings.machine.measure_after_tool_change = measure;
+        match choice {
+            Some(true) => {
+                let reset = std::mem::take(&mut self.reset_tool_wear);
+                self.change_worn_tool(tool, reset);
+                self.finish_tool_change(measure);
+            }
+            Some(false) => self.stop_program(),
+            None => {}
+        }
+    }
+    
+    /// Show the Z-limit guard while the program holds before a move below the lowest Z
+    fn show_z_limit_hold(&mut self, ctx: &egui::Context) {
+        let Some(violation) = self.z_limit_holds.first().copied() else {
+            return;
+        };
+        if !self.streamer.as_ref().is_some_and(|streamer| streamer.is_stopped()) {
+            return;
+        }
+        let mut choice = None;
+        
+        egui::Window::new("⚠ Z Limit Guard")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("The program is held before an auto-leveled move below the lowest allowed Z.").strong());
+                ui.add_space(5.0);
+                ui.label(violation.to_string());
+                ui.weak("A probe point far below its neighbours usually means a missed or false contact.");
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("⏹ Stop Program").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Continue Anyway").on_hover_text("Send the move as leveled").clicked() {
+                        choice = Some(true);
+                    }
+                });
+            });
+        
+        match choice {
+            Some(true) => {
+                // Hold again before the next move below the limit
+                self.z_limit_holds.remove(0);
+                if let Some(streamer) = self.streamer.as_mut() {
+                    match self.z_limit_holds.first() {
+                        Some(next) => streamer.advance_stop(next.line),
+                        None => streamer.clear_stop(),
+                    }
+                }
+                self.console.warning(format!("Continuing past the Z limit at line {}", violation.line + 1));
+            }
+            Some(false) => self.stop_program(),
+            None => {}
+        }
+    }
+    
+    /// Show the comment of a marker the program paused at
+    fn show_comment_prompt(&mut self, ctx: &egui::Context) {
+        let Some((line, comment)) = self.comment_prompt.clone() else {
+            return;
+        };
+        if self.app_state.program.read().state != ExecutionState::Paused {
+            self.comment_prompt = None;
+            return;
+        }
+        let mut choice = None;
+        
+        egui::Window::new("⏸ Program Paused")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.weak(format!("Line {}", line + 1));
+                ui.label(egui::RichText::new(&comment).heading());
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Continue").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("⏹ Stop Program").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        
+        match choice {
+            Some(true) => {
+                self.comment_prompt = None;
+                self.start_program();
+            }
+            Some(false) => self.stop_program(),
+            None => {}
+        }
+    }
+    
+    /// Handle console command submission
+    
+    /// Send jog command for manual positioning
+    fn send_jog_command(&mut self, x: f64, y: f64, z: f64) {
+        let mut requested = [x, y, z];
+        for (distance, &locked) in requested.iter_mut().zip(&self.jog_axis_locks) {
+            if locked {
+                *distance = 0.0;
+            }
+        }
+        if requested == [0.0; 3] {
+            self.status_message = "Jog blocked: axis locked".to_string();
+            return;
+        }
+        let [x, y, z] = requested;
+        let spindle_running = self.app_state.machine.read().spindle_enabled;
+        if self.settings.machine.spindle_jog_interlocked(spindle_running, z) {
+            if self.settings.machine.spindle_jog_interlock == SpindleJogInterlock::Block {
+                self.console.warning(format!("Jog Z{:.3} blocked: spindle is running", z));
+                self.status_message = "Jog blocked: spindle is running".to_string();
+            } else {
+                self.pending_spindle_jog = Some(requested);
+            }
+            return;
+        }
+        self.jog_within_limits(x, y, z);
+    }
+    
+    /// Jog step adjusted for the held modifier keys, in display units
+    fn modified_jog_step(&self, modifiers: egui::Modifiers) -> f64 {
+        JogSettings::modified_step(self.settings.jog.current_step(), modifiers.shift, modifiers.command)
+    }
+    
+    /// Millimeters per display unit for jog steps and feeds
+    fn jog_unit_scale(&self) -> f64 {
+        if self.settings.general.units_metric { 1.0 } else { MM_PER_INCH }
+    }
+    
+    /// Select a jog step and remember it for the next session
+    fn select_jog_step(&mut self, index: usize) {
+        if self.settings.jog.default_step_index == index {
+            return;
+        }
+        self.settings.jog.default_step_index = index;
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Jog in XY to the center of the machine travel
+    fn jog_to_work_center(&mut self) {
+        if !self.settings.jog.limit_to_travel {
+            return;
+        }
+        let [x, y, _] = self.settings.jog.travel.center();
+        let position = self.app_state.machine.read().machine_position;
+        self.send_jog_command(x - position.x, y - position.y, 0.0);
+    }
+    
+    /// Jog one axis to the edge of the jog limits in a single move
+    ///
+    /// Saves step jogging when squaring stock against a rail. The move goes
+    /// through the usual axis locks and interlocks.
+    fn jog_to_edge(&mut self, axis: usize, toward_max: bool) {
+        let Some(limits) = self.settings.jog.jog_limits() else {
+            return;
+        };
+        let position = self.app_state.machine.read().machine_position;
+        let [x, y, z] = limits.edge_jog([position.x, position.y, position.z], axis, toward_max, self.settings.jog.edge_margin);
+        if [x, y, z] == [0.0; 3] {
+            self.status_message = "Already at the edge".to_string();
+            return;
+        }
+        self.send_jog_command(x, y, z);
+    }
+    
+    /// Jog from the arrow keys (XY) and Page Up/Down (Z)
+    ///
+    /// Only active when enabled in the settings, while connected and not
+    /// streaming, and when no text field has keyboard focus. Key repeats are
+    /// ignored so holding a key does not queue a burst of jogs.
+    fn handle_keyboard_jog(&mut self, ctx: &egui::Context) {
+        if !self.settings.jog.keyboard_jog
+            || self.connection_manager.is_none()
+            || self.streamer.is_some()
+            || ctx.wants_keyboard_input()
+        {
+            return;
+        }
+        
+        let jogs: Vec<([f64; 3], egui::Modifiers)> = ctx.input(|i| {
+            i.events
+                .iter()
+                .filter_map(|event| match event {
+                    egui::Event::Key { key, pressed: true, repeat: false, modifiers, .. } => {
+                        let direction = match key {
+                            egui::Key::ArrowLeft => [-1.0, 0.0, 0.0],
+                            egui::Key::ArrowRight => [1.0, 0.0, 0.0],
+                            egui::Key::ArrowUp => [0.0, 1.0, 0.0],
+                            egui::Key::ArrowDown => [0.0, -1.0, 0.0],
+                            egui::Key::PageUp => [0.0, 0.0, 1.0],
+                            egui::Key::PageDown => [0.0, 0.0, -1.0],
+                            _ => return None,
+                        };
+                        Some((direction, *modifiers))
+                    }
+                    _ => None,
+                })
+                .collect()
+        });
+        
+        for ([x, y, z], modifiers) in jogs {
+            let step = self.modified_jog_step(modifiers) * self.jog_unit_scale();
+            self.send_jog_command(x * step, y * step, z * step);
+        }
+    }
+    
+    /// Send a jog, asking first if the soft limits would shorten it
+    fn jog_within_limits(&mut self, x: f64, y: f64, z: f64) {
+        let requested = [x, y, z];
+        if let Some(limits) = self.settings.jog.jog_limits() {
+            let position = {
+                let machine_state = self.app_state.machine.read();
+                let pos = machine_state.machine_position;
+                [pos.x, pos.y, pos.z]
+            };
+            let clamped = limits.clamp_jog(position, requested);
+            if clamped != requested {
+                // Ask before shortening (or overriding) the move
+                self.pending_jog = Some(PendingJog { requested, clamped });
+                return;
+            }
+        }
+        self.issue_jog(x, y, z);
+    }
+    
+    /// Send a jog move without checking the soft limits
+    fn issue_jog(&mut self, x: f64, y: f64, z: f64) {
+        let feed_rate = if z != 0.0 {
+            self.settings.jog.z_feed_rate
+        } else {
+            self.settings.jog.xy_feed_rate
+        } * self.jog_unit_scale();
+        
+        let jog = |x: f64, y: f64, z: f64| GrblCommand::Jog {
+            x: if x != 0.0 { Some(x) } else { None },
+            y: if y != 0.0 { Some(y) } else { None },
+            z: if z != 0.0 { Some(z) } else { None },
+            feed_rate,
+        };
+        let command = jog(x, y, z);
+        
+        if self.connection_manager.is_some() {
+            self.action_log.record(
+                ActionKind::Jog,
+                format!("Jog X{:.3} Y{:.3} Z{:.3}", x, y, z),
+                command.clone(),
+                Some(jog(-x, -y, -z)),
+            );
+        }
+        self.send_command(command);
+        self.status_message = format!("Jogging: X{:.3} Y{:.3} Z{:.3}", x, y, z);
+        tracing::info!("Jog command: X{:.3} Y{:.3} Z{:.3}", x, y, z);
+    }
+    
+    /// Ask before a Z-minus jog while the spindle is running
+    fn show_pending_spindle_jog(&mut self, ctx: &egui::Context) {
+        let Some([x, y, z]) = self.pending_spindle_jog else {
+            return;
+        };
+        let mut proceed = false;
+        
+        egui::Window::new("⚠ Spindle Running")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("The spindle is running. Jog Z down by {:.3}?", -z));
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("⚠ Jog Anyway").clicked() {
+                        proceed = true;
+                    }
+                    if ui.button("❌ Cancel").clicked() {
+                        self.pending_spindle_jog = None;
+                    }
+                });
+            });
+        
+        if proceed {
+            self.pending_spindle_jog = None;
+            self.console.warning(format!("Spindle interlock overridden: jog Z{:.3}", z));
+            self.jog_within_limits(x, y, z);
+        }
+    }
+    
+    /// Ask whether to shorten or override a jog held back by the soft limits
+    fn show_pending_jog(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.pending_jog else {
+            return;
+        };
+        let format_move = |d: [f64; 3]| format!("X{:.3} Y{:.3} Z{:.3}", d[0], d[1], d[2]);
+        let can_clamp = pending.clamped.iter().any(|d| d.abs() > 1e-9);
+        let mut choice = None;
+        
+        egui::Window::new("⚠ Jog Limit")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Requested jog: {}", format_move(pending.requested)));
+                if can_clamp {
+                    ui.label(format!("Limited to: {}", format_move(pending.clamped)));
+                } else {
+                    ui.label("The axis is already at its limit.");
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(can_clamp, egui::Button::new("✔ Jog to Limit")).clicked() {
+                        choice = Some(pending.clamped);
+                    }
+                    if ui.button("⚠ Override").on_hover_text("Jog the full distance").clicked() {
+                        choice = Some(pending.requested);
+                    }
+                    if ui.button("❌ Cancel").clicked() {
+                        self.pending_jog = None;
+                    }
+                });
+            });
+        
+        if let Some([x, y, z]) = choice {
+            self.pending_jog = None;
+            if pending.requested != [x, y, z] {
+                self.console.info(format!("Jog limited to {}", format_move([x, y, z])));
+            } else {
+                self.console.warning(format!("Jog limit overridden: {}", format_move([x, y, z])));
+            }
+            self.issue_jog(x, y, z);
+        }
+    }
+    
+    /// Send home command ($H)
+    fn send_home_command(&mut self) {
+        let command = GrblCommand::HomingCycle;
+        if self.connection_manager.is_some() {
+            self.action_log.record(ActionKind::Home, "Homing cycle", command.clone(), None);
+        }
+        self.send_command(command);
+        self.status_message = "Homing...".to_string();
+        tracing::info!("Home command");
+    }
+    
+    /// Move to a G28/G30 reference position, raising Z first
+    fn go_to_reference(&mut self, reference: ReferencePosition) {
+        let code = reference.code();
+        let commands = vec![
+            format!("G91 {} Z0", code),
+            format!("{} X0 Y0", code),
+            "G90".to_string(),
+        ];
+        if self.send_command_sequence(commands).is_some() {
+            self.status_message = format!("Moving to {} position", code);
+        }
+    }
+    
+    /// Ask the controller for its stored positions and offsets ($#)
+    fn read_parameters(&mut self) {
+        self.send_command(GrblCommand::GetParameters);
+    }
+    
+    /// Confirm storing the current position as a G28/G30 reference position
+    fn show_pending_reference(&mut self, ctx: &egui::Context) {
+        let Some(reference) = self.pending_reference else {
+            return;
+        };
+        let (current, stored) = {
+            let machine = self.app_state.machine.read();
+            let stored = match reference {
+                ReferencePosition::G28 => machine.g28_position,
+                ReferencePosition::G30 => machine.g30_position,
+            };
+            (machine.machine_position, stored)
+        };
+        let code = reference.code();
+        let mut confirmed = false;
+        
+        egui::Window::new(format!("📍 Set {} Position", code))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Store the current machine position as the {} position?", code));
+                ui.add_space(5.0);
+                egui::Grid::new("pending_reference_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("New:");
+                        ui.monospace(format!("X{:.3} Y{:.3} Z{:.3}", current.x, current.y, current.z));
+                        ui.end_row();
+                        ui.label("Stored:");
+                        match stored {
+                            Some(p) => ui.monospace(format!("X{:.3} Y{:.3} Z{:.3}", p.x, p.y, p.z)),
+                            None => ui.weak("Not read"),
+                        };
+                        ui.end_row();
+                    });
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("✔ Set").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("❌ Cancel").clicked() {
+                        self.pending_reference = None;
+                    }
+                });
+            });
+        
+        if confirmed {
+            self.pending_reference = None;
+            let commands = vec![format!("{}.1", code), "$#".to_string()];
+            if self.send_command_sequence(commands).is_some() {
+                self.console.info(format!(
+                    "{} position set to X{:.3} Y{:.3} Z{:.3}",
+                    code, current.x, current.y, current.z
+                ));
+            }
+        }
+    }
+    
+    /// Send unlock command ($X) to clear alarm state
+    fn send_unlock_command(&mut self) {
+        // Send directly to device, bypassing the command queue
+        let manager_opt = self.connection_manager.clone();
+        self.status_message = "Unlocking alarm...".to_string();
+        self.console.info("Sending unlock command ($X)".to_string());
+        tracing::info!("Unlock command ($X)");
+
+        if let Some(manager) = manager_opt {
+            // Spawn an async task to send raw bytes via send_realtime on the manager
+            tokio::spawn(async move {
+                let bytes = b"$X\n";
+                for &b in bytes.iter() {
+                    let _ = manager.lock().await.send_realtime(b).await;
+                    // Small delay between bytes to avoid overwhelming device
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                }
+                tracing::info!("Unlock sequence sent");
+            });
+        } else {
+            self.console.error("Not connected: cannot send unlock sequence".to_string());
+        }
+    }
+    
+    /// Zero a specific axis
+    fn send_zero_axis(&mut self, axis: char) {
+        let gcode = format!("G10 L20 P0 {}0", axis);
+        let command = GrblCommand::GCode(gcode.clone());
+        
+        // Remember the offset being replaced so the zero can be undone
+        let (system, offset) = self.current_work_offset();
+        if self.connection_manager.is_some() {
+            let machine = self.app_state.machine.read().machine_position;
+            self.action_log.record_zero(
+                format!("Zero {} ({})", axis, system),
+                system,
+                &axis.to_string(),
+                offset,
+                machine,
+            );
+        }
+        self.send_command(command);
+        self.status_message = format!("Zeroing {} axis", axis);
+        tracing::info!("Zero axis: {}", axis);
+    }
+    
+    /// Zero all axes
+    fn send_zero_all(&mut self) {
+        let gcode = "G10 L20 P0 X0 Y0 Z0".to_string();
+        let command = GrblCommand::GCode(gcode.clone());
+        
+        let (system, offset) = self.current_work_offset();
+        if self.connection_manager.is_some() {
+            let machine = self.app_state.machine.read().machine_position;
+            self.action_log.record_zero(format!("Zero all ({})", system), system, "XYZ", offset, machine);
+        }
+        self.send_command(command);
+        self.status_message = "Zeroing all axes".to_string();
+        tracing::info!("Zero all axes");
+    }
+    
+    /// Save the active work offset in a named slot of the machine profile
+    fn save_work_zero_slot(&mut self, name: String) {
+        let (system, offset) = self.current_work_offset();
+        if !self.settings.machine.save_work_zero(&name, [offset.x, offset.y, offset.z]) {
+            self.console.warning(format!("All {} work zero slots are in use", MAX_WORK_ZERO_SLOTS));
+            return;
+        }
+        self.console.info(format!(
+            "Saved {} zero as '{}' (X{:.3} Y{:.3} Z{:.3})",
+            system, name, offset.x, offset.y, offset.z
+        ));
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Restore a saved work zero into the active coordinate system
+    fn restore_work_zero_slot(&mut self, index: usize) {
+        let Some(slot) = self.settings.machine.work_zero_slots.get(index).cloned() else {
+            return;
+        };
+        let (system, offset) = self.current_work_offset();
+        let command = GrblCommand::GCode(slot.restore_command(system.p_number()));
+        if self.connection_manager.is_some() {
+            self.action_log.record(
+                ActionKind::Zero,
+                format!("Restore '{}' ({})", slot.name, system),
+                command.clone(),
+                Some(offset_command(system, "XYZ", offset)),
+            );
+        }
+        self.send_command(command);
+        self.status_message = format!("Restored work zero '{}'", slot.name);
+    }
+    
+    /// Use, capture or save a fixture of the library
+    fn handle_fixture_action(&mut self, action: FixtureAction) {
+        let (system, offset) = self.current_work_offset();
+        match action {
+            FixtureAction::Use(index) => {
+                let Some(fixture) = self.settings.machine.fixtures.get(index).cloned() else {
+                    return;
+                };
+                let command = GrblCommand::GCode(fixture.apply_command(system.p_number()));
+                self.action_log.record(
+                    ActionKind::Zero,
+                    format!("Use fixture '{}' ({})", fixture.name, system),
+                    command.clone(),
+                    Some(offset_command(system, "XYZ", offset)),
+                );
+                self.send_command(command);
+                self.fixture_panel.selected = Some(index);
+                self.status_message = format!("{} zero set to fixture '{}'", system, fixture.name);
+                return;
+            }
+            FixtureAction::SetZero(index) => {
+                let Some(fixture) = self.settings.machine.fixtures.get_mut(index) else {
+                    return;
+                };
+                fixture.zero = [offset.x, offset.y, offset.z];
+                self.console.info(format!("Fixture '{}' zero set from {}", fixture.name, system));
+            }
+            FixtureAction::Add => {
+                let name = format!("Fixture {}", self.settings.machine.fixtures.len() + 1);
+                self.settings.machine.fixtures.push(Fixture::new(name, FixtureKind::Vise, [offset.x, offset.y, offset.z]));
+                self.fixture_panel.selected = Some(self.settings.machine.fixtures.len() - 1);
+            }
+            FixtureAction::Changed => {}
+        }
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Send work coordinate system command
+    fn send_wcs_command(&mut self, wcs: u32) {
+        let command = GrblCommand::GCode(format!("G{}", wcs));
+        if self.connection_manager.is_some() {
+            let previous = self.app_state.machine.read().coordinate_system;
+            self.action_log.record(
+                ActionKind::CoordinateSystem,
+                format!("Switch {} to G{}", previous, wcs),
+                command.clone(),
+                Some(GrblCommand::GCode(previous.to_string())),
+            );
+        }
+        self.send_command(command);
+        self.status_message = format!("Switching to G{}", wcs);
+        tracing::info!("WCS command: G{}", wcs);
+    }
+    
+    /// Active coordinate system and its offset from machine zero
+    fn current_work_offset(&self) -> (CoordinateSystem, Position) {
+        let machine_state = self.app_state.machine.read();
+        (machine_state.coordinate_system, machine_state.active_work_offset())
+    }
+    
+    /// Undo or redo a logged action
+    fn handle_action_log_request(&mut self, request: ActionLogRequest) {
+        let command = match request {
+            ActionLogRequest::Undo(id) => self.action_log.undo(id),
+            ActionLogRequest::Redo(id) => self.action_log.redo(id),
+            ActionLogRequest::Clear => {
+                self.action_log.clear();
+                return;
+            }
+        };
+        if let Some(command) = command {
+            let verb = if matches!(request, ActionLogRequest::Undo(_)) { "Undo" } else { "Redo" };
+            self.console.info(format!("{}: {}", verb, command.format().trim()));
+            self.send_command(command);
+        }
+    }
+    
+    /// Handle a connection event from the connection manager
+    fn handle_connection_event(&mut self, event: ConnectionEvent) {
+        match event {
+            ConnectionEvent::StatusStale { missed } => {
+                let warning = format!("No status from controller for {} intervals - streaming paused", missed);
+                self.console.warning(warning.clone());
+                self.link_warning = Some(warning);
+                
+                let mut program_state = self.app_state.program.write();
+                if matches!(program_state.state, ExecutionState::Running) {
+                    program_state.state = ExecutionState::Paused;
+                    self.program_paused_time = Some(std::time::Instant::now());
+                    self.link_paused_program = true;
+                    self.status_message = "Program paused: controller not responding".to_string();
+                }
+            }
+            ConnectionEvent::Reconnecting { attempt } => {
+                let warning = format!("Controller not responding - reconnecting (attempt {})", attempt);
+                self.console.warning(warning.clone());
+                self.link_warning = Some(warning);
+            }
+            ConnectionEvent::StatusRestored => {
+                self.console.info("Status reports restored".to_string());
+                if self.link_paused_program {
+                    self.link_warning = Some("Link restored - press Resume to continue the program".to_string());
+                } else {
+                    // Nothing was running, so let queued commands through again
+                    self.link_warning = None;
+                    self.resume_command_queue();
+                }
+            }
+            ConnectionEvent::JobAborted => {
+                let message = "Controller stopped responding while streaming - job aborted".to_string();
+                self.report_error(Error::Connection(message.clone()));
+                self.link_warning = Some("Job aborted - reset the program, then resume it from the interrupted job prompt".to_string());
+                
+                // Hand the journal to the interrupted job prompt instead of removing it
+                self.write_job_journal();
+                if let Some((journal, _)) = self.job_journal.take() {
+                    self.interrupted_job = Some(journal);
+                }
+                self.abort_program_stream();
+                self.app_state.program.write().state = ExecutionState::Error;
+                self.program_start_time = None;
+                self.program_paused_time = None;
+                self.link_paused_program = false;
+                self.status_message = "Program aborted: controller not responding".to_string();
+            }
+            ConnectionEvent::Error(message) => {
+                self.report_error(Error::Connection(message.clone()));
+                self.link_warning = Some(message);
+            }
+            ConnectionEvent::Connected | ConnectionEvent::Disconnected | ConnectionEvent::DataReceived(_) => {}
+        }
+    }
+    
+    /// Resume the connection manager's command queue
+    fn resume_command_queue(&mut self) {
+        if let Some(ref manager) = self.connection_manager {
+            let manager = Arc::clone(manager);
+            tokio::spawn(async move {
+                let mgr = manager.lock().await;
+                if let Err(e) = mgr.resume().await {
+                    tracing::error!("Failed to resume command queue: {}", e);
+                }
+            });
+        }
+    }
+    
+    /// Handle a response received from GRBL
+    fn handle_grbl_response(&mut self, response: GrblResponse) {
+        // Skip status reports - they're handled separately and would flood the console
+        // Issue #4: Don't display status updates in console
+        if matches!(response, GrblResponse::Status(_)) {
+            tracing::debug!("GRBL status response: {:?}", response);
+            return;
+        }
+        
+        // Format the response for display
+        let response_text = match &response {
+            GrblResponse::Ok => "ok".to_string(),
+            GrblResponse::Error(code) => {
+                let msg = response.error_message().unwrap_or("Unknown error");
+                format!("error:{} ({})", code, msg)
+            }
+            GrblResponse::Alarm(code) => {
+                let msg = response.error_message().unwrap_or("Unknown alarm");
+                format!("ALARM:{} ({})", code, msg)
+            }
+            GrblResponse::Status(_) => {
+                // This case is now unreachable due to early return above
+                unreachable!()
+            }
+            GrblResponse::Welcome { version } => {
+                format!("Grbl {} ['$' for help]", version)
+            }
+            GrblResponse::Setting { number, value } => {
+                if self.onboarding.open {
+                    self.onboarding.record_setting(*number, value);
+                }
+                format!("${}={}", number, value)
+            }
+            GrblResponse::Feedback(msg) => {
+                format!("[{}]", msg)
+            }
+            GrblResponse::Message(msg) => {
+                msg.clone()
+            }
+        };
+        
+        // Add to console with appropriate styling
+        if response.is_error() || response.is_alarm() {
+            self.console.error(response_text);
+        } else {
+            self.console.received(response_text);
+        }
+        
+        // Alarms are broadcast so recovery policies can respond to them
+        if response.is_alarm() {
+            self.state_updater.process_response(&response);
+        }
+        
+        if let GrblResponse::Feedback(msg) = &response {
+            if let Some(parameter) = GrblParameter::parse(msg) {
+                self.app_state.machine.write().apply_parameter(&parameter);
+                self.refresh_machine_view();
+            } else if let Some(result) = ProbeResult::parse(msg) {
+                self.record_probe_result(result);
+            } else if let Some(offset) = msg.strip_prefix("TLO:").and_then(|v| v.parse().ok()) {
+                self.tool_length.offset = offset;
+            }
+        }
+        
+        if matches!(response, GrblResponse::Welcome { .. }) && self.startup_pending {
+            self.startup_pending = false;
+            self.send_startup_commands();
+        }
+        
+        if matches!(response, GrblResponse::Ok | GrblResponse::Error(_)) {
+            if let Some(command) = self.startup_replies.pop_front() {
+                match &response {
+                    GrblResponse::Error(code) => {
+                        let msg = response.error_message().unwrap_or("Unknown error");
+                        self.console.warning(format!("Startup command '{}' failed: error:{} ({})", command, code, msg));
+                    }
+                    _ => self.console.info(format!("Startup command '{}' ok", command)),
+                }
+            } else {
+                self.acknowledge_program_line();
+            }
+        }
+        
+        tracing::debug!("GRBL response: {:?}", response);
+    }
+    
+    /// Record an acknowledgment for the running program
+    fn acknowledge_program_line(&mut self) {
+        if self.tool_change_replies > 0 {
+            self.tool_change_replies -= 1;
+            return;
+        }
+        let Some(streamer) = self.streamer.as_mut() else {
+            return;
+        };
+        let Some(line) = streamer.acknowledge() else {
+            return;
+        };
+        let acknowledged = streamer.acknowledged();
+        let complete = streamer.is_complete();
+        
+        self.current_line = line + 1;
+        self.armed_editor_mut().current_line = Some(line);
+        
+        let mut program_state = self.app_state.program.write();
+        program_state.current_line = self.current_line;
+        program_state.lines_completed = acknowledged;
+        if complete {
+            program_state.state = ExecutionState::Completed;
+        }
+        drop(program_state);
+        
+        if complete {
+            self.streamer = None;
+            self.console.info("Program completed".to_string());
+            self.status_message = "Program completed".to_string();
+            tracing::info!("Program execution completed");
+            self.compare_simulated_time();
+            let commands = self.settings.machine.job_end_commands();
+            if !commands.is_empty() {
+                self.console.info(format!("Job end: {}", commands.join("; ")));
+                self.send_command_sequence(commands);
+            }
+            // Keep the cutting time and runtime counters of the job
+            if let Err(e) = self.launch.save_settings(&self.settings) {
+                self.report_error(e.with_context("Failed to save settings"));
+            }
+        }
+    }
+    
+    /// Compare the run time of a simulated job with the job estimate
+    ///
+    /// With simulator physics the run takes as long as on a machine with the
+    /// simulator's rates and accelerations, which shows how far the estimate
+    /// is off.
+    fn compare_simulated_time(&mut self) {
+        if self.settings.connection.connection_type != ConnectionType::Simulator || self.segments.is_empty() {
+            return;
+        }
+        let Some(device) = &self.simulator_device else {
+            return;
+        };
+        let config = device.lock().unwrap_or_else(|e| e.into_inner()).config().clone();
+        if !config.acceleration_limited {
+            return;
+        }
+        let rapid_rate = config.max_rates[0].min(config.max_rates[1]);
+        let estimate = JobEstimate::from_segments(&self.segments, rapid_rate).total_time();
+        let simulated = self.active_elapsed();
+        let deviation = (estimate.as_secs_f64() - simulated.as_secs_f64()) / simulated.as_secs_f64().max(1e-3) * 100.0;
+        self.console.info(format!(
+            "Simulated run took {}, estimated {} ({:+.0}%)",
+            format_duration(simulated),
+            format_duration(estimate),
+            deviation
+        ));
+    }
+    
+    /// Hand the next batch of program lines to the command queue
+    ///
+    /// Lines are sent sequentially from a single task, and a new batch is
+    /// only started once the previous one has been queued, so program order
+    /// is preserved.
+    fn pump_program_stream(&mut self) {
+        if !matches!(self.app_state.program.read().state, ExecutionState::Running) {
+            return;
+        }
+        if self.stream_task.as_ref().is_some_and(|task| !task.is_finished()) {
+            return;
+        }
+        let mut breakpoints = self.armed_editor().breakpoints.clone();
+        breakpoints.extend(self.comment_pauses.keys());
+        let (Some(streamer), Some(manager)) = (self.streamer.as_mut(), self.connection_manager.as_ref()) else {
+            return;
+        };
+        streamer.set_breakpoints(breakpoints);
+        streamer.set_step_mode(self.step_mode);
+        
+        let idle = self.tool_change.is_none()
+            && self.checking_tool.is_none()
+            && self.broken_tool.is_none()
+            && !streamer.is_stopped();
+        let tool_check = &self.settings.machine.tool_check;
+        if idle && self.last_tool_check.is_some_and(|t| tool_check.interval_due(t.elapsed())) {
+            streamer.request_hold();
+        }
+        let check_at_tool_change = tool_check.enabled && tool_check.after_each_tool;
+        
+        let batch = streamer.next_lines();
+        if batch.is_empty() {
+            let held = streamer.is_held();
+            let pending = streamer.pending_tool_change();
+            let breakpoint = streamer.at_breakpoint();
+            if let Some(line) = breakpoint.filter(|_| idle && self.machine_view.status == MachineStatus::Idle) {
+                self.pause_at_breakpoint(line);
+            } else if held {
+                if idle && !self.begin_tool_check(ToolCheck::Interval) {
+                    // Try again after the next interval rather than on every frame
+                    self.last_tool_check = Some(std::time::Instant::now());
+                    if let Some(streamer) = self.streamer.as_mut() {
+                        streamer.release_hold();
+                    }
+                }
+            } else if let Some(tool) = pending.filter(|_| idle) {
+                if !(check_at_tool_change && self.begin_tool_check(ToolCheck::ToolChange(tool))) {
+                    self.begin_tool_change(tool);
+                }
+            }
+            return;
+        }
+        
+        let lines_sent = streamer.sent();
+        self.app_state.program.write().lines_sent = lines_sent;
+        
+        let manager = Arc::clone(manager);
+        self.stream_task = Some(tokio::spawn(async move {
+            let mgr = manager.lock().await;
+            for line in batch {
+                if let Err(e) = mgr.send_command(GrblCommand::GCode(line.text)).await {
+                    tracing::error!("Failed to send program line {}: {}", line.line_index + 1, e);
+                    break;
+                }
+            }
+        }));
+    }
+    
+    /// Pause the program once the machine has stopped before a breakpoint line
+    ///
+    /// The lines before it have finished, so no feed hold is needed;
+    /// resuming continues to the next breakpoint, or the next line in step
+    /// mode.
+    fn pause_at_breakpoint(&mut self, line: usize) {
+        let mut program_state = self.app_state.program.write();
+        program_state.state = ExecutionState::Paused;
+        drop(program_state);
+        self.program_paused_time = Some(std::time::Instant::now());
+        self.armed_editor_mut().go_to_line(line);
+        if let Some(comment) = self.comment_pauses.get(&line) {
+            self.console.info(format!("Paused at line {}: {}", line + 1, comment));
+            self.status_message = format!("Paused: {}", comment);
+            self.comment_prompt = Some((line, comment.clone()));
+            return;
+        }
+        let message = if self.step_mode {
+            format!("Step: paused before line {}", line + 1)
+        } else {
+            format!("Breakpoint: paused before line {}", line + 1)
+        };
+        self.console.info(message.clone());
+        self.status_message = message;
+    }
+    
+    /// Refresh the link metrics from the connection manager
+    fn poll_link_metrics(&mut self) {
+        let Some(ref manager) = self.connection_manager else {
+            return;
+        };
+        if self.last_metrics_poll.is_some_and(|t| t.elapsed() < METRICS_POLL_INTERVAL) {
+            return;
+        }
+        let now = std::time::Instant::now();
+        self.last_metrics_poll = Some(now);
+        self.link_activity.update(&self.app_state.link_metrics.read(), now);
+        
+        let manager = Arc::clone(manager);
+        let link_metrics = self.app_state.link_metrics.clone();
+        tokio::spawn(async move {
+            let metrics = manager.lock().await.metrics().await;
+            *link_metrics.write() = metrics;
+        });
+    }
+    
+    /// Reset the link metrics
+    fn reset_link_metrics(&mut self) {
+        *self.app_state.link_metrics.write() = Default::default();
+        if let Some(ref manager) = self.connection_manager {
+            let manager = Arc::clone(manager);
+            tokio::spawn(async move {
+                manager.lock().await.reset_metrics().await;
+            });
+        }
+        self.console.info("Link metrics reset".to_string());
+    }
+    
+    /// Stop streaming and discard anything still queued
+    fn abort_program_stream(&mut self) {
+        self.streamer = None;
+        self.tool_change = None;
+        self.tool_change_replies = 0;
+        self.measuring_tool = None;
+        self.checking_tool = None;
+        self.broken_tool = None;
+        self.last_tool_check = None;
+        self.z_limit_holds.clear();
+        self.comment_pauses.clear();
+        self.comment_prompt = None;
+        self.probing_edge = None;
+        if let Some(task) = self.stream_task.take() {
+            task.abort();
+        }
+        
+        if let Some(ref manager) = self.connection_manager {
+            let manager = Arc::clone(manager);
+            tokio::spawn(async move {
+                let mgr = manager.lock().await;
+                if let Err(e) = mgr.clear_queue().await {
+                    tracing::error!("Failed to clear command queue: {}", e);
+                }
+            });
+        }
+    }
+    
+    /// Handle GRBL status update - Issue #1
+    /// 
+    /// This method processes status reports from GRBL (received in response to `?` queries)
+    /// and updates the machine state accordingly.
+    fn handle_grbl_status_update(&mut self, status: crate::grbl::GrblStatus) {
+        self.status_chart.record(&status);
+        if let Some((planner, rx)) = status.buffer {
+            self.link_activity.update_buffer(planner as u32, rx as u32);
+        }
+        
+        // Log status updates (reduced frequency to avoid spam)
+        static STATUS_COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = STATUS_COUNT.fetch_add(1, Ordering::Relaxed);
+        if count % 10 == 0 {  // Log every 10th status update
+            tracing::debug!("Status update: state={:?}, MPos={:?}, WPos={:?}", 
+                status.state, status.mpos, status.wpos);
+        }
+        
+        // Update machine state from the GRBL status; listeners hear about the changes
+        self.state_updater.process_response(&GrblResponse::Status(status));
+    }
+    
+    /// Repaint whenever the shared state changes, even while the window is idle
+    fn spawn_repaint_listener(ctx: &egui::Context, app_state: &AppState) {
+        Self::repaint_on(ctx, app_state.events.subscribe());
+    }
+    
+    /// Repaint whenever a message arrives on a channel, until it closes
+    fn repaint_on<T: Clone + Send + 'static>(ctx: &egui::Context, mut receiver: broadcast::Receiver<T>) {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            while let Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) = receiver.recv().await {
+                ctx.request_repaint();
+            }
+        });
+    }
+    
+    /// Schedule a fallback frame so background work keeps moving
+    ///
+    /// Responses, status reports, connection events and state changes
+    /// repaint as they arrive; this only covers work that is polled from
+    /// the frame loop, and otherwise repaints at a low idle rate.
+    fn schedule_repaint(&self, ctx: &egui::Context) {
+        let busy = self.streamer.is_some()
+            || self.stream_task.as_ref().is_some_and(|task| !task.is_finished())
+            || self.scan_task.is_some()
+            || self.discovery_task.is_some()
+            || self.bluetooth_task.is_some()
+            || self.pending_connection_manager.is_some();
+        let interval = if self.feed_ramp.is_ramping() {
+            FEED_RAMP_INTERVAL
+        } else if busy {
+            METRICS_POLL_INTERVAL
+        } else if self.low_power {
+            LOW_POWER_IDLE_INTERVAL
+        } else {
+            IDLE_REPAINT_INTERVAL
+        };
+        ctx.request_repaint_after(interval);
+    }
+    
+    /// Drain pending state events and refresh the machine view if anything changed
+    ///
+    /// Each event is also offered to the recovery engine, whose steps are
+    /// carried out once the view is up to date.
+    fn drain_state_events(&mut self) {
+        let mut changed = false;
+        let mut recovery = Vec::new();
+        loop {
+            match self.state_events.try_recv() {
+                Ok(event) => {
+                    changed = true;
+                    if let Some(trigger) = RecoveryEngine::trigger_for(&event) {
+                        let steps = self.recovery_engine.handle_event(&event);
+                        if !steps.is_empty() {
+                            recovery.push((trigger, steps));
+                        }
+                    }
+                }
+                Err(broadcast::error::TryRecvError::Lagged(_)) => changed = true,
+                Err(_) => break,
+            }
+        }
+        if changed {
+            self.refresh_machine_view();
+        }
+        for (trigger, steps) in recovery {
+            self.apply_recovery_steps(trigger, steps);
+        }
+    }
+    
+    /// Carry out the steps of a recovery policy
+    fn apply_recovery_steps(&mut self, trigger: RecoveryTrigger, steps: Vec<RecoveryStep>) {
+        self.console.warning(format!("{}: applying recovery policy", trigger));
+        let mut commands = Vec::new();
+        for step in steps {
+            match step {
+                RecoveryStep::Unlock => self.send_unlock_command(),
+                RecoveryStep::RetractZ(_) => commands.extend(step.commands()),
+                RecoveryStep::FeedHold => {
+                    if self.app_state.program.read().state == ExecutionState::Running {
+                        self.pause_program();
+                    } else {
+                        self.send_realtime_byte(RealtimeCommand::FeedHold.as_byte());
+                    }
+                }
+                RecoveryStep::Notify(message) => {
+                    self.console.warning(message.clone());
+                    self.status_message = message;
+                }
+            }
+        }
+        if !commands.is_empty() {
+            self.send_command_sequence(commands);
+        }
+    }
+    
+    /// Take a fresh snapshot of the machine state for display
+    fn refresh_machine_view(&mut self) {
+        self.machine_view = self.app_state.machine.read().clone();
+    }
+    
+    /// Send spindle control command
+    fn send_spindle_command(&mut self, cw: bool, ccw: bool) {
+        let command = if cw {
+            format!("M3 S{:.0}", self.spindle_speed)
+        } else if ccw {
+            format!("M4 S{:.0}", self.spindle_speed)
+        } else {
+            "M5".to_string()
+        };
+        
+        self.console.sent(command.clone());
+        self.status_message = if cw {
+            format!("Spindle CW at {:.0} RPM", self.spindle_speed)
+        } else if ccw {
+            format!("Spindle CCW at {:.0} RPM", self.spindle_speed)
+        } else {
+            "Spindle off".to_string()
+        };
+        
+        // TODO: Send to GRBL via connection manager
+        tracing::info!("Spindle command: {}", command);
+    }
+
+    /// Send a coolant command (M7, M8 or M9)
+    fn send_coolant_command(&mut self, code: &str) {
+        self.send_command(GrblCommand::GCode(code.to_string()));
+        self.status_message = match code {
+            "M7" => "Mist coolant on".to_string(),
+            "M8" => "Flood coolant on".to_string(),
+            _ => "Coolant off".to_string(),
+        };
+    }
+    
+    /// Toggle a grblHAL user output immediately (M64/M65)
+    fn toggle_user_output(&mut self, index: usize) {
+        if self.connection_manager.is_none() {
+            self.console.error("Not connected to device".to_string());
+            return;
+        }
+        if self.user_outputs.len() <= index {
+            self.user_outputs.resize(index + 1, false);
+        }
+        let on = !self.user_outputs[index];
+        self.user_outputs[index] = on;
+        let code = if on { "M64" } else { "M65" };
+        self.send_command(GrblCommand::GCode(format!("{} P{}", code, index)));
+        self.status_message = format!("Output {} {}", index, if on { "on" } else { "off" });
+    }
+    
+    /// Send feed rate override command to GRBL
+    fn send_feed_override(&mut self, target_percent: f64) {
+        if self.connection_manager.is_none() {
+            return; // Silently skip if not connected
+        }
+        
+        if (target_percent - self.feed_ramp.target()).abs() < 0.5 {
+            return; // No significant change
+        }
+        
+        // The steps go out from pump_feed_ramp, spaced to the ramp rate
+        self.feed_ramp.set_target(target_percent);
+        self.pump_feed_ramp();
+        self.console.info(format!("Feed override: {:.0}%", target_percent));
+        tracing::debug!("Feed rate override: {:.0}%", target_percent);
+    }
+    
+    /// Send the feed override steps the ramp rate allows by now
+    fn pump_feed_ramp(&mut self) {
+        if !self.feed_ramp.is_ramping() {
+            return;
+        }
+        if self.connection_manager.is_none() {
+            self.feed_ramp = OverrideRamp::new(self.feed_ramp.current());
+            return;
+        }
+        let rate = self.settings.machine.feed_override_ramp;
+        for step in self.feed_ramp.step(std::time::Instant::now(), rate) {
+            self.send_realtime_byte(OverrideCommand::FeedRate(step).to_byte());
+        }
+    }
+
+    /// Send rapid override command to GRBL
+    fn send_rapid_override(&mut self, target_percent: f64) {
+        if self.connection_manager.is_none() {
+            return; // Silently skip if not connected
+        }
+        
+        let current = self.prev_rapid_override;
+        
+        if (target_percent - current).abs() < 0.5 {
+            return; // No significant change
+        }
+        
+        // GRBL rapid override is discrete: 25%, 50%, or 100%
+        let cmd = if target_percent <= 25.0 {
+            OverrideCommand::Rapid(RapidOverride::Low)
+        } else if target_percent <= 50.0 {
+            OverrideCommand::Rapid(RapidOverride::Medium)
+        } else {
+            OverrideCommand::Rapid(RapidOverride::Reset)
+        };
+        
+        self.send_realtime_byte(cmd.to_byte());
+        self.prev_rapid_override = target_percent;
+        self.console.info(format!("Rapid override: {:.0}%", target_percent));
+        tracing::debug!("Rapid override: {:.0}%", target_percent);
+    }
+
+    /// Send spindle override command to GRBL
+    fn send_spindle_override(&mut self, target_percent: f64) {
+        if self.connection_manager.is_none() {
+            return; // Silently skip if not connected
+        }
+        
+        let current = self.prev_spindle_override;
+        let diff = target_percent - current;
+        
+        if diff.abs() < 0.5 {
+            return; // No significant change
+        }
+        
+        // Determine which override commands to send
+        if diff.abs() >= 10.0 {
+            // Use coarse adjustments for large changes
+            let steps = (diff / 10.0).round() as i32;
+            let cmd = if steps > 0 {
+                OverrideCommand::SpindleSpeed(SpindleOverride::CoarseUp)
+            } else {
+                OverrideCommand::SpindleSpeed(SpindleOverride::CoarseDown)
+            };
+            
+            for _ in 0..steps.abs() {
+                self.send_realtime_byte(cmd.to_byte());
+            }
+        } else {
+            // Use fine adjustments for small changes
+            let steps = diff.round() as i32;
+            let cmd = if steps > 0 {
+                OverrideCommand::SpindleSpeed(SpindleOverride::FineUp)
+            } else {
+                OverrideCommand::SpindleSpeed(SpindleOverride::FineDown)
+            };
+            
+            for _ in 0..steps.abs() {
+                self.send_realtime_byte(cmd.to_byte());
+            }
+        }
+        
+        self.prev_spindle_override = target_percent;
+        self.console.info(format!("Spindle override: {:.0}%", target_percent));
+        tracing::debug!("Spindle speed override: {:.0}%", target_percent);
+    }
+
+    /// Send a real-time command byte to GRBL
+    fn send_realtime_byte(&mut self, byte: u8) {
+        if let Some(ref manager) = self.connection_manager {
+            let manager = Arc::clone(manager);
+            tokio::spawn(async move {
+                let mgr = manager.lock().await;
+                if let Err(e) = mgr.send_realtime(byte).await {
+                    tracing::error!("Failed to send real-time command: {}", e);
+                }
+            });
+        }
+    }
+
+    fn handle_console_command(&mut self, command: &str) {
+        let cmd = command.trim();
+        
+        if cmd.is_empty() {
+            return;
+        }
+        
+        // Log the command response (simulate sending to GRBL)
+        self.console.info(format!("Sending command: {}", cmd));
+        
+        // TODO: Send command to GRBL via connection manager
+        // For now, just simulate a response
+        self.console.received("ok".to_string());
+        
+        tracing::info!("Console command: {}", cmd);
+    }
+    
+    /// Start program execution
+    fn start_program(&mut self) {
+        let mut program_state = self.app_state.program.write();
+        let mut resume = false;
+        let mut resumed_job = None;
+        
+        // Check if we have a program loaded
+        if program_state.total_lines == 0 {
+            self.console.warning("No program loaded".to_string());
+            drop(program_state);
+            self.status_message = "No program loaded".to_string();
+            return;
+        }
+        
+        // Start or resume execution
+        match program_state.state {
+            ExecutionState::NotLoaded => {
+                self.console.warning("No program loaded".to_string());
+                drop(program_state);
+                return;
+            }
+            ExecutionState::Loaded | ExecutionState::Completed => {
+                if self.connection_manager.is_none() {
+                    drop(program_state);
+                    self.console.error("Not connected to device".to_string());
+                    self.status_message = "Not connected".to_string();
+                    return;
+                }
+                
+                if let Some((line, rpm)) = self.settings.machine.spindle_speed_violation(self.armed_content()) {
+                    drop(program_state);
+                    self.console.error(format!(
+                        "Line {} commands S{:.0}, above the machine maximum of {:.0} RPM",
+                        line + 1,
+                        rpm,
+                        self.settings.machine.max_spindle_rpm
+                    ));
+                    self.status_message = "Program blocked: spindle speed above maximum".to_string();
+                    return;
+                }
+                
+                let calibration = &self.settings.machine.spindle_calibration;
+                if calibration.enabled {
+                    if let Some((line, rpm)) = calibration.out_of_range(self.armed_content()) {
+                        let (low, high) = calibration.range().unwrap_or_default();
+                        self.console.warning(format!(
+                            "Line {} commands S{:.0}, outside the calibrated range of {:.0}-{:.0} RPM",
+                            line + 1,
+                            rpm,
+                            low,
+                            high
+                        ));
+                    }
+                }
+                
+                // Start from beginning, expanding cycles unless the controller handles them
+                let options = StreamOptions {
+                    skip_block_delete: self.settings.processing.skip_block_delete,
+                    hold_at_tool_change: self.settings.machine.tool_change_enabled,
+                    spindle_dwell_ms: self.settings.processing.spindle_dwell_ms(),
+                };
+                let calibration = self.settings.machine.spindle_calibration.clone();
+                let plasma = self.settings.machine.plasma.clone();
+                let strip = self.settings.processing.strip_words.clone();
+                // Enter the stock the way the preview shows
+                let plunge_entry = self.settings.processing.plunge_entry();
+                let processing = &self.settings.processing;
+                let dry_run = self.dry_run.then(|| {
+                    self.console.info(format!(
+                        "Dry run: Z stays {:.1} mm above the stock, feeds x{:.1}",
+                        processing.dry_run_clearance, processing.dry_run_feed_scale
+                    ));
+                    DryRun::new(processing.dry_run_clearance, processing.dry_run_feed_scale, processing.dry_run_spindle_off)
+                });
+                let leveler = self.leveling.as_ref().map(|(name, map)| {
+                    self.console.info(format!("Auto-leveling to {}", name));
+                    AutoLeveler::new(map.clone(), processing.leveling_segment, processing.leveling_lowest_z)
+                });
+                if plasma.enabled && plasma.chart_entry().is_none() {
+                    self.console.warning(format!(
+                        "No cut chart entry for {:.1} mm material, using the default pierce delay and program feeds",
+                        plasma.thickness
+                    ));
+                }
+                let mut passes = StreamPasses {
+                    expand: !self.settings.processing.passthrough_cycles,
+                    entry: (plunge_entry != PlungeEntry::Straight).then(|| PlungeRewriter::new(plunge_entry)),
+                    retract: (self.settings.processing.rapid_retract == RapidRetract::Fix)
+                        .then(|| SafeRetract::new(self.settings.general.safe_z)),
+                    dry_run,
+                    backlash: self.settings.machine.backlash_compensation().map(BacklashCompensator::new),
+                    calibration: calibration.enabled.then_some(calibration),
+                    plasma: plasma.enabled.then(|| PlasmaPostProcessor::new(plasma)),
+                    leveler,
+                };
+                let streamer = ExpressionEvaluator::new()
+                    .evaluate(self.armed_content())
+                    .and_then(|program| {
+                        // Blank stripped lines rather than dropping them so line numbers still match
+                        let program = if strip.is_empty() {
+                            program
+                        } else {
+                            program
+                                .lines()
+                                .map(|line| crate::parser::strip_line(line, &strip).unwrap_or_default())
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        };
+                        // Every pass keeps each line's source line, so breakpoints, pauses
+                        // and resume points still refer to the loaded program
+                        let lines = passes.apply(&program)?;
+                        Ok(ProgramStreamer::from_lines(
+                            lines.iter().map(|line| (line.source_line, line.text.as_str())),
+                            options,
+                        ))
+                    });
+                let violations = passes.leveler.as_ref().map(|leveler| leveler.violations().to_vec()).unwrap_or_default();
+                self.z_limit_holds.clear();
+                self.comment_pauses = marker_comments(self.armed_content(), &self.settings.processing.pause_markers);
+                self.progress_map = if self.armed_document == self.active_document {
+                    ProgressMap::new(&self.segments)
+                } else {
+                    ProgressMap::new(&self.documents[self.armed_document].segments)
+                };
+                match streamer {
+                    Ok(mut streamer) => {
+                        if let Some(job) = self.resume_job.take() {
+                            if !streamer.resume_from(job.safe_line, self.settings.general.safe_z) {
+                                self.console.warning(
+                                    "Incremental moves before the resume line leave the position unknown; \
+                                     the tool stays at the safe height"
+                                        .to_string(),
+                                );
+                            }
+                            self.console.info(format!("Resuming at line {}", job.safe_line + 1));
+                            resumed_job = Some(job);
+                        }
+                        // Lines before a resume point are not sent
+                        let first_line = resumed_job.as_ref().map_or(0, |job| job.safe_line);
+                        let violations: Vec<ZLimitViolation> =
+                            violations.into_iter().filter(|violation| violation.line >= first_line).collect();
+                        if let Some(violation) = violations.first() {
+                            if self.settings.processing.z_limit_guard == ZLimitGuard::Abort {
+                                drop(program_state);
+                                self.console.error(violation.to_string());
+                                self.status_message = "Program blocked: auto-leveling goes below the lowest Z".to_string();
+                                return;
+                            }
+                            streamer.stop_before_line(violation.line);
+                            self.console.warning(format!("{} The program will hold before it.", violation));
+                            if violations.len() > 1 {
+                                self.console.warning(format!(
+                                    "{} more lines go below the lowest Z; the program holds before each one",
+                                    violations.len() - 1
+                                ));
+                            }
+                            self.z_limit_holds = violations;
+                        }
+                        self.streamer = Some(streamer);
+                    }
+                    Err(e) => {
+                        drop(program_state);
+                        self.report_error(e.with_context("Cannot start program"));
+                        self.status_message = "Program preparation failed".to_string();
+                        return;
+                    }
+                }
+                program_state.state = ExecutionState::Running;
+                program_state.current_line = 0;
+                program_state.lines_sent = 0;
+                program_state.lines_completed = 0;
+                self.current_line = 0;
+                self.program_start_time = Some(std::time::Instant::now());
+                self.last_tool_check = self.program_start_time;
+                self.total_paused_duration = std::time::Duration::ZERO;
+                self.console.info("Program started".to_string());
+                self.status_message = "Program started".to_string();
+                if self.settings.ui.auto_run_screen {
+                    self.run_screen.open = true;
+                }
+                tracing::info!("Program execution started");
+            }
+            ExecutionState::Paused => {
+                // Resume from pause, continuing past a breakpoint
+                program_state.state = ExecutionState::Running;
+                if let Some(streamer) = self.streamer.as_mut() {
+                    streamer.release_breakpoint();
+                }
+                if let Some(paused_time) = self.program_paused_time.take() {
+                    self.total_paused_duration += paused_time.elapsed();
+                }
+                self.console.info("Program resumed".to_string());
+                self.status_message = "Program resumed".to_string();
+                tracing::info!("Program execution resumed");
+                resume = true;
+            }
+            ExecutionState::Running => {
+                self.console.warning("Program already running".to_string());
+            }
+            ExecutionState::Error => {
+                self.console.warning("Cannot start - program in error state. Reset first.".to_string());
+            }
+        }
+        
+        drop(program_state);
+        
+        // Restore the overrides the interrupted run had
+        if let Some(job) = resumed_job {
+            self.feed_override = job.feed_override;
+            self.spindle_override = job.spindle_override;
+            self.rapid_override = job.rapid_override;
+            self.send_feed_override(job.feed_override);
+            self.send_spindle_override(job.spindle_override);
+            self.send_rapid_override(job.rapid_override);
+        }
+        
+        if resume {
+            if std::mem::take(&mut self.link_paused_program) {
+                self.link_warning = None;
+                self.resume_command_queue();
+            }
+            self.send_realtime_byte(RealtimeCommand::CycleStartResume.as_byte());
+        }
+    }
+    
+    /// Start the program, asking for the pre-run checklist first unless resuming
+    fn request_program_start(&mut self) {
+        let starting = matches!(
+            self.app_state.program.read().state,
+            ExecutionState::Loaded | ExecutionState::Completed
+        );
+        let items = self.settings.machine.checklist_items().len();
+        let skipped = self
+            .armed_path()
+            .is_some_and(|path| SidecarMetadata::load_for(path).skip_checklist);
+        if starting && items > 0 && !skipped && self.connection_manager.is_some() {
+            self.checklist_dialog.ask(items);
+        } else {
+            self.start_program();
+        }
+    }
+    
+    /// Show the pre-run checklist, starting the program once it is confirmed
+    fn show_checklist(&mut self, ctx: &egui::Context) {
+        if !self.checklist_dialog.open {
+            return;
+        }
+        let items = self.settings.machine.checklist_items();
+        let action = self.checklist_dialog.show(ctx, &items, self.armed_path().is_some());
+        match action {
+            Some(ChecklistAction::Start { skip_for_file }) => {
+                if let Some(path) = self.armed_path().cloned().filter(|_| skip_for_file) {
+                    let mut metadata = SidecarMetadata::load_for(&path);
+                    metadata.skip_checklist = true;
+                    if let Err(e) = metadata.save_for(&path) {
+                        self.report_error(e.with_context("Failed to save file metadata"));
+                    }
+                }
+                self.start_program();
+            }
+            Some(ChecklistAction::Cancel) => self.status_message = "Program start cancelled".to_string(),
+            None => {}
+        }
+    }
+    
+    /// Pause program execution
+    fn pause_program(&mut self) {
+        let mut program_state = self.app_state.program.write();
+        
+        if matches!(program_state.state, ExecutionState::Running) {
+            program_state.state = ExecutionState::Paused;
+            self.program_paused_time = Some(std::time::Instant::now());
+            self.console.info("Program paused".to_string());
+            self.status_message = "Program paused".to_string();
+            tracing::info!("Program execution paused");
+            
+            drop(program_state);
+            self.send_realtime_byte(RealtimeCommand::FeedHold.as_byte());
+        } else {
+            self.console.warning("Program is not running".to_string());
+        }
+    }
+    
+    /// Stop program execution
+    fn stop_program(&mut self) {
+        let mut program_state = self.app_state.program.write();
+        
+        if !matches!(program_state.state, ExecutionState::Loaded) {
+            program_state.state = ExecutionState::Loaded;
+            self.program_start_time = None;
+            self.program_paused_time = None;
+            self.total_paused_duration = std::time::Duration::ZERO;
+            self.console.warning("Program stopped".to_string());
+            self.status_message = "Program stopped".to_string();
+            tracing::info!("Program execution stopped");
+            
+            drop(program_state);
+            self.abort_program_stream();
+            self.send_realtime_byte(RealtimeCommand::Reset.as_byte());
+        } else {
+            self.console.warning("Program is not running".to_string());
+        }
+    }
+    
+    /// Reset program to beginning
+    fn reset_program(&mut self) {
+        let mut program_state = self.app_state.program.write();
+        
+        program_state.state = ExecutionState::Loaded;
+        program_state.current_line = 0;
+        program_state.lines_sent = 0;
+        program_state.lines_completed = 0;
+        self.current_line = 0;
+        self.program_start_time = None;
+        self.program_paused_time = None;
+        self.total_paused_duration = std::time::Duration::ZERO;
+        
+        self.console.info("Program reset".to_string());
+        self.status_message = "Program reset".to_string();
+        tracing::info!("Program reset to beginning");
+        
+        drop(program_state);
+        self.armed_editor_mut().current_line = None;
+        
+        self.abort_program_stream();
+    }
+    
+    /// Execute a single step in step mode
+    ///
+    /// Starts the program, which pauses before its first line, or sends the
+    /// line it is paused before.
+    fn execute_single_step(&mut self) {
+        let state = self.app_state.program.read().state;
+        match state {
+            ExecutionState::Loaded | ExecutionState::Completed => self.request_program_start(),
+            ExecutionState::Paused if self.streamer.is_some() => {
+                tracing::debug!("Step mode: executing line {}", self.current_line + 1);
+                self.start_program();
+            }
+            _ => self.console.warning("Step is available while the program is stopped or paused".to_string()),
+        }
+    }
+    
+    /// Running time of the program, excluding pauses
+    fn active_elapsed(&self) -> std::time::Duration {
+        if let Some(start_time) = self.program_start_time {
+            let total_elapsed = start_time.elapsed();
+            if let Some(paused_time) = self.program_paused_time {
+                // Currently paused - subtract pause duration
+                total_elapsed - self.total_paused_duration - paused_time.elapsed()
+            } else {
+                // Not paused - just subtract total paused duration
+                total_elapsed - self.total_paused_duration
+            }
+        } else {
+            std::time::Duration::ZERO
+        }
+    }
+    
+    /// Calculate time estimates for program execution
+    fn calculate_time_estimates(&self) -> (String, String) {
+        let program_state = self.app_state.program.read();
+        let elapsed = self.active_elapsed();
+        let elapsed_text = format_duration(elapsed);
+        
+        // Calculate remaining time estimate
+        let remaining_text = if self.current_line > 0 && program_state.total_lines > self.current_line {
+            let progress = self.current_line as f64 / program_state.total_lines as f64;
+            let estimated_total = elapsed.as_secs_f64() / progress;
+            let remaining_secs = estimated_total - elapsed.as_secs_f64();
+            let remaining = std::time::Duration::from_secs_f64(remaining_secs.max(0.0));
+            format_duration(remaining)
+        } else if matches!(program_state.state, ExecutionState::Completed) {
+            "Complete".to_string()
+        } else {
+            "--:--:--".to_string()
+        };
+        
+        drop(program_state);
+        
+        (elapsed_text, remaining_text)
+    }
+
+    /// Collect the values shown on the run screen
+    fn run_screen_status(&self) -> RunScreenStatus {
+        let (elapsed, remaining) = self.calculate_time_estimates();
+        let machine_state = &self.machine_view;
+        let program_state = self.app_state.program.read();
+        RunScreenStatus {
+            status: machine_state.status,
+            position: [
+                machine_state.work_position.x,
+                machine_state.work_position.y,
+                machine_state.work_position.z,
+            ],
+            current_line: program_state.current_line,
+            total_lines: program_state.total_lines,
+            elapsed,
+            remaining,
+            feed_rate: machine_state.feed_rate,
+            spindle_speed: machine_state.spindle_speed,
+            feed_override: machine_state.feed_override,
+            spindle_override: machine_state.spindle_override,
+            paused: matches!(program_state.state, ExecutionState::Paused),
+            active: matches!(program_state.state, ExecutionState::Running | ExecutionState::Paused),
+            units: if self.settings.general.units_metric { "mm" } else { "in" },
+        }
+    }
+
+    /// Draw toolpath in 2D (XY plane projection)
+    ///
+    /// Returns the segment under `pointer`, if any.
+    fn draw_toolpath_2d(&self, ui: &mut egui::Ui, rect: egui::Rect, pointer: Option<egui::Pos2>) -> Option<usize> {
+        use egui::{Color32, Pos2, Stroke};
+        
+        if self.segments.is_empty() {
+            return None;
+        }
+        
+        // Calculate bounding box
+        let mut min_x = f64::MAX;
+        let mut max_x = f64::MIN;
+        let mut min_y = f64::MAX;
+        let mut max_y = f64::MIN;
+        let mut max_z = f64::MIN;
+        
+        for segment in &self.segments {
+            min_x = min_x.min(segment.start.x).min(segment.end.x);
+            max_x = max_x.max(segment.start.x).max(segment.end.x);
+            min_y = min_y.min(segment.start.y).min(segment.end.y);
+            max_y = max_y.max(segment.start.y).max(segment.end.y);
+            max_z = max_z.max(segment.start.z).max(segment.end.z);
+        }
+        
+        // Fixture areas as seen from the active work zero
+        let fixtures: Vec<([f64; 2], [f64; 2])> = if self.settings.visualization.show_fixtures {
+            let (_, offset) = self.current_work_offset();
+            self.settings
+                .machine
+                .fixtures
+                .iter()
+                .map(|fixture| fixture.area([offset.x, offset.y, offset.z]))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for (low, high) in &fixtures {
+            min_x = min_x.min(low[0]);
+            max_x = max_x.max(high[0]);
+            min_y = min_y.min(low[1]);
+            max_y = max_y.max(high[1]);
+        }
+        
+        // Add some padding
+        let padding = 20.0;
+        let width = (max_x - min_x) as f32;
+        let height = (max_y - min_y) as f32;
+        
+        if width == 0.0 || height == 0.0 {
+            return None;
+        }
+        
+        // Calculate scale to fit in viewport
+        let viewport_width = rect.width() - padding * 2.0;
+        let viewport_height = rect.height() - padding * 2.0;
+        let scale = (viewport_width / width).min(viewport_height / height);
+        
+        // Center offset
+        let offset_x = rect.left() + padding + (viewport_width - width * scale) / 2.0;
+        let offset_y = rect.top() + padding + (viewport_height - height * scale) / 2.0;
+        
+        // Transform function from G-Code coordinates to screen coordinates
+        let to_screen = |x: f64, y: f64| {
+            Pos2::new(
+                offset_x + ((x - min_x) as f32 * scale),
+                // Flip Y axis (G-Code Y increases upward, screen Y increases downward)
+                offset_y + viewport_height - ((y - min_y) as f32 * scale),
+            )
+        };
+        
+        let visualization = &self.settings.visualization;
+        let colors = &visualization.color_scheme;
+        
+        // Draw grid, labelling every few lines with their coordinate
+        if visualization.show_grid {
+            let grid_color = Self::to_color32(colors.grid);
+            let metric = self.settings.general.units_metric;
+            let grid_spacing = if visualization.adaptive_grid {
+                adaptive_spacing(scale, metric) as f64
+            } else {
+                visualization.grid_size.max(1.0) as f64
+            };
+            let line_pixels = grid_spacing as f32 * scale;
+            let label_every = [1, 2, 5, 10, 20, 50]
+                .into_iter()
+                .find(|n| *n as f32 * line_pixels >= 48.0)
+                .unwrap_or(100);
+            let decimals = if grid_spacing * label_every as f64 >= 1.0 { 0 } else { 1 };
+            let minor_color = grid_color.gamma_multiply(0.5);
+            let font = egui::FontId::monospace(10.0);
+            let label_color = grid_color.gamma_multiply(1.8);
+            
+            // Vertical grid lines, labelled along the bottom
+            let first = (min_x / grid_spacing).floor() as i64;
+            let last = (max_x / grid_spacing).ceil() as i64;
+            for index in first..=last {
+                let x = index as f64 * grid_spacing;
+                if x < min_x || x > max_x {
+                    continue;
+                }
+                let major = index % label_every == 0;
+                let p1 = to_screen(x, min_y);
+                let p2 = to_screen(x, max_y);
+                ui.painter().line_segment([p1, p2], Stroke::new(1.0, if major { grid_color } else { minor_color }));
+                if major && visualization.grid_labels {
+                    let text = format!("{:.*}", decimals, x);
+                    ui.painter().text(p1 + egui::vec2(0.0, 2.0), egui::Align2::CENTER_TOP, text, font.clone(), label_color);
+                }
+            }
+            
+            // Horizontal grid lines, labelled along the left
+            let first = (min_y / grid_spacing).floor() as i64;
+            let last = (max_y / grid_spacing).ceil() as i64;
+            for index in first..=last {
+                let y = index as f64 * grid_spacing;
+                if y < min_y || y > max_y {
+                    continue;
+                }
+                let major = index % label_every == 0;
+                let p1 = to_screen(min_x, y);
+                let p2 = to_screen(max_x, y);
+                ui.painter().line_segment([p1, p2], Stroke::new(1.0, if major { grid_color } else { minor_color }));
+                if major && visualization.grid_labels {
+                    let text = format!("{:.*}", decimals, y);
+                    ui.painter().text(p1 + egui::vec2(2.0, -1.0), egui::Align2::LEFT_BOTTOM, text, font.clone(), label_color);
+                }
+            }
+            
+            if visualization.grid_labels {
+                let units = if metric { "mm" } else { "in" };
+                ui.painter().text(
+                    rect.right_bottom() - egui::vec2(10.0, 4.0),
+                    egui::Align2::RIGHT_BOTTOM,
+                    format!("grid {:.*} {}", if grid_spacing >= 1.0 { 0 } else { 1 }, grid_spacing, units),
+                    font,
+                    label_color,
+                );
+            }
+        }
+        
+        // Draw axes
+        let origin = to_screen(0.0, 0.0);
+        if visualization.show_origin && min_x <= 0.0 && max_x >= 0.0 && min_y <= 0.0 && max_y >= 0.0 {
+            // X axis (red)
+            let x_end = to_screen(max_x, 0.0);
+            ui.painter().line_segment(
+                [origin, x_end],
+                Stroke::new(2.0, Color32::from_rgb(200, 50, 50)),
+            );
+            
+            // Y axis (green)
+            let y_end = to_screen(0.0, max_y);
+            ui.painter().line_segment(
+                [origin, y_end],
+                Stroke::new(2.0, Color32::from_rgb(50, 200, 50)),
+            );
+        }
+        
+        // Fixtures, with the job placed on the one selected in the library
+        if !fixtures.is_empty() {
+            let color = Self::to_color32(colors.bounds).gamma_multiply(0.6);
+            let font = egui::FontId::proportional(11.0);
+            for (index, (fixture, (low, high))) in self.settings.machine.fixtures.iter().zip(&fixtures).enumerate() {
+                let frame = egui::Rect::from_two_pos(to_screen(low[0], low[1]), to_screen(high[0], high[1]));
+                let selected = self.fixture_panel.selected == Some(index);
+                let stroke = Stroke::new(if selected { 2.0 } else { 1.0 }, color);
+                ui.painter().rect_stroke(frame, 0.0, stroke);
+                ui.painter().text(
+                    frame.left_top() + egui::vec2(4.0, 2.0),
+                    egui::Align2::LEFT_TOP,
+                    format!("{} ({})", fixture.name, fixture.kind),
+                    font.clone(),
+                    color,
+                );
+                if !selected {
+                    continue;
+                }
+                if let Some(bounds) = BoundingBox::of_job(&self.segments) {
+                    let fits = fixture.fits(
+                        [bounds.min.x as f64, bounds.min.y as f64],
+                        [bounds.max.x as f64, bounds.max.y as f64],
+                    );
+                    let job_color = if fits { Color32::from_rgb(80, 180, 80) } else { Color32::from_rgb(220, 80, 60) };
+                    let placed = egui::Rect::from_two_pos(
+                        to_screen(low[0] + bounds.min.x as f64, low[1] + bounds.min.y as f64),
+                        to_screen(low[0] + bounds.max.x as f64, low[1] + bounds.max.y as f64),
+                    );
+                    ui.painter().rect_filled(placed, 0.0, job_color.gamma_multiply(0.15));
+                    ui.painter().rect_stroke(placed, 0.0, Stroke::new(1.5, job_color));
+                }
+            }
+        }
+        
+        // Gradient ranges for the feed rate and depth color modes
+        let (feed, depth) = match self.settings.visualization.color_mode {
+            ToolpathColorMode::FeedRate => (FeedGradient::from_segments(&self.segments), None),
+            ToolpathColorMode::Depth => (None, DepthGradient::from_segments(&self.segments)),
+            ToolpathColorMode::MoveType => (None, None),
+        };
+        let z_filter = self.z_filter();
+        let clip_plane = self.clip_plane;
+        
+        // Runs of sub-pixel segments are merged into one line so very large
+        // files stay responsive
+        let mut run: Option<(Pos2, Pos2, Color32, f32)> = None;
+        // Connected rapids or arcs, dashed as one path so short pieces keep
+        // their pattern
+        let mut patterned: Option<(Vec<Pos2>, Color32, f32, LinePattern)> = None;
+        let draw_patterned = |path: &[Pos2], color: Color32, width: f32, pattern: LinePattern| {
+            if let Some((dash, gap)) = pattern.dashes() {
+                ui.painter().extend(egui::Shape::dashed_line(path, Stroke::new(width, color), dash, gap));
+            }
+        };
+        
+        // Draw toolpath segments
+        for segment in &self.segments {
+            if z_filter.is_some_and(|filter| !filter.contains(segment)) {
+                continue;
+            }
+            if clip_plane.is_some_and(|plane| !plane.contains(segment)) {
+                continue;
+            }
+            
+            let start = to_screen(segment.start.x, segment.start.y);
+            let end = to_screen(segment.end.x, segment.end.y);
+            
+            let width = if segment.is_cutting() { 2.0 } else { 1.0 };
+            let color = if (feed.is_some() || depth.is_some()) && !segment.is_cutting() {
+                Self::to_color32(FeedGradient::RAPID_COLOR)
+            } else if let Some(ref gradient) = feed {
+                Self::to_color32(gradient.color(segment.feed_rate))
+            } else if let Some(ref gradient) = depth {
+                Self::to_color32(gradient.color(segment))
+            } else {
+                // Color based on segment type
+                match segment.segment_type {
+                    SegmentType::Rapid => Self::to_color32(colors.rapid),
+                    SegmentType::Linear => Self::to_color32(colors.toolpath),
+                    SegmentType::ArcCW | SegmentType::ArcCCW => Self::to_color32(colors.arc),
+                }
+            };
+            
+            let pattern = LinePattern::of(&segment.segment_type);
+            if visualization.line_patterns && pattern != LinePattern::Solid {
+                if let Some((path, path_color, _, path_pattern)) = patterned.as_mut() {
+                    if path.last() == Some(&start) && *path_color == color && *path_pattern == pattern {
+                        path.push(end);
+                        continue;
+                    }
+                }
+                if let Some((path, path_color, path_width, path_pattern)) = patterned.take() {
+                    draw_patterned(&path, path_color, path_width, path_pattern);
+                }
+                patterned = Some((vec![start, end], color, width, pattern));
+                continue;
+            }
+            
+            if let Some((run_start, run_end, run_color, run_width)) = run.as_mut() {
+                if *run_end == start && *run_color == color && run_start.distance(end) < 1.0 {
+                    *run_end = end;
+                    continue;
+                }
+                ui.painter().line_segment([*run_start, *run_end], Stroke::new(*run_width, *run_color));
+            }
+            run = Some((start, end, color, width));
+        }
+        if let Some((start, end, color, width)) = run {
+            ui.painter().line_segment([start, end], Stroke::new(width, color));
+        }
+        if let Some((path, color, width, pattern)) = patterned {
+            draw_patterned(&path, color, width, pattern);
+        }
+        if visualization.line_patterns {
+            Self::draw_pattern_legend(ui, rect, colors);
+        }
+        
+        // Trace of a vertical clipping plane
+        if let Some(plane) = clip_plane {
+            let position = to_screen(plane.position, plane.position);
+            let line = match plane.axis {
+                ClipAxis::X => Some([Pos2::new(position.x, rect.top()), Pos2::new(position.x, rect.bottom())]),
+                ClipAxis::Y => Some([Pos2::new(rect.left(), position.y), Pos2::new(rect.right(), position.y)]),
+                ClipAxis::Z => None,
+            };
+            if let Some(line) = line {
+                let stroke = Stroke::new(1.0, Color32::from_rgb(255, 170, 60));
+                ui.painter().extend(egui::Shape::dashed_line(&line, stroke, 6.0, 4.0));
+            }
+        }
+        
+        // Job bounding box with dimension labels
+        if visualization.show_dimensions {
+            if let Some(bounds) = BoundingBox::of_job(&self.segments) {
+                let color = Self::to_color32(colors.bounds);
+                let low = to_screen(bounds.min.x as f64, bounds.min.y as f64);
+                let high = to_screen(bounds.max.x as f64, bounds.max.y as f64);
+                let frame = egui::Rect::from_two_pos(low, high);
+                ui.painter().rect_stroke(frame, 0.0, Stroke::new(1.0, color));
+                
+                let units = if self.settings.general.units_metric { "mm" } else { "in" };
+                let size = bounds.size();
+                let font = egui::FontId::monospace(11.0);
+                ui.painter().text(
+                    frame.center_bottom() + egui::vec2(0.0, 4.0),
+                    egui::Align2::CENTER_TOP,
+                    format!("X {:.3} {}", size.x, units),
+                    font.clone(),
+                    color,
+                );
+                ui.painter().text(
+                    frame.left_center() - egui::vec2(4.0, 0.0),
+                    egui::Align2::RIGHT_CENTER,
+                    format!("Y {:.3} {}", size.y, units),
+                    font.clone(),
+                    color,
+                );
+                ui.painter().text(
+                    frame.right_top() + egui::vec2(4.0, 0.0),
+                    egui::Align2::LEFT_TOP,
+                    format!("Z {:.3} {}\n({:.3} to {:.3})", size.z, units, bounds.min.z, bounds.max.z),
+                    font,
+                    color,
+                );
+            }
+        }
+        
+        // Highlight the selected segment
+        if let Some(segment) = self.selected_segment.and_then(|i| self.segments.get(i)) {
+            let start = to_screen(segment.start.x, segment.start.y);
+            let end = to_screen(segment.end.x, segment.end.y);
+            ui.painter().line_segment([start, end], Stroke::new(4.0, Self::to_color32(colors.tool)));
+        }
+        
+        if let Some(ref gradient) = feed {
+            Self::draw_gradient_legend(
+                ui,
+                rect,
+                "Feed rate",
+                format!("{:.0}", gradient.min),
+                format!("{:.0}", gradient.max),
+            );
+        } else if let Some(ref gradient) = depth {
+            Self::draw_gradient_legend(
+                ui,
+                rect,
+                "Depth (Z)",
+                format!("{:.3}", gradient.top),
+                format!("{:.3}", gradient.bottom),
+            );
+        }
+        
+        // Draw start point marker
+        if let Some(first) = self.segments.first() {
+            let start = to_screen(first.start.x, first.start.y);
+            ui.painter().circle_filled(start, 4.0, Color32::from_rgb(100, 255, 255));
+            ui.painter().circle_stroke(start, 4.0, Stroke::new(1.0, Color32::WHITE));
+        }
+        
+        // Pick by casting a ray straight down through the pointer
+        let pointer = pointer?;
+        let x = min_x + ((pointer.x - offset_x) / scale) as f64;
+        let y = min_y + ((offset_y + viewport_height - pointer.y) / scale) as f64;
+        let ray = Ray::new(
+            nalgebra::Point3::new(x as f32, y as f32, max_z as f32 + 1.0),
+            nalgebra::Vector3::new(0.0, 0.0, -1.0),
+        );
+        self.segment_index
+            .pick(&ray, 5.0 / scale)
+            .filter(|&i| z_filter.map_or(true, |filter| filter.contains(&self.segments[i])))
+            .filter(|&i| clip_plane.map_or(true, |plane| plane.contains(&self.segments[i])))
+    }
+    
+    /// Tooltip describing a segment
+    fn show_segment_tooltip(&self, ui: &mut egui::Ui, index: usize) {
+        let Some(segment) = self.segments.get(index) else {
+            return;
+        };
+        let units = if self.settings.general.units_metric { "mm" } else { "in" };
+        
+        if let Some(line) = segment.source_line {
+            ui.label(format!("Line {}", line + 1));
+        }
+        if let Some(n) = segment.line_number {
+            ui.label(format!("N{}", n));
+        }
+        if segment.is_cutting() {
+            ui.label(format!("Feed: {:.0} {}/min", segment.feed_rate, units));
+            match segment.feed_rate_mode {
+                crate::parser::FeedRateMode::UnitsPerMinute => {}
+                crate::parser::FeedRateMode::InverseTime => {
+                    ui.weak("Inverse time (G93), converted from move time");
+                }
+                crate::parser::FeedRateMode::UnitsPerRevolution => {
+                    ui.weak(format!("Per revolution (G95) at S{:.0}", segment.spindle_speed));
+                }
+            }
+        } else {
+            ui.label("Rapid");
+        }
+        ui.monospace(format!(
+            "From X{:.3} Y{:.3} Z{:.3}",
+            segment.start.x, segment.start.y, segment.start.z
+        ));
+        ui.monospace(format!(
+            "To   X{:.3} Y{:.3} Z{:.3}",
+            segment.end.x, segment.end.y, segment.end.z
+        ));
+    }
+    
+    /// Select a segment in the viewer and show its line in the editor
+    fn select_segment(&mut self, index: Option<usize>) {
+        self.selected_segment = index;
+        if let Some(line) = index.and_then(|i| self.segments.get(i)).and_then(|s| s.source_line) {
+            self.gcode_editor.go_to_line(line);
+        }
+    }
+    
+    /// Convert a renderer color to an egui color
+    fn to_color32(color: [f32; 4]) -> egui::Color32 {
+        egui::Color32::from_rgba_unmultiplied(
+            (color[0] * 255.0) as u8,
+            (color[1] * 255.0) as u8,
+            (color[2] * 255.0) as u8,
+            (color[3] * 255.0) as u8,
+        )
+    }
+    
+    /// Draw a color gradient legend in the bottom-right corner of the viewer
+    fn draw_gradient_legend(ui: &egui::Ui, rect: egui::Rect, title: &str, low: String, high: String) {
+        use egui::{Align2, Color32, FontId, Pos2, Rect, Stroke, vec2};
+        
+        let painter = ui.painter();
+        let bar = Rect::from_min_size(
+            Pos2::new(rect.right() - 170.0, rect.bottom() - 40.0),
+            vec2(150.0, 10.0),
+        );
+        let steps = 30;
+        let step_width = bar.width() / steps as f32;
+        for i in 0..steps {
+            let color = Self::to_color32(FeedGradient::color_at(i as f64 / (steps - 1) as f64));
+            let min = Pos2::new(bar.left() + step_width * i as f32, bar.top());
+            painter.rect_filled(Rect::from_min_size(min, vec2(step_width + 0.5, bar.height())), 0.0, color);
+        }
+        painter.rect_stroke(bar, 0.0, Stroke::new(1.0, Color32::from_gray(120)));
+        
+        let font = FontId::monospace(11.0);
+        let text_color = Color32::from_rgb(180, 180, 180);
+        painter.text(bar.left_bottom() + vec2(0.0, 3.0), Align2::LEFT_TOP, low, font.clone(), text_color);
+        painter.text(bar.right_bottom() + vec2(0.0, 3.0), Align2::RIGHT_TOP, high, font.clone(), text_color);
+        painter.text(bar.center_top() - vec2(0.0, 3.0), Align2::CENTER_BOTTOM, title, font.clone(), text_color);
+        
+        let rapid = Pos2::new(bar.left() - 60.0, bar.center().y);
+        painter.line_segment([rapid, rapid + vec2(14.0, 0.0)], Stroke::new(2.0, Self::to_color32(FeedGradient::RAPID_COLOR)));
+        painter.text(rapid + vec2(18.0, 0.0), Align2::LEFT_CENTER, "Rapid", font, text_color);
+    }
+    
+    /// Key to the line patterns, in the bottom left corner of the view
+    fn draw_pattern_legend(ui: &egui::Ui, rect: egui::Rect, colors: &crate::settings::ColorScheme) {
+        use egui::{Align2, Color32, FontId, Pos2, Stroke, vec2};
+        
+        let painter = ui.painter();
+        let font = FontId::monospace(11.0);
+        let text_color = Color32::from_rgb(180, 180, 180);
+        let mut position = Pos2::new(rect.left() + 12.0, rect.bottom() - 20.0);
+        for (label, color, pattern) in [
+            ("Cut", colors.toolpath, LinePattern::Solid),
+            ("Arc", colors.arc, LinePattern::Dotted),
+            ("Rapid", colors.rapid, LinePattern::Dashed),
+        ] {
+            let line = [position, position + vec2(24.0, 0.0)];
+            let stroke = Stroke::new(2.0, Self::to_color32(color));
+            match pattern.dashes() {
+                Some((dash, gap)) => painter.extend(egui::Shape::dashed_line(&line, stroke, dash, gap)),
+                None => {
+                    painter.line_segment(line, stroke);
+                }
+            }
+            let text = painter.text(position + vec2(28.0, 0.0), Align2::LEFT_CENTER, label, font.clone(), text_color);
+            position.x = text.right() + 14.0;
+        }
+    }
+    
+    /// Z range currently shown, if filtered to a single level
+    fn z_filter(&self) -> Option<ZFilter> {
+        self.z_filter_level
+            .and_then(|index| self.z_levels.get(index))
+            .map(|&z| ZFilter::level(z))
+    }
+    
+    /// Show a single Z level, or all of them
+    fn set_z_filter_level(&mut self, level: Option<usize>) {
+        self.z_filter_level = level;
+        let filter = self.z_filter();
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.toolpath_mut().z_filter = filter;
+        }
+    }
+    
+    /// Show only the toolpath on one side of a plane, or all of it
+    fn set_clip_plane(&mut self, plane: Option<ClipPlane>) {
+        self.clip_plane = plane;
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.toolpath_mut().clip_plane = plane;
+        }
+    }
+    
+    /// Change how the toolpath is colored and remember it
+    fn set_color_mode(&mut self, mode: ToolpathColorMode) {
+        self.settings.visualization.color_mode = mode;
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.toolpath_mut().color_mode = mode;
+        }
+        if let Err(e) = self.launch.save_settings(&self.settings) {
+            self.report_error(e.with_context("Failed to save settings"));
+        }
+    }
+    
+    /// Apply theme (dark/light mode) to the UI
+    fn apply_theme(ctx: &egui::Context, dark_mode: bool) {
+        if dark_mode {
+            ctx.set_visuals(egui::Visuals::dark());
+        } else {
+            ctx.set_visuals(egui::Visuals::light());
+        }
+    }
+    
+    /// Apply font size to the UI
+    fn apply_font_size(ctx: &egui::Context, font_size: f32) {
+        let mut style = (*ctx.style()).clone();
+        
+        // Update text styles with new font size
+        for (_text_style, font_id) in style.text_styles.iter_mut() {
+            font_id.size = font_size;
+        }
+        
+        ctx.set_style(style);
+    }
+    
+    /// Commands offered by the command palette
+    fn palette_entries(&self) -> Vec<PaletteEntry> {
+        let mut entries = vec![
+            PaletteEntry::new("File: New G-Code", PaletteCommand::NewDocument),
+            PaletteEntry::new("File: Open G-Code", PaletteCommand::OpenFile).with_shortcut("Ctrl+O"),
+            PaletteEntry::new("File: Save", PaletteCommand::SaveFile).with_shortcut("Ctrl+S"),
+            PaletteEntry::new("File: Save As", PaletteCommand::SaveFileAs),
+            PaletteEntry::new("Connection: Connect", PaletteCommand::Connect),
+            PaletteEntry::new("Connection: Disconnect", PaletteCommand::Disconnect),
+            PaletteEntry::new("Connection: Scan for GRBL", PaletteCommand::ScanForDevices),
+            PaletteEntry::new("Program: Run", PaletteCommand::RunProgram).with_shortcut("F5"),
+            PaletteEntry::new("Program: Pause", PaletteCommand::PauseProgram).with_shortcut("F6"),
+            PaletteEntry::new("Program: Stop", PaletteCommand::StopProgram).with_shortcut("F7"),
+            PaletteEntry::new("Program: Reset", PaletteCommand::ResetProgram),
+            PaletteEntry::new("Machine: Home", PaletteCommand::Home).with_shortcut("Ctrl+Shift+H"),
+            PaletteEntry::new("Machine: Unlock", PaletteCommand::Unlock).with_shortcut("Ctrl+Shift+U"),
+            PaletteEntry::new("Machine: Zero X", PaletteCommand::ZeroAxis('X')),
+            PaletteEntry::new("Machine: Zero Y", PaletteCommand::ZeroAxis('Y')),
+            PaletteEntry::new("Machine: Zero Z", PaletteCommand::ZeroAxis('Z')),
+            PaletteEntry::new("Machine: Zero All", PaletteCommand::ZeroAll),
+            PaletteEntry::new("Edit: Find", PaletteCommand::Find).with_shortcut("Ctrl+F"),
+            PaletteEntry::new("Edit: Toggle Breakpoint", PaletteCommand::ToggleBreakpoint).with_shortcut("F9"),
+            PaletteEntry::new("View: Reset Camera", PaletteCommand::ResetCamera),
+            PaletteEntry::new("View: Zoom to Fit", PaletteCommand::ZoomToFit),
+        ];
+        entries.extend(
+            ViewPreset::all()
+                .iter()
+                .map(|&preset| PaletteEntry::new(format!("View: {}", preset), PaletteCommand::ViewPreset(preset))),
+        );
+        entries.extend([
+            PaletteEntry::new("View: Show/Hide Console", PaletteCommand::ToggleConsole),
+            PaletteEntry::new("View: Run Screen", PaletteCommand::RunScreen),
+            PaletteEntry::new("View: Pendant Mode", PaletteCommand::PendantMode),
+            PaletteEntry::new("Tools: Settings", PaletteCommand::Settings).with_shortcut("Ctrl+,"),
+            PaletteEntry::new("Tools: Program Statistics", PaletteCommand::Statistics),
+            PaletteEntry::new("Tools: Compatibility Report", PaletteCommand::Compatibility),
+            PaletteEntry::new("Tools: Edge Finder", PaletteCommand::EdgeFinder),
+            PaletteEntry::new("Tools: Fixtures", PaletteCommand::Fixtures),
+            PaletteEntry::new("Tools: Maintenance", PaletteCommand::Maintenance),
+            PaletteEntry::new("Tools: Plugins", PaletteCommand::Plugins),
+            PaletteEntry::new("Tools: Axis Test Motion", PaletteCommand::AxisTest),
+            PaletteEntry::new("Help: Keyboard Shortcuts", PaletteCommand::Shortcuts).with_shortcut("F1"),
+            PaletteEntry::new("Help: GRBL Reference", PaletteCommand::Reference),
+        ]);
+        for command in &self.user_command_library.commands {
+            let mut entry = PaletteEntry::new(
+                format!("Command: {}", command.name),
+                PaletteCommand::UserCommand(command.name.clone()),
+            );
+            entry.shortcut = command.shortcut.clone();
+            entries.push(entry);
+        }
+        for script in &self.script_library.scripts {
+            entries.push(PaletteEntry::new(
+                format!("Script: Edit {}", script.name),
+                PaletteCommand::EditScript(script.name.clone()),
+            ));
+        }
+        entries
+    }
+    
+    /// Run a command chosen in the command palette
+    fn run_palette_command(&mut self, ctx: &egui::Context, command: PaletteCommand) {
+        match command {
+            PaletteCommand::NewDocument => self.new_document(),
+            PaletteCommand::OpenFile => self.open_file(),
+            PaletteCommand::SaveFile => self.save_file(),
+            PaletteCommand::SaveFileAs => self.save_file_as(),
+            PaletteCommand::Connect => {
+                self.connect_to_grbl(ctx);
+            }
+            PaletteCommand::Disconnect => self.disconnect_from_grbl(),
+            PaletteCommand::ScanForDevices => self.scan_for_devices(),
+            PaletteCommand::RunProgram => self.request_program_start(),
+            PaletteCommand::PauseProgram => self.pause_program(),
+            PaletteCommand::StopProgram => self.stop_program(),
+            PaletteCommand::ResetProgram => self.reset_program(),
+            PaletteCommand::Home => self.send_home_command(),
+            PaletteCommand::Unlock => self.send_unlock_command(),
+            PaletteCommand::ZeroAxis(axis) => self.send_zero_axis(axis),
+            PaletteCommand::ZeroAll => self.send_zero_all(),
+            PaletteCommand::Find => self.gcode_editor.toggle_find_replace(),
+            PaletteCommand::ToggleBreakpoint => self.gcode_editor.toggle_breakpoint_at_cursor(),
+            PaletteCommand::ResetCamera => {
+                if let Some(ref mut renderer) = self.renderer {
+                    renderer.reset_camera();
+                }
+            }
+            PaletteCommand::ZoomToFit => {
+                if let Some(ref mut renderer) = self.renderer {
+                    renderer.zoom_to_fit();
+                }
+            }
+            PaletteCommand::ViewPreset(preset) => self.apply_view_preset(preset),
+            PaletteCommand::ToggleConsole => self.show_console = !self.show_console,
+            PaletteCommand::RunScreen => self.run_screen.open = true,
+            PaletteCommand::PendantMode => {
+                self.pendant.open = true;
+                self.sync_pendant_mode();
+            }
+            PaletteCommand::Settings => {
+                self.show_settings_dialog = true;
+                self.temp_settings = Some(self.settings.clone());
+            }
+            PaletteCommand::Shortcuts => self.show_shortcuts = true,
+            PaletteCommand::Reference => self.reference_panel.open = true,
+            PaletteCommand::Statistics => self.statistics_panel.open = true,
+            PaletteCommand::Compatibility => self.compatibility_panel.open = true,
+            PaletteCommand::EdgeFinder => {
+                self.edge_finder.open = true;
+                self.probing_edge = None;
+            }
+            PaletteCommand::Fixtures => self.fixture_panel.open = true,
+            PaletteCommand::Maintenance => self.maintenance_panel.open = true,
+            PaletteCommand::Plugins => self.plugin_manager.open = true,
+            PaletteCommand::AxisTest => self.axis_test.open = true,
+            PaletteCommand::UserCommand(name) => self.request_user_command(&name),
+            PaletteCommand::EditScript(name) => {
+                if let Some(script) = self.script_library.scripts.iter().find(|script| script.name == name) {
+                    self.editing_script = Some(script.clone());
+                    self.show_script_editor = true;
+                }
+            }
+        }
+    }
+    
+    /// Show the keyboard shortcut list
+    fn show_shortcuts_window(ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("⌨ Keyboard Shortcuts")
+            .open(open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("keyboard_shortcuts")
+                    .num_columns(2)
+                    .striped(true)
+                    .spacing([16.0, 4.0])
+                    .show(ui, |ui| {
+                        for (keys, action) in KEYBOARD_SHORTCUTS {
+                            readout(ui, egui::RichText::new(*keys).monospace(), format!("{}: {}", keys, action));
+                            ui.label(*action);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+    
+    /// Replace the settings, applying what can change while running
+    ///
+    /// Cutting times and runtime counters keep counting in memory and are
+    /// kept. Returns the names of changed settings that only take effect
+    /// after a restart.
+    fn apply_settings(&mut self, ctx: &egui::Context, settings: Settings) -> Vec<&'static str> {
+        let theme_changed = self.settings.ui.dark_mode != settings.ui.dark_mode;
+        let font_changed = self.settings.ui.font_size != settings.ui.font_size;
+        let scale_changed = self.settings.ui.ui_scale != settings.ui.ui_scale;
+        let processing_changed = self.settings.processing.plunge_entry() != settings.processing.plunge_entry()
+            || self.settings.processing.skip_block_delete != settings.processing.skip_block_delete;
+        let rotary_changed = self.settings.visualization.rotary != settings.visualization.rotary;
+        let lathe_changed = self.settings.machine.lathe_mode != settings.machine.lathe_mode
+            || self.settings.machine.lathe_diameter_mode != settings.machine.lathe_diameter_mode;
+        let plugins_changed = self.settings.general.enabled_plugins != settings.general.enabled_plugins;
+        let mut restart = Vec::new();
+        if self.settings.visualization.vsync != settings.visualization.vsync {
+            restart.push("VSync");
+        }
+        if self.settings.visualization.low_power != settings.visualization.low_power {
+            restart.push("Low-power mode");
+        }
+        
+        let tool_usage = std::mem::take(&mut self.settings.machine.tool_wear.tools);
+        let maintenance = std::mem::take(&mut self.settings.machine.maintenance);
+        self.settings = settings;
+        self.settings.machine.tool_wear.tools = tool_usage;
+        self.settings.machine.maintenance = maintenance;
+        self.run_screen.always_on_top = self.settings.ui.run_screen_on_top;
+        self.recovery_engine.set_settings(self.settings.machine.recovery.clone());
+        
+        // Rebuild the preprocessor and refresh the toolpath when processing changed
+        if processing_changed || lathe_changed {
+            self.preprocessor = Self::build_preprocessor(&self.settings);
+            self.parser = Self::build_parser(&self.settings);
+            if !self.gcode_content.is_empty() {
+                self.parse_gcode();
+            }
+        } else if rotary_changed && !self.gcode_content.is_empty() {
+            self.parse_gcode();
+        }
+        
+        if let Some(ref mut renderer) = self.renderer {
+            renderer.apply_visualization(&self.settings.visualization);
+        }
+        if theme_changed {
+            Self::apply_theme(ctx, self.settings.ui.dark_mode);
+        }
+        if font_changed {
+            Self::apply_font_size(ctx, self.settings.ui.font_size);
+        }
+        if scale_changed {
+            ctx.set_zoom_factor(self.settings.ui.ui_scale);
+        }
+        self.console.set_max_messages(self.settings.ui.console_history_limit);
+        crash::record_settings(&self.settings);
+        if plugins_changed {
+            self.reload_plugins();
+        }
+        restart
+    }
+    
+    /// Apply the settings file when it is edited outside rCandle
+    ///
+    /// rCandle's own saves leave the file matching the settings in use, so
+    /// only a file that differs from them is applied. The window geometry
+    /// and runtime counters are tracked in memory and kept.
+    fn poll_settings_file(&mut self, ctx: &egui::Context) {
+        let now = std::time::Instant::now();
+        if self.last_settings_check.is_some_and(|at| now.duration_since(at) < FILE_WATCH_INTERVAL) {
+            return;
+        }
+        self.last_settings_check = Some(now);
+        
+        let Some(path) = self.launch.settings_path() else {
+            return;
+        };
+        let Some(modified) = documents::modified_time(&path) else {
+            return;
+        };
+        if self.settings_modified.replace(modified) == Some(modified) {
+            return;
+        }
+        
+        let mut settings = match Settings::load(&path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                // An editor may save in steps; the next complete write is picked up
+                self.report_error(e.with_context("Settings file changed but could not be read"));
+                return;
+            }
+        };
+        let ui = &self.settings.ui;
+        settings.ui.window_width = ui.window_width;
+        settings.ui.window_height = ui.window_height;
+        settings.ui.window_maximized = ui.window_maximized;
+        settings.ui.window_position = ui.window_position;
+        settings.machine.tool_wear.tools = self.settings.machine.tool_wear.tools.clone();
+        settings.machine.maintenance = self.settings.machine.maintenance.clone();
+        if toml::to_string(&settings).ok() == toml::to_string(&self.settings).ok() {
+            return;
+        }
+        
+        let restart = self.apply_settings(ctx, settings);
+        self.temp_settings = self.show_settings_dialog.then(|| self.settings.clone());
+        self.console.info(format!("Settings reloaded from {}", path.display()));
+        self.error_presenter.notify("Settings reloaded from the config file");
+        for name in restart {
+            if !self.restart_needed.contains(&name) {
+                self.restart_needed.push(name);
+            }
+        }
+    }
+    
+    /// Ask to restart for settings that only take effect on startup
+    fn show_restart_prompt(&mut self, ctx: &egui::Context) {
+        if self.restart_needed.is_empty() {
+            return;
+        }
+        let mut restart = false;
+        let mut later = false;
+        egui::Window::new("🔄 Restart Required")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "The settings file changed {}, which only takes effect after restarting rCandle.",
+                    self.restart_needed.join(" and ")
+                ));
+                ui.horizontal(|ui| {
+                    let busy = self.program_in_progress();
+                    if ui
+                        .add_enabled(!busy, egui::Button::new("Restart Now"))
+                        .on_disabled_hover_text("Wait for the program to finish")
+                        .clicked()
+                    {
+                        restart = true;
+                    }
+                    if ui.button("Later").clicked() {
+                        later = true;
+                    }
+                });
+            });
+        
+        if restart {
+            let relaunch = std::env::current_exe()
+                .and_then(|exe| std::process::Command::new(exe).args(std::env::args_os().skip(1)).spawn());
+            match relaunch {
+                Ok(_) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                Err(e) => self.report_error(Error::from(e).with_context("Failed to restart")),
+            }
+            self.restart_needed.clear();
+        } else if later {
+            self.restart_needed.clear();
+        }
+    }
+    
+    /// Show settings dialog window
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut should_close = false;
+        let mut should_save = false;
+        let mut should_reset = false;
+        
+        egui::Window::new("⚙ Settings")
+            .open(&mut open)
+            .default_size([600.0, 500.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                if let Some(ref mut temp_settings) = self.temp_settings {
+                    // Tabs for different settings categories
+                    egui::TopBottomPanel::top("settings_tabs").show_inside(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let _ = ui.selectable_label(false, "General");
+                            let _ = ui.selectable_label(false, "Connection");
+                            let _ = ui.selectable_label(false, "Visualization");
+                            let _ = ui.selectable_label(false, "Jog");
+                            let _ = ui.selectable_label(false, "UI");
+                        });
+                    });
+                    
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        let was_metric = temp_settings.general.units_metric;
+                        Self::show_general_settings(ui, &mut temp_settings.general);
+                        if temp_settings.general.units_metric != was_metric {
+                            temp_settings.jog.convert_units(temp_settings.general.units_metric);
+                        }
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_connection_settings(ui, &mut temp_settings.connection);
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_visualization_settings(ui, &mut temp_settings.visualization);
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_jog_settings(ui, &mut temp_settings.jog, temp_settings.general.units_metric);
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_machine_settings(ui, &mut temp_settings.machine);
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_ui_settings(ui, &mut temp_settings.ui);
+                        
+                        ui.separator();
+                        ui.add_space(10.0);
+                        
+                        Self::show_processing_settings(ui, &mut temp_settings.processing);
+                    });
+                    
+                    ui.separator();
+                    
+                    // Bottom buttons
+                    ui.horizontal(|ui| {
+                        if ui.button("💾 Save").clicked() {
+                            should_save = true;
+                        }
+                        
+                        if ui.button("🔄 Reset to Defaults").clicked() {
+                            should_reset = true;
+                        }
+                        
+                        if ui.button("❌ Cancel").clicked() {
+                            should_close = true;
+                        }
+                    });
+                }
+            });
+        
+        // Handle actions outside the closure to avoid borrowing issues
+        if should_save {
+            if let Some(temp_settings) = self.temp_settings.clone() {
+                let theme_changed = self.settings.ui.dark_mode != temp_settings.ui.dark_mode;
+                let font_changed = self.settings.ui.font_size != temp_settings.ui.font_size;
+                let restart = self.apply_settings(ctx, temp_settings);
+                if !restart.is_empty() {
+                    self.console.info(format!("{} changes take effect after restart", restart.join(" and ")));
+                }
+                if let Err(e) = self.launch.save_settings(&self.settings) {
+                    self.report_error(e.with_context("Failed to save settings"));
+                } else {
+                    self.console.info("Settings saved".to_string());
+                    if theme_changed || font_changed {
+                        self.console.info("Theme/font changes applied".to_string());
+                    }
+                }
+            }
+            self.show_settings_dialog = false;
+            self.temp_settings = None;
+        }
+        
+        if should_reset {
+            self.temp_settings = Some(crate::settings::Settings::default());
+            self.console.info("Settings reset to defaults".to_string());
+        }
+        
+        if should_close || !open {
+            self.show_settings_dialog = false;
+            self.temp_settings = None;
+        }
+    }
+    
+    /// Show general settings
+    fn show_general_settings(ui: &mut egui::Ui, settings: &mut crate::settings::GeneralSettings) {
+        ui.heading("General Settings");
+        ui.add_space(5.0);
+        
+        egui::Grid::new("general_settings_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Units:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.units_metric, true, "Metric (mm)")
+                        .on_hover_text("Use millimeters for measurements");
+                    ui.radio_value(&mut settings.units_metric, false, "Imperial (inches)")
+                        .on_hover_text("Use inches for measurements");
+                });
+                ui.end_row();
+                
+                ui.label("Arc Precision (°):")
+                    .on_hover_text("Angle between arc interpolation segments");
+                ui.add(egui::DragValue::new(&mut settings.arc_precision)
+                    .speed(0.1)
+                    .range(0.1..=10.0));
+                ui.end_row();
+                
+                ui.label("Arc Segments:")
+                    .on_hover_text("Number of line segments per arc");
+                ui.add(egui::DragValue::new(&mut settings.arc_segments)
+                    .speed(1)
+                    .range(4..=100));
+                ui.end_row();
+                
+                ui.label("Safe Z Height:")
+                    .on_hover_text("Height to retract to before rapid moves");
+                ui.add(egui::DragValue::new(&mut settings.safe_z)
+                    .speed(0.1)
+                    .range(0.0..=100.0)
+                    .suffix(if settings.units_metric { " mm" } else { " in" }));
+                ui.end_row();
+                
+                ui.label("Crash Reports:");
+                ui.checkbox(&mut settings.crash_reports, "Save a report when rCandle crashes")
+                    .on_hover_text("Saves the error, recent log and settings without hosts or passwords to a local zip. Nothing is sent.");
+                ui.end_row();
+            });
+        
+        ui.add_space(10.0);
+        ui.label("Startup Commands (one per line):")
+            .on_hover_text("Sent after connecting, once GRBL has sent its welcome message");
+        Self::edit_command_lines(ui, &mut settings.startup_commands);
+    }
+    
+    /// Edit a list of commands as multiline text
+    fn edit_command_lines(ui: &mut egui::Ui, commands: &mut Vec<String>) {
+        let mut text = commands.join("\n");
+        let response = ui.add(egui::TextEdit::multiline(&mut text)
+            .code_editor()
+            .desired_rows(3)
+            .desired_width(f32::INFINITY));
+        if response.changed() {
+            *commands = text.split('\n').map(str::to_string).collect();
+        }
+    }
+    
+    /// Show connection settings
+    fn show_connection_settings(ui: &mut egui::Ui, settings: &mut crate::settings::ConnectionSettings) {
+        ui.heading("Connection Settings");
+        ui.add_space(5.0);
+        
+        egui::Grid::new("connection_settings_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Connection Type:")
+                    .on_hover_text("Simulator connects to a built-in mock GRBL device");
+                egui::ComboBox::from_id_source("connection_type_combo")
+                    .selected_text(settings.connection_type.to_string())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut settings.connection_type, ConnectionType::Serial, "Serial");
+                        ui.selectable_value(&mut settings.connection_type, ConnectionType::Bluetooth, "Bluetooth");
+                        ui.selectable_value(&mut settings.connection_type, ConnectionType::Simulator, "Simulator");
+                        ui.selectable_value(&mut settings.connection_type, ConnectionType::Network, "Network");
+                    });
+                ui.end_row();
+                
+                ui.label("Bluetooth Device:")
+                    .on_hover_text("Device address (e.g. 98:D3:31:F5:12:34) or the serial port of a paired device");
+                ui.text_edit_singleline(&mut settings.bluetooth_device);
+                ui.end_row();
+                
+                ui.label("RFCOMM Channel:");
+                ui.add(egui::DragValue::new(&mut settings.bluetooth_channel).range(1..=30));
+                ui.end_row();
+                
+                ui.label("Port Name:");
+                ui.text_edit_singleline(&mut settings.port_name);
+                ui.end_row();
+                
+                ui.label("Baud Rate:");
+                egui::ComboBox::from_id_source("baud_rate_combo")
+                    .selected_text(format!("{}", settings.baud_rate))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut settings.baud_rate, 9600, "9600");
+                        ui.selectable_value(&mut settings.baud_rate, 19200, "19200");
+                        ui.selectable_value(&mut settings.baud_rate, 38400, "38400");
+                        ui.selectable_value(&mut settings.baud_rate, 57600, "57600");
+                        ui.selectable_value(&mut settings.baud_rate, 115200, "115200");
+                        ui.selectable_value(&mut settings.baud_rate, 230400, "230400");
+                    });
+                ui.end_row();
+                
+                ui.label("Connection Timeout:");
+                ui.add(egui::DragValue::new(&mut settings.timeout_ms)
+                    .speed(100)
+                    .range(100..=30000)
+                    .suffix(" ms"));
+                ui.end_row();
+                
+                ui.label("Command Timeout:");
+                ui.add(egui::DragValue::new(&mut settings.command_timeout_ms)
+                    .speed(100)
+                    .range(100..=60000)
+                    .suffix(" ms"));
+                ui.end_row();
+                
+                ui.label("Status Query Interval:");
+                ui.add(egui::DragValue::new(&mut settings.status_query_interval_ms)
+                    .speed(10)
+                    .range(50..=5000)
+                    .suffix(" ms"));
+                ui.end_row();
+                
+                ui.label("Network RX Buffer:")
+                    .on_hover_text("Controller receive buffer for sending several lines ahead on Telnet/WebSocket links (127 for GRBL, larger for grblHAL/FluidNC, 0 = wait for each ok)");
+                ui.add(egui::DragValue::new(&mut settings.network_rx_buffer)
+                    .speed(1)
+                    .range(0..=4096)
+                    .suffix(" bytes"));
+                ui.end_row();
+                
+                ui.label("Status Watchdog:")
+                    .on_hover_text("Missed status intervals before streaming is paused and the link recovered (0 = off)");
+                ui.add(egui::DragValue::new(&mut settings.watchdog_missed_intervals)
+                    .speed(1)
+                    .range(0..=50)
+                    .suffix(" intervals"));
+                ui.end_row();
+                
+                ui.label("Auto-connect on Startup:");
+                ui.checkbox(&mut settings.auto_connect, "");
+                ui.end_row();
+                
+                ui.label("Simulator Physics:")
+                    .on_hover_text("Limit simulated motion by the axis max rates and accelerations ($110-$122) so dry runs take realistic time");
+                ui.checkbox(&mut settings.simulator_physics, "");
+                ui.end_row();
+            });
+        
+        ui.add_space(10.0);
+        ui.collapsing("Advanced Serial Options", |ui| {
+            egui::Grid::new("serial_advanced_grid")
+                .num_columns(2)
+                .spacing([10.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label("DTR on Connect:")
+                        .on_hover_text("Many Arduino-based boards reset when DTR toggles; hold it low to connect without a reset");
+                    Self::line_control_combo(ui, "dtr_combo", &mut settings.dtr);
+                    ui.end_row();
+                    
+                    ui.label("RTS on Connect:");
+                    Self::line_control_combo(ui, "rts_combo", &mut settings.rts);
+                    ui.end_row();
+                    
+                    ui.label("Reset Pulse:");
+                    ui.add(egui::DragValue::new(&mut settings.reset_pulse_ms)
+                        .speed(10)
+                        .range(10..=2000)
+                        .suffix(" ms"));
+                    ui.end_row();
+                    
+                    ui.label("Startup Delay:")
+                        .on_hover_text("Time to wait after opening the port before sending the first command");
+                    ui.add(egui::DragValue::new(&mut settings.startup_delay_ms)
+                        .speed(50)
+                        .range(0..=10000)
+                        .suffix(" ms"));
+                    ui.end_row();
+                    
+                    ui.label("Flush on Connect:")
+                        .on_hover_text("Discard anything received while the board was starting up");
+                    ui.checkbox(&mut settings.flush_on_connect, "");
+                    ui.end_row();
+                });
+        });
+    }
+    
+    /// Bluetooth device selection with discovery and pairing hints
+    fn show_bluetooth_selector(&mut self, ui: &mut egui::Ui) {
+        let device = &mut self.settings.connection.bluetooth_device;
+        let mut discover = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("bluetooth_device_combo")
+                .selected_text(device.as_str())
+                .show_ui(ui, |ui| {
+                    for found in &self.bluetooth_devices {
+                        let label = if found.name.is_empty() {
+                            found.target_text()
+                        } else {
+                            format!("{} ({})", found.name, found.target_text())
+                        };
+                        ui.selectable_value(device, found.target_text(), label);
+                    }
+                });
+            let discovering = self.bluetooth_task.is_some();
+            if ui
+                .add_enabled(!discovering, egui::Button::new("🔍"))
+                .on_hover_text("Discover Bluetooth devices")
+                .clicked()
+            {
+                discover = true;
+            }
+            if discovering {
+                ui.spinner();
+            }
+        });
+        if discover {
+            self.discover_bluetooth();
         }
+        ui.horizontal(|ui| {
+            ui.label("Device:");
+            ui.text_edit_singleline(&mut self.settings.connection.bluetooth_device);
+        });
+        ui.collapsing("Pairing help", |ui| {
+            for hint in BluetoothConnection::pairing_hints() {
+                ui.label(format!("• {}", hint));
+            }
+        });
     }
     
-    /// Apply font size to the UI
-    fn apply_font_size(ctx: &egui::Context, font_size: f32) {
-        let mut style = (*ctx.style()).clone();
-        
-        // Update text styles with new font size
-        for (_text_style, font_id) in style.text_styles.iter_mut() {
-            font_id.size = font_size;
+    /// Saved network profiles and discovered devices, with the selected profile's fields
+    fn show_network_selector(&mut self, ui: &mut egui::Ui) {
+        let connection = &mut self.settings.connection;
+        let selected_text = connection
+            .selected_network_profile()
+            .map(|profile| profile.label())
+            .unwrap_or_else(|| "Select a device".to_string());
+        let mut discover = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("network_profile_combo")
+                .selected_text(selected_text)
+                .width(180.0)
+                .show_ui(ui, |ui| {
+                    for profile in &connection.network_profiles {
+                        ui.selectable_value(&mut connection.network_profile, profile.name.clone(), profile.label());
+                    }
+                    if !self.network_devices.is_empty() {
+                        ui.separator();
+                        ui.weak("Discovered");
+                    }
+                    for device in &self.network_devices {
+                        if ui.selectable_label(false, format!("📡 {}", device.label())).clicked() {
+                            let profile = NetworkProfile::from_device(device);
+                            connection.network_profile = profile.name.clone();
+                            match connection.network_profiles.iter_mut().find(|p| p.name == profile.name) {
+                                Some(existing) => *existing = profile,
+                                None => connection.network_profiles.push(profile),
+                            }
+                        }
+                    }
+                });
+            let discovering = self.discovery_task.is_some();
+            if ui
+                .add_enabled(!discovering, egui::Button::new("🔍"))
+                .on_hover_text("Discover FluidNC and grblHAL devices on the network")
+                .clicked()
+            {
+                discover = true;
+            }
+            if discovering {
+                ui.spinner();
+            }
+        });
+        if discover {
+            self.discover_network();
         }
         
-        ctx.set_style(style);
-    }
-    
-    /// Show settings dialog window
-    fn show_settings_window(&mut self, ctx: &egui::Context) {
-        let mut open = true;
-        let mut should_close = false;
-        let mut should_save = false;
-        let mut should_reset = false;
+        let connection = &mut self.settings.connection;
+        ui.horizontal(|ui| {
+            if ui.button("➕ New").on_hover_text("Add a network profile").clicked() {
+                let mut profile = NetworkProfile::default();
+                let mut number = 1;
+                while connection.network_profiles.iter().any(|p| p.name == profile.name) {
+                    number += 1;
+                    profile.name = format!("Controller {}", number);
+                }
+                connection.network_profile = profile.name.clone();
+                connection.network_profiles.push(profile);
+            }
+            let has_selection = connection.selected_network_profile().is_some();
+            if ui.add_enabled(has_selection, egui::Button::new("🗑 Delete")).clicked() {
+                let name = std::mem::take(&mut connection.network_profile);
+                connection.network_profiles.retain(|p| p.name != name);
+            }
+        });
         
-        egui::Window::new("⚙ Settings")
-            .open(&mut open)
-            .default_size([600.0, 500.0])
-            .resizable(true)
-            .show(ctx, |ui| {
-                if let Some(ref mut temp_settings) = self.temp_settings {
-                    // Tabs for different settings categories
-                    egui::TopBottomPanel::top("settings_tabs").show_inside(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            let _ = ui.selectable_label(false, "General");
-                            let _ = ui.selectable_label(false, "Connection");
-                            let _ = ui.selectable_label(false, "Visualization");
-                            let _ = ui.selectable_label(false, "Jog");
-                            let _ = ui.selectable_label(false, "UI");
-                        });
+        let name = connection.network_profile.clone();
+        let Some(profile) = connection.network_profiles.iter_mut().find(|p| p.name == name) else {
+            return;
+        };
+        egui::Grid::new("network_profile_grid")
+            .num_columns(2)
+            .spacing([6.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Name:");
+                if ui.text_edit_singleline(&mut profile.name).changed() {
+                    connection.network_profile = profile.name.clone();
+                }
+                ui.end_row();
+                
+                ui.label("Protocol:");
+                egui::ComboBox::from_id_source("network_protocol_combo")
+                    .selected_text(profile.protocol.to_string())
+                    .show_ui(ui, |ui| {
+                        for protocol in [NetworkProtocol::Telnet, NetworkProtocol::WebSocket] {
+                            if ui.selectable_value(&mut profile.protocol, protocol, protocol.to_string()).changed() {
+                                profile.port = protocol.default_port();
+                            }
+                        }
                     });
+                ui.end_row();
+                
+                ui.label("Host:");
+                ui.text_edit_singleline(&mut profile.host);
+                ui.end_row();
+                
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut profile.port).range(1..=65535));
+                ui.end_row();
+                
+                if profile.protocol == NetworkProtocol::WebSocket {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut profile.path);
+                    ui.end_row();
                     
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        Self::show_general_settings(ui, &mut temp_settings.general);
-                        
-                        ui.separator();
-                        ui.add_space(10.0);
-                        
-                        Self::show_connection_settings(ui, &mut temp_settings.connection);
-                        
-                        ui.separator();
-                        ui.add_space(10.0);
-                        
-                        Self::show_visualization_settings(ui, &mut temp_settings.visualization);
-                        
-                        ui.separator();
-                        ui.add_space(10.0);
-                        
-                        Self::show_jog_settings(ui, &mut temp_settings.jog);
-                        
-                        ui.separator();
-                        ui.add_space(10.0);
-                        
-                        Self::show_ui_settings(ui, &mut temp_settings.ui);
+                    ui.label("Secure:");
+                    ui.checkbox(&mut profile.secure, "wss://");
+                    ui.end_row();
+                }
+            });
+        if profile.protocol == NetworkProtocol::WebSocket {
+            Self::show_websocket_security(ui, profile);
+        }
+    }
+    
+    /// Authentication and certificate options of a WebSocket profile
+    fn show_websocket_security(ui: &mut egui::Ui, profile: &mut NetworkProfile) {
+        ui.collapsing("Security", |ui| {
+            egui::ComboBox::from_label("Authentication")
+                .selected_text(profile.auth.to_string())
+                .show_ui(ui, |ui| {
+                    let options = [
+                        WebSocketAuth::None,
+                        WebSocketAuth::Bearer { token: String::new() },
+                        WebSocketAuth::Basic { username: String::new(), password: String::new() },
+                    ];
+                    for option in options {
+                        let selected = std::mem::discriminant(&profile.auth) == std::mem::discriminant(&option);
+                        if ui.selectable_label(selected, option.to_string()).clicked() && !selected {
+                            profile.auth = option;
+                        }
+                    }
+                });
+            match &mut profile.auth {
+                WebSocketAuth::None => {}
+                WebSocketAuth::Bearer { token } => {
+                    ui.horizontal(|ui| {
+                        ui.label("Token:");
+                        ui.add(egui::TextEdit::singleline(token).password(true));
                     });
-                    
-                    ui.separator();
-                    
-                    // Bottom buttons
+                }
+                WebSocketAuth::Basic { username, password } => {
                     ui.horizontal(|ui| {
-                        if ui.button("💾 Save").clicked() {
-                            should_save = true;
+                        ui.label("Username:");
+                        ui.text_edit_singleline(username);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Password:");
+                        ui.add(egui::TextEdit::singleline(password).password(true));
+                    });
+                }
+            }
+            if profile.auth != WebSocketAuth::None && !profile.secure {
+                ui.colored_label(egui::Color32::YELLOW, "⚠ Credentials are sent unencrypted without wss://");
+            }
+            
+            if profile.secure {
+                ui.checkbox(&mut profile.tls.accept_invalid_certs, "Accept self-signed certificates")
+                    .on_hover_text("Skip certificate validation. Only use this on a trusted network.");
+                ui.checkbox(&mut profile.tls.accept_invalid_hostnames, "Accept certificates for other host names");
+                ui.horizontal(|ui| {
+                    ui.label("CA certificate:");
+                    let text = profile
+                        .tls
+                        .ca_certificate
+                        .as_ref()
+                        .map_or_else(|| "System roots".to_string(), |path| path.display().to_string());
+                    ui.weak(text);
+                    if ui.button("📂").on_hover_text("Trust an additional CA certificate (PEM)").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter("PEM certificate", &["pem", "crt"]).pick_file() {
+                            profile.tls.ca_certificate = Some(path);
                         }
-                        
-                        if ui.button("🔄 Reset to Defaults").clicked() {
-                            should_reset = true;
+                    }
+                    if profile.tls.ca_certificate.is_some() && ui.button("✖").clicked() {
+                        profile.tls.ca_certificate = None;
+                    }
+                });
+            }
+        });
+    }
+    
+    /// Combo box for a DTR/RTS line behavior
+    fn line_control_combo(ui: &mut egui::Ui, id: &str, value: &mut LineControl) {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(value.to_string())
+            .show_ui(ui, |ui| {
+                for option in [LineControl::Unchanged, LineControl::High, LineControl::Low, LineControl::Pulse] {
+                    ui.selectable_value(value, option, option.to_string());
+                }
+            });
+    }
+    
+    /// Show visualization settings
+    fn show_visualization_settings(ui: &mut egui::Ui, settings: &mut crate::settings::VisualizationSettings) {
+        use crate::settings::{ColorPalette, ColorScheme, LowPowerMode, RotaryViewMode};
+        
+        ui.heading("Visualization Settings");
+        ui.add_space(5.0);
+        
+        egui::Grid::new("visualization_settings_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Show Grid:");
+                ui.checkbox(&mut settings.show_grid, "");
+                ui.end_row();
+                
+                ui.label("Adaptive Grid:")
+                    .on_hover_text("Pick 1/5/10/50 mm (0.1/0.5/1/5 in) spacing from the zoom");
+                ui.checkbox(&mut settings.adaptive_grid, "");
+                ui.end_row();
+                
+                ui.label("Grid Size:");
+                ui.add_enabled(!settings.adaptive_grid, egui::DragValue::new(&mut settings.grid_size)
+                    .speed(1.0)
+                    .range(1.0..=100.0));
+                ui.end_row();
+                
+                ui.label("Grid Labels:")
+                    .on_hover_text("Label grid lines with their coordinates");
+                ui.checkbox(&mut settings.grid_labels, "");
+                ui.end_row();
+                
+                ui.label("Show Tool:");
+                ui.checkbox(&mut settings.show_tool, "");
+                ui.end_row();
+                
+                ui.label("Show Origin:");
+                ui.checkbox(&mut settings.show_origin, "");
+                ui.end_row();
+                
+                ui.label("Show Bounds:");
+                ui.checkbox(&mut settings.show_bounds, "");
+                ui.end_row();
+                
+                ui.label("Show Job Dimensions:")
+                    .on_hover_text("Bounding box of the cutting moves with X/Y/Z extents");
+                ui.checkbox(&mut settings.show_dimensions, "");
+                ui.end_row();
+                
+                ui.label("Show Fixtures:")
+                    .on_hover_text("Usable areas of the fixture library in the 2D view");
+                ui.checkbox(&mut settings.show_fixtures, "");
+                ui.end_row();
+                
+                ui.label("MSAA Samples:");
+                egui::ComboBox::from_id_source("msaa_combo")
+                    .selected_text(format!("{}x", settings.msaa_samples))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut settings.msaa_samples, 1, "1x");
+                        ui.selectable_value(&mut settings.msaa_samples, 2, "2x");
+                        ui.selectable_value(&mut settings.msaa_samples, 4, "4x");
+                        ui.selectable_value(&mut settings.msaa_samples, 8, "8x");
+                    });
+                ui.end_row();
+                
+                ui.label("VSync:")
+                    .on_hover_text("Takes effect after restart");
+                ui.checkbox(&mut settings.vsync, "");
+                ui.end_row();
+                
+                ui.label("Low-Power Mode:")
+                    .on_hover_text("For the Raspberry Pi and other weak GPUs: 2D view only, no MSAA \
+                        and a slower idle refresh. Auto turns it on when the GPU looks weak; On also forces \
+                        VSync. Takes effect after restart");
+                egui::ComboBox::from_id_source("low_power_combo")
+                    .selected_text(settings.low_power.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in [LowPowerMode::Auto, LowPowerMode::Off, LowPowerMode::On] {
+                            ui.selectable_value(&mut settings.low_power, mode, mode.to_string());
                         }
-                        
-                        if ui.button("❌ Cancel").clicked() {
-                            should_close = true;
+                    });
+                ui.end_row();
+                
+                ui.label("Field of View:");
+                ui.add(egui::Slider::new(&mut settings.fov, 30.0..=120.0)
+                    .suffix("°"));
+                ui.end_row();
+                
+                ui.label("Camera Speed:");
+                ui.add(egui::Slider::new(&mut settings.camera_speed, 0.1..=5.0));
+                ui.end_row();
+                
+                ui.label("Palette:")
+                    .on_hover_text("Preset colors; the color-blind safe palette avoids red against green");
+                let current = settings.color_scheme.palette();
+                egui::ComboBox::from_id_source("palette_combo")
+                    .selected_text(current.map_or("Custom".to_string(), |palette| palette.to_string()))
+                    .show_ui(ui, |ui| {
+                        for palette in ColorPalette::ALL {
+                            if ui.selectable_label(current == Some(palette), palette.to_string()).clicked() {
+                                settings.color_scheme = ColorScheme::from_palette(palette);
+                            }
                         }
                     });
+                ui.end_row();
+                
+                ui.label("Line Patterns:")
+                    .on_hover_text("Draw rapids dashed and arcs dotted in the 2D view");
+                ui.checkbox(&mut settings.line_patterns, "");
+                ui.end_row();
+                
+                let colors = &mut settings.color_scheme;
+                for (label, color) in [
+                    ("Background:", &mut colors.background),
+                    ("Grid Color:", &mut colors.grid),
+                    ("Toolpath Color:", &mut colors.toolpath),
+                    ("Arc Color:", &mut colors.arc),
+                    ("Rapid Color:", &mut colors.rapid),
+                    ("Tool Color:", &mut colors.tool),
+                ] {
+                    ui.label(label);
+                    ui.color_edit_button_rgba_unmultiplied(color);
+                    ui.end_row();
+                }
+                
+                ui.label("Rotary (A) View:")
+                    .on_hover_text("Show A axis moves on a cylinder of the stock diameter");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.rotary.mode, RotaryViewMode::Off, "Off");
+                    ui.radio_value(&mut settings.rotary.mode, RotaryViewMode::Unwrapped, "Unwrapped");
+                    ui.radio_value(&mut settings.rotary.mode, RotaryViewMode::Wrapped, "Wrapped");
+                });
+                ui.end_row();
+                
+                if settings.rotary.mode != RotaryViewMode::Off {
+                    ui.label("Stock Diameter:");
+                    ui.add(egui::DragValue::new(&mut settings.rotary.diameter)
+                        .speed(0.1)
+                        .range(0.1..=1000.0));
+                    ui.end_row();
                 }
             });
+    }
+    
+    /// Show jog settings
+    fn show_jog_settings(ui: &mut egui::Ui, settings: &mut crate::settings::JogSettings, metric: bool) {
+        let units = if metric { "mm" } else { "in" };
+        ui.heading("Jog Settings");
+        ui.add_space(5.0);
         
-        // Handle actions outside the closure to avoid borrowing issues
-        if should_save {
-            if let Some(ref temp_settings) = self.temp_settings {
-                // Check if theme or font size changed
-                let theme_changed = self.settings.ui.dark_mode != temp_settings.ui.dark_mode;
-                let font_changed = self.settings.ui.font_size != temp_settings.ui.font_size;
+        egui::Grid::new("jog_settings_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("XY Feed Rate:");
+                ui.add(egui::DragValue::new(&mut settings.xy_feed_rate)
+                    .speed(10.0)
+                    .range(1.0..=10000.0)
+                    .suffix(format!(" {}/min", units)));
+                ui.end_row();
                 
-                self.settings = temp_settings.clone();
+                ui.label("Z Feed Rate:");
+                ui.add(egui::DragValue::new(&mut settings.z_feed_rate)
+                    .speed(10.0)
+                    .range(1.0..=5000.0)
+                    .suffix(format!(" {}/min", units)));
+                ui.end_row();
                 
-                // Apply theme and font changes immediately
-                if theme_changed {
-                    Self::apply_theme(ctx, self.settings.ui.dark_mode);
-                }
-                if font_changed {
-                    Self::apply_font_size(ctx, self.settings.ui.font_size);
-                }
+                ui.label("Continuous Mode:");
+                ui.checkbox(&mut settings.continuous_mode, "");
+                ui.end_row();
+                
+                ui.label("Keyboard Jog:");
+                ui.checkbox(&mut settings.keyboard_jog, "Arrow keys jog XY, Page Up/Down jog Z")
+                    .on_hover_text("Hold Shift for 10× the step or Ctrl for 1/10");
+                ui.end_row();
+            });
+        
+        ui.add_space(10.0);
+        ui.label("Jog Limits (machine coordinates):");
+        ui.checkbox(&mut settings.limit_to_travel, "Limit jogs to machine travel");
+        if settings.limit_to_travel {
+            Self::jog_limits_grid(ui, "jog_travel_grid", &mut settings.travel);
+        }
+        ui.checkbox(&mut settings.fence_enabled, "Limit jogs to fence")
+            .on_hover_text("Keep jogs inside a box, e.g. clear of a fragile fixture");
+        if settings.fence_enabled {
+            Self::jog_limits_grid(ui, "jog_fence_grid", &mut settings.fence);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Edge Margin:");
+            ui.add(egui::DragValue::new(&mut settings.edge_margin)
+                .speed(0.1)
+                .range(0.0..=50.0)
+                .suffix(" mm"))
+                .on_hover_text("Distance kept from the limit by the Edge jog buttons");
+        });
+        
+        ui.add_space(10.0);
+        ui.label(format!("Step Sizes ({}):", units));
+        
+        // Show step sizes as editable list
+        let mut i = 0;
+        while i < settings.step_sizes.len() {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.step_sizes[i])
+                    .speed(0.1)
+                    .range(0.001..=1000.0));
                 
-                if let Err(e) = self.settings.save_default() {
-                    self.console.error(format!("Failed to save settings: {}", e));
-                } else {
-                    self.console.info("Settings saved".to_string());
-                    if theme_changed || font_changed {
-                        self.console.info("Theme/font changes applied".to_string());
+                if ui.button("🗑").clicked() {
+                    settings.step_sizes.remove(i);
+                    if settings.default_step_index >= settings.step_sizes.len() {
+                        settings.default_step_index = settings.step_sizes.len().saturating_sub(1);
                     }
+                } else {
+                    i += 1;
                 }
-            }
-            self.show_settings_dialog = false;
-            self.temp_settings = None;
-        }
-        
-        if should_reset {
-            self.temp_settings = Some(crate::settings::Settings::default());
-            self.console.info("Settings reset to defaults".to_string());
+            });
         }
         
-        if should_close || !open {
-            self.show_settings_dialog = false;
-            self.temp_settings = None;
+        if ui.button("➕ Add Step Size").clicked() {
+            settings.step_sizes.push(1.0);
         }
     }
     
-    /// Show general settings
-    fn show_general_settings(ui: &mut egui::Ui, settings: &mut crate::settings::GeneralSettings) {
-        ui.heading("General Settings");
+    /// Show machine profile settings
+    fn show_machine_settings(ui: &mut egui::Ui, settings: &mut crate::settings::MachineProfile) {
+        use crate::settings::{NamedPosition, PositionRole};
+        
+        ui.heading("Machine Profile");
         ui.add_space(5.0);
         
-        egui::Grid::new("general_settings_grid")
+        egui::Grid::new("machine_settings_grid")
             .num_columns(2)
             .spacing([10.0, 8.0])
             .show(ui, |ui| {
-                ui.label("Units:");
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut settings.units_metric, true, "Metric (mm)")
-                        .on_hover_text("Use millimeters for measurements");
-                    ui.radio_value(&mut settings.units_metric, false, "Imperial (inches)")
-                        .on_hover_text("Use inches for measurements");
-                });
+                ui.label("Safe Z (machine):");
+                ui.add(egui::DragValue::new(&mut settings.safe_z).speed(0.5).suffix(" mm"));
                 ui.end_row();
                 
-                ui.label("Arc Precision (°):")
-                    .on_hover_text("Angle between arc interpolation segments");
-                ui.add(egui::DragValue::new(&mut settings.arc_precision)
+                ui.label("Hold at M6:");
+                ui.checkbox(&mut settings.tool_change_enabled, "Pause at tool changes and move to the tool change position");
+                ui.end_row();
+                
+                ui.label("Measure after change:");
+                ui.checkbox(&mut settings.measure_after_tool_change, "Probe the new tool on the probe plate");
+                ui.end_row();
+                
+                let check = &mut settings.tool_check;
+                ui.label("Tool breakage check:");
+                ui.checkbox(&mut check.enabled, "Measure the tool on the probe plate during programs")
+                    .on_hover_text("The program is held if the tool length changed by more than the threshold");
+                ui.end_row();
+                
+                if check.enabled {
+                    ui.label("Check interval:");
+                    ui.add(egui::DragValue::new(&mut check.interval_minutes)
+                        .speed(1.0)
+                        .range(0.0..=600.0)
+                        .suffix(" min"))
+                        .on_hover_text("0 for no timed checks");
+                    ui.end_row();
+                    
+                    ui.label("Check at tool changes:");
+                    ui.add_enabled(
+                        settings.tool_change_enabled,
+                        egui::Checkbox::new(&mut check.after_each_tool, "Check the outgoing tool at each M6"),
+                    );
+                    ui.end_row();
+                    
+                    ui.label("Breakage threshold:");
+                    ui.add(egui::DragValue::new(&mut check.threshold)
+                        .speed(0.01)
+                        .range(0.01..=10.0)
+                        .suffix(" mm"));
+                    ui.end_row();
+                    
+                    ui.label("Spin-up delay:");
+                    ui.add(egui::DragValue::new(&mut check.spin_up_delay)
+                        .speed(0.1)
+                        .range(0.0..=30.0)
+                        .suffix(" s"));
+                    ui.end_row();
+                }
+                
+                let wear = &mut settings.tool_wear;
+                ui.label("Tool wear:");
+                ui.checkbox(&mut wear.enabled, "Lower the feed override as tools cut")
+                    .on_hover_text("Cutting time is tracked per tool and reset from the tool change prompt");
+                ui.end_row();
+                
+                if wear.enabled {
+                    ui.label("Reduce every:");
+                    ui.add(egui::DragValue::new(&mut wear.interval_minutes)
+                        .speed(1.0)
+                        .range(1.0..=600.0)
+                        .suffix(" min"));
+                    ui.end_row();
+                    
+                    ui.label("Reduce by:");
+                    ui.add(egui::DragValue::new(&mut wear.reduction_percent)
+                        .speed(0.5)
+                        .range(0.0..=50.0)
+                        .suffix(" %"));
+                    ui.end_row();
+                    
+                    ui.label("Lowest override:");
+                    ui.add(egui::DragValue::new(&mut wear.minimum_override)
+                        .speed(1.0)
+                        .range(10.0..=100.0)
+                        .suffix(" %"));
+                    ui.end_row();
+                }
+                
+                ui.label("Probe Feed Rate:");
+                ui.add(egui::DragValue::new(&mut settings.probe_feed_rate)
+                    .speed(5.0)
+                    .range(1.0..=2000.0)
+                    .suffix(" mm/min"));
+                ui.end_row();
+                
+                ui.label("Probe Distance:");
+                ui.add(egui::DragValue::new(&mut settings.probe_distance)
+                    .speed(1.0)
+                    .range(1.0..=500.0)
+                    .suffix(" mm"));
+                ui.end_row();
+                
+                ui.label("Probe Retract:");
+                ui.add(egui::DragValue::new(&mut settings.probe_retract)
                     .speed(0.1)
-                    .range(0.1..=10.0));
+                    .range(0.0..=50.0)
+                    .suffix(" mm"));
                 ui.end_row();
                 
-                ui.label("Arc Segments:")
-                    .on_hover_text("Number of line segments per arc");
-                ui.add(egui::DragValue::new(&mut settings.arc_segments)
-                    .speed(1)
-                    .range(4..=100));
+                ui.label("Spindle Jog Interlock:");
+                egui::ComboBox::from_id_source("spindle_jog_interlock")
+                    .selected_text(settings.spindle_jog_interlock.to_string())
+                    .show_ui(ui, |ui| {
+                        for interlock in [SpindleJogInterlock::Off, SpindleJogInterlock::Confirm, SpindleJogInterlock::Block] {
+                            ui.selectable_value(&mut settings.spindle_jog_interlock, interlock, interlock.to_string());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Z-minus jogs beyond the threshold while the spindle is running");
                 ui.end_row();
                 
-                ui.label("Safe Z Height:")
-                    .on_hover_text("Height to retract to before rapid moves");
-                ui.add(egui::DragValue::new(&mut settings.safe_z)
+                ui.label("Interlock Threshold:");
+                ui.add(egui::DragValue::new(&mut settings.spindle_jog_z_limit)
                     .speed(0.1)
                     .range(0.0..=100.0)
-                    .suffix(if settings.units_metric { " mm" } else { " in" }));
+                    .suffix(" mm"));
+                ui.end_row();
+                
+                ui.label("Max Spindle Speed:");
+                ui.add(egui::DragValue::new(&mut settings.max_spindle_rpm)
+                    .speed(100.0)
+                    .range(0.0..=100000.0)
+                    .suffix(" RPM"))
+                    .on_hover_text("Programs commanding a higher speed are not started (0 for no limit)");
+                ui.end_row();
+                
+                ui.label("Feed Override Ramp:");
+                ui.add(egui::DragValue::new(&mut settings.feed_override_ramp)
+                    .speed(1.0)
+                    .range(0.0..=500.0)
+                    .suffix(" %/s"))
+                    .on_hover_text("How fast feed override changes are sent, so a big change doesn't load the bit at once (0 to change at once)");
+                ui.end_row();
+                
+                ui.label("User Outputs:");
+                ui.add(egui::DragValue::new(&mut settings.user_outputs).range(0..=8))
+                    .on_hover_text("grblHAL auxiliary outputs controlled with M62-M65 (0 to hide)");
+                ui.end_row();
+                
+                ui.label("Backlash (X/Y/Z):")
+                    .on_hover_text("Measure with Tools → Measure Backlash");
+                ui.horizontal(|ui| {
+                    for value in settings.backlash.iter_mut() {
+                        ui.add(egui::DragValue::new(value).speed(0.001).range(0.0..=2.0).fixed_decimals(3));
+                    }
+                    ui.label("mm");
+                });
+                ui.end_row();
+                
+                ui.label("Compensate Backlash:");
+                ui.checkbox(&mut settings.compensate_backlash, "Take up backlash on reversals when streaming");
+                ui.end_row();
+                
+                ui.label("Lathe Mode:");
+                ui.checkbox(&mut settings.lathe_mode, "grblHAL lathe: XZ view, G96/G97 and G7/G8 aware")
+                    .on_hover_text("Programs are shown from the front, with X as the cross slide");
                 ui.end_row();
+                
+                ui.label("X as Diameter:");
+                ui.add_enabled(
+                    settings.lathe_mode,
+                    egui::Checkbox::new(&mut settings.lathe_diameter_mode, "Program and show X as a diameter (G7)"),
+                );
+                ui.end_row();
+            });
+        
+        ui.add_space(10.0);
+        ui.label("Named Positions (machine coordinates):");
+        let mut remove = None;
+        egui::Grid::new("machine_positions_grid")
+            .num_columns(6)
+            .spacing([6.0, 4.0])
+            .show(ui, |ui| {
+                for (index, position) in settings.positions.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(&mut position.name).desired_width(100.0));
+                    egui::ComboBox::from_id_source(("position_role", index))
+                        .selected_text(position.role.to_string())
+                        .show_ui(ui, |ui| {
+                            for role in [PositionRole::Park, PositionRole::ToolChange, PositionRole::ProbePlate, PositionRole::Custom] {
+                                ui.selectable_value(&mut position.role, role, role.to_string());
+                            }
+                        });
+                    for value in position.position.iter_mut() {
+                        ui.add(egui::DragValue::new(value).speed(1.0));
+                    }
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
             });
+        if let Some(index) = remove {
+            settings.positions.remove(index);
+        }
+        if ui.button("➕ Add Position").clicked() {
+            settings.positions.push(NamedPosition::new("Position", PositionRole::Custom, [0.0; 3]));
+        }
+        
+        ui.add_space(10.0);
+        Self::show_spindle_calibration(ui, &mut settings.spindle_calibration);
+        
+        ui.add_space(10.0);
+        Self::show_plasma_settings(ui, &mut settings.plasma);
+        
+        ui.add_space(10.0);
+        Self::show_recovery_settings(ui, &mut settings.recovery);
+        
+        ui.add_space(10.0);
+        ui.label("Machine Startup Commands (one per line):")
+            .on_hover_text("Sent after the general startup commands when connecting");
+        Self::edit_command_lines(ui, &mut settings.startup_commands);
+        
+        ui.add_space(10.0);
+        Self::show_job_end_settings(ui, &mut settings.job_end, settings.user_outputs);
+        
+        ui.add_space(10.0);
+        ui.checkbox(&mut settings.checklist_enabled, "Pre-run checklist (one item per line):")
+            .on_hover_text("Every item must be ticked before a program starts");
+        if settings.checklist_enabled {
+            Self::edit_command_lines(ui, &mut settings.checklist);
+        }
     }
     
-    /// Show connection settings
-    fn show_connection_settings(ui: &mut egui::Ui, settings: &mut crate::settings::ConnectionSettings) {
-        ui.heading("Connection Settings");
-        ui.add_space(5.0);
+    /// Edit the actions sent when a program finishes
+    fn show_job_end_settings(ui: &mut egui::Ui, job_end: &mut crate::settings::JobEndSettings, user_outputs: u8) {
+        ui.label("When a Program Finishes:")
+            .on_hover_text("Sent after the last program line, in this order");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut job_end.spindle_off, "Spindle off (M5)");
+            ui.checkbox(&mut job_end.coolant_off, "Coolant off (M9)");
+            ui.checkbox(&mut job_end.park, "Move to park")
+                .on_hover_text("Moves to the named position with the Park role");
+        });
+        ui.label("Then send (one command per line):");
+        Self::edit_command_lines(ui, &mut job_end.commands);
+        ui.horizontal(|ui| {
+            ui.add_enabled(
+                user_outputs > 0,
+                egui::Checkbox::new(&mut job_end.power_off, "Switch off output"),
+            )
+            .on_hover_text("grblHAL M65 once the machine has stopped, e.g. for a relay cutting the spindle contactor power");
+            ui.add_enabled(
+                user_outputs > 0,
+                egui::DragValue::new(&mut job_end.power_output).range(0..=user_outputs.saturating_sub(1)),
+            );
+        });
+    }
+    
+    /// Edit the plasma torch settings and cut chart
+    fn show_plasma_settings(ui: &mut egui::Ui, plasma: &mut crate::parser::PlasmaSettings) {
+        use crate::parser::CutChartEntry;
         
-        egui::Grid::new("connection_settings_grid")
+        ui.label("Plasma:")
+            .on_hover_text("Replaces M3/M4 with a pierce sequence and M5 with the torch-off commands");
+        ui.checkbox(&mut plasma.enabled, "Post-process programs for plasma cutting when streaming");
+        
+        egui::Grid::new("plasma_settings_grid")
             .num_columns(2)
-            .spacing([10.0, 8.0])
+            .spacing([10.0, 4.0])
             .show(ui, |ui| {
-                ui.label("Port Name:");
-                ui.text_edit_singleline(&mut settings.port_name);
+                ui.label("Torch On:");
+                ui.add(egui::TextEdit::singleline(&mut plasma.torch_on).desired_width(160.0))
+                    .on_hover_text("Commands firing the torch, separated by ';'");
                 ui.end_row();
                 
-                ui.label("Baud Rate:");
-                egui::ComboBox::from_id_source("baud_rate_combo")
-                    .selected_text(format!("{}", settings.baud_rate))
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut settings.baud_rate, 9600, "9600");
-                        ui.selectable_value(&mut settings.baud_rate, 19200, "19200");
-                        ui.selectable_value(&mut settings.baud_rate, 38400, "38400");
-                        ui.selectable_value(&mut settings.baud_rate, 57600, "57600");
-                        ui.selectable_value(&mut settings.baud_rate, 115200, "115200");
-                        ui.selectable_value(&mut settings.baud_rate, 230400, "230400");
-                    });
+                ui.label("Torch Off:");
+                ui.add(egui::TextEdit::singleline(&mut plasma.torch_off).desired_width(160.0));
                 ui.end_row();
                 
-                ui.label("Connection Timeout:");
-                ui.add(egui::DragValue::new(&mut settings.timeout_ms)
-                    .speed(100)
-                    .range(100..=30000)
-                    .suffix(" ms"));
+                ui.label("Pierce / Cut Height:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut plasma.pierce_height).speed(0.1).range(0.0..=50.0).suffix(" mm"));
+                    ui.add(egui::DragValue::new(&mut plasma.cut_height).speed(0.1).range(0.0..=50.0).suffix(" mm"));
+                });
                 ui.end_row();
                 
-                ui.label("Command Timeout:");
-                ui.add(egui::DragValue::new(&mut settings.command_timeout_ms)
-                    .speed(100)
-                    .range(100..=60000)
-                    .suffix(" ms"));
+                ui.label("Pierce Delay:");
+                ui.add(egui::DragValue::new(&mut plasma.pierce_delay).speed(0.05).range(0.0..=10.0).suffix(" s"))
+                    .on_hover_text("Used when the cut chart has no entry for the material");
                 ui.end_row();
                 
-                ui.label("Status Query Interval:");
-                ui.add(egui::DragValue::new(&mut settings.status_query_interval_ms)
-                    .speed(10)
-                    .range(50..=5000)
-                    .suffix(" ms"));
+                ui.label("Plunge Feed:");
+                ui.add(egui::DragValue::new(&mut plasma.plunge_feed).speed(10.0).range(1.0..=10000.0).suffix(" mm/min"));
                 ui.end_row();
                 
-                ui.label("Auto-connect on Startup:");
-                ui.checkbox(&mut settings.auto_connect, "");
+                ui.label("Material Thickness:");
+                ui.add(egui::DragValue::new(&mut plasma.thickness).speed(0.1).range(0.0..=100.0).suffix(" mm"));
+                ui.end_row();
+            });
+        
+        ui.label("Cut Chart:");
+        let mut remove = None;
+        egui::Grid::new("plasma_cut_chart_grid")
+            .num_columns(5)
+            .spacing([6.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Material");
+                ui.label("Thickness");
+                ui.label("Feed");
+                ui.label("Pierce Delay");
+                ui.label("");
                 ui.end_row();
+                for (index, entry) in plasma.cut_chart.iter_mut().enumerate() {
+                    ui.add(egui::TextEdit::singleline(&mut entry.material).desired_width(100.0));
+                    ui.add(egui::DragValue::new(&mut entry.thickness).speed(0.1).range(0.0..=100.0).suffix(" mm"));
+                    ui.add(egui::DragValue::new(&mut entry.feed_rate).speed(10.0).range(1.0..=20000.0).suffix(" mm/min"));
+                    ui.add(egui::DragValue::new(&mut entry.pierce_delay).speed(0.05).range(0.0..=10.0).suffix(" s"));
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            plasma.cut_chart.remove(index);
+        }
+        if ui.button("➕ Add Entry").clicked() {
+            plasma.cut_chart.push(CutChartEntry {
+                material: "Mild steel".to_string(),
+                thickness: plasma.thickness,
+                feed_rate: 2000.0,
+                pierce_delay: plasma.pierce_delay,
             });
+        }
+        
+        match plasma.chart_entry() {
+            Some(entry) => ui.weak(format!(
+                "{:.1} mm uses the {} {:.1} mm entry: F{:.0}, pierce {:.2} s",
+                plasma.thickness, entry.material, entry.thickness, entry.feed_rate, entry.pierce_delay
+            )),
+            None => ui.weak("No entry for this thickness: program feeds are kept"),
+        };
     }
     
-    /// Show visualization settings
-    fn show_visualization_settings(ui: &mut egui::Ui, settings: &mut crate::settings::VisualizationSettings) {
-        ui.heading("Visualization Settings");
+    /// Edit the spindle calibration table with a preview of the mapping
+    fn show_spindle_calibration(ui: &mut egui::Ui, calibration: &mut crate::parser::SpindleCalibration) {
+        use crate::parser::CalibrationPoint;
+        
+        ui.label("Spindle Calibration:")
+            .on_hover_text("Commanded S values and the RPM measured with a tachometer");
+        ui.checkbox(&mut calibration.enabled, "Adjust S words while streaming");
+        
+        let mut remove = None;
+        egui::Grid::new("spindle_calibration_grid")
+            .num_columns(3)
+            .spacing([6.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Commanded S");
+                ui.label("Measured RPM");
+                ui.label("");
+                ui.end_row();
+                for (index, point) in calibration.points.iter_mut().enumerate() {
+                    ui.add(egui::DragValue::new(&mut point.commanded).speed(50.0).range(0.0..=100000.0));
+                    ui.add(egui::DragValue::new(&mut point.measured).speed(50.0).range(0.0..=100000.0));
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(index) = remove {
+            calibration.points.remove(index);
+        }
+        if ui.button("➕ Add Point").clicked() {
+            let commanded = calibration.points.last().map_or(1000.0, |p| p.commanded + 1000.0);
+            calibration.points.push(CalibrationPoint { commanded, measured: commanded });
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("PWM range ($31-$30):");
+            ui.add(egui::DragValue::new(&mut calibration.pwm_min).speed(50.0).range(0.0..=100000.0));
+            ui.add(egui::DragValue::new(&mut calibration.pwm_max).speed(50.0).range(0.0..=100000.0));
+            ui.label("RPM");
+        });
+        
+        // Preview: programmed speed, S value sent and resulting PWM duty
+        let Some((low, high)) = calibration.range() else {
+            ui.weak("Add at least two points to calibrate");
+            return;
+        };
+        egui::CollapsingHeader::new("Mapping preview")
+            .id_source("spindle_calibration_preview")
+            .show(ui, |ui| {
+                egui::Grid::new("spindle_calibration_preview_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .spacing([10.0, 2.0])
+                    .show(ui, |ui| {
+                        ui.strong("Program RPM");
+                        ui.strong("Sent S");
+                        ui.strong("PWM");
+                        ui.end_row();
+                        for step in 0..=4 {
+                            let rpm = low + (high - low) * step as f64 / 4.0;
+                            let commanded = calibration.commanded_for(rpm).unwrap_or(rpm);
+                            ui.label(format!("{:.0}", rpm));
+                            ui.label(format!("{:.0}", commanded));
+                            ui.label(format!("{:.1} %", calibration.pwm_duty(commanded)));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+    
+    /// Edit the alarm recovery policies of the machine profile
+    fn show_recovery_settings(ui: &mut egui::Ui, settings: &mut crate::state::RecoverySettings) {
+        use crate::state::RecoveryPolicy;
+        
+        ui.label("Alarm Recovery Policies:")
+            .on_hover_text("Steps taken automatically on an alarm or when the safety door opens");
+        ui.checkbox(&mut settings.dry_run, "Dry run (only log the steps, see View → Recovery Log)");
+        
+        let mut remove = None;
+        for (index, policy) in settings.policies.iter_mut().enumerate() {
+            ui.push_id(("recovery_policy", index), |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut policy.enabled, "");
+                    let mut is_alarm = matches!(policy.trigger, RecoveryTrigger::Alarm(_));
+                    egui::ComboBox::from_id_source("trigger")
+                        .selected_text(if is_alarm { "Alarm" } else { "Door open" })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut is_alarm, true, "Alarm");
+                            ui.selectable_value(&mut is_alarm, false, "Door open");
+                        });
+                    match (policy.trigger, is_alarm) {
+                        (RecoveryTrigger::Alarm(_), false) => policy.trigger = RecoveryTrigger::DoorOpen,
+                        (RecoveryTrigger::DoorOpen, true) => policy.trigger = RecoveryTrigger::Alarm(1),
+                        _ => {}
+                    }
+                    if let RecoveryTrigger::Alarm(code) = &mut policy.trigger {
+                        ui.add(egui::DragValue::new(code).range(1..=99).prefix("ALARM:"));
+                    }
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                });
+                ui.indent("steps", |ui| {
+                    let mut remove_step = None;
+                    for (step_index, step) in policy.steps.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            match step {
+                                RecoveryStep::Unlock => {
+                                    ui.label("Unlock ($X)");
+                                }
+                                RecoveryStep::RetractZ(distance) => {
+                                    ui.label("Retract Z");
+                                    ui.add(egui::DragValue::new(distance).speed(0.5).range(0.0..=100.0).suffix(" mm"));
+                                }
+                                RecoveryStep::FeedHold => {
+                                    ui.label("Feed hold");
+                                }
+                                RecoveryStep::Notify(message) => {
+                                    ui.label("Notify");
+                                    ui.text_edit_singleline(message);
+                                }
+                            }
+                            if ui.small_button("✖").clicked() {
+                                remove_step = Some(step_index);
+                            }
+                        });
+                    }
+                    if let Some(step_index) = remove_step {
+                        policy.steps.remove(step_index);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.weak("Add:");
+                        if ui.small_button("Unlock").clicked() {
+                            policy.steps.push(RecoveryStep::Unlock);
+                        }
+                        if ui.small_button("Retract Z").clicked() {
+                            policy.steps.push(RecoveryStep::RetractZ(5.0));
+                        }
+                        if ui.small_button("Feed hold").clicked() {
+                            policy.steps.push(RecoveryStep::FeedHold);
+                        }
+                        if ui.small_button("Notify").clicked() {
+                            policy.steps.push(RecoveryStep::Notify("Check the machine".to_string()));
+                        }
+                    });
+                });
+            });
+        }
+        if let Some(index) = remove {
+            settings.policies.remove(index);
+        }
+        if ui.button("➕ Add Policy").clicked() {
+            settings.policies.push(RecoveryPolicy {
+                trigger: RecoveryTrigger::Alarm(1),
+                steps: Vec::new(),
+                enabled: true,
+            });
+        }
+    }
+    
+    /// Editable min/max grid for jog limits
+    fn jog_limits_grid(ui: &mut egui::Ui, id: &str, limits: &mut crate::settings::JogLimits) {
+        egui::Grid::new(id)
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label("Min");
+                ui.label("Max");
+                ui.end_row();
+                for (axis, name) in ["X", "Y", "Z"].iter().enumerate() {
+                    ui.label(*name);
+                    ui.add(egui::DragValue::new(&mut limits.min[axis]).speed(1.0));
+                    ui.add(egui::DragValue::new(&mut limits.max[axis]).speed(1.0));
+                    ui.end_row();
+                }
+            });
+    }
+    
+    /// Show G-Code processing settings
+    fn show_processing_settings(ui: &mut egui::Ui, settings: &mut crate::settings::ProcessingSettings) {
+        use crate::settings::PlungeMode;
+        
+        ui.heading("Processing Settings");
         ui.add_space(5.0);
         
-        egui::Grid::new("visualization_settings_grid")
+        egui::Grid::new("processing_settings_grid")
             .num_columns(2)
             .spacing([10.0, 8.0])
             .show(ui, |ui| {
-                ui.label("Show Grid:");
-                ui.checkbox(&mut settings.show_grid, "");
+                ui.label("Plunge Entry:")
+                    .on_hover_text("Replace straight plunges into material with ramped or helical entries");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.plunge_mode, PlungeMode::Straight, "Straight");
+                    ui.radio_value(&mut settings.plunge_mode, PlungeMode::Ramp, "Ramp");
+                    ui.radio_value(&mut settings.plunge_mode, PlungeMode::Helix, "Helix");
+                });
                 ui.end_row();
                 
-                ui.label("Grid Size:");
-                ui.add(egui::DragValue::new(&mut settings.grid_size)
-                    .speed(1.0)
-                    .range(1.0..=100.0));
-                ui.end_row();
+                if settings.plunge_mode != PlungeMode::Straight {
+                    ui.label("Entry Angle (°):")
+                        .on_hover_text("Descent angle from horizontal");
+                    ui.add(egui::DragValue::new(&mut settings.plunge_angle)
+                        .speed(0.1)
+                        .range(0.5..=45.0));
+                    ui.end_row();
+                }
                 
-                ui.label("Show Tool:");
-                ui.checkbox(&mut settings.show_tool, "");
-                ui.end_row();
+                if settings.plunge_mode == PlungeMode::Ramp {
+                    ui.label("Ramp Length:")
+                        .on_hover_text("Length of each ramp leg");
+                    ui.add(egui::DragValue::new(&mut settings.ramp_length)
+                        .speed(0.1)
+                        .range(0.1..=100.0));
+                    ui.end_row();
+                }
                 
-                ui.label("Show Origin:");
-                ui.checkbox(&mut settings.show_origin, "");
+                if settings.plunge_mode == PlungeMode::Helix {
+                    ui.label("Helix Diameter:")
+                        .on_hover_text("Diameter of the helical entry");
+                    ui.add(egui::DragValue::new(&mut settings.helix_diameter)
+                        .speed(0.1)
+                        .range(0.1..=100.0));
+                    ui.end_row();
+                }
+                
+                ui.label("Rapids Below Surface:")
+                    .on_hover_text("Rapids moving in XY below Z0 drag the tool through the stock");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.rapid_retract, RapidRetract::Off, "Ignore");
+                    ui.radio_value(&mut settings.rapid_retract, RapidRetract::Warn, "Warn")
+                        .on_hover_text("Warn when the program is loaded");
+                    ui.radio_value(&mut settings.rapid_retract, RapidRetract::Fix, "Fix")
+                        .on_hover_text("Retract to the safe Z before such rapids while streaming");
+                });
                 ui.end_row();
                 
-                ui.label("Show Bounds:");
-                ui.checkbox(&mut settings.show_bounds, "");
+                ui.label("Leveling Segment:")
+                    .on_hover_text("Feed moves are split into pieces this long when auto-leveling");
+                ui.add(egui::DragValue::new(&mut settings.leveling_segment)
+                    .speed(0.5)
+                    .range(0.5..=50.0)
+                    .suffix(" mm"));
                 ui.end_row();
                 
-                ui.label("MSAA Samples:");
-                egui::ComboBox::from_id_source("msaa_combo")
-                    .selected_text(format!("{}x", settings.msaa_samples))
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut settings.msaa_samples, 1, "1x");
-                        ui.selectable_value(&mut settings.msaa_samples, 2, "2x");
-                        ui.selectable_value(&mut settings.msaa_samples, 4, "4x");
-                        ui.selectable_value(&mut settings.msaa_samples, 8, "8x");
-                    });
+                ui.label("Lowest Leveled Z:")
+                    .on_hover_text("Auto-leveled moves may not go below this work Z");
+                ui.add(egui::DragValue::new(&mut settings.leveling_lowest_z)
+                    .speed(0.1)
+                    .range(-100.0..=0.0)
+                    .suffix(" mm"));
                 ui.end_row();
                 
-                ui.label("VSync:");
-                ui.checkbox(&mut settings.vsync, "");
+                ui.label("Below Lowest Z:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.z_limit_guard, ZLimitGuard::Abort, "Refuse to start");
+                    ui.radio_value(&mut settings.z_limit_guard, ZLimitGuard::Hold, "Hold before the move");
+                });
                 ui.end_row();
                 
-                ui.label("Field of View:");
-                ui.add(egui::Slider::new(&mut settings.fov, 30.0..=120.0)
-                    .suffix("°"));
+                ui.label("Dry Run:")
+                    .on_hover_text("Dry runs keep Z above the stock so the machine traces the job in the air");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut settings.dry_run_clearance)
+                        .speed(0.5)
+                        .range(0.0..=100.0)
+                        .prefix("clearance ")
+                        .suffix(" mm"));
+                    ui.add(egui::DragValue::new(&mut settings.dry_run_feed_scale)
+                        .speed(0.1)
+                        .range(0.1..=10.0)
+                        .prefix("feed x"));
+                    ui.checkbox(&mut settings.dry_run_spindle_off, "Spindle off");
+                });
                 ui.end_row();
                 
-                ui.label("Camera Speed:");
-                ui.add(egui::Slider::new(&mut settings.camera_speed, 0.1..=5.0));
+                ui.label("Pause Markers:")
+                    .on_hover_text("Comments starting with one of these words (one per line) pause the program and show the comment, like M0");
+                Self::edit_command_lines(ui, &mut settings.pause_markers);
                 ui.end_row();
-            });
-    }
-    
-    /// Show jog settings
-    fn show_jog_settings(ui: &mut egui::Ui, settings: &mut crate::settings::JogSettings) {
-        ui.heading("Jog Settings");
-        ui.add_space(5.0);
-        
-        egui::Grid::new("jog_settings_grid")
-            .num_columns(2)
-            .spacing([10.0, 8.0])
-            .show(ui, |ui| {
-                ui.label("XY Feed Rate:");
-                ui.add(egui::DragValue::new(&mut settings.xy_feed_rate)
-                    .speed(10.0)
-                    .range(1.0..=10000.0)
-                    .suffix(" mm/min"));
+                
+                ui.label("Skip Block Delete:")
+                    .on_hover_text("Skip lines starting with \"/\" when streaming and previewing");
+                ui.checkbox(&mut settings.skip_block_delete, "");
                 ui.end_row();
                 
-                ui.label("Z Feed Rate:");
-                ui.add(egui::DragValue::new(&mut settings.z_feed_rate)
-                    .speed(10.0)
-                    .range(1.0..=5000.0)
-                    .suffix(" mm/min"));
+                ui.label("Pass Through Cycles:")
+                    .on_hover_text("Send M98/M99 and G81-G83 unchanged (grblHAL) instead of expanding them");
+                ui.checkbox(&mut settings.passthrough_cycles, "");
                 ui.end_row();
                 
-                ui.label("Continuous Mode:");
-                ui.checkbox(&mut settings.continuous_mode, "");
+                ui.label("Spindle At Speed:")
+                    .on_hover_text("The controller holds motion until the spindle reaches speed (grblHAL)");
+                ui.checkbox(&mut settings.spindle_at_speed, "");
                 ui.end_row();
-            });
-        
-        ui.add_space(10.0);
-        ui.label("Step Sizes:");
-        
-        // Show step sizes as editable list
-        let mut i = 0;
-        while i < settings.step_sizes.len() {
-            ui.horizontal(|ui| {
-                ui.add(egui::DragValue::new(&mut settings.step_sizes[i])
-                    .speed(0.1)
-                    .range(0.001..=1000.0));
                 
-                if ui.button("🗑").clicked() {
-                    settings.step_sizes.remove(i);
-                    if settings.default_step_index >= settings.step_sizes.len() {
-                        settings.default_step_index = settings.step_sizes.len().saturating_sub(1);
-                    }
-                } else {
-                    i += 1;
+                if !settings.spindle_at_speed {
+                    ui.label("Spindle Ramp Dwell:")
+                        .on_hover_text("Dwell inserted after M3/M4 while streaming so cuts start at full speed");
+                    ui.add(egui::DragValue::new(&mut settings.spindle_dwell)
+                        .speed(0.1)
+                        .range(0.0..=60.0)
+                        .suffix(" s"));
+                    ui.end_row();
                 }
             });
-        }
-        
-        if ui.button("➕ Add Step Size").clicked() {
-            settings.step_sizes.push(1.0);
-        }
     }
     
     /// Show UI settings
@@ -1423,6 +6945,14 @@ impl RCandleApp {
                 ui.add(egui::Slider::new(&mut settings.font_size, 8.0..=24.0));
                 ui.end_row();
                 
+                ui.label("UI Scale:");
+                ui.add(egui::Slider::new(&mut settings.ui_scale, crate::settings::UiSettings::SCALE_RANGE)
+                    .step_by(0.05)
+                    .fixed_decimals(2)
+                    .suffix("×"))
+                    .on_hover_text("Scales the whole layout, for high-resolution monitors or a shop TV. Ctrl +/- also changes it.");
+                ui.end_row();
+                
                 ui.label("Show Console:");
                 ui.checkbox(&mut settings.show_console, "");
                 ui.end_row();
@@ -1440,6 +6970,16 @@ impl RCandleApp {
                     .speed(10)
                     .range(100..=10000));
                 ui.end_row();
+                
+                ui.label("Run Screen on Start:");
+                ui.checkbox(&mut settings.auto_run_screen, "")
+                    .on_hover_text("Switch to the full-window run screen when a program starts");
+                ui.end_row();
+                
+                ui.label("Run Screen on Top:");
+                ui.checkbox(&mut settings.run_screen_on_top, "")
+                    .on_hover_text("Keep the window above other windows while the run screen is shown");
+                ui.end_row();
             });
     }
     
@@ -1469,6 +7009,83 @@ impl RCandleApp {
         }
     }
     
+    /// State the user command enable conditions are checked against
+    fn user_command_context(&self) -> CommandContext {
+        CommandContext {
+            connected: self.connection_manager.is_some(),
+            status: self.machine_view.status,
+            program_loaded: self.app_state.program.read().total_lines > 0,
+        }
+    }
+    
+    /// Save the user command library, including its layout
+    fn save_user_commands(&mut self) {
+        if let Err(e) = self.user_command_library.save_default() {
+            self.report_error(e.with_context("Failed to save user commands"));
+        }
+    }
+    
+    /// Run a clicked user command, asking first if it needs confirmation
+    fn request_user_command(&mut self, command_name: &str) {
+        let context = self.user_command_context();
+        let Some(command) = self.user_command_library.get_command(command_name) else {
+            self.console.error(format!("User command not found: {}", command_name));
+            return;
+        };
+        if let Some(condition) = command.unmet_condition(&context) {
+            self.console.warning(format!(
+                "User command {} requires: {}",
+                command_name,
+                condition.label()
+            ));
+            return;
+        }
+        if command.confirm {
+            self.pending_user_command = Some(command_name.to_string());
+        } else {
+            self.execute_user_command(command_name);
+        }
+    }
+    
+    /// Confirm a user command marked as needing confirmation
+    fn show_pending_user_command(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.pending_user_command.clone() else {
+            return;
+        };
+        let Some(command) = self.user_command_library.get_command(&name) else {
+            self.pending_user_command = None;
+            return;
+        };
+        let prompt = command.confirm_prompt();
+        let commands = command.commands.join("\n");
+        let enabled = command.is_enabled(&self.user_command_context());
+        let mut confirmed = false;
+        
+        egui::Window::new(format!("⚠ {}", name))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(prompt);
+                ui.add_space(5.0);
+                ui.monospace(commands);
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(enabled, egui::Button::new("✔ Run")).clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("❌ Cancel").clicked() {
+                        self.pending_user_command = None;
+                    }
+                });
+            });
+        
+        if confirmed {
+            self.pending_user_command = None;
+            self.execute_user_command(&name);
+        }
+    }
+    
     /// Execute a user command
     fn execute_user_command(&mut self, command_name: &str) {
         // Clone commands first to avoid borrowing issues
@@ -1488,6 +7105,33 @@ impl RCandleApp {
         }
     }
     
+    /// Run a script in the background, logging its result to the console
+    ///
+    /// The script's commands arrive on the plugin command channel. It runs
+    /// under the app's supervisor, so a panic is reported instead of
+    /// silently ending the script.
+    fn run_script(&self, name: String, code: String) {
+        let api = self.script_api.clone();
+        self.tasks.spawn(format!("script '{}'", name), move |_shutdown| async move {
+            let log = api.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let mut context = ScriptContext::new(api);
+                context.set_max_operations(MAX_OPERATIONS);
+                context.execute(&code)
+            })
+            .await;
+            let message = match result {
+                Ok(Ok(value)) if value.is_unit() => format!("Script '{}' finished", name),
+                Ok(Ok(value)) => format!("Script '{}' returned {}", name, value),
+                Ok(Err(e)) => format!("Script '{}' failed: {}", name, e),
+                // Pass the panic on so the supervisor reports it
+                Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                Err(_) => return,
+            };
+            log.log(message);
+        });
+    }
+
     /// Show script editor window
     fn show_script_editor_window(&mut self, ctx: &egui::Context) {
         let mut dialog_open = true;
@@ -1560,8 +7204,11 @@ impl RCandleApp {
                     self.editing_script = None;
                 }
                 "test" => {
-                    self.console.info(format!("Testing script: {}", name));
-                    // TODO: Execute script via script executor
+                    if let Some(script) = &self.editing_script {
+                        let code = script.code.clone();
+                        self.console.info(format!("Testing script: {}", name));
+                        self.run_script(name.clone(), code);
+                    }
                     self.status_message = format!("Testing: {}", name);
                 }
                 "cancel" | "close" => {
@@ -1589,25 +7236,44 @@ impl eframe::App for RCandleApp {
         if let Some(pending_slot) = &self.pending_connection_manager {
             // Try to get the manager without blocking
             if let Ok(mut slot_guard) = pending_slot.try_lock() {
-                if let Some(manager) = slot_guard.take() {
-                    // We got the manager! Store it temporarily
-                    manager_to_store = Some(manager);
+                if let Some(outcome) = slot_guard.take() {
+                    // The attempt finished; handle it outside the borrow
+                    manager_to_store = Some(outcome);
                     clear_pending = true;
                 }
             }
         }
         
         // Now update the fields outside the borrow
-        if let Some(manager) = manager_to_store {
+        if let Some(Err(message)) = &manager_to_store {
+            self.auto_connect.failed(std::time::Instant::now());
+            self.link_description = None;
+            self.status_message = "Connection failed".to_string();
+            self.report_error(Error::Connection(message.clone()).with_context("Cannot connect"));
+        }
+        if let Some(Ok(manager)) = manager_to_store {
+            self.auto_connect.succeeded();
             // Subscribe to responses and status before storing the manager
             let manager_guard = tokio::runtime::Handle::current().block_on(manager.lock());
             let response_rx = manager_guard.subscribe_responses();
             let status_rx = manager_guard.subscribe_status();
+            let event_rx = manager_guard.subscribe_events();
+            let failure_rx = manager_guard.subscribe_task_failures();
+            // Wake the UI for controller traffic instead of polling for it
+            Self::repaint_on(ctx, manager_guard.subscribe_responses());
+            Self::repaint_on(ctx, manager_guard.subscribe_status());
+            Self::repaint_on(ctx, manager_guard.subscribe_events());
+            Self::repaint_on(ctx, manager_guard.subscribe_task_failures());
             drop(manager_guard);
             
             self.response_receiver = Some(response_rx);
             self.status_receiver = Some(status_rx);
+            self.event_receiver = Some(event_rx);
+            self.task_failure_receiver = Some(failure_rx);
             self.connection_manager = Some(manager);
+            self.job_active_reported = false;
+            self.startup_pending = true;
+            self.startup_replies.clear();
             self.status_message = "Connected".to_string();
             self.console.info("Connection established".to_string());
             tracing::info!("Connection manager stored successfully");
@@ -1643,14 +7309,76 @@ impl eframe::App for RCandleApp {
         for status in status_updates {
             self.handle_grbl_status_update(status);
         }
+        self.drain_state_events();
+        
+        // Check for connection events (watchdog, errors)
+        let mut events = Vec::new();
+        if let Some(ref mut rx) = self.event_receiver {
+            while let Ok(event) = rx.try_recv() {
+                events.push(event);
+            }
+        }
+        for event in events {
+            self.handle_connection_event(event);
+        }
+        
+        // Surface background tasks that died
+        let mut failures = Vec::new();
+        if let Some(ref mut rx) = self.task_failure_receiver {
+            while let Ok(failure) = rx.try_recv() {
+                failures.push(failure);
+            }
+        }
+        for failure in failures {
+            self.console.error(format!("Background task '{}' failed: {}", failure.task, failure.message));
+            self.status_message = format!("Connection task '{}' stopped unexpectedly", failure.task);
+        }
+        while let Ok(failure) = self.app_task_failures.try_recv() {
+            self.console.error(format!("Background task '{}' failed: {}", failure.task, failure.message));
+            self.status_message = format!("Task '{}' stopped unexpectedly", failure.task);
+        }
         
         // Debug: Log that update is being called
         static FRAME_COUNT: AtomicUsize = AtomicUsize::new(0);
         let count = FRAME_COUNT.fetch_add(1, Ordering::Relaxed);
-        if count % 60 == 0 {  // Log every 60 frames (~1 second)
+        if count % 60 == 0 {  // Log every 60 frames
             tracing::debug!("Update called: frame {}", count);
         }
         
+        // Retry auto-connect and watch for the device being replugged
+        self.poll_auto_connect(ctx);
+        
+        // Start a program requested on the command line
+        self.poll_auto_run();
+        
+        // Keep the running program streaming
+        self.pump_program_stream();
+        self.pump_feed_ramp();
+        self.update_job_journal();
+        self.sync_job_activity();
+        self.track_tool_wear();
+        self.track_runtime();
+        
+        // Refresh link metrics for diagnostics and scripts
+        self.poll_link_metrics();
+        
+        // Pick up port scan results
+        self.poll_scan_task();
+        self.poll_discovery_task();
+        self.poll_bluetooth_task();
+        
+        // Replot the scratch buffer when G-Code is pasted into it
+        self.handle_scratch_paste(ctx);
+        
+        // Pick up the open file being rewritten, such as by a CAM re-post
+        self.poll_file_changes();
+        self.poll_settings_file(ctx);
+        crash::record_loaded_file(self.current_file.as_deref());
+        self.poll_plugin_commands();
+        
+        // Fallback frame for polled work and idle refresh
+        self.schedule_repaint(ctx);
+        
         // Handle keyboard shortcuts
         ctx.input(|i| {
             // Ctrl+F to open find dialog
@@ -1665,17 +7393,104 @@ impl eframe::App for RCandleApp {
             if i.modifiers.command && i.key_pressed(egui::Key::S) {
                 self.save_file();
             }
+            // Ctrl+B to toggle a bookmark on the editor cursor line
+            if i.modifiers.command && i.key_pressed(egui::Key::B) {
+                self.gcode_editor.toggle_bookmark_at_cursor();
+            }
+            // F2 / Shift+F2 to jump to the next / previous bookmark
+            if i.key_pressed(egui::Key::F2) {
+                if i.modifiers.shift {
+                    self.gcode_editor.prev_bookmark();
+                } else {
+                    self.gcode_editor.next_bookmark();
+                }
+            }
+            // F9 to toggle a breakpoint on the editor cursor line
+            if i.key_pressed(egui::Key::F9) {
+                self.gcode_editor.toggle_breakpoint_at_cursor();
+            }
             // Ctrl+, to open settings (common shortcut)
             if i.modifiers.command && i.key_pressed(egui::Key::Comma) {
                 self.show_settings_dialog = true;
                 self.temp_settings = Some(self.settings.clone());
             }
+            // F5 / F6 / F7 to run, pause and stop the program
+            if i.key_pressed(egui::Key::F5) {
+                self.request_program_start();
+            }
+            if i.key_pressed(egui::Key::F6) {
+                self.pause_program();
+            }
+            if i.key_pressed(egui::Key::F7) {
+                self.stop_program();
+            }
+            // Ctrl+Shift+H / Ctrl+Shift+U to home and unlock
+            if i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::H) {
+                self.send_home_command();
+            }
+            if i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::U) {
+                self.send_unlock_command();
+            }
+            // F1 to list the shortcuts
+            if i.key_pressed(egui::Key::F1) {
+                self.show_shortcuts = !self.show_shortcuts;
+            }
+            // Ctrl+P to search the commands
+            if i.modifiers.command && i.key_pressed(egui::Key::P) {
+                self.command_palette.toggle();
+            }
         });
         
+        if let Some(name) = self.user_commands_panel.shortcut_pressed(ctx, &self.user_command_library) {
+            self.request_user_command(&name);
+        }
+        
+        self.handle_keyboard_jog(ctx);
+        
+        // Remember window geometry and keep the window on a connected monitor
+        self.track_window(ctx);
+        
+        // The run screen replaces the normal layout while open
+        if self.run_screen.open {
+            let status = self.run_screen_status();
+            match self.run_screen.show(ctx, &status, self.progress_map.as_ref()) {
+                Some(RunScreenAction::Hold) => self.pause_program(),
+                Some(RunScreenAction::Resume) => self.request_program_start(),
+                Some(RunScreenAction::Stop) => self.stop_program(),
+                Some(RunScreenAction::Exit) | None => {}
+            }
+            if self.run_screen.always_on_top != self.settings.ui.run_screen_on_top {
+                self.settings.ui.run_screen_on_top = self.run_screen.always_on_top;
+                if let Err(e) = self.launch.save_settings(&self.settings) {
+                    self.report_error(e.with_context("Failed to save settings"));
+                }
+            }
+            return;
+        }
+        
+        // The pendant keypad replaces the normal layout while active
+        if self.pendant.open {
+            let status = self.run_screen_status();
+            let steps = self.settings.jog.step_sizes.clone();
+            if let Some(action) = self.pendant.show(ctx, &status, &steps, self.settings.jog.default_step_index) {
+                self.handle_pendant_action(action);
+            }
+            self.sync_pendant_mode();
+            return;
+        }
+        
         // Top panel with menu bar
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
+                    if ui.button("📄 New G-Code").clicked() {
+                        self.new_document();
+                        ui.close_menu();
+                    }
+                    if ui.button("📋 New from Clipboard").on_hover_text("Plot G-Code from the clipboard without saving it").clicked() {
+                        self.new_from_clipboard();
+                        ui.close_menu();
+                    }
                     if ui.button("📂 Open G-Code...").clicked() {
                         tracing::info!("Open button clicked!");  // Debug
                         self.open_file();
@@ -1689,6 +7504,28 @@ impl eframe::App for RCandleApp {
                         self.save_file_as();
                         ui.close_menu();
                     }
+                    if ui.button("✖ Close G-Code").clicked() {
+                        self.close_document(self.active_document);
+                        ui.close_menu();
+                    }
+                    if ui.button("📤 Export Toolpath...").clicked() {
+                        self.export_toolpath();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("🗂 New Project").clicked() {
+                        self.new_project();
+                        ui.close_menu();
+                    }
+                    if ui.button("📂 Open Project...").clicked() {
+                        self.open_project();
+                        ui.close_menu();
+                    }
+                    let has_project = self.project_dialog.path.is_some() || self.project_dialog.modified;
+                    if ui.add_enabled(has_project, egui::Button::new("🗂 Show Project")).clicked() {
+                        self.project_dialog.open = true;
+                        ui.close_menu();
+                    }
                     ui.separator();
                     if ui.button("🚪 Exit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
@@ -1705,6 +7542,10 @@ impl eframe::App for RCandleApp {
                         ui.close_menu();
                     }
                     ui.separator();
+                    if ui.add_enabled(self.scan_task.is_none(), egui::Button::new("🔎 Scan for GRBL")).clicked() {
+                        self.scan_for_devices();
+                        ui.close_menu();
+                    }
                     if ui.button("🔄 Refresh Ports").clicked() {
                         self.refresh_ports();
                         ui.close_menu();
@@ -1716,6 +7557,28 @@ impl eframe::App for RCandleApp {
                         self.gcode_editor.toggle_find_replace();
                         ui.close_menu();
                     }
+                    ui.separator();
+                    if ui.button("🔖 Toggle Bookmark (Ctrl+B)").clicked() {
+                        self.gcode_editor.toggle_bookmark_at_cursor();
+                        ui.close_menu();
+                    }
+                    if ui.button("⏭ Next Bookmark (F2)").clicked() {
+                        self.gcode_editor.next_bookmark();
+                        ui.close_menu();
+                    }
+                    if ui.button("⏮ Previous Bookmark (Shift+F2)").clicked() {
+                        self.gcode_editor.prev_bookmark();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("● Toggle Breakpoint (F9)").clicked() {
+                        self.gcode_editor.toggle_breakpoint_at_cursor();
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!self.gcode_editor.breakpoints.is_empty(), egui::Button::new("Clear Breakpoints")).clicked() {
+                        self.gcode_editor.breakpoints.clear();
+                        ui.close_menu();
+                    }
                 });
                 
                 ui.menu_button("View", |ui| {
@@ -1742,9 +7605,41 @@ impl eframe::App for RCandleApp {
                     if ui.checkbox(&mut self.show_user_commands, "🔧 Show User Commands").clicked() {
                         ui.close_menu();
                     }
+                    if ui.checkbox(&mut self.diagnostics_panel.open, "📈 Show Diagnostics").clicked() {
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.status_chart.open, "📉 Show Status Chart").clicked() {
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.error_presenter.open, "⚠ Show Errors").clicked() {
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.action_log_panel.open, "↶ Show Action Log").clicked() {
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.probe_log_panel.open, "📍 Show Probe Log").clicked() {
+                        ui.close_menu();
+                    }
+                    if ui.checkbox(&mut self.recovery_log_panel.open, "🛟 Show Recovery Log").clicked() {
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("🖥 Run Screen (Esc to exit)").clicked() {
+                        self.run_screen.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("👆 Pendant Mode").on_hover_text("Large touch-screen controls").clicked() {
+                        self.pendant.open = true;
+                        self.sync_pendant_mode();
+                        ui.close_menu();
+                    }
                 });
                 
                 ui.menu_button("Tools", |ui| {
+                    if ui.button("🔎 Command Palette... (Ctrl+P)").clicked() {
+                        self.command_palette.toggle();
+                        ui.close_menu();
+                    }
                     if ui.button("⚙ Settings... (Ctrl+,)").clicked() {
                         self.show_settings_dialog = true;
                         self.temp_settings = Some(self.settings.clone());
@@ -1756,9 +7651,74 @@ impl eframe::App for RCandleApp {
                         self.editing_script = Some(UserScript::new("New Script".to_string(), "// Your script here\n".to_string()));
                         ui.close_menu();
                     }
+                    ui.separator();
+                    if ui.button("✂ Multi-Pass Depth...").clicked() {
+                        self.multipass_dialog.open_with_safe_z(self.settings.general.safe_z);
+                        ui.close_menu();
+                    }
+                    if ui.button("🧹 Format G-Code...").clicked() {
+                        self.formatter_dialog.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("📊 Program Statistics...").clicked() {
+                        self.statistics_panel.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🧩 Compatibility Report...").clicked() {
+                        self.compatibility_panel.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🧮 Feeds & Speeds / Job Cost...").clicked() {
+                        let record = self.current_file.as_ref().and_then(|path| SidecarMetadata::load_for(path).job);
+                        self.calculator_dialog.open_with(record.as_ref());
+                        ui.close_menu();
+                    }
+                    if ui.button("⌖ Edge Finder...").clicked() {
+                        self.edge_finder.open = true;
+                        self.probing_edge = None;
+                        ui.close_menu();
+                    }
+                    if ui.button("🗜 Fixtures...").clicked() {
+                        self.fixture_panel.open = true;
+                        ui.close_menu();
+                    }
+                    let due = self.maintenance_due;
+                    let label = if due > 0 { format!("🛠 Maintenance ({} due)...", due) } else { "🛠 Maintenance...".to_string() };
+                    if ui.button(label).clicked() {
+                        self.maintenance_panel.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🧩 Plugins...").clicked() {
+                        self.plugin_manager.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("↔ Measure Backlash...").clicked() {
+                        self.backlash_wizard.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("⇆ Axis Test Motion...").clicked() {
+                        self.axis_test.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("▦ Flatness Report...").clicked() {
+                        self.flatness_panel.open = true;
+                        ui.close_menu();
+                    }
                 });
                 
                 ui.menu_button("Help", |ui| {
+                    if ui.button("⌨ Keyboard Shortcuts (F1)").clicked() {
+                        self.show_shortcuts = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("📖 GRBL Reference...").clicked() {
+                        self.reference_panel.open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🧭 Setup Wizard...").clicked() {
+                        self.onboarding.start(&self.settings, &self.selected_port);
+                        ui.close_menu();
+                    }
                     if ui.button("ℹ About").clicked() {
                         self.status_message = format!("rCandle v{}", crate::VERSION);
                         ui.close_menu();
@@ -1769,8 +7729,22 @@ impl eframe::App for RCandleApp {
                     }
                 });
             });
-        });
-
+        });
+
+        // Link warning banner
+        if let Some(warning) = self.link_warning.clone() {
+            egui::TopBottomPanel::top("link_warning_panel").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::from_rgb(255, 180, 0), format!("⚠ {}", warning));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                            self.link_warning = None;
+                        }
+                    });
+                });
+            });
+        }
+        
         // Bottom status bar
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -1789,6 +7763,30 @@ impl eframe::App for RCandleApp {
                 ui.label(format!("Units: {}", 
                     if self.settings.general.units_metric { "mm" } else { "inch" }));
                 
+                // Job progress at a glance while a program runs
+                let (active, completed) = {
+                    let program_state = self.app_state.program.read();
+                    let active = matches!(program_state.state, ExecutionState::Running | ExecutionState::Paused);
+                    (active, program_state.current_line)
+                };
+                if let Some(map) = self.progress_map.as_ref().filter(|_| active) {
+                    ui.separator();
+                    let position = [self.machine_view.work_position.x, self.machine_view.work_position.y];
+                    map.show(ui, egui::vec2(120.0, 18.0), completed, position)
+                        .on_hover_text(format!("Line {} of the running program", completed));
+                }
+                
+                if self.maintenance_due > 0 {
+                    ui.separator();
+                    let text = egui::RichText::new(format!("🛠 {} due", self.maintenance_due)).color(egui::Color32::YELLOW);
+                    if ui.add(egui::Label::new(text).sense(egui::Sense::click()))
+                        .on_hover_text("Maintenance reminders are due. Click to open")
+                        .clicked()
+                    {
+                        self.maintenance_panel.open = true;
+                    }
+                }
+                
                 // Connection indicator
                 ui.separator();
                 let connected = self.app_state.is_connected();
@@ -1798,6 +7796,10 @@ impl eframe::App for RCandleApp {
                     egui::Color32::RED 
                 };
                 ui.colored_label(color, if connected { "🟢 Connected" } else { "🔴 Disconnected" });
+                
+                if connected {
+                    self.show_link_health(ui);
+                }
             });
         });
 
@@ -1812,15 +7814,47 @@ impl eframe::App for RCandleApp {
                 ui.group(|ui| {
                     ui.label("Connection");
                     
-                    // Port selection
-                    egui::ComboBox::from_label("Port")
-                        .selected_text(&self.selected_port)
+                    // Connection type selection
+                    let connection_type = &mut self.settings.connection.connection_type;
+                    egui::ComboBox::from_label("Type")
+                        .selected_text(connection_type.to_string())
                         .show_ui(ui, |ui| {
-                            for port in &self.available_ports {
-                                ui.selectable_value(&mut self.selected_port, port.clone(), port);
-                            }
+                            ui.selectable_value(connection_type, ConnectionType::Serial, "Serial");
+                            ui.selectable_value(connection_type, ConnectionType::Bluetooth, "Bluetooth");
+                            ui.selectable_value(connection_type, ConnectionType::Simulator, "Simulator");
+                            ui.selectable_value(connection_type, ConnectionType::Network, "Network");
                         });
                     
+                    // Port selection
+                    match self.settings.connection.connection_type {
+                        ConnectionType::Serial => {
+                            let selected_text = self.detected_devices.iter()
+                                .find(|device| device.port == self.selected_port)
+                                .map(|device| device.label())
+                                .unwrap_or_else(|| self.selected_port.clone());
+                            egui::ComboBox::from_label("Port")
+                                .selected_text(selected_text)
+                                .show_ui(ui, |ui| {
+                                    for port in &self.available_ports {
+                                        match self.detected_devices.iter().find(|device| &device.port == port) {
+                                            Some(device) => {
+                                                if ui.selectable_label(self.selected_port == *port, device.label()).clicked() {
+                                                    self.selected_port = port.clone();
+                                                    self.settings.connection.baud_rate = device.baud_rate;
+                                                }
+                                            }
+                                            None => {
+                                                ui.selectable_value(&mut self.selected_port, port.clone(), port);
+                                            }
+                                        }
+                                    }
+                                });
+                        }
+                        ConnectionType::Bluetooth => self.show_bluetooth_selector(ui),
+                        ConnectionType::Simulator => {}
+                        ConnectionType::Network => self.show_network_selector(ui),
+                    }
+                    
                     ui.horizontal(|ui| {
                         let is_connected = self.app_state.is_connected();
                         
@@ -1836,9 +7870,20 @@ impl eframe::App for RCandleApp {
                             }
                         }
                         
-                        if ui.button("🔄").clicked() {
+                        if ui.button("🔄").on_hover_text("Refresh ports").clicked() {
                             self.refresh_ports();
                         }
+                        
+                        let scanning = self.scan_task.is_some();
+                        if ui.add_enabled(!scanning, egui::Button::new("🔎"))
+                            .on_hover_text("Scan for GRBL devices")
+                            .clicked()
+                        {
+                            self.scan_for_devices();
+                        }
+                        if scanning {
+                            ui.spinner();
+                        }
                     });
                     
                     // Connection status indicator
@@ -1850,6 +7895,7 @@ impl eframe::App for RCandleApp {
                         };
                         ui.colored_label(status_color, status_text);
                     });
+                    self.show_auto_connect_status(ui);
                 });
                 
                 ui.add_space(10.0);
@@ -1861,7 +7907,7 @@ impl eframe::App for RCandleApp {
                     // Extract data from machine_state before UI rendering
                     let (status, machine_pos_x, machine_pos_y, machine_pos_z, 
                          feed_rate, spindle_speed, feed_override, rapid_override, spindle_override) = {
-                        let machine_state = self.app_state.machine.read();
+                        let machine_state = &self.machine_view;
                         (
                             machine_state.status.clone(),
                             machine_state.machine_position.x,
@@ -1885,34 +7931,47 @@ impl eframe::App for RCandleApp {
                             crate::state::MachineStatus::Alarm => egui::Color32::RED,
                             _ => egui::Color32::GRAY,
                         };
-                        ui.colored_label(status_color, format!("{:?}", status));
+                        readout(
+                            ui,
+                            egui::RichText::new(format!("{:?}", status)).color(status_color),
+                            format!("Machine status {:?}", status),
+                        );
                     });
                     
                     ui.separator();
                     
                     // Machine position
+                    let spoken_units = if self.settings.general.units_metric { "millimeters" } else { "inches" };
                     ui.label("Machine Position:");
-                    ui.label(format!("  X: {:.3}", machine_pos_x));
-                    ui.label(format!("  Y: {:.3}", machine_pos_y));
-                    ui.label(format!("  Z: {:.3}", machine_pos_z));
+                    for (axis, value) in [("X", machine_pos_x), ("Y", machine_pos_y), ("Z", machine_pos_z)] {
+                        readout(
+                            ui,
+                            format!("  {}: {:.3}", axis, value),
+                            format!("Machine {} {:.3} {}", axis, value, spoken_units),
+                        );
+                    }
                     
                     ui.add_space(3.0);
                     
                     // Feed and spindle display
                     if feed_rate > 0.0 {
-                        ui.label(format!("Feed: {:.0} mm/min", feed_rate));
+                        readout(ui, format!("Feed: {:.0} mm/min", feed_rate), format!("Feed {:.0} millimeters per minute", feed_rate));
                     }
                     if spindle_speed > 0.0 {
-                        ui.label(format!("Spindle: {:.0} RPM", spindle_speed));
+                        readout(ui, format!("Spindle: {:.0} RPM", spindle_speed), format!("Spindle {:.0} RPM", spindle_speed));
                     }
                     
                     ui.add_space(3.0);
                     
                     // Override values
                     ui.label("Overrides:");
-                    ui.label(format!("  Feed: {:.0}%", feed_override));
-                    ui.label(format!("  Rapid: {:.0}%", rapid_override));
-                    ui.label(format!("  Spindle: {:.0}%", spindle_override));
+                    for (name, value) in [("Feed", feed_override), ("Rapid", rapid_override), ("Spindle", spindle_override)] {
+                        readout(
+                            ui,
+                            format!("  {}: {:.0}%", name, value),
+                            format!("{} override {:.0} percent", name, value),
+                        );
+                    }
                 });
                 
                 ui.add_space(10.0);
@@ -1923,7 +7982,7 @@ impl eframe::App for RCandleApp {
                         ui.label("Jog Controls");
                         
                         // Machine lock status indicator
-                        let machine_status = self.app_state.machine.read().status;
+                        let machine_status = self.machine_view.status;
                         let is_alarm = matches!(machine_status, MachineStatus::Alarm);
                         
                         if is_alarm {
@@ -1945,60 +8004,109 @@ impl eframe::App for RCandleApp {
                         ui.label(format!("({})", machine_status));
                     });
                     
+                    // Axis locks
+                    ui.horizontal(|ui| {
+                        ui.label("Lock:");
+                        for (locked, name) in self.jog_axis_locks.iter_mut().zip(["X", "Y", "Z"]) {
+                            let label = if *locked { format!("🔒 {}", name) } else { format!("🔓 {}", name) };
+                            ui.toggle_value(locked, label)
+                                .on_hover_text("Prevent jogging this axis")
+                                .accessible_label(format!("Lock {} axis", name));
+                        }
+                    });
+                    
                     ui.add_space(5.0);
                     
                     // Jog step size selector
-                    ui.horizontal(|ui| {
+                    let units = if self.settings.general.units_metric { "mm" } else { "in" };
+                    let mut selected_step = None;
+                    ui.horizontal_wrapped(|ui| {
                         ui.label("Step:");
-                        if ui.selectable_label(self.jog_step_size == 0.1, "0.1").clicked() {
-                            self.jog_step_size = 0.1;
-                        }
-                        if ui.selectable_label(self.jog_step_size == 1.0, "1").clicked() {
-                            self.jog_step_size = 1.0;
-                        }
-                        if ui.selectable_label(self.jog_step_size == 10.0, "10").clicked() {
-                            self.jog_step_size = 10.0;
-                        }
-                        if ui.selectable_label(self.jog_step_size == 100.0, "100").clicked() {
-                            self.jog_step_size = 100.0;
+                        for (index, step) in self.settings.jog.step_sizes.iter().enumerate() {
+                            let selected = index == self.settings.jog.default_step_index;
+                            if ui.selectable_label(selected, step.to_string()).clicked() {
+                                selected_step = Some(index);
+                            }
                         }
+                        ui.label(units);
                     });
+                    if let Some(index) = selected_step {
+                        self.select_jog_step(index);
+                    }
                     
                     ui.add_space(5.0);
                     
-                    // XY Jog grid
-                    ui.horizontal(|ui| {
-                        ui.add_space(35.0); // Indent for alignment
-                        if ui.button("↑ Y+").clicked() {
-                            self.send_jog_command(0.0, self.jog_step_size, 0.0);
-                        }
-                    });
+                    // Shift / Ctrl scale the step by 10 while held
+                    let display_step = self.modified_jog_step(ui.input(|i| i.modifiers));
+                    if display_step != self.settings.jog.current_step() {
+                        ui.weak(format!("Step with modifier: {} {}", display_step, units));
+                    }
+                    let step = display_step * self.jog_unit_scale();
                     
-                    ui.horizontal(|ui| {
-                        if ui.button("← X-").clicked() {
-                            self.send_jog_command(-self.jog_step_size, 0.0, 0.0);
-                        }
-                        if ui.button("🏠").clicked() {
-                            self.send_home_command();
-                        }
-                        if ui.button("X+ →").clicked() {
-                            self.send_jog_command(self.jog_step_size, 0.0, 0.0);
-                        }
-                    });
+                    // XY Jog grid
+                    let mut xy_jog = None;
+                    egui::Grid::new("xy_jog_grid")
+                        .num_columns(3)
+                        .spacing([4.0, 4.0])
+                        .show(ui, |ui| {
+                            let rows = [
+                                [("↖", -1.0, 1.0, "Jog X minus Y plus"), ("↑ Y+", 0.0, 1.0, "Jog Y plus"), ("↗", 1.0, 1.0, "Jog X plus Y plus")],
+                                [("← X-", -1.0, 0.0, "Jog X minus"), ("🏠", 0.0, 0.0, "Home"), ("X+ →", 1.0, 0.0, "Jog X plus")],
+                                [("↙", -1.0, -1.0, "Jog X minus Y minus"), ("↓ Y-", 0.0, -1.0, "Jog Y minus"), ("↘", 1.0, -1.0, "Jog X plus Y minus")],
+                            ];
+                            for row in rows {
+                                for (label, x, y, spoken) in row {
+                                    if ui.button(label).accessible_label(spoken).clicked() {
+                                        xy_jog = Some((x, y));
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    match xy_jog {
+                        Some((x, y)) if x == 0.0 && y == 0.0 => self.send_home_command(),
+                        Some((x, y)) => self.send_jog_command(x * step, y * step, 0.0),
+                        None => {}
+                    }
                     
                     ui.horizontal(|ui| {
-                        ui.add_space(35.0); // Indent for alignment
-                        if ui.button("🔓 Unlock").clicked() {
+                        if ui.button("🔓 Unlock").on_hover_text("Clear an alarm ($X, Ctrl+Shift+U)").clicked() {
                             self.send_unlock_command();
                         }
+                        if ui.add_enabled(self.settings.jog.limit_to_travel, egui::Button::new("⌖ Center"))
+                            .on_hover_text("Jog to the center of the machine travel in XY")
+                            .on_disabled_hover_text("Set the machine travel in the jog settings")
+                            .clicked()
+                        {
+                            self.jog_to_work_center();
+                        }
                     });
                     
+                    // Single moves to the edge of the travel or fence
+                    let has_limits = self.settings.jog.jog_limits().is_some();
+                    let mut edge = None;
                     ui.horizontal(|ui| {
-                        ui.add_space(35.0); // Indent for alignment
-                        if ui.button("↓ Y-").clicked() {
-                            self.send_jog_command(0.0, -self.jog_step_size, 0.0);
+                        ui.label("Edge:");
+                        for (label, axis, toward_max, hint) in [
+                            ("⇤ X", 0, false, "Jog to X min"),
+                            ("X ⇥", 0, true, "Jog to X max"),
+                            ("⤓ Y", 1, false, "Jog to Y min"),
+                            ("Y ⤒", 1, true, "Jog to Y max"),
+                            ("Z ⤒", 2, true, "Jog to Z max"),
+                        ] {
+                            if ui.add_enabled(has_limits, egui::Button::new(label))
+                                .accessible_label(hint)
+                                .on_hover_text(hint)
+                                .on_disabled_hover_text("Set the machine travel or fence in the jog settings")
+                                .clicked()
+                            {
+                                edge = Some((axis, toward_max));
+                            }
                         }
                     });
+                    if let Some((axis, toward_max)) = edge {
+                        self.jog_to_edge(axis, toward_max);
+                    }
                     
                     ui.add_space(5.0);
                     
@@ -2006,10 +8114,10 @@ impl eframe::App for RCandleApp {
                     ui.horizontal(|ui| {
                         ui.label("Z:");
                         if ui.button("↑ Z+").clicked() {
-                            self.send_jog_command(0.0, 0.0, self.jog_step_size);
+                            self.send_jog_command(0.0, 0.0, step);
                         }
                         if ui.button("Z- ↓").clicked() {
-                            self.send_jog_command(0.0, 0.0, -self.jog_step_size);
+                            self.send_jog_command(0.0, 0.0, -step);
                         }
                     });
                     
@@ -2028,9 +8136,22 @@ impl eframe::App for RCandleApp {
                         }
                     });
                     
-                    if ui.button("Zero All").clicked() {
-                        self.send_zero_all();
-                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Zero All").clicked() {
+                            self.send_zero_all();
+                        }
+                        let last_zero = self.action_log.entries().rev()
+                            .find(|a| a.kind == ActionKind::Zero && a.can_undo())
+                            .map(|a| a.id);
+                        if ui.add_enabled(last_zero.is_some(), egui::Button::new("↶ Undo Zero"))
+                            .on_hover_text("Restore the offset from before the last zero")
+                            .clicked()
+                        {
+                            if let Some(id) = last_zero {
+                                self.handle_action_log_request(ActionLogRequest::Undo(id));
+                            }
+                        }
+                    });
                 });
                 
                 ui.add_space(10.0);
@@ -2041,7 +8162,7 @@ impl eframe::App for RCandleApp {
                     
                     // Extract data from machine_state before closures
                     let (coord_system, work_pos_x, work_pos_y, work_pos_z) = {
-                        let machine_state = self.app_state.machine.read();
+                        let machine_state = &self.machine_view;
                         (
                             machine_state.coordinate_system.clone(),
                             machine_state.work_position.x,
@@ -2054,9 +8175,16 @@ impl eframe::App for RCandleApp {
                     ui.label(format!("System: {:?}", coord_system));
                     
                     // Display work position (with work offsets applied)
-                    ui.label(format!("X: {:.3}", work_pos_x));
-                    ui.label(format!("Y: {:.3}", work_pos_y));
-                    ui.label(format!("Z: {:.3}", work_pos_z));
+                    let spoken_units = if self.settings.general.units_metric { "millimeters" } else { "inches" };
+                    for (axis, value) in [(self.settings.machine.x_label(), work_pos_x), ("Y", work_pos_y), ("Z", work_pos_z)] {
+                        readout(ui, format!("{}: {:.3}", axis, value), format!("Work {} {:.3} {}", axis, value, spoken_units));
+                    }
+                    readout(
+                        ui,
+                        format!("TLO: {:.3}", self.tool_length.offset),
+                        format!("Tool length offset {:.3} {}", self.tool_length.offset, spoken_units),
+                    )
+                    .on_hover_text("Tool length offset (G43.1)");
                     
                     ui.add_space(5.0);
                     
@@ -2068,6 +8196,135 @@ impl eframe::App for RCandleApp {
                             }
                         }
                     });
+                    
+                    // Saved work zeros for repeat fixtures
+                    let idle = !matches!(
+                        self.app_state.program.read().state,
+                        ExecutionState::Running | ExecutionState::Paused
+                    );
+                    let (mut restore, mut overwrite, mut delete, mut save) = (None, None, None, None);
+                    ui.collapsing("Zero Slots", |ui| {
+                        for (index, slot) in self.settings.machine.work_zero_slots.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                let [x, y, z] = slot.offset;
+                                if ui.add_enabled(idle, egui::Button::new(format!("↺ {}", slot.name)))
+                                    .on_hover_text(format!("Restore X{:.3} Y{:.3} Z{:.3} into {}", x, y, z, coord_system))
+                                    .clicked()
+                                {
+                                    restore = Some(index);
+                                }
+                                if ui.small_button("📍").on_hover_text("Replace with the active work zero").clicked() {
+                                    overwrite = Some(slot.name.clone());
+                                }
+                                if ui.small_button("🗑").on_hover_text("Delete this slot").clicked() {
+                                    delete = Some(index);
+                                }
+                            });
+                        }
+                        let full = self.settings.machine.work_zero_slots.len() >= MAX_WORK_ZERO_SLOTS;
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.zero_slot_name)
+                                .hint_text("Slot name")
+                                .desired_width(100.0));
+                            let name = self.zero_slot_name.trim();
+                            if ui.add_enabled(!name.is_empty() && !full, egui::Button::new("💾 Save"))
+                                .on_hover_text("Save the active work zero in a new slot")
+                                .on_disabled_hover_text(format!("Enter a name; up to {} slots", MAX_WORK_ZERO_SLOTS))
+                                .clicked()
+                            {
+                                save = Some(name.to_string());
+                            }
+                        });
+                    });
+                    if let Some(index) = restore {
+                        self.restore_work_zero_slot(index);
+                    }
+                    if let Some(name) = overwrite {
+                        self.save_work_zero_slot(name);
+                    }
+                    if let Some(name) = save {
+                        self.zero_slot_name.clear();
+                        self.save_work_zero_slot(name);
+                    }
+                    if let Some(index) = delete {
+                        let slot = self.settings.machine.work_zero_slots.remove(index);
+                        self.console.info(format!("Deleted work zero '{}'", slot.name));
+                        if let Err(e) = self.launch.save_settings(&self.settings) {
+                            self.report_error(e.with_context("Failed to save settings"));
+                        }
+                    }
+                });
+                
+                ui.add_space(10.0);
+                
+                // Named machine positions
+                ui.group(|ui| {
+                    ui.label("Machine Positions");
+                    
+                    let idle = !matches!(
+                        self.app_state.program.read().state,
+                        ExecutionState::Running | ExecutionState::Paused
+                    );
+                    let names: Vec<String> = self.settings.machine.positions.iter().map(|p| p.name.clone()).collect();
+                    for (index, name) in names.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(idle, egui::Button::new(format!("➜ {}", name)))
+                                .on_hover_text("Go to this position")
+                                .clicked()
+                            {
+                                self.go_to_position(index);
+                            }
+                            if ui.small_button("📍")
+                                .on_hover_text("Set to the current machine position")
+                                .clicked()
+                            {
+                                self.set_position_from_machine(index);
+                            }
+                        });
+                    }
+                    
+                    let can_measure = self.settings.machine.position(PositionRole::ProbePlate).is_some();
+                    if ui.add_enabled(idle && can_measure, egui::Button::new("📏 Measure Tool"))
+                        .on_hover_text("Probe the tool length on the probe plate")
+                        .clicked()
+                    {
+                        self.measure_tool();
+                    }
+                    
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Reference Positions");
+                        if ui.small_button("⟳").on_hover_text("Read stored positions ($#)").clicked() {
+                            self.read_parameters();
+                        }
+                    });
+                    let (g28, g30) = {
+                        let machine = self.app_state.machine.read();
+                        (machine.g28_position, machine.g30_position)
+                    };
+                    for (reference, stored) in [(ReferencePosition::G28, g28), (ReferencePosition::G30, g30)] {
+                        ui.horizontal(|ui| {
+                            ui.label(reference.code());
+                            match stored {
+                                Some(p) => ui.monospace(format!("{:.3}, {:.3}, {:.3}", p.x, p.y, p.z)),
+                                None => ui.weak("not read"),
+                            };
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(idle, egui::Button::new(format!("➜ Go {}", reference.code())))
+                                .on_hover_text("Raise Z, then move to the stored position")
+                                .clicked()
+                            {
+                                self.go_to_reference(reference);
+                            }
+                            if ui.add_enabled(idle, egui::Button::new("📍 Set..."))
+                                .on_hover_text("Store the current machine position")
+                                .clicked()
+                            {
+                                self.pending_reference = Some(reference);
+                            }
+                        });
+                    }
                 });
                 
                 ui.add_space(10.0);
@@ -2116,6 +8373,50 @@ impl eframe::App for RCandleApp {
                 
                 ui.add_space(10.0);
                 
+                // Coolant and accessory outputs
+                ui.group(|ui| {
+                    ui.label("Coolant");
+                    
+                    let (flood, mist) = {
+                        let machine = self.app_state.machine.read();
+                        (machine.flood_coolant, machine.mist_coolant)
+                    };
+                    let light = |on: bool| if on { egui::Color32::GREEN } else { egui::Color32::DARK_GRAY };
+                    ui.horizontal(|ui| {
+                        ui.colored_label(light(flood), "●");
+                        if ui.button("Flood (M8)").clicked() {
+                            self.send_coolant_command("M8");
+                        }
+                        ui.colored_label(light(mist), "●");
+                        if ui.button("Mist (M7)").clicked() {
+                            self.send_coolant_command("M7");
+                        }
+                        if ui.button("⏹ Off (M9)").clicked() {
+                            self.send_coolant_command("M9");
+                        }
+                    });
+                    
+                    let outputs = self.settings.machine.user_outputs as usize;
+                    if outputs > 0 {
+                        ui.add_space(5.0);
+                        ui.label("Outputs");
+                        ui.horizontal_wrapped(|ui| {
+                            for index in 0..outputs {
+                                let on = self.user_outputs.get(index).copied().unwrap_or(false);
+                                ui.colored_label(light(on), "●");
+                                if ui.button(format!("P{}", index))
+                                    .on_hover_text("Toggle output immediately (M64/M65)")
+                                    .clicked()
+                                {
+                                    self.toggle_user_output(index);
+                                }
+                            }
+                        });
+                    }
+                });
+                
+                ui.add_space(10.0);
+                
                 // Feed rate override
                 ui.group(|ui| {
                     ui.label("Feed Rate Override");
@@ -2145,6 +8446,12 @@ impl eframe::App for RCandleApp {
                     });
                     
                     ui.label(format!("Active: {:.0}%", self.feed_override));
+                    if self.feed_ramp.is_ramping() {
+                        ui.weak(format!("Ramping, now at {:.0}%", self.feed_ramp.current()));
+                    }
+                    if self.wear_reduction > 0.0 {
+                        ui.weak(format!("Includes -{:.0}% for wear of T{}", self.wear_reduction, self.active_tool));
+                    }
                 });
                 
                 ui.add_space(10.0);
@@ -2219,19 +8526,21 @@ impl eframe::App for RCandleApp {
                     
                     // Main control buttons in a grid
                     ui.horizontal(|ui| {
-                        if ui.button("▶ Run").clicked() {
-                            self.start_program();
+                        if ui.button("▶ Run").on_hover_text("Run or resume (F5)").clicked() {
+                            self.request_program_start();
                         }
-                        if ui.button("⏸ Pause").clicked() {
+                        if ui.button("⏸ Pause").on_hover_text("Feed hold (F6)").clicked() {
                             self.pause_program();
                         }
-                        if ui.button("⏹ Stop").clicked() {
+                        if ui.button("⏹ Stop").on_hover_text("Stop (F7)").clicked() {
                             self.stop_program();
                         }
                         if ui.button("🔄 Reset").clicked() {
                             self.reset_program();
                         }
                     });
+                    ui.checkbox(&mut self.dry_run, "Dry run above the stock")
+                        .on_hover_text("Applies from the next start; set the clearance and feed in the processing settings");
                     
                     ui.add_space(5.0);
                     
@@ -2290,7 +8599,18 @@ impl eframe::App for RCandleApp {
                     ui.add_space(5.0);
                     
                     // Step mode controls
-                    ui.checkbox(&mut self.step_mode, "Step Mode");
+                    ui.checkbox(&mut self.step_mode, "Step Mode")
+                        .on_hover_text("Pause before every line. Breakpoints (F9 or the ● gutter) pause before their line");
+                    
+                    if ui.checkbox(&mut self.settings.processing.skip_block_delete, "Skip / (block delete) lines")
+                        .on_hover_text("Lines starting with \"/\" are skipped when checked")
+                        .changed()
+                    {
+                        self.parser = Self::build_parser(&self.settings);
+                        if !self.gcode_content.is_empty() {
+                            self.parse_gcode();
+                        }
+                    }
                     
                     if self.step_mode {
                         if ui.button("⏭ Single Step").clicked() {
@@ -2353,41 +8673,24 @@ impl eframe::App for RCandleApp {
                 
                 // User Commands Panel - Phase 8
                 if self.show_user_commands {
-                    // Store clicked command outside of borrowing scope
-                    let mut clicked_command: Option<String> = None;
+                    let context = self.user_command_context();
+                    let action = ui
+                        .group(|ui| self.user_commands_panel.show(ui, &mut self.user_command_library, &context))
+                        .inner;
                     
-                    ui.group(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("User Commands");
-                            if ui.button("➕").clicked() {
-                                self.show_script_editor = true;
-                                self.editing_script = Some(UserScript::new("New Command".to_string(), String::new()));
-                            }
-                        });
-                        
-                        ui.separator();
-                        
-                        // Display user commands by category
-                        let categories = self.user_command_library.categories();
-                        for category in categories {
-                            ui.label(category.clone());
-                            
-                            let commands = self.user_command_library.commands_by_category(&category);
-                            for command in commands {
-                                if ui.button(&command.name).clicked() {
-                                    clicked_command = Some(command.name.clone());
-                                }
-                            }
-                            
-                            ui.add_space(3.0);
+                    // Act on the panel after the UI closure
+                    match action {
+                        Some(UserCommandsAction::Run(name)) => self.request_user_command(&name),
+                        Some(UserCommandsAction::Add) => {
+                            self.show_script_editor = true;
+                            self.editing_script = Some(UserScript::new("New Command".to_string(), String::new()));
                         }
-                    });
-                    
-                    // Execute clicked command after UI closure
-                    if let Some(cmd_name) = clicked_command {
-                        self.execute_user_command(&cmd_name);
+                        Some(UserCommandsAction::Save) => self.save_user_commands(),
+                        None => {}
                     }
                 }
+                
+                PluginManager::show_panels(ui, &mut self.plugins);
 
             });
 
@@ -2396,10 +8699,14 @@ impl eframe::App for RCandleApp {
             .default_width(300.0)
             .show(ctx, |ui| {
                 ui.heading("G-Code");
+                self.show_document_tabs(ui);
                 ui.separator();
                 
                 // Use the custom GCodeEditor widget
                 self.gcode_editor.show(ui, &mut self.gcode_content);
+                if self.gcode_editor.take_bookmarks_changed() {
+                    self.save_bookmarks();
+                }
             });
 
         // Console panel (bottom, before central panel)
@@ -2416,30 +8723,126 @@ impl eframe::App for RCandleApp {
                         // Handle command submission
                         self.handle_console_command(&command);
                     }
+                    if self.console.take_export_request() {
+                        self.export_console();
+                    }
                 });
         }
 
         // Central panel - 3D viewport
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Toolpath Viewer");
+            ui.horizontal(|ui| {
+                ui.heading("Toolpath Viewer");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let mut mode = self.settings.visualization.color_mode;
+                    egui::ComboBox::from_id_source("toolpath_color_mode")
+                        .selected_text(match mode {
+                            ToolpathColorMode::MoveType => "Move Type",
+                            ToolpathColorMode::FeedRate => "Feed Rate",
+                            ToolpathColorMode::Depth => "Depth",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut mode, ToolpathColorMode::MoveType, "Move Type");
+                            ui.selectable_value(&mut mode, ToolpathColorMode::FeedRate, "Feed Rate");
+                            ui.selectable_value(&mut mode, ToolpathColorMode::Depth, "Depth");
+                        });
+                    ui.label("Color by:");
+                    if mode != self.settings.visualization.color_mode {
+                        self.set_color_mode(mode);
+                    }
+                    
+                    // Isolate a single depth pass
+                    if self.z_levels.len() > 1 {
+                        ui.separator();
+                        let mut level = self.z_filter_level;
+                        if let Some(ref mut index) = level {
+                            let levels = &self.z_levels;
+                            if let Some(step) = index.checked_sub(1).map(|above| levels[above] - levels[*index]) {
+                                ui.label(format!("step {:.3}", step));
+                            }
+                            ui.add(egui::Slider::new(index, 0..=levels.len() - 1)
+                                .custom_formatter(|n, _| format!("Z{:.3}", levels[n as usize])));
+                        }
+                        let mut filtered = level.is_some();
+                        if ui.checkbox(&mut filtered, "Z filter")
+                            .on_hover_text("Show only the cuts at one Z level")
+                            .changed()
+                        {
+                            level = filtered.then_some(0);
+                        }
+                        if level != self.z_filter_level {
+                            self.set_z_filter_level(level);
+                        }
+                    }
+                    
+                    // Section view of nested passes
+                    if !self.segments.is_empty() {
+                        ui.separator();
+                        let segments = &self.segments;
+                        let middle = |axis| ClipPlane::range(segments, axis).map_or(0.0, |(low, high)| (low + high) / 2.0);
+                        let mut plane = self.clip_plane;
+                        if let Some(ref mut plane) = plane {
+                            if let Some((low, high)) = ClipPlane::range(segments, plane.axis) {
+                                ui.add(egui::Slider::new(&mut plane.position, low..=high)
+                                    .fixed_decimals(3)
+                                    .prefix(format!("{} ", plane.axis)));
+                            }
+                            ui.toggle_value(&mut plane.flip, if plane.flip { "▲ above" } else { "▼ below" })
+                                .on_hover_text("Side of the plane that is shown");
+                            let axis = plane.axis;
+                            egui::ComboBox::from_id_source("clip_axis")
+                                .width(40.0)
+                                .selected_text(plane.axis.to_string())
+                                .show_ui(ui, |ui| {
+                                    for axis in [ClipAxis::X, ClipAxis::Y, ClipAxis::Z] {
+                                        ui.selectable_value(&mut plane.axis, axis, axis.to_string());
+                                    }
+                                });
+                            if plane.axis != axis {
+                                plane.position = middle(plane.axis);
+                            }
+                        }
+                        let mut clipped = plane.is_some();
+                        if ui.checkbox(&mut clipped, "Section")
+                            .on_hover_text("Hide the toolpath on one side of a plane to see the inner passes")
+                            .changed()
+                        {
+                            plane = clipped.then(|| ClipPlane {
+                                axis: ClipAxis::Z,
+                                position: middle(ClipAxis::Z),
+                                flip: false,
+                            });
+                        }
+                        if plane != self.clip_plane {
+                            self.set_clip_plane(plane);
+                        }
+                    }
+                });
+            });
             
             let available_size = ui.available_size();
-            // Use hover sense instead of click_and_drag to avoid consuming events
-            let (rect, _response) = ui.allocate_exact_size(
+            // Use click sense instead of click_and_drag to avoid consuming drags
+            let (rect, response) = ui.allocate_exact_size(
                 available_size,
-                egui::Sense::hover()
+                egui::Sense::click()
             );
             
             // Draw background
             ui.painter().rect_filled(
                 rect,
                 0.0,
-                egui::Color32::from_rgb(25, 25, 35)
+                Self::to_color32(self.settings.visualization.color_scheme.background)
             );
             
             // Draw toolpath if we have segments
             if !self.segments.is_empty() {
-                self.draw_toolpath_2d(ui, rect);
+                let hovered = self.draw_toolpath_2d(ui, rect, response.hover_pos());
+                if let Some(index) = hovered {
+                    response.clone().on_hover_ui_at_pointer(|ui| self.show_segment_tooltip(ui, index));
+                }
+                if response.clicked() {
+                    self.select_segment(hovered);
+                }
             } else {
                 // Show placeholder text
                 ui.painter().text(
@@ -2472,19 +8875,296 @@ impl eframe::App for RCandleApp {
             self.show_settings_window(ctx);
         }
         
+        if self.show_shortcuts {
+            Self::show_shortcuts_window(ctx, &mut self.show_shortcuts);
+        }
+        
+        if self.command_palette.open {
+            let entries = self.palette_entries();
+            if let Some(command) = self.command_palette.show(ctx, &entries) {
+                self.run_palette_command(ctx, command);
+            }
+        }
+        
         // Show script editor dialog - Phase 8
         if self.show_script_editor {
             self.show_script_editor_window(ctx);
         }
         
+        // Multi-pass depth dialog
+        if self.multipass_dialog.open {
+            if let Some(generator) = self.multipass_dialog.show(ctx) {
+                self.apply_multipass(generator);
+            }
+        }
+        
+        // Renumber and reformat dialog
+        if self.formatter_dialog.open {
+            if let Some(program) = self.formatter_dialog.show(ctx, &self.gcode_content) {
+                self.gcode_content = program;
+                self.console.info("Program reformatted".to_string());
+                self.parse_gcode();
+            }
+        }
+        
+        // Program statistics
+        if self.statistics_panel.open {
+            self.statistics_panel.show(ctx, &self.gcode_content, &self.segments);
+        }
+        
+        // Words from other G-Code flavors
+        if self.compatibility_panel.open {
+            let skipped = self.settings.processing.strip_words.clone();
+            match self.compatibility_panel.show(ctx, &self.gcode_content, &skipped) {
+                Some(CompatibilityAction::Strip(program)) => {
+                    self.gcode_content = program;
+                    self.console.info("Unsupported words stripped from the program".to_string());
+                    self.parse_gcode();
+                }
+                Some(CompatibilityAction::SkipWhenStreaming(words)) => {
+                    self.settings.processing.strip_words = words;
+                    if let Err(e) = self.launch.save_settings(&self.settings) {
+                        self.report_error(e.with_context("Failed to save settings"));
+                    }
+                }
+                None => {}
+            }
+        }
+        
+        // Fixture library
+        if self.fixture_panel.open {
+            let job = BoundingBox::of_job(&self.segments)
+                .map(|b| ([b.min.x as f64, b.min.y as f64], [b.max.x as f64, b.max.y as f64]));
+            let connected = self.connection_manager.is_some();
+            let action = self.fixture_panel.show(
+                ctx,
+                &mut self.settings.machine.fixtures,
+                &mut self.settings.visualization.show_fixtures,
+                job,
+                connected,
+            );
+            if let Some(action) = action {
+                self.handle_fixture_action(action);
+            }
+        }
+        
+        // Maintenance counters and reminders
+        if self.maintenance_panel.open {
+            if let Some(action) = self.maintenance_panel.show(ctx, &mut self.settings.machine.maintenance) {
+                if let MaintenanceAction::Done(index) = action {
+                    self.settings.machine.maintenance.mark_done(index);
+                    if let Some(reminder) = self.settings.machine.maintenance.reminders.get(index) {
+                        self.console.info(format!("Maintenance done: {}", reminder.name));
+                    }
+                }
+                if let Err(e) = self.launch.save_settings(&self.settings) {
+                    self.report_error(e.with_context("Failed to save settings"));
+                }
+            }
+        }
+        
+        // Plugin manager
+        if self.plugin_manager.open {
+            let folder = PluginHost::default_dir().ok();
+            match self.plugin_manager.show(ctx, &mut self.plugins, folder.as_deref()) {
+                Some(PluginAction::Toggled) => {
+                    self.settings.general.enabled_plugins = self.plugins.enabled_ids();
+                    if let Err(e) = self.launch.save_settings(&self.settings) {
+                        self.report_error(e.with_context("Failed to save settings"));
+                    }
+                }
+                Some(PluginAction::Reload) => self.reload_plugins(),
+                None => {}
+            }
+        }
+        
+        // Jog held back by the soft limits
+        self.show_pending_spindle_jog(ctx);
+        self.show_pending_jog(ctx);
+        self.show_pending_reference(ctx);
+        self.show_pending_user_command(ctx);
+        self.show_reload_prompt(ctx);
+        self.show_tool_change(ctx);
+        self.show_broken_tool(ctx);
+        self.show_z_limit_hold(ctx);
+        self.show_comment_prompt(ctx);
+        self.show_checklist(ctx);
+        self.show_interrupted_job(ctx);
+        self.show_restart_prompt(ctx);
+        self.show_crash_report(ctx);
+        
+        // Action log
+        if self.action_log_panel.open {
+            let connected = self.connection_manager.is_some();
+            if let Some(request) = self.action_log_panel.show(ctx, &self.action_log, connected) {
+                self.handle_action_log_request(request);
+            }
+        }
+        
+        // Recovery log
+        if self.recovery_log_panel.open && self.recovery_log_panel.show(ctx, &self.recovery_engine) {
+            self.recovery_engine.clear_log();
+        }
+        
+        // Edge finder
+        if self.edge_finder.open {
+            let connected = self.connection_manager.is_some();
+            let busy = self.probing_edge.is_some();
+            if let Some(edge) = self.edge_finder.show(ctx, &mut self.settings.machine, connected, busy) {
+                self.probe_edge(edge);
+            }
+        }
+        
+        // Backlash wizard
+        if self.backlash_wizard.open {
+            let connected = self.connection_manager.is_some();
+            match self.backlash_wizard.show(ctx, &mut self.settings.machine, connected) {
+                Some(BacklashAction::Move(commands)) => {
+                    self.send_command_sequence(commands);
+                }
+                Some(BacklashAction::Saved) => {
+                    let [x, y, z] = self.settings.machine.backlash;
+                    self.console.info(format!("Backlash saved: X{:.3} Y{:.3} Z{:.3} mm", x, y, z));
+                    if let Err(e) = self.launch.save_settings(&self.settings) {
+                        self.report_error(e.with_context("Failed to save settings"));
+                    }
+                }
+                None => {}
+            }
+        }
+        
+        // First-run setup wizard
+        if self.onboarding.open {
+            let connected = self.connection_manager.is_some();
+            match self.onboarding.show(ctx, &self.available_ports, connected) {
+                Some(OnboardingAction::RefreshPorts) => self.refresh_ports(),
+                Some(OnboardingAction::Connect { port, baud_rate }) => {
+                    self.settings.connection.connection_type = ConnectionType::Serial;
+                    self.settings.connection.baud_rate = baud_rate;
+                    self.selected_port = port;
+                    self.connect_to_grbl(ctx);
+                }
+                Some(OnboardingAction::Send(commands)) => {
+                    self.send_command_sequence(commands);
+                }
+                Some(OnboardingAction::Finish) => {
+                    self.onboarding.apply(&mut self.settings);
+                    self.selected_port = self.settings.connection.port_name.clone();
+                    if let Some(mask) = self.onboarding.direction_change() {
+                        self.send_command(GrblCommand::SetSetting { setting: 3, value: mask as f64 });
+                        self.console.info(format!("Direction invert mask set to $3={}", mask));
+                    }
+                    if let Err(e) = self.launch.save_settings(&self.settings) {
+                        self.report_error(e.with_context("Failed to save settings"));
+                    }
+                    self.status_message = "Machine setup saved".to_string();
+                }
+                Some(OnboardingAction::Skip) => {
+                    self.settings.general.onboarding_done = true;
+                    if let Err(e) = self.launch.save_settings(&self.settings) {
+                        self.report_error(e.with_context("Failed to save settings"));
+                    }
+                }
+                None => {}
+            }
+        }
+        
+        // Single-axis test motion
+        if self.axis_test.open {
+            let connected = self.connection_manager.is_some();
+            let idle = self.machine_view.status == MachineStatus::Idle;
+            let position = self.app_state.machine.read().machine_position;
+            if let Some(commands) = self.axis_test.show(ctx, [position.x, position.y, position.z], idle, connected) {
+                self.send_command_sequence(commands);
+            }
+        }
+        
+        // Flatness report
+        if self.flatness_panel.open {
+            let has_project_heightmap = self.project_dialog.project.heightmap.is_some();
+            let leveling = self.leveling.as_ref().map(|(name, _)| name.as_str());
+            if let Some(request) = self.flatness_panel.show(ctx, has_project_heightmap, leveling) {
+                self.handle_flatness_request(request);
+            }
+        }
+        
+        // Probe log
+        if self.probe_log_panel.open {
+            let connected = self.connection_manager.is_some();
+            if let Some(request) = self.probe_log_panel.show(ctx, &self.probe_log, &self.tool_length, connected) {
+                self.handle_probe_log_request(request);
+            }
+        }
+        
+        // Project dialog
+        if self.project_dialog.open {
+            let connected = self.connection_manager.is_some();
+            if let Some(action) = self.project_dialog.show(ctx, connected) {
+                self.handle_project_action(action);
+            }
+        }
+        
+        // Feeds, speeds and job cost calculator
+        if self.calculator_dialog.open {
+            let segments = &self.segments;
+            let estimate_for = |rapid_rate| {
+                (!segments.is_empty()).then(|| JobEstimate::from_segments(segments, rapid_rate))
+            };
+            if let Some(record) = self.calculator_dialog.show(ctx, estimate_for) {
+                self.save_job_record(record);
+            }
+        }
+        
+        // Diagnostics panel
+        if self.diagnostics_panel.open {
+            let metrics = self.app_state.link_metrics.read().clone();
+            let connected = self.connection_manager.is_some();
+            if self.diagnostics_panel.show(ctx, &metrics, connected) {
+                self.reset_link_metrics();
+            }
+        }
+        
+        if self.status_chart.open {
+            self.status_chart.show(ctx);
+        }
+        
+        // GRBL reference
+        if self.reference_panel.open {
+            self.reference_panel.show(ctx);
+        }
+        
+        // Error toasts and details
+        match self.error_presenter.show(ctx) {
+            Some((topic, Some(code))) => self.reference_panel.show_code(topic, code),
+            Some((topic, None)) => self.reference_panel.show_topic(topic),
+            None => {}
+        }
     }
 
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
         // Save settings to default location
-        if let Err(e) = self.settings.save_default() {
+        if let Err(e) = self.launch.save_settings(&self.settings) {
             tracing::error!("Failed to save settings: {}", e);
         }
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.window_geometry_changed {
+            if let Err(e) = self.launch.save_settings(&self.settings) {
+                tracing::error!("Failed to save window geometry: {}", e);
+            }
+        }
+        
+        // Stop the connection's background tasks in order instead of aborting them in Drop
+        if let Some(manager) = self.connection_manager.take() {
+            tokio::runtime::Handle::current().block_on(async move {
+                if let Err(e) = manager.lock().await.disconnect().await {
+                    tracing::error!("Error during disconnect on exit: {}", e);
+                }
+            });
+        }
+        tokio::runtime::Handle::current().block_on(self.tasks.shutdown(Duration::from_secs(1)));
+    }
 }
 
 /// Format a duration in HH:MM:SS format
@@ -2495,3 +9175,9 @@ fn format_duration(duration: std::time::Duration) -> String {
     let seconds = total_secs % 60;
     format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
 }
+
+/// Describe a tool length deviation, e.g. "0.512 mm shorter than expected"
+fn describe_deviation(deviation: f64) -> String {
+    let direction = if deviation < 0.0 { "shorter" } else { "longer" };
+    format!("{:.3} mm {} than expected", deviation.abs(), direction)
+}
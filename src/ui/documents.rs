@@ -0,0 +1,85 @@
+//! Open G-Code documents
+//!
+//! Each editor tab holds a document with its own text, editor state and
+//! parse results. The active document's state lives in the application
+//! while it is shown; the others are parked here until their tab is
+//! selected again.
+//!
+//! A document loaded from a file remembers when the file was last modified,
+//! so changes made on disk (such as a CAM re-post) can be picked up.
+
+use crate::parser::Segment;
+use crate::renderer::SegmentIndex;
+use crate::ui::widgets::GCodeEditor;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A G-Code program open in an editor tab
+#[derive(Default)]
+pub struct Document {
+    /// File the program was loaded from or saved to
+    pub path: Option<PathBuf>,
+    /// State of the file when it was loaded or saved
+    pub stamp: Option<FileStamp>,
+    /// Program text
+    pub content: String,
+    /// Scratch buffer holding G-Code pasted from the clipboard
+    pub scratch: bool,
+    /// Editor state: mode, bookmarks, find/replace and execution highlight
+    pub editor: GCodeEditor,
+    /// Parsed segments
+    pub segments: Vec<Segment>,
+    /// Spatial index of the segments for picking
+    pub segment_index: SegmentIndex,
+    /// Segment selected in the viewer
+    pub selected_segment: Option<usize>,
+    /// Z levels of the horizontal cuts, from top to bottom
+    pub z_levels: Vec<f64>,
+    /// Z level shown on its own
+    pub z_filter_level: Option<usize>,
+}
+
+/// State of a document's file when it was last loaded or saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    /// File modification time
+    pub modified: Option<SystemTime>,
+    /// Hash of the text loaded or saved
+    content_hash: u64,
+}
+
+impl FileStamp {
+    /// Stamp a file that was just loaded or saved with this content
+    pub fn new(path: &Path, content: &str) -> Self {
+        Self {
+            modified: modified_time(path),
+            content_hash: content_hash(content),
+        }
+    }
+
+    /// Whether the content is still what was loaded or saved
+    pub fn matches(&self, content: &str) -> bool {
+        self.content_hash == content_hash(content)
+    }
+}
+
+/// Modification time of a file, if it can be read
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Tab title for a document: its file name, "Clipboard" for the scratch buffer, or "Untitled"
+pub fn title(path: Option<&Path>, scratch: bool) -> String {
+    match path.and_then(|path| path.file_name()) {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None if scratch => "📋 Clipboard".to_string(),
+        None => "Untitled".to_string(),
+    }
+}
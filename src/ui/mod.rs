@@ -3,6 +3,7 @@
 //! Provides the main application window and UI components.
 
 mod app;
+mod documents;
 mod panels;
 pub mod widgets;
 
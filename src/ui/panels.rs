@@ -1,8 +0,0 @@
-//! UI panels for rCandle
-//!
-//! This module will contain specialized panels for different UI sections.
-//! TODO: Implement individual panels (control, state, jog, etc.)
-
-#![allow(dead_code)]
-
-// Placeholder for future panel implementations
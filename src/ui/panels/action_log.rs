@@ -0,0 +1,89 @@
+//! Action log panel
+//!
+//! Lists the machine-affecting commands issued from the UI with undo and
+//! redo buttons.
+
+use crate::state::ActionLog;
+
+/// Request from the action log panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionLogRequest {
+    /// Undo the action with this id
+    Undo(u64),
+    /// Redo the action with this id
+    Redo(u64),
+    /// Clear the log
+    Clear,
+}
+
+/// Panel listing issued UI actions
+#[derive(Debug, Clone, Default)]
+pub struct ActionLogPanel {
+    /// Whether the panel is open
+    pub open: bool,
+}
+
+impl ActionLogPanel {
+    /// Show the panel, returning the user's request
+    pub fn show(&mut self, ctx: &egui::Context, log: &ActionLog, connected: bool) -> Option<ActionLogRequest> {
+        let mut request = None;
+
+        egui::Window::new("↶ Action Log")
+            .open(&mut self.open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let last = log.last_undoable();
+                    if ui
+                        .add_enabled(connected && last.is_some(), egui::Button::new("↶ Undo Last"))
+                        .clicked()
+                    {
+                        request = last.map(ActionLogRequest::Undo);
+                    }
+                    if ui.add_enabled(!log.is_empty(), egui::Button::new("Clear")).clicked() {
+                        request = Some(ActionLogRequest::Clear);
+                    }
+                });
+                ui.separator();
+
+                if log.is_empty() {
+                    ui.weak("No actions yet");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        egui::Grid::new("action_log_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for action in log.entries().rev() {
+                                    ui.monospace(action.timestamp.format("%H:%M:%S").to_string());
+                                    ui.label(action.kind.to_string());
+                                    let description = egui::RichText::new(&action.description);
+                                    let label = if action.undone { description.strikethrough() } else { description };
+                                    ui.label(label).on_hover_text(action.command.format());
+
+                                    if action.can_undo() {
+                                        if ui.add_enabled(connected, egui::Button::new("↶").small()).on_hover_text("Undo").clicked() {
+                                            request = Some(ActionLogRequest::Undo(action.id));
+                                        }
+                                    } else if action.can_redo() {
+                                        if ui.add_enabled(connected, egui::Button::new("↷").small()).on_hover_text("Redo").clicked() {
+                                            request = Some(ActionLogRequest::Redo(action.id));
+                                        }
+                                    } else {
+                                        ui.label("");
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        request
+    }
+}
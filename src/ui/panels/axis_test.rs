@@ -0,0 +1,229 @@
+//! Single-axis test motion
+//!
+//! Moves one axis back and forth a number of times for tuning acceleration
+//! and checking for lost steps. Every cycle returns to the start, so once
+//! the machine is idle again its reported position should equal the start
+//! position. The controller reports the steps it sent, so a stalled motor
+//! on open-loop drives only shows once the position is checked against a
+//! reference such as a dial indicator, the edge finder or homing.
+
+use std::time::{Duration, Instant};
+
+const AXES: [char; 3] = ['X', 'Y', 'Z'];
+
+/// Relative moves out and back along one axis, ending where they started
+pub fn test_moves(axis: usize, distance: f64, feed_rate: f64, repetitions: u32) -> Vec<String> {
+    let axis = AXES[axis.min(2)];
+    let mut moves = vec!["G91".to_string()];
+    for _ in 0..repetitions {
+        moves.push(format!("G1 {}{:.3} F{:.0}", axis, distance, feed_rate));
+        moves.push(format!("G1 {}{:.3}", axis, -distance));
+    }
+    moves.push("G90".to_string());
+    moves
+}
+
+/// Commanded and reported machine position at the end of a test
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Report {
+    axis: usize,
+    commanded: [f64; 3],
+    reported: [f64; 3],
+}
+
+/// A test waiting for the machine to finish
+#[derive(Debug, Clone, Copy)]
+struct Running {
+    start: [f64; 3],
+    started: Instant,
+    /// Time the moves take at the programmed feed, ignoring acceleration
+    duration: Duration,
+    /// Whether the machine was seen moving
+    moved: bool,
+}
+
+/// Dialog generating back-and-forth test moves
+#[derive(Debug, Clone)]
+pub struct AxisTestDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    axis: usize,
+    /// Length of each move (mm)
+    distance: f64,
+    /// Feed rate (mm/min)
+    feed_rate: f64,
+    /// Number of out-and-back cycles
+    repetitions: u32,
+    running: Option<Running>,
+    report: Option<Report>,
+}
+
+impl Default for AxisTestDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            axis: 0,
+            distance: 50.0,
+            feed_rate: 3000.0,
+            repetitions: 10,
+            running: None,
+            report: None,
+        }
+    }
+}
+
+impl AxisTestDialog {
+    /// Show the dialog, returning the moves to send when a test is started
+    ///
+    /// `position` is the reported machine position and `idle` whether the
+    /// machine is idle.
+    pub fn show(&mut self, ctx: &egui::Context, position: [f64; 3], idle: bool, connected: bool) -> Option<Vec<String>> {
+        self.track(position, idle);
+        let mut moves = None;
+        let mut open = self.open;
+
+        egui::Window::new("⇆ Axis Test Motion")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let running = self.running.is_some();
+                ui.add_enabled_ui(!running, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Axis:");
+                        for (index, name) in AXES.iter().enumerate() {
+                            ui.selectable_value(&mut self.axis, index, name.to_string());
+                        }
+                    });
+                    egui::Grid::new("axis_test_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Distance:");
+                            ui.add(egui::DragValue::new(&mut self.distance)
+                                .speed(1.0)
+                                .range(-1000.0..=1000.0)
+                                .suffix(" mm"))
+                                .on_hover_text("Negative to move toward minus first");
+                            ui.end_row();
+
+                            ui.label("Feed Rate:");
+                            ui.add(egui::DragValue::new(&mut self.feed_rate)
+                                .speed(50.0)
+                                .range(10.0..=50000.0)
+                                .suffix(" mm/min"));
+                            ui.end_row();
+
+                            ui.label("Repetitions:");
+                            ui.add(egui::DragValue::new(&mut self.repetitions).range(1..=1000));
+                            ui.end_row();
+                        });
+                });
+                ui.weak(format!(
+                    "{:.0} mm of travel, at least {:.0} s. Make sure the axis has room to move {:+.1} mm.",
+                    self.distance.abs() * 2.0 * self.repetitions as f64,
+                    self.duration().as_secs_f64(),
+                    self.distance
+                ));
+                ui.separator();
+
+                if let Some(test) = &self.running {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Testing... {:.0} s", test.started.elapsed().as_secs_f64()));
+                    });
+                    ctx.request_repaint_after(Duration::from_millis(250));
+                } else if ui.add_enabled(connected && idle, egui::Button::new("▶ Run Test")).clicked() {
+                    moves = Some(test_moves(self.axis, self.distance, self.feed_rate, self.repetitions));
+                    self.report = None;
+                    self.running = Some(Running {
+                        start: position,
+                        started: Instant::now(),
+                        duration: self.duration(),
+                        moved: false,
+                    });
+                }
+
+                if let Some(report) = &self.report {
+                    ui.separator();
+                    show_report(ui, report);
+                }
+            });
+
+        self.open = open;
+        moves
+    }
+
+    /// Time the moves take at the programmed feed
+    fn duration(&self) -> Duration {
+        let travel = self.distance.abs() * 2.0 * self.repetitions as f64;
+        Duration::from_secs_f64(travel / self.feed_rate.max(1.0) * 60.0)
+    }
+
+    /// Finish the running test once the machine has moved and is idle again
+    fn track(&mut self, position: [f64; 3], idle: bool) {
+        let Some(test) = self.running.as_mut() else {
+            return;
+        };
+        test.moved |= !idle;
+        // A short test can finish between two status reports
+        let finished = idle && (test.moved || test.started.elapsed() > test.duration + Duration::from_secs(2));
+        if finished {
+            self.report = Some(Report {
+                axis: self.axis,
+                commanded: test.start,
+                reported: position,
+            });
+            self.running = None;
+        }
+    }
+}
+
+/// Show the commanded and reported end positions
+fn show_report(ui: &mut egui::Ui, report: &Report) {
+    ui.strong(format!("{} axis test complete", AXES[report.axis]));
+    egui::Grid::new("axis_test_report")
+        .num_columns(4)
+        .striped(true)
+        .spacing([12.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("");
+            ui.strong("Commanded");
+            ui.strong("Reported");
+            ui.strong("Difference");
+            ui.end_row();
+            for (index, name) in AXES.iter().enumerate() {
+                let difference = report.reported[index] - report.commanded[index];
+                ui.label(name.to_string());
+                ui.monospace(format!("{:.3}", report.commanded[index]));
+                ui.monospace(format!("{:.3}", report.reported[index]));
+                ui.monospace(format!("{:+.3}", difference));
+                ui.end_row();
+            }
+        });
+    let worst = (0..3)
+        .map(|index| (report.reported[index] - report.commanded[index]).abs())
+        .fold(0.0, f64::max);
+    if worst < 0.001 {
+        ui.colored_label(egui::Color32::LIGHT_GREEN, "The machine reports it is back at the start.");
+        ui.weak("Check the start against a reference (indicator, edge or homing) to find steps lost by a stalled motor.");
+    } else {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!("The reported end position differs by {:.3} mm; the test may have been interrupted.", worst),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_return_to_start() {
+        assert_eq!(
+            test_moves(1, 25.0, 2000.0, 2),
+            ["G91", "G1 Y25.000 F2000", "G1 Y-25.000", "G1 Y25.000 F2000", "G1 Y-25.000", "G90"]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Backlash measurement wizard
+//!
+//! Walks through measuring the backlash of one axis with a dial indicator:
+//! preload the axis in the positive direction, zero the indicator, move out
+//! and back, then read how far the carriage stopped short. Several readings
+//! are averaged and the result stored in the machine profile.
+
+use crate::grbl::GrblCommand;
+use crate::settings::MachineProfile;
+
+const AXES: [char; 3] = ['X', 'Y', 'Z'];
+
+/// Stage of the measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    /// Waiting to preload the axis
+    Preload,
+    /// Preloaded; waiting for the indicator to be zeroed
+    Zero,
+    /// Reversed; waiting for the indicator reading
+    Read,
+}
+
+/// Request from the wizard to the application
+#[derive(Debug, Clone, PartialEq)]
+pub enum BacklashAction {
+    /// Send these moves in order
+    Move(Vec<String>),
+    /// The measured backlash was stored in the machine profile
+    Saved,
+}
+
+/// Dialog for measuring per-axis backlash
+#[derive(Debug, Clone)]
+pub struct BacklashWizard {
+    /// Whether the dialog is open
+    pub open: bool,
+    axis: usize,
+    /// Length of the out-and-back move (mm)
+    distance: f64,
+    /// Feed rate for the test moves (mm/min)
+    feed_rate: f64,
+    stage: Stage,
+    /// Indicator reading being entered (mm)
+    reading: f64,
+    /// Readings taken so far for the selected axis (mm)
+    readings: Vec<f64>,
+}
+
+impl Default for BacklashWizard {
+    fn default() -> Self {
+        Self {
+            open: false,
+            axis: 0,
+            distance: 2.0,
+            feed_rate: 200.0,
+            stage: Stage::Preload,
+            reading: 0.0,
+            readings: Vec::new(),
+        }
+    }
+}
+
+impl BacklashWizard {
+    /// Mean of the readings taken, if any
+    fn result(&self) -> Option<f64> {
+        (!self.readings.is_empty()).then(|| self.readings.iter().sum::<f64>() / self.readings.len() as f64)
+    }
+
+    /// Relative jog along the selected axis
+    fn jog(&self, distance: f64) -> String {
+        let mut offsets = [None; 3];
+        offsets[self.axis] = Some(distance);
+        let [x, y, z] = offsets;
+        GrblCommand::Jog { x, y, z, feed_rate: self.feed_rate }.format().trim().to_string()
+    }
+
+    /// Show the dialog, returning a request for the application
+    pub fn show(&mut self, ctx: &egui::Context, profile: &mut MachineProfile, connected: bool) -> Option<BacklashAction> {
+        let mut action = None;
+        let mut open = self.open;
+
+        egui::Window::new("↔ Backlash Measurement")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(340.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Axis:");
+                    for (index, name) in AXES.iter().enumerate() {
+                        if ui.selectable_label(self.axis == index, name.to_string()).clicked() && self.axis != index {
+                            self.axis = index;
+                            self.stage = Stage::Preload;
+                            self.readings.clear();
+                        }
+                    }
+                });
+                egui::Grid::new("backlash_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Test Distance:");
+                        ui.add(egui::DragValue::new(&mut self.distance)
+                            .speed(0.1)
+                            .range(0.5..=20.0)
+                            .suffix(" mm"));
+                        ui.end_row();
+
+                        ui.label("Feed Rate:");
+                        ui.add(egui::DragValue::new(&mut self.feed_rate)
+                            .speed(5.0)
+                            .range(10.0..=2000.0)
+                            .suffix(" mm/min"));
+                        ui.end_row();
+
+                        ui.label("Stored Backlash:");
+                        ui.label(format!("{:.3} mm", profile.backlash[self.axis]));
+                        ui.end_row();
+                    });
+                ui.separator();
+
+                let axis = AXES[self.axis];
+                match self.stage {
+                    Stage::Preload => {
+                        ui.label(format!(
+                            "1. Mount a dial indicator against the {axis} axis, with room to travel {:.1} mm either way.",
+                            self.distance
+                        ));
+                        if ui.add_enabled(connected, egui::Button::new("▶ Preload")).clicked() {
+                            action = Some(BacklashAction::Move(vec![self.jog(-self.distance), self.jog(self.distance)]));
+                            self.stage = Stage::Zero;
+                        }
+                    }
+                    Stage::Zero => {
+                        ui.label(format!("2. The {axis} axis was moved in the positive direction. Zero the indicator."));
+                        if ui.add_enabled(connected, egui::Button::new("▶ Move Out and Back")).clicked() {
+                            action = Some(BacklashAction::Move(vec![self.jog(self.distance), self.jog(-self.distance)]));
+                            self.stage = Stage::Read;
+                        }
+                    }
+                    Stage::Read => {
+                        ui.label("3. Enter how far the indicator reads from zero.");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut self.reading)
+                                .speed(0.001)
+                                .range(-5.0..=5.0)
+                                .fixed_decimals(3)
+                                .suffix(" mm"));
+                            if ui.button("Record").clicked() {
+                                self.readings.push(self.reading.abs());
+                                self.reading = 0.0;
+                                self.stage = Stage::Preload;
+                            }
+                        });
+                    }
+                }
+
+                ui.separator();
+                match self.result() {
+                    Some(result) => {
+                        ui.label(format!(
+                            "{} reading(s), mean backlash {:.3} mm. Repeat to improve the average.",
+                            self.readings.len(),
+                            result
+                        ));
+                        ui.horizontal(|ui| {
+                            if ui.button("💾 Save to Profile").clicked() {
+                                profile.backlash[self.axis] = result;
+                                action = Some(BacklashAction::Saved);
+                            }
+                            if ui.button("Discard").clicked() {
+                                self.readings.clear();
+                            }
+                        });
+                    }
+                    None => {
+                        ui.weak("No readings yet");
+                    }
+                }
+                ui.checkbox(&mut profile.compensate_backlash, "Compensate backlash when streaming programs");
+            });
+
+        self.open = open;
+        action
+    }
+}
@@ -0,0 +1,199 @@
+//! Feeds, speeds and job cost calculator
+//!
+//! Suggests spindle speed and feed rate for a tool in a material, and
+//! estimates the cost of running the loaded program.
+
+use crate::parser::JobEstimate;
+use crate::settings::JobRecord;
+use crate::utils::machining::{FeedsAndSpeeds, JobCost, MATERIAL_PRESETS};
+
+/// Dialog for the feeds and speeds / job cost calculator
+#[derive(Debug, Clone)]
+pub struct CalculatorDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// Selected material preset (index into `MATERIAL_PRESETS`)
+    pub material: usize,
+    /// Tool diameter (mm)
+    pub tool_diameter: f64,
+    /// Number of flutes
+    pub flutes: u32,
+    /// Spindle speed limit (RPM)
+    pub max_rpm: f64,
+    /// Rate used to time rapid moves (mm/min)
+    pub rapid_rate: f64,
+    /// Cost rates
+    pub cost: JobCost,
+}
+
+impl Default for CalculatorDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            material: 0,
+            tool_diameter: 6.0,
+            flutes: 2,
+            max_rpm: 24000.0,
+            rapid_rate: 3000.0,
+            cost: JobCost {
+                hourly_rate: 50.0,
+                material_cost: 0.0,
+            },
+        }
+    }
+}
+
+impl CalculatorDialog {
+    /// Open the dialog, restoring values from a saved record
+    pub fn open_with(&mut self, record: Option<&JobRecord>) {
+        if let Some(record) = record {
+            if let Some(index) = MATERIAL_PRESETS.iter().position(|m| m.name == record.material) {
+                self.material = index;
+            }
+            if record.tool_diameter > 0.0 {
+                self.tool_diameter = record.tool_diameter;
+            }
+            if record.flutes > 0 {
+                self.flutes = record.flutes;
+            }
+            self.cost = JobCost {
+                hourly_rate: record.hourly_rate,
+                material_cost: record.material_cost,
+            };
+        }
+        self.open = true;
+    }
+
+    /// Feeds and speeds for the current dialog values
+    pub fn feeds_and_speeds(&self) -> FeedsAndSpeeds {
+        let preset = &MATERIAL_PRESETS[self.material.min(MATERIAL_PRESETS.len() - 1)];
+        FeedsAndSpeeds::from_preset(preset, self.tool_diameter, self.flutes, self.max_rpm)
+    }
+
+    /// Build a record of the current results for a job estimate
+    pub fn record(&self, estimate: &JobEstimate) -> JobRecord {
+        let calc = self.feeds_and_speeds();
+        let machine_time = estimate.total_time();
+        JobRecord {
+            material: MATERIAL_PRESETS[self.material.min(MATERIAL_PRESETS.len() - 1)].name.to_string(),
+            tool_diameter: self.tool_diameter,
+            flutes: self.flutes,
+            spindle_speed: calc.rpm(),
+            feed_rate: calc.feed_rate(),
+            chip_load: calc.chip_load,
+            machine_time: machine_time.as_secs_f64(),
+            hourly_rate: self.cost.hourly_rate,
+            material_cost: self.cost.material_cost,
+            total_cost: self.cost.total(machine_time),
+        }
+    }
+
+    /// Show the dialog, returning a record when the user saves the results
+    ///
+    /// `estimate_for` is called with the rapid rate to estimate the loaded
+    /// program; it returns `None` when no program is loaded.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        estimate_for: impl Fn(f64) -> Option<JobEstimate>,
+    ) -> Option<JobRecord> {
+        let mut open = self.open;
+        let mut save = false;
+
+        egui::Window::new("🧮 Feeds & Speeds / Job Cost")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Feeds & Speeds");
+                egui::Grid::new("calculator_tool_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Material:");
+                        egui::ComboBox::from_id_source("calculator_material")
+                            .selected_text(MATERIAL_PRESETS[self.material].name)
+                            .show_ui(ui, |ui| {
+                                for (index, preset) in MATERIAL_PRESETS.iter().enumerate() {
+                                    ui.selectable_value(&mut self.material, index, preset.name);
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Tool diameter:");
+                        ui.add(egui::DragValue::new(&mut self.tool_diameter).speed(0.1).range(0.1..=50.0).suffix(" mm"));
+                        ui.end_row();
+
+                        ui.label("Flutes:");
+                        ui.add(egui::DragValue::new(&mut self.flutes).range(1..=8));
+                        ui.end_row();
+
+                        ui.label("Max spindle speed:");
+                        ui.add(egui::DragValue::new(&mut self.max_rpm).speed(100.0).range(100.0..=100000.0).suffix(" RPM"));
+                        ui.end_row();
+                    });
+
+                let calc = self.feeds_and_speeds();
+                ui.add_space(5.0);
+                ui.label(format!("Spindle speed: {:.0} RPM", calc.rpm()));
+                ui.label(format!("Chip load: {:.3} mm/tooth", calc.chip_load));
+                ui.label(format!("Feed rate: {:.0} mm/min", calc.feed_rate()));
+
+                ui.separator();
+                ui.heading("Job Cost");
+                egui::Grid::new("calculator_cost_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Rapid rate:");
+                        ui.add(egui::DragValue::new(&mut self.rapid_rate).speed(100.0).range(1.0..=50000.0).suffix(" mm/min"));
+                        ui.end_row();
+
+                        ui.label("Hourly rate:");
+                        ui.add(egui::DragValue::new(&mut self.cost.hourly_rate).speed(1.0).range(0.0..=10000.0));
+                        ui.end_row();
+
+                        ui.label("Material cost:");
+                        ui.add(egui::DragValue::new(&mut self.cost.material_cost).speed(0.5).range(0.0..=100000.0));
+                        ui.end_row();
+                    });
+
+                let estimate = estimate_for(self.rapid_rate);
+                ui.add_space(5.0);
+                match &estimate {
+                    Some(estimate) => {
+                        let time = estimate.total_time();
+                        ui.label(format!(
+                            "Machine time: {} (cutting {}, rapids {})",
+                            format_duration(time.as_secs()),
+                            format_duration(estimate.cutting_time.as_secs()),
+                            format_duration(estimate.rapid_time.as_secs()),
+                        ));
+                        ui.label(format!("Machine cost: {:.2}", self.cost.machine_cost(time)));
+                        ui.strong(format!("Total cost: {:.2}", self.cost.total(time)));
+                    }
+                    None => {
+                        ui.label("Load a program to estimate job cost.");
+                    }
+                }
+
+                ui.separator();
+                ui.add_enabled_ui(estimate.is_some(), |ui| {
+                    if ui.button("💾 Save to File Metadata").clicked() {
+                        save = true;
+                    }
+                });
+            });
+
+        self.open = open;
+        if save {
+            let estimate = estimate_for(self.rapid_rate)?;
+            return Some(self.record(&estimate));
+        }
+        None
+    }
+}
+
+/// Format seconds as H:MM:SS
+fn format_duration(secs: u64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
@@ -0,0 +1,211 @@
+//! Status chart panel
+//!
+//! Plots feed rate, spindle speed, overrides and planner buffer fill taken
+//! from status reports, to help diagnose stuttering and tune acceleration.
+
+use crate::grbl::GrblStatus;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Selectable time windows (seconds)
+const WINDOWS: [u64; 4] = [30, 60, 120, 300];
+
+/// Samples kept: the longest window at 20 reports per second
+const MAX_SAMPLES: usize = 300 * 20;
+
+/// Height of each plot
+const PLOT_HEIGHT: f32 = 70.0;
+
+/// Values taken from one status report
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// Seconds since the chart started
+    time: f64,
+    feed: Option<f64>,
+    spindle: Option<f64>,
+    /// Feed, rapid and spindle overrides (%)
+    overrides: Option<[u8; 3]>,
+    /// Planner buffer fill (%)
+    planner_fill: Option<f64>,
+}
+
+/// One line in a plot
+struct Series {
+    name: &'static str,
+    color: egui::Color32,
+    points: Vec<[f64; 2]>,
+}
+
+/// Panel charting status report values over time
+#[derive(Debug)]
+pub struct StatusChart {
+    /// Whether the panel is open
+    pub open: bool,
+    samples: VecDeque<Sample>,
+    start: Instant,
+    window_secs: u64,
+    paused: bool,
+    /// Largest number of free planner blocks seen, taken as the buffer size
+    planner_capacity: u8,
+    /// Overrides are only reported every few status reports
+    last_overrides: Option<[u8; 3]>,
+}
+
+impl Default for StatusChart {
+    fn default() -> Self {
+        Self {
+            open: false,
+            samples: VecDeque::new(),
+            start: Instant::now(),
+            window_secs: 60,
+            paused: false,
+            planner_capacity: 0,
+            last_overrides: None,
+        }
+    }
+}
+
+impl StatusChart {
+    /// Record the values of a status report
+    pub fn record(&mut self, status: &GrblStatus) {
+        if self.paused {
+            return;
+        }
+
+        if let (Some(feed), Some(rapid), Some(spindle)) =
+            (status.feed_override, status.rapid_override, status.spindle_override)
+        {
+            self.last_overrides = Some([feed, rapid, spindle]);
+        }
+
+        let planner_fill = status.buffer.map(|(available, _)| {
+            self.planner_capacity = self.planner_capacity.max(available);
+            if self.planner_capacity == 0 {
+                100.0
+            } else {
+                f64::from(self.planner_capacity - available) * 100.0 / f64::from(self.planner_capacity)
+            }
+        });
+
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            time: self.start.elapsed().as_secs_f64(),
+            feed: status.feed_rate,
+            spindle: status.spindle_speed,
+            overrides: self.last_overrides,
+            planner_fill,
+        });
+    }
+
+    /// Forget all samples
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.planner_capacity = 0;
+        self.last_overrides = None;
+    }
+
+    /// Show the panel
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new("📉 Status Chart")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Window:");
+                    for window in WINDOWS {
+                        ui.selectable_value(&mut self.window_secs, window, format!("{} s", window));
+                    }
+                    ui.separator();
+                    ui.toggle_value(&mut self.paused, "⏸ Pause");
+                    if ui.button("Clear").clicked() {
+                        self.clear();
+                    }
+                });
+                ui.add_space(4.0);
+
+                let now = if self.paused {
+                    self.samples.back().map_or(0.0, |s| s.time)
+                } else {
+                    self.start.elapsed().as_secs_f64()
+                };
+                let from = now - self.window_secs as f64;
+                let visible: Vec<Sample> = self.samples.iter().filter(|s| s.time >= from).copied().collect();
+                let series = |name, color, value: fn(&Sample) -> Option<f64>| Series {
+                    name,
+                    color,
+                    points: visible.iter().filter_map(|s| value(s).map(|v| [s.time, v])).collect(),
+                };
+
+                plot(ui, "Feed rate (mm/min)", [from, now], &[
+                    series("Feed", egui::Color32::LIGHT_BLUE, |s| s.feed),
+                ]);
+                plot(ui, "Spindle speed (RPM)", [from, now], &[
+                    series("Spindle", egui::Color32::LIGHT_RED, |s| s.spindle),
+                ]);
+                plot(ui, "Overrides (%)", [from, now], &[
+                    series("Feed", egui::Color32::LIGHT_BLUE, |s| s.overrides.map(|o| f64::from(o[0]))),
+                    series("Rapid", egui::Color32::YELLOW, |s| s.overrides.map(|o| f64::from(o[1]))),
+                    series("Spindle", egui::Color32::LIGHT_RED, |s| s.overrides.map(|o| f64::from(o[2]))),
+                ]);
+                plot(ui, "Planner buffer fill (%)", [from, now], &[
+                    series("Planner", egui::Color32::LIGHT_GREEN, |s| s.planner_fill),
+                ]);
+            });
+        self.open = open;
+
+        if self.open && !self.paused {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+    }
+}
+
+/// Draw a titled line plot spanning `time` on the x axis
+///
+/// The y axis starts at zero and scales to the largest visible value.
+fn plot(ui: &mut egui::Ui, title: &str, time: [f64; 2], series: &[Series]) {
+    ui.horizontal(|ui| {
+        ui.strong(title);
+        for line in series {
+            if let Some([_, value]) = line.points.last() {
+                ui.colored_label(line.color, format!("{} {:.0}", line.name, value));
+            }
+        }
+    });
+
+    let y_max = series
+        .iter()
+        .flat_map(|line| line.points.iter().map(|[_, v]| *v))
+        .fold(0.0, f64::max)
+        .max(1.0)
+        * 1.1;
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), PLOT_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{:.0}", y_max),
+        egui::FontId::monospace(10.0),
+        visuals.weak_text_color(),
+    );
+
+    let span = (time[1] - time[0]).max(f64::EPSILON);
+    let to_screen = |[t, v]: [f64; 2]| {
+        egui::pos2(
+            rect.left() + ((t - time[0]) / span) as f32 * rect.width(),
+            rect.bottom() - (v / y_max) as f32 * rect.height(),
+        )
+    };
+    for line in series {
+        if line.points.len() > 1 {
+            let points = line.points.iter().copied().map(to_screen).collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, line.color)));
+        }
+    }
+    ui.add_space(6.0);
+}
@@ -0,0 +1,79 @@
+//! Pre-run checklist dialog
+//!
+//! Asks the operator to confirm each item of the machine's checklist, such
+//! as the stock being clamped, before a program starts. Once every item is
+//! ticked the program can be started, optionally without asking again for
+//! the same file.
+
+/// Action requested from the checklist dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecklistAction {
+    /// Start the program, skipping the checklist for this file from now on if set
+    Start {
+        /// Don't ask for this file again
+        skip_for_file: bool,
+    },
+    /// Don't start the program
+    Cancel,
+}
+
+/// Dialog confirming the pre-run checklist
+#[derive(Debug, Clone, Default)]
+pub struct ChecklistDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// Ticked items
+    checked: Vec<bool>,
+    /// Don't ask for this file again
+    skip_for_file: bool,
+}
+
+impl ChecklistDialog {
+    /// Open the dialog with every item unticked
+    pub fn ask(&mut self, items: usize) {
+        self.checked = vec![false; items];
+        self.skip_for_file = false;
+        self.open = true;
+    }
+
+    /// Show the dialog
+    ///
+    /// `has_file` offers skipping the checklist for the program's file.
+    pub fn show(&mut self, ctx: &egui::Context, items: &[&str], has_file: bool) -> Option<ChecklistAction> {
+        self.checked.resize(items.len(), false);
+        let mut action = None;
+
+        egui::Window::new("✅ Pre-Run Checklist")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Confirm each item before the program starts:");
+                ui.add_space(5.0);
+                for (item, checked) in items.iter().zip(&mut self.checked) {
+                    ui.checkbox(checked, *item);
+                }
+                ui.add_space(5.0);
+                ui.add_enabled(
+                    has_file,
+                    egui::Checkbox::new(&mut self.skip_for_file, "Don't ask for this file again"),
+                );
+                ui.separator();
+
+                let ready = self.checked.iter().all(|checked| *checked);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(ready, egui::Button::new("▶ Start Program")).clicked() {
+                        action = Some(ChecklistAction::Start { skip_for_file: self.skip_for_file && has_file });
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Some(ChecklistAction::Cancel);
+                    }
+                });
+            });
+
+        if action.is_some() {
+            self.open = false;
+        }
+        action
+    }
+}
@@ -0,0 +1,272 @@
+//! Command palette
+//!
+//! Ctrl+P opens a search box over the application's actions: the fixed
+//! commands registered by the app, the view presets and the entries of the
+//! user command and script libraries. Typing narrows the list with a fuzzy
+//! match on the label, the arrow keys move the highlight and Enter runs the
+//! highlighted command.
+
+use crate::renderer::ViewPreset;
+
+/// Action run from the palette
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteCommand {
+    /// Start a new G-Code document
+    NewDocument,
+    /// Open a G-Code file
+    OpenFile,
+    /// Save the current file
+    SaveFile,
+    /// Save the current file under a new name
+    SaveFileAs,
+    /// Connect to the selected port
+    Connect,
+    /// Disconnect from the controller
+    Disconnect,
+    /// Look for GRBL on the serial ports
+    ScanForDevices,
+    /// Run or resume the program
+    RunProgram,
+    /// Feed hold
+    PauseProgram,
+    /// Stop the program
+    StopProgram,
+    /// Rewind the program to the start
+    ResetProgram,
+    /// Homing cycle
+    Home,
+    /// Clear an alarm
+    Unlock,
+    /// Zero one axis of the work coordinates
+    ZeroAxis(char),
+    /// Zero all axes of the work coordinates
+    ZeroAll,
+    /// Find in the editor
+    Find,
+    /// Toggle a breakpoint on the editor cursor line
+    ToggleBreakpoint,
+    /// Reset the camera
+    ResetCamera,
+    /// Fit the toolpath in the view
+    ZoomToFit,
+    /// Look at the toolpath from a preset direction
+    ViewPreset(ViewPreset),
+    /// Show or hide the console
+    ToggleConsole,
+    /// Open the full-window run screen
+    RunScreen,
+    /// Switch to the touch-screen pendant layout
+    PendantMode,
+    /// Open the settings
+    Settings,
+    /// List the keyboard shortcuts
+    Shortcuts,
+    /// Open the GRBL reference
+    Reference,
+    /// Open the program statistics
+    Statistics,
+    /// Open the compatibility report
+    Compatibility,
+    /// Open the edge finder
+    EdgeFinder,
+    /// Open the fixture library
+    Fixtures,
+    /// Open the maintenance reminders
+    Maintenance,
+    /// Open the plugin manager
+    Plugins,
+    /// Open the axis test motion dialog
+    AxisTest,
+    /// Run a user command by name
+    UserCommand(String),
+    /// Open a script of the library in the editor
+    EditScript(String),
+}
+
+/// A command listed in the palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    /// Text searched and shown
+    pub label: String,
+    /// Keyboard shortcut shown next to the label
+    pub shortcut: Option<String>,
+    /// Action to run
+    pub command: PaletteCommand,
+}
+
+impl PaletteEntry {
+    /// Entry without a shortcut
+    pub fn new(label: impl Into<String>, command: PaletteCommand) -> Self {
+        Self {
+            label: label.into(),
+            shortcut: None,
+            command,
+        }
+    }
+
+    /// Show a shortcut hint
+    pub fn with_shortcut(mut self, keys: impl Into<String>) -> Self {
+        self.shortcut = Some(keys.into());
+        self
+    }
+}
+
+/// Score how well a query matches a label, higher being better
+///
+/// Every character of the query has to appear in the label in order,
+/// ignoring case and spaces in the query. Characters that start a word or
+/// follow the previous match score extra, and the best-scoring placement
+/// is used, so "run" prefers "Program: Run" over "Return to Zero".
+pub fn fuzzy_score(query: &str, label: &str) -> Option<u32> {
+    let text: Vec<char> = label.to_lowercase().chars().collect();
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let word_start = |at: usize| at == 0 || !text[at - 1].is_alphanumeric();
+
+    // Best score with the latest query character matched at each position
+    let mut best: Vec<Option<u32>> = vec![Some(0); 1];
+    for (index, &wanted) in query.iter().enumerate() {
+        let mut next = vec![None; text.len()];
+        for (at, &c) in text.iter().enumerate() {
+            if c != wanted {
+                continue;
+            }
+            let bonus = 1 + if word_start(at) { 10 } else { 0 };
+            let score = if index == 0 {
+                Some(bonus)
+            } else {
+                (0..at)
+                    .filter_map(|before| best[before].map(|score| score + bonus + if before + 1 == at { 5 } else { 0 }))
+                    .max()
+            };
+            next[at] = score;
+        }
+        best = next;
+    }
+    best.into_iter().flatten().max()
+}
+
+/// Search box over the registered commands
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    /// Whether the palette is open
+    pub open: bool,
+    query: String,
+    /// Highlighted row of the filtered list
+    selected: usize,
+    /// Focus the search box on the next frame
+    focus: bool,
+}
+
+impl CommandPalette {
+    /// Open the palette with an empty search, or close it
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.query.clear();
+            self.selected = 0;
+            self.focus = true;
+        }
+    }
+
+    /// Show the palette, returning the command chosen
+    pub fn show(&mut self, ctx: &egui::Context, entries: &[PaletteEntry]) -> Option<PaletteCommand> {
+        if !self.open {
+            return None;
+        }
+
+        let mut matches: Vec<(u32, &PaletteEntry)> = entries
+            .iter()
+            .filter_map(|entry| fuzzy_score(&self.query, &entry.label).map(|score| (score, entry)))
+            .collect();
+        // Stable, so equal scores keep the registration order
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+        let last = matches.len().saturating_sub(1);
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(last);
+
+        let mut chosen = enter
+            .then(|| matches.get(self.selected).map(|(_, entry)| entry.command.clone()))
+            .flatten();
+
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size([440.0, 0.0])
+            .show(ctx, |ui| {
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command...")
+                        .desired_width(f32::INFINITY),
+                );
+                if self.focus {
+                    search.request_focus();
+                    self.focus = false;
+                }
+                if search.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.weak("No matching command");
+                    }
+                    for (index, (_, entry)) in matches.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let selected = index == self.selected;
+                            let row = ui.selectable_label(selected, &entry.label);
+                            if selected && (up || down) {
+                                row.scroll_to_me(None);
+                            }
+                            if row.clicked() {
+                                chosen = Some(entry.command.clone());
+                            }
+                            if let Some(keys) = &entry.shortcut {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.weak(keys);
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+
+        if chosen.is_some() {
+            self.open = false;
+        }
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "Open G-Code"), Some(0));
+        assert_eq!(fuzzy_score("zoom", "Zero X"), None);
+        assert!(fuzzy_score("zx", "Machine: Zero X").is_some());
+        assert!(fuzzy_score("run", "Program: Run") > fuzzy_score("run", "Machine: Return to Zero"));
+        assert!(fuzzy_score("ZERO", "Machine: Zero All") > fuzzy_score("ZERO", "View: Zoom to Fit Remote"));
+    }
+}
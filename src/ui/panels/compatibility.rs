@@ -0,0 +1,120 @@
+//! Compatibility report panel
+//!
+//! Lists the words of the program GRBL does not support, grouped by word
+//! with the flavor they come from. Selected words can be stripped from the
+//! program, or skipped automatically whenever a program is streamed.
+
+use crate::parser::{strip_words, CompatibilityReport};
+
+/// Action requested from the compatibility panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompatibilityAction {
+    /// Replace the program with this stripped one
+    Strip(String),
+    /// Words to skip while streaming changed
+    SkipWhenStreaming(Vec<String>),
+}
+
+/// Panel showing the compatibility report of the program in the editor
+#[derive(Debug, Clone, Default)]
+pub struct CompatibilityPanel {
+    /// Whether the panel is open
+    pub open: bool,
+    /// Words selected for stripping
+    selected: Vec<String>,
+    /// Program text and report of the last refresh
+    cache: Option<(String, CompatibilityReport)>,
+}
+
+impl CompatibilityPanel {
+    /// Report for the program, reusing the last one when nothing changed
+    fn report(&mut self, program: &str) -> &CompatibilityReport {
+        let stale = !matches!(&self.cache, Some((text, _)) if text == program);
+        if stale {
+            self.cache = Some((program.to_string(), CompatibilityReport::check(program)));
+        }
+        &self.cache.as_ref().expect("report was just computed").1
+    }
+
+    /// Show the panel
+    ///
+    /// `skipped` are the words currently skipped while streaming.
+    pub fn show(&mut self, ctx: &egui::Context, program: &str, skipped: &[String]) -> Option<CompatibilityAction> {
+        let mut open = self.open;
+        let mut action = None;
+
+        egui::Window::new("🧩 Compatibility Report")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                let summary = self.report(program).summary();
+                if summary.is_empty() {
+                    ui.weak("The program only uses words GRBL supports");
+                    return;
+                }
+                ui.label("These words are not supported by GRBL and would stop the job with error:20.");
+                ui.separator();
+
+                let mut skip: Vec<String> = skipped.to_vec();
+                egui::Grid::new("compatibility_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("Word");
+                        ui.strong("Flavor");
+                        ui.strong("Lines");
+                        ui.strong("Skip when streaming");
+                        ui.end_row();
+                        for entry in &summary {
+                            let mut selected = self.selected.contains(&entry.word);
+                            if ui.checkbox(&mut selected, "").changed() {
+                                if selected {
+                                    self.selected.push(entry.word.clone());
+                                } else {
+                                    self.selected.retain(|word| *word != entry.word);
+                                }
+                            }
+                            ui.monospace(&entry.word).on_hover_text(entry.description);
+                            ui.label(entry.flavor.to_string());
+                            let lines: Vec<String> = entry.lines.iter().take(5).map(|line| (line + 1).to_string()).collect();
+                            let more = if entry.lines.len() > 5 { ", …" } else { "" };
+                            ui.label(format!("{}{}", lines.join(", "), more));
+                            let mut skipping = skip.contains(&entry.word);
+                            if ui.checkbox(&mut skipping, "").changed() {
+                                if skipping {
+                                    skip.push(entry.word.clone());
+                                } else {
+                                    skip.retain(|word| *word != entry.word);
+                                }
+                                action = Some(CompatibilityAction::SkipWhenStreaming(skip.clone()));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Select All").clicked() {
+                        self.selected = summary.iter().map(|entry| entry.word.clone()).collect();
+                    }
+                    let strip = egui::Button::new("✂ Strip Selected");
+                    if ui
+                        .add_enabled(!self.selected.is_empty(), strip)
+                        .on_hover_text("Remove the words and their parameters from the program")
+                        .clicked()
+                    {
+                        action = Some(CompatibilityAction::Strip(strip_words(program, &self.selected)));
+                        self.selected.clear();
+                    }
+                });
+            });
+
+        self.open = open;
+        if !self.open {
+            self.cache = None;
+        }
+        action
+    }
+}
@@ -0,0 +1,109 @@
+//! Diagnostics panel
+//!
+//! Shows link throughput, latency and error counters for tuning the
+//! connection.
+
+use crate::connection::LinkMetrics;
+
+/// Panel showing connection diagnostics
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsPanel {
+    /// Whether the panel is open
+    pub open: bool,
+}
+
+impl DiagnosticsPanel {
+    /// Show the panel, returning true when the user asks to reset the metrics
+    pub fn show(&mut self, ctx: &egui::Context, metrics: &LinkMetrics, connected: bool) -> bool {
+        let mut reset = false;
+
+        egui::Window::new("📈 Diagnostics")
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if !connected {
+                    ui.colored_label(egui::Color32::GRAY, "Not connected - showing last values");
+                    ui.add_space(5.0);
+                }
+
+                ui.heading("Link");
+                egui::Grid::new("diagnostics_link_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Throughput:");
+                        ui.label(format!("{:.1} lines/s", metrics.lines_per_sec));
+                        ui.end_row();
+
+                        ui.label("Avg ack latency:");
+                        ui.label(format!("{:.1} ms", metrics.avg_ack_latency_ms));
+                        ui.end_row();
+
+                        ui.label("Status round trip:");
+                        ui.label(format!("{:.1} ms", metrics.status_rtt_ms));
+                        ui.end_row();
+
+                        ui.label("Status interval:");
+                        ui.label(format!("{} ms", metrics.status_interval_ms));
+                        ui.end_row();
+
+                        ui.label("Bytes sent / received:");
+                        ui.label(format!("{} / {}", metrics.bytes_sent, metrics.bytes_received));
+                        ui.end_row();
+
+                        ui.label("Serial errors:");
+                        error_count(ui, metrics.serial_errors);
+                        ui.end_row();
+
+                        ui.label("Unparsed lines:");
+                        error_count(ui, metrics.parse_errors);
+                        ui.end_row();
+                    });
+
+                ui.add_space(5.0);
+                ui.heading("Command Queue");
+                egui::Grid::new("diagnostics_queue_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        let queue = &metrics.queue;
+
+                        ui.label("Queued:");
+                        ui.label(format!("{} ({} waiting)", queue.total_queued, queue.current_length));
+                        ui.end_row();
+
+                        ui.label("Sent / completed:");
+                        ui.label(format!("{} / {}", queue.total_sent, queue.total_completed));
+                        ui.end_row();
+
+                        ui.label("Failed:");
+                        error_count(ui, queue.total_failed);
+                        ui.end_row();
+
+                        ui.label("Timed out:");
+                        error_count(ui, queue.total_timeouts);
+                        ui.end_row();
+
+                        ui.label("Avg execution time:");
+                        ui.label(format!("{:.1} ms", queue.avg_execution_time_ms));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                if ui.button("🔄 Reset").clicked() {
+                    reset = true;
+                }
+            });
+
+        reset
+    }
+}
+
+/// Show an error counter, highlighted when non-zero
+fn error_count(ui: &mut egui::Ui, count: u64) {
+    if count > 0 {
+        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), count.to_string());
+    } else {
+        ui.label("0");
+    }
+}
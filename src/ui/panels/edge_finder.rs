@@ -0,0 +1,129 @@
+//! Edge finder dialog
+//!
+//! Guides an X/Y edge finding cycle: pick the stock edge on a diagram,
+//! position the probe beside it and start the cycle. The work offset is set
+//! from the trigger position, compensated for the tip diameter.
+
+use crate::grbl::StockEdge;
+use crate::settings::MachineProfile;
+
+/// Dialog for finding a stock edge
+#[derive(Debug, Clone)]
+pub struct EdgeFinderDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// Edge selected for probing
+    pub edge: StockEdge,
+}
+
+impl Default for EdgeFinderDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            edge: StockEdge::Left,
+        }
+    }
+}
+
+impl EdgeFinderDialog {
+    /// Show the dialog, returning the edge to probe when the cycle is started
+    ///
+    /// `busy` disables the start button while a probe cycle is in progress.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        profile: &mut MachineProfile,
+        connected: bool,
+        busy: bool,
+    ) -> Option<StockEdge> {
+        let mut start = None;
+
+        egui::Window::new("⌖ Edge Finder")
+            .open(&mut self.open)
+            .resizable(false)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for edge in StockEdge::ALL {
+                        ui.selectable_value(&mut self.edge, edge, edge.to_string());
+                    }
+                });
+
+                draw_diagram(ui, self.edge);
+
+                ui.label(format!(
+                    "Jog the probe beside the {} edge, below the top of the stock, then start the cycle.",
+                    self.edge.to_string().to_lowercase()
+                ));
+                ui.add_space(5.0);
+
+                egui::Grid::new("edge_finder_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Tip Diameter:");
+                        ui.add(egui::DragValue::new(&mut profile.probe_tip_diameter)
+                            .speed(0.01)
+                            .range(0.0..=25.0)
+                            .suffix(" mm"));
+                        ui.end_row();
+
+                        ui.label("Probe Distance:");
+                        ui.add(egui::DragValue::new(&mut profile.edge_probe_distance)
+                            .speed(0.5)
+                            .range(1.0..=100.0)
+                            .suffix(" mm"));
+                        ui.end_row();
+
+                        ui.label("Feed Rate:");
+                        ui.add(egui::DragValue::new(&mut profile.probe_feed_rate)
+                            .speed(5.0)
+                            .range(1.0..=2000.0)
+                            .suffix(" mm/min"));
+                        ui.end_row();
+                    });
+
+                ui.add_space(5.0);
+                let label = if busy { "Probing..." } else { "⌖ Probe Edge" };
+                if ui.add_enabled(connected && !busy, egui::Button::new(label)).clicked() {
+                    start = Some(self.edge);
+                }
+            });
+
+        start
+    }
+}
+
+/// Draw the stock with the selected edge and probe approach
+fn draw_diagram(ui: &mut egui::Ui, edge: StockEdge) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(220.0, 150.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let stock = egui::Rect::from_center_size(rect.center(), egui::vec2(120.0, 80.0));
+    painter.rect_filled(stock, 2.0, egui::Color32::from_rgb(140, 100, 60));
+
+    let highlight = egui::Stroke::new(3.0, egui::Color32::YELLOW);
+    let (edge_line, probe_at) = match edge {
+        StockEdge::Left => ([stock.left_top(), stock.left_bottom()], egui::pos2(stock.left() - 30.0, stock.center().y)),
+        StockEdge::Right => ([stock.right_top(), stock.right_bottom()], egui::pos2(stock.right() + 30.0, stock.center().y)),
+        // Y+ is up on screen
+        StockEdge::Front => ([stock.left_bottom(), stock.right_bottom()], egui::pos2(stock.center().x, stock.bottom() + 25.0)),
+        StockEdge::Back => ([stock.left_top(), stock.right_top()], egui::pos2(stock.center().x, stock.top() - 25.0)),
+    };
+    painter.line_segment(edge_line, highlight);
+
+    let probe_color = egui::Color32::LIGHT_BLUE;
+    painter.circle_filled(probe_at, 6.0, probe_color);
+    let travel = match edge.axis() {
+        'X' => egui::vec2(edge.direction() as f32 * 16.0, 0.0),
+        _ => egui::vec2(0.0, -edge.direction() as f32 * 14.0),
+    };
+    painter.arrow(probe_at, travel, egui::Stroke::new(2.0, probe_color));
+
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "Stock",
+        egui::FontId::proportional(14.0),
+        egui::Color32::WHITE,
+    );
+}
@@ -0,0 +1,237 @@
+//! Error presenter
+//!
+//! Shows errors as short-lived toasts in the corner of the window, with a
+//! details dialog giving the error code, category and a recovery hint.
+//! Notices that need no action, such as settings being reloaded, share
+//! the toast corner but are not kept.
+//! Errors carrying a GRBL code, and connection errors, link to the GRBL
+//! reference.
+
+use crate::grbl::{ReferenceEntry, ReferenceTopic};
+use crate::utils::{Error, ErrorCategory};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+/// Most toasts shown at once
+const MAX_TOASTS: usize = 3;
+
+/// Number of errors kept for the details dialog
+const HISTORY_LIMIT: usize = 50;
+
+/// An error as presented to the user
+#[derive(Debug, Clone)]
+struct PresentedError {
+    id: u64,
+    code: String,
+    category: ErrorCategory,
+    message: String,
+    hint: Option<&'static str>,
+    /// Reference section and code that explain the error
+    reference: Option<(ReferenceTopic, Option<u32>)>,
+    time: String,
+    shown_at: Instant,
+    dismissed: bool,
+}
+
+/// Central place the UI reports errors to
+#[derive(Debug, Default)]
+pub struct ErrorPresenter {
+    history: VecDeque<PresentedError>,
+    /// Informational toasts and when they were shown
+    notices: VecDeque<(String, Instant)>,
+    next_id: u64,
+    /// Error shown in the details dialog
+    selected: Option<u64>,
+    /// Whether the details dialog is open
+    pub open: bool,
+}
+
+impl ErrorPresenter {
+    /// Create an empty presenter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Present an error
+    pub fn report(&mut self, error: &Error) {
+        let message = error.to_string();
+        let reference = match ReferenceEntry::code_in(&message) {
+            Some((topic, code)) => Some((topic, Some(code))),
+            None if error.category() == ErrorCategory::Connection => Some((ReferenceTopic::Troubleshooting, None)),
+            None => None,
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(PresentedError {
+            id,
+            code: error.code(),
+            category: error.category(),
+            message,
+            hint: error.recovery_hint(),
+            reference,
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            shown_at: Instant::now(),
+            dismissed: false,
+        });
+    }
+
+    /// Show an informational toast
+    pub fn notify(&mut self, message: impl Into<String>) {
+        if self.notices.len() == MAX_TOASTS {
+            self.notices.pop_front();
+        }
+        self.notices.push_back((message.into(), Instant::now()));
+    }
+
+    /// Draw the toasts and, when open, the details dialog
+    ///
+    /// Returns the reference section and code the user asked to look up.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<(ReferenceTopic, Option<u32>)> {
+        self.show_toasts(ctx);
+        self.show_details(ctx)
+    }
+
+    fn show_toasts(&mut self, ctx: &egui::Context) {
+        self.notices.retain(|(_, shown_at)| shown_at.elapsed() < TOAST_DURATION);
+        let active: Vec<usize> = self
+            .history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, e)| !e.dismissed && e.shown_at.elapsed() < TOAST_DURATION)
+            .map(|(i, _)| i)
+            .take(MAX_TOASTS)
+            .collect();
+        if active.is_empty() && self.notices.is_empty() {
+            return;
+        }
+
+        let mut details = None;
+        egui::Area::new(egui::Id::new("error_toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-12.0, -36.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (message, _) in &self.notices {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::LIGHT_BLUE, "ℹ");
+                            ui.label(message);
+                        });
+                    });
+                    ui.add_space(4.0);
+                }
+                for &index in &active {
+                    let error = &mut self.history[index];
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::RED, format!("⚠ {}", error.code));
+                            ui.weak(error.category.to_string());
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button("✕").clicked() {
+                                    error.dismissed = true;
+                                }
+                            });
+                        });
+                        ui.label(&error.message);
+                        if ui.small_button("Details...").clicked() {
+                            details = Some(error.id);
+                            error.dismissed = true;
+                        }
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+
+        if let Some(id) = details {
+            self.selected = Some(id);
+            self.open = true;
+        }
+        // Repaint so toasts disappear on time even when nothing else happens
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+
+    fn show_details(&mut self, ctx: &egui::Context) -> Option<(ReferenceTopic, Option<u32>)> {
+        if !self.open {
+            return None;
+        }
+        if self.selected.is_none() {
+            self.selected = self.history.back().map(|e| e.id);
+        }
+
+        let mut reference = None;
+        let mut open = self.open;
+        egui::Window::new("⚠ Errors")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                match self.history.iter().find(|e| Some(e.id) == self.selected) {
+                    Some(error) => {
+                        egui::Grid::new("error_details_grid")
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.label("Code:");
+                                ui.monospace(&error.code);
+                                ui.end_row();
+
+                                ui.label("Category:");
+                                ui.label(error.category.to_string());
+                                ui.end_row();
+
+                                ui.label("Time:");
+                                ui.label(&error.time);
+                                ui.end_row();
+                            });
+                        ui.add_space(4.0);
+                        ui.label(&error.message);
+                        if let Some(hint) = error.hint {
+                            ui.add_space(4.0);
+                            ui.horizontal_wrapped(|ui| {
+                                ui.strong("What to try:");
+                                ui.label(hint);
+                            });
+                        }
+                        if let Some((topic, code)) = error.reference {
+                            let label = match code {
+                                Some(_) => "📖 Look Up Code".to_string(),
+                                None => format!("📖 {}", topic),
+                            };
+                            if ui.button(label).on_hover_text("Open the GRBL reference").clicked() {
+                                reference = error.reference;
+                            }
+                        }
+                    }
+                    None => {
+                        ui.weak("No errors reported");
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Recent errors");
+                    if ui.add_enabled(!self.history.is_empty(), egui::Button::new("Clear").small()).clicked() {
+                        self.history.clear();
+                        self.selected = None;
+                    }
+                });
+                egui::ScrollArea::vertical().max_height(180.0).show(ui, |ui| {
+                    for error in self.history.iter().rev() {
+                        let text = format!("{}  {}  {}", error.time, error.code, error.message);
+                        if ui.selectable_label(Some(error.id) == self.selected, text).clicked() {
+                            self.selected = Some(error.id);
+                        }
+                    }
+                });
+            });
+        self.open = open;
+        reference
+    }
+}
@@ -0,0 +1,154 @@
+//! Fixture library panel
+//!
+//! Lists the vises, pin grids and spoilboard areas of the machine profile.
+//! The selected fixture is previewed in the viewport with the job placed on
+//! it, and using a fixture sets the active work zero to the fixture zero.
+
+use crate::settings::{Fixture, FixtureKind};
+
+/// Action requested from the fixture panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureAction {
+    /// Set the active work zero to the fixture's zero
+    Use(usize),
+    /// Set the fixture's zero to the active work zero
+    SetZero(usize),
+    /// Add a fixture at the active work zero
+    Add,
+    /// The library or its display changed and should be saved
+    Changed,
+}
+
+/// Panel editing the fixture library
+#[derive(Debug, Clone, Default)]
+pub struct FixturePanel {
+    /// Whether the panel is open
+    pub open: bool,
+    /// Fixture previewed in the viewport
+    pub selected: Option<usize>,
+}
+
+impl FixturePanel {
+    /// Show the panel
+    ///
+    /// `job` is the XY extent of the loaded job in work coordinates, used to
+    /// tell which fixtures it fits on.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        fixtures: &mut Vec<Fixture>,
+        show_overlay: &mut bool,
+        job: Option<([f64; 2], [f64; 2])>,
+        connected: bool,
+    ) -> Option<FixtureAction> {
+        let mut action = None;
+        let mut open = self.open;
+
+        egui::Window::new("🗜 Fixtures")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.checkbox(show_overlay, "Show in viewport").changed() {
+                        action = Some(FixtureAction::Changed);
+                    }
+                    if ui.button("➕ Add at Work Zero")
+                        .on_hover_text("Add a fixture whose zero is the active work zero")
+                        .clicked()
+                    {
+                        action = Some(FixtureAction::Add);
+                    }
+                });
+                ui.separator();
+
+                if fixtures.is_empty() {
+                    ui.weak("No fixtures yet. Zero on a fixture, then add it here.");
+                    return;
+                }
+
+                let mut delete = None;
+                egui::Grid::new("fixture_grid")
+                    .num_columns(6)
+                    .striped(true)
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("Name");
+                        ui.strong("Kind");
+                        ui.strong("Size (X × Y)");
+                        ui.strong("Zero (machine)");
+                        ui.label("");
+                        ui.end_row();
+
+                        for (index, fixture) in fixtures.iter_mut().enumerate() {
+                            let selected = self.selected == Some(index);
+                            if ui.radio(selected, "").on_hover_text("Preview the job on this fixture").clicked() {
+                                self.selected = (!selected).then_some(index);
+                            }
+
+                            let mut changed = ui
+                                .add(egui::TextEdit::singleline(&mut fixture.name).desired_width(100.0))
+                                .lost_focus();
+                            egui::ComboBox::from_id_source(("fixture_kind", index))
+                                .selected_text(fixture.kind.to_string())
+                                .show_ui(ui, |ui| {
+                                    for kind in [FixtureKind::Vise, FixtureKind::PinGrid, FixtureKind::Spoilboard] {
+                                        changed |= ui.selectable_value(&mut fixture.kind, kind, kind.to_string()).changed();
+                                    }
+                                });
+                            ui.horizontal(|ui| {
+                                for value in &mut fixture.size {
+                                    changed |= ui
+                                        .add(egui::DragValue::new(value).speed(1.0).range(1.0..=5000.0).suffix(" mm"))
+                                        .changed();
+                                }
+                            });
+                            let [x, y, z] = fixture.zero;
+                            ui.monospace(format!("X{:.3} Y{:.3} Z{:.3}", x, y, z));
+                            if changed {
+                                action = Some(FixtureAction::Changed);
+                            }
+
+                            ui.horizontal(|ui| {
+                                let fit = job.map(|(min, max)| fixture.fits(min, max));
+                                let hint = match fit {
+                                    Some(true) => "Set the active work zero to this fixture; the job fits",
+                                    Some(false) => "Set the active work zero to this fixture; the job is larger than its area",
+                                    None => "Set the active work zero to this fixture",
+                                };
+                                let label = if fit == Some(false) { "⚠ Use" } else { "Use" };
+                                if ui.add_enabled(connected, egui::Button::new(label)).on_hover_text(hint).clicked() {
+                                    action = Some(FixtureAction::Use(index));
+                                }
+                                if ui.add_enabled(connected, egui::Button::new("📍").small())
+                                    .on_hover_text("Set the fixture zero to the active work zero")
+                                    .clicked()
+                                {
+                                    action = Some(FixtureAction::SetZero(index));
+                                }
+                                if ui.small_button("🗑").on_hover_text("Delete this fixture").clicked() {
+                                    delete = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(index) = delete {
+                    fixtures.remove(index);
+                    self.selected = match self.selected {
+                        Some(selected) if selected == index => None,
+                        Some(selected) if selected > index => Some(selected - 1),
+                        selected => selected,
+                    };
+                    action = Some(FixtureAction::Changed);
+                }
+            });
+
+        self.open = open;
+        if !self.open {
+            self.selected = None;
+        }
+        action
+    }
+}
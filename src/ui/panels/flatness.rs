@@ -0,0 +1,227 @@
+//! Flatness report panel
+//!
+//! Shows the flatness and tram check for a heightmap: tilt, deviation,
+//! suggested corner shims and a deviation heatmap with the value of every
+//! probe point. The loaded heightmap can be used to auto-level programs.
+
+use crate::heightmap::{FlatnessReport, Heightmap};
+
+/// Largest heatmap cell
+const MAX_CELL: f32 = 64.0;
+
+/// Request from the flatness panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatnessRequest {
+    /// Load a heightmap file
+    OpenFile,
+    /// Use the heightmap attached to the open project
+    UseProjectHeightmap,
+    /// Auto-level programs to the loaded heightmap
+    StartLeveling,
+    /// Stop auto-leveling programs
+    StopLeveling,
+}
+
+/// Panel showing the flatness report of a heightmap
+#[derive(Debug, Default)]
+pub struct FlatnessPanel {
+    /// Whether the panel is open
+    pub open: bool,
+    /// Name of the loaded heightmap
+    source: String,
+    map: Option<Heightmap>,
+    report: Option<FlatnessReport>,
+    /// Show deviation from the best-fit plane instead of the mean height
+    remove_tilt: bool,
+}
+
+impl FlatnessPanel {
+    /// Show the report for a heightmap
+    pub fn load(&mut self, source: impl Into<String>, map: Heightmap) {
+        self.source = source.into();
+        self.report = FlatnessReport::new(&map);
+        self.map = Some(map);
+        self.open = true;
+    }
+
+    /// Name and grid of the loaded heightmap
+    pub fn heightmap(&self) -> Option<(&str, &Heightmap)> {
+        self.map.as_ref().map(|map| (self.source.as_str(), map))
+    }
+
+    /// Show the panel
+    ///
+    /// `has_project_heightmap` enables loading the project's heightmap;
+    /// `leveling` names the heightmap programs are auto-leveled to.
+    pub fn show(&mut self, ctx: &egui::Context, has_project_heightmap: bool, leveling: Option<&str>) -> Option<FlatnessRequest> {
+        let mut request = None;
+        let mut open = self.open;
+
+        egui::Window::new("▦ Flatness Report")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("📂 Open Heightmap...").clicked() {
+                        request = Some(FlatnessRequest::OpenFile);
+                    }
+                    if ui.add_enabled(has_project_heightmap, egui::Button::new("📁 Project Heightmap")).clicked() {
+                        request = Some(FlatnessRequest::UseProjectHeightmap);
+                    }
+                });
+                ui.separator();
+
+                let (Some(map), Some(report)) = (&self.map, &self.report) else {
+                    match &self.map {
+                        Some(_) => ui.colored_label(egui::Color32::YELLOW, "The probe points do not span an area"),
+                        None => ui.weak("Load a heightmap of X,Y,Z probe points"),
+                    };
+                    return;
+                };
+
+                ui.label(format!("{} ({} x {} points)", self.source, map.cols(), map.rows()));
+                ui.horizontal(|ui| {
+                    match leveling {
+                        Some(name) => {
+                            ui.colored_label(egui::Color32::LIGHT_GREEN, format!("Auto-leveling to {}", name));
+                            if ui.button("Stop Auto-Leveling").clicked() {
+                                request = Some(FlatnessRequest::StopLeveling);
+                            }
+                        }
+                        None => {
+                            if ui.button("⛰ Auto-Level Programs")
+                                .on_hover_text("Follow this surface while streaming; the map must be in work coordinates")
+                                .clicked()
+                            {
+                                request = Some(FlatnessRequest::StartLeveling);
+                            }
+                        }
+                    }
+                });
+                ui.add_space(4.0);
+                show_metrics(ui, report);
+                ui.add_space(4.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Deviation from:");
+                    ui.selectable_value(&mut self.remove_tilt, false, "Mean height");
+                    ui.selectable_value(&mut self.remove_tilt, true, "Best-fit plane");
+                });
+                draw_heatmap(ui, map, report, self.remove_tilt);
+            });
+
+        self.open = open;
+        request
+    }
+}
+
+fn show_metrics(ui: &mut egui::Ui, report: &FlatnessReport) {
+    let [tilt_x, tilt_y] = report.tilt_per_100();
+    let [degrees_x, degrees_y] = report.tilt_degrees();
+
+    egui::Grid::new("flatness_metrics_grid")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Height range:");
+            ui.label(format!("{:.3} mm", report.range));
+            ui.end_row();
+
+            ui.label("Max deviation from mean:");
+            ui.label(format!("{:.3} mm", report.max_deviation));
+            ui.end_row();
+
+            ui.label("Flatness (tilt removed):");
+            ui.label(format!("{:.3} mm", report.flatness));
+            ui.end_row();
+
+            ui.label("Tilt X:");
+            ui.label(format!("{:+.3} mm / 100 mm ({:+.4}°)", tilt_x, degrees_x));
+            ui.end_row();
+
+            ui.label("Tilt Y:");
+            ui.label(format!("{:+.3} mm / 100 mm ({:+.4}°)", tilt_y, degrees_y));
+            ui.end_row();
+        });
+
+    ui.add_space(4.0);
+    ui.strong("Tramming");
+    if report.range - report.flatness < 0.01 {
+        ui.label("The tilt is below 0.01 mm across the area; no shims needed.");
+    } else {
+        ui.label("Shim the surface at these corners to level it with the XY travel:");
+        for (corner, shim) in &report.shims {
+            if *shim >= 0.001 {
+                ui.label(format!("  {}: {:.3} mm", corner, shim));
+            }
+        }
+        ui.weak("Or tram the spindle, or surface the spoilboard to remove the tilt.");
+    }
+}
+
+/// Draw the deviation of every probe point as a colored grid, back row at the top
+fn draw_heatmap(ui: &mut egui::Ui, map: &Heightmap, report: &FlatnessReport, remove_tilt: bool) {
+    let deviation = |col: usize, row: usize| {
+        let z = map.height(col, row);
+        if remove_tilt {
+            z - report.plane.height(map.x(col), map.y(row))
+        } else {
+            z - report.mean
+        }
+    };
+    let scale = (0..map.rows())
+        .flat_map(|row| (0..map.cols()).map(move |col| (col, row)))
+        .map(|(col, row)| deviation(col, row).abs())
+        .fold(0.0, f64::max)
+        .max(1e-6);
+
+    let cell = (ui.available_width() / map.cols() as f32).min(MAX_CELL);
+    let size = egui::vec2(cell * map.cols() as f32, cell * map.rows() as f32);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let show_values = cell >= 40.0;
+
+    let mut hovered = None;
+    for row in 0..map.rows() {
+        for col in 0..map.cols() {
+            let min = rect.left_top() + egui::vec2(col as f32 * cell, (map.rows() - 1 - row) as f32 * cell);
+            let cell_rect = egui::Rect::from_min_size(min, egui::vec2(cell, cell));
+            let value = deviation(col, row);
+            painter.rect_filled(cell_rect.shrink(0.5), 0.0, deviation_color(value / scale));
+            if show_values {
+                painter.text(
+                    cell_rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    format!("{:+.3}", value),
+                    egui::FontId::monospace(10.0),
+                    egui::Color32::BLACK,
+                );
+            }
+            if response.hover_pos().is_some_and(|pos| cell_rect.contains(pos)) {
+                hovered = Some((col, row, value));
+            }
+        }
+    }
+
+    if let Some((col, row, value)) = hovered {
+        response.on_hover_text(format!(
+            "X{:.2} Y{:.2}\nZ {:.3} mm\nDeviation {:+.3} mm",
+            map.x(col),
+            map.y(row),
+            map.height(col, row),
+            value
+        ));
+    }
+    ui.weak(format!("Blue is low, red is high; full color is ±{:.3} mm", scale));
+}
+
+/// Blue for low, white for level and red for high, `t` in -1..=1
+fn deviation_color(t: f64) -> egui::Color32 {
+    let t = t.clamp(-1.0, 1.0) as f32;
+    let fade = (255.0 * (1.0 - t.abs())) as u8;
+    if t < 0.0 {
+        egui::Color32::from_rgb(fade, fade, 255)
+    } else {
+        egui::Color32::from_rgb(255, fade, fade)
+    }
+}
@@ -0,0 +1,185 @@
+//! G-Code format dialog
+//!
+//! Renumbers, strips and reformats the program in the editor. The result
+//! is previewed next to the original before it is applied, and the last
+//! change can be undone while the program is still as it was left.
+
+use crate::parser::{GCodeFormatter, LineNumbers};
+
+/// Lines shown in each preview column
+const PREVIEW_LINES: usize = 400;
+
+/// Handling of N words as chosen in the dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Numbering {
+    Keep,
+    Remove,
+    Renumber,
+}
+
+/// Dialog for reformatting the program
+#[derive(Debug, Clone)]
+pub struct FormatterDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    numbering: Numbering,
+    /// First line number when renumbering
+    start: u32,
+    /// Line number increment when renumbering
+    step: u32,
+    strip_comments: bool,
+    strip_blank_lines: bool,
+    normalize: bool,
+    /// Round coordinates and feeds
+    round: bool,
+    /// Decimals kept when rounding
+    decimals: usize,
+    /// Formatter, original text and result of the last preview
+    preview: Option<(GCodeFormatter, String, String)>,
+    /// Program before and after the last applied change
+    undo: Option<(String, String)>,
+}
+
+impl Default for FormatterDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            numbering: Numbering::Keep,
+            start: 10,
+            step: 10,
+            strip_comments: false,
+            strip_blank_lines: false,
+            normalize: true,
+            round: false,
+            decimals: 3,
+            preview: None,
+            undo: None,
+        }
+    }
+}
+
+impl FormatterDialog {
+    /// Build a formatter from the dialog options
+    fn formatter(&self) -> GCodeFormatter {
+        let line_numbers = match self.numbering {
+            Numbering::Keep => LineNumbers::Keep,
+            Numbering::Remove => LineNumbers::Remove,
+            Numbering::Renumber => LineNumbers::Renumber { start: self.start, step: self.step },
+        };
+        GCodeFormatter::new()
+            .with_line_numbers(line_numbers)
+            .with_strip_comments(self.strip_comments)
+            .with_strip_blank_lines(self.strip_blank_lines)
+            .with_normalize(self.normalize)
+            .with_precision(self.round.then_some(self.decimals))
+    }
+
+    /// Formatted program, reusing the last preview when nothing changed
+    fn formatted(&mut self, program: &str) -> &str {
+        let formatter = self.formatter();
+        let stale = !matches!(&self.preview, Some((f, original, _)) if *f == formatter && original == program);
+        if stale {
+            let result = formatter.format(program);
+            self.preview = Some((formatter, program.to_string(), result));
+        }
+        self.preview.as_ref().map(|(_, _, result)| result.as_str()).unwrap_or_default()
+    }
+
+    /// Show the dialog, returning the new program when a change is applied or undone
+    pub fn show(&mut self, ctx: &egui::Context, program: &str) -> Option<String> {
+        let mut open = self.open;
+        let mut result = None;
+
+        egui::Window::new("🧹 Format G-Code")
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("formatter_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Line numbers:");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.numbering, Numbering::Keep, "Keep");
+                            ui.radio_value(&mut self.numbering, Numbering::Remove, "Remove");
+                            ui.radio_value(&mut self.numbering, Numbering::Renumber, "Renumber");
+                            if self.numbering == Numbering::Renumber {
+                                ui.label("from");
+                                ui.add(egui::DragValue::new(&mut self.start).range(0..=99999));
+                                ui.label("step");
+                                ui.add(egui::DragValue::new(&mut self.step).range(1..=1000));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Strip:");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.strip_comments, "Comments");
+                            ui.checkbox(&mut self.strip_blank_lines, "Blank lines");
+                        });
+                        ui.end_row();
+
+                        ui.label("Normalize:");
+                        ui.checkbox(&mut self.normalize, "Uppercase, single spaces and standard word order");
+                        ui.end_row();
+
+                        ui.label("Precision:");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.round, "Round coordinates and feeds to");
+                            ui.add_enabled(self.round, egui::DragValue::new(&mut self.decimals).range(0..=6));
+                            ui.label("decimals");
+                        });
+                        ui.end_row();
+                    });
+                ui.separator();
+
+                let formatted = self.formatted(program).to_string();
+                let changed = program.lines().zip(formatted.lines()).filter(|(a, b)| a != b).count()
+                    + program.lines().count().abs_diff(formatted.lines().count());
+                ui.label(format!(
+                    "{} lines → {} lines{}",
+                    program.lines().count(),
+                    formatted.lines().count(),
+                    if changed == 0 { ", no changes" } else { "" }
+                ));
+
+                ui.columns(2, |columns| {
+                    preview_column(&mut columns[0], "formatter_before", "Before", program);
+                    preview_column(&mut columns[1], "formatter_after", "After", &formatted);
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(formatted != program, egui::Button::new("✔ Apply")).clicked() {
+                        self.undo = Some((program.to_string(), formatted.clone()));
+                        result = Some(formatted);
+                    }
+                    let can_undo = self.undo.as_ref().is_some_and(|(_, after)| after == program);
+                    if ui.add_enabled(can_undo, egui::Button::new("↶ Undo")).clicked() {
+                        result = self.undo.take().map(|(before, _)| before);
+                    }
+                });
+            });
+
+        self.open = open;
+        if !self.open {
+            self.preview = None;
+        }
+        result
+    }
+}
+
+/// Scrollable monospace view of the start of a program
+fn preview_column(ui: &mut egui::Ui, id: &str, title: &str, program: &str) {
+    ui.strong(title);
+    egui::ScrollArea::both()
+        .id_source(id)
+        .max_height(320.0)
+        .show(ui, |ui| {
+            let mut text: String = program.lines().take(PREVIEW_LINES).collect::<Vec<_>>().join("\n");
+            if program.lines().count() > PREVIEW_LINES {
+                text.push_str("\n…");
+            }
+            ui.label(egui::RichText::new(text).monospace());
+        });
+}
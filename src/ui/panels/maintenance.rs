@@ -0,0 +1,123 @@
+//! Maintenance panel
+//!
+//! Shows the machine's runtime counters, spindle hours and distance
+//! traveled, and the maintenance reminders counted against them. Marking a
+//! reminder done starts its interval again from the current counter.
+
+use crate::settings::{MaintenanceBasis, MaintenanceReminder, MaintenanceSettings};
+
+/// Action requested from the maintenance panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceAction {
+    /// The reminder's task was done
+    Done(usize),
+    /// The reminders changed and should be saved
+    Changed,
+}
+
+/// Panel listing runtime counters and maintenance reminders
+#[derive(Debug, Clone, Default)]
+pub struct MaintenancePanel {
+    /// Whether the panel is open
+    pub open: bool,
+}
+
+impl MaintenancePanel {
+    /// Show the panel
+    pub fn show(&mut self, ctx: &egui::Context, maintenance: &mut MaintenanceSettings) -> Option<MaintenanceAction> {
+        let mut action = None;
+        let mut open = self.open;
+
+        egui::Window::new("🛠 Maintenance")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("maintenance_counters")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Spindle hours:");
+                        ui.monospace(format!("{:.1} h", maintenance.spindle_hours));
+                        ui.end_row();
+
+                        ui.label("Distance traveled:");
+                        ui.monospace(format!("{:.1} m", maintenance.distance_m));
+                        ui.end_row();
+                    });
+                ui.separator();
+
+                if maintenance.reminders.is_empty() {
+                    ui.weak("No reminders. Add one for each task, e.g. lubricating the rails.");
+                }
+
+                let counters = maintenance.clone();
+                let mut delete = None;
+                egui::Grid::new("maintenance_reminders")
+                    .num_columns(4)
+                    .striped(true)
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        for (index, reminder) in maintenance.reminders.iter_mut().enumerate() {
+                            let mut changed = ui
+                                .add(egui::TextEdit::singleline(&mut reminder.name).desired_width(140.0))
+                                .lost_focus();
+                            ui.horizontal(|ui| {
+                                ui.label("every");
+                                changed |= ui
+                                    .add(egui::DragValue::new(&mut reminder.interval).speed(1.0).range(0.0..=100000.0))
+                                    .changed();
+                                egui::ComboBox::from_id_source(("maintenance_basis", index))
+                                    .selected_text(reminder.basis.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for basis in [MaintenanceBasis::SpindleHours, MaintenanceBasis::Distance] {
+                                            changed |= ui
+                                                .selectable_value(&mut reminder.basis, basis, basis.to_string())
+                                                .changed();
+                                        }
+                                    });
+                            });
+                            if changed {
+                                action = Some(MaintenanceAction::Changed);
+                            }
+
+                            let remaining = counters.remaining(reminder);
+                            if reminder.interval <= 0.0 {
+                                ui.weak("off");
+                            } else if remaining <= 0.0 {
+                                ui.colored_label(egui::Color32::YELLOW, "⚠ due");
+                            } else {
+                                ui.label(format!("in {:.0} {}", remaining, reminder.basis));
+                            }
+
+                            ui.horizontal(|ui| {
+                                if ui.button("✔ Done").on_hover_text("Start the interval again from now").clicked() {
+                                    action = Some(MaintenanceAction::Done(index));
+                                }
+                                if ui.small_button("🗑").on_hover_text("Delete this reminder").clicked() {
+                                    delete = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(index) = delete {
+                    maintenance.reminders.remove(index);
+                    action = Some(MaintenanceAction::Changed);
+                }
+                if ui.button("➕ Add Reminder").clicked() {
+                    let last_done = maintenance.spindle_hours;
+                    maintenance.reminders.push(MaintenanceReminder {
+                        name: "Maintenance".to_string(),
+                        basis: MaintenanceBasis::SpindleHours,
+                        interval: 50.0,
+                        last_done,
+                    });
+                    action = Some(MaintenanceAction::Changed);
+                }
+            });
+
+        self.open = open;
+        action
+    }
+}
@@ -0,0 +1,65 @@
+//! UI panels for rCandle
+//!
+//! This module contains specialized panels and tool dialogs that are shown
+//! alongside the main application window.
+
+mod action_log;
+mod axis_test;
+mod backlash;
+mod calculator;
+mod chart;
+mod checklist;
+mod command_palette;
+mod compatibility;
+mod diagnostics;
+mod edge_finder;
+mod errors;
+mod fixtures;
+mod flatness;
+mod formatter;
+mod maintenance;
+mod multipass;
+mod onboarding;
+mod pendant;
+mod plugins;
+mod probe_log;
+mod project;
+mod recovery_log;
+mod reference;
+mod run_screen;
+mod statistics;
+mod user_commands;
+
+pub use action_log::{ActionLogPanel, ActionLogRequest};
+pub use axis_test::AxisTestDialog;
+pub use backlash::{BacklashAction, BacklashWizard};
+pub use calculator::CalculatorDialog;
+pub use chart::StatusChart;
+pub use checklist::{ChecklistAction, ChecklistDialog};
+pub use command_palette::{CommandPalette, PaletteCommand, PaletteEntry};
+pub use compatibility::{CompatibilityAction, CompatibilityPanel};
+pub use diagnostics::DiagnosticsPanel;
+pub use edge_finder::EdgeFinderDialog;
+pub use errors::ErrorPresenter;
+pub use fixtures::{FixtureAction, FixturePanel};
+pub use flatness::{FlatnessPanel, FlatnessRequest};
+pub use formatter::FormatterDialog;
+pub use maintenance::{MaintenanceAction, MaintenancePanel};
+pub use multipass::MultiPassDialog;
+pub use onboarding::{OnboardingAction, OnboardingWizard};
+pub use pendant::{Pendant, PendantAction};
+pub use plugins::{PluginAction, PluginManager};
+pub use probe_log::{ProbeLogPanel, ProbeLogRequest};
+pub use project::{ProjectAction, ProjectDialog};
+pub use recovery_log::RecoveryLogPanel;
+pub use reference::ReferencePanel;
+pub use run_screen::{RunScreen, RunScreenAction, RunScreenStatus};
+pub use statistics::StatisticsPanel;
+pub use user_commands::{UserCommandsAction, UserCommandsPanel};
+
+use std::time::Duration;
+
+/// How often a full-window readout refreshes on its own, slower in low-power mode
+fn readout_refresh(low_power: bool) -> Duration {
+    Duration::from_millis(if low_power { 500 } else { 100 })
+}
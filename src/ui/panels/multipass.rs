@@ -0,0 +1,117 @@
+//! Multi-pass depth dialog
+//!
+//! Collects the parameters for regenerating the loaded program as
+//! multiple Z passes.
+
+use crate::parser::MultiPassGenerator;
+
+/// Dialog for configuring multi-pass generation
+#[derive(Debug, Clone)]
+pub struct MultiPassDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// Final depth below the stock top
+    pub final_depth: f64,
+    /// Maximum depth removed per pass
+    pub depth_per_pass: f64,
+    /// Z coordinate of the stock top
+    pub stock_top: f64,
+    /// Safe Z height between passes
+    pub safe_z: f64,
+    /// Plunge feed rate
+    pub plunge_feed: f64,
+    /// Lead-in length before each pass
+    pub lead_in_length: f64,
+}
+
+impl Default for MultiPassDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            final_depth: 3.0,
+            depth_per_pass: 1.0,
+            stock_top: 0.0,
+            safe_z: 5.0,
+            plunge_feed: 100.0,
+            lead_in_length: 2.0,
+        }
+    }
+}
+
+impl MultiPassDialog {
+    /// Open the dialog, seeding the safe Z height
+    pub fn open_with_safe_z(&mut self, safe_z: f64) {
+        self.safe_z = safe_z;
+        self.open = true;
+    }
+
+    /// Build a generator from the current dialog values
+    pub fn generator(&self) -> MultiPassGenerator {
+        MultiPassGenerator::new(self.final_depth, self.depth_per_pass)
+            .with_stock_top(self.stock_top)
+            .with_safe_z(self.safe_z)
+            .with_plunge_feed(self.plunge_feed)
+            .with_lead_in_length(self.lead_in_length)
+    }
+
+    /// Show the dialog, returning a generator when the user applies it
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<MultiPassGenerator> {
+        let mut open = self.open;
+        let mut apply = false;
+        let mut cancel = false;
+
+        egui::Window::new("✂ Multi-Pass Depth")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Regenerate a single-depth profile as multiple Z passes.");
+                ui.add_space(5.0);
+
+                egui::Grid::new("multipass_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Final depth:");
+                        ui.add(egui::DragValue::new(&mut self.final_depth).speed(0.1).range(0.01..=100.0));
+                        ui.end_row();
+
+                        ui.label("Depth per pass:");
+                        ui.add(egui::DragValue::new(&mut self.depth_per_pass).speed(0.1).range(0.01..=100.0));
+                        ui.end_row();
+
+                        ui.label("Stock top Z:");
+                        ui.add(egui::DragValue::new(&mut self.stock_top).speed(0.1));
+                        ui.end_row();
+
+                        ui.label("Safe Z:");
+                        ui.add(egui::DragValue::new(&mut self.safe_z).speed(0.1));
+                        ui.end_row();
+
+                        ui.label("Plunge feed:");
+                        ui.add(egui::DragValue::new(&mut self.plunge_feed).speed(10.0).range(1.0..=10000.0));
+                        ui.end_row();
+
+                        ui.label("Lead-in length:");
+                        ui.add(egui::DragValue::new(&mut self.lead_in_length).speed(0.1).range(0.0..=100.0));
+                        ui.end_row();
+                    });
+
+                if let Ok(depths) = self.generator().pass_depths() {
+                    ui.label(format!("Passes: {}", depths.len()));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ Apply").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        self.open = open && !apply && !cancel;
+        apply.then(|| self.generator())
+    }
+}
@@ -0,0 +1,405 @@
+//! First-run setup wizard
+//!
+//! Walks a new user through the settings needed before the first job:
+//! display units, the serial port and baud rate, a connection test, a
+//! short jog of each axis to check its direction, and the machine travel.
+//! The controller's direction mask ($3) and travel ($130-$132) are read
+//! with `$$` once connected. Finishing writes everything into the settings
+//! and, if an axis moved the wrong way, flips its bit of $3.
+
+use crate::settings::{ConnectionType, JogLimits, Settings};
+
+const AXES: [char; 3] = ['X', 'Y', 'Z'];
+
+/// Baud rates offered, most common first
+const BAUD_RATES: [u32; 6] = [115200, 250000, 230400, 57600, 38400, 9600];
+
+/// Page of the wizard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    Units,
+    Port,
+    Connect,
+    Jog,
+    Travel,
+    Finish,
+}
+
+impl Step {
+    const ALL: [Step; 6] = [Step::Units, Step::Port, Step::Connect, Step::Jog, Step::Travel, Step::Finish];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Step::Units => "Units",
+            Step::Port => "Port",
+            Step::Connect => "Connection Test",
+            Step::Jog => "Jog Test",
+            Step::Travel => "Machine Travel",
+            Step::Finish => "Finish",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Step::ALL.iter().position(|step| step == self).unwrap_or(0)
+    }
+}
+
+/// Request from the wizard to the application
+#[derive(Debug, Clone, PartialEq)]
+pub enum OnboardingAction {
+    /// List the serial ports again
+    RefreshPorts,
+    /// Connect to a port at a baud rate
+    Connect {
+        /// Serial port
+        port: String,
+        /// Baud rate
+        baud_rate: u32,
+    },
+    /// Send these lines in order
+    Send(Vec<String>),
+    /// Setup is complete; apply it with [`OnboardingWizard::apply`]
+    Finish,
+    /// The user skipped the setup
+    Skip,
+}
+
+/// Wizard shown on the first start
+#[derive(Debug, Clone)]
+pub struct OnboardingWizard {
+    /// Whether the wizard is open
+    pub open: bool,
+    step: Step,
+    metric: bool,
+    port: String,
+    baud_rate: u32,
+    /// Jog distance for the direction test (mm)
+    jog_distance: f64,
+    /// Axes that moved the wrong way
+    reversed: [bool; 3],
+    /// Direction invert mask ($3) read from the controller
+    direction_mask: Option<u32>,
+    /// Travel of each axis (mm)
+    travel: [f64; 3],
+    /// Whether the travel was read from the controller
+    travel_read: bool,
+    limit_to_travel: bool,
+    /// Whether `$$` was sent for this connection
+    settings_requested: bool,
+}
+
+impl Default for OnboardingWizard {
+    fn default() -> Self {
+        let defaults = JogLimits::default();
+        Self {
+            open: false,
+            step: Step::Units,
+            metric: true,
+            port: String::new(),
+            baud_rate: 115200,
+            jog_distance: 5.0,
+            reversed: [false; 3],
+            direction_mask: None,
+            travel: [0, 1, 2].map(|axis| defaults.max[axis] - defaults.min[axis]),
+            travel_read: false,
+            limit_to_travel: true,
+            settings_requested: false,
+        }
+    }
+}
+
+impl OnboardingWizard {
+    /// Open the wizard at its first page, starting from the current settings
+    pub fn start(&mut self, settings: &Settings, port: &str) {
+        *self = Self {
+            open: true,
+            metric: settings.general.units_metric,
+            port: port.to_string(),
+            baud_rate: settings.connection.baud_rate,
+            travel: [0, 1, 2].map(|axis| settings.jog.travel.max[axis] - settings.jog.travel.min[axis]),
+            ..Self::default()
+        };
+    }
+
+    /// Take a `$n=value` report from the controller
+    pub fn record_setting(&mut self, number: u32, value: &str) {
+        match number {
+            3 => self.direction_mask = value.trim().parse().ok(),
+            130..=132 => {
+                if let Ok(travel) = value.trim().parse::<f64>() {
+                    self.travel[(number - 130) as usize] = travel;
+                    self.travel_read = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// New direction invert mask ($3), if an axis has to be reversed
+    pub fn direction_change(&self) -> Option<u32> {
+        let flips = (0..3)
+            .filter(|&axis| self.reversed[axis])
+            .fold(0, |mask, axis| mask | 1 << axis);
+        (flips != 0).then_some(self.direction_mask? ^ flips)
+    }
+
+    /// Write the setup into the settings
+    ///
+    /// The travel is stored as the usual GRBL machine space, homed at the
+    /// top right with the axes moving negative from there.
+    pub fn apply(&self, settings: &mut Settings) {
+        if settings.general.units_metric != self.metric {
+            settings.general.units_metric = self.metric;
+            settings.jog.convert_units(self.metric);
+        }
+        settings.connection.connection_type = ConnectionType::Serial;
+        settings.connection.port_name = self.port.clone();
+        settings.connection.baud_rate = self.baud_rate;
+        settings.jog.travel = JogLimits {
+            min: self.travel.map(|travel| -travel.abs()),
+            max: [0.0; 3],
+        };
+        settings.jog.limit_to_travel = self.limit_to_travel;
+        settings.general.onboarding_done = true;
+    }
+
+    /// Show the wizard, returning a request for the application
+    pub fn show(&mut self, ctx: &egui::Context, ports: &[String], connected: bool) -> Option<OnboardingAction> {
+        if !self.open {
+            return None;
+        }
+        let mut action = None;
+
+        // Read the direction mask and travel once connected
+        if !connected {
+            self.settings_requested = false;
+        } else if !self.settings_requested && self.step >= Step::Connect {
+            self.settings_requested = true;
+            action = Some(OnboardingAction::Send(vec!["$$".to_string()]));
+        }
+
+        egui::Window::new("🧭 Machine Setup")
+            .collapsible(false)
+            .resizable(false)
+            .default_width(460.0)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for step in Step::ALL {
+                        let text = egui::RichText::new(step.title());
+                        ui.label(if step == self.step { text.strong() } else { text.weak() });
+                        if step != Step::Finish {
+                            ui.weak("›");
+                        }
+                    }
+                });
+                ui.separator();
+
+                match self.step {
+                    Step::Units => {
+                        ui.label("Welcome to rCandle. A few questions set it up for your machine.");
+                        ui.add_space(6.0);
+                        ui.label("Units for coordinates, jog steps and feed rates:");
+                        ui.radio_value(&mut self.metric, true, "Metric (mm)");
+                        ui.radio_value(&mut self.metric, false, "Imperial (inches)");
+                    }
+                    Step::Port => {
+                        ui.label("Plug in the controller's USB cable and pick its port.");
+                        ui.horizontal(|ui| {
+                            ui.label("Port:");
+                            egui::ComboBox::from_id_source("onboarding_port")
+                                .selected_text(if self.port.is_empty() { "None" } else { self.port.as_str() })
+                                .show_ui(ui, |ui| {
+                                    for port in ports {
+                                        ui.selectable_value(&mut self.port, port.clone(), port);
+                                    }
+                                });
+                            if ui.button("🔄 Refresh").clicked() {
+                                action = Some(OnboardingAction::RefreshPorts);
+                            }
+                        });
+                        if ports.is_empty() {
+                            ui.colored_label(egui::Color32::YELLOW, "No serial ports found. Check the cable and driver.");
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Baud Rate:");
+                            egui::ComboBox::from_id_source("onboarding_baud")
+                                .selected_text(self.baud_rate.to_string())
+                                .show_ui(ui, |ui| {
+                                    for rate in BAUD_RATES {
+                                        ui.selectable_value(&mut self.baud_rate, rate, rate.to_string());
+                                    }
+                                });
+                        });
+                        ui.weak("GRBL 1.1 uses 115200; some grblHAL boards use 250000 or native USB.");
+                    }
+                    Step::Connect => {
+                        ui.label(format!("Connect to {} at {} baud.", self.port, self.baud_rate));
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!connected && !self.port.is_empty(), egui::Button::new("🔌 Connect")).clicked() {
+                                action = Some(OnboardingAction::Connect {
+                                    port: self.port.clone(),
+                                    baud_rate: self.baud_rate,
+                                });
+                            }
+                            if connected {
+                                ui.colored_label(egui::Color32::LIGHT_GREEN, "● Connected");
+                            } else {
+                                ui.weak("○ Not connected");
+                            }
+                        });
+                        if connected {
+                            if self.direction_mask.is_some() || self.travel_read {
+                                ui.label("The controller answered and its settings were read.");
+                            } else {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("Reading the controller settings...");
+                                });
+                            }
+                        } else {
+                            ui.weak("If nothing happens, try the other baud rates or check the console for errors.");
+                        }
+                    }
+                    Step::Jog => self.show_jog_test(ui, connected, &mut action),
+                    Step::Travel => {
+                        ui.label("Travel of each axis from its home position, in machine millimeters:");
+                        egui::Grid::new("onboarding_travel")
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for (index, axis) in AXES.iter().enumerate() {
+                                    ui.label(format!("{}:", axis));
+                                    ui.add(egui::DragValue::new(&mut self.travel[index])
+                                        .speed(1.0)
+                                        .range(1.0..=5000.0)
+                                        .suffix(" mm"));
+                                    ui.end_row();
+                                }
+                            });
+                        if self.travel_read {
+                            ui.weak("Read from the controller ($130-$132).");
+                        }
+                        ui.checkbox(&mut self.limit_to_travel, "Keep jogs inside the travel");
+                    }
+                    Step::Finish => {
+                        ui.label("Ready to save:");
+                        egui::Grid::new("onboarding_summary")
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.label("Units:");
+                                ui.label(if self.metric { "Metric (mm)" } else { "Imperial (inches)" });
+                                ui.end_row();
+                                ui.label("Port:");
+                                ui.label(format!("{} at {} baud", self.port, self.baud_rate));
+                                ui.end_row();
+                                ui.label("Travel:");
+                                ui.label(format!("X {:.0} × Y {:.0} × Z {:.0} mm", self.travel[0], self.travel[1], self.travel[2]));
+                                ui.end_row();
+                                if let Some(mask) = self.direction_change() {
+                                    ui.label("Direction:");
+                                    ui.label(format!("$3={} (reverses the axes that moved the wrong way)", mask));
+                                    ui.end_row();
+                                }
+                            });
+                        ui.weak("Everything can be changed later in Tools → Settings.");
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Skip Setup").on_hover_text("Keep the default settings").clicked() {
+                        action = Some(OnboardingAction::Skip);
+                        self.open = false;
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let index = self.step.index();
+                        if self.step == Step::Finish {
+                            if ui.button("✔ Finish").clicked() {
+                                action = Some(OnboardingAction::Finish);
+                                self.open = false;
+                            }
+                        } else if ui.button("Next ▶").clicked() {
+                            self.step = Step::ALL[index + 1];
+                        }
+                        if ui.add_enabled(index > 0, egui::Button::new("◀ Back")).clicked() {
+                            self.step = Step::ALL[index - 1];
+                        }
+                    });
+                });
+            });
+
+        action
+    }
+
+    /// Jog each axis a short way and ask whether it moved the right way
+    fn show_jog_test(&mut self, ui: &mut egui::Ui, connected: bool, action: &mut Option<OnboardingAction>) {
+        if !connected {
+            ui.colored_label(egui::Color32::YELLOW, "Not connected; go back to connect, or skip this test.");
+            return;
+        }
+        ui.label("Jog each axis in the plus direction and watch which way it moves:");
+        ui.weak("X+ moves the tool right, Y+ away from you (or the table toward you), Z+ up. Jog Z first.");
+        ui.horizontal(|ui| {
+            ui.label("Distance:");
+            ui.add(egui::DragValue::new(&mut self.jog_distance)
+                .speed(0.5)
+                .range(0.5..=50.0)
+                .suffix(" mm"));
+        });
+        egui::Grid::new("onboarding_jog")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for (index, axis) in AXES.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        for sign in [1.0, -1.0] {
+                            let label = format!("{}{}", axis, if sign > 0.0 { "+" } else { "-" });
+                            if ui.button(label).clicked() {
+                                let distance = sign * self.jog_distance;
+                                *action = Some(OnboardingAction::Send(vec![format!(
+                                    "$J=G91 G21 {}{:.3} F{:.0}",
+                                    axis,
+                                    distance,
+                                    if index == 2 { 300.0 } else { 1000.0 }
+                                )]));
+                            }
+                        }
+                    });
+                    ui.add_enabled(
+                        self.direction_mask.is_some(),
+                        egui::Checkbox::new(&mut self.reversed[index], "Moved the wrong way"),
+                    )
+                    .on_disabled_hover_text("The controller's direction setting ($3) has not been read");
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_setup() {
+        let mut settings = Settings::default();
+        let mut wizard = OnboardingWizard::default();
+        wizard.start(&settings, "/dev/ttyUSB0");
+        wizard.metric = false;
+        wizard.record_setting(3, "2");
+        wizard.record_setting(130, "400.000");
+        wizard.record_setting(132, "90");
+        wizard.reversed = [true, true, false];
+        assert_eq!(wizard.direction_change(), Some(1));
+
+        wizard.apply(&mut settings);
+        assert!(!settings.general.units_metric);
+        assert!(settings.general.onboarding_done);
+        assert_eq!(settings.connection.port_name, "/dev/ttyUSB0");
+        assert_eq!(settings.jog.travel.min[0], -400.0);
+        assert_eq!(settings.jog.travel.min[2], -90.0);
+        assert_eq!(settings.jog.travel.max, [0.0; 3]);
+    }
+}
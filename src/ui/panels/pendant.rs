@@ -0,0 +1,188 @@
+//! Pendant keypad
+//!
+//! A touch-screen layout for a display mounted at the machine: large jog,
+//! zero, run/hold/stop and override buttons with no editor, console or
+//! toolpath view.
+
+use super::run_screen::status_color;
+use super::RunScreenStatus;
+
+/// Override change per button press (%)
+const OVERRIDE_STEP: f64 = 10.0;
+
+/// Action requested from the pendant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PendantAction {
+    /// Jog by the selected step in the given direction per axis (-1, 0 or 1)
+    Jog([f64; 3]),
+    /// Select a jog step by index
+    SelectStep(usize),
+    /// Zero an axis of the work coordinates
+    Zero(char),
+    /// Zero all axes
+    ZeroAll,
+    /// Run the homing cycle
+    Home,
+    /// Clear an alarm lock
+    Unlock,
+    /// Start or resume the program
+    Start,
+    /// Feed hold
+    Hold,
+    /// Stop the program
+    Stop,
+    /// Set the feed override (%)
+    FeedOverride(f64),
+    /// Set the spindle override (%)
+    SpindleOverride(f64),
+    /// Leave pendant mode
+    Exit,
+}
+
+/// Full-window touch keypad
+#[derive(Debug, Clone, Default)]
+pub struct Pendant {
+    /// Whether pendant mode is active
+    pub open: bool,
+    /// Refresh the readout less often to spare a weak GPU
+    pub low_power: bool,
+}
+
+impl Pendant {
+    /// Show the pendant in place of the normal layout
+    ///
+    /// `steps` are the jog steps in display units, `step_index` the selected one.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        status: &RunScreenStatus,
+        steps: &[f64],
+        step_index: usize,
+    ) -> Option<PendantAction> {
+        let mut action = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let size = (ui.available_height() / 16.0).clamp(16.0, 48.0);
+            let button = egui::vec2(size * 3.2, size * 2.2);
+            ui.spacing_mut().item_spacing = egui::vec2(size * 0.3, size * 0.3);
+
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(status.status.to_string()).size(size).strong().color(status_color(status.status)));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_sized(egui::vec2(size * 5.0, size * 1.5), text_button("✖ Exit Pendant", size * 0.6)).clicked() {
+                        action = Some(PendantAction::Exit);
+                    }
+                });
+            });
+            ui.separator();
+
+            for (axis, value) in ['X', 'Y', 'Z'].into_iter().zip(status.position) {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(axis.to_string()).size(size * 1.4).monospace().weak());
+                    ui.label(egui::RichText::new(format!("{:>9.3}", value)).size(size * 1.4).monospace().strong());
+                    ui.label(egui::RichText::new(status.units).size(size * 0.6).weak());
+                    if ui.add_sized(egui::vec2(size * 4.0, size * 1.4), text_button(&format!("Zero {}", axis), size * 0.6)).clicked() {
+                        action = Some(PendantAction::Zero(axis));
+                    }
+                });
+            }
+            ui.separator();
+
+            ui.columns(2, |columns| {
+                // Jog pad
+                let ui = &mut columns[0];
+                let jogs = [
+                    [("↖", [-1.0, 1.0, 0.0]), ("↑", [0.0, 1.0, 0.0]), ("↗", [1.0, 1.0, 0.0]), ("Z+", [0.0, 0.0, 1.0])],
+                    [("←", [-1.0, 0.0, 0.0]), ("🏠", [0.0; 3]), ("→", [1.0, 0.0, 0.0]), ("", [0.0; 3])],
+                    [("↙", [-1.0, -1.0, 0.0]), ("↓", [0.0, -1.0, 0.0]), ("↘", [1.0, -1.0, 0.0]), ("Z-", [0.0, 0.0, -1.0])],
+                ];
+                egui::Grid::new("pendant_jog_grid").spacing([size * 0.3, size * 0.3]).show(ui, |ui| {
+                    for row in jogs {
+                        for (label, direction) in row {
+                            if label.is_empty() {
+                                ui.label("");
+                            } else if ui.add_sized(button, text_button(label, size)).clicked() {
+                                action = Some(if direction == [0.0; 3] {
+                                    PendantAction::Home
+                                } else {
+                                    PendantAction::Jog(direction)
+                                });
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.horizontal_wrapped(|ui| {
+                    for (index, step) in steps.iter().enumerate() {
+                        let text = egui::RichText::new(format!("{} {}", step, status.units)).size(size * 0.6);
+                        let selected = egui::SelectableLabel::new(index == step_index, text);
+                        if ui.add_sized(egui::vec2(size * 3.0, size * 1.4), selected).clicked() {
+                            action = Some(PendantAction::SelectStep(index));
+                        }
+                    }
+                });
+
+                // Program and machine controls
+                let ui = &mut columns[1];
+                ui.horizontal(|ui| {
+                    let (label, requested) = if status.active && !status.paused {
+                        ("⏸ Hold", PendantAction::Hold)
+                    } else {
+                        ("▶ Start", PendantAction::Start)
+                    };
+                    let run = text_button(label, size).fill(egui::Color32::from_rgb(40, 120, 40));
+                    if ui.add_sized(button, run).clicked() {
+                        action = Some(requested);
+                    }
+                    let stop = text_button("⏹ Stop", size).fill(egui::Color32::from_rgb(170, 30, 30));
+                    ui.add_enabled_ui(status.active, |ui| {
+                        if ui.add_sized(button, stop).clicked() {
+                            action = Some(PendantAction::Stop);
+                        }
+                    });
+                });
+                ui.horizontal(|ui| {
+                    if ui.add_sized(button, text_button("🔓 Unlock", size * 0.7)).clicked() {
+                        action = Some(PendantAction::Unlock);
+                    }
+                    if ui.add_sized(button, text_button("Zero All", size * 0.7)).clicked() {
+                        action = Some(PendantAction::ZeroAll);
+                    }
+                });
+                ui.add_space(size * 0.5);
+
+                let overrides = [
+                    ("Feed", status.feed_override, PendantAction::FeedOverride as fn(f64) -> PendantAction),
+                    ("Spindle", status.spindle_override, PendantAction::SpindleOverride),
+                ];
+                for (name, percent, set) in overrides {
+                    ui.label(egui::RichText::new(format!("{} {:.0}%", name, percent)).size(size * 0.7));
+                    ui.horizontal(|ui| {
+                        let small = egui::vec2(size * 2.6, size * 1.6);
+                        if ui.add_sized(small, text_button("−", size)).clicked() {
+                            action = Some(set((percent - OVERRIDE_STEP).max(10.0)));
+                        }
+                        if ui.add_sized(small, text_button("100%", size * 0.6)).clicked() {
+                            action = Some(set(100.0));
+                        }
+                        if ui.add_sized(small, text_button("+", size)).clicked() {
+                            action = Some(set((percent + OVERRIDE_STEP).min(200.0)));
+                        }
+                    });
+                }
+            });
+        });
+
+        // Keep the readout live while nothing else is drawn
+        ctx.request_repaint_after(super::readout_refresh(self.low_power));
+
+        if action == Some(PendantAction::Exit) {
+            self.open = false;
+        }
+        action
+    }
+}
+
+fn text_button(text: &str, size: f32) -> egui::Button<'static> {
+    egui::Button::new(egui::RichText::new(text).size(size))
+}
@@ -0,0 +1,146 @@
+//! Plugin manager
+//!
+//! Lists the plugins found in the plugins folder with what each one
+//! extends, and enables or disables them. Enabled plugins' side panels
+//! are drawn in the left panel below the user commands.
+
+use std::path::Path;
+
+use crate::script::{PanelItem, PluginHook, PluginHost};
+
+/// Action requested from the plugin manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginAction {
+    /// A plugin was enabled or disabled
+    Toggled,
+    /// Load the plugins folder again
+    Reload,
+}
+
+/// Window listing plugins
+#[derive(Debug, Clone, Default)]
+pub struct PluginManager {
+    /// Whether the window is open
+    pub open: bool,
+}
+
+impl PluginManager {
+    /// Show the window
+    pub fn show(&mut self, ctx: &egui::Context, host: &mut PluginHost, folder: Option<&Path>) -> Option<PluginAction> {
+        let mut action = None;
+        let mut open = self.open;
+
+        egui::Window::new("🧩 Plugins")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                if let Some(folder) = folder {
+                    ui.horizontal(|ui| {
+                        ui.label("Folder:");
+                        ui.monospace(folder.display().to_string());
+                        if ui.small_button("📋").on_hover_text("Copy path").clicked() {
+                            ui.ctx().copy_text(folder.display().to_string());
+                        }
+                    });
+                }
+                ui.separator();
+
+                if host.plugins().is_empty() {
+                    ui.weak("No plugins found. A plugin is a folder holding a plugin.toml and a Rhai script.");
+                }
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for index in 0..host.plugins().len() {
+                        let plugin = &host.plugins()[index];
+                        let mut enabled = plugin.enabled;
+                        let hooks: Vec<&str> = plugin.hooks.iter().map(PluginHook::label).collect();
+                        ui.group(|ui| {
+                            ui.set_width(ui.available_width());
+                            ui.horizontal(|ui| {
+                                if ui.checkbox(&mut enabled, egui::RichText::new(&plugin.manifest.name).strong()).changed() {
+                                    action = Some(PluginAction::Toggled);
+                                }
+                                if !plugin.manifest.version.is_empty() {
+                                    ui.weak(&plugin.manifest.version);
+                                }
+                                if !plugin.manifest.author.is_empty() {
+                                    ui.weak(format!("by {}", plugin.manifest.author));
+                                }
+                            });
+                            if !plugin.manifest.description.is_empty() {
+                                ui.label(&plugin.manifest.description);
+                            }
+                            if hooks.is_empty() {
+                                ui.weak("Adds nothing: defines none of the plugin functions");
+                            } else {
+                                ui.weak(hooks.join(" · "));
+                            }
+                            if let Some(error) = &plugin.error {
+                                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
+                            }
+                        });
+                        if enabled != host.plugins()[index].enabled {
+                            host.set_enabled(index, enabled);
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("🔄 Reload").on_hover_text("Load the plugins folder again").clicked() {
+                        action = Some(PluginAction::Reload);
+                    }
+                });
+                ui.weak("Plugins can send commands like scripts can; only enable ones you trust. \
+                    Connection wrappers apply from the next connection.");
+            });
+
+        self.open = open;
+        action
+    }
+
+    /// Draw the enabled plugins' side panels
+    pub fn show_panels(ui: &mut egui::Ui, host: &mut PluginHost) {
+        let panels: Vec<usize> = (0..host.plugins().len())
+            .filter(|&index| host.plugins()[index].provides(PluginHook::Panel))
+            .collect();
+        for index in panels {
+            let items = host.panel(index);
+            let plugin = &host.plugins()[index];
+            let mut clicked = None;
+            ui.group(|ui| {
+                ui.label(&plugin.manifest.name);
+                for item in &items {
+                    match item {
+                        PanelItem::Heading(text) => {
+                            ui.strong(text);
+                        }
+                        PanelItem::Label(text) => {
+                            ui.label(text);
+                        }
+                        PanelItem::Value { name, text } => {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}:", name));
+                                ui.monospace(text);
+                            });
+                        }
+                        PanelItem::Button { text, id } => {
+                            if ui.button(text).clicked() {
+                                clicked = Some(id.clone());
+                            }
+                        }
+                        PanelItem::Separator => {
+                            ui.separator();
+                        }
+                    }
+                }
+                if let Some(error) = &plugin.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
+                }
+            });
+            if let Some(id) = clicked {
+                host.click(index, &id);
+            }
+            ui.add_space(10.0);
+        }
+    }
+}
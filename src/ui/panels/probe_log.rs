@@ -0,0 +1,122 @@
+//! Probe log panel
+//!
+//! Lists probe results and manages the tool length offset derived from a
+//! reference tool.
+
+use crate::state::{ProbeLog, ToolLength};
+
+/// Request from the probe log panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeLogRequest {
+    /// Use this result as the reference tool measurement
+    SetReference(u64),
+    /// Apply the offset for the tool measured in this result
+    ApplyOffset(u64),
+    /// Cancel the tool length offset (G49)
+    ClearOffset,
+    /// Clear the log
+    Clear,
+}
+
+/// Panel listing probe results and the tool length offset
+#[derive(Debug, Clone, Default)]
+pub struct ProbeLogPanel {
+    /// Whether the panel is open
+    pub open: bool,
+}
+
+impl ProbeLogPanel {
+    /// Show the panel, returning the user's request
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        log: &ProbeLog,
+        tool_length: &ToolLength,
+        connected: bool,
+    ) -> Option<ProbeLogRequest> {
+        let mut request = None;
+
+        egui::Window::new("📍 Probe Log")
+            .open(&mut self.open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("tool_length_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Reference tool Z:");
+                        match tool_length.reference_z {
+                            Some(z) => ui.monospace(format!("{:.3}", z)),
+                            None => ui.weak("Not set"),
+                        };
+                        ui.end_row();
+
+                        ui.label("Tool length offset:");
+                        ui.horizontal(|ui| {
+                            ui.monospace(format!("{:.3}", tool_length.offset));
+                            if ui.add_enabled(connected, egui::Button::new("G49").small())
+                                .on_hover_text("Cancel the tool length offset")
+                                .clicked()
+                            {
+                                request = Some(ProbeLogRequest::ClearOffset);
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Results");
+                    if ui.add_enabled(!log.is_empty(), egui::Button::new("Clear").small()).clicked() {
+                        request = Some(ProbeLogRequest::Clear);
+                    }
+                });
+
+                if log.is_empty() {
+                    ui.weak("No probe results yet");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        egui::Grid::new("probe_log_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for record in log.entries().rev() {
+                                    ui.monospace(record.timestamp.format("%H:%M:%S").to_string());
+                                    let p = record.position;
+                                    let text = format!("X{:.3} Y{:.3} Z{:.3}", p.x, p.y, p.z);
+                                    if record.success {
+                                        ui.monospace(text);
+                                    } else {
+                                        ui.colored_label(egui::Color32::RED, format!("{} (no contact)", text));
+                                    }
+                                    ui.label(if record.tool_measurement { "Tool" } else { "" });
+
+                                    ui.horizontal(|ui| {
+                                        ui.add_enabled_ui(record.success, |ui| {
+                                            if ui.small_button("Ref").on_hover_text("Use as the reference tool").clicked() {
+                                                request = Some(ProbeLogRequest::SetReference(record.id));
+                                            }
+                                            let can_apply = connected && tool_length.reference_z.is_some();
+                                            if ui.add_enabled(can_apply, egui::Button::new("TLO").small())
+                                                .on_hover_text("Apply the tool length offset for this tool")
+                                                .clicked()
+                                            {
+                                                request = Some(ProbeLogRequest::ApplyOffset(record.id));
+                                            }
+                                        });
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        request
+    }
+}
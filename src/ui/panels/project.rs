@@ -0,0 +1,201 @@
+//! Project dialog
+//!
+//! Edits the open project: notes, programs, heightmap, tools and work
+//! offsets.
+
+use std::path::PathBuf;
+
+use crate::settings::{Project, ProjectTool};
+
+/// Action requested from the project dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectAction {
+    /// Save the project to its file
+    Save,
+    /// Save the project to a new file
+    SaveAs,
+    /// Add the program in the editor
+    AddCurrentProgram,
+    /// Load a program into the editor
+    LoadProgram(usize),
+    /// Attach a heightmap file
+    AttachHeightmap,
+    /// Record the current work offsets
+    CaptureOffsets,
+    /// Send the stored work offsets to the controller
+    ApplyOffsets,
+}
+
+/// Dialog for the open project
+#[derive(Debug, Clone, Default)]
+pub struct ProjectDialog {
+    /// Whether the dialog is open
+    pub open: bool,
+    /// The project being edited
+    pub project: Project,
+    /// File the project was loaded from or saved to
+    pub path: Option<PathBuf>,
+    /// Whether the project changed since it was last saved
+    pub modified: bool,
+}
+
+impl ProjectDialog {
+    /// Start editing a project
+    pub fn set_project(&mut self, project: Project, path: Option<PathBuf>) {
+        self.project = project;
+        self.path = path;
+        self.modified = false;
+        self.open = true;
+    }
+
+    /// Show the dialog, returning the action the user requested
+    pub fn show(&mut self, ctx: &egui::Context, connected: bool) -> Option<ProjectAction> {
+        let mut open = self.open;
+        let mut action = None;
+        let mut changed = false;
+
+        let title = if self.modified { "🗂 Project *" } else { "🗂 Project" };
+        egui::Window::new(title)
+            .id(egui::Id::new("project_dialog"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("project_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        changed |= ui.text_edit_singleline(&mut self.project.name).changed();
+                        ui.end_row();
+
+                        ui.label("File:");
+                        match &self.path {
+                            Some(path) => ui.label(path.display().to_string()),
+                            None => ui.weak("Not saved"),
+                        };
+                        ui.end_row();
+                    });
+
+                ui.label("Notes:");
+                changed |= ui
+                    .add(egui::TextEdit::multiline(&mut self.project.notes).desired_rows(3).desired_width(f32::INFINITY))
+                    .changed();
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Programs");
+                    if ui.small_button("➕ Add Current").clicked() {
+                        action = Some(ProjectAction::AddCurrentProgram);
+                    }
+                });
+                let mut remove_program = None;
+                for (index, program) in self.project.programs.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. {} ({} lines)", index + 1, program.name, program.gcode.lines().count()));
+                        if ui.small_button("📂 Load").clicked() {
+                            action = Some(ProjectAction::LoadProgram(index));
+                        }
+                        if ui.small_button("🗑").clicked() {
+                            remove_program = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = remove_program {
+                    self.project.programs.remove(index);
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Heightmap:");
+                    match &self.project.heightmap {
+                        Some(heightmap) => {
+                            ui.label(&heightmap.name);
+                            if ui.small_button("🗑").clicked() {
+                                self.project.heightmap = None;
+                                changed = true;
+                            }
+                        }
+                        None => {
+                            ui.weak("None");
+                        }
+                    }
+                    if ui.small_button("📎 Attach...").clicked() {
+                        action = Some(ProjectAction::AttachHeightmap);
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Tools");
+                    if ui.small_button("➕ Add").clicked() {
+                        let number = self.project.tools.iter().map(|t| t.number).max().unwrap_or(0) + 1;
+                        self.project.tools.push(ProjectTool {
+                            number,
+                            diameter: 6.0,
+                            flutes: 2,
+                            description: String::new(),
+                        });
+                        changed = true;
+                    }
+                });
+                let mut remove_tool = None;
+                egui::Grid::new("project_tools_grid")
+                    .num_columns(5)
+                    .spacing([6.0, 4.0])
+                    .show(ui, |ui| {
+                        for (index, tool) in self.project.tools.iter_mut().enumerate() {
+                            changed |= ui.add(egui::DragValue::new(&mut tool.number).prefix("T")).changed();
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut tool.diameter).speed(0.1).range(0.0..=100.0).suffix(" mm"))
+                                .changed();
+                            changed |= ui.add(egui::DragValue::new(&mut tool.flutes).range(1..=8).suffix(" fl")).changed();
+                            changed |= ui.text_edit_singleline(&mut tool.description).changed();
+                            if ui.small_button("🗑").clicked() {
+                                remove_tool = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = remove_tool {
+                    self.project.tools.remove(index);
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Work offsets");
+                    if ui.small_button("📥 Capture Current").clicked() {
+                        action = Some(ProjectAction::CaptureOffsets);
+                    }
+                    let apply = ui.add_enabled(
+                        connected && !self.project.work_offsets.is_empty(),
+                        egui::Button::new("📤 Apply to Controller").small(),
+                    );
+                    if apply.clicked() {
+                        action = Some(ProjectAction::ApplyOffsets);
+                    }
+                });
+                for offset in &self.project.work_offsets {
+                    ui.monospace(format!(
+                        "{}  X{:>9.3}  Y{:>9.3}  Z{:>9.3}",
+                        offset.system, offset.offset.x, offset.offset.y, offset.offset.z
+                    ));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save").clicked() {
+                        action = Some(ProjectAction::Save);
+                    }
+                    if ui.button("💾 Save As...").clicked() {
+                        action = Some(ProjectAction::SaveAs);
+                    }
+                });
+            });
+
+        self.open = open;
+        self.modified |= changed;
+        action
+    }
+}
@@ -0,0 +1,67 @@
+//! Recovery log panel
+//!
+//! Lists the alarm recovery policies that fired, including those only
+//! logged in dry-run mode.
+
+use crate::state::RecoveryEngine;
+
+/// Panel listing applied recovery policies
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryLogPanel {
+    /// Whether the panel is open
+    pub open: bool,
+}
+
+impl RecoveryLogPanel {
+    /// Show the panel, returning whether the user asked to clear the log
+    pub fn show(&mut self, ctx: &egui::Context, engine: &RecoveryEngine) -> bool {
+        let mut clear = false;
+
+        egui::Window::new("🛟 Recovery Log")
+            .open(&mut self.open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if engine.settings().dry_run {
+                        ui.colored_label(egui::Color32::from_rgb(220, 160, 0), "Dry run: steps are logged, not sent");
+                    } else {
+                        ui.label("Policies are active");
+                    }
+                    if ui.button("Clear").clicked() {
+                        clear = true;
+                    }
+                });
+                ui.separator();
+
+                if engine.log().next().is_none() {
+                    ui.weak("No recovery policy has fired yet");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        egui::Grid::new("recovery_log_grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for record in engine.log().rev() {
+                                    ui.monospace(record.timestamp.format("%H:%M:%S").to_string());
+                                    ui.label(record.trigger.to_string());
+                                    ui.vertical(|ui| {
+                                        for step in &record.steps {
+                                            let text = egui::RichText::new(step.to_string());
+                                            ui.label(if record.executed { text } else { text.italics().weak() });
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        clear
+    }
+}
@@ -0,0 +1,116 @@
+//! GRBL reference browser
+//!
+//! Searchable view of the built-in reference: `$` settings, error and
+//! alarm codes and troubleshooting steps. Works offline; opened from the
+//! Help menu or from the error details for a GRBL code.
+
+use crate::grbl::{ReferenceEntry, ReferenceTopic};
+
+/// Searchable GRBL reference window
+#[derive(Debug, Clone, Default)]
+pub struct ReferencePanel {
+    /// Whether the panel is open
+    pub open: bool,
+    query: String,
+    /// Section shown, or all of them
+    topic: Option<ReferenceTopic>,
+    selected: Option<ReferenceEntry>,
+    /// Scroll the list to the selected entry on the next frame
+    reveal: bool,
+}
+
+impl ReferencePanel {
+    /// Open the panel at a setting, error or alarm
+    pub fn show_code(&mut self, topic: ReferenceTopic, code: u32) {
+        self.open = true;
+        self.query.clear();
+        self.topic = Some(topic);
+        self.selected = ReferenceEntry::lookup(topic, code);
+        self.reveal = true;
+    }
+
+    /// Open the panel at the start of a section
+    pub fn show_topic(&mut self, topic: ReferenceTopic) {
+        self.open = true;
+        self.query.clear();
+        self.topic = Some(topic);
+        self.selected = None;
+    }
+
+    /// Show the panel
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+
+        egui::Window::new("📖 GRBL Reference")
+            .open(&mut open)
+            .default_size([520.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.add(egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("e.g. $110, error:9, limit, baud")
+                        .desired_width(260.0));
+                    if !self.query.is_empty() && ui.small_button("✕").clicked() {
+                        self.query.clear();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.topic, None, "All");
+                    for topic in ReferenceTopic::ALL {
+                        ui.selectable_value(&mut self.topic, Some(topic), topic.to_string());
+                    }
+                });
+                ui.separator();
+
+                let entries: Vec<ReferenceEntry> = ReferenceEntry::all()
+                    .into_iter()
+                    .filter(|entry| self.topic.map_or(true, |topic| entry.topic == topic))
+                    .filter(|entry| entry.matches(&self.query))
+                    .collect();
+
+                egui::ScrollArea::vertical()
+                    .id_source("reference_list")
+                    .max_height(220.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        if entries.is_empty() {
+                            ui.weak("Nothing found");
+                        }
+                        for entry in &entries {
+                            let text = match entry.code {
+                                Some(_) => format!("{}  {}", entry.key(), entry.title),
+                                None => entry.title.to_string(),
+                            };
+                            let row = ui.selectable_label(self.selected == Some(*entry), text);
+                            if self.reveal && self.selected == Some(*entry) {
+                                row.scroll_to_me(Some(egui::Align::Center));
+                            }
+                            if row.clicked() {
+                                self.selected = Some(*entry);
+                            }
+                        }
+                    });
+                self.reveal = false;
+                ui.separator();
+
+                match &self.selected {
+                    Some(entry) => {
+                        ui.horizontal(|ui| {
+                            if entry.code.is_some() {
+                                ui.monospace(entry.key());
+                            }
+                            ui.strong(entry.title);
+                        });
+                        ui.weak(entry.topic.to_string());
+                        ui.add_space(4.0);
+                        ui.label(entry.text);
+                    }
+                    None => {
+                        ui.weak("Select an entry to see what it means and what to check.");
+                    }
+                }
+            });
+
+        self.open = open;
+    }
+}
@@ -0,0 +1,196 @@
+//! Run screen
+//!
+//! A distraction-free full-window view for watching a running job from
+//! across the shop: large position readout, progress, times, feed and
+//! spindle values, and hold/stop controls.
+
+use crate::state::MachineStatus;
+use crate::ui::widgets::ProgressMap;
+
+/// Snapshot of the values shown on the run screen
+#[derive(Debug, Clone, Default)]
+pub struct RunScreenStatus {
+    /// Machine status
+    pub status: MachineStatus,
+    /// Work position (X, Y, Z)
+    pub position: [f64; 3],
+    /// Lines completed
+    pub current_line: usize,
+    /// Total program lines
+    pub total_lines: usize,
+    /// Elapsed time text
+    pub elapsed: String,
+    /// Remaining time text
+    pub remaining: String,
+    /// Current feed rate
+    pub feed_rate: f64,
+    /// Current spindle speed
+    pub spindle_speed: f64,
+    /// Feed override percentage
+    pub feed_override: f64,
+    /// Spindle override percentage
+    pub spindle_override: f64,
+    /// Whether the program is paused
+    pub paused: bool,
+    /// Whether the program is running or paused
+    pub active: bool,
+    /// Linear units label (e.g. "mm")
+    pub units: &'static str,
+}
+
+impl RunScreenStatus {
+    /// Progress through the program (0.0 to 1.0)
+    pub fn progress(&self) -> f32 {
+        if self.total_lines == 0 {
+            0.0
+        } else {
+            (self.current_line as f32 / self.total_lines as f32).min(1.0)
+        }
+    }
+}
+
+/// Action requested from the run screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunScreenAction {
+    /// Feed hold
+    Hold,
+    /// Resume after a hold
+    Resume,
+    /// Stop the program
+    Stop,
+    /// Leave the run screen
+    Exit,
+}
+
+/// Full-window run screen
+#[derive(Debug, Clone, Default)]
+pub struct RunScreen {
+    /// Whether the run screen is shown
+    pub open: bool,
+    /// Keep the window above others while the run screen is shown
+    pub always_on_top: bool,
+    /// Refresh the readout less often to spare a weak GPU
+    pub low_power: bool,
+}
+
+impl RunScreen {
+    /// Show the run screen in place of the normal layout
+    ///
+    /// `map` is the top view of the running program. Escape leaves the run
+    /// screen.
+    pub fn show(&mut self, ctx: &egui::Context, status: &RunScreenStatus, map: Option<&ProgressMap>) -> Option<RunScreenAction> {
+        let mut action = None;
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            action = Some(RunScreenAction::Exit);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let height = ui.available_height();
+            let dro_size = (height / 9.0).clamp(24.0, 160.0);
+            let text_size = (dro_size / 3.0).max(14.0);
+
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(format!("{}", status.status)).size(text_size).strong().color(status_color(status.status)));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button(egui::RichText::new("✖ Exit Run Screen").size(text_size * 0.6)).clicked() {
+                        action = Some(RunScreenAction::Exit);
+                    }
+                    ui.checkbox(&mut self.always_on_top, egui::RichText::new("📌 On top").size(text_size * 0.6))
+                        .on_hover_text("Keep the window above other windows while the run screen is shown");
+                });
+            });
+
+            ui.separator();
+
+            for (axis, value) in ["X", "Y", "Z"].iter().zip(status.position) {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(*axis).size(dro_size).monospace().weak());
+                    ui.label(egui::RichText::new(format!("{:>10.3}", value)).size(dro_size).monospace().strong());
+                    ui.label(egui::RichText::new(status.units).size(text_size).weak());
+                });
+            }
+
+            ui.separator();
+
+            ui.add(
+                egui::ProgressBar::new(status.progress())
+                    .text(egui::RichText::new(format!(
+                        "{:.1}%  ({} / {})",
+                        status.progress() * 100.0,
+                        status.current_line,
+                        status.total_lines
+                    )).size(text_size * 0.8))
+                    .desired_height(text_size * 1.5),
+            );
+
+            if let Some(map) = map.filter(|_| status.active) {
+                ui.add_space(text_size * 0.5);
+                let size = egui::vec2(ui.available_width().min(text_size * 16.0), text_size * 5.0);
+                map.show(ui, size, status.current_line, [status.position[0], status.position[1]]);
+            }
+
+            ui.add_space(text_size * 0.5);
+            egui::Grid::new("run_screen_grid")
+                .num_columns(4)
+                .spacing([text_size * 2.0, text_size * 0.4])
+                .show(ui, |ui| {
+                    big_label(ui, "Elapsed", &status.elapsed, text_size);
+                    big_label(ui, "Remaining", &status.remaining, text_size);
+                    ui.end_row();
+
+                    big_label(ui, "Feed", &format!("{:.0} ({:.0}%)", status.feed_rate, status.feed_override), text_size);
+                    big_label(ui, "Spindle", &format!("{:.0} ({:.0}%)", status.spindle_speed, status.spindle_override), text_size);
+                    ui.end_row();
+                });
+
+            ui.add_space(text_size);
+            ui.horizontal(|ui| {
+                let button_size = egui::vec2(text_size * 8.0, text_size * 2.5);
+                ui.add_enabled_ui(status.active, |ui| {
+                    let (label, requested) = if status.paused {
+                        ("▶ Resume", RunScreenAction::Resume)
+                    } else {
+                        ("⏸ Hold", RunScreenAction::Hold)
+                    };
+                    let hold = egui::Button::new(egui::RichText::new(label).size(text_size))
+                        .fill(egui::Color32::from_rgb(160, 120, 0));
+                    if ui.add_sized(button_size, hold).clicked() {
+                        action = Some(requested);
+                    }
+
+                    let stop = egui::Button::new(egui::RichText::new("⏹ Stop").size(text_size))
+                        .fill(egui::Color32::from_rgb(170, 30, 30));
+                    if ui.add_sized(button_size, stop).clicked() {
+                        action = Some(RunScreenAction::Stop);
+                    }
+                });
+            });
+        });
+
+        // Keep the readout live while nothing else is drawn
+        ctx.request_repaint_after(super::readout_refresh(self.low_power));
+
+        if action == Some(RunScreenAction::Exit) {
+            self.open = false;
+        }
+        action
+    }
+}
+
+/// Caption and value pair for the run screen grid
+fn big_label(ui: &mut egui::Ui, caption: &str, value: &str, size: f32) {
+    ui.label(egui::RichText::new(caption).size(size * 0.7).weak());
+    ui.label(egui::RichText::new(value).size(size).monospace().strong());
+}
+
+/// Colour for a machine status
+pub(super) fn status_color(status: MachineStatus) -> egui::Color32 {
+    match status {
+        MachineStatus::Idle => egui::Color32::GREEN,
+        MachineStatus::Run => egui::Color32::LIGHT_BLUE,
+        MachineStatus::Hold => egui::Color32::YELLOW,
+        MachineStatus::Alarm => egui::Color32::RED,
+        _ => egui::Color32::GRAY,
+    }
+}
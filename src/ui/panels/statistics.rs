@@ -0,0 +1,118 @@
+//! Program statistics panel
+//!
+//! Shows the G and M codes a program uses, its feeds and spindle speeds,
+//! tool changes and motion breakdown, for checking post-processor output.
+
+use crate::parser::{ProgramStatistics, Segment};
+
+/// Panel summarizing the program in the editor
+#[derive(Debug, Clone, Default)]
+pub struct StatisticsPanel {
+    /// Whether the panel is open
+    pub open: bool,
+    /// Program text, segment count and statistics of the last refresh
+    cache: Option<(String, usize, ProgramStatistics)>,
+}
+
+impl StatisticsPanel {
+    /// Statistics for the program, reusing the last ones when nothing changed
+    fn statistics(&mut self, program: &str, segments: &[Segment]) -> &ProgramStatistics {
+        let stale = !matches!(&self.cache, Some((text, count, _)) if text == program && *count == segments.len());
+        if stale {
+            let stats = ProgramStatistics::new(program, segments);
+            self.cache = Some((program.to_string(), segments.len(), stats));
+        }
+        &self.cache.as_ref().expect("statistics were just computed").2
+    }
+
+    /// Show the panel
+    pub fn show(&mut self, ctx: &egui::Context, program: &str, segments: &[Segment]) {
+        let mut open = self.open;
+
+        egui::Window::new("📊 Program Statistics")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                if program.trim().is_empty() {
+                    ui.weak("No program loaded");
+                    return;
+                }
+                let stats = self.statistics(program, segments).clone();
+
+                egui::Grid::new("statistics_summary_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Lines:");
+                        ui.monospace(program.lines().count().to_string());
+                        ui.end_row();
+                        ui.label("Tool changes:");
+                        ui.monospace(stats.tool_changes.to_string());
+                        ui.end_row();
+                        ui.label("Feed:");
+                        ui.monospace(range_text(stats.feed_range()));
+                        ui.end_row();
+                        ui.label("Spindle:");
+                        ui.monospace(range_text(stats.spindle_range()));
+                        ui.end_row();
+                        ui.label("Moves:");
+                        ui.monospace(format!("{} rapid, {} line, {} arc", stats.rapids, stats.lines, stats.arcs));
+                        ui.end_row();
+                        let total = stats.rapid_distance + stats.cutting_distance;
+                        let share = |distance: f64| if total > 0.0 { distance / total * 100.0 } else { 0.0 };
+                        ui.label("Rapid distance:");
+                        ui.monospace(format!("{:.1} ({:.0}%)", stats.rapid_distance, share(stats.rapid_distance)));
+                        ui.end_row();
+                        ui.label("Cutting distance:");
+                        ui.monospace(format!("{:.1} ({:.0}%)", stats.cutting_distance, share(stats.cutting_distance)));
+                        ui.end_row();
+                    });
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    code_table(ui, "G codes", "statistics_g_grid", &stats.g_codes);
+                    code_table(ui, "M codes", "statistics_m_grid", &stats.m_codes);
+                    egui::CollapsingHeader::new(format!("Feeds ({})", stats.feeds.len()))
+                        .id_source("statistics_feeds")
+                        .show(ui, |ui| {
+                            let feeds: Vec<String> = stats.feeds.iter().map(|feed| format!("F{}", feed)).collect();
+                            ui.horizontal_wrapped(|ui| {
+                                for feed in feeds {
+                                    ui.monospace(feed);
+                                }
+                            });
+                        });
+                });
+            });
+
+        self.open = open;
+        if !self.open {
+            self.cache = None;
+        }
+    }
+}
+
+/// Counts of each code under a collapsible header
+fn code_table(ui: &mut egui::Ui, title: &str, id: &str, codes: &[(String, usize)]) {
+    egui::CollapsingHeader::new(format!("{} ({})", title, codes.len()))
+        .id_source(title)
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new(id).num_columns(2).striped(true).show(ui, |ui| {
+                for (code, uses) in codes {
+                    ui.monospace(code);
+                    ui.monospace(uses.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// "min – max", a single value, or a dash when there are none
+fn range_text(range: Option<(f64, f64)>) -> String {
+    match range {
+        Some((min, max)) if min == max => format!("{}", min),
+        Some((min, max)) => format!("{} – {}", min, max),
+        None => "—".to_string(),
+    }
+}
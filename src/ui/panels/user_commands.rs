@@ -0,0 +1,243 @@
+//! User commands panel
+//!
+//! Shows the user command buttons grouped into collapsible categories. In
+//! layout mode the buttons can be dragged into a new order and given an
+//! icon, color, category and keyboard shortcut.
+
+use crate::script::{CommandContext, UserCommand, UserCommandLibrary};
+
+/// Action requested from the user commands panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserCommandsAction {
+    /// Run the named command
+    Run(String),
+    /// Add a new command
+    Add,
+    /// The layout changed and the library should be saved
+    Save,
+}
+
+/// Parse a shortcut such as "Ctrl+Shift+C" or "Alt+F5"
+///
+/// "Ctrl" and "Cmd" both map to the platform command modifier.
+pub fn parse_shortcut(text: &str) -> Option<egui::KeyboardShortcut> {
+    let mut modifiers = egui::Modifiers::NONE;
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = egui::Key::from_name(parts.pop()?)?;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => modifiers = modifiers | egui::Modifiers::COMMAND,
+            "shift" => modifiers = modifiers | egui::Modifiers::SHIFT,
+            "alt" | "option" => modifiers = modifiers | egui::Modifiers::ALT,
+            _ => return None,
+        }
+    }
+    Some(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+/// Panel listing the user command buttons
+#[derive(Debug, Clone, Default)]
+pub struct UserCommandsPanel {
+    /// Whether the layout is being edited
+    pub editing: bool,
+    /// Command whose appearance is being edited
+    selected: Option<usize>,
+    /// Whether the layout changed since editing started
+    modified: bool,
+}
+
+impl UserCommandsPanel {
+    /// Command triggered by a keyboard shortcut this frame, if any
+    ///
+    /// Shortcuts are ignored while the layout is being edited so typing a
+    /// new shortcut does not run the command.
+    pub fn shortcut_pressed(&self, ctx: &egui::Context, library: &UserCommandLibrary) -> Option<String> {
+        if self.editing {
+            return None;
+        }
+        library.commands.iter().find_map(|command| {
+            let shortcut = parse_shortcut(command.shortcut.as_deref()?)?;
+            ctx.input_mut(|i| i.consume_shortcut(&shortcut))
+                .then(|| command.name.clone())
+        })
+    }
+
+    /// Show the panel, returning the action the user requested
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        library: &mut UserCommandLibrary,
+        context: &CommandContext,
+    ) -> Option<UserCommandsAction> {
+        let mut action = None;
+        let mut moved: Option<(usize, usize)> = None;
+
+        ui.horizontal(|ui| {
+            ui.label("User Commands");
+            if ui.button("➕").on_hover_text("New command").clicked() {
+                action = Some(UserCommandsAction::Add);
+            }
+            let label = if self.editing { "✔ Done" } else { "✏ Layout" };
+            if ui.small_button(label).clicked() {
+                self.editing = !self.editing;
+                self.selected = None;
+                if !self.editing && std::mem::take(&mut self.modified) {
+                    action = Some(UserCommandsAction::Save);
+                }
+            }
+        });
+        if self.editing {
+            ui.weak("Drag buttons to reorder, click one to edit it");
+        }
+
+        ui.separator();
+
+        for category in library.categories() {
+            let collapsed = library.is_collapsed(&category);
+            let header = egui::CollapsingHeader::new(&category)
+                .id_source(("user_command_category", &category))
+                .open(Some(!collapsed))
+                .show(ui, |ui| {
+                    for (index, command) in library.commands_by_category(&category) {
+                        if self.editing {
+                            let id = egui::Id::new(("user_command_drag", index));
+                            let selected = self.selected == Some(index);
+                            let drag = ui.dnd_drag_source(id, index, |ui| {
+                                ui.add(Self::button(command).selected(selected))
+                            });
+                            if let Some(from) = drag.response.dnd_release_payload::<usize>() {
+                                moved = Some((*from, index));
+                            } else if drag.inner.clicked() {
+                                self.selected = Some(index);
+                            }
+                        } else {
+                            let unmet = command.unmet_condition(context);
+                            let mut response = ui.add_enabled(unmet.is_none(), Self::button(command));
+                            if !command.description.is_empty() {
+                                response = response.on_hover_text(&command.description);
+                            }
+                            if let Some(condition) = unmet {
+                                response = response.on_disabled_hover_text(format!("Requires: {}", condition.label()));
+                            }
+                            if response.clicked() {
+                                action = Some(UserCommandsAction::Run(command.name.clone()));
+                            }
+                        }
+                    }
+                });
+            if header.header_response.clicked() {
+                library.set_collapsed(&category, !collapsed);
+                if self.editing {
+                    self.modified = true;
+                } else {
+                    action = Some(UserCommandsAction::Save);
+                }
+            }
+            ui.add_space(3.0);
+        }
+
+        if let Some((from, to)) = moved {
+            if library.move_command(from, to) {
+                self.selected = Some(to);
+                self.modified = true;
+            }
+        }
+
+        if self.editing {
+            if let Some(command) = self.selected.and_then(|index| library.commands.get_mut(index)) {
+                ui.separator();
+                self.modified |= Self::edit_appearance(ui, command);
+            }
+        }
+
+        action
+    }
+
+    /// Button for a command with its icon, color and shortcut hint
+    fn button(command: &UserCommand) -> egui::Button<'static> {
+        let mut button = egui::Button::new(command.label());
+        if let Some([r, g, b]) = command.color {
+            button = button.fill(egui::Color32::from_rgb(r, g, b));
+        }
+        if let Some(shortcut) = command.shortcut.as_deref().filter(|s| parse_shortcut(s).is_some()) {
+            button = button.shortcut_text(shortcut.to_string());
+        }
+        button
+    }
+
+    /// Edit the icon, color, category and shortcut of a command
+    ///
+    /// Returns whether anything changed.
+    fn edit_appearance(ui: &mut egui::Ui, command: &mut UserCommand) -> bool {
+        let mut changed = false;
+        ui.strong(&command.name);
+        egui::Grid::new("user_command_appearance")
+            .num_columns(2)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Icon:");
+                let mut icon = command.icon.clone().unwrap_or_default();
+                if ui.add(egui::TextEdit::singleline(&mut icon).desired_width(40.0)).changed() {
+                    command.icon = (!icon.trim().is_empty()).then_some(icon);
+                    changed = true;
+                }
+                ui.end_row();
+
+                ui.label("Color:");
+                ui.horizontal(|ui| {
+                    let mut custom = command.color.is_some();
+                    if ui.checkbox(&mut custom, "Custom").changed() {
+                        command.color = custom.then_some([90, 90, 90]);
+                        changed = true;
+                    }
+                    if let Some(color) = command.color.as_mut() {
+                        changed |= ui.color_edit_button_srgb(color).changed();
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Category:");
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut command.category).desired_width(120.0))
+                    .changed();
+                ui.end_row();
+
+                ui.label("Shortcut:");
+                ui.horizontal(|ui| {
+                    let mut shortcut = command.shortcut.clone().unwrap_or_default();
+                    if ui
+                        .add(egui::TextEdit::singleline(&mut shortcut).hint_text("Ctrl+Shift+1").desired_width(120.0))
+                        .changed()
+                    {
+                        command.shortcut = (!shortcut.trim().is_empty()).then_some(shortcut);
+                        changed = true;
+                    }
+                    if command.shortcut.as_deref().is_some_and(|s| parse_shortcut(s).is_none()) {
+                        ui.colored_label(egui::Color32::from_rgb(220, 120, 0), "⚠ Not recognized");
+                    }
+                });
+                ui.end_row();
+            });
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shortcut() {
+        let shortcut = parse_shortcut("Ctrl+Shift+C").unwrap();
+        assert_eq!(shortcut.logical_key, egui::Key::C);
+        assert!(shortcut.modifiers.command && shortcut.modifiers.shift && !shortcut.modifiers.alt);
+
+        let shortcut = parse_shortcut("Alt + F5").unwrap();
+        assert_eq!(shortcut.logical_key, egui::Key::F5);
+        assert!(shortcut.modifiers.alt && !shortcut.modifiers.command);
+
+        assert!(parse_shortcut("Ctrl+Hyper+C").is_none());
+        assert!(parse_shortcut("Ctrl+").is_none());
+        assert!(parse_shortcut("").is_none());
+    }
+}
@@ -2,7 +2,14 @@
 //!
 //! This module contains custom egui widgets including G-Code editor and console.
 
+use crate::parser::{operations, Operation, Segment};
+use egui::text::{LayoutJob, TextFormat};
 use egui::{Color32, RichText, ScrollArea, TextEdit, Ui};
+use regex::{Regex, RegexBuilder};
+use std::collections::{BTreeSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
 use std::ops::Range;
 
 /// G-Code editor mode
@@ -41,6 +48,24 @@ pub struct GCodeEditor {
     pub find_replace: FindReplaceState,
     /// Whether to show line numbers
     pub show_line_numbers: bool,
+    /// Bookmarked lines (0-based)
+    pub bookmarks: BTreeSet<usize>,
+    /// Line the bookmark shortcuts operate on (0-based)
+    pub cursor_line: usize,
+    /// Line to scroll into view on the next frame
+    scroll_to_line: Option<usize>,
+    /// Whether bookmarks changed since last checked
+    bookmarks_changed: bool,
+    /// Lines streaming pauses before (0-based)
+    pub breakpoints: BTreeSet<usize>,
+    /// First lines of the folded operations
+    pub folded: BTreeSet<usize>,
+    /// Show the operations navigator
+    pub show_navigator: bool,
+    /// Operations of the content, found again when the content changes
+    outline: Vec<Operation>,
+    /// Hash of the content the outline was found in
+    outline_hash: Option<u64>,
 }
 
 impl Default for GCodeEditor {
@@ -50,6 +75,15 @@ impl Default for GCodeEditor {
             current_line: None,
             find_replace: FindReplaceState::default(),
             show_line_numbers: true,
+            bookmarks: BTreeSet::new(),
+            cursor_line: 0,
+            scroll_to_line: None,
+            bookmarks_changed: false,
+            breakpoints: BTreeSet::new(),
+            folded: BTreeSet::new(),
+            show_navigator: true,
+            outline: Vec::new(),
+            outline_hash: None,
         }
     }
 }
@@ -77,8 +111,97 @@ impl GCodeEditor {
         self.find_replace.show_panel = !self.find_replace.show_panel;
     }
 
+    /// Replace the bookmarks (e.g. when loading a file's sidecar metadata)
+    pub fn set_bookmarks(&mut self, bookmarks: BTreeSet<usize>) {
+        self.bookmarks = bookmarks;
+        self.cursor_line = 0;
+        self.bookmarks_changed = false;
+    }
+
+    /// Toggle a bookmark on a line
+    pub fn toggle_bookmark(&mut self, line: usize) {
+        if !self.bookmarks.remove(&line) {
+            self.bookmarks.insert(line);
+        }
+        self.cursor_line = line;
+        self.bookmarks_changed = true;
+    }
+
+    /// Toggle a bookmark on the cursor line
+    pub fn toggle_bookmark_at_cursor(&mut self) {
+        self.toggle_bookmark(self.cursor_line);
+    }
+
+    /// Toggle a breakpoint on a line
+    pub fn toggle_breakpoint(&mut self, line: usize) {
+        if !self.breakpoints.remove(&line) {
+            self.breakpoints.insert(line);
+        }
+        self.cursor_line = line;
+    }
+
+    /// Toggle a breakpoint on the cursor line
+    pub fn toggle_breakpoint_at_cursor(&mut self) {
+        self.toggle_breakpoint(self.cursor_line);
+    }
+
+    /// Move the cursor to the next bookmark, wrapping around
+    pub fn next_bookmark(&mut self) -> Option<usize> {
+        let line = self
+            .bookmarks
+            .range(self.cursor_line + 1..)
+            .next()
+            .or_else(|| self.bookmarks.iter().next())
+            .copied()?;
+        self.go_to_line(line);
+        Some(line)
+    }
+
+    /// Move the cursor to the previous bookmark, wrapping around
+    pub fn prev_bookmark(&mut self) -> Option<usize> {
+        let line = self
+            .bookmarks
+            .range(..self.cursor_line)
+            .next_back()
+            .or_else(|| self.bookmarks.iter().next_back())
+            .copied()?;
+        self.go_to_line(line);
+        Some(line)
+    }
+
+    /// Move the cursor to a line and scroll it into view
+    pub fn go_to_line(&mut self, line: usize) {
+        self.cursor_line = line;
+        self.scroll_to_line = Some(line);
+        // Unfold the operation hiding the line
+        if let Some(operation) = self.outline.iter().find(|op| op.contains(line) && op.start != line) {
+            self.folded.remove(&operation.start);
+        }
+    }
+
+    /// Find the operations again if the content changed
+    fn refresh_outline(&mut self, content: &str) {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.outline_hash == Some(hash) {
+            return;
+        }
+        self.outline_hash = Some(hash);
+        self.outline = operations(content);
+        let starts: BTreeSet<usize> = self.outline.iter().map(|op| op.start).collect();
+        self.folded.retain(|start| starts.contains(start));
+    }
+
+    /// Check and clear whether bookmarks changed since the last call
+    pub fn take_bookmarks_changed(&mut self) -> bool {
+        std::mem::take(&mut self.bookmarks_changed)
+    }
+
     /// Show the G-Code editor UI
     pub fn show(&mut self, ui: &mut Ui, content: &mut String) {
+        self.refresh_outline(content);
+        
         ui.horizontal(|ui| {
             ui.label("Mode:");
             if ui.selectable_label(self.mode == EditorMode::View, "View").clicked() {
@@ -93,6 +216,11 @@ impl GCodeEditor {
             if ui.button("🔍 Find").clicked() {
                 self.toggle_find_replace();
             }
+            
+            if !self.outline.is_empty() {
+                ui.toggle_value(&mut self.show_navigator, "☰ Operations")
+                    .on_hover_text("List the program's operations to jump between");
+            }
         });
 
         ui.separator();
@@ -103,6 +231,14 @@ impl GCodeEditor {
             ui.separator();
         }
 
+        // Operations navigator
+        if self.mode == EditorMode::View && self.show_navigator && !self.outline.is_empty() {
+            egui::SidePanel::left("gcode_operations")
+                .resizable(true)
+                .default_width(120.0)
+                .show_inside(ui, |ui| self.show_navigator(ui));
+        }
+
         // Main editor area
         ScrollArea::vertical()
             .id_source("gcode_editor_scroll")
@@ -127,22 +263,111 @@ impl GCodeEditor {
                 ui.separator();
                 ui.colored_label(Color32::YELLOW, format!("▶ Line {}", line + 1));
             }
+            if !self.bookmarks.is_empty() {
+                ui.separator();
+                ui.label(format!("🔖 {}", self.bookmarks.len()))
+                    .on_hover_text("Ctrl+B: toggle bookmark, F2 / Shift+F2: next / previous");
+            }
+            if !self.breakpoints.is_empty() {
+                ui.separator();
+                let text = RichText::new(format!("● {}", self.breakpoints.len())).color(Color32::LIGHT_RED);
+                let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()))
+                    .on_hover_text("F9: toggle breakpoint. Click to clear all");
+                if response.clicked() {
+                    self.breakpoints.clear();
+                }
+            }
+        });
+    }
+
+    /// List the operations, jumping to one when clicked
+    fn show_navigator(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Operations");
+            if ui.small_button("⊟").on_hover_text("Fold all").clicked() {
+                self.folded = self.outline.iter().map(|op| op.start).collect();
+            }
+            if ui.small_button("⊞").on_hover_text("Unfold all").clicked() {
+                self.folded.clear();
+            }
         });
+        
+        let mut jump = None;
+        ScrollArea::vertical()
+            .id_source("gcode_operations_scroll")
+            .show(ui, |ui| {
+                for op in &self.outline {
+                    let mut text = RichText::new(&op.name);
+                    if self.current_line.is_some_and(|line| op.contains(line)) {
+                        text = text.color(Color32::YELLOW);
+                    }
+                    let hover = format!("Lines {}-{}", op.start + 1, op.end + 1);
+                    if ui.selectable_label(op.contains(self.cursor_line), text).on_hover_text(hover).clicked() {
+                        jump = Some(op.start);
+                    }
+                }
+            });
+        if let Some(line) = jump {
+            self.go_to_line(line);
+        }
     }
 
     /// Show view mode (read-only with syntax highlighting)
-    fn show_view_mode(&self, ui: &mut Ui, content: &str) {
+    fn show_view_mode(&mut self, ui: &mut Ui, content: &str) {
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
         
+        let mut clicked_line = None;
+        let mut clicked_breakpoint = None;
+        let mut toggled_fold = None;
+        let mut folds = self.outline.iter().map(|op| (op.start, op.end)).peekable();
+        let mut hidden_until = None;
+        
         for (line_num, line) in content.lines().enumerate() {
-            ui.horizontal(|ui| {
+            if hidden_until.is_some_and(|end| line_num <= end) {
+                continue;
+            }
+            let fold = folds.next_if(|(start, _)| *start == line_num);
+            let folded_end = fold.filter(|(start, _)| self.folded.contains(start)).map(|(_, end)| end);
+            hidden_until = folded_end;
+            
+            let row = ui.horizontal(|ui| {
+                // Fold toggle at the start of an operation
+                let toggle = match (fold, folded_end) {
+                    (Some(_), Some(_)) => "▸",
+                    (Some(_), None) => "▾",
+                    (None, _) => " ",
+                };
+                let response = ui.add(egui::Label::new(RichText::new(toggle).weak()).sense(egui::Sense::click()));
+                if fold.is_some() && response.on_hover_text("Fold or unfold the operation").clicked() {
+                    toggled_fold = Some(line_num);
+                }
+                
+                // Breakpoint marker
+                let (marker, color) = if self.breakpoints.contains(&line_num) {
+                    ("●", Color32::LIGHT_RED)
+                } else {
+                    ("·", Color32::DARK_GRAY)
+                };
+                let response = ui.add(egui::Label::new(RichText::new(marker).color(color)).sense(egui::Sense::click()));
+                if response.on_hover_text("Click to toggle breakpoint").clicked() {
+                    clicked_breakpoint = Some(line_num);
+                }
+                
+                // Bookmark marker
+                let marker = if self.bookmarks.contains(&line_num) { "🔖" } else { "  " };
+                ui.label(RichText::new(marker).color(Color32::from_rgb(100, 180, 255)));
+                
                 // Line number
                 if self.show_line_numbers {
                     let line_num_text = format!("{:4} ", line_num + 1);
                     let mut color = Color32::DARK_GRAY;
                     
-                    // Highlight current execution line
-                    if Some(line_num) == self.current_line {
+                    // Highlight current execution line, or the folded operation holding it
+                    let executing = match folded_end {
+                        Some(end) => self.current_line.is_some_and(|current| (line_num..=end).contains(&current)),
+                        None => Some(line_num) == self.current_line,
+                    };
+                    if executing {
                         ui.painter().rect_filled(
                             ui.available_rect_before_wrap(),
                             0.0,
@@ -151,12 +376,42 @@ impl GCodeEditor {
                         color = Color32::YELLOW;
                     }
                     
-                    ui.label(RichText::new(line_num_text).color(color));
+                    if line_num == self.cursor_line {
+                        color = Color32::WHITE;
+                    }
+                    
+                    let response = ui.add(
+                        egui::Label::new(RichText::new(line_num_text).color(color))
+                            .sense(egui::Sense::click()),
+                    );
+                    if response.on_hover_text("Click to toggle bookmark").clicked() {
+                        clicked_line = Some(line_num);
+                    }
                 }
                 
                 // Syntax highlighted line
                 self.show_highlighted_line(ui, line);
+                if let Some(end) = folded_end {
+                    ui.weak(format!(" … {} lines", end - line_num));
+                }
             });
+            
+            if self.scroll_to_line == Some(line_num) {
+                ui.scroll_to_rect(row.response.rect, Some(egui::Align::Center));
+                self.scroll_to_line = None;
+            }
+        }
+        
+        if let Some(line) = clicked_line {
+            self.toggle_bookmark(line);
+        }
+        if let Some(line) = clicked_breakpoint {
+            self.toggle_breakpoint(line);
+        }
+        if let Some(start) = toggled_fold {
+            if !self.folded.remove(&start) {
+                self.folded.insert(start);
+            }
         }
     }
 
@@ -290,6 +545,12 @@ impl GCodeEditor {
             ui.label(RichText::new(line).color(Color32::DARK_GREEN));
             return;
         }
+        
+        // Block delete line
+        if trimmed.starts_with('/') {
+            ui.label(RichText::new(line).color(Color32::GRAY).italics());
+            return;
+        }
 
         // Parse and highlight tokens
         let mut current_pos = 0;
@@ -378,6 +639,119 @@ impl GCodeEditor {
     }
 }
 
+/// A simplified cut of the progress map: start, end and the program line it ends on
+type MapPiece = ([f32; 2], [f32; 2], usize);
+
+/// Miniature top view of a program's cuts for following a running job
+///
+/// Built once when a program starts. Consecutive cuts shorter than a
+/// fraction of the job size are merged so the map stays cheap to draw in
+/// the status bar.
+#[derive(Debug, Clone)]
+pub struct ProgressMap {
+    pieces: Vec<MapPiece>,
+    min: [f32; 2],
+    max: [f32; 2],
+}
+
+impl ProgressMap {
+    /// Pieces per job width or height below which cuts are merged
+    const RESOLUTION: f32 = 200.0;
+
+    /// Build the map of a program's segments, if it cuts anything
+    pub fn new(segments: &[Segment]) -> Option<Self> {
+        let cuts = || segments.iter().filter(|segment| segment.is_cutting());
+        let mut min = [f32::MAX; 2];
+        let mut max = [f32::MIN; 2];
+        for segment in cuts() {
+            for point in [segment.start, segment.end] {
+                min = [min[0].min(point.x as f32), min[1].min(point.y as f32)];
+                max = [max[0].max(point.x as f32), max[1].max(point.y as f32)];
+            }
+        }
+        if cuts().next().is_none() {
+            return None;
+        }
+        let tolerance = (max[0] - min[0]).max(max[1] - min[1]) / Self::RESOLUTION;
+
+        let mut pieces: Vec<MapPiece> = Vec::new();
+        let mut current: Option<MapPiece> = None;
+        let mut line = 0;
+        for segment in cuts() {
+            line = segment.source_line.unwrap_or(line);
+            let start = [segment.start.x as f32, segment.start.y as f32];
+            let end = [segment.end.x as f32, segment.end.y as f32];
+            current = match current {
+                // Extend a short piece this cut continues
+                Some((from, to, _)) if to == start && distance(from, to) < tolerance => Some((from, end, line)),
+                previous => {
+                    pieces.extend(previous);
+                    Some((start, end, line))
+                }
+            };
+        }
+        pieces.extend(current);
+        Some(Self { pieces, min, max })
+    }
+
+    /// Draw the map, shading the cuts before `completed_line` and marking the tool position
+    pub fn show(&self, ui: &mut Ui, size: egui::Vec2, completed_line: usize, position: [f64; 2]) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, Color32::from_black_alpha(60));
+
+        let inner = rect.shrink(3.0);
+        let width = (self.max[0] - self.min[0]).max(f32::EPSILON);
+        let height = (self.max[1] - self.min[1]).max(f32::EPSILON);
+        let scale = (inner.width() / width).min(inner.height() / height);
+        let origin = inner.center() - egui::vec2(width, -height) * scale / 2.0;
+        let to_screen = |[x, y]: [f32; 2]| origin + egui::vec2(x - self.min[0], self.min[1] - y) * scale;
+
+        for &(start, end, line) in &self.pieces {
+            let color = if line < completed_line {
+                Color32::from_rgb(80, 200, 120)
+            } else {
+                Color32::from_gray(110)
+            };
+            painter.line_segment([to_screen(start), to_screen(end)], egui::Stroke::new(1.0, color));
+        }
+
+        let tool = to_screen([position[0] as f32, position[1] as f32]);
+        painter.circle_filled(tool.clamp(inner.min, inner.max), 2.5, Color32::YELLOW);
+        response
+    }
+}
+
+/// Length between two points of the progress map
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
+
+/// Screen-reader text for widgets whose visible text is an icon or a bare
+/// number, such as the jog arrows and the position readouts
+pub trait AccessibleLabel {
+    /// Replace the label reported to assistive technology
+    fn accessible_label(self, label: impl ToString) -> Self;
+}
+
+impl AccessibleLabel for egui::Response {
+    fn accessible_label(self, label: impl ToString) -> Self {
+        let label = label.to_string();
+        let typ = if self.sense.click { egui::WidgetType::Button } else { egui::WidgetType::Label };
+        self.widget_info(|| egui::WidgetInfo::labeled(typ, self.enabled(), &label));
+        self
+    }
+}
+
+/// A value readout that can be reached with Tab and is read out in full
+///
+/// `spoken` names the value for screen readers, e.g. "Work X 12.500
+/// millimeters" for a label showing "X: 12.500".
+pub fn readout(ui: &mut Ui, text: impl Into<egui::WidgetText>, spoken: impl ToString) -> egui::Response {
+    ui.add(egui::Label::new(text).sense(egui::Sense::focusable_noninteractive()))
+        .accessible_label(spoken)
+}
+
 /// Log message severity level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogLevel {
@@ -459,10 +833,61 @@ impl ConsoleMessage {
     }
 }
 
+/// Case-insensitive text or regular expression matcher for console lines
+#[derive(Debug, Clone, Default)]
+pub struct ConsolePattern {
+    /// Compiled pattern, or `None` when empty or invalid
+    regex: Option<Regex>,
+    /// Error from compiling an invalid regular expression
+    error: Option<String>,
+}
+
+impl ConsolePattern {
+    /// Compile a pattern, treating `text` literally unless `use_regex` is set
+    pub fn new(text: &str, use_regex: bool) -> Self {
+        if text.is_empty() {
+            return Self::default();
+        }
+
+        let source = if use_regex { text.to_string() } else { regex::escape(text) };
+        match RegexBuilder::new(&source).case_insensitive(true).build() {
+            Ok(regex) => Self { regex: Some(regex), error: None },
+            Err(e) => Self { regex: None, error: Some(e.to_string()) },
+        }
+    }
+
+    /// Whether the pattern is active (non-empty and valid)
+    pub fn is_active(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    /// Error message if the regular expression is invalid
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Check a line; inactive patterns match everything
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.as_ref().map_or(true, |regex| regex.is_match(text))
+    }
+
+    /// Byte ranges of all matches in a line
+    pub fn find_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.regex {
+            Some(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
 /// Console widget for displaying log messages and command input
 pub struct Console {
-    /// Console messages history
-    messages: Vec<ConsoleMessage>,
+    /// Console messages history, oldest first
+    messages: VecDeque<ConsoleMessage>,
     /// Command input buffer
     command_input: String,
     /// Command history (for up/down arrow navigation)
@@ -489,6 +914,24 @@ pub struct Console {
     show_sent: bool,
     /// Whether to show received messages
     show_received: bool,
+    /// Filter text; only matching lines are shown
+    filter_text: String,
+    /// Treat the filter text as a regular expression
+    filter_regex: bool,
+    /// Compiled filter
+    filter: ConsolePattern,
+    /// Search text; matches are highlighted
+    search_text: String,
+    /// Treat the search text as a regular expression
+    search_regex: bool,
+    /// Compiled search
+    search: ConsolePattern,
+    /// Index of the current search match
+    current_match: usize,
+    /// Scroll the current match into view on the next frame
+    scroll_to_match: bool,
+    /// Whether the user asked to export the filtered messages
+    export_requested: bool,
 }
 
 impl Default for Console {
@@ -501,7 +944,7 @@ impl Console {
     /// Create a new console widget
     pub fn new() -> Self {
         Self {
-            messages: Vec::new(),
+            messages: VecDeque::new(),
             command_input: String::new(),
             command_history: Vec::new(),
             history_index: None,
@@ -515,17 +958,32 @@ impl Console {
             show_error: true,
             show_sent: true,
             show_received: true,
+            filter_text: String::new(),
+            filter_regex: false,
+            filter: ConsolePattern::default(),
+            search_text: String::new(),
+            search_regex: false,
+            search: ConsolePattern::default(),
+            current_match: 0,
+            scroll_to_match: false,
+            export_requested: false,
         }
     }
 
+    /// Set the maximum number of messages kept, dropping the oldest
+    pub fn set_max_messages(&mut self, max_messages: usize) {
+        self.max_messages = max_messages.max(1);
+        let excess = self.messages.len().saturating_sub(self.max_messages);
+        self.messages.drain(..excess);
+    }
+
     /// Add a message to the console
     pub fn add_message(&mut self, level: LogLevel, text: String) {
-        self.messages.push(ConsoleMessage::new(level, text));
-        
-        // Trim to max messages
-        if self.messages.len() > self.max_messages {
-            self.messages.drain(0..self.messages.len() - self.max_messages);
+        // Drop the oldest message once full
+        if self.messages.len() >= self.max_messages {
+            self.messages.pop_front();
         }
+        self.messages.push_back(ConsoleMessage::new(level, text));
     }
 
     /// Add a debug message
@@ -578,14 +1036,93 @@ impl Console {
         }
 
         // Check individual level filters
-        match message.level {
+        let level_shown = match message.level {
             LogLevel::Debug => self.show_debug,
             LogLevel::Info => self.show_info,
             LogLevel::Warning => self.show_warning,
             LogLevel::Error => self.show_error,
             LogLevel::Sent => self.show_sent,
             LogLevel::Received => self.show_received,
+        };
+
+        level_shown && self.filter.is_match(&message.text)
+    }
+
+    /// Check and clear whether the user asked to export the filtered messages
+    pub fn take_export_request(&mut self) -> bool {
+        std::mem::take(&mut self.export_requested)
+    }
+
+    /// Write the currently displayed messages to a file
+    ///
+    /// Returns the number of messages written.
+    pub fn export_filtered<P: AsRef<Path>>(&self, path: P) -> std::io::Result<usize> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut count = 0;
+        for message in self.messages.iter().filter(|m| self.should_display(m)) {
+            writeln!(
+                file,
+                "{} {} {}",
+                message.format_timestamp(),
+                message.level.prefix(),
+                message.text
+            )?;
+            count += 1;
+        }
+        file.flush()?;
+        Ok(count)
+    }
+
+    /// Indices of displayed messages matching the search
+    fn search_matches(&self) -> Vec<usize> {
+        if !self.search.is_active() {
+            return Vec::new();
+        }
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| self.should_display(message) && self.search.is_match(&message.text))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Move to the next or previous search match, wrapping around
+    fn step_match(&mut self, total: usize, forward: bool) {
+        if total == 0 {
+            return;
+        }
+        self.current_match = if forward {
+            (self.current_match + 1) % total
+        } else {
+            (self.current_match + total - 1) % total
+        };
+        self.scroll_to_match = true;
+    }
+
+    /// Build message text with search matches highlighted
+    fn highlighted_text(&self, ui: &Ui, text: &str) -> LayoutJob {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let normal = TextFormat {
+            font_id: font_id.clone(),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let highlight = TextFormat {
+            font_id,
+            color: Color32::BLACK,
+            background: Color32::from_rgb(255, 200, 0),
+            ..Default::default()
+        };
+
+        let mut job = LayoutJob::default();
+        let mut last = 0;
+        for range in self.search.find_ranges(text) {
+            job.append(&text[last..range.start], 0.0, normal.clone());
+            job.append(&text[range.clone()], 0.0, highlight.clone());
+            last = range.end;
         }
+        job.append(&text[last..], 0.0, normal);
+        job
     }
 
     /// Show the console widget
@@ -594,14 +1131,31 @@ impl Console {
 
         // Filter controls
         ui.horizontal(|ui| {
-            ui.label("Show:");
-            ui.checkbox(&mut self.show_debug, "Debug");
-            ui.checkbox(&mut self.show_info, "Info");
-            ui.checkbox(&mut self.show_warning, "Warn");
-            ui.checkbox(&mut self.show_error, "Error");
-            ui.separator();
-            ui.checkbox(&mut self.show_sent, "Sent");
-            ui.checkbox(&mut self.show_received, "Received");
+            ui.menu_button("Levels", |ui| {
+                ui.checkbox(&mut self.show_debug, "Debug");
+                ui.checkbox(&mut self.show_info, "Info");
+                ui.checkbox(&mut self.show_warning, "Warn");
+                ui.checkbox(&mut self.show_error, "Error");
+                ui.separator();
+                ui.checkbox(&mut self.show_sent, "Sent");
+                ui.checkbox(&mut self.show_received, "Received");
+            });
+
+            ui.label("Filter:");
+            let filter_changed = ui
+                .add(TextEdit::singleline(&mut self.filter_text).desired_width(150.0).hint_text("text or regex"))
+                .changed();
+            let filter_mode_changed = ui
+                .toggle_value(&mut self.filter_regex, ".*")
+                .on_hover_text("Use a regular expression")
+                .changed();
+            if filter_changed || filter_mode_changed {
+                self.filter = ConsolePattern::new(&self.filter_text, self.filter_regex);
+            }
+            if let Some(error) = self.filter.error() {
+                ui.colored_label(LogLevel::Error.color(), "⚠").on_hover_text(error);
+            }
+
             ui.separator();
             ui.checkbox(&mut self.show_timestamps, "Timestamps");
             ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
@@ -609,23 +1163,65 @@ impl Console {
             if ui.button("Clear").clicked() {
                 self.clear();
             }
+            if ui.button("💾 Export").on_hover_text("Save the filtered messages to a file").clicked() {
+                self.export_requested = true;
+            }
+        });
+
+        // Search controls
+        let matches = self.search_matches();
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            let response = ui.add(TextEdit::singleline(&mut self.search_text).desired_width(150.0));
+            let search_changed = response.changed();
+            let search_mode_changed = ui
+                .toggle_value(&mut self.search_regex, ".*")
+                .on_hover_text("Use a regular expression")
+                .changed();
+            if search_changed || search_mode_changed {
+                self.search = ConsolePattern::new(&self.search_text, self.search_regex);
+                self.current_match = 0;
+                self.scroll_to_match = true;
+            }
+
+            let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("▼ Next").clicked() || enter {
+                self.step_match(matches.len(), true);
+            }
+            if ui.button("▲ Prev").clicked() {
+                self.step_match(matches.len(), false);
+            }
+
+            if let Some(error) = self.search.error() {
+                ui.colored_label(LogLevel::Error.color(), "⚠").on_hover_text(error);
+            } else if self.search.is_active() {
+                if matches.is_empty() {
+                    ui.label("No matches");
+                } else {
+                    ui.label(format!("{}/{}", self.current_match.min(matches.len() - 1) + 1, matches.len()));
+                }
+                ui.colored_label(Color32::GRAY, "⏸ scrolling paused");
+            }
         });
 
         ui.separator();
 
-        // Console output area
+        // Console output area; scrolling stays put while searching
         let scroll = ScrollArea::vertical()
             .auto_shrink([false, false])
-            .stick_to_bottom(self.auto_scroll);
+            .stick_to_bottom(self.auto_scroll && !self.search.is_active());
+
+        let current = matches.get(self.current_match.min(matches.len().saturating_sub(1))).copied();
+        let scroll_to_match = std::mem::take(&mut self.scroll_to_match);
 
         scroll.show(ui, |ui| {
             ui.vertical(|ui| {
-                for message in &self.messages {
+                for (index, message) in self.messages.iter().enumerate() {
                     if !self.should_display(message) {
                         continue;
                     }
 
-                    ui.horizontal(|ui| {
+                    let row = ui.horizontal(|ui| {
                         // Timestamp
                         if self.show_timestamps {
                             ui.label(
@@ -643,8 +1239,23 @@ impl Console {
                         );
 
                         // Message text
-                        ui.label(RichText::new(&message.text).monospace());
+                        if self.search.is_active() {
+                            ui.label(self.highlighted_text(ui, &message.text));
+                        } else {
+                            ui.label(RichText::new(&message.text).monospace());
+                        }
                     });
+
+                    if current == Some(index) {
+                        ui.painter().rect_stroke(
+                            row.response.rect.expand(1.0),
+                            2.0,
+                            egui::Stroke::new(1.0, Color32::from_rgb(255, 200, 0)),
+                        );
+                        if scroll_to_match {
+                            row.response.scroll_to_me(Some(egui::Align::Center));
+                        }
+                    }
                 }
             });
         });
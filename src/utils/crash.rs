@@ -0,0 +1,257 @@
+//! Local crash reports
+//!
+//! When enabled in the settings, a panic is written to a zip in the data
+//! folder holding the panic message and backtrace, the end of the log and
+//! the settings, both with hosts and credentials removed, and the name of
+//! the loaded file. Nothing is sent anywhere; the next start offers the report for
+//! attaching to a GitHub issue.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::settings::Settings;
+
+/// Where new issues are filed
+pub const ISSUE_URL: &str = "https://github.com/thawkins/rCandle/issues/new";
+
+/// Settings keys whose values are replaced in reports
+const REDACTED_KEYS: &[&str] = &["host", "token", "username", "password"];
+
+/// How much of the end of the log goes into a report
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// Names the report the last run left, until it has been shown
+const PENDING_FILE: &str = "pending";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    settings: String::new(),
+    secrets: Vec::new(),
+    loaded_file: None,
+});
+
+/// What the application was doing, kept for the panic hook
+struct CrashContext {
+    /// Redacted settings as TOML
+    settings: String,
+    /// Values removed from the settings, also removed from the log
+    secrets: Vec<String>,
+    loaded_file: Option<PathBuf>,
+}
+
+/// Install the panic hook
+///
+/// Reports are only written once [`record_settings`] has seen them
+/// enabled. The previous hook still runs, so panics print as before.
+pub fn install(log_dir: Option<PathBuf>, report_dir: Option<PathBuf>) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "unknown panic".to_string()),
+        };
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        tracing::error!("Panic at {}: {}", location, message);
+
+        if ENABLED.load(Ordering::Relaxed) {
+            if let Some(dir) = &report_dir {
+                match write_report(dir, log_dir.as_deref(), &message, &location) {
+                    Ok(path) => eprintln!("rCandle crash report saved to {}", path.display()),
+                    Err(e) => eprintln!("Failed to save crash report: {}", e),
+                }
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// Default report folder, in the data directory
+pub fn default_report_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "rCandle").map(|d| d.data_dir().join("crashes"))
+}
+
+/// Keep the settings for reports, and whether reports are wanted
+pub fn record_settings(settings: &Settings) {
+    ENABLED.store(settings.general.crash_reports, Ordering::Relaxed);
+    let mut secrets = Vec::new();
+    let text = toml::Value::try_from(settings)
+        .ok()
+        .and_then(|mut value| {
+            redact(&mut value, home_dir().as_deref(), &mut secrets);
+            toml::to_string_pretty(&value).ok()
+        })
+        .unwrap_or_default();
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.settings = text;
+        context.secrets = secrets;
+    }
+}
+
+/// Keep the name of the loaded program for reports
+pub fn record_loaded_file(path: Option<&Path>) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        if context.loaded_file.as_deref() != path {
+            context.loaded_file = path.map(Path::to_path_buf);
+        }
+    }
+}
+
+/// Report left by the last run, if it has not been shown yet
+pub fn take_new_report() -> Option<PathBuf> {
+    let pending = default_report_dir()?.join(PENDING_FILE);
+    let path = std::fs::read_to_string(&pending).ok()?;
+    if let Err(e) = std::fs::remove_file(&pending) {
+        tracing::warn!("Failed to clear pending crash report: {}", e);
+    }
+    let path = PathBuf::from(path.trim());
+    path.exists().then_some(path)
+}
+
+/// Replace hosts and credentials, and the home folder in paths
+///
+/// The values replaced are added to `removed`.
+fn redact(value: &mut toml::Value, home: Option<&str>, removed: &mut Vec<String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) && value.is_str() {
+                    let secret = std::mem::replace(value, toml::Value::String("<redacted>".to_string()));
+                    removed.extend(secret.as_str().filter(|text| !text.is_empty()).map(str::to_string));
+                } else {
+                    redact(value, home, removed);
+                }
+            }
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(|item| redact(item, home, removed)),
+        toml::Value::String(text) => {
+            if let Some(home) = home.filter(|home| !home.is_empty()) {
+                *text = text.replace(home, "~");
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove the settings' hosts and credentials, and the home folder, from log text
+fn redact_log(log: &[u8], secrets: &[String], home: Option<&str>) -> Vec<u8> {
+    let mut text = String::from_utf8_lossy(log).into_owned();
+    // Paths first, as the home folder may hold a user name
+    if let Some(home) = home.filter(|home| !home.is_empty()) {
+        text = text.replace(home, "~");
+    }
+    // Longest first, so a value holding another is replaced whole
+    let mut secrets: Vec<&String> = secrets.iter().collect();
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    for secret in secrets {
+        text = text.replace(secret.as_str(), "<redacted>");
+    }
+    text.into_bytes()
+}
+
+fn home_dir() -> Option<String> {
+    directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().into_owned())
+}
+
+/// Write the report zip and mark it for the next start
+fn write_report(dir: &Path, log_dir: Option<&Path>, message: &str, location: &str) -> std::io::Result<PathBuf> {
+    // The panic may have happened while the context was locked
+    let (settings, secrets, loaded_file) = match CONTEXT.try_lock() {
+        Ok(context) => (context.settings.clone(), context.secrets.clone(), context.loaded_file.clone()),
+        Err(_) => (String::new(), Vec::new(), None),
+    };
+    let loaded_file = loaded_file
+        .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "none".to_string());
+    let summary = format!(
+        "rCandle {}\nOS: {} ({})\nTime: {}\nThread: {}\nLocation: {}\nMessage: {}\nLoaded file: {}\n\nBacktrace:\n{}\n",
+        crate::VERSION,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        std::thread::current().name().unwrap_or("unnamed"),
+        location,
+        message,
+        loaded_file,
+        std::backtrace::Backtrace::force_capture(),
+    );
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("rcandle-crash-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path)?);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("crash.txt", options)?;
+    zip.write_all(summary.as_bytes())?;
+    zip.start_file("settings.toml", options)?;
+    zip.write_all(settings.as_bytes())?;
+    if let Some(log) = log_dir.and_then(log_tail) {
+        zip.start_file("recent.log", options)?;
+        zip.write_all(&redact_log(&log, &secrets, home_dir().as_deref()))?;
+    }
+    zip.finish()?;
+
+    std::fs::write(dir.join(PENDING_FILE), path.to_string_lossy().as_bytes())?;
+    Ok(path)
+}
+
+/// End of today's log file
+fn log_tail(log_dir: &Path) -> Option<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let path = log_dir.join(format!("rcandle-{}.log", chrono::Local::now().format("%Y%m%d")));
+    let mut file = std::fs::File::open(path).ok()?;
+    let length = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(length.saturating_sub(LOG_TAIL_BYTES))).ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    Some(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            last_file = "/home/alice/parts/bracket.nc"
+            [[profiles]]
+            name = "Shop"
+            host = "192.168.1.20"
+            auth = { Basic = { username = "alice", password = "secret" } }
+            "#,
+        )
+        .unwrap();
+        let mut removed = Vec::new();
+        redact(&mut value, Some("/home/alice"), &mut removed);
+
+        assert_eq!(value["last_file"].as_str(), Some("~/parts/bracket.nc"));
+        let profile = &value["profiles"][0];
+        assert_eq!(profile["name"].as_str(), Some("Shop"));
+        assert_eq!(profile["host"].as_str(), Some("<redacted>"));
+        assert_eq!(profile["auth"]["Basic"]["username"].as_str(), Some("<redacted>"));
+        assert_eq!(profile["auth"]["Basic"]["password"].as_str(), Some("<redacted>"));
+        removed.sort();
+        assert_eq!(removed, ["192.168.1.20", "alice", "secret"]);
+    }
+
+    #[test]
+    fn test_redact_log() {
+        let log = b"INFO Connecting to 192.168.1.20:23 as alice\nINFO Loaded /home/alice/parts/bracket.nc\n";
+        let secrets = ["192.168.1.20".to_string(), "alice".to_string()];
+        let redacted = redact_log(log, &secrets, Some("/home/alice"));
+        assert_eq!(
+            String::from_utf8(redacted).unwrap(),
+            "INFO Connecting to <redacted>:23 as <redacted>\nINFO Loaded ~/parts/bracket.nc\n"
+        );
+    }
+}
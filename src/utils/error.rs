@@ -1,7 +1,10 @@
 //! Error types for rCandle
 //!
-//! Provides a comprehensive error type using thiserror.
+//! Provides a comprehensive error type using thiserror. Every error belongs
+//! to an [`ErrorCategory`] and has a stable user-facing code plus, where one
+//! exists, a hint on how to recover.
 
+use std::fmt;
 use std::io;
 
 /// Result type alias for rCandle operations
@@ -61,6 +64,56 @@ pub enum Error {
     /// Generic error
     #[error("{0}")]
     Generic(String),
+
+    /// Error with a description of what was being attempted
+    #[error("{context}: {source}")]
+    Context {
+        /// What was being attempted
+        context: String,
+        /// Underlying error
+        source: Box<Error>,
+    },
+}
+
+/// Broad classes of errors shown to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Link to the controller could not be established or was lost
+    Connection,
+    /// Controller rejected or did not answer a request
+    Protocol,
+    /// Reading or writing files and settings
+    File,
+    /// G-code, expression or script input could not be understood
+    Parse,
+    /// Fault inside rCandle itself
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Short prefix used in error codes
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ErrorCategory::Connection => "CON",
+            ErrorCategory::Protocol => "PRO",
+            ErrorCategory::File => "FIL",
+            ErrorCategory::Parse => "PAR",
+            ErrorCategory::Internal => "INT",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorCategory::Connection => "Connection",
+            ErrorCategory::Protocol => "Protocol",
+            ErrorCategory::File => "File",
+            ErrorCategory::Parse => "Parse",
+            ErrorCategory::Internal => "Internal",
+        };
+        f.write_str(name)
+    }
 }
 
 impl Error {
@@ -113,4 +166,99 @@ impl Error {
     pub fn script<S: Into<String>>(msg: S) -> Self {
         Error::Script(msg.into())
     }
+
+    /// Wrap the error with a description of what was being attempted
+    pub fn with_context<S: Into<String>>(self, context: S) -> Self {
+        Error::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost error, past any added context
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Category of the error
+    pub fn category(&self) -> ErrorCategory {
+        match self.root() {
+            Error::SerialPort(_) | Error::Connection(_) | Error::NotConnected => ErrorCategory::Connection,
+            Error::Grbl(_) | Error::Timeout(_) | Error::Queue(_) => ErrorCategory::Protocol,
+            Error::Io(_) | Error::Config(_) => ErrorCategory::File,
+            Error::Parse(_) | Error::Script(_) => ErrorCategory::Parse,
+            Error::Render(_) | Error::InvalidState(_) | Error::Generic(_) | Error::Context { .. } => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    /// Stable code identifying the kind of error, e.g. `CON-002`
+    pub fn code(&self) -> String {
+        let number = match self.root() {
+            Error::SerialPort(_) | Error::Grbl(_) | Error::Io(_) | Error::Parse(_) | Error::Render(_) => 1,
+            Error::Connection(_) | Error::Timeout(_) | Error::Config(_) | Error::Script(_) | Error::InvalidState(_) => 2,
+            Error::NotConnected | Error::Queue(_) | Error::Generic(_) | Error::Context { .. } => 3,
+        };
+        format!("{}-{:03}", self.category().prefix(), number)
+    }
+
+    /// Suggestion for how the user can recover, if there is one
+    pub fn recovery_hint(&self) -> Option<&'static str> {
+        match self.root() {
+            Error::Io(_) => Some("Check that the file exists and that you have permission to access it."),
+            Error::SerialPort(_) => Some("Check the cable and make sure no other program is using the port."),
+            Error::Parse(_) => Some("Check the G-code around the reported line."),
+            Error::Config(_) => Some("Fix or remove the settings file; defaults are used in the meantime."),
+            Error::Connection(_) => Some("Check the connection settings and reconnect."),
+            Error::Grbl(_) => Some("Look up the GRBL code; clear an alarm with Unlock ($X) or Home."),
+            Error::Render(_) => Some("Try disabling MSAA or updating the graphics driver."),
+            Error::InvalidState(_) => Some("Wait for the current operation to finish and try again."),
+            Error::NotConnected => Some("Connect to the machine first."),
+            Error::Timeout(_) => Some("Make sure the controller is powered and responding, then retry."),
+            Error::Queue(_) => Some("Clear the command queue and send the commands again."),
+            Error::Script(_) => Some("Fix the script in the script editor and run it again."),
+            Error::Generic(_) | Error::Context { .. } => None,
+        }
+    }
+}
+
+/// Adds context to errors as they propagate
+pub trait ErrorContext<T> {
+    /// Describe what was being attempted when the error occurred
+    fn context<S: Into<String>>(self, context: S) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ErrorContext<T> for std::result::Result<T, E> {
+    fn context<S: Into<String>>(self, context: S) -> Result<T> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_categories() {
+        let error = Error::connection("port busy");
+        assert_eq!(error.category(), ErrorCategory::Connection);
+        assert_eq!(error.code(), "CON-002");
+        assert!(error.recovery_hint().is_some());
+        assert_eq!(Error::NotConnected.code(), "CON-003");
+        assert_eq!(Error::parse("bad word").code(), "PAR-001");
+    }
+
+    #[test]
+    fn test_context_keeps_root() {
+        let result: Result<()> = Err(io::Error::new(io::ErrorKind::NotFound, "missing").into());
+        let error = result.context("Loading part.nc").unwrap_err();
+        assert_eq!(error.category(), ErrorCategory::File);
+        assert_eq!(error.code(), "FIL-001");
+        assert!(error.to_string().starts_with("Loading part.nc: IO error"));
+        assert!(matches!(error.root(), Error::Io(_)));
+    }
 }
@@ -0,0 +1,144 @@
+//! Machining calculations
+//!
+//! Feeds and speeds from tool and material data, and job cost estimates.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Cutting data for a stock material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialPreset {
+    /// Material name
+    pub name: &'static str,
+    /// Recommended surface speed (m/min)
+    pub surface_speed: f64,
+    /// Chip load per tooth as a fraction of tool diameter
+    pub chip_load_factor: f64,
+}
+
+/// Starting points for common router materials
+///
+/// These are conservative values for carbide end mills; adjust from there.
+pub const MATERIAL_PRESETS: &[MaterialPreset] = &[
+    MaterialPreset { name: "Softwood", surface_speed: 500.0, chip_load_factor: 0.040 },
+    MaterialPreset { name: "Hardwood", surface_speed: 400.0, chip_load_factor: 0.032 },
+    MaterialPreset { name: "Plywood", surface_speed: 450.0, chip_load_factor: 0.035 },
+    MaterialPreset { name: "MDF", surface_speed: 500.0, chip_load_factor: 0.045 },
+    MaterialPreset { name: "Acrylic", surface_speed: 300.0, chip_load_factor: 0.025 },
+    MaterialPreset { name: "HDPE", surface_speed: 350.0, chip_load_factor: 0.035 },
+    MaterialPreset { name: "Aluminium", surface_speed: 200.0, chip_load_factor: 0.008 },
+    MaterialPreset { name: "Brass", surface_speed: 150.0, chip_load_factor: 0.008 },
+    MaterialPreset { name: "Mild steel", surface_speed: 60.0, chip_load_factor: 0.005 },
+];
+
+/// Feeds and speeds for a tool in a material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedsAndSpeeds {
+    /// Tool diameter (mm)
+    pub tool_diameter: f64,
+    /// Number of flutes
+    pub flutes: u32,
+    /// Surface speed (m/min)
+    pub surface_speed: f64,
+    /// Chip load per tooth (mm)
+    pub chip_load: f64,
+    /// Spindle speed limit (RPM)
+    pub max_rpm: f64,
+}
+
+impl FeedsAndSpeeds {
+    /// Create from a material preset
+    pub fn from_preset(preset: &MaterialPreset, tool_diameter: f64, flutes: u32, max_rpm: f64) -> Self {
+        Self {
+            tool_diameter,
+            flutes,
+            surface_speed: preset.surface_speed,
+            chip_load: preset.chip_load_factor * tool_diameter,
+            max_rpm,
+        }
+    }
+
+    /// Spindle speed for the surface speed, capped at the spindle limit
+    pub fn rpm(&self) -> f64 {
+        if self.tool_diameter <= 0.0 {
+            return 0.0;
+        }
+        let rpm = self.surface_speed * 1000.0 / (std::f64::consts::PI * self.tool_diameter);
+        if self.max_rpm > 0.0 {
+            rpm.min(self.max_rpm)
+        } else {
+            rpm
+        }
+    }
+
+    /// Feed rate (mm/min) for the chip load at the spindle speed
+    pub fn feed_rate(&self) -> f64 {
+        self.rpm() * self.flutes as f64 * self.chip_load
+    }
+
+    /// Chip load (mm) produced by a feed rate at a spindle speed
+    pub fn chip_load_for(feed_rate: f64, rpm: f64, flutes: u32) -> f64 {
+        if rpm <= 0.0 || flutes == 0 {
+            return 0.0;
+        }
+        feed_rate / (rpm * flutes as f64)
+    }
+}
+
+/// Cost rates for a job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobCost {
+    /// Machine cost per hour
+    pub hourly_rate: f64,
+    /// Stock material cost
+    pub material_cost: f64,
+}
+
+impl JobCost {
+    /// Cost of running the machine for `machine_time`
+    pub fn machine_cost(&self, machine_time: Duration) -> f64 {
+        machine_time.as_secs_f64() / 3600.0 * self.hourly_rate
+    }
+
+    /// Total cost for a job taking `machine_time`
+    pub fn total(&self, machine_time: Duration) -> f64 {
+        self.machine_cost(machine_time) + self.material_cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feeds_and_speeds() {
+        let calc = FeedsAndSpeeds {
+            tool_diameter: 6.0,
+            flutes: 2,
+            surface_speed: 300.0,
+            chip_load: 0.05,
+            max_rpm: 24000.0,
+        };
+        // 300 m/min on a 6 mm tool is ~15915 RPM
+        assert!((calc.rpm() - 15915.5).abs() < 1.0);
+        assert!((calc.feed_rate() - 1591.5).abs() < 1.0);
+
+        // Capped by the spindle
+        let capped = FeedsAndSpeeds { max_rpm: 10000.0, ..calc };
+        assert_eq!(capped.rpm(), 10000.0);
+        assert!((capped.feed_rate() - 1000.0).abs() < 1e-9);
+
+        assert!((FeedsAndSpeeds::chip_load_for(1000.0, 10000.0, 2) - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_job_cost() {
+        let cost = JobCost {
+            hourly_rate: 60.0,
+            material_cost: 12.5,
+        };
+        let time = Duration::from_secs(30 * 60);
+        assert!((cost.machine_cost(time) - 30.0).abs() < 1e-9);
+        assert!((cost.total(time) - 42.5).abs() < 1e-9);
+    }
+}
@@ -1,9 +1,13 @@
 //! Utilities module
 //!
-//! Provides error types, logging setup, and common utilities.
+//! Provides error types, logging setup, crash reports, and common utilities.
 
+pub mod crash;
 pub mod error;
 pub mod logging;
+pub mod machining;
+pub mod supervisor;
 
-pub use error::{Error, Result};
+pub use error::{Error, ErrorCategory, ErrorContext, Result};
 pub use logging::init_logging;
+pub use supervisor::{ShutdownSignal, TaskFailure, TaskSupervisor};
@@ -0,0 +1,219 @@
+//! Background task supervision
+//!
+//! Tracks spawned tasks so they can be shut down in order and reports tasks
+//! that panic instead of letting them disappear silently.
+//!
+//! Panics are caught by unwinding, so release builds must not set
+//! `panic = "abort"` or a panicking task takes the whole process down.
+
+use futures_util::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+
+/// A supervised task that panicked
+#[derive(Debug, Clone)]
+pub struct TaskFailure {
+    /// Name the task was spawned with
+    pub task: String,
+    /// Panic message
+    pub message: String,
+}
+
+/// Shutdown notification handed to supervised tasks
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Wait until shutdown is requested
+    pub async fn recv(&mut self) {
+        // The supervisor owns the sender, so an error means it is gone: stop too
+        let _ = self.receiver.wait_for(|stop| *stop).await;
+    }
+
+    /// Whether shutdown has been requested
+    pub fn is_requested(&self) -> bool {
+        *self.receiver.borrow()
+    }
+}
+
+struct SupervisedTask {
+    name: String,
+    handle: JoinHandle<()>,
+}
+
+struct Inner {
+    tasks: Mutex<Vec<SupervisedTask>>,
+    shutdown_tx: watch::Sender<bool>,
+    failure_tx: broadcast::Sender<TaskFailure>,
+}
+
+/// Supervisor for background tasks
+///
+/// Cloning shares the same set of tasks.
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    inner: Arc<Inner>,
+}
+
+impl TaskSupervisor {
+    /// Create a supervisor with no tasks
+    pub fn new() -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        let (failure_tx, _) = broadcast::channel(16);
+        Self {
+            inner: Arc::new(Inner {
+                tasks: Mutex::new(Vec::new()),
+                shutdown_tx,
+                failure_tx,
+            }),
+        }
+    }
+
+    /// Spawn a named task
+    ///
+    /// The task should stop once its [`ShutdownSignal`] fires; a panic is
+    /// logged and broadcast to [`TaskSupervisor::subscribe_failures`].
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, task: F)
+    where
+        F: FnOnce(ShutdownSignal) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        let future = task(self.shutdown_signal());
+        let failure_tx = self.inner.failure_tx.clone();
+        let task_name = name.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(panic) = AssertUnwindSafe(future).catch_unwind().await {
+                let message = panic_message(panic.as_ref());
+                tracing::error!("Background task '{}' panicked: {}", task_name, message);
+                let _ = failure_tx.send(TaskFailure { task: task_name, message });
+            }
+        });
+
+        let mut tasks = self.inner.tasks.lock().unwrap();
+        tasks.retain(|task| !task.handle.is_finished());
+        tasks.push(SupervisedTask { name, handle });
+    }
+
+    /// Signal handed to tasks so they know when to stop
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.inner.shutdown_tx.subscribe(),
+        }
+    }
+
+    /// Subscribe to reports of panicked tasks
+    pub fn subscribe_failures(&self) -> broadcast::Receiver<TaskFailure> {
+        self.inner.failure_tx.subscribe()
+    }
+
+    /// Names of the tasks still running
+    pub fn running(&self) -> Vec<String> {
+        self.inner
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|task| !task.handle.is_finished())
+            .map(|task| task.name.clone())
+            .collect()
+    }
+
+    /// Ask every task to stop and wait for them to finish
+    ///
+    /// Tasks still running after `grace` are aborted. The supervisor can be
+    /// reused for new tasks afterwards.
+    pub async fn shutdown(&self, grace: Duration) {
+        self.inner.shutdown_tx.send_replace(true);
+        let tasks = std::mem::take(&mut *self.inner.tasks.lock().unwrap());
+
+        let deadline = tokio::time::Instant::now() + grace;
+        for mut task in tasks {
+            if tokio::time::timeout_at(deadline, &mut task.handle).await.is_err() {
+                tracing::warn!("Background task '{}' did not stop in time, aborting", task.name);
+                task.handle.abort();
+            }
+        }
+
+        self.inner.shutdown_tx.send_replace(false);
+    }
+
+    /// Signal shutdown and abort every task without waiting
+    ///
+    /// Used where awaiting is impossible, such as in `Drop`.
+    pub fn abort_all(&self) {
+        self.inner.shutdown_tx.send_replace(true);
+        for task in self.inner.tasks.lock().unwrap().drain(..) {
+            task.handle.abort();
+        }
+    }
+}
+
+impl Default for TaskSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extract a readable message from a panic payload
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_tasks() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("waiter", |mut shutdown| async move {
+            shutdown.recv().await;
+        });
+        assert_eq!(supervisor.running(), vec!["waiter".to_string()]);
+
+        supervisor.shutdown(Duration::from_secs(1)).await;
+        assert!(supervisor.running().is_empty());
+        assert!(!supervisor.shutdown_signal().is_requested());
+    }
+
+    #[tokio::test]
+    async fn test_stuck_task_is_aborted() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("stuck", |_| async move {
+            std::future::pending::<()>().await;
+        });
+
+        supervisor.shutdown(Duration::from_millis(10)).await;
+        assert!(supervisor.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_panic_is_reported() {
+        let supervisor = TaskSupervisor::new();
+        let mut failures = supervisor.subscribe_failures();
+        supervisor.spawn("faulty", |_| async move {
+            panic!("boom");
+        });
+
+        let failure = tokio::time::timeout(Duration::from_secs(1), failures.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(failure.task, "faulty");
+        assert_eq!(failure.message, "boom");
+    }
+}
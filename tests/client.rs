@@ -0,0 +1,64 @@
+//! GrblClient facade tests
+//!
+//! Streams programs through the public client API against the scripted
+//! mock GRBL device.
+
+use rcandle::connection::{ConnectionManagerConfig, MockConnection, MockDevice, MockDeviceConfig, MockScript};
+use rcandle::grbl::StreamOptions;
+use rcandle::GrblClient;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Create a connected client around a mock device
+async fn connect(script: MockScript) -> (GrblClient, Arc<Mutex<MockDevice>>) {
+    let config = MockDeviceConfig {
+        time_scale: 20.0,
+        ..Default::default()
+    };
+    let connection = MockConnection::with_script(config, script);
+    let device = connection.device();
+
+    let manager_config = ConnectionManagerConfig {
+        status_interval_ms: 50,
+        ..Default::default()
+    };
+    let mut client = GrblClient::with_config(Box::new(connection), manager_config)
+        .with_connect_timeout(Duration::from_secs(1));
+    client.connect().await.unwrap();
+
+    (client, device)
+}
+
+#[tokio::test]
+async fn test_stream_program() {
+    let (mut client, device) = connect(MockScript::new()).await;
+    let program = "(part)\nG21 G90\nG0 X0 Y0\n\nG1 X10 F500 ; cut\nG1 Y10\nM30\n";
+
+    let lines = timeout(Duration::from_secs(5), client.stream_program(program, StreamOptions::default()))
+        .await
+        .expect("Timed out streaming")
+        .unwrap();
+    assert_eq!(lines, 5);
+    assert_eq!(
+        device.lock().unwrap().received(),
+        ["G21 G90", "G0 X0 Y0", "G1 X10 F500", "G1 Y10", "M30"]
+    );
+
+    client.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_stops_at_error() {
+    let script = MockScript::new().respond("G38.2", &["error:20"]);
+    let (mut client, _device) = connect(script).await;
+    let program = "G0 Z5\nG38.2 Z-10 F50\nG0 Z5\n";
+
+    let error = timeout(Duration::from_secs(5), client.stream_program(program, StreamOptions::default()))
+        .await
+        .expect("Timed out streaming")
+        .unwrap_err();
+    assert!(error.to_string().contains("line 2: error:20"), "{}", error);
+
+    client.disconnect().await.unwrap();
+}
@@ -0,0 +1,232 @@
+//! GRBL protocol conformance tests
+//!
+//! Drives the connection manager and command queue against the scripted
+//! mock GRBL device, so regressions in queueing and flow control are caught
+//! without hardware.
+
+use rcandle::connection::{
+    ConnectionEvent, ConnectionManager, ConnectionManagerConfig, MockConnection, MockDevice,
+    MockDeviceConfig, MockScript,
+};
+use rcandle::grbl::{GrblCommand, GrblResponse, MachineState, QueueState, RealtimeCommand};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// Create a connected manager around a mock device
+async fn connect(script: MockScript) -> (ConnectionManager, Arc<Mutex<MockDevice>>) {
+    let config = MockDeviceConfig {
+        time_scale: 20.0,
+        ..Default::default()
+    };
+    let connection = MockConnection::with_script(config, script);
+    let device = connection.device();
+
+    let manager_config = ConnectionManagerConfig {
+        status_interval_ms: 50,
+        ..Default::default()
+    };
+    let mut manager = ConnectionManager::with_config(Box::new(connection), manager_config);
+    manager.connect(Duration::from_secs(1)).await.unwrap();
+
+    (manager, device)
+}
+
+/// Wait until a response matching `predicate` is received
+async fn wait_for_response(
+    rx: &mut tokio::sync::broadcast::Receiver<GrblResponse>,
+    predicate: impl Fn(&GrblResponse) -> bool,
+) -> GrblResponse {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(response) = rx.recv().await {
+                if predicate(&response) {
+                    return response;
+                }
+            }
+        }
+    })
+    .await
+    .expect("Timed out waiting for response")
+}
+
+#[tokio::test]
+async fn test_status_reports() {
+    let (mut manager, device) = connect(MockScript::new()).await;
+    let mut status_rx = manager.subscribe_status();
+
+    let status = timeout(Duration::from_secs(2), status_rx.recv())
+        .await
+        .expect("Timed out waiting for status")
+        .unwrap();
+    assert_eq!(status.state, MachineState::Idle);
+    assert_eq!(status.buffer, Some((15, 128)));
+    assert!(device.lock().unwrap().stats().status_queries > 0);
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_program_streams_in_order_without_overflow() {
+    let (mut manager, device) = connect(MockScript::new()).await;
+
+    let lines: Vec<String> = (1..=30)
+        .map(|i| format!("G1 X{} Y{} F3000", i, i % 5))
+        .collect();
+    for line in &lines {
+        manager.send_command(GrblCommand::GCode(line.clone())).await.unwrap();
+    }
+
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let received = device.lock().unwrap().received().len();
+            if received >= lines.len() {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Not every line reached the device");
+
+    {
+        let device = device.lock().unwrap();
+        assert_eq!(device.received(), lines.as_slice());
+        assert_eq!(device.stats().rx_overflows, 0);
+    }
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_settings_query() {
+    let (mut manager, _device) = connect(MockScript::new()).await;
+    let mut responses = manager.subscribe_responses();
+
+    manager.send_command(GrblCommand::GetSettings).await.unwrap();
+    let setting = wait_for_response(&mut responses, |r| matches!(r, GrblResponse::Setting { .. })).await;
+    assert_eq!(setting, GrblResponse::Setting { number: 0, value: "10".to_string() });
+    wait_for_response(&mut responses, |r| matches!(r, GrblResponse::Ok)).await;
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_scripted_error_is_reported() {
+    let script = MockScript::new().respond("G38.2", &["error:20"]);
+    let (mut manager, _device) = connect(script).await;
+    let mut responses = manager.subscribe_responses();
+
+    manager
+        .send_command(GrblCommand::GCode("G38.2 Z-10 F50".to_string()))
+        .await
+        .unwrap();
+    let response = wait_for_response(&mut responses, |r| r.is_error()).await;
+    assert_eq!(response, GrblResponse::Error(20));
+
+    // The queue keeps working after an error
+    manager.send_command(GrblCommand::GCode("G0 X1".to_string())).await.unwrap();
+    wait_for_response(&mut responses, |r| matches!(r, GrblResponse::Ok)).await;
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_feed_hold_and_resume() {
+    let (mut manager, _device) = connect(MockScript::new()).await;
+    let mut status_rx = manager.subscribe_status();
+
+    // 200mm at 100mm/min runs long enough to hold it
+    manager
+        .send_command(GrblCommand::GCode("G1 X200 F100".to_string()))
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(200)).await;
+    manager.send_realtime(RealtimeCommand::FeedHold.as_byte()).await.unwrap();
+
+    let held = timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(status) = status_rx.recv().await {
+                if status.state == MachineState::Hold {
+                    return status;
+                }
+            }
+        }
+    })
+    .await
+    .expect("Machine never entered hold");
+    assert!(held.mpos.unwrap().x < 200.0);
+
+    manager
+        .send_realtime(RealtimeCommand::CycleStartResume.as_byte())
+        .await
+        .unwrap();
+    timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(status) = status_rx.recv().await {
+                if status.state == MachineState::Run {
+                    return;
+                }
+            }
+        }
+    })
+    .await
+    .expect("Machine never resumed");
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_link_metrics() {
+    let (mut manager, _device) = connect(MockScript::new()).await;
+    let mut responses = manager.subscribe_responses();
+
+    manager.send_command(GrblCommand::GCode("G0 X1".to_string())).await.unwrap();
+    wait_for_response(&mut responses, |r| matches!(r, GrblResponse::Ok)).await;
+    wait_for_response(&mut responses, |r| matches!(r, GrblResponse::Status(_))).await;
+
+    let metrics = manager.metrics().await;
+    assert_eq!(metrics.queue.total_completed, 1);
+    assert!(metrics.lines_per_sec > 0.0);
+    assert!(metrics.bytes_received > 0);
+    assert_eq!(metrics.serial_errors, 0);
+
+    manager.reset_metrics().await;
+    assert_eq!(manager.metrics().await.queue.total_completed, 0);
+
+    manager.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_watchdog_detects_stale_status() {
+    let (mut manager, device) = connect(MockScript::new()).await;
+    let mut events = manager.subscribe_events();
+
+    device.lock().unwrap().set_link_down(true);
+    let stale = timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(ConnectionEvent::StatusStale { missed }) = events.recv().await {
+                return missed;
+            }
+        }
+    })
+    .await
+    .expect("Watchdog never reported a stale link");
+    assert!(stale >= 4);
+    assert!(manager.is_link_stale());
+    assert_eq!(manager.queue_state().await, QueueState::Paused);
+
+    device.lock().unwrap().set_link_down(false);
+    timeout(Duration::from_secs(2), async {
+        loop {
+            if let Ok(ConnectionEvent::StatusRestored) = events.recv().await {
+                return;
+            }
+        }
+    })
+    .await
+    .expect("Watchdog never reported the link restored");
+    assert!(!manager.is_link_stale());
+
+    manager.disconnect().await.unwrap();
+}